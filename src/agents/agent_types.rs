@@ -3,6 +3,7 @@
 use super::task::{AgentArtifact, AgentResult, AgentTask};
 use super::{Agent, AgentError, AgentMetrics, AgentStatus, BaseAgent};
 use crate::ai::AIManager;
+use crate::codegen::refactor::{RustRefactorer, RustRefactoring};
 
 use serde_json::json;
 use std::sync::Arc;
@@ -63,6 +64,8 @@ impl CodeGenerationAgent {
 
         let existing_code = task.context.get("existing_code").and_then(|c| c.as_str());
 
+        let project_context = task.context.get("packed_context").and_then(|c| c.as_str());
+
         // Generate code using AI if available
         let generated_code = if let Some(ai_manager) = &self.ai_manager {
             self.generate_with_ai(
@@ -71,6 +74,7 @@ impl CodeGenerationAgent {
                 language,
                 &requirements,
                 existing_code,
+                project_context,
             )
             .await?
         } else {
@@ -111,6 +115,7 @@ impl CodeGenerationAgent {
         language: &str,
         requirements: &[&str],
         existing_code: Option<&str>,
+        project_context: Option<&str>,
     ) -> Result<String, AgentError> {
         let system_prompt = format!(
             "You are a skilled {} developer. Generate clean, well-documented code that follows best practices.",
@@ -131,6 +136,11 @@ impl CodeGenerationAgent {
             user_prompt.push_str(code);
         }
 
+        if let Some(project_context) = project_context {
+            user_prompt.push_str("\n\nRelevant project context:\n");
+            user_prompt.push_str(project_context);
+        }
+
         user_prompt.push_str(
             "\n\nPlease provide only the code, without explanations or markdown formatting.",
         );
@@ -373,7 +383,10 @@ impl RefactoringAgent {
                     "optimize_performance".to_string(),
                     "improve_structure".to_string(),
                     "update_patterns".to_string(),
-                ],
+                ]
+                .into_iter()
+                .chain(RustRefactoring::TOOL_NAMES.iter().map(|tool| tool.to_string()))
+                .collect(),
             ),
             ai_manager: None,
         }
@@ -388,6 +401,10 @@ impl RefactoringAgent {
     async fn refactor_code(&mut self, task: &AgentTask) -> Result<AgentResult, AgentError> {
         let start_time = std::time::Instant::now();
 
+        if let Some(refactoring) = self.select_refactoring(task).await? {
+            return self.run_refactoring(task, &refactoring, start_time);
+        }
+
         let code = task
             .context
             .get("existing_code")
@@ -427,6 +444,99 @@ impl RefactoringAgent {
         .with_next_action("Run tests to ensure functionality is preserved".to_string()))
     }
 
+    /// Pick a deterministic refactoring for the task, if one applies
+    ///
+    /// An explicit `refactoring` in the context or a tool name as task type
+    /// wins; otherwise, when no code was supplied to rewrite, the model may
+    /// choose one of the tools.
+    async fn select_refactoring(&self, task: &AgentTask) -> Result<Option<RustRefactoring>, AgentError> {
+        if let Some(refactoring) = task.context.get("refactoring") {
+            return serde_json::from_value(refactoring.clone())
+                .map(Some)
+                .map_err(|e| AgentError::TaskExecutionFailed(format!("Invalid refactoring: {}", e)));
+        }
+
+        if RustRefactoring::TOOL_NAMES.contains(&task.task_type.as_str()) {
+            let mut arguments = task.context.as_object().cloned().unwrap_or_default();
+            arguments.insert("tool".to_string(), json!(task.task_type));
+            return serde_json::from_value(serde_json::Value::Object(arguments))
+                .map(Some)
+                .map_err(|e| {
+                    AgentError::TaskExecutionFailed(format!("Invalid {} arguments: {}", task.task_type, e))
+                });
+        }
+
+        match &self.ai_manager {
+            Some(ai_manager) if task.context.get("existing_code").is_none() => {
+                self.choose_refactoring_with_ai(ai_manager, &task.description).await
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn choose_refactoring_with_ai(
+        &self,
+        ai_manager: &AIManager,
+        description: &str,
+    ) -> Result<Option<RustRefactoring>, AgentError> {
+        let system_prompt = "You are an expert Rust engineer. You can refactor code only by calling one of the tools listed by the user. Reply with a single JSON object holding the tool name under \"tool\" and its arguments, or with NONE if no tool fits.";
+
+        let user_prompt = format!(
+            "Tools:\n{}\n\nRefactoring request: {}",
+            serde_json::to_string_pretty(&RustRefactoring::tool_descriptions()).unwrap_or_default(),
+            description
+        );
+
+        let response = ai_manager
+            .generate_response(system_prompt, &user_prompt, Some(500), Some(0.0))
+            .await
+            .map_err(|e| AgentError::AIServiceError(e.to_string()))?;
+
+        let call = match (response.find('{'), response.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &response[start..=end],
+            _ => return Ok(None),
+        };
+        Ok(serde_json::from_str(call).ok())
+    }
+
+    fn run_refactoring(
+        &mut self,
+        task: &AgentTask,
+        refactoring: &RustRefactoring,
+        start_time: std::time::Instant,
+    ) -> Result<AgentResult, AgentError> {
+        let project_root = match task.context.get("project_root").and_then(|root| root.as_str()) {
+            Some(root) => std::path::PathBuf::from(root),
+            None => std::env::current_dir()?,
+        };
+
+        let changeset = match RustRefactorer::new(&project_root).apply(refactoring) {
+            Ok(changeset) => changeset,
+            Err(e) => {
+                self.base.update_metrics(false, start_time.elapsed());
+                return Err(AgentError::TaskExecutionFailed(e.to_string()));
+            }
+        };
+
+        let content = serde_json::to_string_pretty(&changeset)?;
+        let artifact = AgentArtifact::new("changeset".to_string(), "changeset".to_string(), content)
+            .with_mime_type("application/json".to_string())
+            .with_metadata("refactoring_type".to_string(), json!(refactoring.tool_name()))
+            .with_metadata("refactoring".to_string(), json!(refactoring));
+
+        let duration = start_time.elapsed();
+        self.base.update_metrics(true, duration);
+
+        Ok(AgentResult::success(
+            task.id.clone(),
+            self.base.id.clone(),
+            changeset.description.clone(),
+        )
+        .with_artifact(artifact)
+        .with_duration(duration)
+        .with_next_action("Review the changeset before applying it".to_string()))
+    }
+
     async fn refactor_with_ai(
         &self,
         ai_manager: &AIManager,
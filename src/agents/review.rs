@@ -1,13 +1,16 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::time::Instant;
 
 use crate::agents::task::{AgentArtifact, AgentResult, AgentTask};
 use crate::agents::{Agent, AgentError, AgentMetrics, AgentStatus, BaseAgent};
 use crate::ai::AIManager;
+use crate::context::history::{self, CoChangeSuggestion, HistoryAnalyzer, HistoryIndex, Hotspot};
+use crate::lsp::convert::{self, DIAGNOSTIC_SOURCE};
+use crate::lsp::LanguageServerPool;
 use serde_json::json;
 
 /// Code review agent that performs comprehensive code analysis
@@ -15,6 +18,7 @@ use serde_json::json;
 pub struct CodeReviewAgent {
     base: BaseAgent,
     ai_manager: Option<Arc<AIManager>>,
+    language_servers: Option<Arc<LanguageServerPool>>,
 }
 
 /// Severity levels for review issues
@@ -97,6 +101,12 @@ pub struct ReviewResult {
     pub files_reviewed: usize,
     pub total_lines: usize,
     pub review_duration: std::time::Duration,
+    /// Frequently changed files among the reviewed paths, from git history
+    #[serde(default)]
+    pub hotspots: Vec<Hotspot>,
+    /// Files that usually change with the uncommitted changes but were not modified
+    #[serde(default)]
+    pub co_change_suggestions: Vec<CoChangeSuggestion>,
 }
 
 /// Summary statistics from code review
@@ -124,6 +134,7 @@ impl CodeReviewAgent {
                 ],
             ),
             ai_manager: None,
+            language_servers: None,
         }
     }
 
@@ -134,6 +145,12 @@ impl CodeReviewAgent {
         agent
     }
 
+    /// Report diagnostics from language servers alongside the built-in analyses
+    pub fn with_language_servers(mut self, language_servers: Arc<LanguageServerPool>) -> Self {
+        self.language_servers = Some(language_servers);
+        self
+    }
+
     /// Perform code review on specified paths
     pub async fn review_code(
         &mut self,
@@ -167,8 +184,51 @@ impl CodeReviewAgent {
             }
         }
 
-        // Sort issues by severity (highest first)
-        all_issues.sort_by(|a, b| b.severity.cmp(&a.severity));
+        // Weigh the review with change history when the paths are in a repository
+        let history = match paths.first() {
+            Some(path) => self.load_history(path).await,
+            None => None,
+        };
+        let mut hotspots = Vec::new();
+        let mut co_change_suggestions = Vec::new();
+        if let Some((root, index)) = &history {
+            hotspots = history::hotspots(index.iter(), usize::MAX)
+                .into_iter()
+                .filter(|hotspot| is_under_any(&root.join(&hotspot.path), paths))
+                .take(10)
+                .collect();
+
+            let changed = history::uncommitted_changes(root).unwrap_or_default();
+            co_change_suggestions = history::suggest_co_changes(index.iter(), &changed)
+                .into_iter()
+                .filter(|suggestion| is_under_any(&root.join(&suggestion.changed), paths))
+                .collect();
+            all_issues.extend(
+                co_change_suggestions
+                    .iter()
+                    .map(|suggestion| co_change_issue(root, suggestion))
+                    .filter(|issue| issue.severity >= config.severity_threshold),
+            );
+        }
+
+        // Sort issues by severity (highest first), then issues in hotspots first
+        let hotspot_score = |issue: &ReviewIssue| -> f32 {
+            history
+                .as_ref()
+                .and_then(|(root, index)| {
+                    let absolute = absolute_path(&issue.file_path);
+                    index.get(absolute.strip_prefix(root).ok()?)
+                })
+                .map(|file| file.hotspot_score)
+                .unwrap_or(0.0)
+        };
+        all_issues.sort_by(|a, b| {
+            b.severity.cmp(&a.severity).then_with(|| {
+                hotspot_score(b)
+                    .partial_cmp(&hotspot_score(a))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
 
         let summary = self.generate_summary(&all_issues);
         let review_duration = start_time.elapsed();
@@ -181,9 +241,25 @@ impl CodeReviewAgent {
             files_reviewed,
             total_lines,
             review_duration,
+            hotspots,
+            co_change_suggestions,
         })
     }
 
+    /// Load git history for the repository containing `path`
+    async fn load_history(&self, path: &Path) -> Option<(PathBuf, HistoryIndex)> {
+        let root = history::repository_root(&absolute_path(path))?;
+        let root = root.canonicalize().unwrap_or(root);
+        let analysis_root = root.clone();
+        let index = tokio::task::spawn_blocking(move || {
+            HistoryAnalyzer::default().analyze(&analysis_root)
+        })
+        .await
+        .ok()?
+        .ok()?;
+        Some((root, index))
+    }
+
     /// Review a single file
     async fn review_file(
         &self,
@@ -201,6 +277,19 @@ impl CodeReviewAgent {
 
         let mut issues = Vec::new();
 
+        // Compiler findings come first so the per-file limit keeps them
+        if let Some(language_servers) = &self.language_servers {
+            let path = absolute_path(file_path);
+            if let Some(diagnostics) = language_servers.diagnostics(&path, &content).await {
+                issues.extend(
+                    diagnostics
+                        .iter()
+                        .filter(|diagnostic| diagnostic.source.as_deref() != Some(DIAGNOSTIC_SOURCE))
+                        .map(|diagnostic| convert::review_issue(file_path, diagnostic)),
+                );
+            }
+        }
+
         // Run different types of analysis based on focus areas
         for category in &config.focus_areas {
             match category {
@@ -282,6 +371,8 @@ impl CodeReviewAgent {
                 files_reviewed,
                 total_lines,
                 review_duration: std::time::Duration::from_secs(0), // Will be set by caller
+                hotspots: Vec::new(),
+                co_change_suggestions: Vec::new(),
             })
         })
     }
@@ -630,6 +721,48 @@ Return findings as JSON with format: {{\"issues\": [{{\"title\": \"Issue title\"
     }
 }
 
+/// Resolve a path against the current directory, canonicalized when it exists
+fn absolute_path(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    absolute.canonicalize().unwrap_or(absolute)
+}
+
+/// Whether `path` is one of the reviewed paths or below one of them
+fn is_under_any(path: &Path, reviewed: &[PathBuf]) -> bool {
+    reviewed
+        .iter()
+        .any(|reviewed| path.starts_with(absolute_path(reviewed)))
+}
+
+/// Review note for a file that usually changes along with a modified one
+fn co_change_issue(root: &Path, suggestion: &CoChangeSuggestion) -> ReviewIssue {
+    ReviewIssue {
+        category: ReviewCategory::Maintainability,
+        severity: ReviewSeverity::Low,
+        title: format!("{} usually changes too", suggestion.suggested.display()),
+        description: format!(
+            "{} was modified, and {} changed along with it in {} commits ({:.0}% of its changes)",
+            suggestion.changed.display(),
+            suggestion.suggested.display(),
+            suggestion.shared_commits,
+            suggestion.confidence * 100.0
+        ),
+        file_path: root.join(&suggestion.changed),
+        line_start: None,
+        line_end: None,
+        suggestion: Some(format!(
+            "Check whether {} needs a matching change",
+            suggestion.suggested.display()
+        )),
+        auto_fixable: false,
+        code_snippet: None,
+    }
+}
+
 #[async_trait::async_trait]
 impl Agent for CodeReviewAgent {
     fn id(&self) -> &str {
@@ -683,6 +816,11 @@ impl Agent for CodeReviewAgent {
         .with_metadata(
             "files_reviewed".to_string(),
             json!(review_result.files_reviewed),
+        )
+        .with_metadata("hotspots".to_string(), json!(review_result.hotspots))
+        .with_metadata(
+            "co_change_suggestions".to_string(),
+            json!(review_result.co_change_suggestions),
         );
 
         // Update metrics
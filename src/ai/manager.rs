@@ -437,6 +437,7 @@ mod tests {
             },
             openai: None,
             anthropic: None,
+            embeddings: Default::default(),
             context_window_size: 8192,
             temperature: 0.7,
             max_tokens: 1000,
@@ -3,7 +3,8 @@
 //! This module provides comprehensive blueprint versioning, evolution tracking,
 //! and migration capabilities to manage blueprint changes over time.
 
-use anyhow::Result;
+use crate::blueprint::schema::MigrationRegistry;
+use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -330,7 +331,8 @@ impl BlueprintEvolutionTracker {
         Ok(())
     }
 
-    /// Load evolution history from disk
+    /// Load evolution history from disk, upgrading blueprints recorded with
+    /// an older schema version
     pub async fn load(&mut self) -> Result<()> {
         let config_path = self.history_path.join("evolution.json");
         if !config_path.exists() {
@@ -343,11 +345,17 @@ impl BlueprintEvolutionTracker {
         self.current_branch = config.current_branch;
 
         // Load all branches
+        let migrations = MigrationRegistry::default();
         for branch_name in &config.branches {
             let branch_path = self.history_path.join(format!("{}.json", branch_name));
             if branch_path.exists() {
                 let branch_content = tokio::fs::read_to_string(&branch_path).await?;
-                let entries: Vec<EvolutionEntry> = serde_json::from_str(&branch_content)?;
+                let entries: Vec<serde_json::Value> = serde_json::from_str(&branch_content)?;
+                let entries = entries
+                    .into_iter()
+                    .map(|entry| Self::migrate_entry(&migrations, entry))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("Failed to load {}", branch_path.display()))?;
                 self.branches.insert(branch_name.clone(), entries);
             }
         }
@@ -355,6 +363,17 @@ impl BlueprintEvolutionTracker {
         Ok(())
     }
 
+    /// Parse a history entry, upgrading its blueprint to the current schema
+    fn migrate_entry(
+        migrations: &MigrationRegistry,
+        mut entry: serde_json::Value,
+    ) -> Result<EvolutionEntry> {
+        if let Some(blueprint) = entry.get_mut("blueprint") {
+            migrations.migrate(blueprint)?;
+        }
+        Ok(serde_json::from_value(entry)?)
+    }
+
     /// Save evolution history to disk
     pub async fn save(&self) -> Result<()> {
         // Save evolution config
@@ -474,6 +493,8 @@ impl std::str::FromStr for BlueprintVersion {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blueprint::schema::CURRENT_SCHEMA_VERSION;
+    use crate::blueprint::tests::TestUtils;
 
     #[test]
     fn test_blueprint_version_parsing() {
@@ -521,4 +542,34 @@ mod tests {
         version.increment_major();
         assert_eq!(version.to_string(), "2.0.0");
     }
+
+    #[tokio::test]
+    async fn test_load_migrates_legacy_blueprints() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tracker = BlueprintEvolutionTracker::new(dir.path().to_path_buf());
+        tracker.init().await.unwrap();
+
+        let blueprint = TestUtils::create_blueprint_with_modules("orders", "1.0.0", 1);
+        let entry = TestUtils::create_test_entry(BlueprintVersion::new(1, 0, 0), blueprint, vec![]);
+        let mut entry = serde_json::to_value(entry).unwrap();
+        let legacy = entry["blueprint"].as_object_mut().unwrap();
+        legacy.remove("schema_version");
+        legacy.remove("security");
+        legacy["modules"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("internal_structure");
+        std::fs::write(
+            dir.path().join("main.json"),
+            serde_json::to_string(&vec![entry]).unwrap(),
+        )
+        .unwrap();
+
+        tracker.load().await.unwrap();
+        let history = tracker.get_history().unwrap();
+        let blueprint = &history[0].blueprint;
+        assert_eq!(blueprint.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(blueprint.modules[0].name, "module_0");
+        assert!(blueprint.modules[0].internal_structure.functions.is_empty());
+    }
 }
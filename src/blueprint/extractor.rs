@@ -17,6 +17,7 @@ pub struct BlueprintExtractor {
     rust_language: Language,
     codebase_path: PathBuf,
     analysis_cache: HashMap<PathBuf, FileAnalysis>,
+    language_analyzer: languages::MultiLanguageAnalyzer,
 }
 
 /// Analysis results for a single file
@@ -148,6 +149,7 @@ impl BlueprintExtractor {
             rust_language,
             codebase_path,
             analysis_cache: HashMap::new(),
+            language_analyzer: languages::MultiLanguageAnalyzer::new(),
         })
     }
 
@@ -158,8 +160,15 @@ impl BlueprintExtractor {
             self.codebase_path
         );
 
+        // Detect the languages we have analyzers for, most prominent first
+        let mut detected = self
+            .language_analyzer
+            .detect_languages(&self.codebase_path)
+            .await?;
+        detected.retain(|language| self.language_analyzer.analyzer(language).is_some());
+
         // Start with basic metadata
-        let metadata = self.extract_metadata().await?;
+        let metadata = self.extract_metadata(detected.first()).await?;
 
         // Analyze all source files
         let files = self.discover_source_files().await?;
@@ -167,16 +176,30 @@ impl BlueprintExtractor {
 
         // Extract different aspects of the system
         let architecture = self.extract_architectural_decisions().await?;
-        let modules = self.extract_module_blueprints().await?;
+        let mut modules = self.extract_module_blueprints().await?;
         let patterns = self.extract_design_patterns().await?;
-        let implementation = self.extract_implementation_details().await?;
+        let mut implementation = self.extract_implementation_details(&detected).await?;
+        self.extract_language_modules(&detected, &mut modules, &mut implementation)
+            .await?;
         let configuration = self.extract_configuration_strategy().await?;
         let testing = self.extract_testing_strategy().await?;
         let performance = self.extract_performance_optimizations().await?;
         let security = self.extract_security_patterns().await?;
         let deployment = self.extract_deployment_strategy().await?;
 
+        // Polyglot codebases also get a per-language breakdown
+        let languages = if detected.len() > 1 {
+            Some(
+                self.language_analyzer
+                    .analyze_project(&self.codebase_path)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
         let blueprint = SystemBlueprint {
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
             metadata,
             architecture,
             modules,
@@ -187,6 +210,7 @@ impl BlueprintExtractor {
             performance,
             security,
             deployment,
+            languages,
         };
 
         println!(
@@ -196,26 +220,71 @@ impl BlueprintExtractor {
         Ok(blueprint)
     }
 
-    /// Extract system metadata
-    async fn extract_metadata(&self) -> Result<SystemMetadata> {
-        let cargo_toml_path = self.codebase_path.join("Cargo.toml");
+    /// Extract system metadata from the primary language's manifest
+    async fn extract_metadata(
+        &self,
+        primary_language: Option<&languages::Language>,
+    ) -> Result<SystemMetadata> {
         let mut name = "Unknown".to_string();
         let mut version = "0.1.0".to_string();
         let mut description = "No description available".to_string();
 
-        if cargo_toml_path.exists() {
-            let content = fs::read_to_string(&cargo_toml_path).await?;
-            if let Ok(parsed) = toml::from_str::<toml::Value>(&content) {
-                if let Some(package) = parsed.get("package") {
-                    if let Some(pkg_name) = package.get("name").and_then(|v| v.as_str()) {
-                        name = pkg_name.to_string();
-                    }
-                    if let Some(pkg_version) = package.get("version").and_then(|v| v.as_str()) {
-                        version = pkg_version.to_string();
-                    }
-                    if let Some(pkg_desc) = package.get("description").and_then(|v| v.as_str()) {
-                        description = pkg_desc.to_string();
-                    }
+        let manifest = match primary_language {
+            Some(languages::Language::JavaScript) | Some(languages::Language::TypeScript) => {
+                "package.json"
+            }
+            Some(languages::Language::Python) => "pyproject.toml",
+            Some(languages::Language::Go) => "go.mod",
+            _ => "Cargo.toml",
+        };
+        let manifest_path = self.codebase_path.join(manifest);
+
+        if manifest_path.exists() {
+            let content = fs::read_to_string(&manifest_path).await?;
+            let fields = match manifest {
+                "package.json" => serde_json::from_str::<serde_json::Value>(&content)
+                    .ok()
+                    .map(|package| {
+                        ["name", "version", "description"].map(|key| {
+                            package
+                                .get(key)
+                                .and_then(|v| v.as_str())
+                                .map(str::to_string)
+                        })
+                    }),
+                "go.mod" => content
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix("module "))
+                    .map(|module| {
+                        let module = module.trim().trim_matches('"');
+                        [module.rsplit('/').next().map(str::to_string), None, None]
+                    }),
+                // Cargo.toml's [package], PEP 621's [project] or Poetry's [tool.poetry]
+                _ => toml::from_str::<toml::Value>(&content)
+                    .ok()
+                    .and_then(|parsed| {
+                        let package = parsed
+                            .get("package")
+                            .or_else(|| parsed.get("project"))
+                            .or_else(|| parsed.get("tool").and_then(|tool| tool.get("poetry")))?;
+                        Some(["name", "version", "description"].map(|key| {
+                            package
+                                .get(key)
+                                .and_then(|v| v.as_str())
+                                .map(str::to_string)
+                        }))
+                    }),
+            };
+
+            if let Some([pkg_name, pkg_version, pkg_desc]) = fields {
+                if let Some(pkg_name) = pkg_name {
+                    name = pkg_name;
+                }
+                if let Some(pkg_version) = pkg_version {
+                    version = pkg_version;
+                }
+                if let Some(pkg_desc) = pkg_desc {
+                    description = pkg_desc;
                 }
             }
         }
@@ -225,7 +294,8 @@ impl BlueprintExtractor {
             version,
             description,
             architecture_paradigm: "Multi-agent concurrent system".to_string(),
-            primary_language: "Rust".to_string(),
+            primary_language: primary_language
+                .map_or_else(|| "Rust".to_string(), |language| format!("{:?}", language)),
             creation_timestamp: chrono::Utc::now(),
             generator_version: env!("CARGO_PKG_VERSION").to_string(),
         })
@@ -1010,8 +1080,73 @@ impl BlueprintExtractor {
         })
     }
 
+    /// Extract modules and dependency usage for the non-Rust languages, which
+    /// the tree-sitter pass doesn't cover, from their language analyzers
+    async fn extract_language_modules(
+        &self,
+        detected: &[languages::Language],
+        modules: &mut Vec<ModuleBlueprint>,
+        implementation: &mut ImplementationDetails,
+    ) -> Result<()> {
+        for language in detected {
+            if *language == languages::Language::Rust {
+                continue;
+            }
+            let Some(analyzer) = self.language_analyzer.analyzer(language) else {
+                continue;
+            };
+
+            let language_modules = analyzer
+                .extract_modules(&self.codebase_path)
+                .await
+                .with_context(|| format!("Failed to extract {:?} modules", language))?;
+            let dependencies = analyzer
+                .extract_dependencies(&self.codebase_path)
+                .await
+                .with_context(|| format!("Failed to read {:?} dependencies", language))?;
+
+            for dependency in dependencies {
+                let importers = language_modules
+                    .iter()
+                    .filter(|module| {
+                        module
+                            .dependencies
+                            .iter()
+                            .any(|d| imports_dependency(&d.module, &dependency.name))
+                    })
+                    .count();
+
+                implementation
+                    .third_party_dependencies
+                    .push(DependencyUsage {
+                        crate_name: dependency.name,
+                        version: dependency.version,
+                        purpose: dependency.purpose,
+                        integration_pattern: format!(
+                            "{} package imported by {} module(s)",
+                            language.package_manager(),
+                            importers
+                        ),
+                        alternatives_evaluated: Vec::new(),
+                        selection_criteria: vec![if dependency.optional {
+                            "Development dependency".to_string()
+                        } else {
+                            "Runtime dependency".to_string()
+                        }],
+                    });
+            }
+
+            modules.extend(language_modules);
+        }
+
+        Ok(())
+    }
+
     /// Extract implementation details
-    async fn extract_implementation_details(&self) -> Result<ImplementationDetails> {
+    async fn extract_implementation_details(
+        &self,
+        detected: &[languages::Language],
+    ) -> Result<ImplementationDetails> {
         let mut third_party_dependencies = Vec::new();
 
         // Extract dependencies from Cargo.toml analysis
@@ -1030,8 +1165,9 @@ impl BlueprintExtractor {
             }
         }
 
-        Ok(ImplementationDetails {
-            language_specific_features: vec![LanguageFeatureUsage {
+        let mut language_specific_features = Vec::new();
+        if detected.is_empty() || detected.contains(&languages::Language::Rust) {
+            language_specific_features.push(LanguageFeatureUsage {
                 feature: "Ownership and borrowing".to_string(),
                 usage_pattern: "Zero-copy data access where possible".to_string(),
                 justification: "Memory safety without garbage collection".to_string(),
@@ -1039,7 +1175,11 @@ impl BlueprintExtractor {
                     "Reference counting".to_string(),
                     "Garbage collection".to_string(),
                 ],
-            }],
+            });
+        }
+
+        Ok(ImplementationDetails {
+            language_specific_features,
             third_party_dependencies,
             custom_implementations: Vec::new(),
             optimization_techniques: Vec::new(),
@@ -1152,6 +1292,15 @@ impl BlueprintExtractor {
     }
 }
 
+/// Whether an import path refers to `dependency` or one of its submodules
+fn imports_dependency(import: &str, dependency: &str) -> bool {
+    let normalize = |name: &str| name.to_lowercase().replace('-', "_");
+    let (import, dependency) = (normalize(import), normalize(dependency));
+    import
+        .strip_prefix(&dependency)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with('.'))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1217,4 +1366,124 @@ fn main() {
         assert_eq!(blueprint.metadata.version, "0.1.0");
         assert!(!blueprint.modules.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_polyglot_blueprint_extraction() {
+        let temp_dir = tempdir().unwrap();
+        let temp_path = temp_dir.path().to_path_buf();
+
+        tokio::fs::write(
+            temp_path.join("pyproject.toml"),
+            r#"
+[tool.poetry]
+name = "orders"
+version = "2.1.0"
+description = "Order service"
+
+[tool.poetry.dependencies]
+python = "^3.11"
+fastapi = "^0.110"
+"#,
+        )
+        .await
+        .unwrap();
+        tokio::fs::create_dir_all(temp_path.join("orders"))
+            .await
+            .unwrap();
+        tokio::fs::write(
+            temp_path.join("orders/api.py"),
+            r#""""HTTP routes for orders."""
+from fastapi import FastAPI
+
+app = FastAPI()
+
+
+async def list_orders(limit: int = 10) -> list[dict]:
+    """Return the most recent orders."""
+    return []
+"#,
+        )
+        .await
+        .unwrap();
+
+        tokio::fs::write(
+            temp_path.join("go.mod"),
+            "module example.com/worker\n\ngo 1.21\n",
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            temp_path.join("main.go"),
+            "package main\n\n// Run starts the worker.\nfunc Run() {}\n\nfunc main() { Run() }\n",
+        )
+        .await
+        .unwrap();
+
+        let mut extractor = BlueprintExtractor::new(temp_path).unwrap();
+        let blueprint = extractor.extract_blueprint().await.unwrap();
+
+        assert_eq!(blueprint.metadata.name, "orders");
+        assert_eq!(blueprint.metadata.version, "2.1.0");
+        assert_eq!(blueprint.metadata.primary_language, "Python");
+
+        let api = blueprint
+            .modules
+            .iter()
+            .find(|m| m.name == "orders.api")
+            .unwrap();
+        assert_eq!(api.purpose, "HTTP routes for orders.");
+        assert_eq!(api.public_interface[0].name, "list_orders");
+        assert_eq!(
+            api.public_interface[0].signature,
+            "async def list_orders(limit: int = 10) -> list[dict]"
+        );
+        assert_eq!(
+            api.public_interface[0].documentation,
+            "Return the most recent orders."
+        );
+        assert!(blueprint
+            .modules
+            .iter()
+            .any(|m| m.name == "example.com/worker"
+                && m.public_interface.iter().any(|i| i.name == "Run")));
+
+        let fastapi = blueprint
+            .implementation
+            .third_party_dependencies
+            .iter()
+            .find(|d| d.crate_name == "fastapi")
+            .unwrap();
+        assert_eq!(
+            fastapi.integration_pattern,
+            "pip package imported by 1 module(s)"
+        );
+        assert!(blueprint
+            .implementation
+            .language_specific_features
+            .is_empty());
+
+        let languages = blueprint.languages.as_ref().unwrap();
+        assert_eq!(languages.primary_language, languages::Language::Python);
+        assert_eq!(languages.secondary_languages, vec![languages::Language::Go]);
+        assert!(languages
+            .language_modules
+            .contains_key(&languages::Language::Go));
+
+        // The per-language breakdown survives the TOML round trip
+        let path = temp_dir.path().join("blueprint.toml");
+        blueprint.save_to_file(&path).unwrap();
+        let loaded = SystemBlueprint::load_from_file(&path).unwrap();
+        assert_eq!(loaded.languages.unwrap().language_modules.len(), 2);
+    }
+
+    #[test]
+    fn test_imports_dependency() {
+        assert!(imports_dependency("fastapi.responses", "fastapi"));
+        assert!(imports_dependency(
+            "github.com/gin-gonic/gin/binding",
+            "github.com/gin-gonic/gin"
+        ));
+        assert!(imports_dependency("@nestjs/core", "@nestjs/core"));
+        assert!(!imports_dependency("fastapi_users", "fastapi"));
+    }
 }
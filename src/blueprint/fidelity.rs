@@ -0,0 +1,755 @@
+//! Replication Fidelity Scoring
+//!
+//! Measures how closely a replicated project matches the blueprint it was
+//! generated from. The replica's blueprint is re-extracted and compared with
+//! the original module by module, interface by interface and dependency by
+//! dependency, and the replica is built and tested inside a sandbox. The
+//! resulting report gives the replication pipeline a regression metric.
+
+use super::evolution::{BlueprintDiffAnalyzer, BlueprintVersion, DiffSummary};
+use super::extractor::BlueprintExtractor;
+use super::*;
+use crate::codegen::staging::SKIPPED_DIRS;
+use crate::codegen::toolchain::{DiagnosticSeverity, ToolCommand, ToolchainRegistry};
+use crate::sandbox::{SandboxConfig, SandboxError, SandboxManager};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Scores a replicated project against its source blueprint
+pub struct FidelityAnalyzer {
+    weights: FidelityWeights,
+    toolchains: ToolchainRegistry,
+    sandbox_config: SandboxConfig,
+    check_build: bool,
+    timeout: Duration,
+}
+
+/// Weights combining the individual scores into the overall score
+///
+/// Checks that could not run are left out and the remaining weights are
+/// rescaled, so a replica isn't penalised for a missing toolchain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FidelityWeights {
+    pub modules: f64,
+    pub interfaces: f64,
+    pub dependencies: f64,
+    pub build: f64,
+    pub tests: f64,
+}
+
+impl Default for FidelityWeights {
+    fn default() -> Self {
+        Self {
+            modules: 0.3,
+            interfaces: 0.3,
+            dependencies: 0.2,
+            build: 0.1,
+            tests: 0.1,
+        }
+    }
+}
+
+/// Fidelity of a replica, all scores between 0.0 and 1.0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FidelityReport {
+    pub overall_score: f64,
+    pub module_score: f64,
+    pub interface_score: f64,
+    pub dependency_score: f64,
+    pub modules: Vec<ModuleFidelity>,
+    /// Modules the replica has but the original doesn't
+    pub extra_modules: Vec<String>,
+    pub missing_dependencies: Vec<String>,
+    pub checks: Vec<ReplicaCheck>,
+    pub diff_summary: DiffSummary,
+    /// Descriptions of the structural differences found by the diff
+    pub differences: Vec<String>,
+}
+
+/// Fidelity of one module of the original blueprint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleFidelity {
+    pub name: String,
+    pub present: bool,
+    pub interface_score: f64,
+    pub dependency_score: f64,
+    pub missing_interfaces: Vec<String>,
+}
+
+/// Build or test run of the replica
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicaCheck {
+    pub kind: ReplicaCheckKind,
+    pub command: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// What a replica check verifies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicaCheckKind {
+    Build,
+    Tests,
+}
+
+/// Outcome of a replica check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// The check doesn't apply or its tool is not installed
+    Skipped,
+}
+
+impl Default for FidelityAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FidelityAnalyzer {
+    /// Create a fidelity analyzer with the default toolchains and sandbox
+    pub fn new() -> Self {
+        Self {
+            weights: FidelityWeights::default(),
+            toolchains: ToolchainRegistry::default(),
+            sandbox_config: SandboxConfig::default(),
+            check_build: true,
+            timeout: Duration::from_secs(600),
+        }
+    }
+
+    pub fn with_weights(mut self, weights: FidelityWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Toolchains whose type-check and test commands build and test the replica
+    pub fn with_toolchains(mut self, toolchains: ToolchainRegistry) -> Self {
+        self.toolchains = toolchains;
+        self
+    }
+
+    pub fn with_sandbox_config(mut self, config: SandboxConfig) -> Self {
+        self.sandbox_config = config;
+        self
+    }
+
+    /// Timeout for each build or test command
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set whether to build and test the replica in a sandbox
+    pub fn check_build(mut self, check: bool) -> Self {
+        self.check_build = check;
+        self
+    }
+
+    /// Score the project at `replica_path` against the `original` blueprint
+    pub async fn assess(
+        &self,
+        original: &SystemBlueprint,
+        replica_path: &Path,
+    ) -> Result<FidelityReport> {
+        let mut extractor = BlueprintExtractor::new(replica_path.to_path_buf())?;
+        let replica = extractor.extract_blueprint().await?;
+
+        let checks = if self.check_build {
+            self.run_checks(replica_path, &replica.metadata.primary_language)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        self.score(original, &replica, checks)
+    }
+
+    /// Compare two blueprints and combine the result with the replica checks
+    pub fn score(
+        &self,
+        original: &SystemBlueprint,
+        replica: &SystemBlueprint,
+        checks: Vec<ReplicaCheck>,
+    ) -> Result<FidelityReport> {
+        // Metadata such as the extraction timestamp always differs
+        let diff = BlueprintDiffAnalyzer::new()
+            .ignore_paths(vec!["metadata".to_string()])
+            .analyze_diff(
+                original,
+                replica,
+                BlueprintVersion::new(1, 0, 0),
+                BlueprintVersion::new(1, 0, 0),
+            )?;
+
+        let replica_modules: BTreeMap<&str, &ModuleBlueprint> = replica
+            .modules
+            .iter()
+            .map(|module| (module.name.as_str(), module))
+            .collect();
+
+        let modules: Vec<ModuleFidelity> = original
+            .modules
+            .iter()
+            .map(|module| match replica_modules.get(module.name.as_str()) {
+                Some(copy) => {
+                    let interfaces = interface_keys(std::slice::from_ref(module), false);
+                    let copy_interfaces = interface_keys(std::slice::from_ref(*copy), false);
+                    ModuleFidelity {
+                        name: module.name.clone(),
+                        present: true,
+                        interface_score: keyed_score(&interfaces, &copy_interfaces),
+                        dependency_score: keyed_score(
+                            &module_dependency_keys(module),
+                            &module_dependency_keys(copy),
+                        ),
+                        missing_interfaces: interfaces
+                            .keys()
+                            .filter(|name| !copy_interfaces.contains_key(*name))
+                            .cloned()
+                            .collect(),
+                    }
+                }
+                None => ModuleFidelity {
+                    name: module.name.clone(),
+                    present: false,
+                    interface_score: 0.0,
+                    dependency_score: 0.0,
+                    missing_interfaces: module
+                        .public_interface
+                        .iter()
+                        .map(|interface| interface.name.clone())
+                        .collect(),
+                },
+            })
+            .collect();
+
+        let module_names = |blueprint: &SystemBlueprint| -> BTreeMap<String, String> {
+            blueprint
+                .modules
+                .iter()
+                .map(|module| (module.name.clone(), String::new()))
+                .collect()
+        };
+        let original_modules = module_names(original);
+        let extra_modules = replica
+            .modules
+            .iter()
+            .filter(|module| !original_modules.contains_key(&module.name))
+            .map(|module| module.name.clone())
+            .collect();
+
+        let original_dependencies = dependency_keys(original);
+        let replica_dependencies = dependency_keys(replica);
+        let missing_dependencies = original_dependencies
+            .keys()
+            .filter(|name| !replica_dependencies.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let module_score = keyed_score(&original_modules, &module_names(replica));
+        let interface_score = keyed_score(
+            &interface_keys(&original.modules, true),
+            &interface_keys(&replica.modules, true),
+        );
+        let dependency_score = keyed_score(&original_dependencies, &replica_dependencies);
+
+        let mut weighted = vec![
+            (self.weights.modules, module_score),
+            (self.weights.interfaces, interface_score),
+            (self.weights.dependencies, dependency_score),
+        ];
+        for check in &checks {
+            let weight = match check.kind {
+                ReplicaCheckKind::Build => self.weights.build,
+                ReplicaCheckKind::Tests => self.weights.tests,
+            };
+            match check.status {
+                CheckStatus::Passed => weighted.push((weight, 1.0)),
+                CheckStatus::Failed => weighted.push((weight, 0.0)),
+                CheckStatus::Skipped => {}
+            }
+        }
+        let total_weight: f64 = weighted.iter().map(|(weight, _)| weight).sum();
+        let overall_score = if total_weight > 0.0 {
+            weighted
+                .iter()
+                .map(|(weight, score)| weight * score)
+                .sum::<f64>()
+                / total_weight
+        } else {
+            0.0
+        };
+
+        Ok(FidelityReport {
+            overall_score,
+            module_score,
+            interface_score,
+            dependency_score,
+            modules,
+            extra_modules,
+            missing_dependencies,
+            checks,
+            diff_summary: diff.summary,
+            differences: diff
+                .changes
+                .into_iter()
+                .map(|change| change.description)
+                .collect(),
+        })
+    }
+
+    /// Build and test a copy of the replica inside a sandbox
+    async fn run_checks(&self, replica_path: &Path, language: &str) -> Result<Vec<ReplicaCheck>> {
+        // Blueprints record the language's display name, such as "TypeScript"
+        let toolchain_name = match language.to_lowercase().as_str() {
+            "javascript" => "typescript".to_string(),
+            other => other.to_string(),
+        };
+        let Some(toolchain) = self.toolchains.toolchain(&toolchain_name) else {
+            return Ok(vec![
+                ReplicaCheck::skipped(
+                    ReplicaCheckKind::Build,
+                    "",
+                    format!("no toolchain for {}", language),
+                ),
+                ReplicaCheck::skipped(
+                    ReplicaCheckKind::Tests,
+                    "",
+                    format!("no toolchain for {}", language),
+                ),
+            ]);
+        };
+        let commands = [
+            (ReplicaCheckKind::Build, toolchain.type_check.as_ref()),
+            (ReplicaCheckKind::Tests, toolchain.test.as_ref()),
+        ];
+
+        let manager = SandboxManager::new(self.sandbox_config.clone())?;
+        let sandbox_id = manager.create_sandbox().await?;
+        let checks = self
+            .run_in_sandbox(&manager, sandbox_id, replica_path, &commands)
+            .await;
+        manager.destroy_sandbox(sandbox_id).await?;
+        checks
+    }
+
+    async fn run_in_sandbox(
+        &self,
+        manager: &SandboxManager,
+        sandbox_id: Uuid,
+        replica_path: &Path,
+        commands: &[(ReplicaCheckKind, Option<&ToolCommand>)],
+    ) -> Result<Vec<ReplicaCheck>> {
+        let files = walkdir::WalkDir::new(replica_path)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !entry.file_type().is_dir()
+                    || !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+        for entry in files {
+            let path = entry.path().to_path_buf();
+            let relative = path
+                .strip_prefix(replica_path)?
+                .to_string_lossy()
+                .to_string();
+            manager
+                .copy_to_sandbox(sandbox_id, &path, &relative)
+                .await?;
+        }
+
+        let mut checks = Vec::new();
+        for (kind, command) in commands {
+            let Some(command) = command else {
+                checks.push(ReplicaCheck::skipped(
+                    *kind,
+                    "",
+                    "no command configured".to_string(),
+                ));
+                continue;
+            };
+            checks.push(
+                self.run_command(manager, sandbox_id, replica_path, *kind, command)
+                    .await,
+            );
+        }
+        Ok(checks)
+    }
+
+    async fn run_command(
+        &self,
+        manager: &SandboxManager,
+        sandbox_id: Uuid,
+        replica_path: &Path,
+        kind: ReplicaCheckKind,
+        command: &ToolCommand,
+    ) -> ReplicaCheck {
+        let display = command.display();
+        if command.per_file {
+            return ReplicaCheck::skipped(kind, &display, "runs per file".to_string());
+        }
+        if let Some(marker) = &command.requires {
+            if !replica_path.join(marker).exists() {
+                return ReplicaCheck::skipped(
+                    kind,
+                    &display,
+                    format!("no {} in the replica", marker),
+                );
+            }
+        }
+
+        let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+        let result = match manager
+            .execute(sandbox_id, &command.program, &args, Some(self.timeout))
+            .await
+        {
+            Ok(result) => result,
+            Err(SandboxError::ExecutionFailed(message)) => {
+                return ReplicaCheck::skipped(kind, &display, message);
+            }
+            Err(e) => {
+                return ReplicaCheck {
+                    kind,
+                    command: display,
+                    status: CheckStatus::Failed,
+                    detail: e.to_string(),
+                }
+            }
+        };
+
+        let exit_ok = result
+            .exit_code
+            .is_some_and(|code| code == 0 || command.ok_exit_codes.contains(&code));
+        let output = format!("{}{}", result.stdout, result.stderr);
+        let errors: Vec<_> = command
+            .output
+            .parse(&output)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+            .collect();
+
+        let (status, detail) = if exit_ok && errors.is_empty() {
+            (
+                CheckStatus::Passed,
+                format!("finished in {:.1}s", result.execution_time.as_secs_f64()),
+            )
+        } else if let Some(first) = errors.first() {
+            (
+                CheckStatus::Failed,
+                format!("{} error(s), first: {}", errors.len(), first),
+            )
+        } else {
+            let last_line = output.lines().rev().find(|line| !line.trim().is_empty());
+            (
+                CheckStatus::Failed,
+                match (result.exit_code, last_line) {
+                    (Some(code), Some(line)) => format!("exit code {}: {}", code, line.trim()),
+                    (Some(code), None) => format!("exit code {}", code),
+                    (None, _) => "terminated by a signal".to_string(),
+                },
+            )
+        };
+
+        ReplicaCheck {
+            kind,
+            command: display,
+            status,
+            detail,
+        }
+    }
+}
+
+impl ReplicaCheck {
+    fn skipped(kind: ReplicaCheckKind, command: &str, detail: String) -> Self {
+        Self {
+            kind,
+            command: command.to_string(),
+            status: CheckStatus::Skipped,
+            detail,
+        }
+    }
+}
+
+impl FidelityReport {
+    /// Whether the replica reaches `threshold` and none of its checks failed
+    pub fn passes(&self, threshold: f64) -> bool {
+        self.overall_score >= threshold
+            && self
+                .checks
+                .iter()
+                .all(|check| check.status != CheckStatus::Failed)
+    }
+
+    /// Save the report as JSON
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Render the report as a markdown section
+    pub fn to_markdown(&self) -> String {
+        let mut report = String::new();
+
+        report.push_str("## Fidelity\n\n");
+        report.push_str(&format!(
+            "- **Overall**: {:.1}%\n",
+            self.overall_score * 100.0
+        ));
+        report.push_str(&format!(
+            "- **Modules**: {:.1}%\n",
+            self.module_score * 100.0
+        ));
+        report.push_str(&format!(
+            "- **Interfaces**: {:.1}%\n",
+            self.interface_score * 100.0
+        ));
+        report.push_str(&format!(
+            "- **Dependencies**: {:.1}%\n",
+            self.dependency_score * 100.0
+        ));
+        report.push_str(&format!(
+            "- **Differences**: {} ({} breaking)\n\n",
+            self.diff_summary.total_changes, self.diff_summary.breaking_changes
+        ));
+
+        if !self.checks.is_empty() {
+            report.push_str("### Replica Checks\n\n");
+            for check in &self.checks {
+                let status = match check.status {
+                    CheckStatus::Passed => "✅ PASS",
+                    CheckStatus::Failed => "❌ FAIL",
+                    CheckStatus::Skipped => "⏭️ SKIP",
+                };
+                report.push_str(&format!(
+                    "- {} {:?} `{}`: {}\n",
+                    status, check.kind, check.command, check.detail
+                ));
+            }
+            report.push('\n');
+        }
+
+        report.push_str("### Modules\n\n");
+        report.push_str("| Module | Present | Interfaces | Dependencies |\n");
+        report.push_str("|--------|---------|------------|--------------|\n");
+        for module in &self.modules {
+            report.push_str(&format!(
+                "| {} | {} | {:.1}% | {:.1}% |\n",
+                module.name,
+                if module.present { "yes" } else { "no" },
+                module.interface_score * 100.0,
+                module.dependency_score * 100.0
+            ));
+        }
+        report.push('\n');
+
+        let missing: Vec<String> = self
+            .modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .missing_interfaces
+                    .iter()
+                    .map(move |name| format!("{}::{}", module.name, name))
+            })
+            .collect();
+        for (title, items) in [
+            ("Missing Interfaces", &missing),
+            ("Missing Dependencies", &self.missing_dependencies),
+            ("Extra Modules", &self.extra_modules),
+        ] {
+            if !items.is_empty() {
+                report.push_str(&format!("### {}\n\n", title));
+                for item in items {
+                    report.push_str(&format!("- {}\n", item));
+                }
+                report.push('\n');
+            }
+        }
+
+        report
+    }
+}
+
+/// Interface signatures keyed by name, prefixed with the module name when
+/// `qualified`
+fn interface_keys(modules: &[ModuleBlueprint], qualified: bool) -> BTreeMap<String, String> {
+    modules
+        .iter()
+        .flat_map(|module| {
+            module.public_interface.iter().map(move |interface| {
+                let name = if qualified {
+                    format!("{}::{}", module.name, interface.name)
+                } else {
+                    interface.name.clone()
+                };
+                let signature = interface.signature.split_whitespace().collect::<Vec<_>>();
+                (name, signature.join(" "))
+            })
+        })
+        .collect()
+}
+
+fn module_dependency_keys(module: &ModuleBlueprint) -> BTreeMap<String, String> {
+    module
+        .dependencies
+        .iter()
+        .map(|dependency| (dependency.module.clone(), String::new()))
+        .collect()
+}
+
+/// Third-party dependency versions keyed by name
+fn dependency_keys(blueprint: &SystemBlueprint) -> BTreeMap<String, String> {
+    blueprint
+        .implementation
+        .third_party_dependencies
+        .iter()
+        .map(|dependency| (dependency.crate_name.clone(), dependency.version.clone()))
+        .collect()
+}
+
+/// Share of keys present in both maps, with half credit where the values
+/// differ; two empty maps match perfectly
+fn keyed_score(original: &BTreeMap<String, String>, replica: &BTreeMap<String, String>) -> f64 {
+    let union: BTreeSet<&String> = original.keys().chain(replica.keys()).collect();
+    if union.is_empty() {
+        return 1.0;
+    }
+
+    let credit: f64 = original
+        .iter()
+        .filter_map(|(key, value)| {
+            replica
+                .get(key)
+                .map(|other| if other == value { 1.0 } else { 0.5 })
+        })
+        .sum();
+    credit / union.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::staging::SKIPPED_DIRS;
+use crate::codegen::toolchain::{DiagnosticSource, Toolchain};
+    use tempfile::tempdir;
+
+    async fn write_project(root: &Path, with_refund: bool) {
+        tokio::fs::create_dir_all(root.join("orders"))
+            .await
+            .unwrap();
+        tokio::fs::write(
+            root.join("requirements.txt"),
+            if with_refund {
+                "fastapi==0.110.0\nstripe==8.0.0\n"
+            } else {
+                "fastapi==0.110.0\n"
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut api = String::from(
+            "\"\"\"HTTP routes for orders.\"\"\"\nfrom fastapi import FastAPI\n\n\ndef list_orders(limit: int = 10) -> list:\n    return []\n",
+        );
+        if with_refund {
+            api.push_str("\n\ndef refund(order_id: str) -> bool:\n    return True\n");
+        }
+        tokio::fs::write(root.join("orders/api.py"), api)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_keyed_score() {
+        let map = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert_eq!(keyed_score(&map(&[]), &map(&[])), 1.0);
+        assert_eq!(keyed_score(&map(&[("a", "1")]), &map(&[("a", "1")])), 1.0);
+        assert_eq!(
+            keyed_score(&map(&[("a", "1"), ("b", "1")]), &map(&[("a", "2")])),
+            0.25
+        );
+        assert_eq!(
+            keyed_score(&map(&[("a", "1")]), &map(&[("a", "1"), ("c", "1")])),
+            0.5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fidelity_assessment() {
+        let source = tempdir().unwrap();
+        let replica = tempdir().unwrap();
+        let sandboxes = tempdir().unwrap();
+        write_project(source.path(), true).await;
+        write_project(replica.path(), false).await;
+
+        let original = BlueprintExtractor::new(source.path().to_path_buf())
+            .unwrap()
+            .extract_blueprint()
+            .await
+            .unwrap();
+
+        // Stand-in commands: the build passes once the sources were copied
+        // into the sandbox and the tests always fail
+        let toolchains = ToolchainRegistry::new().with_toolchain(
+            Toolchain::new("python", &["*.py"])
+                .with_type_check(
+                    ToolCommand::new("test", &["-f", "orders/api.py"], DiagnosticSource::Pytest)
+                        .requires("requirements.txt"),
+                )
+                .with_test(ToolCommand::new("false", &[], DiagnosticSource::Pytest)),
+        );
+        let analyzer = FidelityAnalyzer::new()
+            .with_toolchains(toolchains)
+            .with_sandbox_config(SandboxConfig {
+                temp_dir: sandboxes.path().to_path_buf(),
+                ..SandboxConfig::default()
+            });
+
+        let report = analyzer.assess(&original, source.path()).await.unwrap();
+        assert_eq!(report.module_score, 1.0);
+        assert_eq!(report.interface_score, 1.0);
+        assert_eq!(report.dependency_score, 1.0);
+        assert_eq!(report.checks[0].status, CheckStatus::Passed);
+        assert_eq!(report.checks[1].status, CheckStatus::Failed);
+        assert!(report.checks[1].detail.starts_with("exit code 1"));
+        assert!((report.overall_score - 0.9).abs() < 1e-9);
+        assert!(!report.passes(0.5));
+
+        let report = analyzer.assess(&original, replica.path()).await.unwrap();
+        let api = report
+            .modules
+            .iter()
+            .find(|module| module.name == "orders.api")
+            .unwrap();
+        assert!(api.present);
+        assert_eq!(api.interface_score, 0.5);
+        assert_eq!(api.missing_interfaces, vec!["refund".to_string()]);
+        assert_eq!(report.missing_dependencies, vec!["stripe".to_string()]);
+        assert_eq!(report.dependency_score, 0.5);
+        assert!(report.interface_score < 1.0);
+        assert!(report.diff_summary.total_changes > 0);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| orders.api | yes | 50.0% |"));
+        assert!(markdown.contains("- orders.api::refund"));
+
+        // Every sandbox is removed once the checks finish
+        assert_eq!(std::fs::read_dir(sandboxes.path()).unwrap().count(), 0);
+    }
+}
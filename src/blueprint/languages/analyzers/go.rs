@@ -0,0 +1,758 @@
+//! Go Language Analyzer
+//!
+//! Analyzes Go modules to extract package structure, dependencies,
+//! and build configuration for blueprint generation.
+
+use super::super::{
+    BuildConfig, Dependency, DependencySource, DocumentationConfig, Language, LanguageAnalyzer,
+    LanguageModule, TestConfig,
+};
+use super::source::{self, flat_text, named_children, preceding_comment, text, ModuleOutline};
+use crate::blueprint::{
+    ConstantDefinition, FunctionDefinition, ModuleBlueprint, Parameter, TypeDefinition,
+};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tree_sitter::Node;
+
+pub struct GoAnalyzer;
+
+impl GoAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Module path declared in go.mod
+    async fn module_path(&self, project_path: &Path) -> Result<Option<String>> {
+        let go_mod = project_path.join("go.mod");
+        if !go_mod.exists() {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(&go_mod).await?;
+        Ok(content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|path| path.trim().trim_matches('"').to_string()))
+    }
+
+    /// Parse go.mod requirements, applying any replace directives
+    async fn parse_go_mod(&self, project_path: &Path) -> Result<Vec<Dependency>> {
+        let go_mod = project_path.join("go.mod");
+        if !go_mod.exists() {
+            return Ok(vec![]);
+        }
+
+        let content = tokio::fs::read_to_string(&go_mod).await?;
+        Ok(self.parse_go_mod_content(&content))
+    }
+
+    /// Parse the contents of a go.mod file
+    fn parse_go_mod_content(&self, content: &str) -> Vec<Dependency> {
+        let mut dependencies = Vec::new();
+        let mut replacements = Vec::new();
+        let mut block = None;
+
+        for line in content.lines() {
+            let (line, comment) = match line.split_once("//") {
+                Some((line, comment)) => (line.trim(), comment.trim()),
+                None => (line.trim(), ""),
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            let (directive, rest) = match block {
+                Some(_) if line == ")" => {
+                    block = None;
+                    continue;
+                }
+                Some(directive) => (directive, line),
+                None => match line.split_once(char::is_whitespace) {
+                    Some((directive, "(")) => {
+                        block = Some(directive);
+                        continue;
+                    }
+                    Some((directive, rest)) => (directive, rest.trim()),
+                    None => continue,
+                },
+            };
+
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            match directive {
+                "require" if fields.len() >= 2 => {
+                    let indirect = comment == "indirect";
+                    dependencies.push(Dependency {
+                        name: fields[0].to_string(),
+                        version: fields[1].to_string(),
+                        source: DependencySource::Registry("proxy.golang.org".to_string()),
+                        purpose: if indirect {
+                            format!("Indirect: {}", self.infer_dependency_purpose(fields[0]))
+                        } else {
+                            self.infer_dependency_purpose(fields[0])
+                        },
+                        optional: false,
+                    });
+                }
+                "replace" => {
+                    if let Some(arrow) = fields.iter().position(|f| *f == "=>") {
+                        if let Some(target) = fields.get(arrow + 1) {
+                            replacements.push((fields[0].to_string(), target.to_string()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (module, target) in replacements {
+            for dependency in dependencies.iter_mut().filter(|d| d.name == module) {
+                dependency.source = if target.starts_with('.') || target.starts_with('/') {
+                    DependencySource::Local(target.clone())
+                } else {
+                    DependencySource::Git(target.clone())
+                };
+            }
+        }
+
+        dependencies
+    }
+
+    /// Find main packages: the module root and `cmd/*`
+    async fn find_entry_points(&self, project_path: &Path) -> Result<Vec<String>> {
+        let mut entry_points = Vec::new();
+
+        if project_path.join("main.go").exists() {
+            entry_points.push("main.go".to_string());
+        }
+
+        let cmd_dir = project_path.join("cmd");
+        if cmd_dir.is_dir() {
+            let mut entries = tokio::fs::read_dir(&cmd_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.path().join("main.go").exists() {
+                    entry_points.push(format!(
+                        "cmd/{}/main.go",
+                        entry.file_name().to_string_lossy()
+                    ));
+                }
+            }
+        }
+
+        entry_points.sort();
+        Ok(entry_points)
+    }
+
+    /// Infer the purpose of a dependency from its module path
+    fn infer_dependency_purpose(&self, name: &str) -> String {
+        let name = name.to_lowercase();
+        let matches = |candidates: &[&str]| candidates.iter().any(|c| name.contains(c));
+
+        if matches(&[
+            "gin-gonic/gin",
+            "labstack/echo",
+            "gofiber/fiber",
+            "go-chi/chi",
+            "gorilla/mux",
+        ]) {
+            "Web framework".to_string()
+        } else if matches(&[
+            "google.golang.org/grpc",
+            "google.golang.org/protobuf",
+            "twitchtv/twirp",
+        ]) {
+            "RPC".to_string()
+        } else if matches(&[
+            "gorm.io",
+            "jmoiron/sqlx",
+            "jackc/pgx",
+            "go-sql-driver",
+            "mattn/go-sqlite3",
+            "mongo-driver",
+            "go-redis",
+        ]) {
+            "Database".to_string()
+        } else if matches(&["spf13/cobra", "urfave/cli", "alecthomas/kong"]) {
+            "CLI framework".to_string()
+        } else if matches(&["spf13/viper", "kelseyhightower/envconfig", "joho/godotenv"]) {
+            "Configuration".to_string()
+        } else if matches(&["uber.org/zap", "sirupsen/logrus", "rs/zerolog"]) {
+            "Logging".to_string()
+        } else if matches(&[
+            "stretchr/testify",
+            "onsi/ginkgo",
+            "onsi/gomega",
+            "golang/mock",
+            "uber.org/mock",
+        ]) {
+            "Testing".to_string()
+        } else if matches(&["prometheus/client_golang", "opentelemetry"]) {
+            "Observability".to_string()
+        } else {
+            "Application dependency".to_string()
+        }
+    }
+
+    /// Detect the test framework from dependencies
+    async fn detect_test_framework(&self, project_path: &Path) -> Result<String> {
+        let deps = self.extract_dependencies(project_path).await?;
+        let framework = if deps.iter().any(|d| d.name.contains("onsi/ginkgo")) {
+            "ginkgo"
+        } else if deps.iter().any(|d| d.name.contains("stretchr/testify")) {
+            "testify"
+        } else {
+            "testing"
+        };
+        Ok(framework.to_string())
+    }
+
+    /// Parse a Go parameter list; grouped names (`a, b int`) each get the type
+    fn parse_parameters(list: Node, content: &str) -> Vec<Parameter> {
+        let mut parameters = Vec::new();
+        for declaration in named_children(list) {
+            let Some(type_node) = declaration.child_by_field_name("type") else {
+                continue;
+            };
+            let mut param_type = flat_text(type_node, content);
+            if declaration.kind() == "variadic_parameter_declaration" {
+                param_type = format!("...{}", param_type);
+            }
+            let mut cursor = declaration.walk();
+            let mut names: Vec<String> = declaration
+                .children_by_field_name("name", &mut cursor)
+                .map(|name| text(name, content).to_string())
+                .collect();
+            if names.is_empty() {
+                names.push("_".to_string());
+            }
+
+            let is_pointer = param_type.starts_with('*');
+            for name in names {
+                parameters.push(Parameter {
+                    name,
+                    is_mutable: is_pointer,
+                    ownership: if is_pointer { "mutable_ref" } else { "owned" }.to_string(),
+                    param_type: param_type.clone(),
+                });
+            }
+        }
+        parameters
+    }
+
+    /// Members of a struct or interface type, without struct tags
+    fn type_members(type_node: Node, content: &str) -> Vec<String> {
+        let members = match type_node.kind() {
+            "struct_type" => type_node
+                .named_child(0)
+                .map(named_children)
+                .unwrap_or_default(),
+            _ => named_children(type_node),
+        };
+        members
+            .into_iter()
+            .map(|member| {
+                let end = member
+                    .child_by_field_name("tag")
+                    .map_or(member.end_byte(), |tag| tag.start_byte());
+                content[member.start_byte()..end]
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// Parse a function or method declaration
+    fn parse_function(
+        outline: &mut ModuleOutline,
+        methods: &mut Vec<(String, String)>,
+        node: Node,
+        content: &str,
+    ) {
+        let (Some(name), Some(params)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("parameters"),
+        ) else {
+            return;
+        };
+        let name = text(name, content);
+        let results = node
+            .child_by_field_name("result")
+            .map(|result| flat_text(result, content))
+            .unwrap_or_default();
+
+        let receiver = node
+            .child_by_field_name("receiver")
+            .and_then(|receiver| named_children(receiver).into_iter().next());
+        let receiver_type = receiver
+            .and_then(|receiver| receiver.child_by_field_name("type"))
+            .map(|receiver_type| {
+                text(receiver_type, content)
+                    .trim_start_matches('*')
+                    .split('[')
+                    .next()
+                    .unwrap_or("")
+                    .to_string()
+            });
+        let qualified = match &receiver_type {
+            Some(receiver_type) => format!("{}.{}", receiver_type, name),
+            None => name.to_string(),
+        };
+
+        let exported = name.starts_with(|c: char| c.is_uppercase())
+            && receiver_type
+                .as_deref()
+                .map_or(true, |r| r.starts_with(|c: char| c.is_uppercase()));
+        let documentation = preceding_comment(content, node.start_byte(), &["//"]);
+        if exported {
+            let signature = format!(
+                "func {}{}({}){}",
+                receiver.map_or(String::new(), |r| format!("({}) ", flat_text(r, content))),
+                name,
+                named_children(params)
+                    .into_iter()
+                    .map(|param| flat_text(param, content))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                if results.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", results)
+                }
+            );
+            let kind = if receiver.is_some() {
+                "method"
+            } else {
+                "function"
+            };
+            outline.export(&qualified, kind, signature, documentation.clone());
+        }
+        if let Some(receiver_type) = receiver_type {
+            methods.push((receiver_type, name.to_string()));
+        }
+
+        outline.functions.push(FunctionDefinition {
+            name: qualified,
+            visibility: if exported { "public" } else { "private" }.to_string(),
+            is_async: false,
+            parameters: Self::parse_parameters(params, content),
+            return_type: if results.is_empty() {
+                "none".to_string()
+            } else {
+                results
+            },
+            purpose: documentation.unwrap_or_else(|| "Auto-extracted function".to_string()),
+            complexity: "medium".to_string(), // Default
+        });
+    }
+
+    /// Parse one `type` spec: a struct, an interface, a defined type or an alias
+    fn parse_type(outline: &mut ModuleOutline, spec: Node, documented: Node, content: &str) {
+        let (Some(name), Some(type_node)) = (
+            spec.child_by_field_name("name"),
+            spec.child_by_field_name("type"),
+        ) else {
+            return;
+        };
+        let name = text(name, content);
+        let documentation = preceding_comment(content, documented.start_byte(), &["//"]);
+
+        let (kind, signature, members) = match type_node.kind() {
+            "struct_type" | "interface_type" => {
+                let kind = type_node.kind().trim_end_matches("_type");
+                (
+                    kind,
+                    format!("type {} {}", name, kind),
+                    Self::type_members(type_node, content),
+                )
+            }
+            _ => {
+                let definition = flat_text(type_node, content);
+                let alias = if spec.kind() == "type_alias" {
+                    "= "
+                } else {
+                    ""
+                };
+                (
+                    "type",
+                    format!("type {} {}{}", name, alias, definition),
+                    vec![definition],
+                )
+            }
+        };
+        if name.starts_with(|c: char| c.is_uppercase()) {
+            outline.export(name, kind, signature, documentation.clone());
+        }
+        outline.types.push(TypeDefinition {
+            name: name.to_string(),
+            type_kind: kind.to_string(),
+            purpose: documentation.unwrap_or_else(|| format!("Auto-extracted {}", kind)),
+            fields_or_variants: members,
+            implementations: Vec::new(),
+        });
+    }
+
+    /// Parse the declarations of one Go source file
+    fn parse_file(&self, relative: &Path, content: &str) -> ModuleOutline {
+        let mut outline = ModuleOutline::new(String::new());
+        outline.is_test = relative.to_string_lossy().ends_with("_test.go");
+        let Some(tree) = source::parse("go", content) else {
+            return outline;
+        };
+        let root = tree.root_node();
+        let declarations = named_children(root);
+
+        if let Some(package) = declarations.iter().find(|d| d.kind() == "package_clause") {
+            outline.purpose = preceding_comment(content, package.start_byte(), &["//"])
+                .filter(|comment| comment.starts_with("Package "));
+        }
+
+        for spec in source::descendants(root, &["import_spec"]) {
+            if let Some(path) = spec.child_by_field_name("path") {
+                outline.add_import(text(path, content).trim_matches(|c| c == '"' || c == '`'));
+            }
+        }
+
+        // Test declarations aren't part of the package's interface
+        if outline.is_test {
+            return outline;
+        }
+
+        let mut methods = Vec::new();
+        for declaration in &declarations {
+            if matches!(
+                declaration.kind(),
+                "function_declaration" | "method_declaration"
+            ) {
+                Self::parse_function(&mut outline, &mut methods, *declaration, content);
+            }
+        }
+
+        for declaration in declarations
+            .iter()
+            .filter(|d| d.kind() == "type_declaration")
+        {
+            let specs = named_children(*declaration);
+            // A lone spec is documented above `type`, grouped ones above themselves
+            let grouped = source::has_token(*declaration, "(");
+            for spec in specs {
+                let documented = if grouped { spec } else { *declaration };
+                Self::parse_type(&mut outline, spec, documented, content);
+            }
+        }
+
+        // Methods are listed on their receiver type
+        for (receiver_type, method) in methods {
+            if let Some(ty) = outline.types.iter_mut().find(|t| t.name == receiver_type) {
+                ty.implementations.push(method);
+            }
+        }
+
+        for declaration in declarations
+            .iter()
+            .filter(|d| d.kind() == "const_declaration")
+        {
+            for spec in named_children(*declaration) {
+                let value_type = spec
+                    .child_by_field_name("type")
+                    .map_or_else(|| "inferred".to_string(), |t| flat_text(t, content));
+                let mut cursor = spec.walk();
+                let names: Vec<Node> = spec.children_by_field_name("name", &mut cursor).collect();
+                for name in names {
+                    outline.constants.push(ConstantDefinition {
+                        name: text(name, content).to_string(),
+                        value_type: value_type.clone(),
+                        purpose: "Auto-extracted constant".to_string(),
+                        scope: "package".to_string(),
+                    });
+                }
+            }
+        }
+
+        if !source::descendants(root, &["go_statement"]).is_empty() {
+            outline.add_pattern("Goroutines");
+        }
+        let uses_channels = source::descendants(
+            root,
+            &["channel_type", "send_statement", "unary_expression"],
+        )
+        .into_iter()
+        .any(|node| node.kind() != "unary_expression" || text(node, content).starts_with("<-"));
+        if uses_channels {
+            outline.add_pattern("Channels");
+        }
+        let uses_context = source::descendants(root, &["qualified_type"])
+            .into_iter()
+            .any(|node| text(node, content) == "context.Context");
+        if uses_context {
+            outline.add_pattern("Context propagation");
+        }
+
+        outline
+    }
+}
+
+#[async_trait::async_trait]
+impl LanguageAnalyzer for GoAnalyzer {
+    async fn analyze(&self, project_path: &Path) -> Result<LanguageModule> {
+        let dependencies = self.extract_dependencies(project_path).await?;
+        let entry_points = self.find_entry_points(project_path).await?;
+        let build_config = self.analyze_build_config(project_path).await?;
+        let test_framework = self.detect_test_framework(project_path).await?;
+
+        let test_config = TestConfig {
+            test_framework,
+            test_directories: vec!["./...".to_string()],
+            coverage_tool: "go test -cover".to_string(),
+            test_commands: vec!["go test ./...".to_string()],
+        };
+
+        let documentation = DocumentationConfig {
+            doc_tool: "godoc".to_string(),
+            doc_format: "html".to_string(),
+            doc_directory: "docs/".to_string(),
+            auto_generate: true,
+        };
+
+        Ok(LanguageModule {
+            language: Language::Go,
+            entry_points,
+            dependencies,
+            build_config,
+            test_config,
+            documentation,
+        })
+    }
+
+    async fn extract_dependencies(&self, project_path: &Path) -> Result<Vec<Dependency>> {
+        self.parse_go_mod(project_path).await
+    }
+
+    async fn analyze_build_config(&self, project_path: &Path) -> Result<BuildConfig> {
+        let mut compile_flags = Vec::new();
+
+        let go_mod = project_path.join("go.mod");
+        if go_mod.exists() {
+            let content = tokio::fs::read_to_string(&go_mod).await?;
+            if let Some(version) = content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("go "))
+            {
+                compile_flags.push(format!("-lang=go{}", version.trim()));
+            }
+        }
+
+        Ok(BuildConfig {
+            build_tool: "go".to_string(),
+            build_file: "go.mod".to_string(),
+            compile_flags,
+            optimization_level: "default".to_string(),
+            target_platforms: vec![
+                "linux/amd64".to_string(),
+                "darwin/arm64".to_string(),
+                "windows/amd64".to_string(),
+            ],
+        })
+    }
+
+    async fn extract_interfaces(&self, project_path: &Path) -> Result<Vec<String>> {
+        let mut interfaces = Vec::new();
+        let deps = self.extract_dependencies(project_path).await?;
+
+        for dep in &deps {
+            match self.infer_dependency_purpose(&dep.name).as_str() {
+                "Web framework" => interfaces.push("REST API".to_string()),
+                "RPC" => interfaces.push("gRPC API".to_string()),
+                "CLI framework" => interfaces.push("CLI Application".to_string()),
+                _ => {}
+            }
+        }
+
+        if !self.find_entry_points(project_path).await?.is_empty()
+            && !interfaces.contains(&"CLI Application".to_string())
+        {
+            interfaces.push("CLI Application".to_string());
+        }
+
+        if interfaces.is_empty() {
+            interfaces.push("Go Library".to_string());
+        }
+
+        interfaces.dedup();
+        Ok(interfaces)
+    }
+
+    async fn extract_modules(&self, project_path: &Path) -> Result<Vec<ModuleBlueprint>> {
+        let module_path = self.module_path(project_path).await?.unwrap_or_default();
+        let sources = source::read_sources(project_path, &["go"]).await?;
+
+        // Go modules are organised by package directory
+        let mut packages: BTreeMap<String, ModuleOutline> = BTreeMap::new();
+        for (relative, content) in &sources {
+            let directory = relative
+                .parent()
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let name = match (module_path.is_empty(), directory.is_empty()) {
+                (true, _) => directory.clone(),
+                (false, true) => module_path.clone(),
+                (false, false) => format!("{}/{}", module_path, directory),
+            };
+
+            let file = self.parse_file(relative, content);
+            packages
+                .entry(directory)
+                .or_insert_with(|| ModuleOutline::new(name))
+                .merge(file);
+        }
+
+        let test_framework = self.detect_test_framework(project_path).await?;
+        Ok(packages
+            .into_values()
+            .map(|package| {
+                package.into_blueprint(&test_framework, |import| {
+                    !module_path.is_empty() && import.starts_with(module_path.as_str())
+                })
+            })
+            .collect())
+    }
+}
+
+impl Default for GoAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_go_mod_parsing() {
+        let go_mod = r#"
+module example.com/shop
+
+go 1.21
+
+require (
+	github.com/gin-gonic/gin v1.9.1
+	github.com/stretchr/testify v1.8.4
+	golang.org/x/sync v0.5.0 // indirect
+)
+
+require example.com/shared v0.0.0
+
+replace example.com/shared => ../shared
+"#;
+
+        let analyzer = GoAnalyzer::new();
+        let deps = analyzer.parse_go_mod_content(go_mod);
+
+        assert_eq!(deps.len(), 4);
+        assert!(deps
+            .iter()
+            .any(|d| d.name == "github.com/gin-gonic/gin" && d.purpose == "Web framework"));
+        assert!(deps
+            .iter()
+            .any(|d| d.name == "golang.org/x/sync" && d.purpose.starts_with("Indirect")));
+        assert!(deps.iter().any(|d| d.name == "example.com/shared"
+            && matches!(&d.source, DependencySource::Local(path) if path == "../shared")));
+    }
+
+    #[tokio::test]
+    async fn test_package_extraction() {
+        let temp_dir = tempdir().unwrap();
+        let temp_path = temp_dir.path();
+
+        std::fs::write(
+            temp_path.join("go.mod"),
+            "module example.com/shop\n\ngo 1.21\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp_path.join("internal/store")).unwrap();
+        std::fs::write(
+            temp_path.join("internal/store/store.go"),
+            r#"// Package store persists orders.
+package store
+
+import (
+	"context"
+	"sync"
+)
+
+// Store keeps orders in memory.
+type Store struct {
+	mu     sync.Mutex
+	orders map[string]int `json:"orders"`
+}
+
+// Get returns the quantity for an order.
+func (s *Store) Get(ctx context.Context, id string) (int, error) {
+	return s.orders[id], nil
+}
+
+func helper(a, b int) int { return a + b }
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            temp_path.join("internal/store/store_test.go"),
+            "package store\n\nimport \"testing\"\n\nfunc TestGet(t *testing.T) {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_path.join("main.go"),
+            "package main\n\nimport \"example.com/shop/internal/store\"\n\nfunc main() { _ = store.Store{} }\n",
+        )
+        .unwrap();
+
+        let analyzer = GoAnalyzer::new();
+        let modules = analyzer.extract_modules(temp_path).await.unwrap();
+        assert_eq!(modules.len(), 2);
+
+        let store = modules
+            .iter()
+            .find(|m| m.name == "example.com/shop/internal/store")
+            .unwrap();
+        assert_eq!(store.purpose, "Package store persists orders.");
+        assert_eq!(store.testing_strategy.test_types, vec!["unit".to_string()]);
+        let names: Vec<_> = store
+            .public_interface
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Store.Get", "Store"]);
+        assert_eq!(
+            store.public_interface[0].signature,
+            "func (s *Store) Get(ctx context.Context, id string) (int, error)"
+        );
+        let helper = store
+            .internal_structure
+            .functions
+            .iter()
+            .find(|f| f.name == "helper")
+            .unwrap();
+        assert_eq!(helper.visibility, "private");
+        assert_eq!(helper.parameters[0].param_type, "int");
+        assert_eq!(
+            store.internal_structure.primary_types[0].fields_or_variants,
+            vec![
+                "mu sync.Mutex".to_string(),
+                "orders map[string]int".to_string()
+            ]
+        );
+        assert_eq!(
+            store.internal_structure.primary_types[0].implementations,
+            vec!["Get".to_string()]
+        );
+
+        let main = modules
+            .iter()
+            .find(|m| m.name == "example.com/shop")
+            .unwrap();
+        assert_eq!(main.dependencies[0].coupling_strength, "tight");
+    }
+}
@@ -7,10 +7,20 @@ use super::super::{
     BuildConfig, Dependency, DependencySource, DocumentationConfig, Language, LanguageAnalyzer,
     LanguageModule, TestConfig,
 };
+use super::source::{
+    self, flat_text, has_token, named_children, preceding_comment, text, ModuleOutline,
+};
+use crate::blueprint::{
+    ConstantDefinition, FunctionDefinition, ModuleBlueprint, Parameter, TypeDefinition,
+};
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+use tree_sitter::Node;
+
+/// Comment markers that can introduce JSDoc and line comments
+const COMMENT_MARKERS: &[&str] = &["/**", "/*", "*/", "*", "//"];
 
 pub struct JavaScriptAnalyzer;
 
@@ -452,6 +462,543 @@ impl JavaScriptAnalyzer {
 
         Ok(false)
     }
+
+    /// Module path for a source file, relative to the project and without extension
+    fn module_name(relative: &Path) -> String {
+        relative
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Whether a source file holds tests rather than application code
+    fn is_test_module(relative: &Path) -> bool {
+        let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        stem.ends_with(".test")
+            || stem.ends_with(".spec")
+            || relative.components().any(|c| {
+                matches!(
+                    c.as_os_str().to_str(),
+                    Some("__tests__") | Some("test") | Some("tests")
+                )
+            })
+    }
+
+    /// Whether an import specifier points into the project rather than a package
+    fn is_local_import(import: &str) -> bool {
+        import.starts_with('.')
+            || import.starts_with('/')
+            || import.starts_with("@/")
+            || import.starts_with("~/")
+    }
+
+    /// Resolve a relative import from `module` to the module name it targets
+    fn resolve_import(module: &str, import: &str) -> Option<String> {
+        if !import.starts_with('.') {
+            return None;
+        }
+
+        let base = Path::new(module).parent().unwrap_or_else(|| Path::new(""));
+        let mut parts: Vec<String> = Vec::new();
+        for component in base.join(import).components() {
+            match component {
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                _ => {}
+            }
+        }
+
+        let resolved = parts.join("/");
+        let without_extension = Path::new(&resolved).with_extension("");
+        match Path::new(&resolved).extension().and_then(|e| e.to_str()) {
+            Some("js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx") => {
+                Some(without_extension.to_string_lossy().replace('\\', "/"))
+            }
+            _ => Some(resolved),
+        }
+    }
+
+    /// Header comment at the top of a file, ignoring any shebang line
+    fn file_comment(content: &str) -> Option<String> {
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim();
+            let is_comment = trimmed.is_empty()
+                || trimmed.starts_with("#!")
+                || COMMENT_MARKERS.iter().any(|m| trimmed.starts_with(m));
+            if !is_comment {
+                break;
+            }
+            offset += line.len();
+        }
+
+        let header = format!("{}\n", content[..offset].trim_end());
+        preceding_comment(&header, header.len(), COMMENT_MARKERS)
+    }
+
+    /// Grammar a source file is parsed with
+    fn grammar_name(relative: &Path) -> &'static str {
+        match relative.extension().and_then(|e| e.to_str()) {
+            Some("ts" | "mts" | "cts") => "typescript",
+            Some("tsx") => "tsx",
+            _ => "javascript",
+        }
+    }
+
+    /// Type written in a `: Type` annotation
+    fn annotation(node: Node, content: &str) -> String {
+        flat_text(node, content)
+            .trim_start_matches(':')
+            .trim()
+            .to_string()
+    }
+
+    /// Parse one parameter of a function signature
+    fn parse_parameter(node: Node, content: &str) -> Parameter {
+        // Destructured parameters keep their pattern as the name
+        let (pattern, type_annotation) = match node.kind() {
+            "required_parameter" | "optional_parameter" => (
+                node.child_by_field_name("pattern").unwrap_or(node),
+                node.child_by_field_name("type"),
+            ),
+            "assignment_pattern" => (node.child_by_field_name("left").unwrap_or(node), None),
+            _ => (node, None),
+        };
+
+        Parameter {
+            name: flat_text(pattern, content),
+            param_type: type_annotation
+                .map_or_else(|| "any".to_string(), |t| Self::annotation(t, content)),
+            is_mutable: false,
+            ownership: "borrowed".to_string(),
+        }
+    }
+
+    /// Build a function definition and its signature from a function-like
+    /// node: a declaration, a function expression, an arrow function or a method
+    fn function_definition(
+        name: &str,
+        node: Node,
+        content: &str,
+        exported: bool,
+        documentation: &Option<String>,
+    ) -> (FunctionDefinition, String) {
+        let params = match (
+            node.child_by_field_name("parameters"),
+            node.child_by_field_name("parameter"),
+        ) {
+            (Some(params), _) => named_children(params),
+            (None, Some(param)) => vec![param],
+            (None, None) => Vec::new(),
+        };
+        let parts: Vec<String> = params
+            .iter()
+            .map(|param| flat_text(*param, content))
+            .collect();
+        let return_type = node
+            .child_by_field_name("return_type")
+            .map_or_else(|| "inferred".to_string(), |t| Self::annotation(t, content));
+        let is_async = has_token(node, "async");
+
+        let signature = format!(
+            "{}function {}({}){}",
+            if is_async { "async " } else { "" },
+            name,
+            parts.join(", "),
+            if return_type == "inferred" {
+                String::new()
+            } else {
+                format!(": {}", return_type)
+            }
+        );
+
+        let function = FunctionDefinition {
+            name: name.to_string(),
+            visibility: if exported { "public" } else { "private" }.to_string(),
+            is_async,
+            parameters: params
+                .iter()
+                .map(|param| Self::parse_parameter(*param, content))
+                .collect(),
+            return_type,
+            purpose: documentation
+                .clone()
+                .unwrap_or_else(|| "Auto-extracted function".to_string()),
+            complexity: "medium".to_string(), // Default
+        };
+        (function, signature)
+    }
+
+    /// Parse the members of a class body, returning its fields
+    fn parse_class_members(
+        outline: &mut ModuleOutline,
+        body: Node,
+        content: &str,
+        class_name: &str,
+        exported: bool,
+    ) -> Vec<String> {
+        let mut fields = Vec::new();
+        for member in named_children(body) {
+            match member.kind() {
+                "method_definition" => {
+                    let Some(name) = member.child_by_field_name("name") else {
+                        continue;
+                    };
+                    let name = text(name, content);
+                    let restricted = named_children(member).into_iter().any(|child| {
+                        child.kind() == "accessibility_modifier"
+                            && matches!(text(child, content), "private" | "protected")
+                    });
+                    let public = !(restricted || name.starts_with('#') || name.starts_with('_'));
+                    let documentation =
+                        preceding_comment(content, member.start_byte(), COMMENT_MARKERS);
+                    let qualified = format!("{}.{}", class_name, name);
+                    let (mut function, signature) =
+                        Self::function_definition(name, member, content, public, &documentation);
+                    if function.is_async {
+                        outline.add_pattern("Async/await");
+                    }
+                    if exported && public {
+                        let kind = if name == "constructor" {
+                            "constructor"
+                        } else {
+                            "method"
+                        };
+                        outline.export(&qualified, kind, signature, documentation);
+                    }
+                    function.name = qualified;
+                    outline.functions.push(function);
+                }
+                "public_field_definition" | "field_definition" => {
+                    fields.push(flat_text(member, content).trim_end_matches(';').to_string());
+                }
+                _ => {}
+            }
+        }
+        fields
+    }
+
+    /// Names exported after their declaration, CommonJS style or via export lists
+    fn exported_names(root: Node, content: &str) -> HashSet<String> {
+        let mut names = HashSet::new();
+        for statement in named_children(root) {
+            match statement.kind() {
+                "export_statement" if statement.child_by_field_name("source").is_none() => {
+                    for clause in named_children(statement)
+                        .into_iter()
+                        .filter(|child| child.kind() == "export_clause")
+                    {
+                        for specifier in named_children(clause) {
+                            if let Some(name) = specifier.child_by_field_name("name") {
+                                names.insert(text(name, content).to_string());
+                            }
+                        }
+                    }
+                }
+                "expression_statement" => {
+                    let Some(assignment) = statement
+                        .named_child(0)
+                        .filter(|node| node.kind() == "assignment_expression")
+                    else {
+                        continue;
+                    };
+                    let (Some(left), Some(right)) = (
+                        assignment.child_by_field_name("left"),
+                        assignment.child_by_field_name("right"),
+                    ) else {
+                        continue;
+                    };
+                    let target = text(left, content);
+                    if matches!(target, "module.exports" | "exports") && right.kind() == "object" {
+                        for property in named_children(right) {
+                            let key = match property.kind() {
+                                "pair" | "method_definition" => property
+                                    .child_by_field_name("key")
+                                    .or_else(|| property.child_by_field_name("name")),
+                                "shorthand_property_identifier" => Some(property),
+                                _ => None,
+                            };
+                            if let Some(key) = key {
+                                names.insert(text(key, content).to_string());
+                            }
+                        }
+                    } else if let Some(name) = target
+                        .strip_prefix("module.exports.")
+                        .or_else(|| target.strip_prefix("exports."))
+                    {
+                        names.insert(name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+
+    /// Parse the declarations of a JavaScript or TypeScript source file
+    fn parse_module(&self, relative: &Path, content: &str) -> ModuleOutline {
+        let mut outline = ModuleOutline::new(Self::module_name(relative));
+        outline.is_test = Self::is_test_module(relative);
+        outline.purpose = Self::file_comment(content);
+        let Some(tree) = source::parse(Self::grammar_name(relative), content) else {
+            return outline;
+        };
+        let root = tree.root_node();
+
+        // `import`, `export ... from`, `require()` and dynamic `import()`
+        for node in source::descendants(
+            root,
+            &["import_statement", "export_statement", "call_expression"],
+        ) {
+            let specifier = match node.kind() {
+                "call_expression" => {
+                    let loads = node
+                        .child_by_field_name("function")
+                        .is_some_and(|function| {
+                            matches!(text(function, content), "require" | "import")
+                        });
+                    node.child_by_field_name("arguments")
+                        .filter(|_| loads)
+                        .and_then(|arguments| arguments.named_child(0))
+                }
+                _ => node.child_by_field_name("source"),
+            };
+            if let Some(specifier) = specifier.filter(|specifier| specifier.kind() == "string") {
+                outline
+                    .add_import(text(specifier, content).trim_matches(|c| c == '\'' || c == '"'));
+            }
+        }
+
+        let exported_names = Self::exported_names(root, content);
+        let mut functions = Vec::new();
+        let mut classes = Vec::new();
+        let mut declarations = Vec::new();
+        let mut values = Vec::new();
+        for statement in named_children(root) {
+            let (exported, declaration) = match statement.kind() {
+                "export_statement" => (
+                    true,
+                    statement
+                        .child_by_field_name("declaration")
+                        .or_else(|| statement.child_by_field_name("value")),
+                ),
+                _ => (false, Some(statement)),
+            };
+            // `declare` wraps the declaration it applies to
+            let Some(declaration) = declaration.and_then(|declaration| match declaration.kind() {
+                "ambient_declaration" => declaration.named_child(0),
+                _ => Some(declaration),
+            }) else {
+                continue;
+            };
+
+            match declaration.kind() {
+                "function_declaration"
+                | "generator_function_declaration"
+                | "function_expression"
+                | "function"
+                | "generator_function" => {
+                    let name = declaration
+                        .child_by_field_name("name")
+                        .map_or("default", |name| text(name, content));
+                    functions.push((statement, name, declaration, exported));
+                }
+                "class_declaration" | "abstract_class_declaration" | "class" => {
+                    classes.push((statement, declaration, exported));
+                }
+                "interface_declaration" | "type_alias_declaration" | "enum_declaration" => {
+                    declarations.push((statement, declaration, exported));
+                }
+                "lexical_declaration" | "variable_declaration" => {
+                    for declarator in named_children(declaration)
+                        .into_iter()
+                        .filter(|child| child.kind() == "variable_declarator")
+                    {
+                        let Some(name) = declarator
+                            .child_by_field_name("name")
+                            .filter(|name| name.kind() == "identifier")
+                            .map(|name| text(name, content))
+                        else {
+                            continue;
+                        };
+                        // `const x = (a + b) * 2` is a value, not a function
+                        match declarator.child_by_field_name("value") {
+                            Some(value)
+                                if matches!(
+                                    value.kind(),
+                                    "arrow_function"
+                                        | "function_expression"
+                                        | "function"
+                                        | "generator_function"
+                                ) =>
+                            {
+                                functions.push((statement, name, value, exported));
+                            }
+                            _ if exported => values.push((statement, name, declarator)),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut declared = HashSet::new();
+        for (statement, name, node, exported) in functions {
+            let exported = exported || exported_names.contains(name);
+            let documentation = preceding_comment(content, statement.start_byte(), COMMENT_MARKERS);
+            let (function, signature) =
+                Self::function_definition(name, node, content, exported, &documentation);
+            if function.is_async {
+                outline.add_pattern("Async/await");
+            }
+            if exported {
+                outline.export(name, "function", signature, documentation);
+            }
+            declared.insert(name);
+            outline.functions.push(function);
+        }
+
+        for (statement, class, exported) in classes {
+            let name = class
+                .child_by_field_name("name")
+                .map_or("default", |name| text(name, content));
+            let exported = exported || exported_names.contains(name);
+
+            let mut base = None;
+            let mut implemented = Vec::new();
+            let heritage = named_children(class)
+                .into_iter()
+                .find(|child| child.kind() == "class_heritage");
+            for clause in heritage.map(named_children).unwrap_or_default() {
+                match clause.kind() {
+                    "extends_clause" => {
+                        base = clause
+                            .child_by_field_name("value")
+                            .map(|value| flat_text(value, content));
+                    }
+                    "implements_clause" => implemented.extend(
+                        named_children(clause)
+                            .into_iter()
+                            .map(|interface| flat_text(interface, content)),
+                    ),
+                    // Plain JavaScript names the base class directly
+                    _ => base = Some(flat_text(clause, content)),
+                }
+            }
+
+            let documentation = preceding_comment(content, statement.start_byte(), COMMENT_MARKERS);
+            if exported {
+                let signature = match &base {
+                    Some(base) => format!("class {} extends {}", name, base),
+                    None => format!("class {}", name),
+                };
+                outline.export(name, "class", signature, documentation.clone());
+            }
+            let fields = class
+                .child_by_field_name("body")
+                .map(|body| Self::parse_class_members(&mut outline, body, content, name, exported))
+                .unwrap_or_default();
+            declared.insert(name);
+            outline.types.push(TypeDefinition {
+                name: name.to_string(),
+                type_kind: "class".to_string(),
+                purpose: documentation.unwrap_or_else(|| "Auto-extracted class".to_string()),
+                fields_or_variants: fields,
+                implementations: base.into_iter().chain(implemented).collect(),
+            });
+        }
+
+        for (statement, declaration, exported) in declarations {
+            let Some(name_node) = declaration.child_by_field_name("name") else {
+                continue;
+            };
+            let name = text(name_node, content);
+            let exported = exported || exported_names.contains(name);
+            let kind = match declaration.kind() {
+                "interface_declaration" => "interface",
+                "enum_declaration" => "enum",
+                _ => "type",
+            };
+
+            let members = match declaration.child_by_field_name("body") {
+                Some(body) if kind != "type" => named_children(body)
+                    .into_iter()
+                    .map(|member| {
+                        flat_text(member, content)
+                            .trim_end_matches([',', ';'])
+                            .trim()
+                            .to_string()
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            let documentation = preceding_comment(content, statement.start_byte(), COMMENT_MARKERS);
+            if exported {
+                let signature = if kind == "type" {
+                    let definition = content[name_node.end_byte()..declaration.end_byte()]
+                        .lines()
+                        .next()
+                        .unwrap_or("")
+                        .trim()
+                        .trim_end_matches(';');
+                    format!("type {} {}", name, definition)
+                } else {
+                    format!("{} {}", kind, name)
+                };
+                outline.export(
+                    name,
+                    kind,
+                    signature.trim_end().to_string(),
+                    documentation.clone(),
+                );
+            }
+            declared.insert(name);
+            outline.types.push(TypeDefinition {
+                name: name.to_string(),
+                type_kind: kind.to_string(),
+                purpose: documentation.unwrap_or_else(|| format!("Auto-extracted {}", kind)),
+                fields_or_variants: members,
+                implementations: Vec::new(),
+            });
+        }
+
+        for (statement, name, declarator) in values {
+            if declared.contains(name) {
+                continue;
+            }
+            let value_type = declarator
+                .child_by_field_name("type")
+                .map_or_else(|| "inferred".to_string(), |t| Self::annotation(t, content));
+            let documentation = preceding_comment(content, statement.start_byte(), COMMENT_MARKERS);
+            outline.export(
+                name,
+                "constant",
+                format!("const {}: {}", name, value_type),
+                documentation,
+            );
+            outline.constants.push(ConstantDefinition {
+                name: name.to_string(),
+                value_type,
+                purpose: "Auto-extracted constant".to_string(),
+                scope: "module".to_string(),
+            });
+        }
+
+        if matches!(
+            relative.extension().and_then(|e| e.to_str()),
+            Some("jsx" | "tsx")
+        ) {
+            outline.add_pattern("React components");
+        }
+
+        outline
+    }
 }
 
 #[async_trait::async_trait]
@@ -579,6 +1126,39 @@ impl LanguageAnalyzer for JavaScriptAnalyzer {
         interfaces.dedup();
         Ok(interfaces)
     }
+
+    async fn extract_modules(&self, project_path: &Path) -> Result<Vec<ModuleBlueprint>> {
+        let sources =
+            source::read_sources(project_path, &["js", "jsx", "mjs", "cjs", "ts", "tsx"]).await?;
+        let mut outlines: Vec<ModuleOutline> = sources
+            .iter()
+            .filter(|(relative, _)| {
+                let name = relative.to_string_lossy();
+                !(name.ends_with(".d.ts") || name.ends_with(".min.js"))
+            })
+            .map(|(relative, content)| self.parse_module(relative, content))
+            .collect();
+
+        // A module is covered when some test module imports it directly
+        let mut tested = HashSet::new();
+        for outline in outlines.iter().filter(|outline| outline.is_test) {
+            for import in &outline.imports {
+                if let Some(target) = Self::resolve_import(&outline.name, import) {
+                    tested.insert(format!("{}/index", target));
+                    tested.insert(target);
+                }
+            }
+        }
+        for outline in outlines.iter_mut().filter(|outline| !outline.is_test) {
+            outline.has_tests = tested.contains(&outline.name);
+        }
+
+        let test_framework = self.detect_test_framework(project_path).await?;
+        Ok(outlines
+            .into_iter()
+            .map(|outline| outline.into_blueprint(&test_framework, Self::is_local_import))
+            .collect())
+    }
 }
 
 impl Default for JavaScriptAnalyzer {
@@ -661,4 +1241,113 @@ mod tests {
 
         assert!(is_ts);
     }
+
+    #[test]
+    fn test_module_parsing() {
+        let content = r#"/**
+ * Order routes.
+ */
+import express from 'express';
+import { Order } from '../models/order';
+const db = require('./db');
+
+/** Fetch one order. */
+export async function getOrder(id: string, opts?: Options): Promise<Order> {
+  return db.find(id);
+}
+
+export const listOrders = async (limit = 10): Promise<Order[]> => {
+  return [];
+};
+
+export const PAGE_SIZE: number = 20;
+
+function internal() {}
+
+export interface Options {
+  cache?: boolean;
+  timeout: number;
+}
+
+export class OrderService extends BaseService implements Service {
+  private cache: Map<string, Order>;
+
+  constructor(private readonly db: Db) {
+    super();
+  }
+
+  async refresh(id: string): Promise<void> {
+    if (id) {
+      helper();
+    }
+  }
+
+  private evict(id: string) {}
+}
+"#;
+
+        let analyzer = JavaScriptAnalyzer::new();
+        let outline = analyzer.parse_module(Path::new("src/routes/orders.ts"), content);
+
+        assert_eq!(outline.name, "src/routes/orders");
+        assert_eq!(outline.purpose.as_deref(), Some("Order routes."));
+        assert_eq!(outline.imports, vec!["express", "../models/order", "./db"]);
+
+        let names: Vec<_> = outline.interface.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "getOrder",
+                "listOrders",
+                "OrderService",
+                "OrderService.constructor",
+                "OrderService.refresh",
+                "Options",
+                "PAGE_SIZE",
+            ]
+        );
+        assert_eq!(
+            outline.interface[0].signature,
+            "async function getOrder(id: string, opts?: Options): Promise<Order>"
+        );
+        assert_eq!(outline.interface[0].documentation, "Fetch one order.");
+
+        let internal = outline
+            .functions
+            .iter()
+            .find(|f| f.name == "internal")
+            .unwrap();
+        assert_eq!(internal.visibility, "private");
+        let get_order = &outline.functions[0];
+        assert_eq!(get_order.parameters[1].name, "opts");
+        assert_eq!(get_order.parameters[1].param_type, "Options");
+
+        let service = outline
+            .types
+            .iter()
+            .find(|t| t.name == "OrderService")
+            .unwrap();
+        assert_eq!(
+            service.implementations,
+            vec!["BaseService".to_string(), "Service".to_string()]
+        );
+        assert_eq!(
+            service.fields_or_variants,
+            vec!["private cache: Map<string, Order>".to_string()]
+        );
+        let options = outline.types.iter().find(|t| t.name == "Options").unwrap();
+        assert_eq!(
+            options.fields_or_variants,
+            vec!["cache?: boolean".to_string(), "timeout: number".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_relative_import_resolution() {
+        assert_eq!(
+            JavaScriptAnalyzer::resolve_import("src/__tests__/orders.test", "../routes/orders.js"),
+            Some("src/routes/orders".to_string())
+        );
+        assert_eq!(JavaScriptAnalyzer::resolve_import("src/a", "express"), None);
+    }
 }
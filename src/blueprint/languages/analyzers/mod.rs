@@ -3,15 +3,18 @@
 //! Provides concrete implementations of language analyzers for
 //! different programming languages.
 
+mod go;
 mod javascript;
 mod python;
 mod rust; // Assuming we already have a Rust analyzer
+mod source;
 
 use super::LanguageAnalyzer;
 use anyhow::Result;
 use std::path::Path;
 use std::sync::Arc;
 
+pub use go::GoAnalyzer;
 pub use javascript::JavaScriptAnalyzer;
 pub use python::PythonAnalyzer;
 pub use rust::RustAnalyzer;
@@ -24,6 +27,7 @@ pub fn create_analyzer_for_language(language: &super::Language) -> Arc<dyn Langu
         super::Language::JavaScript | super::Language::TypeScript => {
             Arc::new(JavaScriptAnalyzer::new())
         }
+        super::Language::Go => Arc::new(GoAnalyzer::new()),
         _ => Arc::new(RustAnalyzer::new()), // Default fallback
     }
 }
@@ -38,6 +42,7 @@ pub async fn create_analyzer_for_path(path: &Path) -> Result<Arc<dyn LanguageAna
         "rust" => Arc::new(RustAnalyzer::new()),
         "python" => Arc::new(PythonAnalyzer::new()),
         "javascript" | "typescript" => Arc::new(JavaScriptAnalyzer::new()),
+        "go" => Arc::new(GoAnalyzer::new()),
         _ => Arc::new(RustAnalyzer::new()), // Default to Rust for now
     };
 
@@ -82,6 +87,10 @@ async fn detect_language(path: &Path) -> Result<String> {
         return Ok("javascript".to_string());
     }
 
+    if path.join("go.mod").exists() {
+        return Ok("go".to_string());
+    }
+
     // Fallback: Check file extensions
     let walker = walkdir::WalkDir::new(path)
         .max_depth(3)
@@ -127,5 +136,6 @@ pub fn get_all_analyzers() -> Vec<Arc<dyn LanguageAnalyzer>> {
         Arc::new(RustAnalyzer::new()),
         Arc::new(PythonAnalyzer::new()),
         Arc::new(JavaScriptAnalyzer::new()),
+        Arc::new(GoAnalyzer::new()),
     ]
 }
@@ -7,9 +7,14 @@ use super::super::{
     BuildConfig, Dependency, DependencySource, DocumentationConfig, Language, LanguageAnalyzer,
     LanguageModule, TestConfig,
 };
+use super::source::{self, flat_text, has_token, named_children, text, ModuleOutline};
+use crate::blueprint::{
+    ConstantDefinition, FunctionDefinition, ModuleBlueprint, Parameter, TypeDefinition,
+};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use tree_sitter::Node;
 
 pub struct PythonAnalyzer;
 
@@ -304,6 +309,336 @@ impl PythonAnalyzer {
         Ok(deps.iter().any(|dep| dep.name.contains("pytest")))
     }
 
+    /// Dotted module path for a source file, e.g. `src/app/routes.py` -> `app.routes`
+    fn module_name(relative: &Path) -> String {
+        let mut parts: Vec<String> = relative
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if parts.len() > 1 && parts[0] == "src" {
+            parts.remove(0);
+        }
+        if parts.len() > 1 && parts.last().is_some_and(|p| p == "__init__") {
+            parts.pop();
+        }
+        parts.join(".")
+    }
+
+    /// Whether a source file holds tests rather than application code
+    fn is_test_module(relative: &Path) -> bool {
+        let stem = relative.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        stem.starts_with("test_")
+            || stem.ends_with("_test")
+            || stem == "conftest"
+            || relative
+                .components()
+                .any(|c| matches!(c.as_os_str().to_str(), Some("tests") | Some("test")))
+    }
+
+    /// First line of the docstring at the start of `text`, skipping comments
+    fn docstring(text: &str) -> Option<String> {
+        let mut text = text.trim_start();
+        while text.starts_with('#') {
+            text = text
+                .split_once('\n')
+                .map_or("", |(_, rest)| rest)
+                .trim_start();
+        }
+
+        let quote = ["\"\"\"", "'''"]
+            .into_iter()
+            .find(|q| text.starts_with(q))?;
+        let body = text[quote.len()..].split(quote).next()?;
+        body.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    }
+
+    /// Parse one parameter of a `def`, skipping `self`, `cls` and bare markers
+    fn parse_parameter(node: Node, content: &str) -> Option<Parameter> {
+        let (name, param_type) = match node.kind() {
+            "typed_parameter" => (
+                named_children(node)
+                    .first()
+                    .map(|name| text(*name, content))?,
+                node.child_by_field_name("type")
+                    .map_or("Any", |t| text(t, content)),
+            ),
+            "default_parameter" | "typed_default_parameter" => (
+                text(node.child_by_field_name("name")?, content),
+                node.child_by_field_name("type")
+                    .map_or("Any", |t| text(t, content)),
+            ),
+            _ => (text(node, content), "Any"),
+        };
+        if matches!(name, "self" | "cls" | "*" | "/") {
+            return None;
+        }
+
+        Some(Parameter {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            is_mutable: false,
+            ownership: "borrowed".to_string(),
+        })
+    }
+
+    /// Parse a `function_definition` node, returning the function, its
+    /// signature and its docstring
+    fn parse_function(
+        node: Node,
+        content: &str,
+    ) -> Option<(FunctionDefinition, String, Option<String>)> {
+        let name = text(node.child_by_field_name("name")?, content);
+        let is_async = has_token(node, "async");
+        let params = named_children(node.child_by_field_name("parameters")?);
+        let return_type = node
+            .child_by_field_name("return_type")
+            .map_or_else(|| "None".to_string(), |t| flat_text(t, content));
+        let documentation = node
+            .child_by_field_name("body")
+            .and_then(|body| Self::docstring(text(body, content)));
+
+        let parts: Vec<String> = params
+            .iter()
+            .map(|param| flat_text(*param, content))
+            .collect();
+        let signature = format!(
+            "{}def {}({}) -> {}",
+            if is_async { "async " } else { "" },
+            name,
+            parts.join(", "),
+            return_type
+        );
+        let visibility = if name.starts_with('_') && name != "__init__" {
+            "private"
+        } else {
+            "public"
+        };
+
+        let function = FunctionDefinition {
+            name: name.to_string(),
+            visibility: visibility.to_string(),
+            is_async,
+            parameters: params
+                .iter()
+                .filter_map(|param| Self::parse_parameter(*param, content))
+                .collect(),
+            return_type,
+            purpose: documentation
+                .clone()
+                .unwrap_or_else(|| "Auto-extracted function".to_string()),
+            complexity: "medium".to_string(), // Default
+        };
+        Some((function, signature, documentation))
+    }
+
+    /// The definition a node holds, looking through decorators
+    fn definition(node: Node) -> Option<Node> {
+        match node.kind() {
+            "decorated_definition" => node.child_by_field_name("definition"),
+            _ => Some(node),
+        }
+    }
+
+    /// Whether a decorated definition is a `@dataclass`
+    fn is_dataclass(node: Node, content: &str) -> bool {
+        let mut cursor = node.walk();
+        let found = node.children(&mut cursor).any(|child| {
+            child.kind() == "decorator" && {
+                let decorator = text(child, content).trim_start_matches('@');
+                decorator
+                    .split('(')
+                    .next()
+                    .unwrap_or("")
+                    .ends_with("dataclass")
+            }
+        });
+        found
+    }
+
+    /// Parse a `class_definition` node
+    fn parse_class(&self, outline: &mut ModuleOutline, node: Node, content: &str) {
+        let (Some(name), Some(body)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("body"),
+        ) else {
+            return;
+        };
+        let name = text(name, content);
+        let bases: Vec<String> = node
+            .child_by_field_name("superclasses")
+            .map(|superclasses| {
+                named_children(superclasses)
+                    .into_iter()
+                    .filter(|base| base.kind() != "keyword_argument")
+                    .map(|base| flat_text(base, content))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut fields = Vec::new();
+        for member in named_children(body) {
+            let Some(member) = Self::definition(member) else {
+                continue;
+            };
+            match member.kind() {
+                "function_definition" => {
+                    let Some((mut function, signature, documentation)) =
+                        Self::parse_function(member, content)
+                    else {
+                        continue;
+                    };
+                    if function.is_async {
+                        outline.add_pattern("Async/await");
+                    }
+                    let qualified = format!("{}.{}", name, function.name);
+                    if function.visibility == "public" && !name.starts_with('_') {
+                        outline.export(&qualified, "method", signature, documentation);
+                    }
+                    function.name = qualified;
+                    outline.functions.push(function);
+                }
+                "expression_statement" => {
+                    let Some(assignment) =
+                        member.named_child(0).filter(|n| n.kind() == "assignment")
+                    else {
+                        continue;
+                    };
+                    let (Some(left), Some(field_type)) = (
+                        assignment
+                            .child_by_field_name("left")
+                            .filter(|left| left.kind() == "identifier"),
+                        assignment.child_by_field_name("type"),
+                    ) else {
+                        continue;
+                    };
+                    fields.push(format!(
+                        "{}: {}",
+                        text(left, content),
+                        flat_text(field_type, content)
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if bases.iter().any(|base| base.ends_with("BaseModel")) {
+            outline.add_pattern("Pydantic models");
+        }
+        if bases.iter().any(|base| {
+            matches!(base.as_str(), "ABC" | "Protocol") || base.starts_with("Protocol[")
+        }) {
+            outline.add_pattern("Abstract base classes");
+        }
+
+        let purpose = Self::docstring(text(body, content));
+        if !name.starts_with('_') {
+            let signature = if bases.is_empty() {
+                format!("class {}", name)
+            } else {
+                format!("class {}({})", name, bases.join(", "))
+            };
+            outline.export(name, "class", signature, purpose.clone());
+        }
+        outline.types.push(TypeDefinition {
+            name: name.to_string(),
+            type_kind: "class".to_string(),
+            purpose: purpose.unwrap_or_else(|| "Auto-extracted class".to_string()),
+            fields_or_variants: fields,
+            implementations: bases,
+        });
+    }
+
+    /// Parse the top-level declarations of a Python source file
+    fn parse_module(&self, relative: &Path, content: &str) -> ModuleOutline {
+        let mut outline = ModuleOutline::new(Self::module_name(relative));
+        outline.is_test = Self::is_test_module(relative);
+        outline.purpose = Self::docstring(content);
+        let Some(tree) = source::parse("python", content) else {
+            return outline;
+        };
+        let root = tree.root_node();
+
+        // Imports count wherever they appear, including inside functions
+        for import in source::descendants(root, &["import_statement", "import_from_statement"]) {
+            if import.kind() == "import_from_statement" {
+                if let Some(module) = import.child_by_field_name("module_name") {
+                    outline.add_import(text(module, content));
+                }
+                continue;
+            }
+            let mut cursor = import.walk();
+            let names: Vec<Node> = import.children_by_field_name("name", &mut cursor).collect();
+            for name in names {
+                let name = name.child_by_field_name("name").unwrap_or(name);
+                outline.add_import(text(name, content));
+            }
+        }
+
+        for statement in named_children(root) {
+            if statement.kind() == "decorated_definition" && Self::is_dataclass(statement, content)
+            {
+                outline.add_pattern("Dataclasses");
+            }
+            let Some(declaration) = Self::definition(statement) else {
+                continue;
+            };
+            match declaration.kind() {
+                "function_definition" => {
+                    let Some((function, signature, documentation)) =
+                        Self::parse_function(declaration, content)
+                    else {
+                        continue;
+                    };
+                    if function.is_async {
+                        outline.add_pattern("Async/await");
+                    }
+                    if function.visibility == "public" {
+                        outline.export(&function.name, "function", signature, documentation);
+                    }
+                    outline.functions.push(function);
+                }
+                "class_definition" => self.parse_class(&mut outline, declaration, content),
+                "expression_statement" => {
+                    let Some(assignment) = declaration
+                        .named_child(0)
+                        .filter(|n| n.kind() == "assignment")
+                    else {
+                        continue;
+                    };
+                    let Some(name) = assignment
+                        .child_by_field_name("left")
+                        .filter(|left| left.kind() == "identifier")
+                        .map(|left| text(left, content))
+                    else {
+                        continue;
+                    };
+                    let is_constant = name.starts_with(|c: char| c.is_ascii_uppercase())
+                        && name
+                            .chars()
+                            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+                    if !is_constant || assignment.child_by_field_name("right").is_none() {
+                        continue;
+                    }
+                    outline.constants.push(ConstantDefinition {
+                        name: name.to_string(),
+                        value_type: assignment
+                            .child_by_field_name("type")
+                            .map_or_else(|| "inferred".to_string(), |t| flat_text(t, content)),
+                        purpose: "Auto-extracted constant".to_string(),
+                        scope: "module".to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        outline
+    }
+
     /// Check for unittest test files
     async fn has_unittest_tests(&self, project_path: &Path) -> Result<bool> {
         let walker = walkdir::WalkDir::new(project_path)
@@ -453,6 +788,41 @@ impl LanguageAnalyzer for PythonAnalyzer {
         interfaces.dedup();
         Ok(interfaces)
     }
+
+    async fn extract_modules(&self, project_path: &Path) -> Result<Vec<ModuleBlueprint>> {
+        let sources = source::read_sources(project_path, &["py"]).await?;
+        let mut outlines: Vec<ModuleOutline> = sources
+            .iter()
+            .map(|(relative, content)| self.parse_module(relative, content))
+            .collect();
+
+        // A module is covered when some test module imports it directly
+        let tested: HashSet<String> = outlines
+            .iter()
+            .filter(|outline| outline.is_test)
+            .flat_map(|outline| outline.imports.iter().cloned())
+            .collect();
+        for outline in outlines.iter_mut().filter(|outline| !outline.is_test) {
+            outline.has_tests = tested.contains(&outline.name);
+        }
+
+        let roots: HashSet<String> = outlines
+            .iter()
+            .filter_map(|outline| outline.name.split('.').next())
+            .map(str::to_string)
+            .collect();
+        let test_framework = self.detect_test_framework(project_path).await?;
+
+        Ok(outlines
+            .into_iter()
+            .map(|outline| {
+                outline.into_blueprint(&test_framework, |import| {
+                    import.starts_with('.')
+                        || roots.contains(import.split('.').next().unwrap_or(import))
+                })
+            })
+            .collect())
+    }
 }
 
 impl Default for PythonAnalyzer {
@@ -509,4 +879,101 @@ mod tests {
 
         assert!(entry_points.contains(&"main.py".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_module_extraction() {
+        let temp_dir = tempdir().unwrap();
+        let temp_path = temp_dir.path();
+
+        tokio::fs::create_dir_all(temp_path.join("src/shop"))
+            .await
+            .unwrap();
+        tokio::fs::create_dir_all(temp_path.join("tests"))
+            .await
+            .unwrap();
+        tokio::fs::write(
+            temp_path.join("src/shop/models.py"),
+            r#""""Domain models."""
+from dataclasses import dataclass
+from pydantic import BaseModel
+
+MAX_ITEMS: int = 50
+
+
+class Order(BaseModel):
+    """A customer order."""
+
+    id: str
+    quantity: int = 1
+
+    def total(self, unit_price: float) -> float:
+        return self.quantity * unit_price
+
+    def _audit(self):
+        pass
+
+
+def _helper(x):
+    return x
+"#,
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            temp_path.join("tests/test_models.py"),
+            "from shop.models import Order\n\ndef test_total():\n    assert Order(id='a').total(2.0) == 2.0\n",
+        )
+        .await
+        .unwrap();
+
+        let analyzer = PythonAnalyzer::new();
+        let modules = analyzer.extract_modules(temp_path).await.unwrap();
+        assert_eq!(modules.len(), 2);
+
+        let models = modules.iter().find(|m| m.name == "shop.models").unwrap();
+        assert_eq!(models.purpose, "Domain models.");
+        assert_eq!(models.testing_strategy.test_types, vec!["unit".to_string()]);
+        assert!(models
+            .internal_structure
+            .internal_patterns
+            .contains(&"Pydantic models".to_string()));
+
+        let names: Vec<_> = models
+            .public_interface
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Order.total", "Order"]);
+        assert_eq!(
+            models.public_interface[0].signature,
+            "def total(self, unit_price: float) -> float"
+        );
+
+        let order = &models.internal_structure.primary_types[0];
+        assert_eq!(order.purpose, "A customer order.");
+        assert_eq!(order.implementations, vec!["BaseModel".to_string()]);
+        assert_eq!(
+            order.fields_or_variants,
+            vec!["id: str".to_string(), "quantity: int".to_string()]
+        );
+        assert_eq!(models.internal_structure.constants[0].value_type, "int");
+
+        let dependencies: Vec<_> = models
+            .dependencies
+            .iter()
+            .map(|d| (d.module.as_str(), d.coupling_strength.as_str()))
+            .collect();
+        assert_eq!(
+            dependencies,
+            vec![("dataclasses", "loose"), ("pydantic", "loose")]
+        );
+
+        let tests = modules
+            .iter()
+            .find(|m| m.name == "tests.test_models")
+            .unwrap();
+        assert!(tests.public_interface.is_empty());
+        assert_eq!(tests.dependencies[0].coupling_strength, "tight");
+        assert_eq!(tests.dependencies[0].dependency_type, "dev");
+    }
 }
@@ -0,0 +1,319 @@
+//! Source Scanning Helpers
+//!
+//! Shared helpers the language analyzers use to walk a project's source files,
+//! parse them with tree-sitter and turn the declarations they find into module
+//! blueprints.
+
+use crate::blueprint::{
+    ConstantDefinition, FunctionDefinition, InterfaceDefinition, ModuleBlueprint, ModuleDependency,
+    ModulePerformanceProfile, ModuleStructure, ModuleTestingStrategy, TypeDefinition,
+};
+use crate::codegen::staging::SKIPPED_DIRS;
+use crate::context::syntax_chunker::grammar;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser, Tree};
+
+/// Generated or vendored code, copied into staging but not analysed
+const GENERATED_DIRS: &[&str] = &["vendor", "dist", "build", "coverage"];
+
+/// Read every source file under `root` with one of `extensions`, paired with
+/// its path relative to `root`
+pub(super) async fn read_sources(
+    root: &Path,
+    extensions: &[&str],
+) -> Result<Vec<(PathBuf, String)>> {
+    let walker = walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !(name.starts_with('.')
+                    || SKIPPED_DIRS.contains(&name.as_ref())
+                    || GENERATED_DIRS.contains(&name.as_ref()))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+
+    let mut sources = Vec::new();
+    for entry in walker {
+        let path = entry.path();
+        let matches = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.contains(&ext));
+        if !matches {
+            continue;
+        }
+
+        // Skip files that aren't valid UTF-8 rather than failing the extraction
+        let Ok(content) = tokio::fs::read_to_string(path).await else {
+            continue;
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        sources.push((relative, content));
+    }
+
+    Ok(sources)
+}
+
+/// Declarations collected from one source module before it becomes a
+/// `ModuleBlueprint`
+#[derive(Debug, Default)]
+pub(super) struct ModuleOutline {
+    pub name: String,
+    pub purpose: Option<String>,
+    pub imports: Vec<String>,
+    pub interface: Vec<InterfaceDefinition>,
+    pub types: Vec<TypeDefinition>,
+    pub functions: Vec<FunctionDefinition>,
+    pub constants: Vec<ConstantDefinition>,
+    pub patterns: Vec<String>,
+    pub is_test: bool,
+    pub has_tests: bool,
+}
+
+impl ModuleOutline {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Record an import, keeping the first occurrence of each path
+    pub fn add_import(&mut self, import: &str) {
+        if !import.is_empty() && !self.imports.iter().any(|i| i == import) {
+            self.imports.push(import.to_string());
+        }
+    }
+
+    /// Record a named pattern once
+    pub fn add_pattern(&mut self, pattern: &str) {
+        if !self.patterns.iter().any(|p| p == pattern) {
+            self.patterns.push(pattern.to_string());
+        }
+    }
+
+    /// Add a declaration to the public interface
+    pub fn export(
+        &mut self,
+        name: &str,
+        interface_type: &str,
+        signature: String,
+        documentation: Option<String>,
+    ) {
+        self.interface.push(InterfaceDefinition {
+            name: name.to_string(),
+            interface_type: interface_type.to_string(),
+            visibility: "public".to_string(),
+            signature,
+            documentation: documentation
+                .unwrap_or_else(|| format!("Auto-extracted {}", interface_type)),
+            usage_examples: Vec::new(),
+        });
+    }
+
+    /// Fold another file's declarations into this module; test files only
+    /// mark the module as tested
+    pub fn merge(&mut self, other: ModuleOutline) {
+        if other.is_test {
+            self.has_tests = true;
+            return;
+        }
+        if self.purpose.is_none() {
+            self.purpose = other.purpose;
+        }
+        for import in &other.imports {
+            self.add_import(import);
+        }
+        for pattern in &other.patterns {
+            self.add_pattern(pattern);
+        }
+        self.interface.extend(other.interface);
+        self.types.extend(other.types);
+        self.functions.extend(other.functions);
+        self.constants.extend(other.constants);
+        self.has_tests |= other.has_tests;
+    }
+
+    /// Build the module blueprint, classifying imports with `is_internal`
+    pub fn into_blueprint(
+        self,
+        test_framework: &str,
+        is_internal: impl Fn(&str) -> bool,
+    ) -> ModuleBlueprint {
+        let dependency_type = if self.is_test { "dev" } else { "required" };
+        let dependencies = self
+            .imports
+            .into_iter()
+            .map(|import| ModuleDependency {
+                coupling_strength: if is_internal(&import) {
+                    "tight"
+                } else {
+                    "loose"
+                }
+                .to_string(),
+                module: import,
+                dependency_type: dependency_type.to_string(),
+                usage_pattern: "import".to_string(),
+            })
+            .collect();
+
+        let tested = self.is_test || self.has_tests;
+        ModuleBlueprint {
+            name: self.name,
+            purpose: self
+                .purpose
+                .unwrap_or_else(|| "Auto-extracted module purpose".to_string()),
+            dependencies,
+            // Test modules expose nothing to the rest of the system
+            public_interface: if self.is_test {
+                Vec::new()
+            } else {
+                self.interface
+            },
+            internal_structure: ModuleStructure {
+                primary_types: self.types,
+                functions: self.functions,
+                constants: self.constants,
+                internal_patterns: self.patterns,
+            },
+            testing_strategy: ModuleTestingStrategy {
+                test_types: if tested {
+                    vec!["unit".to_string()]
+                } else {
+                    Vec::new()
+                },
+                coverage_target: 80.0,
+                test_patterns: if tested {
+                    vec![format!("{} tests", test_framework)]
+                } else {
+                    Vec::new()
+                },
+                mock_strategies: Vec::new(),
+            },
+            performance_characteristics: ModulePerformanceProfile {
+                latency_characteristics: "Unknown".to_string(),
+                memory_usage: "Unknown".to_string(),
+                scalability_limits: None,
+                optimization_opportunities: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Parse a source file with the tree-sitter grammar of `language`
+pub(super) fn parse(language: &str, content: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(grammar(language)?).ok()?;
+    parser.parse(content, None)
+}
+
+/// Source text of a node
+pub(super) fn text<'a>(node: Node, content: &'a str) -> &'a str {
+    &content[node.byte_range()]
+}
+
+/// Source text of a node with each run of whitespace collapsed to a space
+pub(super) fn flat_text(node: Node, content: &str) -> String {
+    text(node, content)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Named children of a node, skipping comments
+pub(super) fn named_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node
+        .named_children(&mut cursor)
+        .filter(|child| !child.kind().contains("comment"))
+        .collect();
+    children
+}
+
+/// Whether a node has an anonymous child token such as `async`
+pub(super) fn has_token(node: Node, token: &str) -> bool {
+    let mut cursor = node.walk();
+    let found = node
+        .children(&mut cursor)
+        .any(|child| !child.is_named() && child.kind() == token);
+    found
+}
+
+/// Every node of one of `kinds` under `node`, in source order
+pub(super) fn descendants<'t>(node: Node<'t>, kinds: &[&str]) -> Vec<Node<'t>> {
+    let mut found = Vec::new();
+    let mut cursor = node.walk();
+    let mut depth = 0usize;
+    loop {
+        if kinds.contains(&cursor.node().kind()) {
+            found.push(cursor.node());
+        }
+        if cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        loop {
+            if depth == 0 {
+                return found;
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            cursor.goto_parent();
+            depth -= 1;
+        }
+    }
+}
+
+/// First line of the comment block that ends on the line above byte `offset`
+pub(super) fn preceding_comment(content: &str, offset: usize, markers: &[&str]) -> Option<String> {
+    let before = content[..offset].trim_end_matches(|c| c != '\n');
+    let mut first = None;
+    for line in before.lines().rev() {
+        let line = line.trim();
+        let Some(marker) = markers.iter().find(|m| line.starts_with(**m)) else {
+            break;
+        };
+        let text = line[marker.len()..].trim().trim_end_matches("*/").trim();
+        if !text.is_empty() {
+            first = Some(text.to_string());
+        }
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_helpers() {
+        let content = "def run(a, b):\n    return helper(a) + helper(b)\n";
+        let tree = parse("python", content).unwrap();
+        let calls = descendants(tree.root_node(), &["call"]);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(text(calls[1], content), "helper(b)");
+
+        let function = named_children(tree.root_node())[0];
+        let parameters = function.child_by_field_name("parameters").unwrap();
+        assert_eq!(flat_text(parameters, content), "(a, b)");
+        assert!(!has_token(function, "async"));
+    }
+
+    #[test]
+    fn test_preceding_comment() {
+        let content =
+            "// Store keeps items.\n// It is safe for concurrent use.\ntype Store struct{}\n";
+        let offset = content.find("type").unwrap();
+        assert_eq!(
+            preceding_comment(content, offset, &["//"]).as_deref(),
+            Some("Store keeps items.")
+        );
+        assert_eq!(preceding_comment(content, 0, &["//"]), None);
+    }
+}
@@ -3,8 +3,10 @@
 //! This module extends the blueprint system to support multiple programming languages,
 //! enabling cross-language analysis, generation, and replication.
 
+use super::ModuleBlueprint;
 use anyhow::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
@@ -13,7 +15,7 @@ use std::sync::Arc;
 pub mod analyzers;
 
 /// Supported programming languages
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub enum Language {
     Rust,
     Python,
@@ -105,7 +107,7 @@ impl Language {
 }
 
 /// Multi-language project structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MultiLanguageBlueprint {
     pub primary_language: Language,
     pub secondary_languages: Vec<Language>,
@@ -116,7 +118,7 @@ pub struct MultiLanguageBlueprint {
 }
 
 /// Language-specific module information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LanguageModule {
     pub language: Language,
     pub entry_points: Vec<String>,
@@ -127,7 +129,7 @@ pub struct LanguageModule {
 }
 
 /// Cross-language interface binding
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InterfaceBinding {
     pub source_language: Language,
     pub target_language: Language,
@@ -137,7 +139,7 @@ pub struct InterfaceBinding {
 }
 
 /// Types of cross-language bindings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum BindingType {
     FFI,          // Foreign Function Interface
     WebAPI,       // REST/GraphQL API
@@ -148,7 +150,7 @@ pub enum BindingType {
 }
 
 /// Dependency information for any language
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Dependency {
     pub name: String,
     pub version: String,
@@ -158,7 +160,7 @@ pub struct Dependency {
 }
 
 /// Source of dependencies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum DependencySource {
     Registry(String), // e.g., "crates.io", "pypi", "npm"
     Git(String),      // Git repository URL
@@ -167,7 +169,7 @@ pub enum DependencySource {
 }
 
 /// Build configuration for a language
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BuildConfig {
     pub build_tool: String,
     pub build_file: String, // e.g., "Cargo.toml", "package.json", "pom.xml"
@@ -177,7 +179,7 @@ pub struct BuildConfig {
 }
 
 /// Test configuration for a language
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestConfig {
     pub test_framework: String,
     pub test_directories: Vec<String>,
@@ -186,7 +188,7 @@ pub struct TestConfig {
 }
 
 /// Documentation configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DocumentationConfig {
     pub doc_tool: String,   // e.g., "rustdoc", "sphinx", "jsdoc"
     pub doc_format: String, // e.g., "html", "markdown"
@@ -195,7 +197,7 @@ pub struct DocumentationConfig {
 }
 
 /// Build orchestration for multi-language projects
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BuildOrchestration {
     pub orchestration_tool: String, // e.g., "make", "bazel", "gradle"
     pub build_order: Vec<Language>,
@@ -205,7 +207,9 @@ pub struct BuildOrchestration {
 }
 
 /// Deployment strategy for multi-language systems
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+// Named apart from the system-wide `DeploymentStrategy` in the schema
+#[schemars(rename = "LanguageDeploymentStrategy")]
 pub struct DeploymentStrategy {
     pub containerization: ContainerConfig,
     pub orchestration: OrchestrationConfig,
@@ -213,7 +217,7 @@ pub struct DeploymentStrategy {
 }
 
 /// Container configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContainerConfig {
     pub base_images: HashMap<Language, String>,
     pub multi_stage_build: bool,
@@ -222,7 +226,7 @@ pub struct ContainerConfig {
 }
 
 /// Orchestration configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OrchestrationConfig {
     pub platform: String, // "kubernetes", "docker-compose", "nomad"
     pub service_mesh: Option<String>,
@@ -231,7 +235,7 @@ pub struct OrchestrationConfig {
 }
 
 /// Monitoring configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MonitoringConfig {
     pub metrics_collection: String,
     pub log_aggregation: String,
@@ -258,6 +262,14 @@ pub trait LanguageAnalyzer: Send + Sync {
 
     /// Extract API interfaces
     async fn extract_interfaces(&self, project_path: &Path) -> Result<Vec<String>>;
+
+    /// Extract module blueprints from the project's source files
+    ///
+    /// Analyzers that don't parse sources return no modules; Rust sources are
+    /// covered by the tree-sitter pass in `BlueprintExtractor`.
+    async fn extract_modules(&self, _project_path: &Path) -> Result<Vec<ModuleBlueprint>> {
+        Ok(Vec::new())
+    }
 }
 
 impl MultiLanguageAnalyzer {
@@ -282,6 +294,10 @@ impl MultiLanguageAnalyzer {
             Language::TypeScript,
             Arc::new(analyzers::JavaScriptAnalyzer::new()) as Arc<dyn LanguageAnalyzer>,
         );
+        analyzers.insert(
+            Language::Go,
+            Arc::new(analyzers::GoAnalyzer::new()) as Arc<dyn LanguageAnalyzer>,
+        );
 
         Self { analyzers }
     }
@@ -291,6 +307,11 @@ impl MultiLanguageAnalyzer {
         self.analyzers.insert(language, analyzer);
     }
 
+    /// Get the analyzer registered for a language
+    pub fn analyzer(&self, language: &Language) -> Option<Arc<dyn LanguageAnalyzer>> {
+        self.analyzers.get(language).cloned()
+    }
+
     /// Detect languages in a project
     pub async fn detect_languages(&self, project_path: &Path) -> Result<Vec<Language>> {
         let mut languages = Vec::new();
@@ -4,14 +4,17 @@
 //! that capture not just the structure, but the architectural decisions, patterns,
 //! and implementation strategies that make the system work.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub mod extractor;
+pub mod fidelity;
 pub mod generator;
 pub mod languages;
 pub mod replicator;
+pub mod schema;
 pub mod templates;
 
 pub mod evolution;
@@ -19,8 +22,11 @@ pub mod evolution;
 pub mod tests;
 
 /// Complete system blueprint containing all information needed for self-replication
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SystemBlueprint {
+    /// Version of the blueprint file format, see `schema::CURRENT_SCHEMA_VERSION`
+    #[serde(default = "schema::legacy_schema_version")]
+    pub schema_version: u32,
     pub metadata: SystemMetadata,
     pub architecture: ArchitecturalDecisions,
     pub modules: Vec<ModuleBlueprint>,
@@ -31,10 +37,13 @@ pub struct SystemBlueprint {
     pub performance: PerformanceOptimizations,
     pub security: SecurityPatterns,
     pub deployment: DeploymentStrategy,
+    /// Per-language breakdown, present when the codebase mixes languages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<languages::MultiLanguageBlueprint>,
 }
 
 /// System metadata and identification
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SystemMetadata {
     pub name: String,
     pub version: String,
@@ -46,7 +55,7 @@ pub struct SystemMetadata {
 }
 
 /// High-level architectural decisions and their reasoning
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ArchitecturalDecisions {
     pub system_type: String, // "multi-agent", "microservices", "monolith"
     pub concurrency_model: ConcurrencyModel,
@@ -58,7 +67,7 @@ pub struct ArchitecturalDecisions {
 }
 
 /// Individual architectural decision with reasoning
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ArchitecturalDecision {
     pub decision: String,
     pub reasoning: String,
@@ -68,7 +77,7 @@ pub struct ArchitecturalDecision {
 }
 
 /// Concurrency model details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConcurrencyModel {
     pub primary_pattern: String, // "actor", "async_tasks", "thread_pool"
     pub synchronization_primitives: Vec<String>,
@@ -78,7 +87,7 @@ pub struct ConcurrencyModel {
 }
 
 /// Data flow patterns in the system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DataFlowPattern {
     pub primary_pattern: String, // "pipeline", "event_driven", "request_response"
     pub message_passing: MessagePassingStrategy,
@@ -87,7 +96,7 @@ pub struct DataFlowPattern {
 }
 
 /// Message passing strategy details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MessagePassingStrategy {
     pub channel_types: Vec<String>,
     pub serialization: String,
@@ -96,7 +105,7 @@ pub struct MessagePassingStrategy {
 }
 
 /// Data transformation steps
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DataTransformation {
     pub stage: String,
     pub input_type: String,
@@ -106,7 +115,7 @@ pub struct DataTransformation {
 }
 
 /// Persistence strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PersistenceStrategy {
     pub primary_storage: String,
     pub caching_layers: Vec<CachingLayer>,
@@ -115,7 +124,7 @@ pub struct PersistenceStrategy {
 }
 
 /// Caching layer configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CachingLayer {
     pub layer_type: String,
     pub eviction_policy: String,
@@ -124,7 +133,7 @@ pub struct CachingLayer {
 }
 
 /// Error handling strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ErrorHandlingStrategy {
     pub error_types: Vec<ErrorType>,
     pub propagation_strategy: String,
@@ -134,7 +143,7 @@ pub struct ErrorHandlingStrategy {
 }
 
 /// Error type definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ErrorType {
     pub name: String,
     pub category: String, // "recoverable", "fatal", "user_error"
@@ -143,7 +152,7 @@ pub struct ErrorType {
 }
 
 /// Resource management strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ResourceManagementStrategy {
     pub memory_management: String,
     pub file_handle_management: String,
@@ -153,7 +162,7 @@ pub struct ResourceManagementStrategy {
 }
 
 /// Module blueprint with detailed specifications
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModuleBlueprint {
     pub name: String,
     pub purpose: String,
@@ -165,7 +174,7 @@ pub struct ModuleBlueprint {
 }
 
 /// Module dependency information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModuleDependency {
     pub module: String,
     pub dependency_type: String, // "required", "optional", "dev"
@@ -174,7 +183,7 @@ pub struct ModuleDependency {
 }
 
 /// Interface definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InterfaceDefinition {
     pub name: String,
     pub interface_type: String, // "function", "trait", "struct", "enum"
@@ -185,7 +194,7 @@ pub struct InterfaceDefinition {
 }
 
 /// Internal module structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModuleStructure {
     pub primary_types: Vec<TypeDefinition>,
     pub functions: Vec<FunctionDefinition>,
@@ -194,7 +203,7 @@ pub struct ModuleStructure {
 }
 
 /// Type definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TypeDefinition {
     pub name: String,
     pub type_kind: String, // "struct", "enum", "trait"
@@ -204,7 +213,7 @@ pub struct TypeDefinition {
 }
 
 /// Function definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FunctionDefinition {
     pub name: String,
     pub visibility: String,
@@ -216,7 +225,7 @@ pub struct FunctionDefinition {
 }
 
 /// Function parameter
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Parameter {
     pub name: String,
     pub param_type: String,
@@ -225,7 +234,7 @@ pub struct Parameter {
 }
 
 /// Constant definition
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConstantDefinition {
     pub name: String,
     pub value_type: String,
@@ -234,7 +243,7 @@ pub struct ConstantDefinition {
 }
 
 /// Module-specific testing strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModuleTestingStrategy {
     pub test_types: Vec<String>, // "unit", "integration", "property"
     pub coverage_target: f32,
@@ -243,7 +252,7 @@ pub struct ModuleTestingStrategy {
 }
 
 /// Module performance profile
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModulePerformanceProfile {
     pub latency_characteristics: String,
     pub memory_usage: String,
@@ -252,7 +261,7 @@ pub struct ModulePerformanceProfile {
 }
 
 /// Design patterns used in the system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DesignPatterns {
     pub creational_patterns: Vec<PatternUsage>,
     pub structural_patterns: Vec<PatternUsage>,
@@ -262,7 +271,7 @@ pub struct DesignPatterns {
 }
 
 /// Pattern usage information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PatternUsage {
     pub pattern_name: String,
     pub usage_context: String,
@@ -272,7 +281,7 @@ pub struct PatternUsage {
 }
 
 /// Anti-pattern avoidance
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AntiPatternAvoidance {
     pub anti_pattern_name: String,
     pub why_avoided: String,
@@ -280,7 +289,7 @@ pub struct AntiPatternAvoidance {
 }
 
 /// Implementation details and technical decisions
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ImplementationDetails {
     pub language_specific_features: Vec<LanguageFeatureUsage>,
     pub third_party_dependencies: Vec<DependencyUsage>,
@@ -290,7 +299,7 @@ pub struct ImplementationDetails {
 }
 
 /// Language feature usage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LanguageFeatureUsage {
     pub feature: String,
     pub usage_pattern: String,
@@ -299,7 +308,7 @@ pub struct LanguageFeatureUsage {
 }
 
 /// Third-party dependency usage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DependencyUsage {
     pub crate_name: String,
     pub version: String,
@@ -310,7 +319,7 @@ pub struct DependencyUsage {
 }
 
 /// Custom implementation details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CustomImplementation {
     pub component: String,
     pub why_custom: String,
@@ -319,7 +328,7 @@ pub struct CustomImplementation {
 }
 
 /// Optimization technique
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct OptimizationTechnique {
     pub technique: String,
     pub target_metric: String, // "latency", "memory", "throughput"
@@ -328,7 +337,7 @@ pub struct OptimizationTechnique {
 }
 
 /// Platform-specific code
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PlatformSpecificCode {
     pub platform: String,
     pub code_section: String,
@@ -337,7 +346,7 @@ pub struct PlatformSpecificCode {
 }
 
 /// Configuration strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ConfigurationStrategy {
     pub hierarchy: Vec<String>,
     pub formats_supported: Vec<String>,
@@ -348,7 +357,7 @@ pub struct ConfigurationStrategy {
 }
 
 /// Environment handling strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EnvironmentHandling {
     pub environment_types: Vec<String>,
     pub configuration_differences: HashMap<String, Vec<String>>,
@@ -356,7 +365,7 @@ pub struct EnvironmentHandling {
 }
 
 /// Secret management approach
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecretManagement {
     pub storage_method: String,
     pub encryption_approach: String,
@@ -365,7 +374,7 @@ pub struct SecretManagement {
 }
 
 /// Testing strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestingStrategy {
     pub test_pyramid: TestPyramid,
     pub test_automation: TestAutomation,
@@ -375,7 +384,7 @@ pub struct TestingStrategy {
 }
 
 /// Test pyramid structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestPyramid {
     pub unit_tests: TestingApproach,
     pub integration_tests: TestingApproach,
@@ -384,7 +393,7 @@ pub struct TestPyramid {
 }
 
 /// Testing approach for each level
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestingApproach {
     pub percentage_of_tests: f32,
     pub frameworks_used: Vec<String>,
@@ -393,7 +402,7 @@ pub struct TestingApproach {
 }
 
 /// Test automation details
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestAutomation {
     pub ci_integration: String,
     pub test_triggers: Vec<String>,
@@ -402,7 +411,7 @@ pub struct TestAutomation {
 }
 
 /// Test data management
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TestDataManagement {
     pub data_generation_strategy: String,
     pub fixture_management: String,
@@ -411,7 +420,7 @@ pub struct TestDataManagement {
 }
 
 /// Performance testing strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PerformanceTestingStrategy {
     pub load_testing: String,
     pub stress_testing: String,
@@ -420,7 +429,7 @@ pub struct PerformanceTestingStrategy {
 }
 
 /// Security testing strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecurityTestingStrategy {
     pub vulnerability_scanning: String,
     pub penetration_testing: String,
@@ -429,7 +438,7 @@ pub struct SecurityTestingStrategy {
 }
 
 /// Performance optimizations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PerformanceOptimizations {
     pub critical_paths: Vec<CriticalPath>,
     pub caching_strategies: Vec<CachingStrategy>,
@@ -439,7 +448,7 @@ pub struct PerformanceOptimizations {
 }
 
 /// Critical path optimization
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CriticalPath {
     pub path_description: String,
     pub bottlenecks: Vec<String>,
@@ -448,7 +457,7 @@ pub struct CriticalPath {
 }
 
 /// Caching strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CachingStrategy {
     pub cache_type: String,
     pub cache_scope: String,
@@ -457,7 +466,7 @@ pub struct CachingStrategy {
 }
 
 /// Resource pooling strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ResourcePooling {
     pub resource_type: String,
     pub pool_size: String,
@@ -466,7 +475,7 @@ pub struct ResourcePooling {
 }
 
 /// Lazy loading strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LazyLoadingStrategy {
     pub component: String,
     pub trigger_condition: String,
@@ -475,7 +484,7 @@ pub struct LazyLoadingStrategy {
 }
 
 /// Batch processing strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BatchProcessingStrategy {
     pub operation_type: String,
     pub batch_size: String,
@@ -484,7 +493,7 @@ pub struct BatchProcessingStrategy {
 }
 
 /// Security patterns
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SecurityPatterns {
     pub authentication: AuthenticationPattern,
     pub authorization: AuthorizationPattern,
@@ -494,7 +503,7 @@ pub struct SecurityPatterns {
 }
 
 /// Authentication pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AuthenticationPattern {
     pub primary_method: String,
     pub multi_factor: bool,
@@ -503,7 +512,7 @@ pub struct AuthenticationPattern {
 }
 
 /// Authorization pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AuthorizationPattern {
     pub model: String, // "RBAC", "ABAC", "ACL"
     pub granularity: String,
@@ -512,7 +521,7 @@ pub struct AuthorizationPattern {
 }
 
 /// Data protection pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DataProtectionPattern {
     pub encryption_at_rest: String,
     pub encryption_in_transit: String,
@@ -521,7 +530,7 @@ pub struct DataProtectionPattern {
 }
 
 /// Communication security
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CommunicationSecurity {
     pub protocol_security: String,
     pub certificate_management: String,
@@ -530,7 +539,7 @@ pub struct CommunicationSecurity {
 }
 
 /// Input validation pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InputValidationPattern {
     pub validation_layers: Vec<String>,
     pub sanitization_approach: String,
@@ -539,7 +548,7 @@ pub struct InputValidationPattern {
 }
 
 /// Deployment strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DeploymentStrategy {
     pub deployment_model: String,
     pub infrastructure: InfrastructurePattern,
@@ -549,7 +558,7 @@ pub struct DeploymentStrategy {
 }
 
 /// Infrastructure pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct InfrastructurePattern {
     pub architecture_type: String, // "containerized", "serverless", "traditional"
     pub orchestration: String,
@@ -558,7 +567,7 @@ pub struct InfrastructurePattern {
 }
 
 /// Scaling strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ScalingStrategy {
     pub horizontal_scaling: String,
     pub vertical_scaling: String,
@@ -567,7 +576,7 @@ pub struct ScalingStrategy {
 }
 
 /// Monitoring strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MonitoringStrategy {
     pub metrics_collection: String,
     pub logging_strategy: String,
@@ -576,7 +585,7 @@ pub struct MonitoringStrategy {
 }
 
 /// Maintenance strategy
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MaintenanceStrategy {
     pub update_strategy: String,
     pub backup_procedures: String,
@@ -588,6 +597,7 @@ impl SystemBlueprint {
     /// Create a new empty system blueprint
     pub fn new(name: String, description: String) -> Self {
         Self {
+            schema_version: schema::CURRENT_SCHEMA_VERSION,
             metadata: SystemMetadata {
                 name,
                 version: "0.1.0".to_string(),
@@ -606,6 +616,7 @@ impl SystemBlueprint {
             performance: PerformanceOptimizations::default(),
             security: SecurityPatterns::default(),
             deployment: DeploymentStrategy::default(),
+            languages: None,
         }
     }
 
@@ -616,10 +627,17 @@ impl SystemBlueprint {
         Ok(())
     }
 
-    /// Load a blueprint from a TOML file
+    /// Load a blueprint from a TOML file, upgrading files written with an
+    /// older schema version
     pub fn load_from_file(path: &std::path::Path) -> Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let blueprint = toml::from_str(&content)?;
+        let mut value = schema::read_blueprint_value(path)?;
+        schema::MigrationRegistry::default().migrate(&mut value)?;
+        let blueprint = serde_json::from_value(value).with_context(|| {
+            format!(
+                "{} does not match the blueprint schema; run `devkit blueprint validate --schema` for details",
+                path.display()
+            )
+        })?;
         Ok(blueprint)
     }
 
@@ -4,6 +4,7 @@
 //! combining blueprint extraction, validation, and project generation.
 
 use super::extractor::BlueprintExtractor;
+use super::fidelity::{CheckStatus, FidelityAnalyzer, FidelityReport};
 use super::generator::BlueprintGenerator;
 use super::*;
 use anyhow::Result;
@@ -16,6 +17,7 @@ pub struct SystemReplicator {
     target_path: PathBuf,
     preserve_git: bool,
     validate_generated: bool,
+    check_fidelity: bool,
     dry_run: bool,
 }
 
@@ -26,6 +28,8 @@ pub struct ReplicationConfig {
     pub target_path: PathBuf,
     pub preserve_git: bool,
     pub validate_generated: bool,
+    /// Score the replica against the source blueprint
+    pub check_fidelity: bool,
     pub dry_run: bool,
     pub include_tests: bool,
     pub include_documentation: bool,
@@ -39,6 +43,7 @@ pub struct ReplicationResult {
     pub blueprint_path: PathBuf,
     pub generated_files: Vec<PathBuf>,
     pub validation_results: Vec<ValidationResult>,
+    pub fidelity: Option<FidelityReport>,
     pub execution_time: std::time::Duration,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
@@ -71,6 +76,7 @@ impl SystemReplicator {
             target_path,
             preserve_git: false,
             validate_generated: true,
+            check_fidelity: false,
             dry_run: false,
         }
     }
@@ -82,6 +88,7 @@ impl SystemReplicator {
             target_path: config.target_path,
             preserve_git: config.preserve_git,
             validate_generated: config.validate_generated,
+            check_fidelity: config.check_fidelity,
             dry_run: config.dry_run,
         }
     }
@@ -98,6 +105,12 @@ impl SystemReplicator {
         self
     }
 
+    /// Set whether to score the replica against the source blueprint
+    pub fn check_fidelity(mut self, check: bool) -> Self {
+        self.check_fidelity = check;
+        self
+    }
+
     /// Set dry run mode (only show what would be done)
     pub fn dry_run(mut self, dry_run: bool) -> Self {
         self.dry_run = dry_run;
@@ -172,9 +185,36 @@ impl SystemReplicator {
             }
         }
 
-        // Step 5: Copy additional assets (if requested)
+        // Step 5: Score the replica against the source blueprint
+        let mut fidelity = None;
+        if self.check_fidelity && !self.dry_run {
+            println!("\n📏 Step 5: Scoring replication fidelity...");
+            let report = FidelityAnalyzer::new()
+                .assess(&blueprint, &self.target_path)
+                .await?;
+            report.save_to_file(&self.target_path.join("fidelity_report.json"))?;
+            println!(
+                "✅ Fidelity: {:.1}% (modules {:.1}%, interfaces {:.1}%, dependencies {:.1}%)",
+                report.overall_score * 100.0,
+                report.module_score * 100.0,
+                report.interface_score * 100.0,
+                report.dependency_score * 100.0
+            );
+
+            for check in &report.checks {
+                if check.status == CheckStatus::Failed {
+                    warnings.push(format!(
+                        "Replica {:?} check failed: {}",
+                        check.kind, check.detail
+                    ));
+                }
+            }
+            fidelity = Some(report);
+        }
+
+        // Step 6: Copy additional assets (if requested)
         if self.preserve_git && !self.dry_run {
-            println!("\n📁 Step 5: Preserving git history...");
+            println!("\n📁 Step 6: Preserving git history...");
             self.preserve_git_history().await?;
         }
 
@@ -200,6 +240,7 @@ impl SystemReplicator {
             blueprint_path,
             generated_files,
             validation_results,
+            fidelity,
             execution_time,
             warnings,
             errors,
@@ -555,6 +596,11 @@ impl SystemReplicator {
             ));
         }
 
+        if let Some(fidelity) = &result.fidelity {
+            report.push('\n');
+            report.push_str(&fidelity.to_markdown());
+        }
+
         if !self.dry_run {
             fs::write(&report_path, report).await?;
             println!("📊 Replication report saved to: {:?}", report_path);
@@ -576,6 +622,7 @@ impl Default for ReplicationConfig {
             target_path: PathBuf::from("./replicated_system"),
             preserve_git: false,
             validate_generated: true,
+            check_fidelity: false,
             dry_run: false,
             include_tests: true,
             include_documentation: true,
@@ -636,6 +683,7 @@ edition = "2021"
         assert_eq!(config.target_path, PathBuf::from("./replicated_system"));
         assert!(!config.preserve_git);
         assert!(config.validate_generated);
+        assert!(!config.check_fidelity);
         assert!(!config.dry_run);
     }
 }
@@ -0,0 +1,448 @@
+//! Blueprint Schema
+//!
+//! JSON Schema for blueprint files, generated from the blueprint types, and
+//! the migrations that upgrade files written by older devkit versions. Every
+//! blueprint file records the schema version it was written with; files
+//! without one predate versioning and are treated as version 1.
+
+use super::{ModulePerformanceProfile, ModuleStructure, ModuleTestingStrategy, SystemBlueprint};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Schema version written by this devkit
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Version of blueprint files written before blueprints were versioned
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// Default for blueprints deserialized without a version field, which
+/// predate versioning
+pub(crate) fn legacy_schema_version() -> u32 {
+    LEGACY_SCHEMA_VERSION
+}
+
+/// JSON Schema of the current blueprint format
+///
+/// The schema is strict: objects may only hold the fields the blueprint types
+/// declare, so misspelt keys are reported instead of silently ignored.
+pub fn blueprint_schema() -> Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(SystemBlueprint))
+        .expect("blueprint schema serializes to JSON");
+    deny_unknown_properties(&mut schema);
+    schema
+}
+
+fn deny_unknown_properties(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            object.values_mut().for_each(deny_unknown_properties);
+        }
+        Value::Array(items) => items.iter_mut().for_each(deny_unknown_properties),
+        _ => {}
+    }
+}
+
+/// A place where a blueprint doesn't match the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, such as `/modules/0/name`
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "(root): {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+/// Check a blueprint, as JSON, against the current schema
+pub fn validate_value(blueprint: &Value) -> Result<Vec<SchemaViolation>> {
+    let schema = blueprint_schema();
+    let compiled = jsonschema::JSONSchema::compile(&schema)
+        .map_err(|e| anyhow!("Invalid blueprint schema: {}", e))?;
+
+    let violations = match compiled.validate(blueprint) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .map(|error| SchemaViolation {
+                pointer: error.instance_path.to_string(),
+                message: error.to_string(),
+            })
+            .collect(),
+    };
+    Ok(violations)
+}
+
+/// Result of checking a blueprint file against the schema
+#[derive(Debug, Clone)]
+pub struct SchemaReport {
+    /// Version the file was written with
+    pub file_version: u32,
+    /// Descriptions of the migrations that upgraded the file, in order
+    pub applied_migrations: Vec<&'static str>,
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl SchemaReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Upgrade a blueprint file to the current schema and validate it
+pub fn validate_file(path: &Path) -> Result<SchemaReport> {
+    let mut blueprint = read_blueprint_value(path)?;
+    let file_version = schema_version(&blueprint)?;
+    let applied_migrations = MigrationRegistry::default().migrate(&mut blueprint)?;
+    let violations = validate_value(&blueprint)?;
+
+    Ok(SchemaReport {
+        file_version,
+        applied_migrations,
+        violations,
+    })
+}
+
+/// Read a TOML blueprint file as JSON
+pub fn read_blueprint_value(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read blueprint {}", path.display()))?;
+    let table: toml::Value = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse blueprint {}", path.display()))?;
+    Ok(toml_to_json(table))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        // Unquoted TOML timestamps read like the RFC 3339 strings chrono writes
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Schema version recorded in a blueprint
+pub fn schema_version(blueprint: &Value) -> Result<u32> {
+    match blueprint.get("schema_version") {
+        None => Ok(LEGACY_SCHEMA_VERSION),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "schema_version must be a positive integer, found {}",
+                    version
+                )
+            }),
+    }
+}
+
+/// Upgrade of blueprint files from one schema version to the next
+pub struct BlueprintMigration {
+    /// Version the migration upgrades from; it produces `from_version + 1`
+    pub from_version: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Map<String, Value>) -> Result<()>,
+}
+
+/// Migrations by the version they upgrade from
+pub struct MigrationRegistry {
+    migrations: BTreeMap<u32, BlueprintMigration>,
+}
+
+impl Default for MigrationRegistry {
+    fn default() -> Self {
+        Self::new().with_migration(BlueprintMigration {
+            from_version: 1,
+            description: "Record the schema version and fill missing sections with defaults",
+            apply: fill_missing_sections,
+        })
+    }
+}
+
+impl MigrationRegistry {
+    /// Registry without any migrations
+    pub fn new() -> Self {
+        Self {
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Add a migration, replacing any previous one from the same version
+    pub fn with_migration(mut self, migration: BlueprintMigration) -> Self {
+        self.migrations.insert(migration.from_version, migration);
+        self
+    }
+
+    /// Upgrade a blueprint to the current schema version, returning the
+    /// descriptions of the migrations applied
+    pub fn migrate(&self, blueprint: &mut Value) -> Result<Vec<&'static str>> {
+        let mut version = schema_version(blueprint)?;
+        if version > CURRENT_SCHEMA_VERSION {
+            bail!(
+                "Blueprint schema version {} is newer than version {} supported by this devkit",
+                version,
+                CURRENT_SCHEMA_VERSION
+            );
+        }
+        let object = blueprint
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("A blueprint must be a table"))?;
+
+        let mut applied = Vec::new();
+        while version < CURRENT_SCHEMA_VERSION {
+            let migration = self
+                .migrations
+                .get(&version)
+                .ok_or_else(|| anyhow!("No migration from blueprint schema version {}", version))?;
+            (migration.apply)(object).with_context(|| {
+                format!(
+                    "Failed to migrate blueprint from schema version {}",
+                    version
+                )
+            })?;
+            version += 1;
+            object.insert("schema_version".to_string(), Value::from(version));
+            applied.push(migration.description);
+        }
+        Ok(applied)
+    }
+}
+
+/// Version 1 files were often written by hand and may lack whole sections,
+/// both at the top level and inside each module
+fn fill_missing_sections(blueprint: &mut Map<String, Value>) -> Result<()> {
+    let mut defaults = serde_json::to_value(SystemBlueprint::new(String::new(), String::new()))?;
+    defaults["modules"] = Value::Array(vec![module_defaults()?]);
+    if let Value::Object(defaults) = defaults {
+        merge_missing(blueprint, defaults);
+    }
+    Ok(())
+}
+
+/// The sections of a module that have defaults; its name and purpose don't
+fn module_defaults() -> Result<Value> {
+    let mut module = Map::new();
+    module.insert("dependencies".to_string(), Value::Array(Vec::new()));
+    module.insert("public_interface".to_string(), Value::Array(Vec::new()));
+    module.insert(
+        "internal_structure".to_string(),
+        serde_json::to_value(ModuleStructure::default())?,
+    );
+    module.insert(
+        "testing_strategy".to_string(),
+        serde_json::to_value(ModuleTestingStrategy::default())?,
+    );
+    module.insert(
+        "performance_characteristics".to_string(),
+        serde_json::to_value(ModulePerformanceProfile::default())?,
+    );
+    Ok(Value::Object(module))
+}
+
+/// Copy keys from `defaults` that `target` lacks, descending into tables
+/// both have. An array in `defaults` holds at most one table, the defaults
+/// for every element of the matching array in `target`; a missing array is
+/// filled in empty.
+fn merge_missing(target: &mut Map<String, Value>, defaults: Map<String, Value>) {
+    for (key, default) in defaults {
+        match (target.get_mut(&key), default) {
+            (None, default) => {
+                target.insert(key, without_element_defaults(default));
+            }
+            (Some(Value::Object(existing)), Value::Object(default)) => {
+                merge_missing(existing, default)
+            }
+            (Some(Value::Array(items)), Value::Array(default)) => {
+                if let Some(Value::Object(element)) = default.into_iter().next() {
+                    for item in items {
+                        if let Value::Object(item) = item {
+                            merge_missing(item, element.clone());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn without_element_defaults(default: Value) -> Value {
+    match default {
+        Value::Array(_) => Value::Array(Vec::new()),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, without_element_defaults(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const LEGACY_BLUEPRINT: &str = r#"
+[metadata]
+name = "orders"
+version = "1.2.0"
+description = "Order service"
+architecture_paradigm = "Layered"
+primary_language = "Rust"
+creation_timestamp = 2024-03-01T12:00:00Z
+generator_version = "0.1.0"
+
+[[modules]]
+name = "api"
+purpose = "HTTP routes"
+dependencies = []
+public_interface = []
+
+[modules.internal_structure]
+primary_types = []
+functions = []
+constants = []
+internal_patterns = []
+
+[modules.testing_strategy]
+test_types = []
+coverage_target = 80
+test_patterns = []
+mock_strategies = []
+
+[modules.performance_characteristics]
+latency_characteristics = "Unknown"
+memory_usage = "Unknown"
+optimization_opportunities = []
+"#;
+
+    #[test]
+    fn test_published_schema_is_current() {
+        let published: Value =
+            serde_json::from_str(include_str!("../../docs/schemas/blueprint.schema.json")).unwrap();
+        assert!(
+            published == blueprint_schema(),
+            "docs/schemas/blueprint.schema.json is stale; regenerate it with `devkit blueprint schema --output docs/schemas/blueprint.schema.json`"
+        );
+    }
+
+    #[test]
+    fn test_legacy_blueprint_migration() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("blueprint.toml");
+        std::fs::write(&path, LEGACY_BLUEPRINT).unwrap();
+
+        let report = validate_file(&path).unwrap();
+        assert_eq!(report.file_version, LEGACY_SCHEMA_VERSION);
+        assert_eq!(report.applied_migrations.len(), 1);
+        assert!(report.is_valid(), "{:?}", report.violations);
+
+        let blueprint = SystemBlueprint::load_from_file(&path).unwrap();
+        assert_eq!(blueprint.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(blueprint.metadata.name, "orders");
+        assert_eq!(blueprint.modules[0].name, "api");
+        assert_eq!(blueprint.architecture.system_type, "Unknown");
+
+        // Saving writes the current version, so a reload needs no migration
+        blueprint.save_to_file(&path).unwrap();
+        let report = validate_file(&path).unwrap();
+        assert_eq!(report.file_version, CURRENT_SCHEMA_VERSION);
+        assert!(report.applied_migrations.is_empty());
+        assert!(report.is_valid(), "{:?}", report.violations);
+    }
+
+    #[test]
+    fn test_violations_carry_json_pointers() {
+        let mut blueprint = serde_json::to_value(SystemBlueprint::new(
+            "orders".to_string(),
+            "Order service".to_string(),
+        ))
+        .unwrap();
+        assert!(validate_value(&blueprint).unwrap().is_empty());
+
+        blueprint["metadata"]["name"] = Value::from(42);
+        blueprint["architecture"]["sytem_type"] = Value::from("typo");
+        blueprint["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("version");
+
+        let violations = validate_value(&blueprint).unwrap();
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert!(pointers.contains(&"/metadata/name"), "{:?}", violations);
+        assert!(pointers.contains(&"/metadata"), "{:?}", violations);
+        assert!(pointers.contains(&"/architecture"), "{:?}", violations);
+        assert!(violations
+            .iter()
+            .any(|v| v.pointer == "/architecture" && v.message.contains("sytem_type")));
+    }
+
+    #[test]
+    fn test_legacy_modules_get_section_defaults() {
+        let mut legacy = serde_json::json!({
+            "modules": [{ "name": "api", "purpose": "HTTP routes", "dependencies": [] }],
+        });
+        MigrationRegistry::default().migrate(&mut legacy).unwrap();
+        assert!(validate_value(&legacy).unwrap().is_empty());
+
+        let blueprint: SystemBlueprint = serde_json::from_value(legacy).unwrap();
+        assert_eq!(blueprint.modules.len(), 1);
+        assert!(blueprint.modules[0].public_interface.is_empty());
+        assert_eq!(
+            blueprint.modules[0]
+                .performance_characteristics
+                .memory_usage,
+            "Unknown"
+        );
+
+        // Element defaults only fill existing elements, they are never added
+        let mut empty = serde_json::json!({});
+        MigrationRegistry::default().migrate(&mut empty).unwrap();
+        assert_eq!(empty["modules"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_migration_registry_versions() {
+        let mut legacy = serde_json::json!({ "metadata": {} });
+        let error = MigrationRegistry::new().migrate(&mut legacy).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("No migration from blueprint schema version 1"));
+
+        let mut future = serde_json::json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1 });
+        let error = MigrationRegistry::default()
+            .migrate(&mut future)
+            .unwrap_err();
+        assert!(error.to_string().contains("newer than version"));
+
+        let mut current = serde_json::json!({ "schema_version": CURRENT_SCHEMA_VERSION });
+        assert!(MigrationRegistry::default()
+            .migrate(&mut current)
+            .unwrap()
+            .is_empty());
+    }
+}
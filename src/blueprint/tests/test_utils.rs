@@ -24,6 +24,7 @@ impl TestUtils {
     /// Create a test blueprint with specified version
     pub fn create_test_blueprint(name: &str, version: &str) -> SystemBlueprint {
         SystemBlueprint {
+            schema_version: crate::blueprint::schema::CURRENT_SCHEMA_VERSION,
             metadata: SystemMetadata {
                 name: name.to_string(),
                 version: version.to_string(),
@@ -42,6 +43,7 @@ impl TestUtils {
             performance: Default::default(),
             security: Default::default(),
             deployment: Default::default(),
+            languages: None,
         }
     }
 
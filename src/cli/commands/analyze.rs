@@ -51,6 +51,9 @@ pub async fn run(
         }
     };
 
+    // Reuse the on-disk index so unchanged files are not re-chunked or re-embedded
+    analysis_config.persist_index = !args.no_index;
+
     // Configure test file inclusion
     if args.include_tests {
         // Remove test exclusions from default patterns
@@ -7,7 +7,7 @@ use crate::blueprint::{
     extractor::BlueprintExtractor,
     generator::BlueprintGenerator,
     replicator::{ReplicationConfig, SystemReplicator},
-    SystemBlueprint,
+    schema, SystemBlueprint,
 };
 use crate::cli::{BlueprintCommands, CliRunner};
 use anyhow::{Context, Result};
@@ -33,9 +33,23 @@ pub async fn run(
             target,
             preserve_git,
             skip_validation,
+            fidelity,
             dry_run,
-        } => replicate_system(cli, target, preserve_git, !skip_validation, dry_run).await,
-        BlueprintCommands::Validate { blueprint } => validate_blueprint(cli, blueprint).await,
+        } => {
+            replicate_system(
+                cli,
+                target,
+                preserve_git,
+                !skip_validation,
+                fidelity,
+                dry_run,
+            )
+            .await
+        }
+        BlueprintCommands::Validate { blueprint, schema } => {
+            validate_blueprint(cli, blueprint, schema).await
+        }
+        BlueprintCommands::Schema { output } => print_schema(cli, output).await,
         BlueprintCommands::Info {
             blueprint,
             detailed,
@@ -94,6 +108,21 @@ async fn extract_blueprint(
     cli.print_success(&format!("Blueprint extracted successfully!"));
     cli.print_output(&format!("  • Source: {:?}", source), None);
     cli.print_output(&format!("  • Output: {:?}", output), None);
+    cli.print_output(
+        &format!("  • Language: {}", blueprint.metadata.primary_language),
+        None,
+    );
+    if let Some(languages) = &blueprint.languages {
+        let secondary: Vec<String> = languages
+            .secondary_languages
+            .iter()
+            .map(|language| format!("{:?}", language))
+            .collect();
+        cli.print_output(
+            &format!("  • Also detected: {}", secondary.join(", ")),
+            None,
+        );
+    }
     cli.print_output(&format!("  • Modules: {}", blueprint.modules.len()), None);
     cli.print_output(
         &format!(
@@ -167,6 +196,7 @@ async fn replicate_system(
     target: PathBuf,
     preserve_git: bool,
     validate_generated: bool,
+    check_fidelity: bool,
     dry_run: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let source = std::env::current_dir().context("Failed to get current directory")?;
@@ -184,6 +214,7 @@ async fn replicate_system(
         target_path: target.clone(),
         preserve_git,
         validate_generated,
+        check_fidelity,
         dry_run,
         include_tests: true,
         include_documentation: true,
@@ -230,6 +261,19 @@ async fn replicate_system(
             );
         }
 
+        if let Some(fidelity) = &result.fidelity {
+            cli.print_output(
+                &format!(
+                    "  • Fidelity: {:.1}% (modules {:.1}%, interfaces {:.1}%, dependencies {:.1}%)",
+                    fidelity.overall_score * 100.0,
+                    fidelity.module_score * 100.0,
+                    fidelity.interface_score * 100.0,
+                    fidelity.dependency_score * 100.0
+                ),
+                None,
+            );
+        }
+
         // Generate detailed report
         replicator
             .generate_report(&result)
@@ -262,9 +306,42 @@ async fn replicate_system(
 async fn validate_blueprint(
     cli: &mut CliRunner,
     blueprint_path: PathBuf,
+    check_schema: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     cli.print_info(&format!("Validating blueprint: {:?}", blueprint_path));
 
+    if check_schema {
+        let report =
+            schema::validate_file(&blueprint_path).context("Failed to check blueprint schema")?;
+
+        if !report.applied_migrations.is_empty() {
+            cli.print_info(&format!(
+                "Blueprint uses schema version {}; checked after upgrading to version {}:",
+                report.file_version,
+                schema::CURRENT_SCHEMA_VERSION
+            ));
+            for migration in &report.applied_migrations {
+                cli.print_output(&format!("  • {}", migration), None);
+            }
+        }
+
+        if !report.is_valid() {
+            cli.print_error(&format!(
+                "Blueprint has {} schema violations:",
+                report.violations.len()
+            ));
+            for violation in &report.violations {
+                cli.print_output(&format!("  • {}", violation), None);
+            }
+            return Err("Blueprint does not match the schema".into());
+        }
+
+        cli.print_success(&format!(
+            "✅ Blueprint matches schema version {}",
+            schema::CURRENT_SCHEMA_VERSION
+        ));
+    }
+
     let blueprint = SystemBlueprint::load_from_file(&blueprint_path)
         .context("Failed to load blueprint file")?;
 
@@ -308,6 +385,25 @@ async fn validate_blueprint(
     Ok(())
 }
 
+/// Print or save the blueprint JSON Schema
+async fn print_schema(
+    cli: &mut CliRunner,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let content = serde_json::to_string_pretty(&schema::blueprint_schema())?;
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, format!("{}\n", content))
+                .with_context(|| format!("Failed to write schema to {:?}", path))?;
+            cli.print_success(&format!("Blueprint schema saved to: {:?}", path));
+        }
+        None => println!("{}", content),
+    }
+
+    Ok(())
+}
+
 /// Show blueprint information
 async fn show_blueprint_info(
     cli: &mut CliRunner,
@@ -0,0 +1,179 @@
+//! Applied changeset commands
+//!
+//! Lists, shows and undoes changesets recorded in the apply journal, and
+//! finishes applies that were interrupted by a crash.
+
+use crate::cli::{ChangesCommands, CliRunner, OutputFormat};
+use crate::codegen::diff_apply::{ChangeType, DiffApplySystem};
+use crate::codegen::journal::{JournalEntry, JournalStatus};
+
+/// Execute changes commands
+pub async fn run(
+    runner: &mut CliRunner,
+    command: ChangesCommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let project_root = std::env::current_dir()?;
+    // Opened without startup recovery, so `list` shows interrupted applies
+    // and `recover` reports the ones it finishes
+    let mut system = DiffApplySystem::new(
+        runner.config_manager().config().quality_gates.clone(),
+        &project_root,
+    )?;
+
+    match command {
+        ChangesCommands::List { format } => handle_list(runner, &system, format),
+        ChangesCommands::Show { id } => handle_show(runner, &system, &id),
+        ChangesCommands::Undo { id } => handle_undo(runner, &mut system, &project_root, &id).await,
+        ChangesCommands::Recover => handle_recover(runner, &mut system, &project_root),
+    }
+}
+
+fn status_label(status: &JournalStatus) -> &'static str {
+    match status {
+        JournalStatus::Applying => "applying",
+        JournalStatus::Applied => "applied",
+        JournalStatus::RolledBack => "rolled back",
+    }
+}
+
+fn handle_list(
+    runner: &mut CliRunner,
+    system: &DiffApplySystem,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = system.journal().entries()?;
+
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        _ => {
+            if entries.is_empty() {
+                runner.print_info("No changesets have been applied in this project");
+                return Ok(());
+            }
+            println!("{:<10} {:<12} {:<17} {:>5}  TITLE", "ID", "STATUS", "APPLIED", "FILES");
+            for entry in &entries {
+                println!(
+                    "{:<10} {:<12} {:<17} {:>5}  {}",
+                    short_id(entry),
+                    status_label(&entry.status),
+                    entry.recorded_at.format("%Y-%m-%d %H:%M"),
+                    entry.files.len(),
+                    entry.title
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn short_id(entry: &JournalEntry) -> &str {
+    entry.changeset_id.get(..8).unwrap_or(&entry.changeset_id)
+}
+
+fn handle_show(
+    runner: &mut CliRunner,
+    system: &DiffApplySystem,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = system.journal().find(id)?;
+
+    println!("Changeset {}", entry.changeset_id);
+    println!("Title:   {}", entry.title);
+    println!("Status:  {}", status_label(&entry.status));
+    println!("Applied: {}", entry.recorded_at.format("%Y-%m-%d %H:%M:%S"));
+    println!("Agent:   {} (task {})", entry.agent_id, entry.task_id);
+    println!();
+
+    let Some(changeset) = system.journaled_changeset(&entry.changeset_id) else {
+        runner.print_warning("The backup of this changeset is missing; only the journal entry is available");
+        for file in &entry.files {
+            println!("  {}", file.path.display());
+        }
+        return Ok(());
+    };
+
+    for file_diff in &changeset.files {
+        let action = match &file_diff.change_type {
+            ChangeType::Create => "create".to_string(),
+            ChangeType::Modify => "modify".to_string(),
+            ChangeType::Delete => "delete".to_string(),
+            ChangeType::Rename { old_path } => format!("rename from {}", old_path.display()),
+        };
+        println!("── {} ({})", file_diff.file_path.display(), action);
+        if !file_diff.diff_text.is_empty() {
+            runner.print_code(&file_diff.diff_text);
+        }
+    }
+    Ok(())
+}
+
+async fn handle_undo(
+    runner: &mut CliRunner,
+    system: &mut DiffApplySystem,
+    project_root: &std::path::Path,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = system.journal().find(id)?;
+    if entry.status == JournalStatus::RolledBack {
+        runner.print_warning(&format!("Changeset {} has already been rolled back", entry.changeset_id));
+        return Ok(());
+    }
+    if entry.status == JournalStatus::Applying {
+        runner.print_warning(&format!(
+            "Changeset {} was interrupted while applying; run `devkit changes recover` first",
+            entry.changeset_id
+        ));
+        return Ok(());
+    }
+
+    let conflicts = system.rollback_conflicts(&entry.changeset_id, project_root)?;
+    if !conflicts.is_empty() {
+        runner.print_error(&format!(
+            "Refusing to undo {}: {} file(s) were edited after it was applied",
+            entry.changeset_id,
+            conflicts.len()
+        ));
+        for conflict in &conflicts {
+            println!("── {}", conflict.path.display());
+            runner.print_code(&conflict.three_way_diff());
+        }
+        return Err("changeset has conflicting edits; revert them or resolve by hand".into());
+    }
+
+    system.rollback_changeset(&entry.changeset_id, project_root).await?;
+    runner.print_success(&format!("Rolled back {} ({})", entry.title, entry.changeset_id));
+    Ok(())
+}
+
+fn handle_recover(
+    runner: &mut CliRunner,
+    system: &mut DiffApplySystem,
+    project_root: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = system.recover(project_root)?;
+
+    for id in &report.replayed {
+        runner.print_success(&format!("Finished applying {}", id));
+    }
+    for id in &report.missing_backups {
+        runner.print_warning(&format!("Cannot finish {}: its backup is missing", id));
+    }
+    for conflict in &report.conflicts {
+        runner.print_error(&format!(
+            "Not finishing {}: these files changed since the apply began",
+            conflict.changeset_id
+        ));
+        for path in &conflict.files {
+            println!("  {}", path.display());
+        }
+    }
+    if report.replayed.is_empty() && report.missing_backups.is_empty() && report.conflicts.is_empty() {
+        runner.print_info("No interrupted applies to recover");
+    }
+    if !report.conflicts.is_empty() {
+        return Err("interrupted changesets conflict with later edits; resolve them by hand".into());
+    }
+    Ok(())
+}
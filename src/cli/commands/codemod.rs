@@ -0,0 +1,207 @@
+//! Codemod commands
+//!
+//! Runs tree-sitter query rules over the project and turns their rewrites
+//! into a changeset that can be reviewed, exported or applied.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ai::AIManager;
+use crate::cli::{CliRunner, CodemodCommands};
+use crate::codegen::codemod::{CodemodEngine, CodemodRule, RewriteSource, RULES_DIR};
+use crate::codegen::diff_apply::ChangeSet;
+use crate::codegen::edit_formats::HunkLine;
+use crate::ui::review::{OverallDecision, ReviewConfig, ReviewSystem};
+
+/// Execute codemod commands
+pub async fn run(
+    runner: &mut CliRunner,
+    command: CodemodCommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let project_root = std::env::current_dir()?;
+
+    match command {
+        CodemodCommands::Run {
+            rule,
+            paths,
+            dry_run,
+            llm_fallback,
+            export,
+            apply,
+            review,
+            force,
+        } => {
+            let rule = CodemodRule::load(&project_root, &rule)?;
+            if dry_run {
+                return handle_dry_run(runner, &project_root, &rule, &paths);
+            }
+
+            let mut engine = CodemodEngine::new(&project_root).with_llm_fallback(llm_fallback);
+            if rule.llm_fallback || llm_fallback {
+                let cfg = runner.config_manager().config().clone();
+                match AIManager::from_config(&cfg).await {
+                    Ok(ai) => engine = engine.with_ai_manager(Arc::new(ai)),
+                    Err(e) => runner.print_warning(&format!("LLM fallback unavailable: {}", e)),
+                }
+            }
+
+            let report = engine.run(&rule, &paths).await?;
+            runner.print_info(&format!(
+                "{}: {} match(es), {} rewritten",
+                rule.name,
+                report.match_count(),
+                report.rewritten_count()
+            ));
+            for outcome in &report.outcomes {
+                let location = format!(
+                    "{}:{}:{}",
+                    outcome.matched.file.display(),
+                    outcome.matched.line,
+                    outcome.matched.column
+                );
+                match &outcome.source {
+                    RewriteSource::Model => runner.print_info(&format!("{} rewritten by the model", location)),
+                    RewriteSource::Unresolved(reason) => {
+                        runner.print_warning(&format!("{} left unchanged: {}", location, reason))
+                    }
+                    RewriteSource::Template => {}
+                }
+            }
+
+            let Some(changeset) = report.changeset else {
+                runner.print_info("Nothing to change");
+                return Ok(());
+            };
+
+            if let Some(export) = export {
+                std::fs::write(&export, serde_json::to_string_pretty(&changeset)?)?;
+                runner.print_success(&format!("Changeset exported to {}", export.display()));
+            } else if apply {
+                handle_apply(runner, &project_root, changeset, force).await?;
+            } else if review {
+                handle_review(runner, &project_root, changeset).await?;
+            } else {
+                print_hunks(runner, &changeset);
+                runner.print_info("Re-run with --apply or --review to apply these changes or --export <file> to review them");
+            }
+            Ok(())
+        }
+        CodemodCommands::List => handle_list(runner, &project_root),
+    }
+}
+
+fn handle_dry_run(
+    runner: &mut CliRunner,
+    project_root: &Path,
+    rule: &CodemodRule,
+    paths: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let matches = CodemodEngine::new(project_root).find_matches(rule, paths)?;
+    for matched in &matches {
+        println!(
+            "{}:{}:{}  {}",
+            matched.file.display(),
+            matched.line,
+            matched.column,
+            matched.text.lines().next().unwrap_or_default().trim()
+        );
+    }
+    let files: BTreeSet<&PathBuf> = matches.iter().map(|matched| &matched.file).collect();
+    runner.print_info(&format!(
+        "{}: {} match(es) in {} file(s)",
+        rule.name,
+        matches.len(),
+        files.len()
+    ));
+    Ok(())
+}
+
+async fn handle_apply(
+    runner: &mut CliRunner,
+    project_root: &Path,
+    mut changeset: ChangeSet,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut system = runner.diff_apply_system(project_root)?;
+    system.validate_changeset(&mut changeset, project_root).await?;
+    if let Some(results) = &changeset.validation_results {
+        for error in &results.errors {
+            runner.print_warning(error);
+        }
+    }
+
+    system.apply_changeset(&changeset, project_root, force).await?;
+    runner.print_success(&format!(
+        "Applied {} ({} file(s)); undo with `devkit changes undo {}`",
+        changeset.title,
+        changeset.files.len(),
+        changeset.id.get(..8).unwrap_or(&changeset.id)
+    ));
+    Ok(())
+}
+
+async fn handle_review(
+    runner: &mut CliRunner,
+    project_root: &Path,
+    mut changeset: ChangeSet,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let review = ReviewSystem::new(ReviewConfig::default())
+        .review_changeset(changeset.clone())
+        .await?;
+    if review.overall_decision == OverallDecision::Cancelled {
+        runner.print_info("Review cancelled; nothing was applied");
+        return Ok(());
+    }
+
+    review.apply_to(&mut changeset)?;
+    if changeset.accepted_subset().files.is_empty() {
+        runner.print_info("No hunks were accepted; nothing was applied");
+        return Ok(());
+    }
+
+    let mut system = runner.diff_apply_system(project_root)?;
+    let applied = system.apply_partial(&changeset, project_root, false).await?;
+    runner.print_success(&format!(
+        "Applied the accepted hunks of {} ({} file(s)); undo with `devkit changes undo {}`",
+        applied.title,
+        applied.files.len(),
+        applied.id.get(..8).unwrap_or(&applied.id)
+    ));
+    Ok(())
+}
+
+fn print_hunks(runner: &CliRunner, changeset: &ChangeSet) {
+    for file in &changeset.files {
+        for (index, hunk) in file.hunks.iter().enumerate() {
+            println!("── {} (hunk {}) {}", file.file_path.display(), index + 1, hunk.header);
+            let lines: Vec<String> = hunk
+                .lines
+                .iter()
+                .map(|line| match line {
+                    HunkLine::Context(text) => format!(" {}", text),
+                    HunkLine::Remove(text) => format!("-{}", text),
+                    HunkLine::Add(text) => format!("+{}", text),
+                })
+                .collect();
+            runner.print_code(&lines.join("\n"));
+        }
+    }
+}
+
+fn handle_list(runner: &mut CliRunner, project_root: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let rules = CodemodRule::discover(project_root)?;
+    if rules.is_empty() {
+        runner.print_info(&format!("No codemod rules in {}", project_root.join(RULES_DIR).display()));
+        return Ok(());
+    }
+
+    println!("{:<28} {:<12} DESCRIPTION", "NAME", "LANGUAGE");
+    for (path, rule) in rules {
+        match rule {
+            Ok(rule) => println!("{:<28} {:<12} {}", rule.name, rule.language, rule.description),
+            Err(e) => runner.print_warning(&format!("{}: {}", path.display(), e)),
+        }
+    }
+    Ok(())
+}
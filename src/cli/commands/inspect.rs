@@ -1,4 +1,7 @@
 use crate::cli::{CliRunner, InspectCommands};
+use crate::context::history::{self, CoChangeSuggestion, Hotspot};
+use crate::context::references::{Definition, ReferenceIndex};
+use crate::context::symbols::ReferenceType;
 use crate::context::RelationshipType;
 use serde_json::json;
 use std::collections::HashMap;
@@ -28,6 +31,21 @@ pub async fn run(
         InspectCommands::Quality { targets, detailed } => {
             inspect_quality(runner, targets, detailed).await?
         }
+        InspectCommands::References { symbol, kind } => {
+            inspect_references(runner, symbol, kind).await?
+        }
+        InspectCommands::Callers { symbol, depth } => {
+            inspect_call_chain(runner, symbol, depth, CallDirection::Callers).await?
+        }
+        InspectCommands::Callees { symbol, depth } => {
+            inspect_call_chain(runner, symbol, depth, CallDirection::Callees).await?
+        }
+        InspectCommands::CallGraph {
+            symbol,
+            depth,
+            export,
+            output,
+        } => inspect_call_graph(runner, symbol, depth, export, output).await?,
     }
 
     Ok(())
@@ -363,6 +381,7 @@ async fn inspect_dependencies(
                         json!({
                             "name": dep.name,
                             "version": dep.version,
+                            "resolved_version": dep.resolved_version,
                             "dependency_type": format!("{:?}", dep.dependency_type),
                             "source": format!("{:?}", dep.source)
                         })
@@ -393,9 +412,23 @@ async fn inspect_dependencies(
                 println!("📦 {} Dependencies ({}):", dep_type, deps.len());
                 for dep in deps {
                     let version_str = dep.version.as_deref().unwrap_or("unknown");
-                    println!("  • {} ({})", dep.name, version_str);
-                    if let crate::context::DependencySource::PackageManager(pm) = &dep.source {
-                        println!("    Source: {}", pm);
+                    match &dep.resolved_version {
+                        Some(resolved) => {
+                            println!("  • {} ({} → {})", dep.name, version_str, resolved)
+                        }
+                        None => println!("  • {} ({})", dep.name, version_str),
+                    }
+                    match &dep.source {
+                        crate::context::DependencySource::PackageManager(pm) => {
+                            println!("    Source: {}", pm)
+                        }
+                        crate::context::DependencySource::Path(path) => {
+                            println!("    Source: {}", path.display())
+                        }
+                        crate::context::DependencySource::Git(url) => {
+                            println!("    Source: {}", url)
+                        }
+                        _ => {}
                     }
                 }
                 println!();
@@ -545,6 +578,14 @@ async fn inspect_quality(
             // Check for potential issues
             if file_ctx.line_count > 1000 {
                 quality_metrics.large_files += 1;
+                // Large files that keep changing are the costliest to maintain
+                if file_ctx
+                    .history
+                    .as_ref()
+                    .is_some_and(|history| history.hotspot_score >= HOTSPOT_THRESHOLD)
+                {
+                    quality_metrics.large_hotspot_files += 1;
+                }
             }
 
             if file_ctx.symbols.is_empty() && file_ctx.line_count > 10 {
@@ -567,6 +608,29 @@ async fn inspect_quality(
             }
         }
 
+        // Change history signals, with paths relative to the target
+        let histories = context
+            .files
+            .iter()
+            .filter_map(|f| Some((f.relative_path.as_path(), f.history.as_ref()?)));
+        let qualify = |path: &std::path::Path| {
+            if targets.len() > 1 {
+                target.join(path)
+            } else {
+                path.to_path_buf()
+            }
+        };
+        for mut hotspot in history::hotspots(histories.clone(), MAX_HOTSPOTS) {
+            hotspot.path = qualify(&hotspot.path);
+            quality_metrics.hotspots.push(hotspot);
+        }
+        let changed = history::uncommitted_changes(target).unwrap_or_default();
+        for mut suggestion in history::suggest_co_changes(histories, &changed) {
+            suggestion.changed = qualify(&suggestion.changed);
+            suggestion.suggested = qualify(&suggestion.suggested);
+            quality_metrics.co_change_suggestions.push(suggestion);
+        }
+
         // Calculate percentages
         if quality_metrics.total_symbols > 0 {
             quality_metrics.documentation_coverage = (quality_metrics.documented_symbols as f64
@@ -580,6 +644,13 @@ async fn inspect_quality(
         }
     }
 
+    quality_metrics.hotspots.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    quality_metrics.hotspots.truncate(MAX_HOTSPOTS);
+
     // Output results
     match runner.format() {
         crate::cli::OutputFormat::Json => {
@@ -597,8 +668,11 @@ async fn inspect_quality(
                         "documented_symbols": quality_metrics.documented_symbols,
                         "documentation_coverage_percent": quality_metrics.documentation_coverage,
                         "test_files": quality_metrics.test_files,
-                        "test_coverage_estimate_percent": quality_metrics.test_coverage_estimate
-                    }
+                        "test_coverage_estimate_percent": quality_metrics.test_coverage_estimate,
+                        "large_hotspot_files": quality_metrics.large_hotspot_files
+                    },
+                    "hotspots": quality_metrics.hotspots,
+                    "co_change_suggestions": quality_metrics.co_change_suggestions
                 }
             });
             println!("{}", serde_json::to_string_pretty(&quality_data)?);
@@ -667,6 +741,45 @@ async fn inspect_quality(
                 runner.print_warning("   Low test coverage estimate (<20%)");
             }
 
+            if !quality_metrics.hotspots.is_empty() {
+                println!();
+                println!("🔥 Hotspots (frequently changed files):");
+                for hotspot in &quality_metrics.hotspots {
+                    println!(
+                        "   {} (score {:.2}, {} commits, last changed {} days ago{})",
+                        hotspot.path.display(),
+                        hotspot.score,
+                        hotspot.commits,
+                        hotspot.age_days,
+                        if hotspot.recent_authors.is_empty() {
+                            String::new()
+                        } else {
+                            format!(", by {}", hotspot.recent_authors.join(", "))
+                        }
+                    );
+                }
+                if quality_metrics.large_hotspot_files > 0 {
+                    runner.print_warning(&format!(
+                        "   Large files that are also hotspots: {}",
+                        quality_metrics.large_hotspot_files
+                    ));
+                }
+            }
+
+            if !quality_metrics.co_change_suggestions.is_empty() {
+                println!();
+                println!("🔗 Files that usually change with your uncommitted changes:");
+                for suggestion in &quality_metrics.co_change_suggestions {
+                    println!(
+                        "   {} (with {} in {} commits, {:.0}%)",
+                        suggestion.suggested.display(),
+                        suggestion.changed.display(),
+                        suggestion.shared_commits,
+                        suggestion.confidence * 100.0
+                    );
+                }
+            }
+
             // Overall quality score
             let mut quality_score = 0.0;
             let mut factors = 0;
@@ -708,6 +821,323 @@ async fn inspect_quality(
     Ok(())
 }
 
+/// Direction to follow the call graph in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CallDirection {
+    Callers,
+    Callees,
+}
+
+/// Analyze the current directory and return its cross-reference index
+async fn load_reference_index(
+    runner: &mut CliRunner,
+) -> Result<(PathBuf, ReferenceIndex), Box<dyn std::error::Error>> {
+    // Ensure context manager is available
+    runner.ensure_context_manager().await?;
+
+    let current_dir = std::env::current_dir().unwrap_or_default();
+
+    let context = if let Some(context_manager) = runner.context_manager_mut() {
+        context_manager
+            .analyze_directory(&current_dir, false)
+            .await?
+    } else {
+        return Err("Context manager not available".into());
+    };
+
+    let references = context
+        .references
+        .ok_or("Reference index not available for this codebase")?;
+    Ok((current_dir, references))
+}
+
+fn find_definitions<'a>(
+    index: &'a ReferenceIndex,
+    symbol: &str,
+) -> Result<Vec<&'a Definition>, Box<dyn std::error::Error>> {
+    let definitions = index.find_definitions(symbol);
+    if definitions.is_empty() {
+        return Err(format!("No definition found for: {}", symbol).into());
+    }
+    Ok(definitions)
+}
+
+fn display_path(path: &std::path::Path, root: &std::path::Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+fn definition_json(definition: &Definition, root: &std::path::Path) -> serde_json::Value {
+    json!({
+        "id": definition.id,
+        "name": definition.name,
+        "qualified_name": definition.qualified_name,
+        "symbol_type": format!("{:?}", definition.symbol_type),
+        "file": display_path(&definition.file_path, root),
+        "line": definition.line
+    })
+}
+
+fn parse_reference_kind(kind: &str) -> Result<ReferenceType, Box<dyn std::error::Error>> {
+    match kind.to_lowercase().as_str() {
+        "call" | "calls" => Ok(ReferenceType::Call),
+        "import" | "imports" => Ok(ReferenceType::Import),
+        "implementation" | "impl" => Ok(ReferenceType::Implementation),
+        "inheritance" | "extends" => Ok(ReferenceType::Inheritance),
+        "usage" | "usages" => Ok(ReferenceType::Usage),
+        other => Err(format!("Unknown reference kind: {}", other).into()),
+    }
+}
+
+async fn inspect_references(
+    runner: &mut CliRunner,
+    symbol: String,
+    kind: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    runner.print_info(&format!("Finding references to: {}", symbol));
+
+    let kind = kind.as_deref().map(parse_reference_kind).transpose()?;
+    let (root, index) = load_reference_index(runner).await?;
+    let definitions = find_definitions(&index, &symbol)?;
+
+    let results: Vec<_> = definitions
+        .iter()
+        .map(|definition| {
+            let references: Vec<_> = index
+                .references_to(&definition.id)
+                .into_iter()
+                .filter(|r| match &kind {
+                    Some(kind) => &r.reference.reference_type == kind,
+                    None => true,
+                })
+                .collect();
+            (*definition, references)
+        })
+        .collect();
+
+    match runner.format() {
+        crate::cli::OutputFormat::Json => {
+            let references_data = json!({
+                "reference_inspection": {
+                    "symbol": symbol,
+                    "definitions": results.iter().map(|(definition, references)| {
+                        json!({
+                            "definition": definition_json(definition, &root),
+                            "references_count": references.len(),
+                            "references": references.iter().map(|r| {
+                                json!({
+                                    "file": display_path(&r.reference.file_path, &root),
+                                    "line": r.reference.line_number,
+                                    "column": r.reference.column,
+                                    "kind": format!("{:?}", r.reference.reference_type),
+                                    "caller": r.caller.as_ref()
+                                        .and_then(|caller| index.get_definition(caller))
+                                        .map(|caller| caller.qualified_name.clone())
+                                })
+                            }).collect::<Vec<_>>()
+                        })
+                    }).collect::<Vec<_>>()
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&references_data)?);
+        }
+        _ => {
+            let total: usize = results.iter().map(|(_, references)| references.len()).sum();
+            runner.print_success(&format!(
+                "Found {} references across {} definitions",
+                total,
+                results.len()
+            ));
+            println!();
+
+            for (definition, references) in results {
+                println!(
+                    "🔍 {} ({:?}) at {}:{}",
+                    definition.qualified_name,
+                    definition.symbol_type,
+                    display_path(&definition.file_path, &root),
+                    definition.line
+                );
+                for reference in references {
+                    let caller = reference
+                        .caller
+                        .as_ref()
+                        .and_then(|caller| index.get_definition(caller))
+                        .map(|caller| format!(" in {}", caller.name))
+                        .unwrap_or_default();
+                    println!(
+                        "  • {:?} {}:{}{}",
+                        reference.reference.reference_type,
+                        display_path(&reference.reference.file_path, &root),
+                        reference.reference.line_number,
+                        caller
+                    );
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn inspect_call_chain(
+    runner: &mut CliRunner,
+    symbol: String,
+    depth: usize,
+    direction: CallDirection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let label = match direction {
+        CallDirection::Callers => "callers",
+        CallDirection::Callees => "callees",
+    };
+    runner.print_info(&format!("Finding {} of: {}", label, symbol));
+
+    let depth = depth.max(1);
+    let (root, index) = load_reference_index(runner).await?;
+    let definitions = find_definitions(&index, &symbol)?;
+
+    let results: Vec<_> = definitions
+        .iter()
+        .map(|definition| {
+            let chain = match direction {
+                CallDirection::Callers => index.blast_radius(&definition.id, depth),
+                CallDirection::Callees => index.transitive_callees(&definition.id, depth),
+            };
+            (*definition, chain)
+        })
+        .collect();
+
+    match runner.format() {
+        crate::cli::OutputFormat::Json => {
+            let chain_data = json!({
+                "call_inspection": {
+                    "symbol": symbol,
+                    "direction": label,
+                    "depth": depth,
+                    "definitions": results.iter().map(|(definition, chain)| {
+                        let mut files: Vec<String> = chain
+                            .iter()
+                            .map(|(d, _)| display_path(&d.file_path, &root))
+                            .collect();
+                        files.sort();
+                        files.dedup();
+                        json!({
+                            "definition": definition_json(definition, &root),
+                            label: chain.iter().map(|(d, level)| {
+                                let mut entry = definition_json(d, &root);
+                                entry["depth"] = json!(level);
+                                entry
+                            }).collect::<Vec<_>>(),
+                            "affected_files": files
+                        })
+                    }).collect::<Vec<_>>()
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&chain_data)?);
+        }
+        _ => {
+            for (definition, chain) in results {
+                println!(
+                    "🔍 {} at {}:{}",
+                    definition.qualified_name,
+                    display_path(&definition.file_path, &root),
+                    definition.line
+                );
+
+                if chain.is_empty() {
+                    println!("  No {} found", label);
+                    println!();
+                    continue;
+                }
+
+                for (d, level) in &chain {
+                    println!(
+                        "  {}• {} ({}:{})",
+                        "  ".repeat(level - 1),
+                        d.qualified_name,
+                        display_path(&d.file_path, &root),
+                        d.line
+                    );
+                }
+
+                if direction == CallDirection::Callers {
+                    let files: std::collections::HashSet<_> =
+                        chain.iter().map(|(d, _)| &d.file_path).collect();
+                    println!(
+                        "  Blast radius: {} functions in {} files",
+                        chain.len(),
+                        files.len()
+                    );
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn inspect_call_graph(
+    runner: &mut CliRunner,
+    symbol: Option<String>,
+    depth: usize,
+    export: String,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, index) = load_reference_index(runner).await?;
+
+    let root_id = match &symbol {
+        Some(symbol) => {
+            let definitions = find_definitions(&index, symbol)?;
+            if definitions.len() > 1 {
+                runner.print_warning(&format!(
+                    "{} definitions match {}; using {}",
+                    definitions.len(),
+                    symbol,
+                    definitions[0].qualified_name
+                ));
+            }
+            Some(definitions[0].id.clone())
+        }
+        None => None,
+    };
+
+    let graph = index.call_graph(root_id.as_deref(), depth);
+    let rendered = match export.to_lowercase().as_str() {
+        "dot" => graph.to_dot(),
+        "json" => serde_json::to_string_pretty(&graph)?,
+        other => {
+            return Err(
+                format!("Unsupported call graph format: {} (use dot or json)", other).into(),
+            )
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            runner.print_success(&format!(
+                "Call graph with {} functions and {} edges written to {}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path.display()
+            ));
+        }
+        None => println!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+/// Hotspots listed by `inspect quality`
+const MAX_HOTSPOTS: usize = 10;
+
+/// Hotspot score above which a file counts as a hotspot
+const HOTSPOT_THRESHOLD: f32 = 0.5;
+
 #[derive(Debug, Default)]
 struct QualityMetrics {
     total_files: usize,
@@ -720,6 +1150,9 @@ struct QualityMetrics {
     documentation_coverage: f64,
     test_files: usize,
     test_coverage_estimate: f64,
+    large_hotspot_files: usize,
+    hotspots: Vec<Hotspot>,
+    co_change_suggestions: Vec<CoChangeSuggestion>,
 }
 
 impl QualityMetrics {
@@ -1,5 +1,9 @@
 use crate::agents::AgentSystem;
 use crate::cli::{session_manager::SessionManager, CliRunner, InteractiveArgs};
+use crate::config::rules::RulesManager;
+use crate::context::packer::{PackRequest, DEFAULT_TOKEN_BUDGET};
+use crate::context::watcher::{ContextWatcher, WatcherConfig};
+use crate::context::ContextUpdateSummary;
 use crate::interactive::{ConversationEntry, ConversationRole, EntryType, InteractiveSession};
 use crate::ui::notifications::Notification;
 use crate::ui::{Application, UIConfig, UIEvent};
@@ -20,43 +24,52 @@ pub async fn run(
     // Get project path 
     let project_path = std::env::current_dir().ok();
     
+    // Create analysis configuration for interactive mode
+    let analysis_config = crate::context::AnalysisConfig {
+        include_patterns: vec![
+            "*.rs".to_string(),
+            "*.py".to_string(), 
+            "*.js".to_string(),
+            "*.ts".to_string(),
+            "*.go".to_string(),
+            "*.java".to_string(),
+            "*.cpp".to_string(),
+            "*.c".to_string(),
+            "*.h".to_string(),
+            "*.md".to_string(),
+            "*.toml".to_string(),
+            "*.json".to_string(),
+            "*.yaml".to_string(),
+            "*.yml".to_string(),
+        ],
+        exclude_patterns: vec![
+            "target/**".to_string(),
+            "node_modules/**".to_string(),
+            ".git/**".to_string(),
+            ".devkit/**".to_string(),
+            "*.log".to_string(),
+            "*.tmp".to_string(),
+        ],
+        max_file_size_mb: 10,
+        follow_symlinks: false,
+        respect_gitignore: true,
+        max_concurrency: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4),
+        analyze_dependencies: true,
+        deep_analysis: false, // Disable for faster startup
+        generate_embeddings: false, // Disable for faster startup
+        build_lexical_index: true,
+        resolve_references: true,
+        analyze_history: true,
+        persist_index: true,
+        cache_results: true,
+    };
+    
     // Analyze context if possible
     let codebase_context = match (&project_path, runner.context_manager_mut()) {
         (Some(path), Some(context_manager)) => {
-            // Create analysis configuration for interactive mode
-            let config = crate::context::AnalysisConfig {
-                include_patterns: vec![
-                    "*.rs".to_string(),
-                    "*.py".to_string(), 
-                    "*.js".to_string(),
-                    "*.ts".to_string(),
-                    "*.go".to_string(),
-                    "*.java".to_string(),
-                    "*.cpp".to_string(),
-                    "*.c".to_string(),
-                    "*.h".to_string(),
-                    "*.md".to_string(),
-                    "*.toml".to_string(),
-                    "*.json".to_string(),
-                    "*.yaml".to_string(),
-                    "*.yml".to_string(),
-                ],
-                exclude_patterns: vec![
-                    "target/**".to_string(),
-                    "node_modules/**".to_string(),
-                    ".git/**".to_string(),
-                    "*.log".to_string(),
-                    "*.tmp".to_string(),
-                ],
-                max_file_size_mb: 10,
-                follow_symlinks: false,
-                analyze_dependencies: true,
-                deep_analysis: false, // Disable for faster startup
-                generate_embeddings: false, // Disable for faster startup
-                cache_results: true,
-            };
-            
-            match context_manager.analyze_codebase(path.clone(), config).await {
+            match context_manager.analyze_codebase(path.clone(), analysis_config.clone()).await {
                 Ok(context) => {
                     Some(context)
                 },
@@ -171,7 +184,35 @@ pub async fn run(
     app.add_notification(welcome_notification);
 
     // Take ownership of context manager from runner to pass to interactive manager
-    let context_manager = runner.context_manager.take();
+    let context_manager = runner.context_manager.take().map(|manager| Arc::new(RwLock::new(manager)));
+    let codebase_context = codebase_context.map(|context| Arc::new(RwLock::new(context)));
+
+    // Keep the context current while files are edited outside devkit
+    let context_watcher = match (&context_manager, &codebase_context) {
+        (Some(manager), Some(context)) if !args.no_watch => {
+            let mut watcher = ContextWatcher::new(
+                manager.clone(),
+                context.clone(),
+                analysis_config,
+                WatcherConfig::default(),
+            );
+            match watcher.start().await {
+                Ok(()) => {
+                    spawn_context_update_forwarder(
+                        watcher.subscribe(),
+                        ui_tx.clone(),
+                        web_event_broadcaster.clone(),
+                    );
+                    Some(watcher)
+                }
+                Err(e) => {
+                    runner.print_warning(&format!("File watching disabled: {}", e));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
     
     // Create interactive manager to handle the session
     let interactive_manager = InteractiveManager::new(
@@ -206,6 +247,9 @@ pub async fn run(
     // Cleanup background tasks
     agent_monitor.abort();
     command_processor.abort();
+    if let Some(mut watcher) = context_watcher {
+        watcher.stop();
+    }
     
     // Cleanup web server if it was started
     if let Some(web_handle) = web_server_handle {
@@ -224,12 +268,32 @@ pub async fn run(
         }
     }
 }
+/// Files of the codebase mentioned by path or file name in a command
+fn mentioned_files(command: &str, context: &crate::context::CodebaseContext) -> Vec<std::path::PathBuf> {
+    let words: Vec<&str> = command
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c: char| matches!(c, '`' | '\'' | '"' | ',' | ':' | '(' | ')')))
+        .filter(|word| word.contains('.') || word.contains('/'))
+        .collect();
+
+    context
+        .files
+        .iter()
+        .filter(|file| {
+            let relative = file.relative_path.to_string_lossy();
+            let name = file.relative_path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            words.iter().any(|word| *word == relative || *word == name)
+        })
+        .map(|file| file.path.clone())
+        .collect()
+}
+
 /// Interactive manager to handle session state and agent communication
 struct InteractiveManager {
     session: Arc<RwLock<InteractiveSession>>,
     agent_system: Arc<AgentSystem>,
-    context_manager: Option<crate::context::ContextManager>,
-    codebase_context: Option<crate::context::CodebaseContext>,
+    context_manager: Option<Arc<RwLock<crate::context::ContextManager>>>,
+    codebase_context: Option<Arc<RwLock<crate::context::CodebaseContext>>>,
     ui_sender: mpsc::UnboundedSender<UIEvent>,
     command_sender: mpsc::UnboundedSender<String>,
     session_manager: Arc<RwLock<SessionManager>>,
@@ -241,11 +305,11 @@ impl InteractiveManager {
     fn new(
         session: InteractiveSession,
         agent_system: Arc<AgentSystem>,
-        context_manager: Option<crate::context::ContextManager>,
+        context_manager: Option<Arc<RwLock<crate::context::ContextManager>>>,
         ui_sender: mpsc::UnboundedSender<UIEvent>,
         command_sender: mpsc::UnboundedSender<String>,
         web_event_sender: Option<broadcast::Sender<UIEvent>>,
-        codebase_context: Option<crate::context::CodebaseContext>,
+        codebase_context: Option<Arc<RwLock<crate::context::CodebaseContext>>>,
     ) -> Arc<Self> {
         let mut session_manager = SessionManager::new();
         let session_id = session_manager.create_session(session.project_path.clone());
@@ -660,7 +724,8 @@ impl InteractiveManager {
         });
         
         // Add codebase context if available
-        if let Some(ref codebase_ctx) = self.codebase_context {
+        if let Some(codebase_ctx) = &self.codebase_context {
+            let codebase_ctx = codebase_ctx.read().await;
             context["codebase"] = serde_json::json!({
                 "root_path": codebase_ctx.root_path,
                 "total_files": codebase_ctx.metadata.total_files,
@@ -686,6 +751,36 @@ impl InteractiveManager {
                     "total_commits": repo.commit_count
                 }))
             });
+
+            // Pack the code most relevant to the command within the prompt budget
+            if let Some(context_manager) = &self.context_manager {
+                let mut rules = RulesManager::new();
+                let rules = match rules.load_rules_hierarchy(&codebase_ctx.root_path).await {
+                    Ok(()) => Some(rules),
+                    Err(e) => {
+                        tracing::debug!("Failed to load project rules: {}", e);
+                        None
+                    }
+                };
+                let request = PackRequest::new(command, DEFAULT_TOKEN_BUDGET)
+                    .with_target_files(mentioned_files(command, &codebase_ctx))
+                    .with_task_type(task_type);
+
+                match context_manager
+                    .read()
+                    .await
+                    .pack_context(&request, &codebase_ctx, rules.as_ref())
+                    .await
+                {
+                    Ok(packed) if !packed.is_empty() => {
+                        context["packed_context"] = serde_json::json!(packed.render());
+                        context["context_manifest"] =
+                            serde_json::to_value(&packed.manifest).unwrap_or_default();
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::debug!("Context packing failed: {}", e),
+                }
+            }
         }
         
         // Create agent task
@@ -1064,7 +1159,8 @@ impl InteractiveManager {
 
     /// Show codebase context information
     async fn show_codebase_context(&self) -> String {
-        if let Some(ref context) = self.codebase_context {
+        if let Some(context) = &self.codebase_context {
+            let context = context.read().await;
             let mut output = String::from("🏗️  Codebase Context Information\n\n");
             
             // Basic stats
@@ -1639,3 +1735,41 @@ fn spawn_command_processor(
         tracing::debug!("Command processor ended - channel closed");
     })
 }
+
+/// Spawn task that surfaces live context updates in the UI and web dashboard
+fn spawn_context_update_forwarder(
+    mut updates: broadcast::Receiver<ContextUpdateSummary>,
+    ui_sender: mpsc::UnboundedSender<UIEvent>,
+    web_event_sender: Option<broadcast::Sender<UIEvent>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let summary = match updates.recv().await {
+                Ok(summary) => summary,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            let files: Vec<String> = summary
+                .changes()
+                .take(3)
+                .map(|(path, change)| {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    format!("{} ({})", name, change)
+                })
+                .collect();
+            let more = summary.len().saturating_sub(files.len());
+            let message = if more > 0 {
+                format!("{} and {} more", files.join(", "), more)
+            } else {
+                files.join(", ")
+            };
+
+            let event = UIEvent::Notification(Notification::info("Context updated".to_string(), message));
+            let _ = ui_sender.send(event.clone());
+            if let Some(web_sender) = &web_event_sender {
+                let _ = web_sender.send(event);
+            }
+        }
+    })
+}
@@ -0,0 +1,36 @@
+//! Language server command implementation
+
+use std::sync::Arc;
+
+use crate::ai::AIManager;
+use crate::cli::{CliRunner, LspArgs};
+use crate::context::ContextManager;
+
+/// Run the devkit language server over stdio until the editor disconnects
+pub async fn run(runner: &mut CliRunner, args: LspArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = runner.config_manager().config().clone();
+
+    // Nothing may be printed to stdout from here on: it carries the protocol
+    let mut manager = ContextManager::new()?;
+    manager.set_embedding_batch_size(config.codegen.ai_model_settings.embeddings.batch_size);
+    match crate::context::embeddings::create_embedding_provider(&config.codegen.ai_model_settings) {
+        Ok(Some(provider)) => manager.set_embedding_provider(provider),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Embeddings disabled: {}", e),
+    }
+
+    let ai_manager = if args.no_ai {
+        None
+    } else {
+        match AIManager::from_config(&config).await {
+            Ok(ai_manager) => Some(Arc::new(ai_manager)),
+            Err(e) => {
+                tracing::warn!("AI features disabled in the language server: {}", e);
+                None
+            }
+        }
+    };
+
+    crate::lsp::serve_stdio(manager, ai_manager).await;
+    Ok(())
+}
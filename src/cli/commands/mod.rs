@@ -7,7 +7,9 @@
 pub mod agent;
 pub mod analyze;
 pub mod blueprint;
+pub mod changes;
 pub mod chat;
+pub mod codemod;
 pub mod config;
 pub mod demo;
 pub mod evolution;
@@ -15,6 +17,7 @@ pub mod generate;
 pub mod init;
 pub mod inspect;
 pub mod interactive;
+pub mod lsp;
 pub mod profile;
 pub mod review;
 pub mod shell;
@@ -192,7 +192,7 @@ fn build_review_config(args: &ReviewArgs) -> Result<ReviewConfig, Box<dyn std::e
 
 /// Run a single code review
 async fn run_single_review(
-    _runner: &mut CliRunner,
+    runner: &mut CliRunner,
     paths: &[PathBuf],
     config: &ReviewConfig,
     args: &ReviewArgs,
@@ -205,54 +205,26 @@ async fn run_single_review(
         );
     }
 
-    // For now, create a mock result since the CLI integration needs more work
-    // TODO: Integrate with actual agent system once CLI runner is properly set up
-
-    let mock_result = crate::agents::review::ReviewResult {
-        issues: vec![crate::agents::review::ReviewIssue {
-            category: crate::agents::review::ReviewCategory::CodeSmell,
-            severity: crate::agents::review::ReviewSeverity::Medium,
-            title: "Example code smell detected".to_string(),
-            description: "This is a demonstration of the code review functionality.".to_string(),
-            file_path: paths
-                .get(0)
-                .unwrap_or(&std::path::PathBuf::from("."))
-                .clone(),
-            line_start: Some(1),
-            line_end: Some(1),
-            suggestion: Some("Consider refactoring for better maintainability.".to_string()),
-            auto_fixable: false,
-            code_snippet: None,
-        }],
-        summary: crate::agents::review::ReviewSummary {
-            total_issues: 1,
-            issues_by_severity: std::collections::HashMap::from([("Medium".to_string(), 1)]),
-            issues_by_category: std::collections::HashMap::from([("CodeSmell".to_string(), 1)]),
-            auto_fixable_issues: 0,
-            most_common_issue: Some("CodeSmell".to_string()),
-        },
-        files_reviewed: paths.len(),
-        total_lines: 100, // Mock value
-        review_duration: std::time::Duration::from_secs(1),
-    };
+    let mut agent = runner.review_agent()?;
+    let result = agent.review_code(paths, config.clone()).await?;
 
     if args.verbose {
         println!(
             "Review completed in {:.2}s",
-            mock_result.review_duration.as_secs_f64()
+            result.review_duration.as_secs_f64()
         );
     }
 
-    display_review_results(&mock_result, args)?;
+    display_review_results(&result, args)?;
 
     // Save to file if requested
     if let Some(output_path) = &args.save_to {
-        save_review_results(&mock_result, output_path, &args.output)?;
+        save_review_results(&result, output_path, &args.output)?;
         println!("✅ Review results saved to: {}", output_path.display());
     }
 
     // Show summary
-    display_review_summary(&mock_result);
+    display_review_summary(&result);
 
     Ok(())
 }
@@ -14,7 +14,8 @@ use crossterm::{
 };
 use is_terminal::IsTerminal;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 pub mod commands;
 pub mod completion;
@@ -25,10 +26,33 @@ pub mod progress;
 pub mod session_manager;
 pub mod validation;
 
+use crate::agents::review::CodeReviewAgent;
 use crate::agents::AgentSystem;
 use crate::cli::session_manager::SessionManager;
+use crate::codegen::diff_apply::{DiffApplyError, DiffApplySystem, QualityGateConfig};
 use crate::config::ConfigManager;
 use crate::context::ContextManager;
+use crate::lsp::LanguageServerPool;
+
+fn build_review_agent(language_servers: Option<Arc<LanguageServerPool>>) -> CodeReviewAgent {
+    let agent = CodeReviewAgent::new();
+    match language_servers {
+        Some(language_servers) => agent.with_language_servers(language_servers),
+        None => agent,
+    }
+}
+
+fn build_diff_apply_system(
+    config: QualityGateConfig,
+    project_root: &Path,
+    language_servers: Option<Arc<LanguageServerPool>>,
+) -> Result<DiffApplySystem, DiffApplyError> {
+    let system = DiffApplySystem::start(config, project_root)?;
+    Ok(match language_servers {
+        Some(language_servers) => system.with_language_servers(language_servers),
+        None => system,
+    })
+}
 
 /// Simple color support detection
 fn supports_color() -> bool {
@@ -208,6 +232,15 @@ pub enum Commands {
 
     /// Run project diagnostics
     Diagnose(DiagnoseArgs),
+
+    /// Run a Language Server Protocol server over stdio
+    Lsp(LspArgs),
+
+    /// Applied changesets: list, show and undo
+    Changes(ChangesArgs),
+
+    /// Run tree-sitter query codemods
+    Codemod(CodemodArgs),
 }
 
 /// Project initialization arguments
@@ -263,6 +296,10 @@ pub struct InteractiveArgs {
     /// Web dashboard host (default: 127.0.0.1)
     #[arg(long)]
     pub web_host: Option<String>,
+
+    /// Don't watch the project for file changes
+    #[arg(long)]
+    pub no_watch: bool,
 }
 
 /// Code analysis arguments
@@ -290,6 +327,10 @@ pub struct AnalyzeArgs {
     /// Show progress
     #[arg(short, long)]
     pub progress: bool,
+
+    /// Don't read or update the persistent code index in .devkit/index
+    #[arg(long)]
+    pub no_index: bool,
 }
 
 /// Code generation arguments
@@ -342,10 +383,22 @@ pub struct GenerateArgs {
     #[arg(long)]
     pub root: Option<PathBuf>,
 
-    /// Stack preset (e.g. rust-axum, rust-actix, rust-axum-sqlx, node-express, node-nest, nextjs, python-fastapi, python-fastapi-sqlalchemy)
+    /// Stack to scaffold: a built-in (e.g. rust-axum, node-express, python-fastapi), one from ~/.devkit/stacks or .devkit/stacks, a stack directory or a git URL (url#ref)
     #[arg(long)]
     pub stack: Option<String>,
 
+    /// Stack variable as KEY=VALUE (repeatable)
+    #[arg(long = "var", value_name = "KEY=VALUE")]
+    pub vars: Vec<String>,
+
+    /// Use default values for stack variables instead of prompting
+    #[arg(long)]
+    pub defaults: bool,
+
+    /// Skip the stack's post-generate commands
+    #[arg(long)]
+    pub no_post_generate: bool,
+
     /// Dry run scaffolding (print plan, do not write)
     #[arg(long)]
     pub dry_run: bool,
@@ -365,6 +418,18 @@ pub struct GenerateArgs {
     /// Apply a previously exported plan JSON instead of generating
     #[arg(long)]
     pub apply_plan: Option<PathBuf>,
+
+    /// Build and test the generated file in a staged copy of the project, repairing it until it passes
+    #[arg(long)]
+    pub until_green: bool,
+
+    /// Maximum repair rounds with --until-green or --from-tests
+    #[arg(long, default_value_t = 3)]
+    pub max_repairs: usize,
+
+    /// Implement the code under test until these tests pass (test file or glob, repeatable); the tests are never modified
+    #[arg(long = "from-tests", value_name = "TESTS")]
+    pub from_tests: Vec<String>,
 }
 
 /// Agent management arguments
@@ -588,6 +653,45 @@ pub enum InspectCommands {
         #[arg(short, long)]
         detailed: bool,
     },
+    /// Find references to a symbol
+    References {
+        /// Symbol name or qualified suffix (e.g. ModelRouter::select_model)
+        symbol: String,
+        /// Only show references of this kind (call, import, implementation, inheritance, usage)
+        #[arg(short, long)]
+        kind: Option<String>,
+    },
+    /// Show functions that call a symbol
+    Callers {
+        /// Symbol name or qualified suffix
+        symbol: String,
+        /// Follow callers transitively up to this depth (the blast radius of a change)
+        #[arg(short, long, default_value = "1")]
+        depth: usize,
+    },
+    /// Show functions called by a symbol
+    Callees {
+        /// Symbol name or qualified suffix
+        symbol: String,
+        /// Follow callees transitively up to this depth
+        #[arg(short, long, default_value = "1")]
+        depth: usize,
+    },
+    /// Export the call graph
+    CallGraph {
+        /// Limit the graph to the neighbourhood of this symbol
+        #[arg(short, long)]
+        symbol: Option<String>,
+        /// Neighbourhood depth when a symbol is given
+        #[arg(short, long, default_value = "2")]
+        depth: usize,
+        /// Export format (dot, json)
+        #[arg(short, long, default_value = "dot")]
+        export: String,
+        /// Write the graph to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 /// Profiling and diagnostics arguments
@@ -815,6 +919,9 @@ pub enum BlueprintCommands {
         /// Skip validation of generated code
         #[arg(long)]
         skip_validation: bool,
+        /// Score the replica against the source blueprint, building and testing it in a sandbox
+        #[arg(long)]
+        fidelity: bool,
         /// Dry run (show what would be done)
         #[arg(long)]
         dry_run: bool,
@@ -823,6 +930,15 @@ pub enum BlueprintCommands {
     Validate {
         /// Blueprint file path
         blueprint: PathBuf,
+        /// Check the file against the blueprint JSON Schema and report every violation
+        #[arg(long)]
+        schema: bool,
+    },
+    /// Print the JSON Schema for blueprint files
+    Schema {
+        /// Write the schema to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
     /// Show blueprint information
     Info {
@@ -1099,6 +1215,91 @@ pub struct DiagnoseArgs {
     pub verbose: bool,
 }
 
+/// Language server arguments
+#[derive(Args)]
+pub struct LspArgs {
+    /// Communicate over stdin/stdout (the only transport; accepted for editor compatibility)
+    #[arg(long)]
+    pub stdio: bool,
+    /// Disable AI-backed review and refactor code actions
+    #[arg(long)]
+    pub no_ai: bool,
+}
+
+/// Applied changeset arguments
+#[derive(Args)]
+pub struct ChangesArgs {
+    #[command(subcommand)]
+    pub command: ChangesCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ChangesCommands {
+    /// List journaled changesets, most recent first
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Show the files and diffs of a changeset
+    Show {
+        /// Changeset id or a unique prefix of it
+        id: String,
+    },
+    /// Roll back an applied changeset
+    Undo {
+        /// Changeset id or a unique prefix of it
+        id: String,
+    },
+    /// Finish applies interrupted by a crash, where files are unchanged since
+    Recover,
+}
+
+/// Codemod arguments
+#[derive(Args)]
+pub struct CodemodArgs {
+    #[command(subcommand)]
+    pub command: CodemodCommands,
+}
+
+#[derive(Subcommand)]
+pub enum CodemodCommands {
+    /// Rewrite every match of a rule into a reviewable changeset
+    Run {
+        /// Rule name under .devkit/codemods, or a path to a rule file
+        rule: String,
+
+        /// Files or directories to search, relative to the project; defaults to the whole project
+        paths: Vec<PathBuf>,
+
+        /// Only count and list the matches
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Ask the model for matches the template cannot rewrite
+        #[arg(long)]
+        llm_fallback: bool,
+
+        /// Write the changeset as JSON for review instead of printing it
+        #[arg(long)]
+        export: Option<PathBuf>,
+
+        /// Validate and apply the changeset
+        #[arg(long)]
+        apply: bool,
+
+        /// Review the hunks in the terminal and apply the accepted ones
+        #[arg(long, conflicts_with_all = ["apply", "export"])]
+        review: bool,
+
+        /// Apply even if the quality gates fail
+        #[arg(long, requires = "apply")]
+        force: bool,
+    },
+    /// List the rules under .devkit/codemods
+    List,
+}
+
 #[derive(Subcommand)]
 pub enum ShellCommands {
     /// Generate shell completion scripts
@@ -1167,6 +1368,8 @@ pub struct CliRunner {
     config_manager: ConfigManager,
     pub(crate) context_manager: Option<ContextManager>,
     agent_system: Option<AgentSystem>,
+    /// Language servers shared by the context manager, review and quality gates
+    language_servers: Option<Arc<LanguageServerPool>>,
     session_manager: SessionManager,
     verbose: bool,
     quiet: bool,
@@ -1200,6 +1403,7 @@ impl CliRunner {
             config_manager,
             context_manager: None,
             agent_system: None,
+            language_servers: None,
             session_manager: SessionManager::new(),
             verbose: cli.verbose,
             quiet: cli.quiet,
@@ -1269,6 +1473,9 @@ impl CliRunner {
             Commands::Export(args) => self.run_export(args).await,
             Commands::Behavior(args) => self.run_behavior(args.command).await,
             Commands::Diagnose(args) => self.run_diagnose(args).await,
+            Commands::Lsp(args) => self.run_lsp(args).await,
+            Commands::Changes(args) => self.run_changes(args.command).await,
+            Commands::Codemod(args) => self.run_codemod(args.command).await,
         }
     }
 
@@ -1356,14 +1563,64 @@ impl CliRunner {
     /// Initialize context manager if not already done
     pub async fn ensure_context_manager(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if self.context_manager.is_none() {
-            let _config = self.config_manager.config();
+            let config = self.config_manager.config();
+            let provider = crate::context::embeddings::create_embedding_provider(
+                &config.codegen.ai_model_settings,
+            );
+
+            let mut context_manager = ContextManager::new()?;
+            context_manager
+                .set_embedding_batch_size(config.codegen.ai_model_settings.embeddings.batch_size);
+            match provider {
+                Ok(Some(provider)) => context_manager.set_embedding_provider(provider),
+                Ok(None) => {}
+                Err(e) => self.print_warning(&format!("Embeddings disabled: {}", e)),
+            }
 
-            self.context_manager = Some(ContextManager::new()?);
+            if let Some(language_servers) = self.language_servers()? {
+                context_manager.set_language_servers(language_servers);
+            }
+
+            self.context_manager = Some(context_manager);
             self.print_verbose("Context manager initialized");
         }
         Ok(())
     }
 
+    /// Configured language servers, or `None` when they are disabled
+    ///
+    /// Servers start on first use, so creating the pool costs nothing up front.
+    pub fn language_servers(&mut self) -> Result<Option<Arc<LanguageServerPool>>, Box<dyn std::error::Error>> {
+        let config = self.config_manager.config();
+        if self.language_servers.is_none() && config.language_servers.enabled {
+            let root = match &config.general.workspace_path {
+                Some(path) => path.clone(),
+                None => std::env::current_dir()?,
+            };
+            self.language_servers = Some(Arc::new(LanguageServerPool::new(
+                root,
+                config.language_servers.clone(),
+            )));
+        }
+        Ok(self.language_servers.clone())
+    }
+
+    /// Code review agent reporting language server diagnostics alongside its own analyses
+    pub fn review_agent(&mut self) -> Result<CodeReviewAgent, Box<dyn std::error::Error>> {
+        Ok(build_review_agent(self.language_servers()?))
+    }
+
+    /// Diff apply system with the configured quality gates, whose compile
+    /// gate asks the language servers first
+    pub fn diff_apply_system(&mut self, project_root: &Path) -> Result<DiffApplySystem, Box<dyn std::error::Error>> {
+        let language_servers = self.language_servers()?;
+        Ok(build_diff_apply_system(
+            self.config_manager().config().quality_gates.clone(),
+            project_root,
+            language_servers,
+        )?)
+    }
+
     /// Get mutable reference to context manager
     pub fn context_manager_mut(&mut self) -> Option<&mut ContextManager> {
         self.context_manager.as_mut()
@@ -1584,6 +1841,18 @@ impl CliRunner {
         commands::behavior::run(self, command).await
     }
 
+    async fn run_lsp(&mut self, args: LspArgs) -> Result<(), Box<dyn std::error::Error>> {
+        commands::lsp::run(self, args).await
+    }
+
+    async fn run_changes(&mut self, command: ChangesCommands) -> Result<(), Box<dyn std::error::Error>> {
+        commands::changes::run(self, command).await
+    }
+
+    async fn run_codemod(&mut self, command: CodemodCommands) -> Result<(), Box<dyn std::error::Error>> {
+        commands::codemod::run(self, command).await
+    }
+
     async fn run_diagnose(&mut self, args: DiagnoseArgs) -> Result<(), Box<dyn std::error::Error>> {
         self.print_info(&format!("🔧 Running {} diagnostics...", args.check));
 
@@ -1616,3 +1885,82 @@ impl CliRunner {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::review::ReviewConfig;
+    use crate::codegen::diff_apply::{ChangeSet, ChangeType, DiffMetadata, FileDiff, GateStatus};
+    use crate::codegen::staging::StagingStrategy;
+    use crate::config::{LanguageServerConfig, LanguageServersConfig};
+    use crate::lsp::client::tests::fake_server_client;
+
+    async fn fake_language_servers(root: &Path) -> Arc<LanguageServerPool> {
+        let config = LanguageServersConfig {
+            enabled: true,
+            diagnostics_timeout_ms: 5000,
+            servers: vec![LanguageServerConfig {
+                language: "rust".to_string(),
+                command: "rust-analyzer".to_string(),
+                args: Vec::new(),
+                extensions: vec!["rs".to_string()],
+                initialization_options: None,
+            }],
+        };
+        let language_servers = Arc::new(LanguageServerPool::new(root.to_path_buf(), config));
+        language_servers.insert_client(fake_server_client(root).await).await;
+        language_servers
+    }
+
+    #[tokio::test]
+    async fn test_review_and_compile_gate_use_language_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = "fn main() {}\n";
+        let changed = "fn main() {}\nlet bad = 1;\n";
+        std::fs::write(dir.path().join("lib.rs"), changed).unwrap();
+        let language_servers = fake_language_servers(dir.path()).await;
+
+        let mut agent = build_review_agent(Some(language_servers.clone()));
+        let review = agent
+            .review_code(&[dir.path().join("lib.rs")], ReviewConfig::default())
+            .await
+            .unwrap();
+        assert!(review.issues.iter().any(|issue| issue.title == "mismatched types"));
+
+        let config = QualityGateConfig {
+            enabled_gates: vec!["compile".to_string()],
+            staging: StagingStrategy::InPlace,
+            ..QualityGateConfig::default()
+        };
+        let system = build_diff_apply_system(config, dir.path(), Some(language_servers)).unwrap();
+        let path = PathBuf::from("lib.rs");
+        let mut changeset = ChangeSet::new(
+            "edit",
+            "",
+            "agent",
+            "task",
+            vec![FileDiff {
+                diff_text: DiffApplySystem::generate_diff(Some(original), changed, &path),
+                file_path: path,
+                original_content: Some(original.to_string()),
+                new_content: changed.to_string(),
+                change_type: ChangeType::Modify,
+                metadata: DiffMetadata {
+                    created_at: chrono::Utc::now(),
+                    agent_id: "agent".to_string(),
+                    task_id: "task".to_string(),
+                    confidence_score: 1.0,
+                    estimated_lines_changed: 1,
+                    language: Some("rust".to_string()),
+                    description: String::new(),
+                },
+                hunks: Vec::new(),
+            }],
+        );
+        system.validate_changeset(&mut changeset, dir.path()).await.unwrap();
+
+        let compile = &changeset.validation_results.unwrap().gates["compile"];
+        assert_eq!(compile.status, GateStatus::Failed);
+        assert_eq!(compile.message, "Language servers reported 1 error(s)");
+    }
+}
@@ -285,6 +285,9 @@ impl CliValidator {
             Commands::Export(_) => { /* Export validation placeholder */ },
             Commands::Behavior(_) => { /* Behavior validation placeholder */ },
             Commands::Diagnose(_) => { /* Diagnose validation placeholder */ },
+            Commands::Lsp(_) => { /* No arguments to validate */ },
+            Commands::Changes(_) => { /* Changeset ids are resolved against the journal */ },
+            Commands::Codemod(_) => { /* Rules and paths are resolved against the project */ },
         }
 
         result.is_valid = result.errors.is_empty();
@@ -775,9 +778,10 @@ impl CliValidator {
                     }
                 }
             }
-            BlueprintCommands::Validate { blueprint } => {
+            BlueprintCommands::Validate { blueprint, .. } => {
                 self.validate_file_path(blueprint, "blueprint", result);
             }
+            BlueprintCommands::Schema { .. } => {}
             BlueprintCommands::Info { blueprint, .. } => {
                 self.validate_file_path(blueprint, "blueprint", result);
             }
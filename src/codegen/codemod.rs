@@ -0,0 +1,819 @@
+//! Tree-sitter query codemods
+//!
+//! A codemod rule pairs a tree-sitter query with a rewrite template and is
+//! written in TOML or YAML, usually under `.devkit/codemods`:
+//!
+//! ```toml
+//! description = "Use unwrap_or_default instead of unwrap_or_else(Default::default)"
+//! language = "rust"
+//! query = '''
+//! (call_expression
+//!   function: (field_expression value: (_) @receiver field: (field_identifier) @method)
+//!   arguments: (arguments (scoped_identifier) @arg)
+//!   (#eq? @method "unwrap_or_else")
+//!   (#eq? @arg "Default::default")) @match
+//! '''
+//! rewrite = "{{receiver}}.unwrap_or_default()"
+//! ```
+//!
+//! The node captured as `@match` (or the rule's `target`) is replaced by the
+//! template with `{{capture}}` placeholders filled in. Matches the template
+//! cannot rewrite, because a placeholder's capture is missing from the match
+//! or the rule has no template, can be handed to the model instead. The
+//! result is a `ChangeSet` with one hunk per rewritten match.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tree_sitter::{Parser, Query, QueryCursor};
+
+use super::diff_apply::{
+    hunk_header_start, ChangeSet, ChangeType, DiffApplySystem, DiffHunk, DiffMetadata, FileDiff, HunkState,
+};
+use super::edit_formats::HunkLine;
+use crate::ai::AIManager;
+use crate::context::syntax_chunker::grammar;
+
+/// Where rules are looked up by name
+pub const RULES_DIR: &str = ".devkit/codemods";
+
+const RULE_EXTENSIONS: &[&str] = &["toml", "yaml", "yml"];
+
+/// A rule file and the rule parsed from it
+pub type DiscoveredRule = (PathBuf, Result<CodemodRule, CodemodError>);
+
+/// Errors loading or running a codemod
+#[derive(Debug, Error)]
+pub enum CodemodError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Rule not found: {0}")]
+    RuleNotFound(String),
+
+    #[error("Invalid rule {path}: {message}")]
+    InvalidRule { path: PathBuf, message: String },
+
+    #[error("Unsupported language: {0}")]
+    UnsupportedLanguage(String),
+
+    #[error("Invalid query: {0}")]
+    Query(String),
+
+    #[error("Invalid template: {0}")]
+    Template(String),
+
+    #[error("Invalid glob '{pattern}': {message}")]
+    Glob { pattern: String, message: String },
+}
+
+/// A codemod rule as written in a rule file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodemodRule {
+    /// Defaults to the rule file's stem
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Analyzer language name: rust, python, javascript, typescript or go
+    pub language: String,
+    /// Tree-sitter query; predicates such as `#eq?` and `#match?` are honored
+    pub query: String,
+    /// Replacement with `{{capture}}` placeholders; without one every match
+    /// needs the model
+    #[serde(default)]
+    pub rewrite: Option<String>,
+    /// Capture whose node is replaced
+    #[serde(default = "default_target")]
+    pub target: String,
+    /// Ask the model for matches the template cannot rewrite
+    #[serde(default)]
+    pub llm_fallback: bool,
+    /// Globs relative to the project root; empty means every file of the language
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_target() -> String {
+    "match".to_string()
+}
+
+impl CodemodRule {
+    /// Parse a rule from a `.toml`, `.yaml` or `.yml` file
+    pub fn from_file(path: &Path) -> Result<Self, CodemodError> {
+        let content = std::fs::read_to_string(path)?;
+        let invalid = |message: String| CodemodError::InvalidRule {
+            path: path.to_path_buf(),
+            message,
+        };
+        let mut rule: CodemodRule = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| invalid(e.to_string()))?,
+            Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?,
+            _ => return Err(invalid("expected a .toml, .yaml or .yml file".to_string())),
+        };
+        if rule.name.is_empty() {
+            rule.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+        Ok(rule)
+    }
+
+    /// Load a rule given as a path, or by name from the project's rules directory
+    pub fn load(project_root: &Path, rule: &str) -> Result<Self, CodemodError> {
+        let direct = Path::new(rule);
+        if direct.is_file() {
+            return Self::from_file(direct);
+        }
+        let dir = project_root.join(RULES_DIR);
+        RULE_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", rule, ext)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| CodemodError::RuleNotFound(format!("{} (looked in {})", rule, dir.display())))
+            .and_then(|path| Self::from_file(&path))
+    }
+
+    /// Rule files in the project's rules directory, sorted by path
+    pub fn discover(project_root: &Path) -> Result<Vec<DiscoveredRule>, CodemodError> {
+        let dir = project_root.join(RULES_DIR);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| RULE_EXTENSIONS.contains(&ext))
+            })
+            .collect();
+        paths.sort();
+        Ok(paths.into_iter().map(|path| {
+            let rule = Self::from_file(&path);
+            (path, rule)
+        }).collect())
+    }
+
+    fn compile(&self) -> Result<CompiledRule, CodemodError> {
+        let language = grammar(&self.language).ok_or_else(|| CodemodError::UnsupportedLanguage(self.language.clone()))?;
+        let query = Query::new(language, &self.query).map_err(|e| CodemodError::Query(e.to_string()))?;
+        let target = query.capture_index_for_name(&self.target).ok_or_else(|| {
+            CodemodError::Query(format!("the query has no @{} capture to rewrite", self.target))
+        })?;
+        let template = self.rewrite.as_deref().map(Template::parse).transpose()?;
+        if let Some(template) = &template {
+            if let Some(unknown) = template.captures().find(|name| query.capture_index_for_name(name).is_none()) {
+                return Err(CodemodError::Template(format!("{{{{{}}}}} is not a capture of the query", unknown)));
+            }
+        }
+        Ok(CompiledRule {
+            language,
+            query,
+            target,
+            template,
+            include: glob_set(&self.include)?,
+            exclude: glob_set(&self.exclude)?,
+        })
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>, CodemodError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| CodemodError::Glob {
+            pattern: pattern.clone(),
+            message: e.to_string(),
+        })?);
+    }
+    builder.build().map(Some).map_err(|e| CodemodError::Glob {
+        pattern: patterns.join(", "),
+        message: e.to_string(),
+    })
+}
+
+struct CompiledRule {
+    language: tree_sitter::Language,
+    query: Query,
+    target: u32,
+    template: Option<Template>,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+/// A rewrite template split into literal text and capture placeholders
+#[derive(Debug, Clone, PartialEq)]
+struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Capture(String),
+}
+
+impl Template {
+    fn parse(template: &str) -> Result<Self, CodemodError> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| CodemodError::Template(format!("unclosed placeholder in '{}'", template)))?;
+            let name = rest[start + 2..start + end].trim();
+            if name.is_empty() {
+                return Err(CodemodError::Template(format!("empty placeholder in '{}'", template)));
+            }
+            parts.push(TemplatePart::Capture(name.to_string()));
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    fn captures(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            TemplatePart::Capture(name) => Some(name.as_str()),
+            TemplatePart::Text(_) => None,
+        })
+    }
+
+    /// Fill in the captures; `Err` names the first capture the match lacks
+    fn render(&self, captures: &BTreeMap<String, String>) -> Result<String, String> {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Text(text) => rendered.push_str(text),
+                TemplatePart::Capture(name) => rendered.push_str(captures.get(name).ok_or_else(|| name.clone())?),
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+/// One node matched by a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodemodMatch {
+    /// Relative to the project root
+    pub file: PathBuf,
+    /// 1-based line and column of the replaced node
+    pub line: usize,
+    pub column: usize,
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// Text of the replaced node
+    pub text: String,
+    /// Text of every capture in the match
+    pub captures: BTreeMap<String, String>,
+}
+
+impl CodemodMatch {
+    fn range(&self) -> Range<usize> {
+        self.start_byte..self.end_byte
+    }
+}
+
+/// How a match was rewritten
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteSource {
+    Template,
+    Model,
+    /// Left as is, with the reason
+    Unresolved(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodemodOutcome {
+    pub matched: CodemodMatch,
+    pub replacement: Option<String>,
+    pub source: RewriteSource,
+}
+
+/// Result of running a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodemodReport {
+    pub rule: String,
+    pub outcomes: Vec<CodemodOutcome>,
+    /// `None` when no match was rewritten
+    pub changeset: Option<ChangeSet>,
+}
+
+impl CodemodReport {
+    pub fn match_count(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn rewritten_count(&self) -> usize {
+        self.outcomes.iter().filter(|outcome| outcome.replacement.is_some()).count()
+    }
+
+    pub fn unresolved(&self) -> impl Iterator<Item = &CodemodOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome.source, RewriteSource::Unresolved(_)))
+    }
+}
+
+/// Runs codemod rules over a project
+pub struct CodemodEngine {
+    project_root: PathBuf,
+    ai_manager: Option<Arc<AIManager>>,
+    llm_fallback: bool,
+}
+
+impl CodemodEngine {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            project_root: project_root.to_path_buf(),
+            ai_manager: None,
+            llm_fallback: false,
+        }
+    }
+
+    pub fn with_ai_manager(mut self, ai_manager: Arc<AIManager>) -> Self {
+        self.ai_manager = Some(ai_manager);
+        self
+    }
+
+    /// Use the model for unrewritable matches even if the rule does not ask for it
+    pub fn with_llm_fallback(mut self, llm_fallback: bool) -> Self {
+        self.llm_fallback = llm_fallback;
+        self
+    }
+
+    /// Matches of a rule under the given paths, or the whole project when empty
+    ///
+    /// Matches nested inside an earlier match are dropped, since the outer
+    /// rewrite replaces them.
+    pub fn find_matches(&self, rule: &CodemodRule, paths: &[PathBuf]) -> Result<Vec<CodemodMatch>, CodemodError> {
+        let compiled = rule.compile()?;
+        let mut matches = Vec::new();
+        for file in self.files(rule, &compiled, paths)? {
+            let content = std::fs::read_to_string(self.project_root.join(&file))?;
+            matches.extend(match_file(&compiled, &file, &content));
+        }
+        Ok(matches)
+    }
+
+    /// Rewrite every match of a rule into a changeset
+    pub async fn run(&self, rule: &CodemodRule, paths: &[PathBuf]) -> Result<CodemodReport, CodemodError> {
+        let compiled = rule.compile()?;
+        let use_model = rule.llm_fallback || self.llm_fallback;
+
+        let mut outcomes = Vec::new();
+        let mut files = Vec::new();
+        for file in self.files(rule, &compiled, paths)? {
+            let content = std::fs::read_to_string(self.project_root.join(&file))?;
+            let matches = match_file(&compiled, &file, &content);
+            if matches.is_empty() {
+                continue;
+            }
+
+            let mut edits = Vec::new();
+            for matched in matches {
+                let templated = match &compiled.template {
+                    Some(template) => template
+                        .render(&matched.captures)
+                        .map_err(|capture| format!("the match has no @{} capture", capture)),
+                    None => Err("the rule has no rewrite template".to_string()),
+                };
+                let (replacement, source) = match templated {
+                    Ok(replacement) => (Some(replacement), RewriteSource::Template),
+                    Err(reason) => match (&self.ai_manager, use_model) {
+                        (Some(ai_manager), true) => match self.rewrite_with_ai(ai_manager, rule, &matched, &content).await {
+                            Ok(replacement) => (Some(replacement), RewriteSource::Model),
+                            Err(e) => (None, RewriteSource::Unresolved(format!("{}; the model failed: {}", reason, e))),
+                        },
+                        (None, true) => (None, RewriteSource::Unresolved(format!("{}; no AI backend is available", reason))),
+                        (_, false) => (None, RewriteSource::Unresolved(reason)),
+                    },
+                };
+                if let Some(replacement) = &replacement {
+                    edits.push((matched.range(), replacement.clone()));
+                }
+                outcomes.push(CodemodOutcome {
+                    matched,
+                    replacement,
+                    source,
+                });
+            }
+
+            if let Some(file_diff) = file_diff(rule, &file, &content, &edits) {
+                files.push(file_diff);
+            }
+        }
+
+        let changeset = (!files.is_empty()).then(|| {
+            let description = if rule.description.is_empty() {
+                format!("Codemod {}", rule.name)
+            } else {
+                rule.description.clone()
+            };
+            ChangeSet::new(&format!("Codemod {}", rule.name), &description, "codemod", &rule.name, files)
+        });
+        Ok(CodemodReport {
+            rule: rule.name.clone(),
+            outcomes,
+            changeset,
+        })
+    }
+
+    /// Files of the rule's language under the paths, relative to the project root
+    fn files(&self, rule: &CodemodRule, compiled: &CompiledRule, paths: &[PathBuf]) -> Result<Vec<PathBuf>, CodemodError> {
+        let extensions = extensions(&rule.language);
+        let roots: Vec<PathBuf> = if paths.is_empty() {
+            vec![self.project_root.clone()]
+        } else {
+            paths.iter().map(|path| self.project_root.join(path)).collect()
+        };
+
+        let mut files = Vec::new();
+        for root in roots {
+            if !root.exists() {
+                return Err(CodemodError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("{} does not exist", root.display()),
+                )));
+            }
+            for entry in ignore::WalkBuilder::new(&root).build() {
+                let entry = entry.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                let path = entry.path();
+                if !path.is_file() || !path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| extensions.contains(&ext)) {
+                    continue;
+                }
+                let relative = path.strip_prefix(&self.project_root).unwrap_or(path).to_path_buf();
+                let included = compiled.include.as_ref().map_or(true, |set| set.is_match(&relative));
+                let excluded = compiled.exclude.as_ref().is_some_and(|set| set.is_match(&relative));
+                if included && !excluded {
+                    files.push(relative);
+                }
+            }
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    async fn rewrite_with_ai(
+        &self,
+        ai_manager: &AIManager,
+        rule: &CodemodRule,
+        matched: &CodemodMatch,
+        content: &str,
+    ) -> Result<String, String> {
+        let system_prompt = "You are applying a mechanical code migration. Rewrite only the given code fragment. Return ONLY the replacement fragment without explanations or markdown formatting.";
+
+        let lines: Vec<&str> = content.lines().collect();
+        let first = matched.line.saturating_sub(4);
+        let last = (matched.line + matched.text.lines().count() + 2).min(lines.len());
+        let mut user_prompt = format!(
+            "Migration: {}\nLanguage: {}\n",
+            if rule.description.is_empty() { &rule.name } else { &rule.description },
+            rule.language
+        );
+        if let Some(rewrite) = &rule.rewrite {
+            user_prompt.push_str(&format!("Intended rewrite template: {}\n", rewrite));
+        }
+        user_prompt.push_str(&format!(
+            "\nSurrounding code ({}:{}):\n{}\n\nFragment to rewrite:\n{}\n\nCaptures:\n",
+            matched.file.display(),
+            matched.line,
+            lines[first..last].join("\n"),
+            matched.text
+        ));
+        for (name, text) in &matched.captures {
+            user_prompt.push_str(&format!("- {}: {}\n", name, text));
+        }
+
+        let response = ai_manager
+            .generate_response(system_prompt, &user_prompt, Some(500), Some(0.0))
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut replacement = response.trim();
+        if replacement.starts_with("```") {
+            let body = replacement.split_once('\n').map_or("", |(_, body)| body);
+            replacement = body.trim_end().strip_suffix("```").unwrap_or(body).trim_end();
+        }
+        if replacement.is_empty() {
+            return Err("empty response".to_string());
+        }
+        Ok(replacement.to_string())
+    }
+}
+
+/// File extensions of an analyzer language
+fn extensions(language: &str) -> &'static [&'static str] {
+    match language {
+        "rust" => &["rs"],
+        "python" => &["py"],
+        "javascript" => &["js", "jsx", "mjs", "cjs"],
+        "typescript" => &["ts", "mts", "cts"],
+        "go" => &["go"],
+        _ => &[],
+    }
+}
+
+fn match_file(rule: &CompiledRule, file: &Path, content: &str) -> Vec<CodemodMatch> {
+    let mut parser = Parser::new();
+    if parser.set_language(rule.language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+
+    let names = rule.query.capture_names();
+    let mut cursor = QueryCursor::new();
+    let mut matches: Vec<CodemodMatch> = cursor
+        .matches(&rule.query, tree.root_node(), content.as_bytes())
+        .filter_map(|found| {
+            let target = found.captures.iter().find(|capture| capture.index == rule.target)?.node;
+            // Quantified captures span from their first to their last node
+            let mut spans: BTreeMap<String, Range<usize>> = BTreeMap::new();
+            for capture in found.captures {
+                let range = capture.node.byte_range();
+                spans
+                    .entry(names[capture.index as usize].clone())
+                    .and_modify(|span| *span = span.start.min(range.start)..span.end.max(range.end))
+                    .or_insert(range);
+            }
+            let position = target.start_position();
+            Some(CodemodMatch {
+                file: file.to_path_buf(),
+                line: position.row + 1,
+                column: position.column + 1,
+                start_byte: target.start_byte(),
+                end_byte: target.end_byte(),
+                text: content[target.byte_range()].to_string(),
+                captures: spans
+                    .into_iter()
+                    .map(|(name, span)| (name, content[span].to_string()))
+                    .collect(),
+            })
+        })
+        .collect();
+
+    matches.sort_by_key(|matched| (matched.start_byte, std::cmp::Reverse(matched.end_byte)));
+    let mut kept: Vec<CodemodMatch> = Vec::new();
+    for matched in matches {
+        if kept.last().map_or(true, |last| matched.start_byte >= last.end_byte) {
+            kept.push(matched);
+        }
+    }
+    kept
+}
+
+/// File diff with one hunk per group of edits sharing lines
+fn file_diff(rule: &CodemodRule, file: &Path, content: &str, edits: &[(Range<usize>, String)]) -> Option<FileDiff> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+        .filter(|start| *start < content.len() || content.is_empty())
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|start| *start <= offset).saturating_sub(1);
+    let line_end = |line: usize| line_starts.get(line + 1).copied().unwrap_or(content.len());
+
+    // Edits sharing a line end up in the same hunk
+    struct Group<'e> {
+        first: usize,
+        last: usize,
+        edits: Vec<&'e (Range<usize>, String)>,
+    }
+    let mut groups: Vec<Group> = Vec::new();
+    for edit in edits {
+        let first = line_of(edit.0.start);
+        let last = line_of(edit.0.end.saturating_sub(1).max(edit.0.start));
+        match groups.last_mut() {
+            Some(group) if first <= group.last => {
+                group.last = group.last.max(last);
+                group.edits.push(edit);
+            }
+            _ => groups.push(Group { first, last, edits: vec![edit] }),
+        }
+    }
+
+    let mut new_content = String::new();
+    let mut hunks = Vec::new();
+    let mut cursor = 0;
+    let mut delta: isize = 0;
+    for Group { first, last, edits } in groups {
+        let region = line_starts[first]..line_end(last);
+        let mut replaced = String::new();
+        let mut position = region.start;
+        for (range, replacement) in edits {
+            replaced.push_str(&content[position..range.start]);
+            replaced.push_str(replacement);
+            position = range.end;
+        }
+        replaced.push_str(&content[position..region.end]);
+        new_content.push_str(&content[cursor..region.start]);
+        new_content.push_str(&replaced);
+        cursor = region.end;
+
+        let old_text = &content[region];
+        if old_text == replaced {
+            continue;
+        }
+        let old_lines: Vec<&str> = old_text.lines().collect();
+        let new_lines: Vec<&str> = replaced.lines().collect();
+        let old_range = first..first + old_lines.len();
+        let new_start = (first as isize + delta) as usize;
+        let new_range = new_start..new_start + new_lines.len();
+        delta += new_lines.len() as isize - old_lines.len() as isize;
+        hunks.push(DiffHunk {
+            header: format!(
+                "@@ -{},{} +{},{} @@",
+                hunk_header_start(&old_range),
+                old_range.len(),
+                hunk_header_start(&new_range),
+                new_range.len()
+            ),
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            lines: old_lines
+                .iter()
+                .map(|line| HunkLine::Remove(line.to_string()))
+                .chain(new_lines.iter().map(|line| HunkLine::Add(line.to_string())))
+                .collect(),
+            state: HunkState::Pending,
+        });
+    }
+    new_content.push_str(&content[cursor..]);
+    if hunks.is_empty() {
+        return None;
+    }
+
+    Some(FileDiff {
+        file_path: file.to_path_buf(),
+        diff_text: DiffApplySystem::generate_diff(Some(content), &new_content, file),
+        change_type: ChangeType::Modify,
+        metadata: DiffMetadata {
+            created_at: chrono::Utc::now(),
+            agent_id: "codemod".to_string(),
+            task_id: rule.name.clone(),
+            confidence_score: 1.0,
+            estimated_lines_changed: hunks.iter().map(|hunk| hunk.old_lines + hunk.new_lines).sum(),
+            language: Some(rule.language.clone()),
+            description: format!("Codemod {}: {} match(es)", rule.name, hunks.len()),
+        },
+        original_content: Some(content.to_string()),
+        new_content,
+        hunks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+fn main() {
+    let a = first().unwrap_or_else(Default::default);
+    let b = second().unwrap_or_else(|| 3);
+    let c = third().unwrap_or_else(Default::default);
+}
+";
+
+    fn rule(rewrite: Option<&str>) -> CodemodRule {
+        CodemodRule {
+            name: "unwrap-or-default".to_string(),
+            description: String::new(),
+            language: "rust".to_string(),
+            query: r#"
+                (call_expression
+                  function: (field_expression value: (_) @receiver field: (field_identifier) @method)
+                  arguments: (arguments (_) @arg)
+                  (#eq? @method "unwrap_or_else")) @match
+            "#
+            .to_string(),
+            rewrite: rewrite.map(str::to_string),
+            target: default_target(),
+            llm_fallback: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    fn project() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), SOURCE).unwrap();
+        std::fs::write(dir.path().join("notes.py"), "x = 1\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn rules_load_from_toml_and_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = dir.path().join(RULES_DIR);
+        std::fs::create_dir_all(&rules).unwrap();
+        std::fs::write(
+            rules.join("first.toml"),
+            "language = \"rust\"\nquery = \"(identifier) @match\"\nrewrite = \"x\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            rules.join("second.yaml"),
+            "name: renamed\nlanguage: python\nquery: \"(identifier) @id\"\ntarget: id\nllm_fallback: true\n",
+        )
+        .unwrap();
+
+        let first = CodemodRule::load(dir.path(), "first").unwrap();
+        assert_eq!(first.name, "first");
+        assert_eq!(first.target, "match");
+        let second = CodemodRule::load(dir.path(), "second").unwrap();
+        assert_eq!(second.name, "renamed");
+        assert!(second.llm_fallback && second.rewrite.is_none());
+        assert_eq!(CodemodRule::discover(dir.path()).unwrap().len(), 2);
+        assert!(matches!(CodemodRule::load(dir.path(), "missing"), Err(CodemodError::RuleNotFound(_))));
+    }
+
+    #[test]
+    fn invalid_queries_and_templates_are_rejected() {
+        let mut missing_target = rule(Some("x"));
+        missing_target.target = "nothing".to_string();
+        assert!(matches!(missing_target.compile(), Err(CodemodError::Query(_))));
+        assert!(matches!(rule(Some("{{unknown}}")).compile(), Err(CodemodError::Template(_))));
+        assert!(matches!(rule(Some("{{receiver")).compile(), Err(CodemodError::Template(_))));
+    }
+
+    #[test]
+    fn dry_run_finds_matches_with_captures() {
+        let dir = project();
+        let matches = CodemodEngine::new(dir.path()).find_matches(&rule(None), &[]).unwrap();
+
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[0].file, PathBuf::from("src/main.rs"));
+        assert_eq!((matches[0].line, matches[0].column), (2, 13));
+        assert_eq!(matches[1].captures["receiver"], "second()");
+        assert_eq!(matches[1].captures["arg"], "|| 3");
+    }
+
+    #[tokio::test]
+    async fn rewrites_each_match_as_its_own_hunk() {
+        let dir = project();
+        let mut rule = rule(Some("{{receiver}}.unwrap_or_default()"));
+        rule.query = rule.query.replace(
+            "(#eq? @method \"unwrap_or_else\")",
+            "(#eq? @method \"unwrap_or_else\") (#eq? @arg \"Default::default\")",
+        );
+
+        let report = CodemodEngine::new(dir.path()).run(&rule, &[PathBuf::from("src")]).await.unwrap();
+
+        assert_eq!((report.match_count(), report.rewritten_count()), (2, 2));
+        let changeset = report.changeset.unwrap();
+        let file = &changeset.files[0];
+        assert_eq!(
+            file.new_content,
+            SOURCE
+                .replace("first().unwrap_or_else(Default::default)", "first().unwrap_or_default()")
+                .replace("third().unwrap_or_else(Default::default)", "third().unwrap_or_default()")
+        );
+        assert_eq!(file.hunks.len(), 2);
+        assert_eq!((file.hunks[1].old_start, file.hunks[1].new_start), (4, 4));
+
+        // Accepting only the second hunk rewrites only the second match
+        let mut reviewed = changeset.clone();
+        reviewed.set_hunk_state(Path::new("src/main.rs"), 1, HunkState::Accepted).unwrap();
+        let subset = reviewed.accepted_subset();
+        assert!(subset.files[0].new_content.contains("first().unwrap_or_else(Default::default)"));
+        assert!(subset.files[0].new_content.contains("third().unwrap_or_default()"));
+    }
+
+    #[tokio::test]
+    async fn matches_the_template_cannot_rewrite_are_reported() {
+        let dir = project();
+        let mut rule = rule(Some("{{receiver}}.unwrap_or({{default}})"));
+        rule.query = rule.query.replace(
+            "arguments: (arguments (_) @arg)",
+            "arguments: (arguments [(closure_expression body: (_) @default) (scoped_identifier)] @arg)",
+        );
+        rule.llm_fallback = true;
+
+        let report = CodemodEngine::new(dir.path()).run(&rule, &[]).await.unwrap();
+
+        assert_eq!(report.rewritten_count(), 1);
+        let unresolved: Vec<_> = report.unresolved().collect();
+        assert_eq!(unresolved.len(), 2);
+        assert!(matches!(&unresolved[0].source, RewriteSource::Unresolved(reason) if reason.contains("@default")));
+        assert!(report.changeset.unwrap().files[0].new_content.contains("second().unwrap_or(3)"));
+    }
+}
@@ -4,14 +4,24 @@
 //! 1. Changes are presented as diffs for review
 //! 2. Quality gates validate changes (format, lint, test, security)
 //! 3. Changes can be applied incrementally or rolled back
-//! 4. Full audit trail of what was changed and why
+//! 4. Full audit trail of what was changed and why, journaled on disk so
+//!    rollback keeps working across restarts
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 use tokio::fs;
 use tokio::process::Command as AsyncCommand;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+use super::edit_formats::HunkLine;
+use super::journal::{content_hash, file_hash, write_atomic, ChangeJournal, JournalEntry, JournalError, JournalStatus};
+use super::staging::{StagingError, StagingStrategy, StagingWorkspace};
+use super::toolchain::{CompilerDiagnostic, ToolKind, ToolOutcome, ToolchainRegistry};
+use crate::lsp::convert::format_diagnostic;
+use crate::lsp::LanguageServerPool;
 
 /// A single file change represented as a diff
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +32,10 @@ pub struct FileDiff {
     pub diff_text: String,
     pub change_type: ChangeType,
     pub metadata: DiffMetadata,
+    /// Hunks with their review state; empty until split, in which case the
+    /// file is reviewed and applied as a whole
+    #[serde(default)]
+    pub hunks: Vec<DiffHunk>,
 }
 
 /// Type of change being made
@@ -33,6 +47,49 @@ pub enum ChangeType {
     Rename { old_path: PathBuf },
 }
 
+/// Review state of a single hunk
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HunkState {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+    /// Accepted with the hunk's new side replaced by this text
+    Edited(String),
+}
+
+impl HunkState {
+    /// Whether the hunk contributes to a partial apply; pending hunks do not
+    pub fn is_applied(&self) -> bool {
+        matches!(self, HunkState::Accepted | HunkState::Edited(_))
+    }
+}
+
+/// A contiguous region of change within a file diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub header: String,
+    /// 1-based first line of the hunk in the original content
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// 1-based first line of the hunk in the new content
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<HunkLine>,
+    #[serde(default)]
+    pub state: HunkState,
+}
+
+impl DiffHunk {
+    pub fn added_lines(&self) -> usize {
+        self.lines.iter().filter(|line| matches!(line, HunkLine::Add(_))).count()
+    }
+
+    pub fn removed_lines(&self) -> usize {
+        self.lines.iter().filter(|line| matches!(line, HunkLine::Remove(_))).count()
+    }
+}
+
 /// Metadata about a diff
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiffMetadata {
@@ -69,6 +126,297 @@ pub struct ChangeSetMetadata {
     pub affects_dependencies: bool,
 }
 
+impl ChangeSet {
+    /// Create a changeset, deriving its totals from the file diffs
+    pub fn new(title: &str, description: &str, agent_id: &str, task_id: &str, files: Vec<FileDiff>) -> Self {
+        let (mut added, mut removed) = (0, 0);
+        for file in &files {
+            for line in file.diff_text.lines() {
+                if line.starts_with('+') && !line.starts_with("+++") {
+                    added += 1;
+                } else if line.starts_with('-') && !line.starts_with("---") {
+                    removed += 1;
+                }
+            }
+        }
+        let affects_tests = files.iter().any(|file| is_test_path(&file.file_path));
+        let affects_dependencies = files.iter().any(|file| is_dependency_manifest(&file.file_path));
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            metadata: ChangeSetMetadata {
+                created_at: chrono::Utc::now(),
+                agent_id: agent_id.to_string(),
+                task_id: task_id.to_string(),
+                total_files: files.len(),
+                total_lines_added: added,
+                total_lines_removed: removed,
+                affects_tests,
+                affects_dependencies,
+            },
+            files,
+            validation_results: None,
+        }
+    }
+
+    /// Split every file that has not been split yet into pending hunks
+    pub fn split_hunks(&mut self) {
+        for file in &mut self.files {
+            if file.hunks.is_empty() {
+                file.split_hunks();
+            }
+        }
+    }
+
+    /// Record the review state of one hunk
+    pub fn set_hunk_state(&mut self, file_path: &Path, index: usize, state: HunkState) -> Result<(), DiffApplyError> {
+        let file = self
+            .files
+            .iter_mut()
+            .find(|file| file.file_path == file_path)
+            .ok_or_else(|| DiffApplyError::HunkNotFound(format!("{} is not part of the changeset", file_path.display())))?;
+        let hunk = file.hunks.get_mut(index).ok_or_else(|| {
+            DiffApplyError::HunkNotFound(format!("{} has no hunk {}", file_path.display(), index))
+        })?;
+        hunk.state = state;
+        Ok(())
+    }
+
+    /// Set every hunk of a file to the same state
+    pub fn set_file_state(&mut self, file_path: &Path, state: HunkState) -> Result<(), DiffApplyError> {
+        let file = self
+            .files
+            .iter_mut()
+            .find(|file| file.file_path == file_path)
+            .ok_or_else(|| DiffApplyError::HunkNotFound(format!("{} is not part of the changeset", file_path.display())))?;
+        for hunk in &mut file.hunks {
+            hunk.state = state.clone();
+        }
+        Ok(())
+    }
+
+    /// Changeset with only the applied hunks, under the same id
+    ///
+    /// Validation results are dropped since they covered the full changeset.
+    pub fn accepted_subset(&self) -> ChangeSet {
+        let files = self.files.iter().filter_map(FileDiff::accepted).collect();
+        let mut subset = ChangeSet::new(
+            &self.title,
+            &self.description,
+            &self.metadata.agent_id,
+            &self.metadata.task_id,
+            files,
+        );
+        subset.id = self.id.clone();
+        subset.metadata.created_at = self.metadata.created_at;
+        subset
+    }
+
+    /// Check that the id and every path stay inside the directories they are joined onto
+    pub fn check_paths(&self) -> Result<(), DiffApplyError> {
+        if !is_contained_path(Path::new(&self.id)) || Path::new(&self.id).components().count() != 1 {
+            return Err(DiffApplyError::InvalidPath(format!("changeset id '{}'", self.id)));
+        }
+        for file in &self.files {
+            let old_path = match &file.change_type {
+                ChangeType::Rename { old_path } => Some(old_path),
+                _ => None,
+            };
+            for path in std::iter::once(&file.file_path).chain(old_path) {
+                if !is_contained_path(path) {
+                    return Err(DiffApplyError::InvalidPath(path.display().to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FileDiff {
+    /// Split the change into hunks with three lines of context
+    pub fn compute_hunks(&self) -> Vec<DiffHunk> {
+        let original = self.original_content.as_deref().unwrap_or("");
+        let diff = similar::TextDiff::from_lines(original, self.new_content.as_str());
+
+        diff.grouped_ops(3)
+            .iter()
+            .filter_map(|group| {
+                let first = group.first()?;
+                let last = group.last()?;
+                let old_range = first.old_range().start..last.old_range().end;
+                let new_range = first.new_range().start..last.new_range().end;
+
+                let lines = group
+                    .iter()
+                    .flat_map(|op| diff.iter_changes(op))
+                    .map(|change| {
+                        let text = change.value().trim_end_matches(['\r', '\n']).to_string();
+                        match change.tag() {
+                            similar::ChangeTag::Equal => HunkLine::Context(text),
+                            similar::ChangeTag::Delete => HunkLine::Remove(text),
+                            similar::ChangeTag::Insert => HunkLine::Add(text),
+                        }
+                    })
+                    .collect();
+
+                Some(DiffHunk {
+                    header: format!(
+                        "@@ -{},{} +{},{} @@",
+                        hunk_header_start(&old_range),
+                        old_range.len(),
+                        hunk_header_start(&new_range),
+                        new_range.len()
+                    ),
+                    old_start: old_range.start + 1,
+                    old_lines: old_range.len(),
+                    new_start: new_range.start + 1,
+                    new_lines: new_range.len(),
+                    lines,
+                    state: HunkState::Pending,
+                })
+            })
+            .collect()
+    }
+
+    /// Replace the hunks with freshly computed, pending ones
+    pub fn split_hunks(&mut self) {
+        self.hunks = self.compute_hunks();
+    }
+
+    /// Content with only the applied hunks, the original elsewhere
+    pub fn partial_content(&self) -> String {
+        let original = self.original_content.as_deref().unwrap_or("");
+        let old_lines: Vec<&str> = original.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = self.new_content.split_inclusive('\n').collect();
+
+        let mut content = String::new();
+        let mut cursor = 0;
+        for hunk in &self.hunks {
+            // Headers are 1-based; a zero start only comes from a hand-written hunk
+            let old_start = hunk.old_start.saturating_sub(1).min(old_lines.len());
+            let old_end = (old_start + hunk.old_lines).min(old_lines.len());
+            content.extend(old_lines[cursor.min(old_start)..old_start].iter().copied());
+
+            match &hunk.state {
+                HunkState::Accepted => {
+                    let new_start = hunk.new_start.saturating_sub(1).min(new_lines.len());
+                    let new_end = (new_start + hunk.new_lines).min(new_lines.len());
+                    content.extend(new_lines[new_start..new_end].iter().copied());
+                }
+                HunkState::Edited(text) => {
+                    content.push_str(text);
+                    // Keep the line structure of what the hunk replaced
+                    let replaced_newline = old_end == old_lines.len() && !original.ends_with('\n');
+                    if !text.is_empty() && !text.ends_with('\n') && !replaced_newline {
+                        content.push('\n');
+                    }
+                }
+                HunkState::Pending | HunkState::Rejected => {
+                    content.extend(old_lines[old_start..old_end].iter().copied());
+                }
+            }
+            cursor = old_end;
+        }
+        content.extend(old_lines[cursor.min(old_lines.len())..].iter().copied());
+        content
+    }
+
+    /// The change limited to its applied hunks; `None` when nothing is applied
+    ///
+    /// A file that was never split into hunks is taken as a whole.
+    pub fn accepted(&self) -> Option<FileDiff> {
+        if self.hunks.is_empty() {
+            return Some(self.clone());
+        }
+        if !self.hunks.iter().any(|hunk| hunk.state.is_applied()) {
+            return None;
+        }
+
+        let all_accepted = self.hunks.iter().all(|hunk| hunk.state == HunkState::Accepted);
+        let new_content = if all_accepted {
+            self.new_content.clone()
+        } else {
+            self.partial_content()
+        };
+        // A partially accepted deletion leaves the file in place
+        let change_type = match &self.change_type {
+            ChangeType::Delete if !all_accepted => ChangeType::Modify,
+            other => other.clone(),
+        };
+        let diff_text = DiffApplySystem::generate_diff(
+            self.original_content.as_deref(),
+            &new_content,
+            &self.file_path,
+        );
+
+        let mut metadata = self.metadata.clone();
+        metadata.estimated_lines_changed = self
+            .hunks
+            .iter()
+            .filter(|hunk| hunk.state.is_applied())
+            .map(|hunk| hunk.added_lines() + hunk.removed_lines())
+            .sum();
+
+        Some(FileDiff {
+            file_path: self.file_path.clone(),
+            original_content: self.original_content.clone(),
+            new_content,
+            diff_text,
+            change_type,
+            metadata,
+            hunks: self.hunks.clone(),
+        })
+    }
+}
+
+/// Start line of a unified diff hunk header; empty ranges name the line before
+pub(super) fn hunk_header_start(range: &std::ops::Range<usize>) -> usize {
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
+    }
+}
+
+/// Whether a path follows a test file convention of a supported language:
+/// a `tests`, `test` or `__tests__` directory, `*.test.*` and `*.spec.*`,
+/// Go's `*_test.go`, or pytest's `test_*.py`, `*_test.py` and `conftest.py`
+pub(crate) fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|component| matches!(component.as_os_str().to_str(), Some("tests" | "test" | "__tests__")))
+    });
+    if in_test_dir {
+        return true;
+    }
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let parts: Vec<&str> = name.split('.').collect();
+    name.ends_with("_test.go")
+        || name == "conftest.py"
+        || (name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py")))
+        || (parts.len() > 2 && parts[1..parts.len() - 1].iter().any(|part| matches!(*part, "test" | "spec")))
+}
+
+/// Whether a path is relative and made only of normal components, so joining
+/// it onto a directory can't leave that directory
+pub(crate) fn is_contained_path(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path.components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn is_dependency_manifest(path: &Path) -> bool {
+    matches!(
+        path.file_name().and_then(|name| name.to_str()),
+        Some("Cargo.toml" | "Cargo.lock" | "package.json" | "package-lock.json" | "yarn.lock"
+            | "pnpm-lock.yaml" | "requirements.txt" | "pyproject.toml" | "poetry.lock"
+            | "go.mod" | "go.sum")
+    )
+}
+
 /// Results from quality gate validation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationResults {
@@ -87,6 +435,9 @@ pub struct GateResult {
     pub status: GateStatus,
     pub message: String,
     pub details: Option<String>,
+    /// Diagnostics parsed from the tools' output
+    #[serde(default)]
+    pub diagnostics: Vec<CompilerDiagnostic>,
     pub execution_time_ms: u64,
     pub is_blocking: bool,
 }
@@ -111,7 +462,11 @@ pub enum ValidationStatus {
 }
 
 /// Configuration for quality gates
+///
+/// Loaded from the `quality_gates` section of the devkit config; missing
+/// fields fall back to [`QualityGateConfig::default`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct QualityGateConfig {
     pub enabled_gates: Vec<String>,
     pub auto_apply_on_pass: bool,
@@ -119,6 +474,13 @@ pub struct QualityGateConfig {
     pub timeout_seconds: u64,
     pub parallel_execution: bool,
     pub custom_commands: HashMap<String, String>,
+    /// Where gates run: a staged copy of the project with the changeset applied by default
+    #[serde(default)]
+    pub staging: StagingStrategy,
+    /// Formatter, linter, type-checker and test commands per language;
+    /// a configured list replaces the built-in toolchains
+    #[serde(default)]
+    pub toolchains: ToolchainRegistry,
 }
 
 impl Default for QualityGateConfig {
@@ -136,6 +498,8 @@ impl Default for QualityGateConfig {
             timeout_seconds: 300,
             parallel_execution: true,
             custom_commands: HashMap::new(),
+            staging: StagingStrategy::Copy,
+            toolchains: ToolchainRegistry::default(),
         }
     }
 }
@@ -146,6 +510,59 @@ pub struct DiffApplySystem {
     backup_dir: PathBuf,
     quality_gates: Vec<Box<dyn QualityGate>>,
     applied_changesets: HashMap<String, ChangeSet>,
+    journal: ChangeJournal,
+}
+
+/// A file edited after its changeset was applied, which blocks rollback
+#[derive(Debug, Clone)]
+pub struct RollbackConflict {
+    pub path: PathBuf,
+    /// Content before the changeset; `None` if the changeset created the file
+    pub before: Option<String>,
+    /// Content the changeset wrote; `None` if it deleted the file
+    pub applied: Option<String>,
+    /// Content on disk now
+    pub current: Option<String>,
+}
+
+/// Outcome of finishing applies that were interrupted by a crash
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// Changesets whose remaining files were written
+    pub replayed: Vec<String>,
+    /// Changesets left alone because files changed since the apply began
+    pub conflicts: Vec<RecoveryConflict>,
+    /// Changesets without a backup to replay from
+    pub missing_backups: Vec<String>,
+}
+
+/// An interrupted changeset with files that match neither side of the apply
+#[derive(Debug, Clone)]
+pub struct RecoveryConflict {
+    pub changeset_id: String,
+    pub files: Vec<PathBuf>,
+}
+
+impl RollbackConflict {
+    /// What the changeset changed, followed by what was edited since
+    pub fn three_way_diff(&self) -> String {
+        let path = self.path.display();
+        let before = self.before.as_deref().unwrap_or("");
+        let applied = self.applied.as_deref().unwrap_or("");
+        let current = self.current.as_deref().unwrap_or("");
+
+        let changeset_diff = similar::TextDiff::from_lines(before, applied)
+            .unified_diff()
+            .context_radius(3)
+            .header(&format!("{} (before)", path), &format!("{} (applied)", path))
+            .to_string();
+        let edits_diff = similar::TextDiff::from_lines(applied, current)
+            .unified_diff()
+            .context_radius(3)
+            .header(&format!("{} (applied)", path), &format!("{} (current)", path))
+            .to_string();
+        format!("{}{}", changeset_diff, edits_diff)
+    }
 }
 
 /// Trait for implementing quality gates
@@ -194,6 +611,7 @@ pub trait QualityGate: Send + Sync {
                 status: GateStatus::Passed,
                 message: "Basic security checks passed".to_string(),
                 details: None,
+                diagnostics: Vec::new(),
                 execution_time_ms: duration.as_millis() as u64,
                 is_blocking: false,
             })
@@ -203,6 +621,7 @@ pub trait QualityGate: Send + Sync {
                 status: GateStatus::Warning,
                 message: "Potential security issues found".to_string(),
                 details: Some(warnings.join("\n")),
+                diagnostics: Vec::new(),
                 execution_time_ms: duration.as_millis() as u64,
                 is_blocking: false,
             })
@@ -233,6 +652,18 @@ pub enum DiffApplyError {
     
     #[error("File conflict: {0}")]
     FileConflict(String),
+    
+    #[error("Hunk not found: {0}")]
+    HunkNotFound(String),
+    
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+    
+    #[error("Journal error: {0}")]
+    JournalError(#[from] JournalError),
+    
+    #[error("Staging failed: {0}")]
+    StagingFailed(#[from] StagingError),
 }
 
 /// Errors in quality gates
@@ -262,22 +693,136 @@ impl DiffApplySystem {
             backup_dir,
             quality_gates: Vec::new(),
             applied_changesets: HashMap::new(),
+            journal: ChangeJournal::open(project_root)?,
         };
         
         // Register default quality gates
         system.register_default_gates();
         
+        system.load_applied()?;
+        
         Ok(system)
     }
     
+    /// Open the system for a session that applies changes, first finishing
+    /// applies a crash interrupted
+    ///
+    /// Changesets that can't be finished safely stay in the journal and are
+    /// logged; `recover` reports them in detail.
+    pub fn start(config: QualityGateConfig, project_root: &Path) -> Result<Self, DiffApplyError> {
+        let mut system = Self::new(config, project_root)?;
+        let report = system.recover(project_root)?;
+        for conflict in &report.conflicts {
+            tracing::warn!(
+                "Interrupted changeset {} conflicts with later edits; run `devkit changes recover`",
+                conflict.changeset_id
+            );
+        }
+        for changeset_id in &report.missing_backups {
+            tracing::warn!("Interrupted changeset {} has no backup to finish it from", changeset_id);
+        }
+        Ok(system)
+    }
+    
+    /// Reload applied changesets from the journal, without touching the worktree
+    fn load_applied(&mut self) -> Result<(), DiffApplyError> {
+        for entry in self.journal.entries()? {
+            if entry.status != JournalStatus::Applied {
+                continue;
+            }
+            
+            match self.load_backed_up_changeset(&entry.changeset_id) {
+                Some(changeset) => {
+                    self.applied_changesets.insert(changeset.id.clone(), changeset);
+                }
+                None => tracing::warn!("No backup found for journaled changeset {}", entry.changeset_id),
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Finish applies that were interrupted by a crash
+    ///
+    /// A file is written only if it still matches the journaled before-hash;
+    /// files already at their after-hash are skipped. If any file matches
+    /// neither, the changeset is reported as a conflict and nothing is written.
+    pub fn recover(&mut self, project_root: &Path) -> Result<RecoveryReport, DiffApplyError> {
+        let mut report = RecoveryReport::default();
+        
+        for entry in self.journal.entries()? {
+            if entry.status != JournalStatus::Applying {
+                continue;
+            }
+            
+            let Some(changeset) = self.load_backed_up_changeset(&entry.changeset_id) else {
+                tracing::warn!("No backup found for journaled changeset {}", entry.changeset_id);
+                report.missing_backups.push(entry.changeset_id.clone());
+                continue;
+            };
+            
+            let mut pending = Vec::new();
+            let mut conflicts = Vec::new();
+            for file in &entry.files {
+                let Some(file_diff) = changeset.files.iter().find(|diff| diff.file_path == file.path) else {
+                    conflicts.push(file.path.clone());
+                    continue;
+                };
+                if file_hash(&project_root.join(&file.path)) == file.after_hash {
+                    continue;
+                }
+                let before_path = match &file.change_type {
+                    ChangeType::Rename { old_path } => old_path,
+                    _ => &file.path,
+                };
+                if file_hash(&project_root.join(before_path)) == file.before_hash {
+                    pending.push(file_diff);
+                } else {
+                    conflicts.push(file.path.clone());
+                }
+            }
+            
+            if !conflicts.is_empty() {
+                tracing::warn!(
+                    "Not replaying changeset {}: {} file(s) changed since the apply began",
+                    entry.changeset_id,
+                    conflicts.len()
+                );
+                report.conflicts.push(RecoveryConflict {
+                    changeset_id: entry.changeset_id.clone(),
+                    files: conflicts,
+                });
+                continue;
+            }
+            
+            tracing::info!("Replaying interrupted apply of changeset {}", entry.changeset_id);
+            for file_diff in pending {
+                Self::write_file_diff(file_diff, project_root)?;
+            }
+            self.journal.set_status(&entry.changeset_id, JournalStatus::Applied)?;
+            report.replayed.push(entry.changeset_id.clone());
+            self.applied_changesets.insert(changeset.id.clone(), changeset);
+        }
+        
+        Ok(report)
+    }
+    
+    /// Changeset as saved alongside its backups
+    fn load_backed_up_changeset(&self, changeset_id: &str) -> Option<ChangeSet> {
+        let metadata_file = self.backup_dir.join(changeset_id).join("changeset.json");
+        let json = std::fs::read_to_string(metadata_file).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+    
     /// Register default quality gates based on configuration
     fn register_default_gates(&mut self) {
+        let toolchains = &self.config.toolchains;
         for gate_name in &self.config.enabled_gates {
             match gate_name.as_str() {
-                "format" => self.quality_gates.push(Box::new(FormatGate::new())),
-                "lint" => self.quality_gates.push(Box::new(LintGate::new())),
-                "compile" => self.quality_gates.push(Box::new(CompileGate::new())),
-                "test" => self.quality_gates.push(Box::new(TestGate::new())),
+                "format" => self.quality_gates.push(Box::new(FormatGate::with_toolchains(toolchains.clone()))),
+                "lint" => self.quality_gates.push(Box::new(LintGate::with_toolchains(toolchains.clone()))),
+                "compile" => self.quality_gates.push(Box::new(CompileGate::with_toolchains(toolchains.clone()))),
+                "test" => self.quality_gates.push(Box::new(TestGate::with_toolchains(toolchains.clone()))),
                 "security" => self.quality_gates.push(Box::new(SecurityGate::new())),
                 _ => {
                     if let Some(command) = self.config.custom_commands.get(gate_name) {
@@ -288,6 +833,19 @@ impl DiffApplySystem {
         }
     }
     
+    /// Answer the compile gate from language servers where they cover the changed files
+    pub fn with_language_servers(mut self, language_servers: Arc<LanguageServerPool>) -> Self {
+        for gate in &mut self.quality_gates {
+            if gate.name() == "compile" {
+                *gate = Box::new(
+                    CompileGate::with_toolchains(self.config.toolchains.clone())
+                        .with_language_servers(language_servers.clone()),
+                );
+            }
+        }
+        self
+    }
+
     /// Preview a changeset as diffs without applying
     pub async fn preview_changeset(&self, changeset: &ChangeSet) -> Result<String, DiffApplyError> {
         let mut preview = String::new();
@@ -321,6 +879,9 @@ impl DiffApplySystem {
     
     /// Validate a changeset through all quality gates
     pub async fn validate_changeset(&self, changeset: &mut ChangeSet, project_root: &Path) -> Result<(), DiffApplyError> {
+        // Staging writes the changed files too, so refuse escaping paths first
+        changeset.check_paths()?;
+        
         let mut results = ValidationResults {
             gates: HashMap::new(),
             overall_status: ValidationStatus::Pending,
@@ -330,12 +891,26 @@ impl DiffApplySystem {
             validated_at: chrono::Utc::now(),
         };
         
+        // Gates must see the code after the change, so run them in a staged copy
+        let staging = match self.config.staging {
+            StagingStrategy::InPlace => None,
+            ref strategy => Some(StagingWorkspace::create(project_root, changeset, strategy.clone()).await?),
+        };
+        let gate_root = staging.as_ref().map_or(project_root, |staging| staging.root());
+        
         // Run quality gates
-        if self.config.parallel_execution {
-            self.run_gates_parallel(changeset, project_root, &mut results).await?;
+        let gates_run = if self.config.parallel_execution {
+            self.run_gates_parallel(changeset, gate_root, &mut results).await
         } else {
-            self.run_gates_sequential(changeset, project_root, &mut results).await?;
+            self.run_gates_sequential(changeset, gate_root, &mut results).await
+        };
+        if let Some(staging) = staging {
+            for gate_result in results.gates.values_mut() {
+                staging.focus_result(gate_result);
+            }
+            staging.cleanup().await;
         }
+        gates_run?;
         
         // Determine overall status
         let mut has_failures = false;
@@ -408,6 +983,8 @@ impl DiffApplySystem {
     
     /// Apply a validated changeset
     pub async fn apply_changeset(&mut self, changeset: &ChangeSet, project_root: &Path, force: bool) -> Result<(), DiffApplyError> {
+        changeset.check_paths()?;
+        
         // Check validation results
         if !force {
             if let Some(validation) = &changeset.validation_results {
@@ -423,20 +1000,73 @@ impl DiffApplySystem {
             }
         }
         
+        // Never write over edits made since the changeset was generated
+        Self::check_originals(changeset, project_root)?;
+        
         // Create backup before applying changes
         self.create_backup(changeset, project_root).await?;
         
+        // Journal the apply before touching any file so a crash can be replayed
+        self.journal.record(&JournalEntry::for_changeset(changeset, project_root))?;
+        
         // Apply each file change
         for file_diff in &changeset.files {
-            self.apply_file_diff(file_diff, project_root).await?;
+            Self::write_file_diff(file_diff, project_root)?;
         }
         
+        self.journal.set_status(&changeset.id, JournalStatus::Applied)?;
+        
         // Store applied changeset for potential rollback
         self.applied_changesets.insert(changeset.id.clone(), changeset.clone());
         
         Ok(())
     }
     
+    /// Apply only the accepted hunks of a reviewed changeset
+    ///
+    /// Quality gates run again on the accepted subset, which is returned with
+    /// its validation results and recorded for rollback under the original id.
+    pub async fn apply_partial(&mut self, changeset: &ChangeSet, project_root: &Path, force: bool) -> Result<ChangeSet, DiffApplyError> {
+        let mut subset = changeset.accepted_subset();
+        if subset.files.is_empty() {
+            return Err(DiffApplyError::ValidationFailed(
+                "No hunks were accepted".to_string()
+            ));
+        }
+        
+        self.validate_changeset(&mut subset, project_root).await?;
+        self.apply_changeset(&subset, project_root, force).await?;
+        
+        Ok(subset)
+    }
+    
+    /// Fail if any file no longer has the content the changeset was made from
+    fn check_originals(changeset: &ChangeSet, project_root: &Path) -> Result<(), DiffApplyError> {
+        let stale: Vec<String> = changeset.files.iter()
+            .filter(|file| {
+                let source = match &file.change_type {
+                    ChangeType::Rename { old_path } => old_path,
+                    _ => &file.file_path,
+                };
+                let current = file_hash(&project_root.join(source));
+                match (&file.original_content, &file.change_type) {
+                    (Some(original), _) => current != Some(content_hash(original)),
+                    (None, ChangeType::Create) => current.is_some(),
+                    (None, _) => false,
+                }
+            })
+            .map(|file| file.file_path.display().to_string())
+            .collect();
+        
+        if stale.is_empty() {
+            Ok(())
+        } else {
+            Err(DiffApplyError::FileConflict(
+                format!("changed on disk since the changeset was made: {}", stale.join(", "))
+            ))
+        }
+    }
+    
     /// Create backup of files that will be changed
     async fn create_backup(&self, changeset: &ChangeSet, project_root: &Path) -> Result<(), DiffApplyError> {
         let backup_path = self.backup_dir.join(&changeset.id);
@@ -467,40 +1097,34 @@ impl DiffApplySystem {
         Ok(())
     }
     
-    /// Apply a single file diff
-    async fn apply_file_diff(&self, file_diff: &FileDiff, project_root: &Path) -> Result<(), DiffApplyError> {
-        let file_path = project_root.join(&file_diff.file_path);
+    /// Apply a single file diff; safe to repeat when replaying the journal
+    pub(crate) fn write_file_diff(file_diff: &FileDiff, project_root: &Path) -> Result<(), DiffApplyError> {
+        let file_path = Self::project_file(project_root, &file_diff.file_path)?;
         
         match &file_diff.change_type {
             ChangeType::Create | ChangeType::Modify => {
-                // Create parent directories if needed
-                if let Some(parent) = file_path.parent() {
-                    fs::create_dir_all(parent).await?;
-                }
-                
-                // Write new content
-                fs::write(&file_path, &file_diff.new_content).await?;
+                write_atomic(&file_path, file_diff.new_content.as_bytes())?;
             }
             ChangeType::Delete => {
                 if file_path.exists() {
-                    fs::remove_file(&file_path).await?;
+                    std::fs::remove_file(&file_path)?;
                 }
             }
             ChangeType::Rename { old_path } => {
-                let old_file_path = project_root.join(old_path);
+                let old_file_path = Self::project_file(project_root, old_path)?;
                 if old_file_path.exists() {
                     // Create parent directories for new location
                     if let Some(parent) = file_path.parent() {
-                        fs::create_dir_all(parent).await?;
+                        std::fs::create_dir_all(parent)?;
                     }
                     
                     // Move file
-                    fs::rename(&old_file_path, &file_path).await?;
-                    
-                    // Update content if provided
-                    if !file_diff.new_content.is_empty() {
-                        fs::write(&file_path, &file_diff.new_content).await?;
-                    }
+                    std::fs::rename(&old_file_path, &file_path)?;
+                }
+                
+                // Update content if provided
+                if file_path.exists() && !file_diff.new_content.is_empty() {
+                    write_atomic(&file_path, file_diff.new_content.as_bytes())?;
                 }
             }
         }
@@ -508,6 +1132,46 @@ impl DiffApplySystem {
         Ok(())
     }
     
+    /// Join a changeset path onto the project root, refusing any that would leave it
+    fn project_file(project_root: &Path, path: &Path) -> Result<PathBuf, DiffApplyError> {
+        if is_contained_path(path) {
+            Ok(project_root.join(path))
+        } else {
+            Err(DiffApplyError::InvalidPath(path.display().to_string()))
+        }
+    }
+    
+    /// Files of an applied changeset that were edited since, with their content
+    pub fn rollback_conflicts(&self, changeset_id: &str, project_root: &Path) -> Result<Vec<RollbackConflict>, DiffApplyError> {
+        let changeset = self.applied_changesets.get(changeset_id)
+            .ok_or_else(|| DiffApplyError::ChangesetNotFound(changeset_id.to_string()))?;
+        let entry = self.journal.load(changeset_id)?;
+        let backup_path = self.backup_dir.join(changeset_id);
+        
+        let conflicts = entry
+            .modified_files(project_root)
+            .into_iter()
+            .map(|path| {
+                let file_diff = changeset.files.iter().find(|file| file.file_path == path);
+                let before = match file_diff.map(|file| &file.change_type) {
+                    Some(ChangeType::Create) => None,
+                    _ => std::fs::read_to_string(backup_path.join(&path)).ok(),
+                };
+                let applied = file_diff
+                    .filter(|file| file.change_type != ChangeType::Delete)
+                    .map(|file| file.new_content.clone());
+                RollbackConflict {
+                    current: std::fs::read_to_string(project_root.join(&path)).ok(),
+                    path,
+                    before,
+                    applied,
+                }
+            })
+            .collect();
+        
+        Ok(conflicts)
+    }
+    
     /// Rollback a previously applied changeset
     pub async fn rollback_changeset(&mut self, changeset_id: &str, project_root: &Path) -> Result<(), DiffApplyError> {
         let changeset = self.applied_changesets.get(changeset_id)
@@ -522,11 +1186,22 @@ impl DiffApplySystem {
             ));
         }
         
+        // Never overwrite edits made after the changeset was applied
+        let conflicts = self.rollback_conflicts(changeset_id, project_root)?;
+        if !conflicts.is_empty() {
+            let paths: Vec<String> = conflicts.iter().map(|c| c.path.display().to_string()).collect();
+            return Err(DiffApplyError::FileConflict(
+                format!("modified since changeset {} was applied: {}", changeset_id, paths.join(", "))
+            ));
+        }
+        
         // Restore files from backup
         for file_diff in changeset.files.iter().rev() {
             self.rollback_file_diff(file_diff, project_root, &backup_path).await?;
         }
         
+        self.journal.set_status(changeset_id, JournalStatus::RolledBack)?;
+        
         // Remove from applied changesets
         self.applied_changesets.remove(changeset_id);
         
@@ -588,72 +1263,122 @@ impl DiffApplySystem {
         self.applied_changesets.values().collect()
     }
     
+    /// Changeset recorded in the journal, applied or rolled back
+    pub fn journaled_changeset(&self, changeset_id: &str) -> Option<ChangeSet> {
+        self.applied_changesets
+            .get(changeset_id)
+            .cloned()
+            .or_else(|| self.load_backed_up_changeset(changeset_id))
+    }
+    
+    /// Journal of applied changesets
+    pub fn journal(&self) -> &ChangeJournal {
+        &self.journal
+    }
+    
     /// Generate unified diff text for a file change
     pub fn generate_diff(original: Option<&str>, new: &str, file_path: &Path) -> String {
-        match original {
-            Some(orig) if orig != new => {
-                // Simple unified diff format
-                let mut diff = format!("--- {}\n+++ {}\n", file_path.display(), file_path.display());
-                
-                let orig_lines: Vec<&str> = orig.lines().collect();
-                let new_lines: Vec<&str> = new.lines().collect();
-                
-                // Simple line-by-line diff (could be enhanced with proper diff algorithm)
-                let max_lines = orig_lines.len().max(new_lines.len());
-                let mut context = 3; // Lines of context
-                
-                for i in 0..max_lines {
-                    let orig_line = orig_lines.get(i).unwrap_or(&"");
-                    let new_line = new_lines.get(i).unwrap_or(&"");
-                    
-                    if orig_line != new_line {
-                        if context == 3 {
-                            diff.push_str(&format!("@@ -{},{} +{},{} @@\n", 
-                                i.saturating_sub(3), orig_lines.len(),
-                                i.saturating_sub(3), new_lines.len()
-                            ));
-                        }
-                        context = 0;
-                        
-                        if !orig_line.is_empty() && orig_lines.get(i).is_some() {
-                            diff.push_str(&format!("-{}\n", orig_line));
-                        }
-                        if !new_line.is_empty() && new_lines.get(i).is_some() {
-                            diff.push_str(&format!("+{}\n", new_line));
-                        }
-                    } else if context < 3 {
-                        diff.push_str(&format!(" {}\n", orig_line));
-                        context += 1;
-                    }
-                }
-                
-                diff
+        let path = file_path.display().to_string();
+        let old_label = if original.is_some() { path.as_str() } else { "/dev/null" };
+        let original = original.unwrap_or("");
+        if original == new {
+            return String::new();
+        }
+
+        similar::TextDiff::from_lines(original, new)
+            .unified_diff()
+            .context_radius(3)
+            .header(old_label, &path)
+            .to_string()
+    }
+}
+
+// Quality Gate Implementations
+
+/// Run one kind of tool for every language a changeset touches and summarise the runs
+///
+/// Failures are reported with `failure_status`; languages whose tools are
+/// missing are skipped rather than failed.
+async fn run_toolchain_gate(
+    toolchains: &ToolchainRegistry,
+    kind: ToolKind,
+    name: &str,
+    failure_status: GateStatus,
+    changeset: &ChangeSet,
+    project_root: &Path,
+) -> GateResult {
+    let start = std::time::Instant::now();
+    let runs = toolchains.run(kind, changeset, project_root).await;
+    let duration = start.elapsed();
+    let is_blocking = failure_status == GateStatus::Failed;
+
+    let mut checked = Vec::new();
+    let mut failed = Vec::new();
+    let mut unavailable = Vec::new();
+    let mut details = Vec::new();
+    let mut diagnostics = Vec::new();
+    for run in runs {
+        match run.outcome {
+            ToolOutcome::Passed { diagnostics: warnings } => {
+                checked.push(run.language);
+                diagnostics.extend(warnings);
             }
-            None => {
-                // New file
-                let mut diff = format!("--- /dev/null\n+++ {}\n", file_path.display());
-                diff.push_str(&format!("@@ -0,0 +1,{} @@\n", new.lines().count()));
-                for line in new.lines() {
-                    diff.push_str(&format!("+{}\n", line));
-                }
-                diff
+            ToolOutcome::Failed { output, diagnostics: found } => {
+                details.push(format!("$ {}\n{}", run.command, output.trim_end()));
+                failed.push(format!("{} ({})", run.language, run.command));
+                diagnostics.extend(found);
             }
-            Some(_) => {
-                // No changes
-                String::new()
+            ToolOutcome::Unavailable(reason) => {
+                unavailable.push(format!("{}: {}", run.language, reason));
             }
         }
     }
-}
 
-// Quality Gate Implementations
+    if !failed.is_empty() {
+        GateResult {
+            name: name.to_string(),
+            status: failure_status,
+            message: format!("Failed for {}", failed.join(", ")),
+            details: Some(details.join("\n\n")),
+            diagnostics,
+            execution_time_ms: duration.as_millis() as u64,
+            is_blocking,
+        }
+    } else if !checked.is_empty() {
+        GateResult {
+            name: name.to_string(),
+            status: GateStatus::Passed,
+            message: format!("Passed for {}", checked.join(", ")),
+            details: (!unavailable.is_empty()).then(|| format!("Skipped {}", unavailable.join("; "))),
+            diagnostics,
+            execution_time_ms: duration.as_millis() as u64,
+            is_blocking,
+        }
+    } else {
+        GateResult {
+            name: name.to_string(),
+            status: GateStatus::Skipped,
+            message: "No tool available for the changed files".to_string(),
+            details: (!unavailable.is_empty()).then(|| unavailable.join("\n")),
+            diagnostics,
+            execution_time_ms: 0,
+            is_blocking: false,
+        }
+    }
+}
 
-/// Format checking gate (cargo fmt, prettier, etc.)
-pub struct FormatGate;
+/// Format checking gate (rustfmt, prettier, black, gofmt)
+pub struct FormatGate {
+    toolchains: ToolchainRegistry,
+}
 
 impl FormatGate {
     pub fn new() -> Self {
-        Self
+        Self::with_toolchains(ToolchainRegistry::default())
+    }
+
+    pub fn with_toolchains(toolchains: ToolchainRegistry) -> Self {
+        Self { toolchains }
     }
 }
 
@@ -668,61 +1393,22 @@ impl QualityGate for FormatGate {
     }
     
     async fn validate(&self, changeset: &ChangeSet, project_root: &Path) -> Result<GateResult, QualityGateError> {
-        let start = std::time::Instant::now();
-        
-        // Check if this is a Rust project
-        let is_rust = project_root.join("Cargo.toml").exists();
-        
-        if is_rust {
-            let output = AsyncCommand::new("cargo")
-                .arg("fmt")
-                .arg("--check")
-                .current_dir(project_root)
-                .output()
-                .await
-                .map_err(|e| QualityGateError::CommandFailed(format!("cargo fmt failed: {}", e)))?;
-            
-            let duration = start.elapsed();
-            
-            if output.status.success() {
-                Ok(GateResult {
-                    name: "format".to_string(),
-                    status: GateStatus::Passed,
-                    message: "All files are properly formatted".to_string(),
-                    details: None,
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: false,
-                })
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Ok(GateResult {
-                    name: "format".to_string(),
-                    status: GateStatus::Warning,
-                    message: "Some files need formatting".to_string(),
-                    details: Some(stderr.to_string()),
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: false,
-                })
-            }
-        } else {
-            Ok(GateResult {
-                name: "format".to_string(),
-                status: GateStatus::Skipped,
-                message: "No format checker found for this project type".to_string(),
-                details: None,
-                execution_time_ms: 0,
-                is_blocking: false,
-            })
-        }
+        Ok(run_toolchain_gate(&self.toolchains, ToolKind::Format, "format", GateStatus::Warning, changeset, project_root).await)
     }
 }
 
-/// Lint checking gate (clippy, eslint, etc.)
-pub struct LintGate;
+/// Lint checking gate (clippy, eslint, ruff, go vet)
+pub struct LintGate {
+    toolchains: ToolchainRegistry,
+}
 
 impl LintGate {
     pub fn new() -> Self {
-        Self
+        Self::with_toolchains(ToolchainRegistry::default())
+    }
+
+    pub fn with_toolchains(toolchains: ToolchainRegistry) -> Self {
+        Self { toolchains }
     }
 }
 
@@ -737,60 +1423,84 @@ impl QualityGate for LintGate {
     }
     
     async fn validate(&self, changeset: &ChangeSet, project_root: &Path) -> Result<GateResult, QualityGateError> {
-        let start = std::time::Instant::now();
-        
-        if project_root.join("Cargo.toml").exists() {
-            let output = AsyncCommand::new("cargo")
-                .arg("clippy")
-                .arg("--")
-                .arg("-D")
-                .arg("warnings")
-                .current_dir(project_root)
-                .output()
-                .await
-                .map_err(|e| QualityGateError::CommandFailed(format!("cargo clippy failed: {}", e)))?;
-            
-            let duration = start.elapsed();
-            
-            if output.status.success() {
-                Ok(GateResult {
-                    name: "lint".to_string(),
-                    status: GateStatus::Passed,
-                    message: "No lint issues found".to_string(),
-                    details: None,
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Ok(GateResult {
-                    name: "lint".to_string(),
-                    status: GateStatus::Failed,
-                    message: "Lint issues found".to_string(),
-                    details: Some(stderr.to_string()),
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            }
-        } else {
-            Ok(GateResult {
-                name: "lint".to_string(),
-                status: GateStatus::Skipped,
-                message: "No linter found for this project type".to_string(),
-                details: None,
-                execution_time_ms: 0,
-                is_blocking: false,
-            })
-        }
+        Ok(run_toolchain_gate(&self.toolchains, ToolKind::Lint, "lint", GateStatus::Failed, changeset, project_root).await)
     }
 }
 
 /// Compilation checking gate
-pub struct CompileGate;
+///
+/// With language servers attached, the new content of changed files is
+/// checked in memory by their servers instead of running a full build. The
+/// gate falls back to the toolchains' type-checkers, such as `cargo check`
+/// or `tsc`, when a changed file has no server.
+pub struct CompileGate {
+    toolchains: ToolchainRegistry,
+    language_servers: Option<Arc<LanguageServerPool>>,
+}
 
 impl CompileGate {
     pub fn new() -> Self {
-        Self
+        Self::with_toolchains(ToolchainRegistry::default())
+    }
+
+    pub fn with_toolchains(toolchains: ToolchainRegistry) -> Self {
+        Self { toolchains, language_servers: None }
+    }
+
+    pub fn with_language_servers(mut self, language_servers: Arc<LanguageServerPool>) -> Self {
+        self.language_servers = Some(language_servers);
+        self
+    }
+
+    /// Check changed files with language servers; `None` when they cannot answer for all of them
+    async fn check_with_language_servers(&self, changeset: &ChangeSet) -> Option<GateResult> {
+        let language_servers = self.language_servers.as_ref()?;
+        let files: Vec<&FileDiff> = changeset.files
+            .iter()
+            .filter(|file| file.change_type != ChangeType::Delete)
+            .collect();
+        if files.is_empty() || !files.iter().all(|file| language_servers.covers(&file.file_path)) {
+            return None;
+        }
+
+        let start = std::time::Instant::now();
+        let mut errors = Vec::new();
+        for file in files {
+            // Servers know the real project, not a staging copy of it
+            let path = language_servers.root().join(&file.file_path);
+            let diagnostics = language_servers.diagnostics(&path, &file.new_content).await;
+            // The change is not on disk yet; let the server read the real file again
+            language_servers.close_document(&path).await;
+            errors.extend(
+                diagnostics?
+                    .iter()
+                    .filter(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR))
+                    .map(|diagnostic| format_diagnostic(&file.file_path, diagnostic)),
+            );
+        }
+        let duration = start.elapsed();
+
+        Some(if errors.is_empty() {
+            GateResult {
+                name: "compile".to_string(),
+                status: GateStatus::Passed,
+                message: "No errors reported by language servers".to_string(),
+                details: None,
+                diagnostics: Vec::new(),
+                execution_time_ms: duration.as_millis() as u64,
+                is_blocking: true,
+            }
+        } else {
+            GateResult {
+                name: "compile".to_string(),
+                status: GateStatus::Failed,
+                message: format!("Language servers reported {} error(s)", errors.len()),
+                details: Some(errors.join("\n")),
+                diagnostics: Vec::new(),
+                execution_time_ms: duration.as_millis() as u64,
+                is_blocking: true,
+            }
+        })
     }
 }
 
@@ -805,57 +1515,26 @@ impl QualityGate for CompileGate {
     }
     
     async fn validate(&self, changeset: &ChangeSet, project_root: &Path) -> Result<GateResult, QualityGateError> {
-        let start = std::time::Instant::now();
-        
-        if project_root.join("Cargo.toml").exists() {
-            let output = AsyncCommand::new("cargo")
-                .arg("check")
-                .current_dir(project_root)
-                .output()
-                .await
-                .map_err(|e| QualityGateError::CommandFailed(format!("cargo check failed: {}", e)))?;
-            
-            let duration = start.elapsed();
-            
-            if output.status.success() {
-                Ok(GateResult {
-                    name: "compile".to_string(),
-                    status: GateStatus::Passed,
-                    message: "Code compiles successfully".to_string(),
-                    details: None,
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Ok(GateResult {
-                    name: "compile".to_string(),
-                    status: GateStatus::Failed,
-                    message: "Compilation failed".to_string(),
-                    details: Some(stderr.to_string()),
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            }
-        } else {
-            Ok(GateResult {
-                name: "compile".to_string(),
-                status: GateStatus::Skipped,
-                message: "No compilation check available for this project type".to_string(),
-                details: None,
-                execution_time_ms: 0,
-                is_blocking: false,
-            })
+        if let Some(result) = self.check_with_language_servers(changeset).await {
+            return Ok(result);
         }
+
+        Ok(run_toolchain_gate(&self.toolchains, ToolKind::TypeCheck, "compile", GateStatus::Failed, changeset, project_root).await)
     }
 }
 
 /// Test execution gate
-pub struct TestGate;
+pub struct TestGate {
+    toolchains: ToolchainRegistry,
+}
 
 impl TestGate {
     pub fn new() -> Self {
-        Self
+        Self::with_toolchains(ToolchainRegistry::default())
+    }
+
+    pub fn with_toolchains(toolchains: ToolchainRegistry) -> Self {
+        Self { toolchains }
     }
 }
 
@@ -870,49 +1549,7 @@ impl QualityGate for TestGate {
     }
     
     async fn validate(&self, changeset: &ChangeSet, project_root: &Path) -> Result<GateResult, QualityGateError> {
-        let start = std::time::Instant::now();
-        
-        if project_root.join("Cargo.toml").exists() {
-            let output = AsyncCommand::new("cargo")
-                .arg("test")
-                .current_dir(project_root)
-                .output()
-                .await
-                .map_err(|e| QualityGateError::CommandFailed(format!("cargo test failed: {}", e)))?;
-            
-            let duration = start.elapsed();
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            
-            if output.status.success() {
-                Ok(GateResult {
-                    name: "test".to_string(),
-                    status: GateStatus::Passed,
-                    message: "All tests passed".to_string(),
-                    details: Some(stdout.to_string()),
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Ok(GateResult {
-                    name: "test".to_string(),
-                    status: GateStatus::Failed,
-                    message: "Some tests failed".to_string(),
-                    details: Some(format!("STDOUT:\n{}\n\nSTDERR:\n{}", stdout, stderr)),
-                    execution_time_ms: duration.as_millis() as u64,
-                    is_blocking: true,
-                })
-            }
-        } else {
-            Ok(GateResult {
-                name: "test".to_string(),
-                status: GateStatus::Skipped,
-                message: "No test runner found for this project type".to_string(),
-                details: None,
-                execution_time_ms: 0,
-                is_blocking: false,
-            })
-        }
+        Ok(run_toolchain_gate(&self.toolchains, ToolKind::Test, "test", GateStatus::Failed, changeset, project_root).await)
     }
 }
 
@@ -955,6 +1592,7 @@ impl QualityGate for SecurityGate {
                         status: GateStatus::Passed,
                         message: "No known security vulnerabilities found".to_string(),
                         details: None,
+                        diagnostics: Vec::new(),
                         execution_time_ms: duration.as_millis() as u64,
                         is_blocking: true,
                     })
@@ -966,6 +1604,7 @@ impl QualityGate for SecurityGate {
                         status: GateStatus::Warning,
                         message: "Security scan completed with warnings".to_string(),
                         details: Some(stderr.to_string()),
+                        diagnostics: Vec::new(),
                         execution_time_ms: duration.as_millis() as u64,
                         is_blocking: false,
                     })
@@ -1030,6 +1669,7 @@ impl QualityGate for CustomGate {
                 status: GateStatus::Passed,
                 message: format!("Custom gate '{}' passed", self.name),
                 details: if !stdout.is_empty() { Some(stdout.to_string()) } else { None },
+                diagnostics: Vec::new(),
                 execution_time_ms: duration.as_millis() as u64,
                 is_blocking: true,
             })
@@ -1039,9 +1679,278 @@ impl QualityGate for CustomGate {
                 status: GateStatus::Failed,
                 message: format!("Custom gate '{}' failed", self.name),
                 details: Some(format!("STDOUT:\n{}\n\nSTDERR:\n{}", stdout, stderr)),
+                diagnostics: Vec::new(),
                 execution_time_ms: duration.as_millis() as u64,
                 is_blocking: true,
             })
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modified_file() -> FileDiff {
+        let original: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let new_content = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line 18\nline 18.5\n");
+        let path = PathBuf::from("src/lib.rs");
+        FileDiff {
+            diff_text: DiffApplySystem::generate_diff(Some(&original), &new_content, &path),
+            file_path: path,
+            original_content: Some(original),
+            new_content,
+            change_type: ChangeType::Modify,
+            metadata: DiffMetadata {
+                created_at: chrono::Utc::now(),
+                agent_id: "test".to_string(),
+                task_id: "task".to_string(),
+                confidence_score: 1.0,
+                estimated_lines_changed: 3,
+                language: Some("rust".to_string()),
+                description: String::new(),
+            },
+            hunks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn splits_separate_changes_into_hunks() {
+        let hunks = modified_file().compute_hunks();
+
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header, "@@ -1,5 +1,5 @@");
+        assert_eq!((hunks[0].added_lines(), hunks[0].removed_lines()), (1, 1));
+        assert_eq!(hunks[1].header, "@@ -16,5 +16,6 @@");
+        assert_eq!((hunks[1].added_lines(), hunks[1].removed_lines()), (1, 0));
+    }
+
+    #[test]
+    fn partial_content_applies_only_accepted_hunks() {
+        let mut file = modified_file();
+        file.split_hunks();
+        assert_eq!(file.partial_content(), file.original_content.clone().unwrap());
+
+        file.hunks[1].state = HunkState::Accepted;
+        let content = file.partial_content();
+        assert!(content.contains("line 2\n"));
+        assert!(content.contains("line 18.5\n"));
+
+        file.hunks[0].state = HunkState::Accepted;
+        assert_eq!(file.partial_content(), file.new_content);
+
+        file.hunks[0].state = HunkState::Edited("line 1\nline 2 edited\nline 3\nline 4\nline 5".to_string());
+        let content = file.partial_content();
+        assert!(content.starts_with("line 1\nline 2 edited\nline 3\n"));
+        assert!(content.contains("line 5\nline 6\n"));
+    }
+
+    #[test]
+    fn partial_content_tolerates_zero_hunk_starts() {
+        let mut file = modified_file();
+        file.split_hunks();
+        file.hunks[0].old_start = 0;
+        file.hunks[0].new_start = 0;
+        file.hunks[0].state = HunkState::Accepted;
+
+        assert!(file.partial_content().starts_with("line 1\nline two\n"));
+    }
+
+    #[test]
+    fn accepted_subset_drops_files_without_applied_hunks() {
+        let mut changeset = ChangeSet::new("t", "d", "agent", "task", vec![modified_file()]);
+        changeset.split_hunks();
+        assert!(changeset.accepted_subset().files.is_empty());
+
+        let path = PathBuf::from("src/lib.rs");
+        changeset.set_hunk_state(&path, 1, HunkState::Accepted).unwrap();
+        assert!(changeset.set_hunk_state(&path, 2, HunkState::Accepted).is_err());
+
+        let subset = changeset.accepted_subset();
+        assert_eq!(subset.id, changeset.id);
+        assert_eq!(subset.files.len(), 1);
+        assert_eq!(subset.metadata.total_lines_added, 1);
+        assert_eq!(subset.metadata.total_lines_removed, 0);
+        assert!(subset.files[0].diff_text.contains("+line 18.5"));
+    }
+
+    fn modify(path: &str, original: &str, new_content: &str) -> ChangeSet {
+        let mut file = modified_file();
+        file.file_path = PathBuf::from(path);
+        file.original_content = Some(original.to_string());
+        file.new_content = new_content.to_string();
+        file.diff_text = DiffApplySystem::generate_diff(Some(original), new_content, &file.file_path);
+        ChangeSet::new("edit", "", "agent", "task", vec![file])
+    }
+
+    #[test]
+    fn quality_gate_config_loads_toolchains_from_toml() {
+        let config: QualityGateConfig = toml::from_str(
+            r#"
+enabled_gates = ["compile"]
+
+[[toolchains]]
+language = "rust"
+patterns = ["*.rs"]
+type_check = { program = "cargo", args = ["check", "--workspace"], output = "rustc" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.enabled_gates, vec!["compile".to_string()]);
+        assert_eq!(config.staging, StagingStrategy::Copy);
+        let rust = config.toolchains.toolchain("rust").unwrap();
+        assert_eq!(rust.type_check.as_ref().unwrap().display(), "cargo check --workspace");
+        assert!(config.toolchains.toolchain("python").is_none());
+    }
+
+    #[tokio::test]
+    async fn rollback_survives_restart_and_refuses_edited_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "old\n").unwrap();
+        let changeset = modify("a.txt", "old\n", "new\n");
+
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        system.apply_changeset(&changeset, dir.path(), true).await.unwrap();
+        drop(system);
+
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        assert_eq!(system.get_applied_changesets().len(), 1);
+
+        std::fs::write(&file, "edited\n").unwrap();
+        let conflicts = system.rollback_conflicts(&changeset.id, dir.path()).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].three_way_diff().contains("+edited"));
+        assert!(matches!(
+            system.rollback_changeset(&changeset.id, dir.path()).await,
+            Err(DiffApplyError::FileConflict(_))
+        ));
+
+        std::fs::write(&file, "new\n").unwrap();
+        system.rollback_changeset(&changeset.id, dir.path()).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old\n");
+        assert_eq!(system.journal().load(&changeset.id).unwrap().status, JournalStatus::RolledBack);
+    }
+
+    #[tokio::test]
+    async fn refuses_paths_that_leave_the_project() {
+        assert!(is_contained_path(Path::new("src/lib.rs")));
+        for path in ["", "../a.txt", "src/../../a.txt", "/tmp/a.txt", "./a.txt"] {
+            assert!(!is_contained_path(Path::new(path)), "{}", path);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), &project).unwrap();
+
+        let mut changeset = modify("../a.txt", "old\n", "new\n");
+        changeset.files[0].original_content = None;
+        changeset.files[0].change_type = ChangeType::Create;
+        assert!(matches!(
+            system.apply_changeset(&changeset, &project, true).await,
+            Err(DiffApplyError::InvalidPath(_))
+        ));
+        assert!(!dir.path().join("a.txt").exists());
+
+        let mut changeset = modify("a.txt", "old\n", "new\n");
+        std::fs::write(project.join("a.txt"), "old\n").unwrap();
+        changeset.id = "../escaped".to_string();
+        assert!(matches!(
+            system.apply_changeset(&changeset, &project, true).await,
+            Err(DiffApplyError::InvalidPath(_))
+        ));
+        assert_eq!(std::fs::read_to_string(project.join("a.txt")).unwrap(), "old\n");
+    }
+
+    #[tokio::test]
+    async fn refuses_files_changed_since_the_changeset_was_made() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "edited\n").unwrap();
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+
+        let changeset = modify("a.txt", "old\n", "new\n");
+        assert!(matches!(
+            system.apply_changeset(&changeset, dir.path(), true).await,
+            Err(DiffApplyError::FileConflict(_))
+        ));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "edited\n");
+
+        let mut create = modify("a.txt", "old\n", "new\n");
+        create.files[0].original_content = None;
+        create.files[0].change_type = ChangeType::Create;
+        assert!(matches!(
+            system.apply_changeset(&create, dir.path(), true).await,
+            Err(DiffApplyError::FileConflict(_))
+        ));
+        assert!(system.get_applied_changesets().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recovers_interrupted_apply_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "old\n").unwrap();
+        let changeset = modify("a.txt", "old\n", "new\n");
+
+        // Simulate a crash after journaling but before any file was written
+        let system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        system.create_backup(&changeset, dir.path()).await.unwrap();
+        system.journal().record(&JournalEntry::for_changeset(&changeset, dir.path())).unwrap();
+        drop(system);
+
+        // Opening the system leaves the worktree alone
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "old\n");
+        assert_eq!(system.journal().load(&changeset.id).unwrap().status, JournalStatus::Applying);
+
+        let report = system.recover(dir.path()).unwrap();
+        assert_eq!(report.replayed, vec![changeset.id.clone()]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new\n");
+        assert_eq!(system.journal().load(&changeset.id).unwrap().status, JournalStatus::Applied);
+        assert_eq!(system.get_applied_changesets().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn starting_the_system_finishes_interrupted_applies() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "old\n").unwrap();
+        let changeset = modify("a.txt", "old\n", "new\n");
+
+        let system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        system.create_backup(&changeset, dir.path()).await.unwrap();
+        system.journal().record(&JournalEntry::for_changeset(&changeset, dir.path())).unwrap();
+        drop(system);
+
+        let system = DiffApplySystem::start(QualityGateConfig::default(), dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new\n");
+        assert_eq!(system.journal().load(&changeset.id).unwrap().status, JournalStatus::Applied);
+        assert_eq!(system.get_applied_changesets().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn recovery_reports_files_edited_since_the_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "old\n").unwrap();
+        let changeset = modify("a.txt", "old\n", "new\n");
+
+        let mut system = DiffApplySystem::new(QualityGateConfig::default(), dir.path()).unwrap();
+        system.create_backup(&changeset, dir.path()).await.unwrap();
+        system.journal().record(&JournalEntry::for_changeset(&changeset, dir.path())).unwrap();
+        std::fs::write(&file, "edited\n").unwrap();
+        std::fs::write(system.journal().dir().join("broken.json"), "{").unwrap();
+
+        let report = system.recover(dir.path()).unwrap();
+        assert!(report.replayed.is_empty());
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].files, vec![PathBuf::from("a.txt")]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "edited\n");
+        assert_eq!(system.journal().load(&changeset.id).unwrap().status, JournalStatus::Applying);
+    }
+}
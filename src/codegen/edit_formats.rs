@@ -0,0 +1,1195 @@
+//! Edit formats emitted by models
+//!
+//! Rewriting whole files is slow and risky for large sources, so models are
+//! asked for edits instead. This module parses the three formats they
+//! actually produce (unified diffs, search/replace blocks and whole-file
+//! fences) and turns them into `FileDiff`s against the current files.
+//!
+//! Hunks are located with whitespace-tolerant matching. A hunk that matches
+//! away from the line in its header is applied and its drift recorded; a hunk
+//! that matches nowhere, or in several places, produces an `EditError` with
+//! the closest candidate in the file, which `retry_prompt` renders as
+//! feedback for the model.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use super::diff_apply::{ChangeSet, ChangeType, DiffApplySystem, DiffMetadata, FileDiff};
+use super::language_detection::LanguageDetector;
+
+/// Format an edit was written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditFormat {
+    UnifiedDiff,
+    /// `<<<<<<< SEARCH` / `=======` / `>>>>>>> REPLACE` blocks
+    SearchReplace,
+    /// A fenced code block holding the complete new file
+    WholeFile,
+}
+
+/// One line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+/// A contiguous change: the lines it expects and what replaces them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    /// 1-based start line in the original file, from the hunk header
+    pub original_start: Option<usize>,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Search/replace block as a hunk without a position
+    pub fn replacement(search: &str, replace: &str) -> Self {
+        let lines = search
+            .lines()
+            .map(|line| HunkLine::Remove(line.to_string()))
+            .chain(replace.lines().map(|line| HunkLine::Add(line.to_string())))
+            .collect();
+        Self {
+            original_start: None,
+            lines,
+        }
+    }
+
+    /// Lines the hunk expects to find in the file
+    pub fn before(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk leaves in their place
+    pub fn after(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// What an edit does to its file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditOperation {
+    /// Hunks applied in order to the current content
+    Patch(Vec<Hunk>),
+    /// Complete new content
+    Replace(String),
+    Delete,
+}
+
+/// A parsed edit to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEdit {
+    /// Path as written by the model, relative to the project root
+    pub path: PathBuf,
+    pub format: EditFormat,
+    pub operation: EditOperation,
+}
+
+/// Why an edit could not be parsed or applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditErrorKind {
+    /// The output held no recognizable edit
+    NoEdits,
+    /// An edit block was cut off or badly formed
+    Malformed,
+    /// The path is absolute or leaves the project
+    InvalidPath,
+    /// The file to patch does not exist
+    FileNotFound,
+    /// The expected lines do not occur in the file
+    NoMatch,
+    /// The expected lines occur several times and the hunk position does not decide
+    Ambiguous,
+}
+
+/// Lines in the file most similar to a hunk that did not match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClosestMatch {
+    /// 1-based first line of the candidate
+    pub line: usize,
+    pub text: String,
+    /// Share of the hunk's lines that match, ignoring whitespace
+    pub similarity: f32,
+}
+
+/// Structured failure of an edit, suitable for feeding back to the model
+#[derive(Debug, Clone, PartialEq, Error, Serialize, Deserialize)]
+#[error("{message}")]
+pub struct EditError {
+    pub kind: EditErrorKind,
+    pub file_path: Option<PathBuf>,
+    /// 0-based index of the hunk or block within the file's edit
+    pub hunk: Option<usize>,
+    pub message: String,
+    /// Lines the edit expected to find
+    pub expected: Option<String>,
+    pub closest_match: Option<Box<ClosestMatch>>,
+}
+
+impl EditError {
+    fn new(kind: EditErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            file_path: None,
+            hunk: None,
+            message: message.into(),
+            expected: None,
+            closest_match: None,
+        }
+    }
+
+    fn in_file(mut self, path: &Path) -> Self {
+        self.file_path = Some(path.to_path_buf());
+        self
+    }
+}
+
+/// How strictly hunks must match the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchConfig {
+    /// Fall back to comparing lines with all whitespace runs collapsed
+    pub ignore_whitespace: bool,
+    /// Lines a repeated match may lie from the hunk header before it counts as ambiguous
+    pub max_drift: usize,
+    /// Minimum similarity for a near miss to be reported as the closest match
+    pub min_similarity: f32,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            ignore_whitespace: true,
+            max_drift: 30,
+            min_similarity: 0.5,
+        }
+    }
+}
+
+/// Where a hunk was applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedHunk {
+    pub hunk: usize,
+    /// 1-based line the header pointed at, adjusted for earlier hunks
+    pub expected_line: Option<usize>,
+    /// 1-based line the hunk matched
+    pub line: usize,
+    /// Matched line minus expected line; zero without a header
+    pub drift: isize,
+    /// Whether whitespace had to be ignored to match
+    pub fuzzy: bool,
+}
+
+/// Parse model output into file edits.
+///
+/// Search/replace blocks take precedence, then unified diffs; otherwise
+/// fenced code blocks labelled with a path are read as whole files.
+pub fn parse_edits(output: &str) -> Result<Vec<FileEdit>, EditError> {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+
+    let edits = if lines.iter().any(|line| is_search_marker(line)) {
+        parse_search_replace(&lines)?
+    } else if lines.iter().any(|line| line.starts_with("@@"))
+        && lines.iter().any(|line| line.starts_with("+++ "))
+    {
+        parse_unified_diff(&lines)?
+    } else {
+        parse_whole_files(&lines)
+    };
+
+    if edits.is_empty() {
+        return Err(EditError::new(
+            EditErrorKind::NoEdits,
+            "No edits found: expected a unified diff, SEARCH/REPLACE blocks or fenced files labelled with their path",
+        ));
+    }
+    Ok(edits)
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+fn is_search_marker(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("<<<<<<<") && line.ends_with("SEARCH")
+}
+
+fn is_divider(line: &str) -> bool {
+    let line = line.trim();
+    line.len() >= 5 && line.chars().all(|ch| ch == '=')
+}
+
+fn is_replace_marker(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with(">>>>>>>") && line.ends_with("REPLACE")
+}
+
+fn parse_search_replace(lines: &[&str]) -> Result<Vec<FileEdit>, EditError> {
+    let mut edits: Vec<FileEdit> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        if !is_search_marker(lines[index]) {
+            index += 1;
+            continue;
+        }
+        let marker_line = index + 1;
+
+        // The path sits on the line before the block, possibly before its fence
+        let path = lines[..index]
+            .iter()
+            .rev()
+            .filter(|line| !line.trim().is_empty())
+            .take(2)
+            .find_map(|line| fence_path(line).or_else(|| label_path(line)))
+            .or_else(|| edits.last().map(|edit| edit.path.clone()))
+            .ok_or_else(|| {
+                EditError::new(
+                    EditErrorKind::Malformed,
+                    format!(
+                        "SEARCH block at line {} is not preceded by a file path",
+                        marker_line
+                    ),
+                )
+            })?;
+
+        let mut search = Vec::new();
+        index += 1;
+        while index < lines.len() && !is_divider(lines[index]) {
+            search.push(lines[index]);
+            index += 1;
+        }
+        let mut replace = Vec::new();
+        index += 1;
+        while index < lines.len() && !is_replace_marker(lines[index]) {
+            replace.push(lines[index]);
+            index += 1;
+        }
+        if index >= lines.len() {
+            return Err(EditError::new(
+                EditErrorKind::Malformed,
+                format!(
+                    "SEARCH block at line {} is missing its ======= or >>>>>>> REPLACE marker",
+                    marker_line
+                ),
+            )
+            .in_file(&path));
+        }
+        index += 1;
+
+        let hunk = Hunk {
+            original_start: None,
+            lines: search
+                .iter()
+                .map(|line| HunkLine::Remove(line.to_string()))
+                .chain(replace.iter().map(|line| HunkLine::Add(line.to_string())))
+                .collect(),
+        };
+        match edits.last_mut() {
+            Some(FileEdit {
+                path: last,
+                operation: EditOperation::Patch(hunks),
+                ..
+            }) if *last == path => hunks.push(hunk),
+            _ => edits.push(FileEdit {
+                path,
+                format: EditFormat::SearchReplace,
+                operation: EditOperation::Patch(vec![hunk]),
+            }),
+        }
+    }
+
+    Ok(edits)
+}
+
+/// Path from a `---`/`+++` header, `None` for `/dev/null`
+fn diff_header_path(header: &str) -> Option<PathBuf> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(PathBuf::from(path))
+}
+
+/// Start line of the original side from `@@ -12,5 +12,7 @@`
+fn hunk_start(header: &str) -> Option<usize> {
+    let range = header
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('-'))?;
+    range.split(',').next()?.parse().ok()
+}
+
+fn parse_unified_diff(lines: &[&str]) -> Result<Vec<FileEdit>, EditError> {
+    struct Pending {
+        old: Option<PathBuf>,
+        new: Option<PathBuf>,
+        hunks: Vec<Hunk>,
+    }
+
+    fn finish(pending: Option<Pending>, edits: &mut Vec<FileEdit>) -> Result<(), EditError> {
+        let Some(pending) = pending else {
+            return Ok(());
+        };
+        let edit = match (pending.old, pending.new) {
+            (Some(old), None) => FileEdit {
+                path: old,
+                format: EditFormat::UnifiedDiff,
+                operation: EditOperation::Delete,
+            },
+            (_, Some(new)) if pending.hunks.is_empty() => {
+                return Err(EditError::new(
+                    EditErrorKind::Malformed,
+                    format!("Diff for {} has no hunks", new.display()),
+                )
+                .in_file(&new));
+            }
+            (_, Some(new)) => FileEdit {
+                path: new,
+                format: EditFormat::UnifiedDiff,
+                operation: EditOperation::Patch(pending.hunks),
+            },
+            (None, None) => return Ok(()),
+        };
+        edits.push(edit);
+        Ok(())
+    }
+
+    let mut edits = Vec::new();
+    let mut pending: Option<Pending> = None;
+    let mut in_hunk = false;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+
+        if line.starts_with("diff ") || is_fence(line) {
+            in_hunk = false;
+            continue;
+        }
+        if let (Some(old), Some(next)) = (line.strip_prefix("--- "), lines.get(index)) {
+            if let Some(new) = next.strip_prefix("+++ ") {
+                finish(pending.take(), &mut edits)?;
+                pending = Some(Pending {
+                    old: diff_header_path(old),
+                    new: diff_header_path(new),
+                    hunks: Vec::new(),
+                });
+                in_hunk = false;
+                index += 1;
+                continue;
+            }
+        }
+        if line.starts_with("@@") {
+            let Some(file) = pending.as_mut() else {
+                return Err(EditError::new(
+                    EditErrorKind::Malformed,
+                    format!("Hunk at line {} has no ---/+++ file header", index),
+                ));
+            };
+            file.hunks.push(Hunk {
+                original_start: hunk_start(line),
+                lines: Vec::new(),
+            });
+            in_hunk = true;
+            continue;
+        }
+        if !in_hunk {
+            continue;
+        }
+
+        let Some(hunk) = pending.as_mut().and_then(|file| file.hunks.last_mut()) else {
+            continue;
+        };
+        match line.chars().next() {
+            Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+            Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+            Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+            Some('\\') => {}
+            // Models often drop the leading space of blank context lines
+            None => hunk.lines.push(HunkLine::Context(String::new())),
+            Some(_) => in_hunk = false,
+        }
+    }
+    finish(pending, &mut edits)?;
+
+    Ok(edits)
+}
+
+fn parse_whole_files(lines: &[&str]) -> Vec<FileEdit> {
+    let mut edits = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        if !is_fence(line) {
+            index += 1;
+            continue;
+        }
+
+        let path = fence_path(line).or_else(|| {
+            lines[..index]
+                .iter()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .and_then(|line| label_path(line))
+        });
+        let start = index + 1;
+        let end = lines[start..]
+            .iter()
+            .position(|line| line.trim() == "```")
+            .map_or(lines.len(), |offset| start + offset);
+
+        if let Some(path) = path {
+            let mut content = lines[start..end].join("\n");
+            content.push('\n');
+            edits.push(FileEdit {
+                path,
+                format: EditFormat::WholeFile,
+                operation: EditOperation::Replace(content),
+            });
+        }
+        index = end + 1;
+    }
+
+    edits
+}
+
+fn looks_like_path(candidate: &str) -> bool {
+    if candidate.is_empty()
+        || candidate.contains(char::is_whitespace)
+        || candidate.contains("://")
+        || candidate.ends_with('.')
+    {
+        return false;
+    }
+    let name = candidate.rsplit('/').next().unwrap_or(candidate);
+    match name.rsplit_once('.') {
+        Some((_, extension)) => {
+            !extension.is_empty()
+                && extension.len() <= 10
+                && extension.chars().all(|ch| ch.is_ascii_alphanumeric())
+        }
+        None => matches!(name, "Makefile" | "Dockerfile" | "Justfile" | "Procfile"),
+    }
+}
+
+/// Path in a fence's info string, as in "```rust src/main.rs"
+fn fence_path(line: &str) -> Option<PathBuf> {
+    let info = line.trim_start().strip_prefix("```")?;
+    info.split_whitespace()
+        .map(|token| token.trim_matches('`'))
+        .find(|token| looks_like_path(token))
+        .map(PathBuf::from)
+}
+
+/// Path labelling a block, as in "src/main.rs", "### `src/main.rs`" or "File: src/main.rs:"
+fn label_path(line: &str) -> Option<PathBuf> {
+    let mut label = line.trim().trim_start_matches('#').trim();
+    for prefix in ["File:", "file:", "Path:", "path:", "Filename:", "filename:"] {
+        if let Some(rest) = label.strip_prefix(prefix) {
+            label = rest.trim();
+        }
+    }
+    let label = label
+        .trim_end_matches(':')
+        .trim_matches(|ch| ch == '*' || ch == '`')
+        .trim_end_matches(':');
+    looks_like_path(label).then(|| PathBuf::from(label))
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Indentation change between the hunk's lines and the file's
+#[derive(Debug, Clone, PartialEq)]
+enum Reindent {
+    Keep,
+    Add(String),
+    Remove(usize),
+}
+
+impl Reindent {
+    fn detect(file_line: &str, hunk_line: &str) -> Self {
+        let file_indent = leading_whitespace(file_line);
+        let hunk_indent = leading_whitespace(hunk_line);
+        if file_indent == hunk_indent {
+            Reindent::Keep
+        } else if let Some(extra) = file_indent.strip_suffix(hunk_indent) {
+            Reindent::Add(extra.to_string())
+        } else if hunk_indent.ends_with(file_indent) {
+            Reindent::Remove(hunk_indent.len() - file_indent.len())
+        } else {
+            Reindent::Keep
+        }
+    }
+
+    fn apply(&self, line: &str) -> String {
+        match self {
+            _ if line.trim().is_empty() => line.to_string(),
+            Reindent::Keep => line.to_string(),
+            Reindent::Add(prefix) => format!("{}{}", prefix, line),
+            Reindent::Remove(width) => {
+                let indent = leading_whitespace(line).len();
+                line[indent.min(*width)..].to_string()
+            }
+        }
+    }
+}
+
+/// Positions where `block` occurs, with the strictest comparison that finds any
+fn find_block(lines: &[String], block: &[&str], config: &MatchConfig) -> (Vec<usize>, bool) {
+    let positions = |eq: &dyn Fn(&str, &str) -> bool| -> Vec<usize> {
+        if block.len() > lines.len() {
+            return Vec::new();
+        }
+        (0..=lines.len() - block.len())
+            .filter(|&start| {
+                block
+                    .iter()
+                    .enumerate()
+                    .all(|(offset, expected)| eq(lines[start + offset].as_str(), expected))
+            })
+            .collect()
+    };
+
+    let exact = positions(&|a, b| a == b);
+    if !exact.is_empty() {
+        return (exact, false);
+    }
+    let trailing = positions(&|a, b| a.trim_end() == b.trim_end());
+    if !trailing.is_empty() || !config.ignore_whitespace {
+        return (trailing, false);
+    }
+    (positions(&|a, b| normalize(a) == normalize(b)), true)
+}
+
+/// The window of the file most similar to `block`
+fn closest_match(
+    lines: &[String],
+    block: &[&str],
+    expected: Option<usize>,
+    config: &MatchConfig,
+) -> Option<ClosestMatch> {
+    if block.is_empty() || lines.is_empty() {
+        return None;
+    }
+    let window = block.len().min(lines.len());
+    let block: Vec<String> = block.iter().map(|line| normalize(line)).collect();
+    let lines_normalized: Vec<String> = lines.iter().map(|line| normalize(line)).collect();
+
+    let mut best: Option<(usize, usize)> = None;
+    for start in 0..=lines.len() - window {
+        let matching = (0..window)
+            .filter(|&offset| lines_normalized[start + offset] == block[offset])
+            .count();
+        let better = match best {
+            None => true,
+            Some((best_start, best_matching)) => {
+                matching > best_matching
+                    || (matching == best_matching
+                        && expected.is_some_and(|expected| {
+                            start.abs_diff(expected) < best_start.abs_diff(expected)
+                        }))
+            }
+        };
+        if better {
+            best = Some((start, matching));
+        }
+    }
+
+    let (start, matching) = best?;
+    let similarity = matching as f32 / block.len() as f32;
+    (similarity >= config.min_similarity).then(|| ClosestMatch {
+        line: start + 1,
+        text: lines[start..start + window].join("\n"),
+        similarity,
+    })
+}
+
+/// Apply hunks in order, returning the new content and where each hunk landed.
+///
+/// Hunks that fail are skipped so every failure is reported at once.
+pub fn apply_hunks(
+    original: &str,
+    hunks: &[Hunk],
+    config: &MatchConfig,
+) -> Result<(String, Vec<AppliedHunk>), Vec<EditError>> {
+    let line_ending = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let mut applied = Vec::new();
+    let mut errors = Vec::new();
+    // Line count change from earlier hunks, to adjust later headers
+    let mut offset: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let before = hunk.before();
+        let expected = hunk
+            .original_start
+            .map(|start| (start.saturating_sub(1) as isize + offset).max(0) as usize);
+
+        let (start, fuzzy) = if before.is_empty() {
+            // Pure insertion: at the header position, or appended
+            (expected.unwrap_or(lines.len()).min(lines.len()), false)
+        } else {
+            let (candidates, fuzzy) = find_block(&lines, &before, config);
+            let chosen = match (candidates.len(), expected) {
+                (0, _) => None,
+                (1, _) => Some(candidates[0]),
+                (_, Some(expected)) => {
+                    let mut by_distance = candidates.clone();
+                    by_distance.sort_by_key(|start| start.abs_diff(expected));
+                    let nearest = by_distance[0];
+                    let tie = by_distance[1].abs_diff(expected) == nearest.abs_diff(expected);
+                    (!tie && nearest.abs_diff(expected) <= config.max_drift).then_some(nearest)
+                }
+                (_, None) => None,
+            };
+
+            let Some(start) = chosen else {
+                let mut error = if candidates.is_empty() {
+                    let mut error = EditError::new(
+                        EditErrorKind::NoMatch,
+                        format!("Hunk {} does not match the file", index + 1),
+                    );
+                    error.closest_match =
+                        closest_match(&lines, &before, expected, config).map(Box::new);
+                    error
+                } else {
+                    let lines: Vec<String> = candidates
+                        .iter()
+                        .map(|start| (start + 1).to_string())
+                        .collect();
+                    EditError::new(
+                        EditErrorKind::Ambiguous,
+                        format!(
+                            "Hunk {} matches {} places (lines {}); include more context",
+                            index + 1,
+                            candidates.len(),
+                            lines.join(", ")
+                        ),
+                    )
+                };
+                error.hunk = Some(index);
+                error.expected = Some(before.join("\n"));
+                errors.push(error);
+                continue;
+            };
+            (start, fuzzy)
+        };
+
+        // Added lines follow the indentation of the nearest matched line before them
+        let mut reindent = match before.iter().position(|line| !line.trim().is_empty()) {
+            Some(first) if fuzzy => Reindent::detect(&lines[start + first], before[first]),
+            _ => Reindent::Keep,
+        };
+
+        // Context lines keep the file's text
+        let mut replacement = Vec::new();
+        let mut cursor = start;
+        for line in &hunk.lines {
+            match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => {
+                    if fuzzy && !text.trim().is_empty() {
+                        reindent = Reindent::detect(&lines[cursor], text);
+                    }
+                    if matches!(line, HunkLine::Context(_)) {
+                        replacement.push(lines[cursor].clone());
+                    }
+                    cursor += 1;
+                }
+                HunkLine::Add(text) => replacement.push(reindent.apply(text)),
+            }
+        }
+        let added = replacement.len() as isize - before.len() as isize;
+        lines.splice(start..start + before.len(), replacement);
+        offset += added;
+
+        applied.push(AppliedHunk {
+            hunk: index,
+            expected_line: expected.map(|line| line + 1),
+            line: start + 1,
+            drift: expected.map_or(0, |expected| start as isize - expected as isize),
+            fuzzy,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut content = lines.join(line_ending);
+    if !lines.is_empty() && (original.is_empty() || original.ends_with('\n')) {
+        content.push_str(line_ending);
+    }
+    Ok((content, applied))
+}
+
+/// Feedback asking the model to resend the edits that failed
+pub fn retry_prompt(errors: &[EditError]) -> String {
+    let mut prompt = String::from(
+        "Some edits could not be applied. Resend only these edits, copying the lines to replace exactly as they appear in the current file.\n",
+    );
+    for error in errors {
+        prompt.push('\n');
+        match &error.file_path {
+            Some(path) => prompt.push_str(&format!("- {}: {}\n", path.display(), error.message)),
+            None => prompt.push_str(&format!("- {}\n", error.message)),
+        }
+        if let Some(expected) = &error.expected {
+            prompt.push_str(&format!("  Expected:\n```\n{}\n```\n", expected));
+        }
+        if let Some(closest) = &error.closest_match {
+            prompt.push_str(&format!(
+                "  Closest match at line {} ({:.0}% similar):\n```\n{}\n```\n",
+                closest.line,
+                closest.similarity * 100.0,
+                closest.text
+            ));
+        }
+    }
+    prompt
+}
+
+/// A file diff built from an edit, with where its hunks landed
+#[derive(Debug, Clone)]
+pub struct AppliedEdit {
+    pub diff: FileDiff,
+    pub hunks: Vec<AppliedHunk>,
+}
+
+/// Builds `FileDiff`s from parsed edits against the files under a root
+#[derive(Debug, Clone)]
+pub struct EditApplier {
+    root: PathBuf,
+    config: MatchConfig,
+}
+
+impl EditApplier {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            config: MatchConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: MatchConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Reject paths that are absolute or climb out of the root
+    fn check_path(&self, path: &Path) -> Result<(), EditError> {
+        let escapes = path.components().any(|component| {
+            matches!(
+                component,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        });
+        if escapes || path.as_os_str().is_empty() {
+            return Err(EditError::new(
+                EditErrorKind::InvalidPath,
+                format!("{} is not a path inside the project", path.display()),
+            )
+            .in_file(path));
+        }
+        Ok(())
+    }
+
+    /// Apply one edit to the current file content
+    pub async fn apply(
+        &self,
+        edit: &FileEdit,
+        agent_id: &str,
+        task_id: &str,
+    ) -> Result<AppliedEdit, Vec<EditError>> {
+        self.check_path(&edit.path).map_err(|e| vec![e])?;
+        let original = tokio::fs::read_to_string(self.root.join(&edit.path))
+            .await
+            .ok();
+
+        let (change_type, new_content, hunks) = match &edit.operation {
+            EditOperation::Delete if original.is_none() => {
+                return Err(vec![EditError::new(
+                    EditErrorKind::FileNotFound,
+                    format!("Cannot delete {}: file does not exist", edit.path.display()),
+                )
+                .in_file(&edit.path)]);
+            }
+            EditOperation::Delete => (ChangeType::Delete, String::new(), Vec::new()),
+            EditOperation::Replace(content) => {
+                let change_type = if original.is_some() {
+                    ChangeType::Modify
+                } else {
+                    ChangeType::Create
+                };
+                (change_type, content.clone(), Vec::new())
+            }
+            EditOperation::Patch(patch) => {
+                // A patch made only of insertions may create the file
+                let creates =
+                    original.is_none() && patch.iter().all(|hunk| hunk.before().is_empty());
+                if original.is_none() && !creates {
+                    return Err(vec![EditError::new(
+                        EditErrorKind::FileNotFound,
+                        format!("Cannot patch {}: file does not exist", edit.path.display()),
+                    )
+                    .in_file(&edit.path)]);
+                }
+                let (content, hunks) =
+                    apply_hunks(original.as_deref().unwrap_or(""), patch, &self.config).map_err(
+                        |errors| {
+                            errors
+                                .into_iter()
+                                .map(|error| error.in_file(&edit.path))
+                                .collect::<Vec<_>>()
+                        },
+                    )?;
+                let change_type = if creates {
+                    ChangeType::Create
+                } else {
+                    ChangeType::Modify
+                };
+                (change_type, content, hunks)
+            }
+        };
+
+        let diff_text =
+            DiffApplySystem::generate_diff(original.as_deref(), &new_content, &edit.path);
+        let lines_changed = diff_text
+            .lines()
+            .filter(|line| {
+                (line.starts_with('+') && !line.starts_with("+++"))
+                    || (line.starts_with('-') && !line.starts_with("---"))
+            })
+            .count();
+        let fuzzy = hunks.iter().filter(|hunk| hunk.fuzzy).count();
+        let max_drift = hunks
+            .iter()
+            .map(|hunk| hunk.drift.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        let mut description = format!("{:?} edit", edit.format);
+        if !hunks.is_empty() {
+            description.push_str(&format!(
+                ", {} hunk{} ({} fuzzy, max drift {} lines)",
+                hunks.len(),
+                if hunks.len() == 1 { "" } else { "s" },
+                fuzzy,
+                max_drift
+            ));
+        }
+
+        let filename = edit.path.to_string_lossy();
+        Ok(AppliedEdit {
+            diff: FileDiff {
+                file_path: edit.path.clone(),
+                original_content: original,
+                new_content,
+                diff_text,
+                change_type,
+                metadata: DiffMetadata {
+                    created_at: chrono::Utc::now(),
+                    agent_id: agent_id.to_string(),
+                    task_id: task_id.to_string(),
+                    // Fuzzy or drifted hunks deserve a closer look
+                    confidence_score: if fuzzy > 0 || max_drift > 0 { 0.8 } else { 1.0 },
+                    estimated_lines_changed: lines_changed,
+                    language: LanguageDetector::new().detect_from_extension(&filename),
+                    description,
+                },
+                hunks: Vec::new(),
+            },
+            hunks,
+        })
+    }
+
+    /// Parse model output and build a changeset, collecting every failure
+    pub async fn changeset(
+        &self,
+        output: &str,
+        title: &str,
+        agent_id: &str,
+        task_id: &str,
+    ) -> Result<ChangeSet, Vec<EditError>> {
+        let edits = parse_edits(output).map_err(|e| vec![e])?;
+
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        for edit in &edits {
+            match self.apply(edit, agent_id, task_id).await {
+                Ok(applied) => files.push(applied.diff),
+                Err(failed) => errors.extend(failed),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let description = files
+            .iter()
+            .map(|file| {
+                format!(
+                    "{}: {}",
+                    file.file_path.display(),
+                    file.metadata.description
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(ChangeSet::new(
+            title,
+            &description,
+            agent_id,
+            task_id,
+            files,
+        ))
+    }
+
+    /// Build a changeset, sending failed edits back with [`retry_prompt`]
+    ///
+    /// `resend` gets the rejected output and the retry prompt and returns the
+    /// model's next answer, or `None` if there is none. Output without any
+    /// edit is not retried, and the errors of the last attempt are returned
+    /// once `max_retries` resends have failed.
+    pub async fn changeset_with_retries<F, Fut>(
+        &self,
+        output: &str,
+        title: &str,
+        agent_id: &str,
+        task_id: &str,
+        max_retries: usize,
+        mut resend: F,
+    ) -> Result<ChangeSet, Vec<EditError>>
+    where
+        F: FnMut(String, String) -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        let mut output = output.to_string();
+        let mut retries = 0;
+        loop {
+            let errors = match self.changeset(&output, title, agent_id, task_id).await {
+                Ok(changeset) => return Ok(changeset),
+                Err(errors) => errors,
+            };
+            let no_edits = errors
+                .iter()
+                .all(|error| error.kind == EditErrorKind::NoEdits);
+            if no_edits || retries == max_retries {
+                return Err(errors);
+            }
+
+            retries += 1;
+            match resend(output, retry_prompt(&errors)).await {
+                Some(next) => output = next,
+                None => return Err(errors),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let total = add(1, 2);\n    println!(\"{}\", total);\n}\n\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n";
+
+    #[test]
+    fn test_parse_formats() {
+        let diff = "Here is the fix:\n```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -6,3 +6,3 @@\n fn add(a: i32, b: i32) -> i32 {\n-    a + b\n+    a.saturating_add(b)\n }\n```\n";
+        let edits = parse_edits(diff).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].path, PathBuf::from("src/main.rs"));
+        let EditOperation::Patch(hunks) = &edits[0].operation else {
+            panic!("expected a patch");
+        };
+        assert_eq!(hunks[0].original_start, Some(6));
+        assert_eq!(hunks[0].before().len(), 3);
+
+        let blocks = "src/main.rs\n```rust\n<<<<<<< SEARCH\n    a + b\n=======\n    a.saturating_add(b)\n>>>>>>> REPLACE\n```\n\n```rust\n<<<<<<< SEARCH\nfn main() {\n=======\npub fn main() {\n>>>>>>> REPLACE\n```\n";
+        let edits = parse_edits(blocks).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].format, EditFormat::SearchReplace);
+        assert!(matches!(&edits[0].operation, EditOperation::Patch(hunks) if hunks.len() == 2));
+
+        let whole = "**src/lib.rs**\n```rust\npub fn one() -> u32 { 1 }\n```\n\n```toml Cargo.toml\n[package]\n```\n";
+        let edits = parse_edits(whole).unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(
+            edits[0].operation,
+            EditOperation::Replace("pub fn one() -> u32 { 1 }\n".to_string())
+        );
+        assert_eq!(edits[1].path, PathBuf::from("Cargo.toml"));
+
+        let error = parse_edits("Just use a HashMap.").unwrap_err();
+        assert_eq!(error.kind, EditErrorKind::NoEdits);
+    }
+
+    #[test]
+    fn test_apply_hunks_with_drift_and_whitespace() {
+        // Header points at line 2 but the code moved down; the model also lost the indentation
+        let hunk = Hunk {
+            original_start: Some(2),
+            lines: vec![
+                HunkLine::Context("fn add(a: i32, b: i32) -> i32 {".to_string()),
+                HunkLine::Remove("a + b".to_string()),
+                HunkLine::Add("a.saturating_add(b)".to_string()),
+                HunkLine::Context("}".to_string()),
+            ],
+        };
+        let (content, applied) = apply_hunks(SOURCE, &[hunk], &MatchConfig::default()).unwrap();
+        assert!(content.contains("fn add(a: i32, b: i32) -> i32 {\n    a.saturating_add(b)\n}\n"));
+        assert_eq!(applied[0].line, 6);
+        assert_eq!(applied[0].drift, 4);
+        assert!(applied[0].fuzzy);
+
+        let strict = MatchConfig {
+            ignore_whitespace: false,
+            ..MatchConfig::default()
+        };
+        let hunk = Hunk::replacement("a + b", "a - b");
+        assert!(apply_hunks(SOURCE, &[hunk], &strict).is_err());
+    }
+
+    #[test]
+    fn test_mismatch_reports_closest_match_for_retry() {
+        let hunks = vec![
+            Hunk::replacement(
+                "    let total = add(1, 3);\n    println!(\"{}\", total);",
+                "    let total = add(1, 3);",
+            ),
+            Hunk::replacement("}", "};"),
+        ];
+        let errors = apply_hunks(SOURCE, &hunks, &MatchConfig::default()).unwrap_err();
+        assert_eq!(errors.len(), 2);
+
+        assert_eq!(errors[0].kind, EditErrorKind::NoMatch);
+        let closest = errors[0].closest_match.as_ref().unwrap();
+        assert_eq!(closest.line, 2);
+        assert!((closest.similarity - 0.5).abs() < f32::EPSILON);
+
+        assert_eq!(errors[1].kind, EditErrorKind::Ambiguous);
+        assert_eq!(errors[1].hunk, Some(1));
+
+        let prompt = retry_prompt(&errors);
+        assert!(prompt.contains("Closest match at line 2 (50% similar)"));
+        assert!(prompt.contains("include more context"));
+    }
+
+    #[tokio::test]
+    async fn test_changeset_from_model_output() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), SOURCE).unwrap();
+        let applier = EditApplier::new(dir.path());
+
+        let output = "src/main.rs\n<<<<<<< SEARCH\n    a + b\n=======\n    a.saturating_add(b)\n>>>>>>> REPLACE\n\nsrc/util.rs\n<<<<<<< SEARCH\n=======\npub fn helper() {}\n>>>>>>> REPLACE\n";
+        let changeset = applier
+            .changeset(output, "Saturate", "agent", "task")
+            .await
+            .unwrap();
+        assert_eq!(changeset.files.len(), 2);
+        assert_eq!(changeset.files[0].change_type, ChangeType::Modify);
+        assert!(changeset.files[0]
+            .new_content
+            .contains("    a.saturating_add(b)\n"));
+        assert!(changeset.files[0]
+            .diff_text
+            .contains("+    a.saturating_add(b)"));
+        assert_eq!(changeset.files[1].change_type, ChangeType::Create);
+        assert_eq!(changeset.files[1].new_content, "pub fn helper() {}\n");
+        assert_eq!(changeset.metadata.total_lines_added, 2);
+        assert_eq!(changeset.metadata.total_lines_removed, 1);
+
+        let escape = "../outside.rs\n<<<<<<< SEARCH\n=======\nfn x() {}\n>>>>>>> REPLACE\n";
+        let errors = applier
+            .changeset(escape, "Escape", "agent", "task")
+            .await
+            .unwrap_err();
+        assert_eq!(errors[0].kind, EditErrorKind::InvalidPath);
+    }
+
+    #[tokio::test]
+    async fn test_changeset_retries_failed_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), SOURCE).unwrap();
+        let applier = EditApplier::new(dir.path());
+
+        let stale = "src/main.rs\n<<<<<<< SEARCH\n    a - b\n=======\n    a.saturating_sub(b)\n>>>>>>> REPLACE\n";
+        let fixed = "src/main.rs\n<<<<<<< SEARCH\n    a + b\n=======\n    a.saturating_add(b)\n>>>>>>> REPLACE\n";
+        let mut prompts = Vec::new();
+        let changeset = applier
+            .changeset_with_retries(stale, "Saturate", "agent", "task", 2, |rejected, prompt| {
+                assert_eq!(rejected, stale);
+                prompts.push(prompt);
+                let next = fixed.to_string();
+                async move { Some(next) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("src/main.rs"));
+        assert!(changeset.files[0]
+            .new_content
+            .contains("a.saturating_add(b)"));
+
+        // Plain code is not an edit and is not retried
+        let mut resent = 0;
+        let errors = applier
+            .changeset_with_retries("no edits here", "None", "agent", "task", 2, |_, _| {
+                resent += 1;
+                async { None }
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(errors[0].kind, EditErrorKind::NoEdits);
+        assert_eq!(resent, 0);
+
+        let errors = applier
+            .changeset_with_retries(
+                stale,
+                "Stale",
+                "agent",
+                "task",
+                1,
+                |rejected, _| async move { Some(rejected) },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(errors[0].kind, EditErrorKind::NoMatch);
+    }
+}
@@ -178,6 +178,7 @@ function {name}({params}) {{
                     context_files_analyzed: 1,
                     template_used: Some("template_fallback".to_string()),
                 },
+                changeset: None,
             })
         }
     }
@@ -249,6 +250,7 @@ function {name}({params}) {{
                 context_files_analyzed: context.files.len(),
                 template_used: Some("ai_generation".to_string()),
             },
+            changeset: None,
         })
     }
 
@@ -503,6 +505,7 @@ function {name}({params}) {{
                             context_files_analyzed: 1,
                             template_used: Some("extract_function".to_string()),
                         },
+                        changeset: None,
                     })
                 } else {
                     Err(CodeGenError::InvalidConfig(
@@ -526,6 +529,7 @@ function {name}({params}) {{
                         context_files_analyzed: 1,
                         template_used: Some("rename_symbol".to_string()),
                     },
+                    changeset: None,
                 })
             }
             RefactorType::SimplifyLogic => {
@@ -548,6 +552,7 @@ function {name}({params}) {{
                         context_files_analyzed: 1,
                         template_used: Some("simplify_logic".to_string()),
                     },
+                    changeset: None,
                 })
             }
             RefactorType::OptimizePerformance => {
@@ -570,6 +575,7 @@ function {name}({params}) {{
                         context_files_analyzed: 1,
                         template_used: Some("optimize_performance".to_string()),
                     },
+                    changeset: None,
                 })
             }
             RefactorType::ImproveReadability => {
@@ -598,6 +604,7 @@ function {name}({params}) {{
                         context_files_analyzed: 1,
                         template_used: Some("improve_readability".to_string()),
                     },
+                    changeset: None,
                 })
             }
         }
@@ -0,0 +1,309 @@
+//! Persistent journal of applied changesets
+//!
+//! Every apply is recorded under `.devkit/journal` before any file is touched
+//! and marked complete afterwards, together with content hashes of each file
+//! before and after the change. An entry still marked as applying after a
+//! crash is replayed when the apply system next starts
+//! (`DiffApplySystem::start`) where the hashes show the files are untouched,
+//! and the after-hashes let a rollback detect files that were edited since.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use super::diff_apply::{ChangeSet, ChangeType};
+
+/// Progress of a journaled changeset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalStatus {
+    /// Recorded, but not every file has been written yet
+    Applying,
+    Applied,
+    RolledBack,
+}
+
+/// One file of a journaled changeset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalFile {
+    pub path: PathBuf,
+    pub change_type: ChangeType,
+    /// Hash of the file before the apply; `None` if it did not exist
+    pub before_hash: Option<String>,
+    /// Hash of the file after the apply; `None` if the apply removes it
+    pub after_hash: Option<String>,
+}
+
+/// Record of an applied changeset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub changeset_id: String,
+    pub title: String,
+    pub agent_id: String,
+    pub task_id: String,
+    pub status: JournalStatus,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub files: Vec<JournalFile>,
+}
+
+impl JournalEntry {
+    /// Entry for a changeset about to be applied, hashing the files as they are now
+    pub fn for_changeset(changeset: &ChangeSet, project_root: &Path) -> Self {
+        let files = changeset
+            .files
+            .iter()
+            .map(|file| {
+                let before_path = match &file.change_type {
+                    ChangeType::Rename { old_path } => old_path,
+                    _ => &file.file_path,
+                };
+                JournalFile {
+                    path: file.file_path.clone(),
+                    change_type: file.change_type.clone(),
+                    before_hash: file_hash(&project_root.join(before_path)),
+                    after_hash: match file.change_type {
+                        ChangeType::Delete => None,
+                        _ => Some(content_hash(&file.new_content)),
+                    },
+                }
+            })
+            .collect();
+
+        let now = chrono::Utc::now();
+        Self {
+            changeset_id: changeset.id.clone(),
+            title: changeset.title.clone(),
+            agent_id: changeset.metadata.agent_id.clone(),
+            task_id: changeset.metadata.task_id.clone(),
+            status: JournalStatus::Applying,
+            recorded_at: now,
+            updated_at: now,
+            files,
+        }
+    }
+
+    /// Files whose content no longer matches what the apply wrote
+    pub fn modified_files(&self, project_root: &Path) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|file| file_hash(&project_root.join(&file.path)) != file.after_hash)
+            .map(|file| file.path.clone())
+            .collect()
+    }
+}
+
+/// Errors reading or writing the journal
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Corrupt journal entry {path}: {message}")]
+    Corrupt { path: PathBuf, message: String },
+
+    #[error("No journaled changeset matches '{0}'")]
+    NotFound(String),
+
+    #[error("'{0}' matches more than one journaled changeset")]
+    Ambiguous(String),
+}
+
+/// Journal directory of a project
+#[derive(Debug, Clone)]
+pub struct ChangeJournal {
+    dir: PathBuf,
+}
+
+impl ChangeJournal {
+    /// Open the journal under `.devkit/journal`, creating it if needed
+    pub fn open(project_root: &Path) -> Result<Self, JournalError> {
+        let dir = project_root.join(".devkit").join("journal");
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, changeset_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", changeset_id))
+    }
+
+    /// Write an entry, replacing any previous record of the same changeset
+    pub fn record(&self, entry: &JournalEntry) -> Result<(), JournalError> {
+        let json = serde_json::to_string_pretty(entry).map_err(|e| JournalError::Corrupt {
+            path: self.entry_path(&entry.changeset_id),
+            message: e.to_string(),
+        })?;
+        write_atomic(&self.entry_path(&entry.changeset_id), json.as_bytes())?;
+        Ok(())
+    }
+
+    /// Update the status of a recorded entry
+    pub fn set_status(&self, changeset_id: &str, status: JournalStatus) -> Result<JournalEntry, JournalError> {
+        let mut entry = self.load(changeset_id)?;
+        entry.status = status;
+        entry.updated_at = chrono::Utc::now();
+        self.record(&entry)?;
+        Ok(entry)
+    }
+
+    /// Entry for an exact changeset id
+    pub fn load(&self, changeset_id: &str) -> Result<JournalEntry, JournalError> {
+        let path = self.entry_path(changeset_id);
+        if !path.exists() {
+            return Err(JournalError::NotFound(changeset_id.to_string()));
+        }
+        read_entry(&path)
+    }
+
+    /// Entry whose id starts with `prefix`
+    pub fn find(&self, prefix: &str) -> Result<JournalEntry, JournalError> {
+        let mut matches = self
+            .entries()?
+            .into_iter()
+            .filter(|entry| entry.changeset_id.starts_with(prefix));
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(entry),
+            (Some(_), Some(_)) => Err(JournalError::Ambiguous(prefix.to_string())),
+            (None, _) => Err(JournalError::NotFound(prefix.to_string())),
+        }
+    }
+
+    /// All readable entries, most recent first; unreadable ones are skipped
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, JournalError> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match read_entry(&path) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => tracing::warn!("Skipping journal entry: {}", e),
+            }
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.recorded_at));
+        Ok(entries)
+    }
+}
+
+fn read_entry(path: &Path) -> Result<JournalEntry, JournalError> {
+    let json = fs::read_to_string(path)?;
+    serde_json::from_str(&json).map_err(|e| JournalError::Corrupt {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+/// Hash used to recognise file content in the journal
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", md5::compute(content.as_bytes()))
+}
+
+/// Hash of a file on disk; `None` if it does not exist
+pub fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path)
+        .ok()
+        .map(|bytes| format!("{:x}", md5::compute(bytes)))
+}
+
+/// Write a file by renaming a fully written sibling temp file over it
+///
+/// Readers see either the old or the new content, never a partial write. The
+/// temp file is synced before the rename and the directory after it, so the
+/// new content survives a crash once this returns.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = parent.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    let result = write_synced(&temp_path, contents).and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+    sync_dir(parent)
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Persist a rename by syncing the directory holding it
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Directories can't be opened for syncing on Windows, where the rename is
+/// durable once it returns
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> JournalEntry {
+        let now = chrono::Utc::now();
+        JournalEntry {
+            changeset_id: id.to_string(),
+            title: "title".to_string(),
+            agent_id: "agent".to_string(),
+            task_id: "task".to_string(),
+            status: JournalStatus::Applying,
+            recorded_at: now,
+            updated_at: now,
+            files: vec![JournalFile {
+                path: PathBuf::from("a.txt"),
+                change_type: ChangeType::Modify,
+                before_hash: Some(content_hash("old\n")),
+                after_hash: Some(content_hash("new\n")),
+            }],
+        }
+    }
+
+    #[test]
+    fn records_and_finds_entries_by_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = ChangeJournal::open(dir.path()).unwrap();
+        journal.record(&entry("abc123")).unwrap();
+        journal.record(&entry("abd456")).unwrap();
+
+        assert_eq!(journal.find("abc").unwrap().changeset_id, "abc123");
+        assert!(matches!(journal.find("ab"), Err(JournalError::Ambiguous(_))));
+        assert!(matches!(journal.find("x"), Err(JournalError::NotFound(_))));
+
+        journal.set_status("abc123", JournalStatus::Applied).unwrap();
+        assert_eq!(journal.load("abc123").unwrap().status, JournalStatus::Applied);
+        assert_eq!(journal.entries().unwrap().len(), 2);
+
+        fs::write(journal.dir().join("broken.json"), "{").unwrap();
+        assert_eq!(journal.entries().unwrap().len(), 2);
+        assert!(matches!(journal.load("broken"), Err(JournalError::Corrupt { .. })));
+    }
+
+    #[test]
+    fn detects_files_modified_after_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = entry("id");
+
+        write_atomic(&dir.path().join("a.txt"), b"new\n").unwrap();
+        assert!(entry.modified_files(dir.path()).is_empty());
+
+        write_atomic(&dir.path().join("a.txt"), b"edited\n").unwrap();
+        assert_eq!(entry.modified_files(dir.path()), vec![PathBuf::from("a.txt")]);
+    }
+}
@@ -5,17 +5,30 @@
 //! suggestions based on codebase context.
 
 pub mod analyzer;
+pub mod codemod;
 pub mod diff_apply;
+pub mod edit_formats;
 pub mod generator;
+pub mod journal;
 pub mod language_detection;
+pub mod refactor;
+pub mod repair;
+pub mod stacks;
+pub mod staging;
 pub mod stubs;
 pub mod templates;
+pub mod test_first;
+pub mod toolchain;
 
-use crate::ai::AIManager;
+use crate::ai::{AIManager, ChatMessage, ChatRequest};
 use crate::context::CodebaseContext;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
+/// Times a model is asked to resend edits that did not apply
+const MAX_EDIT_RETRIES: usize = 2;
+
 /// Main code generation engine
 #[derive(Debug, Clone)]
 pub struct CodeGenerator {
@@ -95,6 +108,9 @@ pub struct GenerationResult {
     pub suggestions: Vec<String>,
     pub modifications: Vec<CodeModification>,
     pub metadata: GenerationMetadata,
+    /// Edits to existing files when the model answered with an edit format
+    #[serde(default)]
+    pub changeset: Option<diff_apply::ChangeSet>,
 }
 
 /// Metadata about the generation process
@@ -181,14 +197,24 @@ impl CodeGenerator {
 
         // Use AI-powered generation if available, otherwise fallback to templates
         if self.has_ai() {
-            self.generator
+            let mut result = self
+                .generator
                 .generate_with_ai(
                     &request.prompt,
                     &language,
                     &request.context,
                     &request.config,
                 )
-                .await
+                .await?;
+            result.changeset = self
+                .model_changeset(
+                    &request.context.root_path,
+                    &request.prompt,
+                    vec![ChatMessage::user(request.prompt.clone())],
+                    &result.generated_code,
+                )
+                .await?;
+            Ok(result)
         } else {
             // Fallback to template-based generation
             let start_time = std::time::Instant::now();
@@ -220,6 +246,7 @@ impl CodeGenerator {
                     context_files_analyzed: request.context.files.len(),
                     template_used: None, // Will be populated by template selection logic
                 },
+                changeset: None,
             })
         }
     }
@@ -255,7 +282,86 @@ impl CodeGenerator {
         refactor_type: RefactorType,
         context: &CodebaseContext,
     ) -> Result<GenerationResult, CodeGenError> {
-        self.generator.refactor(code, refactor_type, context).await
+        let mut result = self
+            .generator
+            .refactor(code, refactor_type.clone(), context)
+            .await?;
+
+        // With a model, the refactoring is applied as edits to the project files
+        if let Some(ai_manager) = &self.ai_manager {
+            let messages = vec![
+                ChatMessage::system(
+                    "You are an expert software developer refactoring code. Answer with SEARCH/REPLACE blocks, \
+                    each preceded by the path of the file it edits relative to the project root.",
+                ),
+                ChatMessage::user(format!("Refactoring: {:?}\n\n```\n{}\n```", refactor_type, code)),
+            ];
+            let output = chat(ai_manager, messages.clone()).await?;
+            result.changeset = self
+                .model_changeset(&context.root_path, "Refactor", messages, &output)
+                .await?;
+        }
+
+        Ok(result)
+    }
+
+    /// Edits in model output as a changeset against the files under `root`
+    ///
+    /// Edits that do not apply are sent back to the model with the errors up
+    /// to `MAX_EDIT_RETRIES` times. Output without edits, such as a new
+    /// file's plain code, yields `None`.
+    async fn model_changeset(
+        &self,
+        root: &Path,
+        title: &str,
+        mut messages: Vec<ChatMessage>,
+        output: &str,
+    ) -> Result<Option<diff_apply::ChangeSet>, CodeGenError> {
+        let Some(ai_manager) = self.ai_manager.clone() else {
+            return Ok(None);
+        };
+
+        let applier = edit_formats::EditApplier::new(root);
+        let result = applier
+            .changeset_with_retries(
+                output,
+                title,
+                "code-generator",
+                "generate",
+                MAX_EDIT_RETRIES,
+                |rejected, feedback| {
+                    messages.push(ChatMessage::assistant(rejected));
+                    messages.push(ChatMessage::user(feedback));
+                    let ai_manager = ai_manager.clone();
+                    let messages = messages.clone();
+                    async move {
+                        chat(&ai_manager, messages)
+                            .await
+                            .map_err(|e| tracing::warn!("Could not ask for corrected edits: {}", e))
+                            .ok()
+                    }
+                },
+            )
+            .await;
+
+        match result {
+            Ok(changeset) => Ok(Some(changeset)),
+            Err(errors)
+                if errors
+                    .iter()
+                    .all(|e| e.kind == edit_formats::EditErrorKind::NoEdits) =>
+            {
+                Ok(None)
+            }
+            Err(errors) => Err(CodeGenError::GenerationFailed(format!(
+                "Model edits could not be applied: {}",
+                errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))),
+        }
     }
 
     /// Generate code from a GenerationRequest - main entry point for CLI
@@ -272,8 +378,6 @@ impl CodeGenerator {
         &self,
         request: &GenerationRequest,
     ) -> Result<String, CodeGenError> {
-        use crate::ai::{ChatMessage, ChatRequest};
-
         if let Some(ai_manager) = &self.ai_manager {
             // Build comprehensive prompt for AI
             let system_prompt = self.build_ai_system_prompt(&request);
@@ -631,6 +735,21 @@ impl CodeGenerator {
     }
 }
 
+/// Send a conversation to the default model and return its answer
+async fn chat(ai_manager: &AIManager, messages: Vec<ChatMessage>) -> Result<String, CodeGenError> {
+    let request = ChatRequest {
+        model: String::new(), // Use default model
+        messages,
+        parameters: None,
+        stream: false,
+    };
+    ai_manager
+        .chat_completion_default(request)
+        .await
+        .map(|response| response.message.content)
+        .map_err(|e| CodeGenError::GenerationFailed(format!("AI generation failed: {}", e)))
+}
+
 /// Types of refactoring operations
 #[derive(Debug, Clone)]
 pub enum RefactorType {
@@ -0,0 +1,2410 @@
+//! Deterministic Rust refactorings
+//!
+//! Mechanical changes such as renaming a symbol or extracting a function are
+//! computed from the `syn` syntax tree and the cross-reference index instead
+//! of asking a model to rewrite whole files. Every refactoring produces a
+//! `ChangeSet`, so it goes through the same review, quality gates and journal
+//! as generated edits. Source positions come from span locations, and edits
+//! are spliced into the original text so formatting and comments survive.
+
+use proc_macro2::{LineColumn, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Block, Expr, Item, Pat, Stmt, Token, UseTree};
+use thiserror::Error;
+
+use super::diff_apply::{ChangeSet, ChangeType, DiffApplySystem, DiffMetadata, FileDiff};
+use crate::context::references::ReferenceIndex;
+use crate::context::symbols::SymbolType;
+use crate::context::syntax_chunker::module_path;
+
+/// A mechanical Rust refactoring
+///
+/// Serialized with a `tool` tag so a model can request one as a tool call,
+/// e.g. `{"tool": "rename_symbol", "name": "Config::load", "new_name": "read"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "tool", rename_all = "snake_case")]
+pub enum RustRefactoring {
+    /// Rename a definition and every reference to it across the crate
+    RenameSymbol {
+        /// Name, or a qualified suffix such as `Config::load`
+        name: String,
+        new_name: String,
+        /// Narrow the definition to one file
+        #[serde(default)]
+        file: Option<PathBuf>,
+        /// Narrow the definition to the one spanning this line
+        #[serde(default)]
+        line: Option<usize>,
+    },
+    /// Move whole statements of a function body into a new function
+    ExtractFunction {
+        file: PathBuf,
+        start_line: usize,
+        end_line: usize,
+        name: String,
+    },
+    /// Replace uses of a `let` binding with its initializer
+    InlineVariable {
+        file: PathBuf,
+        /// Line of the `let` statement
+        line: usize,
+        name: String,
+    },
+    /// Move a top-level item and its impls to another module, fixing up paths
+    MoveItem {
+        file: PathBuf,
+        name: String,
+        /// File of the destination module, which must already exist
+        to: PathBuf,
+    },
+    AddDerive {
+        file: PathBuf,
+        type_name: String,
+        derive: String,
+    },
+    RemoveDerive {
+        file: PathBuf,
+        type_name: String,
+        derive: String,
+    },
+}
+
+impl RustRefactoring {
+    /// Tool name, as used in the `tool` tag
+    pub fn tool_name(&self) -> &'static str {
+        match self {
+            RustRefactoring::RenameSymbol { .. } => "rename_symbol",
+            RustRefactoring::ExtractFunction { .. } => "extract_function",
+            RustRefactoring::InlineVariable { .. } => "inline_variable",
+            RustRefactoring::MoveItem { .. } => "move_item",
+            RustRefactoring::AddDerive { .. } => "add_derive",
+            RustRefactoring::RemoveDerive { .. } => "remove_derive",
+        }
+    }
+
+    /// Names of all refactoring tools
+    pub const TOOL_NAMES: &'static [&'static str] = &[
+        "rename_symbol",
+        "extract_function",
+        "inline_variable",
+        "move_item",
+        "add_derive",
+        "remove_derive",
+    ];
+
+    /// Tool descriptions for a model, with their arguments
+    ///
+    /// Paths are relative to the project root and lines are 1-based.
+    pub fn tool_descriptions() -> serde_json::Value {
+        serde_json::json!([
+            {
+                "tool": "rename_symbol",
+                "description": "Rename a Rust definition and every reference to it across the crate",
+                "arguments": {
+                    "name": "symbol name or qualified suffix such as Type::method",
+                    "new_name": "new identifier",
+                    "file": "optional file containing the definition",
+                    "line": "optional line within the definition"
+                }
+            },
+            {
+                "tool": "extract_function",
+                "description": "Move the whole statements between two lines of a function body into a new function",
+                "arguments": {"file": "file path", "start_line": "first line", "end_line": "last line", "name": "new function name"}
+            },
+            {
+                "tool": "inline_variable",
+                "description": "Replace every use of a let binding with its initializer and remove the binding",
+                "arguments": {"file": "file path", "line": "line of the let statement", "name": "variable name"}
+            },
+            {
+                "tool": "move_item",
+                "description": "Move a top-level item and its impl blocks to another existing module file and fix up use paths",
+                "arguments": {"file": "source file", "name": "item name", "to": "destination module file"}
+            },
+            {
+                "tool": "add_derive",
+                "description": "Add a derive to a struct, enum or union",
+                "arguments": {"file": "file path", "type_name": "type name", "derive": "derive path such as Clone or serde::Serialize"}
+            },
+            {
+                "tool": "remove_derive",
+                "description": "Remove a derive from a struct, enum or union",
+                "arguments": {"file": "file path", "type_name": "type name", "derive": "derive to remove"}
+            }
+        ])
+    }
+}
+
+/// Errors computing a refactoring
+#[derive(Debug, Error)]
+pub enum RefactorError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("'{name}' matches more than one definition: {candidates}")]
+    Ambiguous { name: String, candidates: String },
+
+    #[error("Invalid identifier: {0}")]
+    InvalidName(String),
+
+    #[error("Name conflict: {0}")]
+    Conflict(String),
+
+    #[error("Cannot refactor: {0}")]
+    Unsupported(String),
+
+    #[error("Nothing to change: {0}")]
+    NothingToDo(String),
+}
+
+/// Computes refactorings for the Rust crate at a project root
+pub struct RustRefactorer {
+    root: PathBuf,
+}
+
+impl RustRefactorer {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            root: project_root.to_path_buf(),
+        }
+    }
+
+    /// Compute a refactoring as a changeset; nothing is written
+    pub fn apply(&self, refactoring: &RustRefactoring) -> Result<ChangeSet, RefactorError> {
+        match refactoring {
+            RustRefactoring::RenameSymbol { name, new_name, file, line } => {
+                self.rename_symbol(name, new_name, file.as_deref(), *line)
+            }
+            RustRefactoring::ExtractFunction { file, start_line, end_line, name } => {
+                self.extract_function(file, *start_line, *end_line, name)
+            }
+            RustRefactoring::InlineVariable { file, line, name } => self.inline_variable(file, *line, name),
+            RustRefactoring::MoveItem { file, name, to } => self.move_item(file, name, to),
+            RustRefactoring::AddDerive { file, type_name, derive } => self.add_derive(file, type_name, derive),
+            RustRefactoring::RemoveDerive { file, type_name, derive } => self.remove_derive(file, type_name, derive),
+        }
+    }
+
+    /// Rename a definition and the references the cross-reference index links to it
+    ///
+    /// Only the crate owning the definition is searched, and only the exact
+    /// name of each reference is rewritten. References that the index could
+    /// not tie to a single definition, including method calls on receivers of
+    /// unknown type, are left alone and listed in the changeset description.
+    pub fn rename_symbol(
+        &self,
+        name: &str,
+        new_name: &str,
+        file: Option<&Path>,
+        line: Option<usize>,
+    ) -> Result<ChangeSet, RefactorError> {
+        validate_identifier(new_name)?;
+        let leaf = name.rsplit("::").next().unwrap_or(name);
+
+        let crate_dir = file.map(|file| self.owning_crate(file)).unwrap_or_default();
+        let sources: BTreeMap<PathBuf, String> = self.crate_sources(&crate_dir)?.into_iter().collect();
+        let mut index = ReferenceIndex::new();
+        for (path, content) in &sources {
+            index.update_file(path, "rust", content);
+        }
+        index.resolve();
+
+        let definitions: Vec<_> = index
+            .find_definitions(name)
+            .into_iter()
+            .filter(|definition| file.map_or(true, |file| definition.file_path == file))
+            .filter(|definition| line.map_or(true, |line| (definition.line..=definition.end_line).contains(&line)))
+            .collect();
+        let definition = match definitions.as_slice() {
+            [] => return Err(RefactorError::NotFound(format!("no definition of `{}`", name))),
+            [definition] => *definition,
+            _ => {
+                return Err(RefactorError::Ambiguous {
+                    name: name.to_string(),
+                    candidates: definitions
+                        .iter()
+                        .map(|d| format!("{} ({}:{})", d.qualified_name, d.file_path.display(), d.line))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                })
+            }
+        };
+        if definition.symbol_type == SymbolType::Module {
+            return Err(RefactorError::Unsupported("renaming modules requires renaming their files".to_string()));
+        }
+
+        let scope = definition.qualified_name.rsplit_once("::").map(|(scope, _)| scope);
+        if let Some(existing) = index
+            .find_definitions(new_name)
+            .into_iter()
+            .find(|other| other.qualified_name.rsplit_once("::").map(|(scope, _)| scope) == scope)
+        {
+            return Err(RefactorError::Conflict(format!(
+                "`{}` is already defined at {}:{}",
+                existing.qualified_name,
+                existing.file_path.display(),
+                existing.line
+            )));
+        }
+
+        let content = &sources[&definition.file_path];
+        let mut idents = Vec::new();
+        flatten_idents(tokenize(&definition.file_path, content)?, &mut idents);
+        let name_span = idents
+            .windows(2)
+            .find(|pair| {
+                pair[1] == leaf
+                    && pair[1].span().start().line == definition.line
+                    && DEFINITION_KEYWORDS.contains(&pair[0].to_string().as_str())
+            })
+            .map(|pair| pair[1].span())
+            .ok_or_else(|| {
+                RefactorError::NotFound(format!(
+                    "the name of `{}` at {}:{}",
+                    definition.qualified_name,
+                    definition.file_path.display(),
+                    definition.line
+                ))
+            })?;
+        let source = Source::new(content);
+        let range = source.range(name_span);
+
+        let mut ranges: HashMap<PathBuf, BTreeSet<(usize, usize)>> = HashMap::new();
+        ranges.entry(definition.file_path.clone()).or_default().insert((range.start, range.end));
+        let mut ambiguous = Vec::new();
+        for reference in index.references_to(&definition.id) {
+            let location = &reference.reference;
+            if reference.targets.len() > 1 {
+                ambiguous.push(format!("{}:{}", location.file_path.display(), location.line_number));
+                continue;
+            }
+            let content = &sources[&location.file_path];
+            match name_range(&Source::new(content), location.line_number, location.column, leaf) {
+                Some(range) => {
+                    ranges.entry(location.file_path.clone()).or_default().insert((range.start, range.end));
+                }
+                None => tracing::warn!(
+                    "Could not locate `{}` at {}:{}",
+                    leaf,
+                    location.file_path.display(),
+                    location.line_number
+                ),
+            }
+        }
+
+        // `Vec::len` names another type, but `items.len()` may call this one
+        for reference in index.unresolved_references(leaf) {
+            let location = &reference.reference;
+            let content = &sources[&location.file_path];
+            let on_receiver = name_range(&Source::new(content), location.line_number, location.column, leaf)
+                .is_some_and(|range| content[..range.start].trim_end().ends_with('.'));
+            if on_receiver {
+                ambiguous.push(format!("{}:{}", location.file_path.display(), location.line_number));
+            }
+        }
+
+        // The index does not count impl headers as references
+        let is_type = matches!(
+            definition.symbol_type,
+            SymbolType::Struct | SymbolType::Enum | SymbolType::Trait | SymbolType::Type
+        );
+        if is_type && index.find_definitions(leaf).len() == 1 {
+            for (path, content) in &sources {
+                let syntax = match parse_rust(path, content) {
+                    Ok(syntax) => syntax,
+                    Err(e) => {
+                        tracing::warn!("Skipping impl headers of unparsable file: {}", e);
+                        continue;
+                    }
+                };
+                let mut headers = ImplHeaders { name: leaf, spans: Vec::new() };
+                headers.visit_file(&syntax);
+                let source = Source::new(content);
+                ranges.entry(path.clone()).or_default().extend(headers.spans.into_iter().map(|span| {
+                    let range = source.range(span);
+                    (range.start, range.end)
+                }));
+            }
+        }
+
+        let mut changes = Changes::default();
+        for (path, content) in &sources {
+            for &(start, end) in ranges.get(path).into_iter().flatten() {
+                changes.edit(path, content, start..end, new_name.to_string());
+            }
+        }
+
+        let mut description = format!("Renamed `{}` to `{}`", definition.qualified_name, new_name);
+        if !ambiguous.is_empty() {
+            description.push_str(&format!(
+                "\nAmbiguous references left unchanged: {}",
+                ambiguous.join(", ")
+            ));
+        }
+        changes.into_changeset(&format!("Rename {} to {}", leaf, new_name), &description, "rename_symbol")
+    }
+
+    /// Move whole statements of a function body into a new function
+    ///
+    /// Variables from the enclosing function become reference parameters, so
+    /// their bindings need type annotations; bindings declared by the
+    /// statements and used afterwards are returned.
+    pub fn extract_function(
+        &self,
+        file: &Path,
+        start_line: usize,
+        end_line: usize,
+        name: &str,
+    ) -> Result<ChangeSet, RefactorError> {
+        validate_identifier(name)?;
+        if start_line == 0 || end_line < start_line {
+            return Err(RefactorError::Unsupported(format!("invalid line range {}-{}", start_line, end_line)));
+        }
+        let content = self.read(file)?;
+        let syntax = parse_rust(file, &content)?;
+        let source = Source::new(&content);
+
+        let mut functions = Functions::default();
+        functions.visit_file(&syntax);
+        if functions.found.iter().any(|function| function.sig.ident == name) {
+            return Err(RefactorError::Conflict(format!("a function named `{}` already exists in {}", name, file.display())));
+        }
+        let function = functions
+            .found
+            .iter()
+            .filter(|function| {
+                let (first, last) = span_lines(function.block.span());
+                first <= start_line && end_line <= last
+            })
+            .min_by_key(|function| {
+                let (first, last) = span_lines(function.block.span());
+                last - first
+            })
+            .ok_or_else(|| RefactorError::NotFound(format!("no function body spans lines {}-{}", start_line, end_line)))?;
+
+        let (block, selected) = select_statements(function.block, start_line, end_line)?;
+        let statements = &block.stmts[selected.clone()];
+        let first = statements.first().expect("selection is not empty");
+        let last = statements.last().expect("selection is not empty");
+        let selection = source.offset(first.span().start())..source.offset(last.span().end());
+        let lines = source.whole_lines(selection.clone());
+        if lines == selection {
+            return Err(RefactorError::Unsupported("the selection must cover whole lines".to_string()));
+        }
+
+        let mut scan = Scan::new(&source);
+        for statement in statements {
+            scan.visit_stmt(statement);
+        }
+        if let Some(construct) = scan.control_flow {
+            return Err(RefactorError::Unsupported(format!(
+                "the selection uses `{}`, which would leave the new function",
+                construct
+            )));
+        }
+        if scan.uses_self {
+            return Err(RefactorError::Unsupported("the selection uses `self`".to_string()));
+        }
+
+        // Bindings in scope before the selection; later ones shadow earlier ones
+        let mut bindings = Bindings { source: &source, found: Vec::new() };
+        for input in &function.sig.inputs {
+            bindings.visit_fn_arg(input);
+        }
+        bindings.visit_block(function.block);
+        let mut outer: HashMap<String, Binding> = HashMap::new();
+        for binding in bindings.found {
+            if binding.offset < selection.start {
+                outer.insert(binding.name.clone(), binding);
+            }
+        }
+
+        let mut parameters: Vec<(Binding, Passing)> = Vec::new();
+        for used in &scan.uses {
+            if scan.bound.contains(&used.name) || parameters.iter().any(|(binding, _)| binding.name == used.name) {
+                continue;
+            }
+            let Some(binding) = outer.get(&used.name) else {
+                continue;
+            };
+            let Some(ty) = &binding.ty else {
+                return Err(RefactorError::Unsupported(format!(
+                    "cannot infer the type of `{}`; annotate its binding",
+                    binding.name
+                )));
+            };
+            let passing = if ty.starts_with('&') {
+                Passing::Value
+            } else if binding.mutable {
+                Passing::MutRef
+            } else {
+                Passing::Ref
+            };
+            parameters.push((binding.clone(), passing));
+        }
+
+        // Bindings declared by the selection and still needed after it
+        let mut used_after = HashSet::new();
+        for statement in &block.stmts[selected.end..] {
+            let mut idents = Vec::new();
+            flatten_free_idents(statement.to_token_stream(), &mut idents, &mut Vec::new());
+            used_after.extend(idents.into_iter().map(|(ident, _)| ident.to_string()));
+        }
+        let mut returned: Vec<Binding> = Vec::new();
+        for statement in statements {
+            let Stmt::Local(local) = statement else {
+                continue;
+            };
+            let mut declared = Bindings { source: &source, found: Vec::new() };
+            declared.visit_pat(&local.pat);
+            for binding in declared.found {
+                if !used_after.contains(&binding.name) {
+                    continue;
+                }
+                if binding.ty.is_none() {
+                    return Err(RefactorError::Unsupported(format!(
+                        "`{}` is used after the selection; annotate its type so it can be returned",
+                        binding.name
+                    )));
+                }
+                returned.push(binding);
+            }
+        }
+
+        let is_tail = matches!(last, Stmt::Expr(_, None)) && selected.end == block.stmts.len();
+        let tail_type = if is_tail {
+            if !std::ptr::eq(block, function.block) || !returned.is_empty() {
+                return Err(RefactorError::Unsupported(
+                    "the selection ends with the value of an inner block".to_string(),
+                ));
+            }
+            match &function.sig.output {
+                syn::ReturnType::Default => None,
+                syn::ReturnType::Type(_, ty) => Some(source.slice(ty.span()).to_string()),
+            }
+        } else {
+            None
+        };
+
+        // Parameters are references now: dereference plain uses
+        let mut rewrites = Vec::new();
+        for used in &scan.uses {
+            let by_reference = parameters
+                .iter()
+                .any(|(binding, passing)| binding.name == used.name && *passing != Passing::Value);
+            if !by_reference || scan.bound.contains(&used.name) {
+                continue;
+            }
+            let text = match used.kind {
+                UseKind::Plain => format!("*{}", used.name),
+                UseKind::Shorthand => format!("{}: *{}", used.name, used.name),
+                UseKind::Receiver | UseKind::Base => continue,
+            };
+            rewrites.push(Edit {
+                range: used.range.start - lines.start..used.range.end - lines.start,
+                text,
+            });
+        }
+        let indent = source.indentation(function.sig.fn_token.span.start().line).to_string();
+        let body_indent = format!("{}    ", indent);
+        let mut body = reindent(&apply_edits(&content[lines.clone()], rewrites), &body_indent);
+        match returned.as_slice() {
+            [] => {}
+            [single] => body.push_str(&format!("{}{}\n", body_indent, single.name)),
+            many => body.push_str(&format!(
+                "{}({})\n",
+                body_indent,
+                many.iter().map(|binding| binding.name.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+
+        let parameter_list: Vec<String> = parameters
+            .iter()
+            .map(|(binding, passing)| {
+                let ty = binding.ty.as_deref().unwrap_or_default();
+                match passing {
+                    Passing::Value => format!("{}: {}", binding.name, ty),
+                    Passing::Ref => format!("{}: &{}", binding.name, ty),
+                    Passing::MutRef => format!("{}: &mut {}", binding.name, ty),
+                }
+            })
+            .collect();
+        let return_type = match (returned.as_slice(), &tail_type) {
+            ([], Some(ty)) => format!(" -> {}", ty),
+            ([], None) => String::new(),
+            ([single], _) => format!(" -> {}", single.ty.as_deref().unwrap_or_default()),
+            (many, _) => format!(
+                " -> ({})",
+                many.iter().map(|binding| binding.ty.as_deref().unwrap_or_default()).collect::<Vec<_>>().join(", ")
+            ),
+        };
+        let signature_text = format!("{} {}", parameter_list.join(" "), return_type);
+        let generics = &function.sig.generics;
+        let generic_names: Vec<String> = generics
+            .params
+            .iter()
+            .map(|param| match param {
+                syn::GenericParam::Lifetime(lifetime) => lifetime.lifetime.to_string(),
+                syn::GenericParam::Type(ty) => ty.ident.to_string(),
+                syn::GenericParam::Const(constant) => constant.ident.to_string(),
+            })
+            .collect();
+        let (generics_text, where_text) = if generic_names.iter().any(|name| mentions(&signature_text, name)) {
+            let params = match (&generics.lt_token, &generics.gt_token) {
+                (Some(lt), Some(gt)) => content[source.offset(lt.span.start())..source.offset(gt.span.end())].to_string(),
+                _ => String::new(),
+            };
+            let where_clause = generics
+                .where_clause
+                .as_ref()
+                .map(|clause| format!(" {}", source.slice(clause.span())))
+                .unwrap_or_default();
+            (params, where_clause)
+        } else {
+            (String::new(), String::new())
+        };
+
+        let new_function = format!(
+            "{}fn {}{}({}){}{} {{\n{}{}}}\n",
+            indent,
+            name,
+            generics_text,
+            parameter_list.join(", "),
+            return_type,
+            where_text,
+            body,
+            indent
+        );
+
+        let arguments: Vec<String> = parameters
+            .iter()
+            .map(|(binding, passing)| match passing {
+                Passing::Value => binding.name.clone(),
+                Passing::Ref => format!("&{}", binding.name),
+                Passing::MutRef => format!("&mut {}", binding.name),
+            })
+            .collect();
+        let call = format!(
+            "{}{}({})",
+            if function.in_impl { "Self::" } else { "" },
+            name,
+            arguments.join(", ")
+        );
+        let call_statement = match returned.as_slice() {
+            [] if is_tail => call,
+            [] => format!("{};", call),
+            [single] => format!("let {}{} = {};", if single.mutable { "mut " } else { "" }, single.name, call),
+            many => format!(
+                "let ({}) = {};",
+                many.iter()
+                    .map(|binding| format!("{}{}", if binding.mutable { "mut " } else { "" }, binding.name))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                call
+            ),
+        };
+        let call_indent = source.indentation(first.span().start().line);
+
+        let item_end = source.line_end(function.item_span.end().line);
+        let separator = if content[..item_end].ends_with('\n') { "\n" } else { "\n\n" };
+        let mut changes = Changes::default();
+        changes.edit(file, &content, lines, format!("{}{}\n", call_indent, call_statement));
+        changes.edit(file, &content, item_end..item_end, format!("{}{}", separator, new_function));
+        changes.into_changeset(
+            &format!("Extract function {}", name),
+            &format!(
+                "Extracted lines {}-{} of `{}` into `{}`",
+                start_line, end_line, function.sig.ident, name
+            ),
+            "extract_function",
+        )
+    }
+
+    /// Replace every use of a `let` binding with its initializer
+    pub fn inline_variable(&self, file: &Path, line: usize, name: &str) -> Result<ChangeSet, RefactorError> {
+        let content = self.read(file)?;
+        let syntax = parse_rust(file, &content)?;
+        let source = Source::new(&content);
+
+        let mut blocks = Blocks::default();
+        blocks.visit_file(&syntax);
+        let (block, index, local) = blocks
+            .found
+            .iter()
+            .find_map(|block| {
+                block.stmts.iter().enumerate().find_map(|(index, statement)| match statement {
+                    Stmt::Local(local) if binds(&local.pat, name) && {
+                        let (first, last) = span_lines(local.span());
+                        first <= line && line <= last
+                    } => Some((*block, index, local)),
+                    _ => None,
+                })
+            })
+            .ok_or_else(|| RefactorError::NotFound(format!("no `let {}` on line {}", name, line)))?;
+
+        match &local.pat {
+            Pat::Ident(ident) if ident.by_ref.is_none() && ident.mutability.is_none() && ident.subpat.is_none() => {}
+            Pat::Ident(_) => {
+                return Err(RefactorError::Unsupported(format!("`{}` is mutable or bound by reference", name)))
+            }
+            _ => {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` has a type annotation that inlining would lose",
+                    name
+                )))
+            }
+        }
+        let initializer = match &local.init {
+            Some(init) if init.diverge.is_none() => &init.expr,
+            _ => return Err(RefactorError::Unsupported(format!("`{}` has no plain initializer", name))),
+        };
+
+        let mut init_scan = Scan::new(&source);
+        init_scan.visit_expr(initializer);
+
+        let mut scan = Scan::new(&source);
+        for statement in &block.stmts[index + 1..] {
+            match statement {
+                // A later `let` of the same name ends the variable's scope
+                Stmt::Local(shadowing) if binds(&shadowing.pat, name) => {
+                    if let Some(init) = &shadowing.init {
+                        scan.visit_expr(&init.expr);
+                    }
+                    break;
+                }
+                statement => scan.visit_stmt(statement),
+            }
+        }
+        if scan.bound.contains(name) {
+            return Err(RefactorError::Unsupported(format!("`{}` is shadowed in its scope", name)));
+        }
+        if scan
+            .literals
+            .iter()
+            .any(|literal| literal.contains(&format!("{{{}}}", name)) || literal.contains(&format!("{{{}:", name)))
+        {
+            return Err(RefactorError::Unsupported(format!(
+                "`{}` is captured by a format string",
+                name
+            )));
+        }
+        // Inlining must not move a read past a change to what it reads
+        for input in init_scan.uses.iter().map(|used| &used.name) {
+            if scan.mutated.contains(input) || scan.bound.contains(input) {
+                return Err(RefactorError::Unsupported(format!(
+                    "`{}` changes between the binding and its uses",
+                    input
+                )));
+            }
+        }
+
+        let uses: Vec<&Use> = scan.uses.iter().filter(|used| used.name == name).collect();
+        if init_scan.has_side_effects {
+            let deferred = uses.iter().any(|used| used.deferred);
+            if uses.len() != 1 || deferred {
+                return Err(RefactorError::Unsupported(format!(
+                    "the initializer of `{}` has side effects and would run {} times",
+                    name,
+                    if deferred { "a different number of".to_string() } else { uses.len().to_string() }
+                )));
+            }
+        }
+
+        let init_text = source.slice(initializer.span()).to_string();
+        let operand = if is_primary(initializer) { init_text.clone() } else { format!("({})", init_text) };
+        let mut changes = Changes::default();
+        let statement = source.range(local.span());
+        changes.edit(file, &content, source.whole_lines(statement.clone()), String::new());
+        for used in uses {
+            let text = match used.kind {
+                UseKind::Shorthand => format!("{}: {}", name, init_text),
+                _ => operand.clone(),
+            };
+            changes.edit(file, &content, used.range.clone(), text);
+        }
+        changes.into_changeset(
+            &format!("Inline {}", name),
+            &format!("Inlined `{}` from {}:{}", name, file.display(), line),
+            "inline_variable",
+        )
+    }
+
+    /// Move a top-level item and its impl blocks to another module
+    ///
+    /// `use` declarations and absolute paths naming the item are rewritten
+    /// across the crate. The destination gets the imports the item needs, and
+    /// private items and members the source module still uses become
+    /// `pub(crate)`.
+    pub fn move_item(&self, file: &Path, name: &str, to: &Path) -> Result<ChangeSet, RefactorError> {
+        if file == to {
+            return Err(RefactorError::NothingToDo(format!("`{}` is already in {}", name, to.display())));
+        }
+        if !self.root.join(to).exists() {
+            return Err(RefactorError::NotFound(format!(
+                "destination module {} does not exist; declare it first",
+                to.display()
+            )));
+        }
+        let sources: BTreeMap<PathBuf, String> = self.crate_sources(&self.owning_crate(file))?.into_iter().collect();
+        let content = sources
+            .get(file)
+            .ok_or_else(|| RefactorError::NotFound(format!("{} is not part of the crate", file.display())))?;
+        let target_content = sources
+            .get(to)
+            .ok_or_else(|| RefactorError::NotFound(format!("{} is not part of the crate", to.display())))?;
+        let syntax = parse_rust(file, content)?;
+        let target_syntax = parse_rust(to, target_content)?;
+        let source = Source::new(content);
+        let source_module = module_path(file, "rust");
+        let target_module = module_path(to, "rust");
+        let mut old_path = source_module.clone();
+        old_path.push(name.to_string());
+        let mut new_path = target_module.clone();
+        new_path.push(name.to_string());
+
+        let target_item = syntax
+            .items
+            .iter()
+            .find(|item| item_name(item).as_deref() == Some(name))
+            .ok_or_else(|| RefactorError::NotFound(format!("no top-level item `{}` in {}", name, file.display())))?;
+        if target_syntax.items.iter().any(|item| item_name(item).as_deref() == Some(name)) {
+            return Err(RefactorError::Conflict(format!("{} already defines `{}`", to.display(), name)));
+        }
+        let moved: Vec<&Item> = syntax
+            .items
+            .iter()
+            .filter(|candidate| {
+                std::ptr::eq(*candidate, target_item) || matches!(candidate, Item::Impl(block) if impl_is_for(block, name))
+            })
+            .collect();
+        let moved_ranges: Vec<Range<usize>> =
+            moved.iter().map(|item| source.whole_lines(source.range(item.span()))).collect();
+
+        let mut removed = content.clone();
+        for range in moved_ranges.iter().rev() {
+            removed.replace_range(range.clone(), "");
+        }
+        let mut remaining_idents = Vec::new();
+        flatten_idents(tokenize(file, &removed)?, &mut remaining_idents);
+        let remaining: HashSet<String> = remaining_idents.iter().map(|ident| ident.to_string()).collect();
+
+        let mut moved_idents = Vec::new();
+        for item in &moved {
+            flatten_idents(item.to_token_stream(), &mut moved_idents);
+        }
+        let moved_names: HashSet<String> = moved_idents.iter().map(|ident| ident.to_string()).collect();
+
+        let mut changes = Changes::default();
+        let mut exposed = Vec::new();
+
+        // The moved text, with private members the source still uses exposed
+        let mut moved_text = String::new();
+        for (item, range) in moved.iter().zip(&moved_ranges) {
+            let mut edits = Vec::new();
+            let mut expose = |span: Span, what: String| {
+                let offset = source.offset(span.start());
+                edits.push(Edit {
+                    range: offset - range.start..offset - range.start,
+                    text: "pub(crate) ".to_string(),
+                });
+                exposed.push(what);
+            };
+            if std::ptr::eq(*item, target_item) {
+                if let Some(span) = private_item_start(item) {
+                    expose(span, format!("`{}`", name));
+                }
+            }
+            match item {
+                Item::Struct(item) if item.ident == name => {
+                    for field in &item.fields {
+                        if let (syn::Visibility::Inherited, Some(ident)) = (&field.vis, &field.ident) {
+                            if remaining.contains(&ident.to_string()) {
+                                expose(ident.span(), format!("field `{}`", ident));
+                            }
+                        }
+                    }
+                }
+                Item::Impl(block) if block.trait_.is_none() => {
+                    for member in &block.items {
+                        if let syn::ImplItem::Fn(method) = member {
+                            if matches!(method.vis, syn::Visibility::Inherited)
+                                && remaining.contains(&method.sig.ident.to_string())
+                            {
+                                let start = method.sig.constness.map(|token| token.span)
+                                    .or(method.sig.asyncness.map(|token| token.span))
+                                    .or(method.sig.unsafety.map(|token| token.span))
+                                    .unwrap_or(method.sig.fn_token.span);
+                                expose(start, format!("method `{}`", method.sig.ident));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+            let (text, rewrites) = absolutize_relative_paths(&content[range.clone()], &source_module, file)?;
+            let text = apply_edits(&text, shift_edits(edits, &rewrites));
+            if !moved_text.is_empty() {
+                moved_text.push('\n');
+            }
+            moved_text.push_str(text.trim_end_matches('\n'));
+            moved_text.push('\n');
+        }
+        for range in &moved_ranges {
+            changes.edit(file, content, removed_with_blank_line(content, range.clone()), String::new());
+        }
+
+        // Imports the moved code relies on
+        let mut imports: Vec<String> = Vec::new();
+        for use_item in syntax.items.iter().filter_map(|item| match item {
+            Item::Use(use_item) => Some(use_item),
+            _ => None,
+        }) {
+            let mut leaves = Vec::new();
+            use_leaves(&use_item.tree, &mut Vec::new(), &mut leaves);
+            for leaf in leaves {
+                let needed = match &leaf.bound {
+                    Some(bound) => moved_names.contains(bound),
+                    None => true,
+                };
+                if needed {
+                    let path = absolute_use_path(&leaf.path, &source_module, file, &self.root);
+                    imports.push(render_import(&path, &leaf));
+                }
+            }
+        }
+        for other in &syntax.items {
+            if moved.iter().any(|item| std::ptr::eq(*item, other)) {
+                continue;
+            }
+            let Some(other_name) = item_name(other) else {
+                continue;
+            };
+            if !moved_names.contains(&other_name) {
+                continue;
+            }
+            let mut path = source_module.clone();
+            path.push(other_name.clone());
+            imports.push(format!("use {};", path.join("::")));
+            if let Some(span) = private_item_start(other) {
+                let offset = source.offset(span.start());
+                changes.edit(file, content, offset..offset, "pub(crate) ".to_string());
+                exposed.push(format!("`{}`", other_name));
+            }
+        }
+
+        let target_source = Source::new(target_content);
+        let mut target_bound = HashSet::new();
+        for item in &target_syntax.items {
+            match item {
+                Item::Use(use_item) => {
+                    let mut leaves = Vec::new();
+                    use_leaves(&use_item.tree, &mut Vec::new(), &mut leaves);
+                    target_bound.extend(leaves.into_iter().filter_map(|leaf| leaf.bound));
+                }
+                item => target_bound.extend(item_name(item)),
+            }
+        }
+        let mut seen = HashSet::new();
+        imports.retain(|import| {
+            let bound = import.trim_end_matches(';').rsplit([':', ' ']).next().unwrap_or_default().to_string();
+            !target_bound.contains(&bound) && bound != name && seen.insert(import.clone())
+        });
+        let import_offset = import_position(&target_syntax, &target_source);
+        if !imports.is_empty() {
+            let text = imports.iter().map(|import| format!("{}\n", import)).collect::<String>();
+            changes.edit(to, target_content, import_offset..import_offset, text);
+        }
+        let end = target_content.len();
+        let separator = if target_content.is_empty() || target_content.ends_with("\n\n") {
+            ""
+        } else if target_content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        changes.edit(to, target_content, end..end, format!("{}{}", separator, moved_text));
+
+        // Point `use` declarations and absolute paths at the new module
+        for (path, text) in &sources {
+            let file_syntax = if path == file { syntax.clone() } else { parse_rust(path, text)? };
+            let file_source = Source::new(text);
+            let module = module_path(path, "rust");
+            let mut needs_import = path == file && remaining.contains(name);
+            let mut use_ranges = Vec::new();
+
+            for use_item in file_syntax.items.iter().filter_map(|item| match item {
+                Item::Use(use_item) => Some(use_item),
+                _ => None,
+            }) {
+                let range = file_source.range(use_item.span());
+                use_ranges.push(range.clone());
+                let mut leaves = Vec::new();
+                use_leaves(&use_item.tree, &mut Vec::new(), &mut leaves);
+                let mut matched = false;
+                for leaf in &leaves {
+                    let absolute = absolute_use_path(&leaf.path, &module, path, &self.root);
+                    if leaf.glob && absolute == source_module {
+                        let mut idents = Vec::new();
+                        flatten_idents(tokenize(path, text)?, &mut idents);
+                        needs_import |= path != to && idents.iter().any(|ident| ident == name);
+                    } else if absolute == old_path {
+                        matched = true;
+                        needs_import |= path != to;
+                    }
+                }
+                if !matched {
+                    continue;
+                }
+                let rewritten = remove_use_leaf(&use_item.tree, &mut Vec::new(), &|leaf_path: &[String]| {
+                    absolute_use_path(leaf_path, &module, path, &self.root) == old_path
+                });
+                let replacement = match rewritten {
+                    Some(tree) => format!("{}use {};", render_visibility(&use_item.vis), render_use_tree(&tree)),
+                    None => String::new(),
+                };
+                if replacement.is_empty() {
+                    changes.edit(path, text, file_source.whole_lines(range), String::new());
+                } else {
+                    changes.edit(path, text, range, replacement);
+                }
+            }
+
+            if needs_import {
+                let offset = import_position(&file_syntax, &file_source);
+                changes.edit(path, text, offset..offset, format!("use {};\n", new_path.join("::")));
+            }
+
+            for (range, segments) in code_paths(&tokenize(path, text)?, &file_source) {
+                if use_ranges.iter().any(|use_range| use_range.start <= range.start && range.end <= use_range.end) {
+                    continue;
+                }
+                if path == file && moved_ranges.iter().any(|moved| moved.start <= range.start && range.end <= moved.end) {
+                    continue;
+                }
+                if resolve_path(&segments, &module) == old_path {
+                    changes.edit(path, text, range, new_path.join("::"));
+                }
+            }
+        }
+
+        let mut description = format!(
+            "Moved `{}` from {} to {}",
+            name,
+            source_module.join("::"),
+            target_module.join("::")
+        );
+        if !exposed.is_empty() {
+            description.push_str(&format!("\nMade pub(crate): {}", exposed.join(", ")));
+        }
+        changes.into_changeset(&format!("Move {} to {}", name, target_module.join("::")), &description, "move_item")
+    }
+
+    /// Add a derive to a struct, enum or union
+    pub fn add_derive(&self, file: &Path, type_name: &str, derive: &str) -> Result<ChangeSet, RefactorError> {
+        syn::parse_str::<syn::Path>(derive).map_err(|_| RefactorError::InvalidName(derive.to_string()))?;
+        let content = self.read(file)?;
+        let syntax = parse_rust(file, &content)?;
+        let source = Source::new(&content);
+        let (attrs, start) = find_type(&syntax, type_name)
+            .ok_or_else(|| RefactorError::NotFound(format!("no struct, enum or union `{}` in {}", type_name, file.display())))?;
+
+        let mut changes = Changes::default();
+        match derive_attributes(attrs).into_iter().next() {
+            Some((attr, paths)) => {
+                if paths.iter().any(|path| same_derive(path, derive)) {
+                    return Err(RefactorError::NothingToDo(format!("`{}` already derives {}", type_name, derive)));
+                }
+                let mut paths = paths;
+                paths.push(derive.to_string());
+                changes.edit(file, &content, source.range(attr.span()), format!("#[derive({})]", paths.join(", ")));
+            }
+            None => {
+                // Derives go after doc comments, before other attributes
+                let anchor = attrs
+                    .iter()
+                    .find(|attr| !attr.path().is_ident("doc"))
+                    .map(|attr| attr.span())
+                    .unwrap_or(start);
+                let line = anchor.start().line;
+                let offset = source.line_start(line);
+                changes.edit(
+                    file,
+                    &content,
+                    offset..offset,
+                    format!("{}#[derive({})]\n", source.indentation(line), derive),
+                );
+            }
+        }
+        changes.into_changeset(
+            &format!("Derive {} for {}", derive, type_name),
+            &format!("Added `{}` to the derives of `{}`", derive, type_name),
+            "add_derive",
+        )
+    }
+
+    /// Remove a derive from a struct, enum or union
+    pub fn remove_derive(&self, file: &Path, type_name: &str, derive: &str) -> Result<ChangeSet, RefactorError> {
+        let content = self.read(file)?;
+        let syntax = parse_rust(file, &content)?;
+        let source = Source::new(&content);
+        let (attrs, _) = find_type(&syntax, type_name)
+            .ok_or_else(|| RefactorError::NotFound(format!("no struct, enum or union `{}` in {}", type_name, file.display())))?;
+
+        let (attr, paths) = derive_attributes(attrs)
+            .into_iter()
+            .find(|(_, paths)| paths.iter().any(|path| same_derive(path, derive)))
+            .ok_or_else(|| RefactorError::NothingToDo(format!("`{}` does not derive {}", type_name, derive)))?;
+        let kept: Vec<String> = paths.into_iter().filter(|path| !same_derive(path, derive)).collect();
+
+        let mut changes = Changes::default();
+        let range = source.range(attr.span());
+        if kept.is_empty() {
+            changes.edit(file, &content, source.whole_lines(range), String::new());
+        } else {
+            changes.edit(file, &content, range, format!("#[derive({})]", kept.join(", ")));
+        }
+        changes.into_changeset(
+            &format!("Remove derive {} from {}", derive, type_name),
+            &format!("Removed `{}` from the derives of `{}`", derive, type_name),
+            "remove_derive",
+        )
+    }
+
+    fn read(&self, file: &Path) -> Result<String, RefactorError> {
+        std::fs::read_to_string(self.root.join(file))
+            .map_err(|e| RefactorError::NotFound(format!("{}: {}", file.display(), e)))
+    }
+
+    /// Directory of the crate a file belongs to, relative to the project root
+    ///
+    /// This is the nearest ancestor holding a `Cargo.toml`, or the project
+    /// root when there is none.
+    fn owning_crate(&self, file: &Path) -> PathBuf {
+        file.ancestors()
+            .skip(1)
+            .find(|dir| self.root.join(dir).join("Cargo.toml").is_file())
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    /// Rust files of a crate, relative to the project root
+    ///
+    /// Only `src/` is walked, so tests, examples, scaffolding templates and
+    /// nested crates are never rewritten.
+    fn crate_sources(&self, crate_dir: &Path) -> Result<Vec<(PathBuf, String)>, RefactorError> {
+        let crate_root = self.root.join(crate_dir);
+        let src = crate_root.join("src");
+        let walk_root = if src.is_dir() { src } else { crate_root };
+        let mut sources = Vec::new();
+        let walker = walkdir::WalkDir::new(&walk_root).into_iter().filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(name.starts_with('.')
+                    || name == "target"
+                    || name == "node_modules"
+                    || entry.path().join("Cargo.toml").is_file())
+        });
+        for entry in walker {
+            let entry = entry.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            if entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "rs") {
+                let relative = entry.path().strip_prefix(&self.root).unwrap_or(entry.path()).to_path_buf();
+                sources.push((relative, std::fs::read_to_string(entry.path())?));
+            }
+        }
+        sources.sort();
+        Ok(sources)
+    }
+}
+
+fn validate_identifier(name: &str) -> Result<(), RefactorError> {
+    syn::parse_str::<syn::Ident>(name)
+        .map(|_| ())
+        .map_err(|_| RefactorError::InvalidName(name.to_string()))
+}
+
+fn parse_rust(path: &Path, content: &str) -> Result<syn::File, RefactorError> {
+    syn::parse_file(content).map_err(|e| RefactorError::Parse {
+        path: path.to_path_buf(),
+        message: format!("{} at line {}", e, e.span().start().line),
+    })
+}
+
+fn tokenize(path: &Path, content: &str) -> Result<TokenStream, RefactorError> {
+    content.parse::<TokenStream>().map_err(|e| RefactorError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn span_lines(span: Span) -> (usize, usize) {
+    (span.start().line, span.end().line)
+}
+
+/// Keywords that introduce the name of a definition
+const DEFINITION_KEYWORDS: &[&str] = &["fn", "struct", "enum", "trait", "type", "union", "const", "static", "mod", "macro_rules"];
+
+/// Byte range of `name` at a 1-based line and byte column, or of its next
+/// whole-word occurrence on the line when the column marks a path or macro
+fn name_range(source: &Source, line: usize, column: usize, name: &str) -> Option<Range<usize>> {
+    let start = source.line_start(line) + column;
+    let end = source.line_end(line);
+    let is_ident = |c: char| c == '_' || c.is_alphanumeric();
+    source
+        .text
+        .get(start..end)?
+        .match_indices(name)
+        .map(|(index, _)| start + index)
+        .find(|&at| !source.text[..at].ends_with(is_ident) && !source.text[at + name.len()..].starts_with(is_ident))
+        .map(|at| at..at + name.len())
+}
+
+/// Whether `name` occurs in `text` as a whole word
+fn mentions(text: &str, name: &str) -> bool {
+    text.match_indices(name).any(|(index, _)| {
+        let before = text[..index].chars().next_back();
+        let after = text[index + name.len()..].chars().next();
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '\'');
+        !is_word(before) && !is_word(after)
+    })
+}
+
+/// Original text with line offsets, for turning spans into byte ranges
+struct Source<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Source<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+        Self { text, line_starts }
+    }
+
+    /// Byte offset of a 1-based line and a column counted in characters
+    fn offset(&self, position: LineColumn) -> usize {
+        let start = self.line_start(position.line);
+        self.text[start..]
+            .char_indices()
+            .nth(position.column)
+            .map_or(self.text.len(), |(index, _)| start + index)
+    }
+
+    fn range(&self, span: Span) -> Range<usize> {
+        self.offset(span.start())..self.offset(span.end())
+    }
+
+    fn slice(&self, span: Span) -> &'a str {
+        &self.text[self.range(span)]
+    }
+
+    fn line_start(&self, line: usize) -> usize {
+        self.line_starts
+            .get(line.saturating_sub(1))
+            .copied()
+            .unwrap_or(self.text.len())
+    }
+
+    /// Offset just past the line's newline
+    fn line_end(&self, line: usize) -> usize {
+        self.line_starts.get(line).copied().unwrap_or(self.text.len())
+    }
+
+    fn indentation(&self, line: usize) -> &'a str {
+        let rest = &self.text[self.line_start(line)..];
+        &rest[..rest.len() - rest.trim_start_matches([' ', '\t']).len()]
+    }
+
+    /// Widen a range to whole lines when nothing else shares them
+    fn whole_lines(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.text[..range.start].rfind('\n').map_or(0, |index| index + 1);
+        let end = self.text[range.end..]
+            .find('\n')
+            .map_or(self.text.len(), |index| range.end + index + 1);
+        if self.text[start..range.start].trim().is_empty() && self.text[range.end..end].trim().is_empty() {
+            start..end
+        } else {
+            range
+        }
+    }
+}
+
+/// Also drop a following blank line when the removed lines sit between blank lines
+fn removed_with_blank_line(text: &str, range: Range<usize>) -> Range<usize> {
+    let before_blank = range.start == 0 || text[..range.start].ends_with("\n\n");
+    let rest = &text[range.end..];
+    let next_line = rest.find('\n').map_or(rest.len(), |index| index + 1);
+    if before_blank && next_line > 0 && rest[..next_line].trim().is_empty() {
+        range.start..range.end + next_line
+    } else {
+        range
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+/// Splice non-overlapping edits into a text
+fn apply_edits(text: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    edits.dedup();
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for edit in edits {
+        if edit.range.start < position {
+            tracing::warn!("Skipping overlapping refactoring edit at byte {}", edit.range.start);
+            continue;
+        }
+        result.push_str(&text[position..edit.range.start]);
+        result.push_str(&edit.text);
+        position = edit.range.end;
+    }
+    result.push_str(&text[position..]);
+    result
+}
+
+/// Map edits made relative to an original text onto a rewritten copy of it
+fn shift_edits(edits: Vec<Edit>, rewrites: &[Edit]) -> Vec<Edit> {
+    edits
+        .into_iter()
+        .map(|edit| {
+            let delta: isize = rewrites
+                .iter()
+                .filter(|rewrite| rewrite.range.end <= edit.range.start)
+                .map(|rewrite| rewrite.text.len() as isize - rewrite.range.len() as isize)
+                .sum();
+            let start = (edit.range.start as isize + delta) as usize;
+            Edit {
+                range: start..start + edit.range.len(),
+                text: edit.text,
+            }
+        })
+        .collect()
+}
+
+/// Strip the common indentation of a block of lines and indent it anew
+fn reindent(text: &str, indent: &str) -> String {
+    let common = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                "\n".to_string()
+            } else {
+                format!("{}{}\n", indent, &line[common..])
+            }
+        })
+        .collect()
+}
+
+/// Edits per file, turned into a changeset once complete
+#[derive(Default)]
+struct Changes {
+    files: BTreeMap<PathBuf, (String, Vec<Edit>)>,
+}
+
+impl Changes {
+    fn edit(&mut self, path: &Path, content: &str, range: Range<usize>, text: String) {
+        self.files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| (content.to_string(), Vec::new()))
+            .1
+            .push(Edit { range, text });
+    }
+
+    fn into_changeset(self, title: &str, description: &str, tool: &str) -> Result<ChangeSet, RefactorError> {
+        let mut files = Vec::new();
+        for (path, (original, edits)) in self.files {
+            let updated = apply_edits(&original, edits);
+            if updated == original {
+                continue;
+            }
+            let diff_text = DiffApplySystem::generate_diff(Some(&original), &updated, &path);
+            files.push(FileDiff {
+                file_path: path,
+                diff_text,
+                change_type: ChangeType::Modify,
+                metadata: DiffMetadata {
+                    created_at: chrono::Utc::now(),
+                    agent_id: "rust-refactorer".to_string(),
+                    task_id: tool.to_string(),
+                    confidence_score: 1.0,
+                    estimated_lines_changed: similar::TextDiff::from_lines(&original, &updated)
+                        .iter_all_changes()
+                        .filter(|change| change.tag() != similar::ChangeTag::Equal)
+                        .count(),
+                    language: Some("rust".to_string()),
+                    description: description.to_string(),
+                },
+                original_content: Some(original),
+                new_content: updated,
+                hunks: Vec::new(),
+            });
+        }
+        if files.is_empty() {
+            return Err(RefactorError::NothingToDo(description.to_string()));
+        }
+        Ok(ChangeSet::new(title, description, "rust-refactorer", tool, files))
+    }
+}
+
+fn flatten_idents(stream: TokenStream, out: &mut Vec<proc_macro2::Ident>) {
+    for token in stream {
+        match token {
+            TokenTree::Ident(ident) => out.push(ident),
+            TokenTree::Group(group) => flatten_idents(group.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+/// Identifiers that may name a variable, with whether they are followed by
+/// `.` or `[`; field and method names, paths and macro names are skipped
+fn flatten_free_idents(stream: TokenStream, out: &mut Vec<(proc_macro2::Ident, bool)>, literals: &mut Vec<String>) {
+    let tokens: Vec<TokenTree> = stream.into_iter().collect();
+    let is_punct = |token: Option<&TokenTree>, c: char| matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == c);
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) => flatten_free_idents(group.stream(), out, literals),
+            TokenTree::Literal(literal) => literals.push(literal.to_string()),
+            TokenTree::Ident(ident) => {
+                let previous = index.checked_sub(1).and_then(|previous| tokens.get(previous));
+                let next = tokens.get(index + 1);
+                if is_punct(previous, '.') || is_punct(previous, ':') || is_punct(next, '!') || is_punct(next, ':') {
+                    continue;
+                }
+                let is_base = is_punct(next, '.') || matches!(next, Some(TokenTree::Group(g)) if g.delimiter() == proc_macro2::Delimiter::Bracket);
+                out.push((ident.clone(), is_base));
+            }
+            TokenTree::Punct(_) => {}
+        }
+    }
+}
+
+/// Lines of impl headers naming a type
+struct ImplHeaders<'n> {
+    name: &'n str,
+    spans: Vec<Span>,
+}
+
+impl<'ast> Visit<'ast> for ImplHeaders<'_> {
+    fn visit_item_impl(&mut self, block: &'ast syn::ItemImpl) {
+        if let syn::Type::Path(ty) = &*block.self_ty {
+            if let Some(segment) = ty.path.segments.last().filter(|segment| segment.ident == self.name) {
+                self.spans.push(segment.ident.span());
+            }
+        }
+        if let Some((_, path, _)) = &block.trait_ {
+            if let Some(segment) = path.segments.last().filter(|segment| segment.ident == self.name) {
+                self.spans.push(segment.ident.span());
+            }
+        }
+        visit::visit_item_impl(self, block);
+    }
+}
+
+/// A function with a body
+struct FunctionRef<'ast> {
+    sig: &'ast syn::Signature,
+    block: &'ast Block,
+    item_span: Span,
+    in_impl: bool,
+}
+
+#[derive(Default)]
+struct Functions<'ast> {
+    found: Vec<FunctionRef<'ast>>,
+}
+
+impl<'ast> Visit<'ast> for Functions<'ast> {
+    fn visit_item_fn(&mut self, function: &'ast syn::ItemFn) {
+        self.found.push(FunctionRef {
+            sig: &function.sig,
+            block: &function.block,
+            item_span: function.span(),
+            in_impl: false,
+        });
+        visit::visit_item_fn(self, function);
+    }
+
+    fn visit_impl_item_fn(&mut self, function: &'ast syn::ImplItemFn) {
+        self.found.push(FunctionRef {
+            sig: &function.sig,
+            block: &function.block,
+            item_span: function.span(),
+            in_impl: true,
+        });
+        visit::visit_impl_item_fn(self, function);
+    }
+}
+
+#[derive(Default)]
+struct Blocks<'ast> {
+    found: Vec<&'ast Block>,
+}
+
+impl<'ast> Visit<'ast> for Blocks<'ast> {
+    fn visit_block(&mut self, block: &'ast Block) {
+        self.found.push(block);
+        visit::visit_block(self, block);
+    }
+}
+
+/// Pick the block and statement range best covered by a line selection
+///
+/// The block whose fully selected statements reach furthest wins, so
+/// selecting a whole `if` takes the `if` rather than its body.
+fn select_statements(body: &Block, start_line: usize, end_line: usize) -> Result<(&Block, Range<usize>), RefactorError> {
+    let mut blocks = Blocks::default();
+    blocks.visit_block(body);
+
+    let mut best: Option<(&Block, Range<usize>, usize)> = None;
+    for block in blocks.found {
+        let (first_line, last_line) = span_lines(block.span());
+        if start_line < first_line || end_line > last_line {
+            continue;
+        }
+        let mut selected: Option<Range<usize>> = None;
+        let mut partial = false;
+        for (index, statement) in block.stmts.iter().enumerate() {
+            let (first, last) = span_lines(statement.span());
+            if start_line <= first && last <= end_line {
+                selected = Some(selected.map_or(index..index + 1, |range| range.start..index + 1));
+            } else if first <= end_line && start_line <= last {
+                partial = true;
+            }
+        }
+        let Some(selected) = selected.filter(|_| !partial) else {
+            continue;
+        };
+        let coverage = span_lines(block.stmts[selected.end - 1].span()).1 - span_lines(block.stmts[selected.start].span()).0;
+        if best.as_ref().map_or(true, |(_, _, best)| coverage > *best) {
+            best = Some((block, selected, coverage));
+        }
+    }
+    best.map(|(block, selected, _)| (block, selected)).ok_or_else(|| {
+        RefactorError::Unsupported(format!(
+            "lines {}-{} do not cover whole statements of one block",
+            start_line, end_line
+        ))
+    })
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    name: String,
+    ty: Option<String>,
+    mutable: bool,
+    offset: usize,
+}
+
+/// How an extracted function receives a variable
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Passing {
+    /// Already a reference
+    Value,
+    Ref,
+    MutRef,
+}
+
+/// Variables bound by patterns, with their annotated types
+struct Bindings<'s> {
+    source: &'s Source<'s>,
+    found: Vec<Binding>,
+}
+
+impl Bindings<'_> {
+    fn record(&mut self, ident: &syn::PatIdent, ty: Option<String>) {
+        self.found.push(Binding {
+            name: ident.ident.to_string(),
+            ty,
+            mutable: ident.mutability.is_some(),
+            offset: self.source.offset(ident.ident.span().start()),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for Bindings<'_> {
+    fn visit_pat_type(&mut self, pattern: &'ast syn::PatType) {
+        match &*pattern.pat {
+            Pat::Ident(ident) if ident.by_ref.is_none() => {
+                let ty = self.source.slice(pattern.ty.span()).to_string();
+                self.record(ident, Some(ty));
+            }
+            _ => visit::visit_pat_type(self, pattern),
+        }
+    }
+
+    fn visit_pat_ident(&mut self, pattern: &'ast syn::PatIdent) {
+        self.record(pattern, None);
+        visit::visit_pat_ident(self, pattern);
+    }
+
+    fn visit_item(&mut self, _item: &'ast Item) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UseKind {
+    Plain,
+    /// Receiver of a method call, which may mutate it
+    Receiver,
+    /// Base of a field access or index, auto-dereferenced
+    Base,
+    /// `Struct { name }` shorthand
+    Shorthand,
+}
+
+#[derive(Debug, Clone)]
+struct Use {
+    name: String,
+    range: Range<usize>,
+    kind: UseKind,
+    /// Inside a loop or closure, so possibly evaluated more than once
+    deferred: bool,
+}
+
+/// Variable uses, bindings and control flow in a stretch of code
+struct Scan<'s> {
+    source: &'s Source<'s>,
+    contexts: HashMap<usize, UseKind>,
+    uses: Vec<Use>,
+    bound: HashSet<String>,
+    mutated: HashSet<String>,
+    literals: Vec<String>,
+    loop_depth: usize,
+    closure_depth: usize,
+    control_flow: Option<&'static str>,
+    uses_self: bool,
+    has_side_effects: bool,
+}
+
+impl<'s> Scan<'s> {
+    fn new(source: &'s Source<'s>) -> Self {
+        Self {
+            source,
+            contexts: HashMap::new(),
+            uses: Vec::new(),
+            bound: HashSet::new(),
+            mutated: HashSet::new(),
+            literals: Vec::new(),
+            loop_depth: 0,
+            closure_depth: 0,
+            control_flow: None,
+            uses_self: false,
+            has_side_effects: false,
+        }
+    }
+
+    fn mark(&mut self, expr: &Expr, kind: UseKind) {
+        if let Expr::Path(path) = expr {
+            if let Some(ident) = path.path.get_ident() {
+                self.contexts.insert(self.source.offset(ident.span().start()), kind);
+                if kind == UseKind::Receiver {
+                    self.mutated.insert(ident.to_string());
+                }
+            }
+        }
+    }
+
+    fn mark_mutated(&mut self, expr: &Expr) {
+        let mut target = expr;
+        // `a.b = x` and `a[i] = x` change `a`
+        loop {
+            match target {
+                Expr::Field(field) => target = &field.base,
+                Expr::Index(index) => target = &index.expr,
+                Expr::Paren(paren) => target = &paren.expr,
+                _ => break,
+            }
+        }
+        if let Expr::Path(path) = target {
+            if let Some(ident) = path.path.get_ident() {
+                self.mutated.insert(ident.to_string());
+            }
+        }
+    }
+
+    fn deferred(&self) -> bool {
+        self.loop_depth > 0 || self.closure_depth > 0
+    }
+}
+
+impl<'ast> Visit<'ast> for Scan<'_> {
+    fn visit_expr_path(&mut self, expr: &'ast syn::ExprPath) {
+        if expr.qself.is_none() {
+            if let Some(ident) = expr.path.get_ident() {
+                let name = ident.to_string();
+                if name == "self" {
+                    self.uses_self = true;
+                }
+                let offset = self.source.offset(ident.span().start());
+                self.uses.push(Use {
+                    name,
+                    range: self.source.range(ident.span()),
+                    kind: self.contexts.get(&offset).copied().unwrap_or(UseKind::Plain),
+                    deferred: self.deferred(),
+                });
+            }
+        }
+        visit::visit_expr_path(self, expr);
+    }
+
+    fn visit_expr_method_call(&mut self, expr: &'ast syn::ExprMethodCall) {
+        self.has_side_effects = true;
+        self.mark(&expr.receiver, UseKind::Receiver);
+        visit::visit_expr_method_call(self, expr);
+    }
+
+    fn visit_expr_field(&mut self, expr: &'ast syn::ExprField) {
+        self.mark(&expr.base, UseKind::Base);
+        visit::visit_expr_field(self, expr);
+    }
+
+    fn visit_expr_index(&mut self, expr: &'ast syn::ExprIndex) {
+        self.mark(&expr.expr, UseKind::Base);
+        visit::visit_expr_index(self, expr);
+    }
+
+    fn visit_field_value(&mut self, field: &'ast syn::FieldValue) {
+        if field.colon_token.is_none() {
+            self.mark(&field.expr, UseKind::Shorthand);
+        }
+        visit::visit_field_value(self, field);
+    }
+
+    fn visit_expr_call(&mut self, expr: &'ast syn::ExprCall) {
+        self.has_side_effects = true;
+        visit::visit_expr_call(self, expr);
+    }
+
+    fn visit_expr_assign(&mut self, expr: &'ast syn::ExprAssign) {
+        self.has_side_effects = true;
+        self.mark_mutated(&expr.left);
+        visit::visit_expr_assign(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        use syn::BinOp;
+        if matches!(
+            expr.op,
+            BinOp::AddAssign(_)
+                | BinOp::SubAssign(_)
+                | BinOp::MulAssign(_)
+                | BinOp::DivAssign(_)
+                | BinOp::RemAssign(_)
+                | BinOp::BitXorAssign(_)
+                | BinOp::BitAndAssign(_)
+                | BinOp::BitOrAssign(_)
+                | BinOp::ShlAssign(_)
+                | BinOp::ShrAssign(_)
+        ) {
+            self.has_side_effects = true;
+            self.mark_mutated(&expr.left);
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_reference(&mut self, expr: &'ast syn::ExprReference) {
+        if expr.mutability.is_some() {
+            self.mark_mutated(&expr.expr);
+        }
+        visit::visit_expr_reference(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.has_side_effects = true;
+        let mut idents = Vec::new();
+        flatten_free_idents(mac.tokens.clone(), &mut idents, &mut self.literals);
+        for (ident, is_base) in idents {
+            let name = ident.to_string();
+            if name == "self" {
+                self.uses_self = true;
+            }
+            self.uses.push(Use {
+                name,
+                range: self.source.range(ident.span()),
+                kind: if is_base { UseKind::Base } else { UseKind::Plain },
+                deferred: self.deferred(),
+            });
+        }
+        visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_return(&mut self, expr: &'ast syn::ExprReturn) {
+        if self.closure_depth == 0 {
+            self.control_flow = Some("return");
+        }
+        visit::visit_expr_return(self, expr);
+    }
+
+    fn visit_expr_try(&mut self, expr: &'ast syn::ExprTry) {
+        if self.closure_depth == 0 {
+            self.control_flow = Some("?");
+        }
+        visit::visit_expr_try(self, expr);
+    }
+
+    fn visit_expr_await(&mut self, expr: &'ast syn::ExprAwait) {
+        self.has_side_effects = true;
+        if self.closure_depth == 0 {
+            self.control_flow = Some(".await");
+        }
+        visit::visit_expr_await(self, expr);
+    }
+
+    fn visit_expr_break(&mut self, expr: &'ast syn::ExprBreak) {
+        if self.closure_depth == 0 && (self.loop_depth == 0 || expr.label.is_some()) {
+            self.control_flow = Some("break");
+        }
+        visit::visit_expr_break(self, expr);
+    }
+
+    fn visit_expr_continue(&mut self, expr: &'ast syn::ExprContinue) {
+        if self.closure_depth == 0 && (self.loop_depth == 0 || expr.label.is_some()) {
+            self.control_flow = Some("continue");
+        }
+        visit::visit_expr_continue(self, expr);
+    }
+
+    fn visit_expr_loop(&mut self, expr: &'ast syn::ExprLoop) {
+        self.loop_depth += 1;
+        visit::visit_expr_loop(self, expr);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.loop_depth += 1;
+        visit::visit_expr_while(self, expr);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.loop_depth += 1;
+        visit::visit_expr_for_loop(self, expr);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_expr_closure(&mut self, expr: &'ast syn::ExprClosure) {
+        self.closure_depth += 1;
+        visit::visit_expr_closure(self, expr);
+        self.closure_depth -= 1;
+    }
+
+    fn visit_expr_async(&mut self, expr: &'ast syn::ExprAsync) {
+        self.closure_depth += 1;
+        visit::visit_expr_async(self, expr);
+        self.closure_depth -= 1;
+    }
+
+    fn visit_pat_ident(&mut self, pattern: &'ast syn::PatIdent) {
+        self.bound.insert(pattern.ident.to_string());
+        visit::visit_pat_ident(self, pattern);
+    }
+
+    // Nested items cannot see local variables
+    fn visit_item(&mut self, _item: &'ast Item) {}
+}
+
+/// Whether a pattern binds `name` directly
+fn binds(pattern: &Pat, name: &str) -> bool {
+    match pattern {
+        Pat::Ident(ident) => ident.ident == name,
+        Pat::Type(typed) => binds(&typed.pat, name),
+        _ => false,
+    }
+}
+
+/// Expressions that can replace a variable without parentheses
+fn is_primary(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Lit(_)
+            | Expr::Path(_)
+            | Expr::Call(_)
+            | Expr::MethodCall(_)
+            | Expr::Field(_)
+            | Expr::Macro(_)
+            | Expr::Paren(_)
+            | Expr::Index(_)
+            | Expr::Tuple(_)
+            | Expr::Array(_)
+            | Expr::Struct(_)
+    )
+}
+
+fn item_name(item: &Item) -> Option<String> {
+    let ident = match item {
+        Item::Fn(item) => &item.sig.ident,
+        Item::Struct(item) => &item.ident,
+        Item::Enum(item) => &item.ident,
+        Item::Union(item) => &item.ident,
+        Item::Trait(item) => &item.ident,
+        Item::Type(item) => &item.ident,
+        Item::Const(item) => &item.ident,
+        Item::Static(item) => &item.ident,
+        Item::Mod(item) => &item.ident,
+        _ => return None,
+    };
+    Some(ident.to_string())
+}
+
+/// First token after the attributes of a private item
+fn private_item_start(item: &Item) -> Option<Span> {
+    let (vis, start) = match item {
+        Item::Fn(item) => (&item.vis, item.sig.span()),
+        Item::Struct(item) => (&item.vis, item.struct_token.span),
+        Item::Enum(item) => (&item.vis, item.enum_token.span),
+        Item::Union(item) => (&item.vis, item.union_token.span),
+        Item::Trait(item) => (&item.vis, item.unsafety.map_or(item.trait_token.span, |token| token.span)),
+        Item::Type(item) => (&item.vis, item.type_token.span),
+        Item::Const(item) => (&item.vis, item.const_token.span),
+        Item::Static(item) => (&item.vis, item.static_token.span),
+        Item::Mod(item) => (&item.vis, item.mod_token.span),
+        _ => return None,
+    };
+    matches!(vis, syn::Visibility::Inherited).then_some(start)
+}
+
+fn impl_is_for(block: &syn::ItemImpl, name: &str) -> bool {
+    match &*block.self_ty {
+        syn::Type::Path(ty) => ty.path.segments.last().is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Offset after the last top-level `use`, or after the inner attributes
+fn import_position(syntax: &syn::File, source: &Source) -> usize {
+    let last_use = syntax
+        .items
+        .iter()
+        .rfind(|item| matches!(item, Item::Use(_)))
+        .map(|item| item.span().end().line);
+    let last_inner = syntax.attrs.last().map(|attr| attr.span().end().line);
+    match (last_use, last_inner) {
+        (Some(line), _) | (None, Some(line)) => source.line_end(line),
+        (None, None) => 0,
+    }
+}
+
+/// One name brought into scope by a `use` tree
+struct UseLeaf {
+    /// Full path, ending in the imported name
+    path: Vec<String>,
+    /// Name bound in the importing module; `None` for globs
+    bound: Option<String>,
+    rename: Option<String>,
+    glob: bool,
+}
+
+fn use_leaves(tree: &UseTree, prefix: &mut Vec<String>, out: &mut Vec<UseLeaf>) {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            use_leaves(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        UseTree::Name(name) if name.ident == "self" => out.push(UseLeaf {
+            path: prefix.clone(),
+            bound: prefix.last().cloned(),
+            rename: None,
+            glob: false,
+        }),
+        UseTree::Name(name) => {
+            let mut path = prefix.clone();
+            path.push(name.ident.to_string());
+            out.push(UseLeaf {
+                path,
+                bound: Some(name.ident.to_string()),
+                rename: None,
+                glob: false,
+            });
+        }
+        UseTree::Rename(rename) => {
+            let mut path = prefix.clone();
+            if rename.ident != "self" {
+                path.push(rename.ident.to_string());
+            }
+            out.push(UseLeaf {
+                path,
+                bound: Some(rename.rename.to_string()),
+                rename: Some(rename.rename.to_string()),
+                glob: false,
+            });
+        }
+        UseTree::Glob(_) => out.push(UseLeaf {
+            path: prefix.clone(),
+            bound: None,
+            rename: None,
+            glob: true,
+        }),
+        UseTree::Group(group) => {
+            for item in &group.items {
+                use_leaves(item, prefix, out);
+            }
+        }
+    }
+}
+
+fn render_import(path: &[String], leaf: &UseLeaf) -> String {
+    let mut import = format!("use {}", path.join("::"));
+    if leaf.glob {
+        import.push_str("::*");
+    }
+    if let Some(rename) = &leaf.rename {
+        import.push_str(&format!(" as {}", rename));
+    }
+    import.push(';');
+    import
+}
+
+/// A `use` tree without the leaves matching `remove`; `None` when nothing is left
+fn remove_use_leaf(tree: &UseTree, prefix: &mut Vec<String>, remove: &dyn Fn(&[String]) -> bool) -> Option<UseTree> {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            let inner = remove_use_leaf(&path.tree, prefix, remove);
+            prefix.pop();
+            inner.map(|inner| {
+                let mut path = path.clone();
+                path.tree = Box::new(inner);
+                UseTree::Path(path)
+            })
+        }
+        UseTree::Name(name) => {
+            let mut path = prefix.clone();
+            if name.ident != "self" {
+                path.push(name.ident.to_string());
+            }
+            (!remove(&path)).then(|| tree.clone())
+        }
+        UseTree::Rename(rename) => {
+            let mut path = prefix.clone();
+            if rename.ident != "self" {
+                path.push(rename.ident.to_string());
+            }
+            (!remove(&path)).then(|| tree.clone())
+        }
+        UseTree::Glob(_) => Some(tree.clone()),
+        UseTree::Group(group) => {
+            let items: Punctuated<UseTree, Token![,]> =
+                group.items.iter().filter_map(|item| remove_use_leaf(item, prefix, remove)).collect();
+            if items.is_empty() {
+                return None;
+            }
+            let mut group = group.clone();
+            group.items = items;
+            Some(UseTree::Group(group))
+        }
+    }
+}
+
+fn render_use_tree(tree: &UseTree) -> String {
+    match tree {
+        UseTree::Path(path) => format!("{}::{}", path.ident, render_use_tree(&path.tree)),
+        UseTree::Name(name) => name.ident.to_string(),
+        UseTree::Rename(rename) => format!("{} as {}", rename.ident, rename.rename),
+        UseTree::Glob(_) => "*".to_string(),
+        UseTree::Group(group) => {
+            let items: Vec<String> = group.items.iter().map(render_use_tree).collect();
+            match group.items.first() {
+                Some(UseTree::Name(name)) if items.len() == 1 && name.ident != "self" => items[0].clone(),
+                _ => format!("{{{}}}", items.join(", ")),
+            }
+        }
+    }
+}
+
+fn render_visibility(vis: &syn::Visibility) -> String {
+    match vis {
+        syn::Visibility::Inherited => String::new(),
+        vis => format!("{} ", vis.to_token_stream().to_string().replace(" (", "(").replace("( ", "(").replace(" )", ")")),
+    }
+}
+
+/// Resolve a `use` path against the importing module
+///
+/// Paths starting with `self`, `super` or a child module of the importing
+/// file become `crate::` paths; anything else is taken as an external crate.
+fn absolute_use_path(path: &[String], module: &[String], file: &Path, root: &Path) -> Vec<String> {
+    let Some(first) = path.first() else {
+        return Vec::new();
+    };
+    match first.as_str() {
+        "crate" | "self" | "super" => resolve_path(path, module),
+        child => {
+            let dir = module_dir(file);
+            let is_child = root.join(&dir).join(format!("{}.rs", child)).exists()
+                || root.join(&dir).join(child).join("mod.rs").exists();
+            if is_child {
+                module.iter().chain(path).cloned().collect()
+            } else {
+                path.to_vec()
+            }
+        }
+    }
+}
+
+/// Resolve `crate`, `self` and `super` prefixes; other paths are taken relative to the module
+fn resolve_path(path: &[String], module: &[String]) -> Vec<String> {
+    match path.first().map(String::as_str) {
+        Some("crate") => path.to_vec(),
+        Some("self") => module.iter().chain(&path[1..]).cloned().collect(),
+        Some("super") => {
+            let supers = path.iter().take_while(|segment| *segment == "super").count();
+            let base = &module[..module.len().saturating_sub(supers).max(1)];
+            base.iter().chain(&path[supers..]).cloned().collect()
+        }
+        _ => module.iter().chain(path).cloned().collect(),
+    }
+}
+
+/// Directory holding a module file's child modules
+fn module_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().map(Path::to_path_buf).unwrap_or_default();
+    match file.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod" | "lib" | "main") | None => parent,
+        Some(stem) => parent.join(stem),
+    }
+}
+
+/// Paths of two or more segments (`a::b::C`) in a token stream, with their ranges
+fn code_paths(stream: &TokenStream, source: &Source) -> Vec<(Range<usize>, Vec<String>)> {
+    let mut found = Vec::new();
+    let tokens: Vec<TokenTree> = stream.clone().into_iter().collect();
+    let mut index = 0;
+    while index < tokens.len() {
+        if let TokenTree::Group(group) = &tokens[index] {
+            found.extend(code_paths(&group.stream(), source));
+            index += 1;
+            continue;
+        }
+        let TokenTree::Ident(first) = &tokens[index] else {
+            index += 1;
+            continue;
+        };
+        let previous_is_separator = index >= 1 && matches!(&tokens[index - 1], TokenTree::Punct(p) if p.as_char() == ':' || p.as_char() == '.');
+        let mut segments = vec![first.clone()];
+        let mut next = index + 1;
+        while next + 2 < tokens.len() + 1 {
+            match (&tokens.get(next), &tokens.get(next + 1), &tokens.get(next + 2)) {
+                (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)), Some(TokenTree::Ident(ident)))
+                    if a.as_char() == ':' && b.as_char() == ':' =>
+                {
+                    segments.push(ident.clone());
+                    next += 3;
+                }
+                _ => break,
+            }
+        }
+        if segments.len() > 1 && !previous_is_separator {
+            // Every prefix may name the item, e.g. `crate::a::Item::new`
+            for end in 2..=segments.len() {
+                let range = source.offset(segments[0].span().start())..source.offset(segments[end - 1].span().end());
+                found.push((range, segments[..end].iter().map(|ident| ident.to_string()).collect()));
+            }
+        }
+        index = next.max(index + 1);
+    }
+    found
+}
+
+/// Rewrite `self::` and `super::` paths of moved code as `crate::` paths
+///
+/// Returns the rewritten text and the rewrites, relative to `text`.
+fn absolutize_relative_paths(
+    text: &str,
+    module: &[String],
+    file: &Path,
+) -> Result<(String, Vec<Edit>), RefactorError> {
+    let local = Source::new(text);
+    // Prefixes of one path share its start; keep the longest
+    let mut longest: BTreeMap<usize, (Range<usize>, Vec<String>)> = BTreeMap::new();
+    for (range, segments) in code_paths(&tokenize(file, text)?, &local) {
+        if matches!(segments[0].as_str(), "self" | "super")
+            && longest.get(&range.start).map_or(true, |(other, _)| other.end < range.end)
+        {
+            longest.insert(range.start, (range, segments));
+        }
+    }
+    let edits: Vec<Edit> = longest
+        .into_values()
+        .map(|(range, segments)| Edit {
+            range,
+            text: resolve_path(&segments, module).join("::"),
+        })
+        .collect();
+    Ok((apply_edits(text, edits.clone()), edits))
+}
+
+/// Struct, enum or union by name, with its attributes and first token after them
+fn find_type<'a>(syntax: &'a syn::File, name: &str) -> Option<(&'a [syn::Attribute], Span)> {
+    struct Finder<'a, 'n> {
+        name: &'n str,
+        found: Option<(&'a [syn::Attribute], Span)>,
+    }
+    impl<'a> Visit<'a> for Finder<'a, '_> {
+        fn visit_item_struct(&mut self, item: &'a syn::ItemStruct) {
+            if item.ident == self.name && self.found.is_none() {
+                self.found = Some((&item.attrs, first_after_attributes(&item.vis, item.struct_token.span)));
+            }
+        }
+        fn visit_item_enum(&mut self, item: &'a syn::ItemEnum) {
+            if item.ident == self.name && self.found.is_none() {
+                self.found = Some((&item.attrs, first_after_attributes(&item.vis, item.enum_token.span)));
+            }
+        }
+        fn visit_item_union(&mut self, item: &'a syn::ItemUnion) {
+            if item.ident == self.name && self.found.is_none() {
+                self.found = Some((&item.attrs, first_after_attributes(&item.vis, item.union_token.span)));
+            }
+        }
+    }
+    let mut finder = Finder { name, found: None };
+    finder.visit_file(syntax);
+    finder.found
+}
+
+fn first_after_attributes(vis: &syn::Visibility, keyword: Span) -> Span {
+    match vis {
+        syn::Visibility::Inherited => keyword,
+        vis => vis.span(),
+    }
+}
+
+/// `#[derive(...)]` attributes with their paths as written
+fn derive_attributes(attrs: &[syn::Attribute]) -> Vec<(&syn::Attribute, Vec<String>)> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            let paths = attr
+                .parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+                .ok()?;
+            let paths = paths
+                .iter()
+                .map(|path| {
+                    path.segments
+                        .iter()
+                        .map(|segment| segment.ident.to_string())
+                        .collect::<Vec<_>>()
+                        .join("::")
+                })
+                .collect();
+            Some((attr, paths))
+        })
+        .collect()
+}
+
+/// `serde::Serialize` and `Serialize` name the same derive
+fn same_derive(existing: &str, wanted: &str) -> bool {
+    let last = |path: &str| path.rsplit("::").next().unwrap_or(path).trim().to_string();
+    existing == wanted || last(existing) == last(wanted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn new_content<'a>(changeset: &'a ChangeSet, path: &str) -> &'a str {
+        &changeset
+            .files
+            .iter()
+            .find(|file| file.file_path == Path::new(path))
+            .unwrap()
+            .new_content
+    }
+
+    #[test]
+    fn renames_a_function_and_its_callers_across_files() {
+        let dir = project(&[
+            ("src/lib.rs", "pub mod util;\n\npub fn run() -> u32 {\n    util::compute(2)\n}\n"),
+            ("src/util.rs", "pub fn compute(x: u32) -> u32 {\n    x * 2\n}\n\npub fn other() -> u32 {\n    compute(1)\n}\n"),
+        ]);
+        let changeset = RustRefactorer::new(dir.path())
+            .rename_symbol("compute", "double", None, None)
+            .unwrap();
+
+        assert_eq!(new_content(&changeset, "src/lib.rs"), "pub mod util;\n\npub fn run() -> u32 {\n    util::double(2)\n}\n");
+        assert_eq!(
+            new_content(&changeset, "src/util.rs"),
+            "pub fn double(x: u32) -> u32 {\n    x * 2\n}\n\npub fn other() -> u32 {\n    double(1)\n}\n"
+        );
+        assert!(matches!(
+            RustRefactorer::new(dir.path()).rename_symbol("compute", "other", None, None),
+            Err(RefactorError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn renames_only_references_resolved_to_the_method() {
+        let dir = project(&[
+            ("Cargo.toml", "[package]\nname = \"demo\"\n"),
+            (
+                "src/lib.rs",
+                "pub struct Foo;\n\nimpl Foo {\n    pub fn len(&self) -> usize {\n        0\n    }\n\n    pub fn total(&self, items: &Vec<u8>) -> usize {\n        Foo::len(self) + Vec::len(items) + self.len()\n    }\n}\n\npub fn count(items: Vec<u8>) -> usize {\n    items.len()\n}\n",
+            ),
+            ("stacks/app/template/Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("stacks/app/template/src/lib.rs", "pub struct Foo;\n\nimpl Foo {\n    pub fn len(&self) -> usize {\n        1\n    }\n}\n"),
+            ("src/broken.rs", "fn len( {\n"),
+        ]);
+        let changeset = RustRefactorer::new(dir.path())
+            .rename_symbol("Foo::len", "size", None, None)
+            .unwrap();
+
+        assert_eq!(
+            new_content(&changeset, "src/lib.rs"),
+            "pub struct Foo;\n\nimpl Foo {\n    pub fn size(&self) -> usize {\n        0\n    }\n\n    pub fn total(&self, items: &Vec<u8>) -> usize {\n        Foo::size(self) + Vec::len(items) + self.size()\n    }\n}\n\npub fn count(items: Vec<u8>) -> usize {\n    items.len()\n}\n"
+        );
+        assert_eq!(changeset.files.len(), 1);
+        assert!(changeset.description.contains("src/lib.rs:14"));
+        assert!(!changeset.description.contains("src/lib.rs:9"));
+    }
+
+    #[test]
+    fn extracts_statements_with_parameters_and_return_values() {
+        let source = "\
+fn total(items: &[u32]) -> u32 {
+    let mut sum: u32 = 0;
+    let bonus: u32 = 5;
+    for item in items {
+        sum += item;
+    }
+    let doubled: u32 = sum * 2 + bonus;
+    doubled + 1
+}
+";
+        let dir = project(&[("src/lib.rs", source)]);
+        let changeset = RustRefactorer::new(dir.path())
+            .extract_function(Path::new("src/lib.rs"), 4, 7, "accumulate")
+            .unwrap();
+
+        assert_eq!(
+            new_content(&changeset, "src/lib.rs"),
+            "\
+fn total(items: &[u32]) -> u32 {
+    let mut sum: u32 = 0;
+    let bonus: u32 = 5;
+    let doubled = accumulate(items, &mut sum, &bonus);
+    doubled + 1
+}
+
+fn accumulate(items: &[u32], sum: &mut u32, bonus: &u32) -> u32 {
+    for item in items {
+        *sum += item;
+    }
+    let doubled: u32 = *sum * 2 + *bonus;
+    doubled
+}
+"
+        );
+    }
+
+    #[test]
+    fn inlines_a_variable_and_refuses_duplicated_side_effects() {
+        let source = "\
+fn area(width: u32, height: u32) -> u32 {
+    let size = width * height;
+    let report = Report { size };
+    report.total(size)
+}
+
+fn fetch() -> u32 {
+    let value = compute();
+    value + value
+}
+";
+        let dir = project(&[("src/lib.rs", source)]);
+        let refactorer = RustRefactorer::new(dir.path());
+        let changeset = refactorer.inline_variable(Path::new("src/lib.rs"), 2, "size").unwrap();
+
+        assert!(new_content(&changeset, "src/lib.rs").starts_with(
+            "\
+fn area(width: u32, height: u32) -> u32 {
+    let report = Report { size: width * height };
+    report.total((width * height))
+}
+"
+        ));
+        assert!(matches!(
+            refactorer.inline_variable(Path::new("src/lib.rs"), 8, "value"),
+            Err(RefactorError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn moves_an_item_and_rewrites_imports() {
+        let dir = project(&[
+            ("src/lib.rs", "pub mod a;\npub mod b;\n\nuse crate::a::{Config, helper};\n\npub fn load() -> Config {\n    helper();\n    crate::a::Config::default()\n}\n"),
+            ("src/a.rs", "use std::collections::HashMap;\n\n/// Settings\n#[derive(Default)]\npub struct Config {\n    values: HashMap<String, String>,\n}\n\nimpl Config {\n    fn get(&self) -> usize {\n        self.values.len()\n    }\n}\n\npub fn helper() -> usize {\n    Config::default().get()\n}\n"),
+            ("src/b.rs", "pub fn unrelated() {}\n"),
+        ]);
+        let changeset = RustRefactorer::new(dir.path())
+            .move_item(Path::new("src/a.rs"), "Config", Path::new("src/b.rs"))
+            .unwrap();
+
+        assert_eq!(
+            new_content(&changeset, "src/lib.rs"),
+            "pub mod a;\npub mod b;\n\nuse crate::a::helper;\nuse crate::b::Config;\n\npub fn load() -> Config {\n    helper();\n    crate::b::Config::default()\n}\n"
+        );
+        assert_eq!(
+            new_content(&changeset, "src/a.rs"),
+            "use std::collections::HashMap;\nuse crate::b::Config;\n\npub fn helper() -> usize {\n    Config::default().get()\n}\n"
+        );
+        assert_eq!(
+            new_content(&changeset, "src/b.rs"),
+            "use std::collections::HashMap;\npub fn unrelated() {}\n\n/// Settings\n#[derive(Default)]\npub struct Config {\n    values: HashMap<String, String>,\n}\n\nimpl Config {\n    pub(crate) fn get(&self) -> usize {\n        self.values.len()\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_derives() {
+        let dir = project(&[(
+            "src/lib.rs",
+            "/// A point\npub struct Point {\n    x: i32,\n}\n\n#[derive(Debug, Clone)]\nenum Shape {\n    Dot,\n}\n",
+        )]);
+        let refactorer = RustRefactorer::new(dir.path());
+        let file = Path::new("src/lib.rs");
+
+        let added = refactorer.add_derive(file, "Point", "Debug").unwrap();
+        assert!(new_content(&added, "src/lib.rs").starts_with("/// A point\n#[derive(Debug)]\npub struct Point {"));
+        let extended = refactorer.add_derive(file, "Shape", "serde::Serialize").unwrap();
+        assert!(new_content(&extended, "src/lib.rs").contains("#[derive(Debug, Clone, serde::Serialize)]\nenum Shape"));
+        let removed = refactorer.remove_derive(file, "Shape", "Clone").unwrap();
+        assert!(new_content(&removed, "src/lib.rs").contains("#[derive(Debug)]\nenum Shape"));
+        assert!(matches!(
+            refactorer.add_derive(file, "Shape", "Debug"),
+            Err(RefactorError::NothingToDo(_))
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+//! Self-healing generation driven by compiler and test feedback
+//!
+//! `CodeGenerator::generate_until_green` writes each generated file into a
+//! staging workspace, runs the compile and test gates against it and, while
+//! they fail, sends the parsed diagnostics back to the model for another
+//! attempt, up to a fixed number of repair rounds.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::diff_apply::{
+    ChangeSet, ChangeType, DiffApplySystem, DiffMetadata, FileDiff, GateStatus, QualityGateConfig,
+    ValidationResults, ValidationStatus,
+};
+use super::staging::StagingStrategy;
+use super::toolchain::{parse_diagnostics, CompilerDiagnostic, DiagnosticSeverity, ToolchainRegistry};
+use super::{CodeGenError, CodeGenerator, GenerationRequest};
+
+/// Settings for `CodeGenerator::generate_until_green`
+#[derive(Debug, Clone)]
+pub struct RepairConfig {
+    /// Project the generated file belongs to
+    pub project_root: PathBuf,
+    /// Path of the generated file, relative to the project root
+    pub target: PathBuf,
+    /// Repair rounds after the first attempt
+    pub max_rounds: usize,
+    pub staging: StagingStrategy,
+    /// Compile and test commands per language
+    pub toolchains: ToolchainRegistry,
+}
+
+impl RepairConfig {
+    pub fn new(project_root: PathBuf, target: PathBuf) -> Self {
+        Self {
+            project_root,
+            target,
+            max_rounds: 3,
+            staging: StagingStrategy::Copy,
+            toolchains: ToolchainRegistry::default(),
+        }
+    }
+
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn with_staging(mut self, staging: StagingStrategy) -> Self {
+        self.staging = staging;
+        self
+    }
+
+    pub fn with_toolchains(mut self, toolchains: ToolchainRegistry) -> Self {
+        self.toolchains = toolchains;
+        self
+    }
+}
+
+/// One generated version of the file and how it fared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairAttempt {
+    /// 1 for the initial generation, then one per repair round
+    pub number: usize,
+    pub code: String,
+    pub validation: ValidationResults,
+    pub diagnostics: Vec<CompilerDiagnostic>,
+}
+
+impl RepairAttempt {
+    /// Whether the compile and test gates passed
+    pub fn is_green(&self) -> bool {
+        self.validation.overall_status != ValidationStatus::Failed
+    }
+
+    /// Errors to report back to the model
+    pub fn errors(&self) -> Vec<&CompilerDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+            .collect()
+    }
+}
+
+/// Every attempt of a generate-until-green run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepairOutcome {
+    pub attempts: Vec<RepairAttempt>,
+}
+
+impl RepairOutcome {
+    pub fn final_attempt(&self) -> Option<&RepairAttempt> {
+        self.attempts.last()
+    }
+
+    pub fn is_green(&self) -> bool {
+        self.final_attempt().is_some_and(RepairAttempt::is_green)
+    }
+}
+
+impl CodeGenerator {
+    /// Generate code and repair it with compiler and test feedback until the
+    /// compile and test gates pass or the repair rounds run out
+    ///
+    /// `on_attempt` is called after each attempt has been validated. Without
+    /// an AI backend there is nothing to repair with, so only one attempt runs.
+    pub async fn generate_until_green<F>(
+        &self,
+        request: GenerationRequest,
+        config: &RepairConfig,
+        mut on_attempt: F,
+    ) -> Result<RepairOutcome, CodeGenError>
+    where
+        F: FnMut(&RepairAttempt) + Send,
+    {
+        let gate_config = QualityGateConfig {
+            enabled_gates: vec!["compile".to_string(), "test".to_string()],
+            require_all_gates: true,
+            // Sequential so a failed build skips the tests
+            parallel_execution: false,
+            custom_commands: HashMap::new(),
+            staging: config.staging.clone(),
+            toolchains: config.toolchains.clone(),
+            ..QualityGateConfig::default()
+        };
+        let system = DiffApplySystem::new(gate_config, &config.project_root)
+            .map_err(|e| CodeGenError::GenerationFailed(format!("Failed to set up quality gates: {}", e)))?;
+
+        let mut code = self.generate_from_prompt(request.clone()).await?.generated_code;
+        let mut outcome = RepairOutcome { attempts: Vec::new() };
+
+        for number in 1..=config.max_rounds + 1 {
+            let attempt = validate_attempt(&system, config, number, code).await?;
+            on_attempt(&attempt);
+            let done = attempt.is_green() || number > config.max_rounds || !self.has_ai();
+            let repair_prompt = (!done).then(|| repair_prompt(&request, config, &attempt));
+            outcome.attempts.push(attempt);
+
+            match repair_prompt {
+                Some(prompt) => code = self.request_repair(&request, prompt).await?,
+                None => break,
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Ask the model for a corrected version of the file
+    async fn request_repair(&self, request: &GenerationRequest, prompt: String) -> Result<String, CodeGenError> {
+        use crate::ai::{ChatMessage, ChatRequest};
+
+        let ai_manager = self.ai_manager.as_ref().ok_or_else(|| {
+            CodeGenError::GenerationFailed("AI manager not available".to_string())
+        })?;
+
+        let parameters = crate::ai::ModelParameters {
+            temperature: Some(request.config.temperature.unwrap_or(0.2)),
+            max_tokens: request.config.max_tokens,
+            ..Default::default()
+        };
+
+        let chat_request = ChatRequest {
+            model: "llama3.2:latest".to_string(), // Use default model
+            messages: vec![
+                ChatMessage::system(
+                    "You are an expert software developer fixing code so that it builds and its tests pass. \
+                    Return ONLY the complete corrected file without explanations or markdown formatting.",
+                ),
+                ChatMessage::user(prompt),
+            ],
+            parameters: Some(parameters),
+            stream: false,
+        };
+
+        let response = ai_manager
+            .chat_completion_default(chat_request)
+            .await
+            .map_err(|e| CodeGenError::GenerationFailed(format!("AI repair failed: {}", e)))?;
+
+        // The previous attempt already carries any file header
+        let mut repair_request = request.clone();
+        repair_request.file_path = None;
+        self.post_process_ai_response(&response.message.content, &repair_request)
+    }
+}
+
+/// Stage the code as the target file and run the gates on it
+async fn validate_attempt(
+    system: &DiffApplySystem,
+    config: &RepairConfig,
+    number: usize,
+    code: String,
+) -> Result<RepairAttempt, CodeGenError> {
+    let mut changeset = ChangeSet::new(
+        &format!("Generated {}", config.target.display()),
+        &format!("Attempt {}", number),
+        "code-generator",
+        "generate-until-green",
+        vec![file_diff(&config.project_root, &config.target, &code, "generate-until-green")],
+    );
+    system
+        .validate_changeset(&mut changeset, &config.project_root)
+        .await
+        .map_err(|e| CodeGenError::GenerationFailed(format!("Validation failed: {}", e)))?;
+    let validation = changeset
+        .validation_results
+        .ok_or_else(|| CodeGenError::GenerationFailed("Validation produced no results".to_string()))?;
+    let diagnostics = gate_diagnostics(&validation);
+
+    Ok(RepairAttempt {
+        number,
+        code,
+        validation,
+        diagnostics,
+    })
+}
+
+/// Diagnostics of the failed gates
+///
+/// Gates that know their tools report parsed diagnostics; the raw output of
+/// the others is parsed here.
+pub(super) fn gate_diagnostics(validation: &ValidationResults) -> Vec<CompilerDiagnostic> {
+    validation
+        .gates
+        .values()
+        .filter(|gate| matches!(gate.status, GateStatus::Failed | GateStatus::Error))
+        .flat_map(|gate| match (&gate.details, gate.diagnostics.is_empty()) {
+            (Some(details), true) => parse_diagnostics(details),
+            _ => gate.diagnostics.clone(),
+        })
+        .collect()
+}
+
+/// Diff replacing a project file, or creating it, with generated code
+pub(super) fn file_diff(project_root: &Path, target: &Path, code: &str, task_id: &str) -> FileDiff {
+    let original = std::fs::read_to_string(project_root.join(target)).ok();
+    FileDiff {
+        file_path: target.to_path_buf(),
+        diff_text: DiffApplySystem::generate_diff(original.as_deref(), code, target),
+        change_type: if original.is_some() { ChangeType::Modify } else { ChangeType::Create },
+        original_content: original,
+        new_content: code.to_string(),
+        metadata: DiffMetadata {
+            created_at: chrono::Utc::now(),
+            agent_id: "code-generator".to_string(),
+            task_id: task_id.to_string(),
+            confidence_score: 0.5,
+            estimated_lines_changed: code.lines().count(),
+            language: None,
+            description: "Generated code under validation".to_string(),
+        },
+        hunks: Vec::new(),
+    }
+}
+
+/// Prompt asking the model to fix the failures of an attempt
+fn repair_prompt(request: &GenerationRequest, config: &RepairConfig, attempt: &RepairAttempt) -> String {
+    let language = request.config.target_language.as_deref().unwrap_or("");
+    let mut prompt = format!(
+        "The following code was generated for this task but does not build or pass its tests.\n\n\
+        Task: {}\nFile: {}\n",
+        request.prompt,
+        config.target.display()
+    );
+    if !language.is_empty() {
+        prompt.push_str(&format!("Language: {}\n", language));
+    }
+    prompt.push_str(&format!("\nCurrent code:\n```{}\n{}\n```\n\n", language, attempt.code));
+
+    let errors = attempt.errors();
+    if errors.is_empty() {
+        // Nothing parsed; fall back to the end of the raw gate output
+        prompt.push_str("Failure output:\n");
+        for gate in attempt.validation.gates.values() {
+            if let (GateStatus::Failed | GateStatus::Error, Some(details)) = (&gate.status, &gate.details) {
+                let lines: Vec<&str> = details.lines().collect();
+                let tail = &lines[lines.len().saturating_sub(40)..];
+                prompt.push_str(&format!("[{}]\n{}\n", gate.name, tail.join("\n")));
+            }
+        }
+    } else {
+        prompt.push_str("Errors:\n");
+        for error in errors {
+            prompt.push_str(&format!("- {}\n", error));
+        }
+    }
+    prompt.push_str("\nFix every error and return the complete corrected file.");
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::diff_apply::GateResult;
+    use crate::codegen::toolchain::DiagnosticSource;
+    use crate::codegen::GenerationConfig;
+    use crate::context::CodebaseContext;
+
+    #[test]
+    fn repair_prompt_lists_errors_but_not_warnings() {
+        let diagnostic = |severity, message: &str| CompilerDiagnostic {
+            source: DiagnosticSource::Rustc,
+            severity,
+            file: Some(PathBuf::from("src/lib.rs")),
+            line: Some(3),
+            column: Some(5),
+            code: None,
+            message: message.to_string(),
+        };
+        let gate = GateResult {
+            name: "compile".to_string(),
+            status: GateStatus::Failed,
+            message: "Failed for rust (cargo check)".to_string(),
+            details: Some("raw output".to_string()),
+            diagnostics: Vec::new(),
+            execution_time_ms: 0,
+            is_blocking: true,
+        };
+        let attempt = RepairAttempt {
+            number: 1,
+            code: "fn add() -> i32 { \"1\" }".to_string(),
+            validation: ValidationResults {
+                gates: HashMap::from([("compile".to_string(), gate)]),
+                overall_status: ValidationStatus::Failed,
+                can_auto_apply: false,
+                warnings: Vec::new(),
+                errors: Vec::new(),
+                validated_at: chrono::Utc::now(),
+            },
+            diagnostics: vec![
+                diagnostic(DiagnosticSeverity::Error, "mismatched types"),
+                diagnostic(DiagnosticSeverity::Warning, "unused variable"),
+            ],
+        };
+        let request = GenerationRequest {
+            prompt: "add two numbers".to_string(),
+            file_path: None,
+            context: CodebaseContext::default(),
+            config: GenerationConfig::default(),
+            constraints: Vec::new(),
+        };
+        let config = RepairConfig::new(PathBuf::from("/project"), PathBuf::from("src/lib.rs"));
+
+        let prompt = repair_prompt(&request, &config, &attempt);
+
+        assert!(!attempt.is_green());
+        assert!(prompt.contains("- src/lib.rs:3:5: error: mismatched types"));
+        assert!(!prompt.contains("unused variable"));
+        assert!(!prompt.contains("raw output"));
+        assert!(prompt.contains(&attempt.code));
+    }
+}
@@ -0,0 +1,733 @@
+//! Scaffold stacks for `devkit generate --stack`
+//!
+//! A stack is a directory holding a `stack.toml` manifest and a `template/`
+//! tree. Every template file is rendered with Handlebars, including its path,
+//! so `{{project_name}}` and the manifest's variables can appear anywhere. A
+//! leading `dot_` in a file or directory name becomes `.`, which keeps
+//! `dot_gitignore` from acting on the repository holding the stack.
+//!
+//! ```toml
+//! name = "team-service"
+//! description = "Internal service with our CI setup"
+//! language = "rust"
+//! version = "2.1.0"
+//!
+//! [[variables]]
+//! name = "port"
+//! prompt = "Port to listen on"
+//! default = "8080"
+//!
+//! [[variables]]
+//! name = "docker"
+//! kind = "bool"
+//! default = "false"
+//!
+//! [[files]]
+//! path = "Dockerfile"
+//! when = "docker"
+//!
+//! [[post_generate]]
+//! run = "cargo fmt"
+//! optional = true
+//! ```
+//!
+//! Stacks are looked up in the built-in set, then `~/.devkit/stacks`, then the
+//! project's `.devkit/stacks`; later ones replace earlier ones of the same
+//! name. A stack can also be given as a directory or a git URL.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+/// Directory of stacks, relative to the home directory or a project
+pub const STACKS_DIR: &str = ".devkit/stacks";
+
+const MANIFEST: &str = "stack.toml";
+const TEMPLATE_DIR: &str = "template";
+
+#[derive(rust_embed::RustEmbed)]
+#[folder = "stacks/"]
+struct BuiltinStacks;
+
+/// Errors loading or rendering a stack
+#[derive(Debug, Error)]
+pub enum StackError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unknown stack: {0}")]
+    NotFound(String),
+
+    #[error("Invalid stack manifest {path}: {message}")]
+    InvalidManifest { path: PathBuf, message: String },
+
+    #[error("Invalid value for {name}: {message}")]
+    InvalidValue { name: String, message: String },
+
+    #[error("Stack {stack} has no variable {name}")]
+    UnknownVariable { stack: String, name: String },
+
+    #[error("Failed to render {path}: {message}")]
+    Render { path: String, message: String },
+
+    #[error("Git error: {0}")]
+    Git(#[from] git2::Error),
+
+    #[error("Post-generate command `{command}` failed: {message}")]
+    PostGenerate { command: String, message: String },
+}
+
+/// A stack's `stack.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackManifest {
+    /// Defaults to the stack's directory name
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Language of the generated project, as taken by `--language`
+    pub language: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub variables: Vec<StackVariable>,
+    /// Template files included only under a condition
+    #[serde(default)]
+    pub files: Vec<ConditionalFiles>,
+    #[serde(default)]
+    pub post_generate: Vec<PostGenerateCommand>,
+    /// Globs of template files copied without rendering
+    #[serde(default)]
+    pub raw: Vec<String>,
+}
+
+/// A value asked for when the stack is generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackVariable {
+    pub name: String,
+    /// Question shown when asking for the value
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Template rendered with the built-in values and earlier variables
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub kind: VariableKind,
+    /// Allowed values of a choice
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableKind {
+    #[default]
+    String,
+    Bool,
+    Choice,
+}
+
+/// Template files, selected by a glob, that are generated only when `when` holds
+///
+/// Conditions are `var`, `!var`, `var == value` or `var != value`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionalFiles {
+    pub path: String,
+    pub when: String,
+}
+
+/// A shell command run in the generated project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostGenerateCommand {
+    /// Rendered like a template before it runs
+    pub run: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub when: Option<String>,
+    /// A failure is reported as a warning instead of an error
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// Values of the built-in and stack variables
+pub type StackValues = BTreeMap<String, serde_json::Value>;
+
+/// Where a stack was loaded from
+#[derive(Debug, Clone, PartialEq)]
+pub enum StackSource {
+    Builtin,
+    Directory(PathBuf),
+    Git { url: String, reference: Option<String> },
+}
+
+impl fmt::Display for StackSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackSource::Builtin => write!(f, "built-in"),
+            StackSource::Directory(dir) => write!(f, "{}", dir.display()),
+            StackSource::Git { url, reference: Some(reference) } => write!(f, "{}#{}", url, reference),
+            StackSource::Git { url, reference: None } => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A loaded stack with its template files
+#[derive(Debug, Clone)]
+pub struct Stack {
+    pub manifest: StackManifest,
+    pub source: StackSource,
+    /// Template files by path relative to the template directory
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl Stack {
+    /// Load a stack directory holding `stack.toml` and `template/`
+    pub fn from_dir(dir: &Path) -> Result<Self, StackError> {
+        let manifest_path = dir.join(MANIFEST);
+        let manifest = std::fs::read_to_string(&manifest_path)?;
+        let mut files = BTreeMap::new();
+        let template_dir = dir.join(TEMPLATE_DIR);
+        if template_dir.is_dir() {
+            for entry in walkdir::WalkDir::new(&template_dir) {
+                let entry = entry.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let relative = entry.path().strip_prefix(&template_dir).unwrap_or(entry.path());
+                files.insert(relative.to_path_buf(), read_text(entry.path(), std::fs::read(entry.path())?)?);
+            }
+        }
+        let default_name = dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        Self::new(&manifest_path, &manifest, &default_name, StackSource::Directory(dir.to_path_buf()), files)
+    }
+
+    /// Clone a stack from git; `url#reference` checks out a branch, tag or commit
+    pub fn from_git(spec: &str) -> Result<Self, StackError> {
+        let (url, reference) = match spec.rsplit_once('#') {
+            Some((url, reference)) => (url, Some(reference)),
+            None => (spec, None),
+        };
+        let checkout = tempfile::tempdir()?;
+        let repo = git2::Repository::clone(url, checkout.path())?;
+        if let Some(reference) = reference {
+            let object = repo
+                .revparse_single(&format!("origin/{}", reference))
+                .or_else(|_| repo.revparse_single(reference))?;
+            repo.checkout_tree(&object, Some(git2::build::CheckoutBuilder::new().force()))?;
+            repo.set_head_detached(object.peel_to_commit()?.id())?;
+        }
+
+        let mut stack = Self::from_dir(checkout.path())?;
+        if stack.manifest.name == checkout.path().file_name().unwrap_or_default().to_string_lossy() {
+            stack.manifest.name = url
+                .trim_end_matches('/')
+                .trim_end_matches(".git")
+                .rsplit(['/', ':'])
+                .next()
+                .unwrap_or(url)
+                .to_string();
+        }
+        stack.source = StackSource::Git {
+            url: url.to_string(),
+            reference: reference.map(str::to_string),
+        };
+        Ok(stack)
+    }
+
+    fn new(
+        manifest_path: &Path,
+        manifest: &str,
+        default_name: &str,
+        source: StackSource,
+        files: BTreeMap<PathBuf, String>,
+    ) -> Result<Self, StackError> {
+        let invalid = |message: String| StackError::InvalidManifest {
+            path: manifest_path.to_path_buf(),
+            message,
+        };
+        let mut manifest: StackManifest = toml::from_str(manifest).map_err(|e| invalid(e.to_string()))?;
+        if manifest.name.is_empty() {
+            manifest.name = default_name.to_string();
+        }
+        for variable in &manifest.variables {
+            if variable.kind == VariableKind::Choice && variable.choices.is_empty() {
+                return Err(invalid(format!("choice variable {} has no choices", variable.name)));
+            }
+        }
+        for pattern in manifest.files.iter().map(|files| &files.path).chain(&manifest.raw) {
+            Glob::new(pattern).map_err(|e| invalid(format!("invalid glob '{}': {}", pattern, e)))?;
+        }
+        Ok(Self { manifest, source, files })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    /// Resolve every variable, built-in ones included
+    ///
+    /// `provided` values win; otherwise `ask` is offered the variable and its
+    /// rendered default and may return an answer, or `None` to take the default.
+    pub fn resolve_variables<F>(
+        &self,
+        builtins: &BTreeMap<String, String>,
+        provided: &HashMap<String, String>,
+        mut ask: F,
+    ) -> Result<StackValues, StackError>
+    where
+        F: FnMut(&StackVariable, Option<&str>) -> Option<String>,
+    {
+        if let Some(unknown) = provided
+            .keys()
+            .find(|name| !builtins.contains_key(*name) && !self.manifest.variables.iter().any(|v| &v.name == *name))
+        {
+            return Err(StackError::UnknownVariable {
+                stack: self.manifest.name.clone(),
+                name: unknown.clone(),
+            });
+        }
+
+        let mut values: StackValues = builtins
+            .iter()
+            .map(|(name, value)| {
+                let value = provided.get(name).unwrap_or(value);
+                (name.clone(), serde_json::Value::String(value.clone()))
+            })
+            .collect();
+        for variable in &self.manifest.variables {
+            let default = variable
+                .default
+                .as_deref()
+                .map(|default| render(&format!("default of {}", variable.name), default, &values))
+                .transpose()?;
+            let raw = match provided.get(&variable.name) {
+                Some(value) => Some(value.clone()),
+                None => ask(variable, default.as_deref()).or(default),
+            };
+            let value = match raw {
+                Some(raw) => parse_value(variable, &raw)?,
+                None if variable.kind == VariableKind::Bool => serde_json::Value::Bool(false),
+                None => {
+                    return Err(StackError::InvalidValue {
+                        name: variable.name.clone(),
+                        message: "a value is required".to_string(),
+                    })
+                }
+            };
+            values.insert(variable.name.clone(), value);
+        }
+        Ok(values)
+    }
+
+    /// Render the template into files relative to the project root
+    pub fn render(&self, values: &StackValues) -> Result<Vec<(PathBuf, String)>, StackError> {
+        let raw = glob_set(self.manifest.raw.iter())?;
+        let mut excluded = Vec::new();
+        for files in &self.manifest.files {
+            if !condition_holds(&files.when, values)? {
+                excluded.push(&files.path);
+            }
+        }
+        let excluded = glob_set(excluded.into_iter())?;
+
+        let mut rendered = Vec::new();
+        for (path, content) in &self.files {
+            if excluded.is_match(path) {
+                continue;
+            }
+            let label = path.display().to_string();
+            let target = render(&label, &output_path(path), values)?;
+            let target = PathBuf::from(target);
+            if target.components().any(|component| !matches!(component, Component::Normal(_))) {
+                return Err(StackError::Render {
+                    path: label,
+                    message: format!("rendered path {} leaves the project", target.display()),
+                });
+            }
+            let content = if raw.is_match(path) {
+                content.clone()
+            } else {
+                render(&label, content, values)?
+            };
+            rendered.push((target, content));
+        }
+        Ok(rendered)
+    }
+
+    /// Post-generate commands whose condition holds, rendered
+    pub fn post_generate(&self, values: &StackValues) -> Result<Vec<PostGenerateCommand>, StackError> {
+        let mut commands = Vec::new();
+        for command in &self.manifest.post_generate {
+            if let Some(when) = &command.when {
+                if !condition_holds(when, values)? {
+                    continue;
+                }
+            }
+            let mut command = command.clone();
+            command.run = render("post_generate", &command.run, values)?;
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+}
+
+/// Run a post-generate command through the platform shell
+pub fn run_post_generate(command: &PostGenerateCommand, dir: &Path) -> Result<(), StackError> {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let failed = |message: String| StackError::PostGenerate {
+        command: command.run.clone(),
+        message,
+    };
+    let output = std::process::Command::new(shell)
+        .arg(flag)
+        .arg(&command.run)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| failed(e.to_string()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(failed(format!("{}: {}", output.status, stderr.trim())))
+    }
+}
+
+/// Stacks by name
+#[derive(Debug, Clone, Default)]
+pub struct StackRegistry {
+    stacks: BTreeMap<String, Stack>,
+}
+
+impl StackRegistry {
+    /// The stacks shipped with devkit
+    pub fn builtin() -> Self {
+        let mut grouped: BTreeMap<String, (Option<String>, BTreeMap<PathBuf, String>)> = BTreeMap::new();
+        for path in BuiltinStacks::iter() {
+            let Some(file) = BuiltinStacks::get(&path) else {
+                continue;
+            };
+            let Some((stack, rest)) = path.split_once('/') else {
+                continue;
+            };
+            let entry = grouped.entry(stack.to_string()).or_default();
+            let Ok(text) = read_text(Path::new(path.as_ref()), file.data.into_owned()) else {
+                continue;
+            };
+            if rest == MANIFEST {
+                entry.0 = Some(text);
+            } else if let Some(template_path) = rest.strip_prefix("template/") {
+                entry.1.insert(PathBuf::from(template_path), text);
+            }
+        }
+
+        let mut registry = Self::default();
+        for (name, (manifest, files)) in grouped {
+            let Some(manifest) = manifest else {
+                continue;
+            };
+            match Stack::new(&Path::new(&name).join(MANIFEST), &manifest, &name, StackSource::Builtin, files) {
+                Ok(stack) => registry = registry.with_stack(stack),
+                Err(e) => tracing::warn!("Skipping built-in stack {}: {}", name, e),
+            }
+        }
+        registry
+    }
+
+    /// Built-in stacks, then the user's, then the project's
+    pub fn discover(project_root: &Path) -> Self {
+        let mut registry = Self::builtin();
+        let dirs = dirs::home_dir()
+            .map(|home| home.join(STACKS_DIR))
+            .into_iter()
+            .chain(std::iter::once(project_root.join(STACKS_DIR)));
+        for dir in dirs {
+            registry = registry.with_stacks_in(&dir);
+        }
+        registry
+    }
+
+    /// Add every stack directory under `dir`, replacing stacks of the same name
+    pub fn with_stacks_in(mut self, dir: &Path) -> Self {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return self;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(MANIFEST).is_file())
+            .collect();
+        paths.sort();
+        for path in paths {
+            match Stack::from_dir(&path) {
+                Ok(stack) => self = self.with_stack(stack),
+                Err(e) => tracing::warn!("Skipping stack {}: {}", path.display(), e),
+            }
+        }
+        self
+    }
+
+    pub fn with_stack(mut self, stack: Stack) -> Self {
+        self.stacks.insert(stack.manifest.name.clone(), stack);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Stack> {
+        self.stacks.get(name)
+    }
+
+    pub fn stacks(&self) -> impl Iterator<Item = &Stack> {
+        self.stacks.values()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.stacks.keys().map(String::as_str).collect()
+    }
+
+    /// A stack by name, by directory or by git URL
+    pub fn resolve(&self, spec: &str) -> Result<Stack, StackError> {
+        if is_git_url(spec) {
+            return Stack::from_git(spec);
+        }
+        if let Some(stack) = self.get(spec) {
+            return Ok(stack.clone());
+        }
+        let dir = Path::new(spec);
+        if dir.join(MANIFEST).is_file() {
+            return Stack::from_dir(dir);
+        }
+        Err(StackError::NotFound(spec.to_string()))
+    }
+}
+
+fn is_git_url(spec: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://", "git@"]
+        .iter()
+        .any(|prefix| spec.starts_with(prefix))
+        || spec.split('#').next().is_some_and(|url| url.ends_with(".git"))
+}
+
+fn read_text(path: &Path, data: Vec<u8>) -> Result<String, StackError> {
+    String::from_utf8(data).map_err(|_| StackError::Render {
+        path: path.display().to_string(),
+        message: "template files must be UTF-8 text".to_string(),
+    })
+}
+
+/// Template path with `dot_` prefixes turned into dots
+fn output_path(path: &Path) -> String {
+    path.components()
+        .map(|component| {
+            let name = component.as_os_str().to_string_lossy();
+            match name.strip_prefix("dot_") {
+                Some(rest) => format!(".{}", rest),
+                None => name.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn glob_set<'a>(patterns: impl Iterator<Item = &'a String>) -> Result<GlobSet, StackError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(|e| StackError::Render {
+            path: pattern.clone(),
+            message: e.to_string(),
+        })?);
+    }
+    builder.build().map_err(|e| StackError::Render {
+        path: "globs".to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn render(label: &str, template: &str, values: &StackValues) -> Result<String, StackError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.render_template(template, values).map_err(|e| StackError::Render {
+        path: label.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn parse_value(variable: &StackVariable, raw: &str) -> Result<serde_json::Value, StackError> {
+    let invalid = |message: String| StackError::InvalidValue {
+        name: variable.name.clone(),
+        message,
+    };
+    match variable.kind {
+        VariableKind::String => Ok(serde_json::Value::String(raw.to_string())),
+        VariableKind::Bool => match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" => Ok(serde_json::Value::Bool(true)),
+            "false" | "no" | "n" | "0" | "" => Ok(serde_json::Value::Bool(false)),
+            other => Err(invalid(format!("'{}' is not a boolean", other))),
+        },
+        VariableKind::Choice => {
+            if variable.choices.iter().any(|choice| choice == raw) {
+                Ok(serde_json::Value::String(raw.to_string()))
+            } else {
+                Err(invalid(format!("'{}' is not one of {}", raw, variable.choices.join(", "))))
+            }
+        }
+    }
+}
+
+/// Evaluate `var`, `!var`, `var == value` or `var != value`
+fn condition_holds(condition: &str, values: &StackValues) -> Result<bool, StackError> {
+    let lookup = |name: &str| {
+        values.get(name.trim()).ok_or_else(|| StackError::InvalidValue {
+            name: name.trim().to_string(),
+            message: format!("condition '{}' uses an unknown variable", condition),
+        })
+    };
+    let text = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let expected = |value: &str| value.trim().trim_matches(['"', '\'']).to_string();
+
+    if let Some((name, value)) = condition.split_once("!=") {
+        return Ok(text(lookup(name)?) != expected(value));
+    }
+    if let Some((name, value)) = condition.split_once("==") {
+        return Ok(text(lookup(name)?) == expected(value));
+    }
+    let (negated, name) = match condition.trim().strip_prefix('!') {
+        Some(name) => (true, name),
+        None => (false, condition),
+    };
+    let truthy = match lookup(name)? {
+        serde_json::Value::Bool(value) => *value,
+        serde_json::Value::String(value) => !value.is_empty() && value != "false",
+        serde_json::Value::Null => false,
+        _ => true,
+    };
+    Ok(truthy != negated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builtins(project_name: &str) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("project_name".to_string(), project_name.to_string()),
+            ("prompt".to_string(), "a service".to_string()),
+        ])
+    }
+
+    fn write_stack(dir: &Path, manifest: &str, files: &[(&str, &str)]) {
+        std::fs::create_dir_all(dir.join(TEMPLATE_DIR)).unwrap();
+        std::fs::write(dir.join(MANIFEST), manifest).unwrap();
+        for (path, content) in files {
+            let path = dir.join(TEMPLATE_DIR).join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn builtin_stacks_render_with_defaults() {
+        let registry = StackRegistry::builtin();
+        for name in ["rust-axum", "rust-actix", "rust-axum-sqlx", "node-express", "node-nest", "nextjs", "python-fastapi", "python-fastapi-sqlalchemy"] {
+            let stack = registry.get(name).unwrap_or_else(|| panic!("missing built-in stack {}", name));
+            let values = stack.resolve_variables(&builtins("demo"), &HashMap::new(), |_, _| None).unwrap();
+            let files = stack.render(&values).unwrap();
+            assert!(files.iter().any(|(path, _)| path == Path::new("README.md")), "{}", name);
+            assert!(files.iter().any(|(path, _)| path == Path::new(".gitignore")), "{}", name);
+            assert!(!files.iter().any(|(path, _)| path == Path::new("Dockerfile")), "{}", name);
+        }
+
+        let axum = registry.get("rust-axum").unwrap();
+        let provided = HashMap::from([("port".to_string(), "8080".to_string()), ("docker".to_string(), "yes".to_string())]);
+        let values = axum.resolve_variables(&builtins("svc"), &provided, |_, _| None).unwrap();
+        let files: BTreeMap<PathBuf, String> = axum.render(&values).unwrap().into_iter().collect();
+        assert!(files[Path::new("Cargo.toml")].contains("name = \"svc\""));
+        assert!(files[Path::new("src/main.rs")].contains("127.0.0.1:8080"));
+        assert!(files[Path::new("Dockerfile")].contains("EXPOSE 8080"));
+    }
+
+    #[test]
+    fn variables_are_validated_and_defaults_rendered() {
+        let dir = tempfile::tempdir().unwrap();
+        write_stack(
+            dir.path(),
+            "language = \"rust\"\n\n[[variables]]\nname = \"crate_name\"\ndefault = \"{{project_name}}-core\"\n\n[[variables]]\nname = \"db\"\nkind = \"choice\"\nchoices = [\"postgres\", \"sqlite\"]\ndefault = \"sqlite\"\n\n[[files]]\npath = \"migrations/**\"\nwhen = \"db == postgres\"\n\n[[post_generate]]\nrun = \"echo {{crate_name}}\"\nwhen = \"!db == sqlite\"\n",
+            &[("{{crate_name}}.txt", "{{db}}"), ("migrations/001.sql", "create table t();"), ("raw.hbs", "{{literal}}")],
+        );
+        let stack = Stack::from_dir(dir.path()).unwrap();
+        assert_eq!(stack.name(), dir.path().file_name().unwrap().to_string_lossy());
+
+        let mut asked = Vec::new();
+        let values = stack
+            .resolve_variables(&builtins("app"), &HashMap::new(), |variable, default| {
+                asked.push((variable.name.clone(), default.map(str::to_string)));
+                None
+            })
+            .unwrap();
+        assert_eq!(asked[0], ("crate_name".to_string(), Some("app-core".to_string())));
+        assert!(matches!(stack.render(&values), Err(StackError::Render { .. })));
+
+        let mut raw = stack.clone();
+        raw.manifest.raw = vec!["*.hbs".to_string()];
+        let files: BTreeMap<PathBuf, String> = raw.render(&values).unwrap().into_iter().collect();
+        assert_eq!(files[Path::new("app-core.txt")], "sqlite");
+        assert_eq!(files[Path::new("raw.hbs")], "{{literal}}");
+        assert!(!files.contains_key(Path::new("migrations/001.sql")));
+
+        let invalid = HashMap::from([("db".to_string(), "mysql".to_string())]);
+        assert!(matches!(
+            stack.resolve_variables(&builtins("app"), &invalid, |_, _| None),
+            Err(StackError::InvalidValue { .. })
+        ));
+        let unknown = HashMap::from([("colour".to_string(), "red".to_string())]);
+        assert!(matches!(
+            stack.resolve_variables(&builtins("app"), &unknown, |_, _| None),
+            Err(StackError::UnknownVariable { .. })
+        ));
+    }
+
+    #[test]
+    fn project_stacks_replace_builtin_ones() {
+        let project = tempfile::tempdir().unwrap();
+        write_stack(
+            &project.path().join(STACKS_DIR).join("custom"),
+            "name = \"rust-axum\"\nlanguage = \"rust\"\nversion = \"9.0.0\"\n",
+            &[("main.rs", "fn main() {}\n")],
+        );
+        let registry = StackRegistry::builtin().with_stacks_in(&project.path().join(STACKS_DIR));
+
+        let stack = registry.resolve("rust-axum").unwrap();
+        assert_eq!(stack.manifest.version.as_deref(), Some("9.0.0"));
+        assert!(matches!(stack.source, StackSource::Directory(_)));
+        assert!(registry.get("nextjs").is_some());
+        assert!(matches!(registry.resolve("no-such-stack"), Err(StackError::NotFound(_))));
+    }
+
+    #[test]
+    fn stacks_can_be_cloned_from_git() {
+        let remote = tempfile::tempdir().unwrap();
+        write_stack(remote.path(), "language = \"go\"\n", &[("main.go", "package main\n")]);
+        let repo = git2::Repository::init(remote.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("devkit", "devkit@example.com").unwrap();
+        let commit = repo.commit(Some("HEAD"), &signature, &signature, "stack", &tree, &[]).unwrap();
+        repo.tag_lightweight("v1", &repo.find_object(commit, None).unwrap(), false).unwrap();
+
+        let url = format!("file://{}", remote.path().display());
+        let stack = StackRegistry::default().resolve(&format!("{}#v1", url)).unwrap();
+
+        assert_eq!(stack.manifest.language, "go");
+        assert_eq!(stack.source, StackSource::Git { url, reference: Some("v1".to_string()) });
+        let values = stack.resolve_variables(&builtins("app"), &HashMap::new(), |_, _| None).unwrap();
+        assert_eq!(stack.render(&values).unwrap(), vec![(PathBuf::from("main.go"), "package main\n".to_string())]);
+    }
+}
@@ -0,0 +1,432 @@
+//! Staging workspaces for quality gates
+//!
+//! Gates must see the code as it will be after a changeset is applied, without
+//! touching the project. `StagingWorkspace` builds a scratch copy of the
+//! project, either as a file copy (copy-on-write where the filesystem supports
+//! it) or as a detached git worktree carrying over uncommitted changes, and
+//! writes the changeset into it. Copies leave out what `.gitignore` excludes.
+//!
+//! Build output in the staging copy goes to `.devkit/staging/target`, which
+//! persists between validations so only changed crates rebuild, and
+//! dependency directories such as `node_modules` are linked from the project.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use thiserror::Error;
+use tokio::process::Command as AsyncCommand;
+use ignore::WalkBuilder;
+
+use super::diff_apply::{ChangeSet, DiffApplyError, DiffApplySystem, GateResult};
+
+/// How gates get a workspace with the changeset applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StagingStrategy {
+    /// Copy the project into a temporary directory
+    #[default]
+    Copy,
+    /// Check out HEAD in a temporary git worktree and copy uncommitted changes over
+    GitWorktree,
+    /// Run gates in the project itself, against the code before the change
+    InPlace,
+}
+
+/// Directories that never hold first-party sources: VCS and tool metadata,
+/// build output and installed dependencies. Staging, fidelity sandboxes and
+/// source scanning all leave them out.
+pub(crate) const SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    ".devkit",
+    "target",
+    "node_modules",
+    ".venv",
+    "venv",
+    "__pycache__",
+];
+
+/// Dependency directories linked into the staging workspace instead of copied
+const LINKED_DIRS: &[&str] = &["node_modules", ".venv", "venv"];
+
+/// Errors preparing a staging workspace
+#[derive(Debug, Error)]
+pub enum StagingError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Git error: {0}")]
+    Git(String),
+
+    #[error("Failed to write the changeset: {0}")]
+    Apply(String),
+}
+
+/// A diagnostic in gate output that points into a changed file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticLocation {
+    /// Path relative to the project root
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for DiagnosticLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file.display(), self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Scratch copy of a project with a changeset applied
+#[derive(Debug)]
+pub struct StagingWorkspace {
+    project_root: PathBuf,
+    root: PathBuf,
+    strategy: StagingStrategy,
+    changed_files: Vec<PathBuf>,
+    // Removed on drop
+    _dir: TempDir,
+}
+
+impl StagingWorkspace {
+    /// Stage `changeset` on top of the current state of `project_root`
+    pub async fn create(project_root: &Path, changeset: &ChangeSet, strategy: StagingStrategy) -> Result<Self, StagingError> {
+        let dir = tempfile::Builder::new().prefix("devkit-staging-").tempdir()?;
+        let root = dir.path().join("workspace");
+
+        match strategy {
+            StagingStrategy::GitWorktree => {
+                add_worktree(project_root, &root).await?;
+                let source = project_root.to_path_buf();
+                let target = root.clone();
+                tokio::task::spawn_blocking(move || copy_uncommitted(&source, &target))
+                    .await
+                    .map_err(|e| StagingError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))??;
+            }
+            StagingStrategy::Copy | StagingStrategy::InPlace => {
+                let source = project_root.to_path_buf();
+                let target = root.clone();
+                tokio::task::spawn_blocking(move || copy_tree(&source, &target))
+                    .await
+                    .map_err(|e| StagingError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))??;
+            }
+        }
+        link_caches(project_root, &root)?;
+
+        for file_diff in &changeset.files {
+            DiffApplySystem::write_file_diff(file_diff, &root).map_err(|e: DiffApplyError| {
+                StagingError::Apply(format!("{}: {}", file_diff.file_path.display(), e))
+            })?;
+        }
+
+        Ok(Self {
+            project_root: project_root.to_path_buf(),
+            root,
+            strategy,
+            changed_files: changeset.files.iter().map(|file| file.file_path.clone()).collect(),
+            _dir: dir,
+        })
+    }
+
+    /// Root of the staged project
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Rewrite gate details in terms of the project and lead with the
+    /// diagnostics that point into changed files
+    pub fn focus_result(&self, result: &mut GateResult) {
+        for diagnostic in &mut result.diagnostics {
+            if let Some(file) = &diagnostic.file {
+                if let Ok(relative) = file.strip_prefix(&self.root) {
+                    diagnostic.file = Some(relative.to_path_buf());
+                }
+            }
+        }
+
+        let Some(details) = result.details.take() else {
+            return;
+        };
+        let details = details.replace(&format!("{}/", self.root.display()), "");
+        let locations = locate_diagnostics(&details, &self.changed_files);
+
+        result.details = Some(if locations.is_empty() {
+            details
+        } else {
+            let summary: Vec<String> = locations.iter().map(|location| format!("  {}", location)).collect();
+            format!("Diagnostics in changed files:\n{}\n\n{}", summary.join("\n"), details)
+        });
+    }
+
+    /// Remove the staging workspace, unregistering it from git if needed
+    pub async fn cleanup(self) {
+        if self.strategy == StagingStrategy::GitWorktree {
+            let output = AsyncCommand::new("git")
+                .args(["worktree", "remove", "--force"])
+                .arg(&self.root)
+                .current_dir(&self.project_root)
+                .output()
+                .await;
+            if !output.is_ok_and(|output| output.status.success()) {
+                tracing::debug!("Failed to remove staging worktree {}", self.root.display());
+            }
+        }
+    }
+}
+
+/// Diagnostics in compiler, linter or test output that point into `changed_files`
+///
+/// Understands rustc's `--> file:line:col` form and the common
+/// `file:line[:col]: message` form used by most other tools.
+pub fn locate_diagnostics(output: &str, changed_files: &[PathBuf]) -> Vec<DiagnosticLocation> {
+    let changed: HashSet<&Path> = changed_files.iter().map(PathBuf::as_path).collect();
+    let arrow = Regex::new(r"^\s*--> ([^\s:]+):(\d+):(\d+)").unwrap();
+    let inline = Regex::new(r"^\s*(?:\./)?([^\s:()]+\.[A-Za-z0-9]+):(\d+)(?::(\d+))?:?\s*(.*)$").unwrap();
+
+    let lines: Vec<&str> = output.lines().collect();
+    let mut locations = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let (file, line_number, column, message) = if let Some(captures) = arrow.captures(line) {
+            // The message is on the `error`/`warning` line above the arrow
+            let message = lines[..index]
+                .iter()
+                .rev()
+                .take(10)
+                .find(|line| line.starts_with("error") || line.starts_with("warning"))
+                .map(|line| line.trim().to_string())
+                .unwrap_or_default();
+            (captures[1].to_string(), captures[2].to_string(), Some(captures[3].to_string()), message)
+        } else if let Some(captures) = inline.captures(line) {
+            (
+                captures[1].to_string(),
+                captures[2].to_string(),
+                captures.get(3).map(|m| m.as_str().to_string()),
+                captures[4].trim().to_string(),
+            )
+        } else {
+            continue;
+        };
+
+        let file = PathBuf::from(file.trim_start_matches("./"));
+        if !changed.contains(file.as_path()) {
+            continue;
+        }
+        let Ok(line_number) = line_number.parse() else {
+            continue;
+        };
+        let location = DiagnosticLocation {
+            file,
+            line: line_number,
+            column: column.and_then(|column| column.parse().ok()),
+            message,
+        };
+        if !locations.contains(&location) {
+            locations.push(location);
+        }
+    }
+    locations
+}
+
+fn skipped(name: &str) -> bool {
+    SKIPPED_DIRS.contains(&name)
+}
+
+/// Copy the project tree, leaving out VCS metadata, build output and
+/// anything `.gitignore` excludes
+fn copy_tree(source: &Path, target: &Path) -> Result<(), StagingError> {
+    std::fs::create_dir_all(target)?;
+    let walker = WalkBuilder::new(source)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| {
+            !(entry.file_type().is_some_and(|file_type| file_type.is_dir())
+                && skipped(&entry.file_name().to_string_lossy()))
+        })
+        .build();
+
+    for entry in walker {
+        let entry = entry.map_err(|e| StagingError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        let Some(file_type) = entry.file_type() else {
+            continue;
+        };
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let destination = target.join(relative);
+
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&destination)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(entry.path(), &destination)?;
+        } else {
+            // Uses copy_file_range, which clones extents on filesystems that support it
+            std::fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(source)?, destination)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::fs::copy(source, destination).map(|_| ())
+}
+
+/// Check out HEAD into a detached worktree
+async fn add_worktree(project_root: &Path, root: &Path) -> Result<(), StagingError> {
+    let output = AsyncCommand::new("git")
+        .args(["worktree", "add", "--detach", "--quiet"])
+        .arg(root)
+        .arg("HEAD")
+        .current_dir(project_root)
+        .output()
+        .await
+        .map_err(|e| StagingError::Git(format!("failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(StagingError::Git(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(())
+}
+
+/// Bring modified, deleted and untracked files of the project into a worktree
+fn copy_uncommitted(project_root: &Path, root: &Path) -> Result<(), StagingError> {
+    let repository = git2::Repository::open(project_root).map_err(|e| StagingError::Git(e.to_string()))?;
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+    let statuses = repository.statuses(Some(&mut options)).map_err(|e| StagingError::Git(e.to_string()))?;
+
+    for entry in statuses.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        if Path::new(path).components().any(|component| skipped(&component.as_os_str().to_string_lossy())) {
+            continue;
+        }
+        let source = project_root.join(path);
+        let destination = root.join(path);
+        if entry.status().intersects(git2::Status::WT_DELETED | git2::Status::INDEX_DELETED) && !source.exists() {
+            if destination.exists() {
+                std::fs::remove_file(&destination)?;
+            }
+        } else if source.is_file() {
+            if let Some(parent) = destination.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&source, &destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Point build output at the persistent staging cache and link dependency directories
+fn link_caches(project_root: &Path, root: &Path) -> Result<(), StagingError> {
+    if project_root.join("Cargo.toml").exists() {
+        let cache = project_root.join(".devkit").join("staging").join("target");
+        std::fs::create_dir_all(&cache)?;
+        link_dir(&cache, &root.join("target"))?;
+    }
+    for name in LINKED_DIRS {
+        let source = project_root.join(name);
+        if source.is_dir() {
+            link_dir(&source, &root.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn link_dir(source: &Path, link: &Path) -> std::io::Result<()> {
+    if link.exists() {
+        return Ok(());
+    }
+    std::os::unix::fs::symlink(source, link)
+}
+
+#[cfg(not(unix))]
+fn link_dir(_source: &Path, _link: &Path) -> std::io::Result<()> {
+    // Without symlinks the staging copy builds its caches from scratch
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::diff_apply::{ChangeType, DiffMetadata, FileDiff};
+
+    fn changeset(path: &str, content: &str) -> ChangeSet {
+        let file = FileDiff {
+            file_path: PathBuf::from(path),
+            original_content: None,
+            new_content: content.to_string(),
+            diff_text: String::new(),
+            change_type: ChangeType::Create,
+            metadata: DiffMetadata {
+                created_at: chrono::Utc::now(),
+                agent_id: "agent".to_string(),
+                task_id: "task".to_string(),
+                confidence_score: 1.0,
+                estimated_lines_changed: 1,
+                language: None,
+                description: String::new(),
+            },
+            hunks: Vec::new(),
+        };
+        ChangeSet::new("t", "d", "agent", "task", vec![file])
+    }
+
+    #[tokio::test]
+    async fn stages_changeset_without_touching_the_project() {
+        let project = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(project.path().join("src")).unwrap();
+        std::fs::write(project.path().join("src/lib.rs"), "old\n").unwrap();
+        std::fs::create_dir_all(project.path().join("target/debug")).unwrap();
+        std::fs::create_dir_all(project.path().join("dist")).unwrap();
+        std::fs::write(project.path().join("dist/bundle.js"), "").unwrap();
+        std::fs::write(project.path().join("debug.log"), "").unwrap();
+        std::fs::write(project.path().join(".gitignore"), "dist/\n*.log\n").unwrap();
+
+        let staging = StagingWorkspace::create(
+            project.path(),
+            &changeset("src/lib.rs", "new\n"),
+            StagingStrategy::Copy,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read_to_string(staging.root().join("src/lib.rs")).unwrap(), "new\n");
+        assert_eq!(std::fs::read_to_string(project.path().join("src/lib.rs")).unwrap(), "old\n");
+        assert!(!staging.root().join("target/debug").exists());
+        assert!(!staging.root().join("dist").exists());
+        assert!(!staging.root().join("debug.log").exists());
+        assert!(staging.root().join(".gitignore").exists());
+    }
+
+    #[test]
+    fn locates_diagnostics_in_changed_files() {
+        let output = "\
+error[E0308]: mismatched types
+  --> src/lib.rs:3:5
+   |
+warning: unused variable
+  --> src/other.rs:1:1
+./main.go:7:2: undefined: x
+";
+        let changed = vec![PathBuf::from("src/lib.rs"), PathBuf::from("main.go")];
+        let locations = locate_diagnostics(output, &changed);
+
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].to_string(), "src/lib.rs:3:5: error[E0308]: mismatched types");
+        assert_eq!(locations[1].to_string(), "main.go:7:2: undefined: x");
+    }
+}
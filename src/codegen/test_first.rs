@@ -0,0 +1,1201 @@
+//! Test-first generation: implement code until given tests pass
+//!
+//! `TestSuite::discover` reads the test files, finds the symbols they exercise
+//! through the `SymbolIndex` and decides which files the implementation goes
+//! in. `CodeGenerator::generate_from_tests` asks the model for edits to those
+//! files and runs only these tests in a staging workspace, feeding the
+//! failures back until they pass. The tests themselves are never changed: an
+//! edit to a test file or to anything named like one, to the test module of a
+//! file with inline tests, or one that redefines an assertion macro, is
+//! rejected and reported back to the model.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use super::diff_apply::{is_test_path, ChangeSet, DiffApplySystem, QualityGateConfig, ValidationResults, ValidationStatus};
+use super::edit_formats::{apply_hunks, parse_edits, retry_prompt, EditError, EditErrorKind, EditOperation, MatchConfig};
+use super::repair::{file_diff, gate_diagnostics};
+use super::staging::StagingStrategy;
+use super::toolchain::{CompilerDiagnostic, DiagnosticSeverity, DiagnosticSource, ToolCommand, ToolOutcome, ToolchainRegistry};
+use super::{CodeGenError, CodeGenerator};
+use crate::context::symbols::{Symbol, SymbolIndex, SymbolType};
+
+const TASK_ID: &str = "generate-from-tests";
+
+static ASSERTION_MACRO_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"macro_rules!\s*(assert|assert_eq|assert_ne|assert_matches|debug_assert|debug_assert_eq|debug_assert_ne)\b")
+        .unwrap()
+});
+
+/// Errors preparing or running test-first generation
+#[derive(Debug, Error)]
+pub enum TestFirstError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid test pattern '{pattern}': {message}")]
+    InvalidPattern { pattern: String, message: String },
+
+    #[error("No test files match {0}")]
+    NoTests(String),
+
+    #[error("Unsupported test file {0}")]
+    UnsupportedLanguage(PathBuf),
+
+    #[error("Test files mix languages: {0}")]
+    MixedLanguages(String),
+
+    #[error("Cannot run the tests: {0}")]
+    TestsUnavailable(String),
+
+    #[error("No implementation file found for {0}; pass one with --output")]
+    NoTarget(String),
+
+    #[error("Test-first generation needs an AI backend")]
+    NoAiBackend,
+
+    #[error(transparent)]
+    Generation(#[from] CodeGenError),
+}
+
+/// Language of a test suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestLanguage {
+    Rust,
+    Python,
+    TypeScript,
+    JavaScript,
+    Go,
+}
+
+impl TestLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "py" => Some(Self::Python),
+            "ts" | "tsx" | "mts" | "cts" => Some(Self::TypeScript),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::TypeScript => "typescript",
+            Self::JavaScript => "javascript",
+            Self::Go => "go",
+        }
+    }
+
+    /// Language of the toolchain that builds and tests it
+    fn toolchain(&self) -> &'static str {
+        match self {
+            Self::TypeScript | Self::JavaScript => "typescript",
+            other => other.name(),
+        }
+    }
+}
+
+/// A file holding tests that must pass unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestFile {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    pub content: String,
+    pub test_names: Vec<String>,
+    /// 1-based line where the inline test module of a source file starts;
+    /// `None` when the whole file is tests
+    pub test_module_line: Option<usize>,
+}
+
+impl TestFile {
+    fn new(path: PathBuf, content: String, language: TestLanguage) -> Self {
+        let test_names = test_names(&content, language);
+        let in_tests_dir = path.components().any(|component| component.as_os_str() == "tests");
+        let test_module_line = (language == TestLanguage::Rust && !in_tests_dir)
+            .then(|| content.lines().position(|line| line.trim_start().starts_with("#[cfg(test)]")))
+            .flatten()
+            .map(|index| index + 1);
+        Self {
+            path,
+            content,
+            test_names,
+            test_module_line,
+        }
+    }
+
+    /// The part of the file that must not change: the whole file, or
+    /// everything from the line above the inline test module to the end
+    pub fn protected_text(&self) -> &str {
+        match self.test_module_line {
+            Some(line) => {
+                let offset: usize = self.content.split_inclusive('\n').take(line.saturating_sub(2)).map(str::len).sum();
+                &self.content[offset..]
+            }
+            None => &self.content,
+        }
+    }
+
+    /// Whether new content for this file leaves the tests untouched
+    ///
+    /// Inline tests must end the new content byte for byte, starting on a
+    /// line of their own, and no new attribute may be stacked on them.
+    pub fn check_edit(&self, new_content: &str) -> Result<(), String> {
+        match self.test_module_line {
+            None if new_content == self.content => Ok(()),
+            None => Err(format!("{} is a test file and cannot be modified", self.path.display())),
+            Some(line) => {
+                let protected = self.protected_text();
+                let original = &self.content[..self.content.len() - protected.len()];
+                match new_content.strip_suffix(protected) {
+                    Some(prefix)
+                        if (prefix.is_empty() || prefix.ends_with('\n'))
+                            && trailing_attribute(prefix) == trailing_attribute(original) =>
+                    {
+                        Ok(())
+                    }
+                    _ => Err(format!(
+                        "the test module of {} (from line {} to the end) cannot be modified",
+                        self.path.display(),
+                        line.saturating_sub(1).max(1)
+                    )),
+                }
+            }
+        }
+    }
+}
+
+/// A symbol the tests exercise
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestTarget {
+    pub name: String,
+    /// Current definition; `None` when the tests expect it to be created
+    pub symbol: Option<Symbol>,
+    /// File the implementation goes in, relative to the project root
+    pub file: PathBuf,
+}
+
+/// Tests to satisfy and the code they exercise
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSuite {
+    pub project_root: PathBuf,
+    pub language: TestLanguage,
+    pub tests: Vec<TestFile>,
+    pub targets: Vec<TestTarget>,
+    /// Files the implementation may go in, besides those of the targets
+    pub extra_files: Vec<PathBuf>,
+}
+
+impl TestSuite {
+    /// Read the test files matching `patterns` and locate the code under test
+    ///
+    /// `output`, when given, is always offered to the model as an
+    /// implementation file and receives symbols no other file could be found for.
+    pub fn discover(
+        project_root: &Path,
+        patterns: &[String],
+        symbols: &SymbolIndex,
+        output: Option<&Path>,
+    ) -> Result<Self, TestFirstError> {
+        let paths = resolve_patterns(project_root, patterns)?;
+        let mut language: Option<TestLanguage> = None;
+        let mut tests = Vec::new();
+        for path in paths {
+            let file_language =
+                TestLanguage::from_path(&path).ok_or_else(|| TestFirstError::UnsupportedLanguage(path.clone()))?;
+            match language {
+                Some(language) if language != file_language => {
+                    return Err(TestFirstError::MixedLanguages(format!(
+                        "{} and {}",
+                        language.name(),
+                        file_language.name()
+                    )));
+                }
+                _ => language = Some(file_language),
+            }
+            let content = std::fs::read_to_string(project_root.join(&path))?;
+            tests.push(TestFile::new(path, content, file_language));
+        }
+        let language = language.ok_or_else(|| TestFirstError::NoTests(patterns.join(", ")))?;
+
+        let mut suite = Self {
+            project_root: project_root.to_path_buf(),
+            language,
+            tests,
+            targets: Vec::new(),
+            extra_files: output.map(|output| relative_to(project_root, output)).into_iter().collect(),
+        };
+        suite.find_targets(symbols)?;
+        Ok(suite)
+    }
+
+    fn find_targets(&mut self, symbols: &SymbolIndex) -> Result<(), TestFirstError> {
+        let crate_name = (self.language == TestLanguage::Rust).then(|| rust_crate_name(&self.project_root)).flatten();
+
+        let mut imported: BTreeMap<String, Option<PathBuf>> = BTreeMap::new();
+        let mut used: BTreeSet<String> = BTreeSet::new();
+        for test in &self.tests {
+            for (name, module_file) in imported_names(test, self.language, &self.project_root, crate_name.as_deref()) {
+                let entry = imported.entry(name).or_insert(None);
+                if entry.is_none() {
+                    *entry = module_file;
+                }
+            }
+            used.extend(used_names(&test.content));
+        }
+
+        let mut targets = Vec::new();
+        for name in imported.keys().chain(used.iter().filter(|name| !imported.contains_key(*name))) {
+            let definitions: Vec<&Symbol> = symbols
+                .find_symbols(name)
+                .into_iter()
+                .filter(|symbol| is_target_type(&symbol.symbol_type) && !self.is_in_tests(symbol))
+                .collect();
+            let files: BTreeSet<PathBuf> = definitions
+                .iter()
+                .map(|symbol| relative_to(&self.project_root, &symbol.file_path))
+                .collect();
+            // Names defined all over the project, like `new`, say little about the code under test
+            if files.len() > 3 {
+                continue;
+            }
+            let mut seen = BTreeSet::new();
+            for symbol in definitions {
+                let file = relative_to(&self.project_root, &symbol.file_path);
+                if seen.insert(file.clone()) {
+                    targets.push(TestTarget {
+                        name: name.clone(),
+                        symbol: Some(symbol.clone()),
+                        file,
+                    });
+                }
+            }
+            if seen.is_empty() {
+                if let Some(module_file) = imported.get(name) {
+                    let file = module_file
+                        .clone()
+                        .or_else(|| self.extra_files.first().cloned())
+                        .ok_or_else(|| TestFirstError::NoTarget(name.clone()))?;
+                    targets.push(TestTarget {
+                        name: name.clone(),
+                        symbol: None,
+                        file,
+                    });
+                }
+            }
+        }
+        self.targets = targets;
+
+        // Code next to the tests is under test even when no symbol points at it
+        for test in &self.tests {
+            if let Some(file) = companion_file(test, self.language, &self.project_root) {
+                if !self.extra_files.contains(&file) {
+                    self.extra_files.push(file);
+                }
+            }
+        }
+        if self.implementation_files().is_empty() {
+            return Err(TestFirstError::NoTarget("the tests".to_string()));
+        }
+        Ok(())
+    }
+
+    fn is_in_tests(&self, symbol: &Symbol) -> bool {
+        let file = relative_to(&self.project_root, &symbol.file_path);
+        self.test_file(&file)
+            .is_some_and(|test| test.test_module_line.map_or(true, |line| symbol.line >= line))
+    }
+
+    pub fn test_file(&self, path: &Path) -> Option<&TestFile> {
+        self.tests.iter().find(|test| test.path == path)
+    }
+
+    /// Files offered to the model for the implementation
+    pub fn implementation_files(&self) -> Vec<PathBuf> {
+        let files: BTreeSet<PathBuf> = self
+            .targets
+            .iter()
+            .map(|target| target.file.clone())
+            .chain(self.extra_files.iter().cloned())
+            .collect();
+        files.into_iter().collect()
+    }
+
+    pub fn test_count(&self) -> usize {
+        self.tests.iter().map(|test| test.test_names.len()).sum()
+    }
+
+    /// Command running exactly these tests
+    pub fn test_command(&self) -> ToolCommand {
+        let files: Vec<String> = self.tests.iter().map(|test| test.path.to_string_lossy().to_string()).collect();
+        let names: Vec<String> = self.tests.iter().flat_map(|test| test.test_names.clone()).collect();
+        match self.language {
+            TestLanguage::Rust => {
+                let mut command = ToolCommand::new("cargo", &["test"], DiagnosticSource::RustTest).requires("Cargo.toml");
+                for test in &self.tests {
+                    match (test.test_module_line, test.path.file_stem()) {
+                        (None, Some(stem)) if test.path.starts_with("tests") => {
+                            command.args.push("--test".to_string());
+                            command.args.push(stem.to_string_lossy().to_string());
+                        }
+                        _ => {
+                            if !command.args.iter().any(|arg| arg == "--lib") {
+                                command.args.push("--lib".to_string());
+                            }
+                        }
+                    }
+                }
+                if !names.is_empty() {
+                    command.args.push("--".to_string());
+                    command.args.extend(names);
+                }
+                command
+            }
+            TestLanguage::Python => {
+                let mut command = ToolCommand::new("pytest", &["-q"], DiagnosticSource::Pytest);
+                command.args.extend(files);
+                command
+            }
+            TestLanguage::TypeScript | TestLanguage::JavaScript => {
+                let mut command =
+                    ToolCommand::new("npm", &["test", "--silent", "--"], DiagnosticSource::Tsc).requires("package.json");
+                command.args.extend(files);
+                command
+            }
+            TestLanguage::Go => {
+                let mut command = ToolCommand::new("go", &["test"], DiagnosticSource::Go).requires("go.mod");
+                if !names.is_empty() {
+                    command.args.push("-run".to_string());
+                    command.args.push(format!("^({})$", names.join("|")));
+                }
+                let packages: BTreeSet<String> = self
+                    .tests
+                    .iter()
+                    .map(|test| match test.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                        Some(dir) => format!("./{}", dir.display()),
+                        None => ".".to_string(),
+                    })
+                    .collect();
+                command.args.extend(packages);
+                command
+            }
+        }
+    }
+
+    /// `registry` with the test command of this suite's language running only this suite
+    pub fn toolchains(&self, registry: ToolchainRegistry) -> ToolchainRegistry {
+        match registry.toolchain(self.language.toolchain()).cloned() {
+            Some(toolchain) => registry.with_toolchain(toolchain.with_test(self.test_command())),
+            None => registry,
+        }
+    }
+}
+
+/// Settings for `CodeGenerator::generate_from_tests`
+#[derive(Debug, Clone)]
+pub struct TestFirstConfig {
+    /// Repair rounds after the first attempt
+    pub max_rounds: usize,
+    pub staging: StagingStrategy,
+    /// Compile and test commands per language; the suite's own test command
+    /// replaces the test command of its language
+    pub toolchains: ToolchainRegistry,
+}
+
+impl Default for TestFirstConfig {
+    fn default() -> Self {
+        Self {
+            max_rounds: 3,
+            staging: StagingStrategy::Copy,
+            toolchains: ToolchainRegistry::default(),
+        }
+    }
+}
+
+impl TestFirstConfig {
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn with_staging(mut self, staging: StagingStrategy) -> Self {
+        self.staging = staging;
+        self
+    }
+
+    pub fn with_toolchains(mut self, toolchains: ToolchainRegistry) -> Self {
+        self.toolchains = toolchains;
+        self
+    }
+}
+
+/// An edit the model made to the tests, which was not applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedEdit {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// One round of edits and how the tests fared with them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestFirstAttempt {
+    /// 1 for the initial implementation, then one per repair round
+    pub number: usize,
+    /// Implementation changes so far, against the project
+    pub changeset: ChangeSet,
+    pub validation: Option<ValidationResults>,
+    pub diagnostics: Vec<CompilerDiagnostic>,
+    pub rejected: Vec<RejectedEdit>,
+    /// Edits that could not be parsed or applied
+    pub edit_errors: Vec<EditError>,
+}
+
+impl TestFirstAttempt {
+    /// Whether the code builds and the tests pass
+    pub fn is_green(&self) -> bool {
+        self.validation
+            .as_ref()
+            .is_some_and(|validation| validation.overall_status != ValidationStatus::Failed)
+    }
+
+    pub fn errors(&self) -> Vec<&CompilerDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+            .collect()
+    }
+}
+
+/// Every attempt of a test-first run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestFirstOutcome {
+    /// The tests passed before anything was generated
+    pub already_passing: bool,
+    pub attempts: Vec<TestFirstAttempt>,
+}
+
+impl TestFirstOutcome {
+    pub fn final_attempt(&self) -> Option<&TestFirstAttempt> {
+        self.attempts.last()
+    }
+
+    pub fn is_green(&self) -> bool {
+        self.already_passing || self.final_attempt().is_some_and(TestFirstAttempt::is_green)
+    }
+
+    /// Every edit to the tests the model attempted
+    pub fn rejected(&self) -> Vec<&RejectedEdit> {
+        self.attempts.iter().flat_map(|attempt| &attempt.rejected).collect()
+    }
+}
+
+impl CodeGenerator {
+    /// Implement the code under test until the suite passes or the repair
+    /// rounds run out
+    ///
+    /// Nothing is written to the project; the final attempt's changeset holds
+    /// the implementation. `on_attempt` is called after each attempt has been
+    /// validated.
+    pub async fn generate_from_tests<F>(
+        &self,
+        task: &str,
+        suite: &TestSuite,
+        config: &TestFirstConfig,
+        mut on_attempt: F,
+    ) -> Result<TestFirstOutcome, TestFirstError>
+    where
+        F: FnMut(&TestFirstAttempt) + Send,
+    {
+        if !self.has_ai() {
+            return Err(TestFirstError::NoAiBackend);
+        }
+
+        let failures = match suite.test_command().run(&suite.project_root, &[]).await {
+            ToolOutcome::Passed { .. } => {
+                return Ok(TestFirstOutcome {
+                    already_passing: true,
+                    attempts: Vec::new(),
+                })
+            }
+            ToolOutcome::Unavailable(reason) => return Err(TestFirstError::TestsUnavailable(reason)),
+            ToolOutcome::Failed { output, .. } => output,
+        };
+
+        let gate_config = QualityGateConfig {
+            enabled_gates: vec!["compile".to_string(), "test".to_string()],
+            require_all_gates: true,
+            // Sequential so a failed build skips the tests
+            parallel_execution: false,
+            custom_commands: HashMap::new(),
+            staging: config.staging.clone(),
+            toolchains: suite.toolchains(config.toolchains.clone()),
+            ..QualityGateConfig::default()
+        };
+        let system = DiffApplySystem::new(gate_config, &suite.project_root)
+            .map_err(|e| CodeGenError::GenerationFailed(format!("Failed to set up quality gates: {}", e)))?;
+
+        let mut overlay: BTreeMap<PathBuf, String> = suite
+            .implementation_files()
+            .into_iter()
+            .map(|file| {
+                let content = std::fs::read_to_string(suite.project_root.join(&file)).unwrap_or_default();
+                (file, content)
+            })
+            .collect();
+        let mut prompt = initial_prompt(task, suite, &overlay, &failures);
+        let mut outcome = TestFirstOutcome {
+            already_passing: false,
+            attempts: Vec::new(),
+        };
+
+        for number in 1..=config.max_rounds + 1 {
+            let response = self.request_edits(std::mem::take(&mut prompt)).await?;
+            let (rejected, edit_errors) = apply_edits(suite, &mut overlay, &response);
+
+            let files = overlay
+                .iter()
+                .filter(|(file, content)| {
+                    std::fs::read_to_string(suite.project_root.join(file)).ok().as_deref() != Some(content.as_str())
+                })
+                .map(|(file, content)| file_diff(&suite.project_root, file, content, TASK_ID))
+                .collect();
+            let mut changeset = ChangeSet::new(
+                &format!("Implement {}", task),
+                &format!("Attempt {}", number),
+                "code-generator",
+                TASK_ID,
+                files,
+            );
+            let validation = if changeset.files.is_empty() {
+                None
+            } else {
+                system
+                    .validate_changeset(&mut changeset, &suite.project_root)
+                    .await
+                    .map_err(|e| CodeGenError::GenerationFailed(format!("Validation failed: {}", e)))?;
+                changeset.validation_results.clone()
+            };
+            let attempt = TestFirstAttempt {
+                number,
+                diagnostics: validation.as_ref().map(gate_diagnostics).unwrap_or_default(),
+                changeset,
+                validation,
+                rejected,
+                edit_errors,
+            };
+            on_attempt(&attempt);
+
+            let done = attempt.is_green() || number > config.max_rounds;
+            if !done {
+                prompt = repair_prompt(suite, &overlay, &attempt);
+            }
+            outcome.attempts.push(attempt);
+            if done {
+                break;
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    async fn request_edits(&self, prompt: String) -> Result<String, CodeGenError> {
+        use crate::ai::{ChatMessage, ChatRequest};
+
+        let ai_manager = self.ai_manager.as_ref().ok_or_else(|| {
+            CodeGenError::GenerationFailed("AI manager not available".to_string())
+        })?;
+
+        let chat_request = ChatRequest {
+            model: "llama3.2:latest".to_string(), // Use default model
+            messages: vec![
+                ChatMessage::system(
+                    "You are an expert software developer implementing code to make existing tests pass. \
+                    Never modify the tests. Reply only with edits: for each file, its path on a line of its own \
+                    followed by <<<<<<< SEARCH / ======= / >>>>>>> REPLACE blocks, or by a fenced block holding \
+                    the complete new file.",
+                ),
+                ChatMessage::user(prompt),
+            ],
+            parameters: Some(crate::ai::ModelParameters {
+                temperature: Some(0.2),
+                ..Default::default()
+            }),
+            stream: false,
+        };
+
+        let response = ai_manager
+            .chat_completion_default(chat_request)
+            .await
+            .map_err(|e| CodeGenError::GenerationFailed(format!("AI generation failed: {}", e)))?;
+        Ok(response.message.content)
+    }
+}
+
+/// Apply the model's edits to the working copy, refusing any that touch the tests
+fn apply_edits(
+    suite: &TestSuite,
+    overlay: &mut BTreeMap<PathBuf, String>,
+    response: &str,
+) -> (Vec<RejectedEdit>, Vec<EditError>) {
+    let edits = match parse_edits(response) {
+        Ok(edits) => edits,
+        Err(error) => return (Vec::new(), vec![error]),
+    };
+
+    let mut rejected = Vec::new();
+    let mut errors = Vec::new();
+    for edit in edits {
+        let path = edit.path.clone();
+        let reject = |reason: String| RejectedEdit { path: path.clone(), reason };
+        if path.as_os_str().is_empty()
+            || path
+                .components()
+                .any(|component| matches!(component, Component::ParentDir | Component::RootDir | Component::Prefix(_)))
+        {
+            errors.push(EditError {
+                kind: EditErrorKind::InvalidPath,
+                file_path: Some(path.clone()),
+                hunk: None,
+                message: format!("{} is not a path inside the project", path.display()),
+                expected: None,
+                closest_match: None,
+            });
+            continue;
+        }
+        let test = suite.test_file(&path);
+        if is_test_path(&path) || test.is_some_and(|test| test.test_module_line.is_none()) {
+            rejected.push(reject(format!("{} is a test file and cannot be modified", path.display())));
+            continue;
+        }
+
+        let current = overlay
+            .get(&path)
+            .cloned()
+            .or_else(|| std::fs::read_to_string(suite.project_root.join(&path)).ok());
+        let content = match edit.operation {
+            EditOperation::Replace(content) => content,
+            EditOperation::Delete => {
+                rejected.push(reject("files cannot be deleted while implementing tests".to_string()));
+                continue;
+            }
+            EditOperation::Patch(hunks) => {
+                match apply_hunks(current.as_deref().unwrap_or(""), &hunks, &MatchConfig::default()) {
+                    Ok((content, _)) => content,
+                    Err(failed) => {
+                        errors.extend(failed.into_iter().map(|mut error| {
+                            error.file_path = Some(path.clone());
+                            error
+                        }));
+                        continue;
+                    }
+                }
+            }
+        };
+        if let Some(Err(reason)) = test.map(|test| test.check_edit(&content)) {
+            rejected.push(reject(reason));
+            continue;
+        }
+        let existing = current.as_deref().map(shadowed_assertions).unwrap_or_default();
+        if let Some(name) = shadowed_assertions(&content).difference(&existing).next() {
+            rejected.push(reject(format!("defining a `{}!` macro would change what the tests assert", name)));
+            continue;
+        }
+        overlay.insert(path, content);
+    }
+    (rejected, errors)
+}
+
+/// Names of std assertion macros that `macro_rules!` definitions in `content` shadow
+fn shadowed_assertions(content: &str) -> BTreeSet<String> {
+    ASSERTION_MACRO_RE
+        .captures_iter(content)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// The outer attribute closing `text`, which applies to whatever follows it
+fn trailing_attribute(text: &str) -> Option<&str> {
+    text.lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .filter(|line| line.starts_with("#["))
+}
+
+fn fence(language: TestLanguage) -> &'static str {
+    language.name()
+}
+
+fn push_files(prompt: &mut String, language: TestLanguage, overlay: &BTreeMap<PathBuf, String>) {
+    for (file, content) in overlay {
+        if content.is_empty() {
+            prompt.push_str(&format!("{} (new file)\n\n", file.display()));
+        } else {
+            prompt.push_str(&format!("{}\n```{}\n{}\n```\n\n", file.display(), fence(language), content.trim_end()));
+        }
+    }
+}
+
+fn tail(output: &str, lines: usize) -> String {
+    let all: Vec<&str> = output.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+/// Prompt for the first implementation
+fn initial_prompt(task: &str, suite: &TestSuite, overlay: &BTreeMap<PathBuf, String>, failures: &str) -> String {
+    let language = suite.language;
+    let mut prompt = format!("Task: {}\nLanguage: {}\n\n", task, language.name());
+
+    prompt.push_str("Make these tests pass. They are read-only; edits to them will be rejected.\n\n");
+    for test in &suite.tests {
+        prompt.push_str(&format!("{}\n```{}\n{}\n```\n\n", test.path.display(), fence(language), test.protected_text().trim_end()));
+    }
+    prompt.push_str(&format!("Current failures:\n```\n{}\n```\n\n", tail(failures, 60)));
+
+    if !suite.targets.is_empty() {
+        prompt.push_str("Code under test:\n");
+        for target in &suite.targets {
+            match &target.symbol {
+                Some(symbol) => prompt.push_str(&format!(
+                    "- {} ({}) in {}:{}\n",
+                    target.name,
+                    symbol.symbol_type,
+                    target.file.display(),
+                    symbol.line
+                )),
+                None => prompt.push_str(&format!(
+                    "- {}: not defined yet, create it in {}\n",
+                    target.name,
+                    target.file.display()
+                )),
+            }
+        }
+        prompt.push('\n');
+    }
+
+    prompt.push_str("Implementation files:\n\n");
+    push_files(&mut prompt, language, overlay);
+    prompt.push_str("Reply with the edits to the implementation files that make every test pass.");
+    prompt
+}
+
+/// Prompt asking the model to fix what still fails
+fn repair_prompt(suite: &TestSuite, overlay: &BTreeMap<PathBuf, String>, attempt: &TestFirstAttempt) -> String {
+    let mut prompt = String::new();
+    if !attempt.rejected.is_empty() {
+        prompt.push_str("These edits were rejected because the tests must not change:\n");
+        for rejected in &attempt.rejected {
+            prompt.push_str(&format!("- {}: {}\n", rejected.path.display(), rejected.reason));
+        }
+        prompt.push('\n');
+    }
+    if !attempt.edit_errors.is_empty() {
+        prompt.push_str(&retry_prompt(&attempt.edit_errors));
+        prompt.push('\n');
+    }
+
+    match &attempt.validation {
+        None => prompt.push_str("No implementation change was applied, so the tests still fail.\n"),
+        Some(validation) => {
+            let errors = attempt.errors();
+            if errors.is_empty() {
+                prompt.push_str("The tests still fail:\n");
+                for gate in validation.gates.values() {
+                    if let (super::diff_apply::GateStatus::Failed | super::diff_apply::GateStatus::Error, Some(details)) =
+                        (&gate.status, &gate.details)
+                    {
+                        prompt.push_str(&format!("[{}]\n{}\n", gate.name, tail(details, 40)));
+                    }
+                }
+            } else {
+                prompt.push_str("Errors:\n");
+                for error in errors {
+                    prompt.push_str(&format!("- {}\n", error));
+                }
+            }
+        }
+    }
+
+    prompt.push_str("\nCurrent implementation files:\n\n");
+    push_files(&mut prompt, suite.language, overlay);
+    prompt.push_str("Reply with edits to the implementation files only.");
+    prompt
+}
+
+/// Test files matching the patterns, relative to the project root
+fn resolve_patterns(project_root: &Path, patterns: &[String]) -> Result<Vec<PathBuf>, TestFirstError> {
+    let mut files = BTreeSet::new();
+    let mut globs = globset::GlobSetBuilder::new();
+    let mut has_globs = false;
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let absolute = if path.is_absolute() { path.to_path_buf() } else { project_root.join(path) };
+        if absolute.is_file() {
+            files.insert(relative_to(project_root, &absolute));
+            continue;
+        }
+        let glob = globset::Glob::new(pattern).map_err(|e| TestFirstError::InvalidPattern {
+            pattern: pattern.clone(),
+            message: e.to_string(),
+        })?;
+        globs.add(glob);
+        has_globs = true;
+    }
+
+    if has_globs {
+        let globs = globs.build().map_err(|e| TestFirstError::InvalidPattern {
+            pattern: patterns.join(", "),
+            message: e.to_string(),
+        })?;
+        for entry in ignore::WalkBuilder::new(project_root).build() {
+            let entry = entry.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            let relative = relative_to(project_root, entry.path());
+            if entry.path().is_file() && globs.is_match(&relative) {
+                files.insert(relative);
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return Err(TestFirstError::NoTests(patterns.join(", ")));
+    }
+    Ok(files.into_iter().collect())
+}
+
+fn relative_to(project_root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(project_root).unwrap_or(path).to_path_buf()
+}
+
+fn is_target_type(symbol_type: &SymbolType) -> bool {
+    !matches!(symbol_type, SymbolType::Variable | SymbolType::Module | SymbolType::Namespace | SymbolType::Unknown)
+}
+
+/// Names of the individual tests in a file
+fn test_names(content: &str, language: TestLanguage) -> Vec<String> {
+    let pattern = match language {
+        TestLanguage::Rust => r"#\[(?:[\w:]+::)?test[^\]]*\]\s*(?:#\[[^\]]*\]\s*)*(?:pub\s+)?(?:async\s+)?fn\s+(\w+)",
+        TestLanguage::Python => r"(?m)^\s*(?:async\s+)?def\s+(test\w*)\s*\(",
+        TestLanguage::TypeScript | TestLanguage::JavaScript => r#"\b(?:it|test)\s*\(\s*['"`]([^'"`]+)['"`]"#,
+        TestLanguage::Go => r"(?m)^func\s+(Test\w+)\s*\(\s*\w+\s+\*testing\.T\s*\)",
+    };
+    let regex = Regex::new(pattern).unwrap();
+    regex.captures_iter(content).map(|captures| captures[1].to_string()).collect()
+}
+
+/// Identifiers called or used as a path in the test code
+fn used_names(content: &str) -> BTreeSet<String> {
+    let regex = Regex::new(r"\b([A-Za-z_]\w*)\s*(?:\(|::|\{)").unwrap();
+    regex.captures_iter(content).map(|captures| captures[1].to_string()).collect()
+}
+
+/// Names the tests import from project code, with the file the import points at
+fn imported_names(
+    test: &TestFile,
+    language: TestLanguage,
+    project_root: &Path,
+    crate_name: Option<&str>,
+) -> Vec<(String, Option<PathBuf>)> {
+    let mut names = Vec::new();
+    let split = |list: &str| -> Vec<String> {
+        list.split(',')
+            .map(|name| name.split_whitespace().next().unwrap_or("").trim_matches(['(', ')', '{', '}']).to_string())
+            .filter(|name| !name.is_empty() && name != "*" && name != "self")
+            .collect()
+    };
+
+    match language {
+        TestLanguage::Rust => {
+            let regex = Regex::new(r"(?m)^\s*use\s+([\w:]+?)::(?:\{([^}]*)\}|(\w+))\s*;").unwrap();
+            for captures in regex.captures_iter(&test.content) {
+                let path = &captures[1];
+                let mut segments = path.split("::");
+                let root = segments.next().unwrap_or("");
+                let local = matches!(root, "crate" | "super" | "self") || Some(root) == crate_name;
+                if !local {
+                    continue;
+                }
+                let module: Vec<&str> = segments.collect();
+                let file = if root == "super" || root == "self" {
+                    Some(test.path.clone())
+                } else {
+                    rust_module_file(project_root, &module)
+                };
+                let list = captures.get(2).or_else(|| captures.get(3)).map_or("", |m| m.as_str());
+                names.extend(split(list).into_iter().map(|name| (name, file.clone())));
+            }
+        }
+        TestLanguage::Python => {
+            let regex = Regex::new(r"(?m)^\s*from\s+([\w.]+)\s+import\s+\(?([\w\s,]+)\)?").unwrap();
+            for captures in regex.captures_iter(&test.content) {
+                let module = captures[1].replace('.', "/");
+                let file = [format!("{}.py", module), format!("src/{}.py", module), format!("{}/__init__.py", module)]
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .find(|file| project_root.join(file).is_file());
+                if let Some(file) = file {
+                    names.extend(split(&captures[2]).into_iter().map(|name| (name, Some(file.clone()))));
+                }
+            }
+        }
+        TestLanguage::TypeScript | TestLanguage::JavaScript => {
+            let regex = Regex::new(
+                r#"(?:import\s*\{([^}]*)\}\s*from|(?:const|let|var)\s*\{([^}]*)\}\s*=\s*require\()\s*['"](\.{1,2}/[^'"]+)['"]"#,
+            )
+            .unwrap();
+            let dir = test.path.parent().unwrap_or(Path::new(""));
+            for captures in regex.captures_iter(&test.content) {
+                let list = captures.get(1).or_else(|| captures.get(2)).map_or("", |m| m.as_str());
+                let file = js_module_file(project_root, dir, &captures[3], &test.path);
+                names.extend(split(list).into_iter().map(|name| (name, Some(file.clone()))));
+            }
+        }
+        TestLanguage::Go => {}
+    }
+    names
+}
+
+/// The source file a test file sits next to, when it exists or is implied
+fn companion_file(test: &TestFile, language: TestLanguage, project_root: &Path) -> Option<PathBuf> {
+    if test.test_module_line.is_some() {
+        return Some(test.path.clone());
+    }
+    let name = test.path.file_name()?.to_str()?;
+    let companion = match language {
+        TestLanguage::Go => name.strip_suffix("_test.go").map(|stem| format!("{}.go", stem)),
+        TestLanguage::Python => name.strip_prefix("test_").map(str::to_string),
+        TestLanguage::TypeScript | TestLanguage::JavaScript => {
+            let (stem, extension) = name.split_once('.')?;
+            extension
+                .strip_prefix("test.")
+                .or_else(|| extension.strip_prefix("spec."))
+                .map(|extension| format!("{}.{}", stem, extension))
+        }
+        TestLanguage::Rust => None,
+    }?;
+    let file = test.path.with_file_name(companion);
+    // Go tests always share a package with their source; elsewhere only existing files count
+    (language == TestLanguage::Go || project_root.join(&file).is_file()).then_some(file)
+}
+
+fn rust_crate_name(project_root: &Path) -> Option<String> {
+    let manifest: toml::Value = toml::from_str(&std::fs::read_to_string(project_root.join("Cargo.toml")).ok()?).ok()?;
+    let name = manifest.get("lib").and_then(|lib| lib.get("name")).or_else(|| manifest.get("package")?.get("name"))?;
+    Some(name.as_str()?.replace('-', "_"))
+}
+
+/// Source file of a module path such as `parser::lexer`
+fn rust_module_file(project_root: &Path, module: &[&str]) -> Option<PathBuf> {
+    for depth in (1..=module.len()).rev() {
+        let path = module[..depth].join("/");
+        for file in [format!("src/{}.rs", path), format!("src/{}/mod.rs", path)] {
+            if project_root.join(&file).is_file() {
+                return Some(PathBuf::from(file));
+            }
+        }
+    }
+    ["src/lib.rs", "src/main.rs"]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|file| project_root.join(file).is_file())
+}
+
+/// File a relative JavaScript or TypeScript import resolves to
+fn js_module_file(project_root: &Path, dir: &Path, specifier: &str, test_path: &Path) -> PathBuf {
+    let mut base = dir.to_path_buf();
+    for segment in specifier.split('/') {
+        match segment {
+            "." | "" => {}
+            ".." => {
+                base.pop();
+            }
+            segment => base.push(segment),
+        }
+    }
+    let extensions = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+    if base.extension().is_some() && project_root.join(&base).is_file() {
+        return base;
+    }
+    for extension in extensions {
+        let file = base.with_extension(extension);
+        if project_root.join(&file).is_file() {
+            return file;
+        }
+        let index = base.join(format!("index.{}", extension));
+        if project_root.join(&index).is_file() {
+            return index;
+        }
+    }
+    // A module the tests expect to exist; create it in the tests' language
+    let extension = test_path.extension().and_then(|extension| extension.to_str()).unwrap_or("ts");
+    base.with_extension(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::symbols::Visibility;
+
+    fn symbol(name: &str, file: &Path, line: usize) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            qualified_name: None,
+            symbol_type: SymbolType::Function,
+            file_path: file.to_path_buf(),
+            line,
+            line_number: line,
+            column: 0,
+            signature: None,
+            documentation: None,
+            visibility: Visibility::Public,
+            references: Vec::new(),
+        }
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn discover_finds_existing_and_missing_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"calc-lib\"\nversion = \"0.1.0\"\n");
+        write(root, "src/lib.rs", "pub mod ops;\n");
+        write(root, "src/ops.rs", "pub fn add(a: i32, b: i32) -> i32 { a - b }\n");
+        write(
+            root,
+            "tests/ops.rs",
+            "use calc_lib::ops::{add, mul};\n\n#[test]\nfn adds() {\n    assert_eq!(add(1, 2), 3);\n}\n\n#[test]\nfn multiplies() {\n    assert_eq!(mul(2, 3), 6);\n}\n",
+        );
+        let mut symbols = SymbolIndex::new();
+        symbols.add_symbol(symbol("add", &root.join("src/ops.rs"), 1));
+        symbols.add_symbol(symbol("adds", &root.join("tests/ops.rs"), 4));
+
+        let suite = TestSuite::discover(root, &["tests/*.rs".to_string()], &symbols, None).unwrap();
+
+        assert_eq!(suite.language, TestLanguage::Rust);
+        assert_eq!(suite.tests[0].test_names, vec!["adds", "multiplies"]);
+        let targets: Vec<(&str, bool, &Path)> = suite
+            .targets
+            .iter()
+            .map(|target| (target.name.as_str(), target.symbol.is_some(), target.file.as_path()))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![("add", true, Path::new("src/ops.rs")), ("mul", false, Path::new("src/ops.rs"))]
+        );
+        assert_eq!(suite.implementation_files(), vec![PathBuf::from("src/ops.rs")]);
+        assert_eq!(
+            suite.test_command().display(),
+            "cargo test --test ops -- adds multiplies"
+        );
+    }
+
+    #[test]
+    fn edits_to_tests_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "src/calc.rs", "pub fn add(a: i32, b: i32) -> i32 { a - b }\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn adds() { assert_eq!(add(1, 2), 3); }\n}\n");
+        write(root, "tests/more.rs", "#[test]\nfn more() {}\n");
+        let suite = TestSuite::discover(
+            root,
+            &["src/calc.rs".to_string(), "tests/more.rs".to_string()],
+            &SymbolIndex::new(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(suite.tests[0].test_module_line, Some(3));
+        assert_eq!(suite.implementation_files(), vec![PathBuf::from("src/calc.rs")]);
+
+        let mut overlay = BTreeMap::new();
+        let response = "src/calc.rs\n```rust\n<<<<<<< SEARCH\npub fn add(a: i32, b: i32) -> i32 { a - b }\n=======\npub fn add(a: i32, b: i32) -> i32 { a + b }\n>>>>>>> REPLACE\n```\n\n\
+            tests/more.rs\n```rust\n<<<<<<< SEARCH\nfn more() {}\n=======\nfn more() { panic!() }\n>>>>>>> REPLACE\n```\n\n\
+            src/calc.rs\n```rust\n<<<<<<< SEARCH\n    fn adds() { assert_eq!(add(1, 2), 3); }\n=======\n    fn adds() {}\n>>>>>>> REPLACE\n```\n";
+        let (rejected, errors) = apply_edits(&suite, &mut overlay, response);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[0].path, PathBuf::from("tests/more.rs"));
+        assert_eq!(rejected[1].path, PathBuf::from("src/calc.rs"));
+        assert!(rejected[1].reason.contains("test module"));
+        let calc = &overlay[Path::new("src/calc.rs")];
+        assert!(calc.contains("a + b"));
+        assert!(calc.contains(suite.tests[0].protected_text()));
+    }
+
+    #[test]
+    fn edits_that_sidestep_the_tests_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "src/calc.rs", "pub fn add(a: i32, b: i32) -> i32 { a - b }\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn adds() { assert_eq!(add(1, 2), 3); }\n}\n");
+        let suite = TestSuite::discover(root, &["src/calc.rs".to_string()], &SymbolIndex::new(), None).unwrap();
+
+        let mut overlay = BTreeMap::new();
+        let response = "src/calc.rs\n```rust\n<<<<<<< SEARCH\npub fn add(a: i32, b: i32) -> i32 { a - b }\n=======\nmacro_rules! assert_eq { ($($t:tt)*) => {}; }\npub fn add(a: i32, b: i32) -> i32 { a - b }\n>>>>>>> REPLACE\n```\n\n\
+            conftest.py\n```python\n<<<<<<< SEARCH\n=======\nimport pytest\n>>>>>>> REPLACE\n```\n\n\
+            src/calc.rs\n```rust\n<<<<<<< SEARCH\npub fn add(a: i32, b: i32) -> i32 { a - b }\n=======\npub fn add(a: i32, b: i32) -> i32 { a + b }\n#[cfg(any())]\n>>>>>>> REPLACE\n```\n";
+        let (rejected, errors) = apply_edits(&suite, &mut overlay, response);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(rejected.len(), 3);
+        assert!(rejected[0].reason.contains("assert_eq!"));
+        assert_eq!(rejected[1].path, PathBuf::from("conftest.py"));
+        assert!(rejected[2].reason.contains("test module"));
+        assert!(overlay.is_empty());
+
+        for path in [
+            "tests/api.rs",
+            "test/helpers.js",
+            "src/__tests__/setup.js",
+            "pkg/area_test.go",
+            "test_area.py",
+            "area_test.py",
+            "tests/conftest.py",
+            "conftest.py",
+            "src/area.test.ts",
+            "web/area.spec.js",
+        ] {
+            assert!(is_test_path(Path::new(path)), "{}", path);
+        }
+        for path in ["src/area.rs", "pkg/area.go", "testing.py", "src/test.ts", "src/latest/area.js", "test_area.go"] {
+            assert!(!is_test_path(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_commands_are_scoped_per_language() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "pkg/shapes/area.go", "package shapes\n");
+        write(
+            root,
+            "pkg/shapes/area_test.go",
+            "package shapes\n\nimport \"testing\"\n\nfunc TestSquare(t *testing.T) {}\nfunc TestCircle(t *testing.T) {}\n",
+        );
+        write(root, "tests/test_slug.py", "from slug import slugify\n\ndef test_lower():\n    assert slugify('A') == 'a'\n");
+        write(root, "slug.py", "");
+
+        let go = TestSuite::discover(root, &["pkg/**/*_test.go".to_string()], &SymbolIndex::new(), None).unwrap();
+        assert_eq!(go.implementation_files(), vec![PathBuf::from("pkg/shapes/area.go")]);
+        assert_eq!(go.test_command().display(), "go test -run ^(TestSquare|TestCircle)$ ./pkg/shapes");
+
+        let python = TestSuite::discover(root, &["tests/test_slug.py".to_string()], &SymbolIndex::new(), None).unwrap();
+        assert_eq!(python.targets[0].name, "slugify");
+        assert_eq!(python.targets[0].file, PathBuf::from("slug.py"));
+        assert_eq!(python.test_command().display(), "pytest -q tests/test_slug.py");
+        assert!(matches!(
+            TestSuite::discover(root, &["nothing/*.rs".to_string()], &SymbolIndex::new(), None),
+            Err(TestFirstError::NoTests(_))
+        ));
+    }
+}
@@ -0,0 +1,865 @@
+//! Per-language toolchains for the quality gates
+//!
+//! A `ToolchainRegistry` maps languages, recognised by file globs, to the
+//! commands that check their formatting, lint them, type-check them and run
+//! their tests. The format, lint, compile and test gates only run the
+//! toolchains of languages a changeset touches, and every tool's output is
+//! parsed into file/line diagnostics.
+
+use globset::Glob;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command as AsyncCommand;
+
+use super::diff_apply::{ChangeSet, ChangeType};
+
+static RUSTFMT_DIFF_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^Diff in (.+?)(?: at line |:)(\d+)").unwrap());
+static ESLINT_UNIX_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\S.*?):(\d+):(\d+): (.+?) \[(Error|Warning)/(.+)\]$").unwrap());
+static RUFF_CONCISE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\S.*?):(\d+):(\d+): ([A-Z]+\d+) (.+)$").unwrap());
+static MYPY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\S.*?\.pyi?):(\d+):(?:(\d+):)? (error|warning): (.+?)(?:  \[([\w-]+)\])?$").unwrap()
+});
+static GO_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(?:\./)?(\S+?\.go):(\d+)(?::(\d+))?: (.+)$").unwrap());
+static RUSTC_HEADER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(error|warning)(?:\[(\w+)\])?: (.+)$").unwrap());
+static RUSTC_ARROW_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*--> (.+?):(\d+):(\d+)").unwrap());
+static RUST_PANIC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\S.*?panicked at (?:'(.*)', )?([^\s:]+):(\d+):(\d+):?$").unwrap());
+static TSC_PAREN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\S.*?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.+)$").unwrap());
+static TSC_PRETTY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\S.*?):(\d+):(\d+) - (error|warning) (TS\d+): (.+)$").unwrap());
+static PYTEST_LOCATION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\S+\.py):(\d+): (\w+.*)$").unwrap());
+static PYTEST_FAILED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:FAILED|ERROR) (\S+\.py)(?:::(\S+))?(?: - (.+))?$").unwrap());
+/// A failed suite, with the failing test when the runner names it (`FAIL  file > test`)
+static JS_FAIL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*FAIL\s+(\S+?\.[cm]?[jt]sx?)(?: > (.+))?$").unwrap());
+/// A stack frame or vitest location pointing into a script
+static JS_LOCATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:\(|at |❯ )(?:\./)?([^\s()]+?\.[cm]?[jt]sx?):(\d+):(\d+)\)?$").unwrap());
+
+/// Tool output format, and so the tool that produced a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSource {
+    /// rustc, as printed by `cargo check` and `cargo clippy`
+    Rustc,
+    /// `cargo test`: compile errors plus failing tests, located by their panic
+    RustTest,
+    Rustfmt,
+    Prettier,
+    /// ESLint with `--format unix`
+    Eslint,
+    Tsc,
+    Black,
+    /// Ruff with `--output-format concise`
+    Ruff,
+    Mypy,
+    /// `gofmt -l`
+    Gofmt,
+    /// `go vet`, `go build` and `go test`
+    Go,
+    Pytest,
+    /// Jest or Vitest run through `npm test`, including tsc-style compile errors
+    JsTest,
+}
+
+impl DiagnosticSource {
+    /// Parse this tool's output into diagnostics
+    pub fn parse(&self, output: &str) -> Vec<CompilerDiagnostic> {
+        let diagnostics = match self {
+            DiagnosticSource::Rustc | DiagnosticSource::RustTest => parse_rustc(output),
+            DiagnosticSource::Tsc => parse_tsc(output),
+            DiagnosticSource::Pytest => parse_pytest(output),
+            DiagnosticSource::JsTest => parse_js_test(output),
+            DiagnosticSource::Rustfmt => {
+                output
+                    .lines()
+                    .filter_map(|line| RUSTFMT_DIFF_RE.captures(line))
+                    .map(|captures| {
+                        unformatted(*self, &captures[1], captures[2].parse().ok(), "rustfmt")
+                    })
+                    .collect()
+            }
+            DiagnosticSource::Prettier => output
+                .lines()
+                .filter_map(|line| line.strip_prefix("[warn] "))
+                .filter(|file| !file.starts_with("Code style issues"))
+                .map(|file| unformatted(*self, file, None, "prettier"))
+                .collect(),
+            DiagnosticSource::Black => output
+                .lines()
+                .filter_map(|line| line.strip_prefix("would reformat "))
+                .map(|file| unformatted(*self, file.trim(), None, "black"))
+                .collect(),
+            DiagnosticSource::Gofmt => output
+                .lines()
+                .map(str::trim)
+                .filter(|file| file.ends_with(".go"))
+                .map(|file| unformatted(*self, file, None, "gofmt"))
+                .collect(),
+            DiagnosticSource::Eslint => {
+                output
+                    .lines()
+                    .filter_map(|line| ESLINT_UNIX_RE.captures(line))
+                    .map(|captures| CompilerDiagnostic {
+                        source: *self,
+                        severity: if &captures[5] == "Error" { DiagnosticSeverity::Error } else { DiagnosticSeverity::Warning },
+                        file: Some(PathBuf::from(&captures[1])),
+                        line: captures[2].parse().ok(),
+                        column: captures[3].parse().ok(),
+                        code: Some(captures[6].to_string()),
+                        message: captures[4].to_string(),
+                    })
+                    .collect()
+            }
+            DiagnosticSource::Ruff => {
+                output
+                    .lines()
+                    .filter_map(|line| RUFF_CONCISE_RE.captures(line))
+                    .map(|captures| CompilerDiagnostic {
+                        source: *self,
+                        severity: DiagnosticSeverity::Error,
+                        file: Some(PathBuf::from(&captures[1])),
+                        line: captures[2].parse().ok(),
+                        column: captures[3].parse().ok(),
+                        code: Some(captures[4].to_string()),
+                        message: captures[5].trim_start_matches("[*] ").to_string(),
+                    })
+                    .collect()
+            }
+            DiagnosticSource::Mypy => {
+                output
+                    .lines()
+                    .filter_map(|line| MYPY_RE.captures(line))
+                    .map(|captures| CompilerDiagnostic {
+                        source: *self,
+                        severity: if &captures[4] == "error" { DiagnosticSeverity::Error } else { DiagnosticSeverity::Warning },
+                        file: Some(PathBuf::from(&captures[1])),
+                        line: captures[2].parse().ok(),
+                        column: captures.get(3).and_then(|column| column.as_str().parse().ok()),
+                        code: captures.get(6).map(|code| code.as_str().to_string()),
+                        message: captures[5].to_string(),
+                    })
+                    .collect()
+            }
+            DiagnosticSource::Go => {
+                // go test indents failures under the test name
+                output
+                    .lines()
+                    .filter_map(|line| GO_RE.captures(line))
+                    .map(|captures| CompilerDiagnostic {
+                        source: *self,
+                        severity: DiagnosticSeverity::Error,
+                        file: Some(PathBuf::from(&captures[1])),
+                        line: captures[2].parse().ok(),
+                        column: captures.get(3).and_then(|column| column.as_str().parse().ok()),
+                        code: None,
+                        message: captures[4].to_string(),
+                    })
+                    .collect()
+            }
+        };
+        dedup(diagnostics)
+    }
+
+    /// Whether the tool exits successfully even when it reports problems
+    fn reports_by_output(&self) -> bool {
+        matches!(self, DiagnosticSource::Gofmt)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+/// A compiler error, lint or test failure parsed from tool output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompilerDiagnostic {
+    pub source: DiagnosticSource,
+    pub severity: DiagnosticSeverity,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Error code or rule, such as `E0308`, `TS2322` or `no-unused-vars`
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for CompilerDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+                if let Some(column) = self.column {
+                    write!(f, ":{}", column)?;
+                }
+            }
+            write!(f, ": ")?;
+        }
+        let severity = match self.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+        };
+        match &self.code {
+            Some(code) => write!(f, "{}[{}]: {}", severity, code, self.message),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+fn unformatted(source: DiagnosticSource, file: &str, line: Option<usize>, tool: &str) -> CompilerDiagnostic {
+    CompilerDiagnostic {
+        source,
+        severity: DiagnosticSeverity::Warning,
+        file: Some(PathBuf::from(file)),
+        line,
+        column: None,
+        code: None,
+        message: format!("not formatted according to {}", tool),
+    }
+}
+
+fn dedup(diagnostics: Vec<CompilerDiagnostic>) -> Vec<CompilerDiagnostic> {
+    let mut unique = Vec::new();
+    for diagnostic in diagnostics {
+        if !unique.contains(&diagnostic) {
+            unique.push(diagnostic);
+        }
+    }
+    unique
+}
+
+/// Parse mixed rustc, `cargo test`, tsc and pytest output into diagnostics
+///
+/// For output whose producing tool is unknown, such as custom gate commands.
+pub fn parse_diagnostics(output: &str) -> Vec<CompilerDiagnostic> {
+    let mut diagnostics = parse_rustc(output);
+    diagnostics.extend(parse_tsc(output));
+    diagnostics.extend(parse_pytest(output));
+    dedup(diagnostics)
+}
+
+fn parse_rustc(output: &str) -> Vec<CompilerDiagnostic> {
+
+    let lines: Vec<&str> = output.lines().collect();
+    let mut diagnostics = Vec::new();
+    // rustc prints the message first and the location on a following line
+    let mut pending: Option<CompilerDiagnostic> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(captures) = RUSTC_ARROW_RE.captures(line) {
+            if let Some(mut diagnostic) = pending.take() {
+                diagnostic.file = Some(PathBuf::from(&captures[1]));
+                diagnostic.line = captures[2].parse().ok();
+                diagnostic.column = captures[3].parse().ok();
+                diagnostics.push(diagnostic);
+            }
+        } else if let Some(captures) = RUSTC_HEADER_RE.captures(line) {
+            let message = captures[3].to_string();
+            // Summary lines carry no location and nothing worth fixing
+            pending = (!is_rustc_summary(&message)).then(|| CompilerDiagnostic {
+                source: DiagnosticSource::Rustc,
+                severity: if &captures[1] == "error" { DiagnosticSeverity::Error } else { DiagnosticSeverity::Warning },
+                file: None,
+                line: None,
+                column: None,
+                code: captures.get(2).map(|code| code.as_str().to_string()),
+                message,
+            });
+        } else if let Some(captures) = RUST_PANIC_RE.captures(line) {
+            // Newer toolchains print the panic message on the next line
+            let message = captures
+                .get(1)
+                .map(|message| message.as_str().to_string())
+                .or_else(|| lines.get(index + 1).map(|next| next.trim().to_string()))
+                .unwrap_or_default();
+            let test = line
+                .split('\'')
+                .nth(1)
+                .filter(|_| line.starts_with("thread"))
+                .map(|test| format!("test {} failed: ", test))
+                .unwrap_or_default();
+            diagnostics.push(CompilerDiagnostic {
+                source: DiagnosticSource::RustTest,
+                severity: DiagnosticSeverity::Error,
+                file: Some(PathBuf::from(&captures[2])),
+                line: captures[3].parse().ok(),
+                column: captures[4].parse().ok(),
+                code: None,
+                message: format!("{}{}", test, message),
+            });
+        }
+    }
+    diagnostics
+}
+
+fn is_rustc_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || message.starts_with("could not compile")
+        || message.contains("warnings emitted")
+        || message.contains("warning emitted")
+        || message.starts_with("test failed")
+}
+
+fn parse_tsc(output: &str) -> Vec<CompilerDiagnostic> {
+    output
+        .lines()
+        .filter_map(|line| TSC_PAREN_RE.captures(line).or_else(|| TSC_PRETTY_RE.captures(line)))
+        .map(|captures| CompilerDiagnostic {
+            source: DiagnosticSource::Tsc,
+            severity: if &captures[4] == "error" { DiagnosticSeverity::Error } else { DiagnosticSeverity::Warning },
+            file: Some(PathBuf::from(&captures[1])),
+            line: captures[2].parse().ok(),
+            column: captures[3].parse().ok(),
+            code: Some(captures[5].to_string()),
+            message: captures[6].trim().to_string(),
+        })
+        .collect()
+}
+
+fn parse_pytest(output: &str) -> Vec<CompilerDiagnostic> {
+
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        if let Some(captures) = PYTEST_LOCATION_RE.captures(line) {
+            diagnostics.push(CompilerDiagnostic {
+                source: DiagnosticSource::Pytest,
+                severity: DiagnosticSeverity::Error,
+                file: Some(PathBuf::from(&captures[1])),
+                line: captures[2].parse().ok(),
+                column: None,
+                code: None,
+                message: captures[3].trim().to_string(),
+            });
+        } else if let Some(captures) = PYTEST_FAILED_RE.captures(line) {
+            let test = captures.get(2).map(|test| format!("{} failed", test.as_str()));
+            let message = match (test, captures.get(3)) {
+                (Some(test), Some(reason)) => format!("{}: {}", test, reason.as_str()),
+                (Some(test), None) => test,
+                (None, Some(reason)) => reason.as_str().to_string(),
+                (None, None) => "collection failed".to_string(),
+            };
+            diagnostics.push(CompilerDiagnostic {
+                source: DiagnosticSource::Pytest,
+                severity: DiagnosticSeverity::Error,
+                file: Some(PathBuf::from(&captures[1])),
+                line: None,
+                column: None,
+                code: None,
+                message,
+            });
+        }
+    }
+    diagnostics
+}
+
+fn parse_js_test(output: &str) -> Vec<CompilerDiagnostic> {
+    let mut diagnostics = parse_tsc(output);
+    let mut failed_suites: Vec<String> = Vec::new();
+    // Failing test awaiting its location, with the first line of its message
+    let mut failing: Option<(String, Option<String>)> = None;
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(captures) = JS_FAIL_RE.captures(line) {
+            failed_suites.push(captures[1].to_string());
+            failing = captures.get(2).map(|test| (test.as_str().to_string(), None));
+        } else if let Some(test) = trimmed.strip_prefix("● ").filter(|test| !test.starts_with("Console")) {
+            failing = Some((test.to_string(), None));
+        } else if let Some(captures) = JS_LOCATION_RE.captures(trimmed).filter(|captures| !captures[1].contains("node_modules")) {
+            if let Some((test, message)) = failing.take() {
+                diagnostics.push(CompilerDiagnostic {
+                    source: DiagnosticSource::JsTest,
+                    severity: DiagnosticSeverity::Error,
+                    file: Some(PathBuf::from(&captures[1])),
+                    line: captures[2].parse().ok(),
+                    column: captures[3].parse().ok(),
+                    code: None,
+                    message: match message {
+                        Some(message) => format!("test {} failed: {}", test, message),
+                        None => format!("test {} failed", test),
+                    },
+                });
+            }
+        } else if let Some((_, message @ None)) = &mut failing {
+            if !trimmed.is_empty() {
+                *message = Some(trimmed.to_string());
+            }
+        }
+    }
+
+    // Suites that failed without a located test, such as on a syntax error
+    for suite in failed_suites {
+        let path = PathBuf::from(&suite);
+        if !diagnostics.iter().any(|diagnostic| diagnostic.file.as_ref() == Some(&path)) {
+            diagnostics.push(CompilerDiagnostic {
+                source: DiagnosticSource::JsTest,
+                severity: DiagnosticSeverity::Error,
+                file: Some(path),
+                line: None,
+                column: None,
+                code: None,
+                message: "test suite failed".to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// What a toolchain command checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    Format,
+    Lint,
+    TypeCheck,
+    Test,
+}
+
+/// A command run by a quality gate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Format of the command's output
+    pub output: DiagnosticSource,
+    /// Pass the changed files of the language as trailing arguments
+    #[serde(default)]
+    pub per_file: bool,
+    /// File that must exist in the project root, such as `Cargo.toml`
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// Exit codes besides 0 that mean nothing was wrong
+    #[serde(default)]
+    pub ok_exit_codes: Vec<i32>,
+}
+
+impl ToolCommand {
+    pub fn new(program: &str, args: &[&str], output: DiagnosticSource) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            output,
+            per_file: false,
+            requires: None,
+            ok_exit_codes: Vec::new(),
+        }
+    }
+
+    pub fn per_file(mut self) -> Self {
+        self.per_file = true;
+        self
+    }
+
+    pub fn requires(mut self, marker: &str) -> Self {
+        self.requires = Some(marker.to_string());
+        self
+    }
+
+    pub fn with_ok_exit_codes(mut self, codes: &[i32]) -> Self {
+        self.ok_exit_codes = codes.to_vec();
+        self
+    }
+
+    /// Command line as shown to the user
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Run the command in `project_root` for the given changed files
+    pub async fn run(&self, project_root: &Path, files: &[PathBuf]) -> ToolOutcome {
+        if let Some(marker) = &self.requires {
+            if !project_root.join(marker).exists() {
+                return ToolOutcome::Unavailable(format!("no {} in the project", marker));
+            }
+        }
+
+        let mut command = AsyncCommand::new(&self.program);
+        command.args(&self.args).current_dir(project_root);
+        if self.per_file {
+            command.args(files);
+        }
+        let output = match command.output().await {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return ToolOutcome::Unavailable(format!("{} is not installed", self.program));
+            }
+            Err(e) => return ToolOutcome::Unavailable(format!("{} could not be started: {}", self.program, e)),
+        };
+
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let diagnostics = self.output.parse(&text);
+        let exit_ok = output
+            .status
+            .code()
+            .is_some_and(|code| code == 0 || self.ok_exit_codes.contains(&code));
+        let failed = !exit_ok
+            || diagnostics.iter().any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+            || (self.output.reports_by_output() && !diagnostics.is_empty());
+
+        if failed {
+            ToolOutcome::Failed { output: text, diagnostics }
+        } else {
+            ToolOutcome::Passed { diagnostics }
+        }
+    }
+}
+
+/// Result of running a toolchain command
+#[derive(Debug, Clone)]
+pub enum ToolOutcome {
+    /// Warnings may still be reported
+    Passed { diagnostics: Vec<CompilerDiagnostic> },
+    Failed { output: String, diagnostics: Vec<CompilerDiagnostic> },
+    /// The tool does not apply to this project or is not installed
+    Unavailable(String),
+}
+
+/// One command run for one language
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    pub language: String,
+    pub command: String,
+    pub outcome: ToolOutcome,
+}
+
+/// Tools for one language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Toolchain {
+    pub language: String,
+    /// Globs matched against project-relative paths, such as `*.rs`
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub format: Option<ToolCommand>,
+    #[serde(default)]
+    pub lint: Option<ToolCommand>,
+    #[serde(default)]
+    pub type_check: Option<ToolCommand>,
+    #[serde(default)]
+    pub test: Option<ToolCommand>,
+}
+
+impl Toolchain {
+    pub fn new(language: &str, patterns: &[&str]) -> Self {
+        Self {
+            language: language.to_string(),
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            format: None,
+            lint: None,
+            type_check: None,
+            test: None,
+        }
+    }
+
+    pub fn with_format(mut self, command: ToolCommand) -> Self {
+        self.format = Some(command);
+        self
+    }
+
+    pub fn with_lint(mut self, command: ToolCommand) -> Self {
+        self.lint = Some(command);
+        self
+    }
+
+    pub fn with_type_check(mut self, command: ToolCommand) -> Self {
+        self.type_check = Some(command);
+        self
+    }
+
+    pub fn with_test(mut self, command: ToolCommand) -> Self {
+        self.test = Some(command);
+        self
+    }
+
+    pub fn command(&self, kind: ToolKind) -> Option<&ToolCommand> {
+        match kind {
+            ToolKind::Format => self.format.as_ref(),
+            ToolKind::Lint => self.lint.as_ref(),
+            ToolKind::TypeCheck => self.type_check.as_ref(),
+            ToolKind::Test => self.test.as_ref(),
+        }
+    }
+
+    /// Whether a project-relative path belongs to this language
+    pub fn matches(&self, path: &Path) -> bool {
+        self.patterns.iter().any(|pattern| match Glob::new(pattern) {
+            Ok(glob) => glob.compile_matcher().is_match(path),
+            Err(e) => {
+                tracing::warn!("Invalid toolchain pattern {} for {}: {}", pattern, self.language, e);
+                false
+            }
+        })
+    }
+}
+
+/// Toolchains by language, serialized as a plain list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ToolchainRegistry {
+    pub toolchains: Vec<Toolchain>,
+}
+
+impl Default for ToolchainRegistry {
+    fn default() -> Self {
+        use DiagnosticSource as Output;
+
+        Self::new()
+            .with_toolchain(
+                Toolchain::new("rust", &["*.rs", "**/Cargo.toml"])
+                    .with_format(ToolCommand::new("cargo", &["fmt", "--check"], Output::Rustfmt).requires("Cargo.toml"))
+                    .with_lint(
+                        ToolCommand::new("cargo", &["clippy", "--", "-D", "warnings"], Output::Rustc)
+                            .requires("Cargo.toml"),
+                    )
+                    .with_type_check(ToolCommand::new("cargo", &["check"], Output::Rustc).requires("Cargo.toml"))
+                    .with_test(ToolCommand::new("cargo", &["test"], Output::RustTest).requires("Cargo.toml")),
+            )
+            .with_toolchain(
+                Toolchain::new("typescript", &["*.ts", "*.tsx", "*.js", "*.jsx", "*.mjs", "*.cjs"])
+                    .with_format(
+                        ToolCommand::new("npx", &["--no-install", "prettier", "--check"], Output::Prettier)
+                            .per_file()
+                            .requires("package.json"),
+                    )
+                    .with_lint(
+                        ToolCommand::new("npx", &["--no-install", "eslint", "--format", "unix"], Output::Eslint)
+                            .per_file()
+                            .requires("package.json"),
+                    )
+                    .with_type_check(
+                        ToolCommand::new("npx", &["--no-install", "tsc", "--noEmit", "--pretty", "false"], Output::Tsc)
+                            .requires("tsconfig.json"),
+                    )
+                    .with_test(ToolCommand::new("npm", &["test", "--silent"], Output::JsTest).requires("package.json")),
+            )
+            .with_toolchain(
+                Toolchain::new("python", &["*.py", "*.pyi"])
+                    .with_format(ToolCommand::new("black", &["--check"], Output::Black).per_file())
+                    .with_lint(
+                        ToolCommand::new("ruff", &["check", "--output-format", "concise"], Output::Ruff).per_file(),
+                    )
+                    .with_type_check(ToolCommand::new("mypy", &["--show-column-numbers"], Output::Mypy).per_file())
+                    // pytest exits with 5 when there is nothing to collect
+                    .with_test(ToolCommand::new("pytest", &["-q"], Output::Pytest).with_ok_exit_codes(&[5])),
+            )
+            .with_toolchain(
+                Toolchain::new("go", &["*.go", "**/go.mod"])
+                    .with_format(ToolCommand::new("gofmt", &["-l"], Output::Gofmt).per_file())
+                    .with_lint(ToolCommand::new("go", &["vet", "./..."], Output::Go).requires("go.mod"))
+                    .with_type_check(ToolCommand::new("go", &["build", "./..."], Output::Go).requires("go.mod"))
+                    .with_test(ToolCommand::new("go", &["test", "./..."], Output::Go).requires("go.mod")),
+            )
+    }
+}
+
+impl ToolchainRegistry {
+    /// Registry without any toolchains
+    pub fn new() -> Self {
+        Self { toolchains: Vec::new() }
+    }
+
+    /// Add a toolchain, replacing any previous one for the same language
+    pub fn with_toolchain(mut self, toolchain: Toolchain) -> Self {
+        self.toolchains.retain(|existing| existing.language != toolchain.language);
+        self.toolchains.push(toolchain);
+        self
+    }
+
+    pub fn toolchain(&self, language: &str) -> Option<&Toolchain> {
+        self.toolchains.iter().find(|toolchain| toolchain.language == language)
+    }
+
+    /// Toolchains of the languages a changeset touches, with their changed files
+    ///
+    /// A file belongs to the first toolchain whose patterns match it. Deleted
+    /// files count towards the language but are not passed to per-file tools.
+    pub fn touched_by(&self, changeset: &ChangeSet) -> Vec<(&Toolchain, Vec<PathBuf>)> {
+        let mut touched: Vec<(&Toolchain, Vec<PathBuf>)> = Vec::new();
+        for file in &changeset.files {
+            let Some(toolchain) = self.toolchains.iter().find(|toolchain| toolchain.matches(&file.file_path)) else {
+                continue;
+            };
+            let index = match touched.iter().position(|(existing, _)| existing.language == toolchain.language) {
+                Some(index) => index,
+                None => {
+                    touched.push((toolchain, Vec::new()));
+                    touched.len() - 1
+                }
+            };
+            if file.change_type != ChangeType::Delete {
+                touched[index].1.push(file.file_path.clone());
+            }
+        }
+        touched
+    }
+
+    /// Run the tool of one kind for every language the changeset touches
+    pub async fn run(&self, kind: ToolKind, changeset: &ChangeSet, project_root: &Path) -> Vec<ToolRun> {
+        let mut runs = Vec::new();
+        for (toolchain, files) in self.touched_by(changeset) {
+            let Some(command) = toolchain.command(kind) else {
+                continue;
+            };
+            // Per-file tools have nothing to check when the language's files were only deleted
+            if command.per_file && files.is_empty() {
+                continue;
+            }
+            runs.push(ToolRun {
+                language: toolchain.language.clone(),
+                command: command.display(),
+                outcome: command.run(project_root, &files).await,
+            });
+        }
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::diff_apply::{DiffMetadata, FileDiff};
+
+    fn changeset(paths: &[&str]) -> ChangeSet {
+        let files = paths
+            .iter()
+            .map(|path| FileDiff {
+                file_path: PathBuf::from(path),
+                original_content: None,
+                new_content: String::new(),
+                diff_text: String::new(),
+                change_type: ChangeType::Create,
+                metadata: DiffMetadata {
+                    created_at: chrono::Utc::now(),
+                    agent_id: "agent".to_string(),
+                    task_id: "task".to_string(),
+                    confidence_score: 1.0,
+                    estimated_lines_changed: 0,
+                    language: None,
+                    description: String::new(),
+                },
+                hunks: Vec::new(),
+            })
+            .collect();
+        ChangeSet::new("title", "description", "agent", "task", files)
+    }
+
+    #[test]
+    fn selects_toolchains_of_touched_languages() {
+        let registry = ToolchainRegistry::default();
+        let touched = registry.touched_by(&changeset(&["src/lib.rs", "app/main.py", "README.md", "app/util.py"]));
+
+        let languages: Vec<&str> = touched.iter().map(|(toolchain, _)| toolchain.language.as_str()).collect();
+        assert_eq!(languages, vec!["rust", "python"]);
+        assert_eq!(touched[1].1, vec![PathBuf::from("app/main.py"), PathBuf::from("app/util.py")]);
+    }
+
+    #[test]
+    fn parses_jest_and_vitest_failures() {
+        let jest = "\
+ FAIL  src/sum.test.js
+  ● sum › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+      at Object.toBe (node_modules/expect/build/index.js:10:3)
+      at Object.<anonymous> (src/sum.test.js:4:21)
+
+ FAIL  src/broken.test.js
+  ● Test suite failed to run
+";
+        let diagnostics = DiagnosticSource::JsTest.parse(jest);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "src/sum.test.js:4:21: error: test sum › adds numbers failed: expect(received).toBe(expected) // Object.is equality"
+        );
+        assert_eq!(diagnostics[1].file, Some(PathBuf::from("src/broken.test.js")));
+
+        let vitest = " FAIL  src/sum.test.ts > adds\nAssertionError: expected 4 to be 3\n ❯ src/sum.test.ts:5:17\n";
+        let diagnostics = DiagnosticSource::JsTest.parse(vitest);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(5));
+        assert_eq!(diagnostics[0].message, "test adds failed: AssertionError: expected 4 to be 3");
+    }
+
+    #[test]
+    fn parses_rustc_errors_and_test_panics() {
+        let output = "\
+error[E0308]: mismatched types
+  --> src/lib.rs:3:5
+   |
+warning: unused variable: `x`
+ --> src/lib.rs:1:9
+error: aborting due to 1 previous error
+thread 'tests::adds' panicked at src/lib.rs:10:5:
+assertion `left == right` failed
+";
+        let diagnostics = DiagnosticSource::RustTest.parse(output);
+
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].to_string(), "src/lib.rs:3:5: error[E0308]: mismatched types");
+        assert_eq!(diagnostics[1].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostics[2].source, DiagnosticSource::RustTest);
+        assert_eq!(
+            diagnostics[2].message,
+            "test tests::adds failed: assertion `left == right` failed"
+        );
+    }
+
+    #[test]
+    fn parses_tsc_and_pytest_output() {
+        let output = "\
+src/index.ts(4,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/app.ts:9:1 - error TS2304: Cannot find name 'foo'.
+tests/test_math.py:12: AssertionError
+FAILED tests/test_math.py::test_add - assert 3 == 4
+";
+        let diagnostics = parse_diagnostics(output);
+
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics[0].source, DiagnosticSource::Tsc);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("TS2322"));
+        assert_eq!(diagnostics[1].to_string(), "src/app.ts:9:1: error[TS2304]: Cannot find name 'foo'.");
+        assert_eq!(diagnostics[2].line, Some(12));
+        assert_eq!(diagnostics[3].message, "test_add failed: assert 3 == 4");
+    }
+
+    #[test]
+    fn parses_linter_and_formatter_output() {
+        let eslint = DiagnosticSource::Eslint.parse("/p/src/a.ts:3:7: 'x' is assigned a value but never used. [Error/no-unused-vars]\n\n1 problem\n");
+        assert_eq!(eslint[0].code.as_deref(), Some("no-unused-vars"));
+        assert_eq!(eslint[0].line, Some(3));
+
+        let ruff = DiagnosticSource::Ruff.parse("app/main.py:1:8: F401 [*] `os` imported but unused\nFound 1 error.\n");
+        assert_eq!(ruff[0].to_string(), "app/main.py:1:8: error[F401]: `os` imported but unused");
+
+        let mypy = DiagnosticSource::Mypy.parse(
+            "app/main.py:4:12: error: Incompatible return value type (got \"str\", expected \"int\")  [return-value]\nFound 1 error in 1 file\n",
+        );
+        assert_eq!(mypy[0].column, Some(12));
+        assert_eq!(mypy[0].code.as_deref(), Some("return-value"));
+
+        let go = DiagnosticSource::Go.parse("# example.com/app\n./main.go:7:2: x declared and not used\n");
+        assert_eq!(go[0].file, Some(PathBuf::from("main.go")));
+
+        assert_eq!(DiagnosticSource::Black.parse("would reformat app/main.py\nOh no!\n").len(), 1);
+        assert_eq!(DiagnosticSource::Prettier.parse("Checking formatting...\n[warn] src/a.ts\n[warn] Code style issues found in the above file. Run Prettier to fix.\n").len(), 1);
+        assert_eq!(DiagnosticSource::Rustfmt.parse("Diff in /p/src/lib.rs at line 3:\n").len(), 1);
+        assert!(DiagnosticSource::Gofmt.reports_by_output());
+    }
+}
@@ -44,6 +44,8 @@ impl SystemDefaults {
             agents: self.generate_agent_config(),
             codegen: self.generate_codegen_config(),
             chat: ChatConfig::default(),
+            language_servers: LanguageServersConfig::default(),
+            quality_gates: QualityGateConfig::default(),
             shell: self.generate_shell_config(),
             ui: self.generate_ui_config(),
             web: WebConfig::default(),
@@ -332,6 +334,7 @@ impl SystemDefaults {
             },
             openai: None, // User needs to configure API keys manually
             anthropic: None,
+            embeddings: EmbeddingProviderConfig::default(),
             context_window_size: context_window,
             temperature: 0.7,
             max_tokens,
@@ -13,6 +13,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::codegen::diff_apply::QualityGateConfig;
+
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -21,6 +23,11 @@ pub struct Config {
     pub codegen: CodegenConfig,
     #[serde(default)]
     pub chat: ChatConfig,
+    #[serde(default)]
+    pub language_servers: LanguageServersConfig,
+    /// Quality gates and per-language toolchains used when applying changesets
+    #[serde(default)]
+    pub quality_gates: QualityGateConfig,
     pub shell: ShellConfig,
     pub ui: UIConfig,
     pub web: WebConfig,
@@ -87,6 +94,29 @@ pub struct ChatConfig {
     pub language_hint_weight: f32,
 }
 
+/// Language servers used for compiler-grade diagnostics, types and definitions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageServersConfig {
+    pub enabled: bool,
+    /// How long to wait for a server to publish diagnostics for a changed document
+    pub diagnostics_timeout_ms: u64,
+    pub servers: Vec<LanguageServerConfig>,
+}
+
+/// A language server launched over stdio for one language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageServerConfig {
+    /// LSP language identifier, e.g. "rust" or "python"
+    pub language: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// File extensions the server handles, without the leading dot
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub initialization_options: Option<serde_json::Value>,
+}
+
 /// Code style configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StyleConfig {
@@ -115,6 +145,8 @@ pub struct AIModelConfig {
     pub ollama: OllamaConfig,
     pub openai: Option<OpenAIConfig>,
     pub anthropic: Option<AnthropicConfig>,
+    #[serde(default)]
+    pub embeddings: EmbeddingProviderConfig,
     pub context_window_size: usize,
     pub temperature: f64,
     pub max_tokens: usize,
@@ -129,6 +161,18 @@ pub struct OllamaConfig {
     pub default_model: Option<String>,
 }
 
+/// Embedding provider selection for semantic code search
+///
+/// Missing fields fall back to [`EmbeddingProviderConfig::default`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingProviderConfig {
+    pub provider: String, // "none", "local", "ollama" or "openai"
+    pub model: Option<String>,
+    pub batch_size: usize,
+    pub dimension: Option<usize>, // Detected from the first response when unset
+}
+
 /// OpenAI-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAIConfig {
@@ -833,6 +877,8 @@ impl Default for Config {
             agents: AgentConfig::default(),
             codegen: CodegenConfig::default(),
             chat: ChatConfig::default(),
+            language_servers: LanguageServersConfig::default(),
+            quality_gates: QualityGateConfig::default(),
             shell: ShellConfig::default(),
             ui: UIConfig::default(),
             web: WebConfig::default(),
@@ -901,6 +947,36 @@ impl Default for ChatConfig {
     }
 }
 
+impl Default for LanguageServersConfig {
+    fn default() -> Self {
+        let server = |language: &str, command: &str, args: &[&str], extensions: &[&str]| {
+            LanguageServerConfig {
+                language: language.to_string(),
+                command: command.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+                extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+                initialization_options: None,
+            }
+        };
+
+        Self {
+            enabled: true,
+            diagnostics_timeout_ms: 5000,
+            servers: vec![
+                server("rust", "rust-analyzer", &[], &["rs"]),
+                server("python", "pyright-langserver", &["--stdio"], &["py", "pyi"]),
+                server("go", "gopls", &[], &["go"]),
+                server(
+                    "typescript",
+                    "typescript-language-server",
+                    &["--stdio"],
+                    &["ts", "tsx", "js", "jsx", "mjs", "cjs"],
+                ),
+            ],
+        }
+    }
+}
+
 impl Default for StyleConfig {
     fn default() -> Self {
         Self {
@@ -922,6 +998,7 @@ impl Default for AIModelConfig {
             ollama: OllamaConfig::default(),
             openai: None,
             anthropic: None,
+            embeddings: EmbeddingProviderConfig::default(),
             context_window_size: 8192,
             temperature: 0.7,
             max_tokens: 1000,
@@ -929,6 +1006,17 @@ impl Default for AIModelConfig {
     }
 }
 
+impl Default for EmbeddingProviderConfig {
+    fn default() -> Self {
+        Self {
+            provider: "none".to_string(),
+            model: None,
+            batch_size: 32,
+            dimension: None,
+        }
+    }
+}
+
 impl Default for OllamaConfig {
     fn default() -> Self {
         Self {
@@ -408,6 +408,21 @@ impl ConfigValidator {
             ));
         }
 
+        // Validate embedding provider selection
+        let embeddings = &ai_config.embeddings;
+        if !["none", "local", "ollama", "openai"].contains(&embeddings.provider.as_str()) {
+            return Err(ConfigError::ValidationError(format!(
+                "Invalid embedding provider '{}'. Valid options are: none, local, ollama, openai",
+                embeddings.provider
+            )));
+        }
+
+        if embeddings.batch_size == 0 || embeddings.batch_size > 1024 {
+            return Err(ConfigError::ValidationError(
+                "Embedding batch_size must be between 1 and 1024".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
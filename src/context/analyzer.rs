@@ -1,14 +1,13 @@
 //! Codebase analyzer for extracting structure and relationships.
 
-use crate::context::{
-    AnalysisConfig, ContextError, Dependency, DependencySource, DependencyType, FileContext,
-};
+use crate::context::walker::{is_binary, FileWalker};
+use crate::context::{manifests, AnalysisConfig, ContextError, Dependency, FileContext};
+use futures_util::stream::{self, StreamExt};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 /// Analyzer for examining codebases and extracting structural information
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CodebaseAnalyzer {
     // Configuration and state for analysis
 }
@@ -20,114 +19,101 @@ impl CodebaseAnalyzer {
     }
 
     /// Analyze files in a directory and extract context information
+    ///
+    /// Files are discovered by a gitignore-aware parallel walk and analyzed
+    /// with at most `config.max_concurrency` files in flight.
     pub async fn analyze_files(
         &self,
         root_path: &PathBuf,
         config: &AnalysisConfig,
     ) -> Result<Vec<FileContext>, ContextError> {
-        let mut file_contexts = Vec::new();
-
-        // Walk through the directory tree
-        for entry in WalkDir::new(root_path)
-            .follow_links(config.follow_symlinks)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let file_path = entry.path();
-
-            // Apply include/exclude patterns
-            if !self.should_include_file(file_path, config) {
-                continue;
-            }
-
-            // Check file size limits
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.len() > (config.max_file_size_mb * 1024 * 1024) as u64 {
-                    continue;
-                }
-            }
-
-            // Analyze the file
-            match self.analyze_single_file(file_path, root_path).await {
-                Ok(file_context) => file_contexts.push(file_context),
-                Err(e) => {
-                    // Only log warnings for non-UTF-8 issues if it's not a known binary file
-                    let error_msg = e.to_string();
-                    if error_msg.contains("non-UTF-8 content") {
-                        // Skip binary files silently
-                        continue;
-                    } else {
-                        tracing::warn!("Failed to analyze file {:?}: {}", file_path, e);
-                        continue;
-                    }
-                }
-            }
-        }
-
-        Ok(file_contexts)
+        let walker = FileWalker::new(root_path, config)?;
+        let file_paths = tokio::task::spawn_blocking(move || walker.walk())
+            .await
+            .map_err(|e| ContextError::AnalysisFailed(format!("File walk failed: {}", e)))?;
+
+        Ok(self
+            .analyze_paths(root_path, file_paths, config.max_concurrency)
+            .await)
     }
 
     /// Analyze specific files (for incremental updates)
+    ///
+    /// Paths are resolved against `root_path` so relative paths and
+    /// relationships match a full analysis. Files excluded by the config or
+    /// by ignore files are skipped.
     pub async fn analyze_specific_files(
         &self,
+        root_path: &PathBuf,
         file_paths: &[PathBuf],
-        _config: &AnalysisConfig,
+        config: &AnalysisConfig,
     ) -> Result<Vec<FileContext>, ContextError> {
-        let mut file_contexts = Vec::new();
-
-        for file_path in file_paths {
-            if let Some(root_path) = file_path.parent() {
-                match self
-                    .analyze_single_file(file_path, &root_path.to_path_buf())
-                    .await
-                {
-                    Ok(file_context) => file_contexts.push(file_context),
-                    Err(e) => {
+        let walker = FileWalker::new(root_path, config)?;
+        let file_paths = file_paths
+            .iter()
+            .filter(|path| walker.is_included(path))
+            .cloned()
+            .collect();
+
+        Ok(self
+            .analyze_paths(root_path, file_paths, config.max_concurrency)
+            .await)
+    }
+
+    /// Analyze files on blocking threads with bounded concurrency
+    async fn analyze_paths(
+        &self,
+        root_path: &Path,
+        file_paths: Vec<PathBuf>,
+        max_concurrency: usize,
+    ) -> Vec<FileContext> {
+        let mut file_contexts: Vec<FileContext> = stream::iter(file_paths)
+            .map(|file_path| {
+                let analyzer = self.clone();
+                let root_path = root_path.to_path_buf();
+                tokio::task::spawn_blocking(move || {
+                    let result = analyzer.analyze_single_file(&file_path, &root_path);
+                    (file_path, result)
+                })
+            })
+            .buffer_unordered(max_concurrency.max(1))
+            .filter_map(|joined| async move {
+                match joined {
+                    Ok((_, Ok(file_context))) => file_context,
+                    Ok((file_path, Err(e))) => {
                         tracing::warn!("Failed to analyze file {:?}: {}", file_path, e);
-                        continue;
+                        None
+                    }
+                    Err(e) => {
+                        tracing::warn!("File analysis task failed: {}", e);
+                        None
                     }
                 }
-            }
-        }
+            })
+            .collect()
+            .await;
 
-        Ok(file_contexts)
+        file_contexts.sort_by(|a, b| a.path.cmp(&b.path));
+        file_contexts
     }
 
-    /// Analyze dependencies in the codebase
+    /// Analyze dependencies declared by the manifests in the codebase
     pub async fn analyze_dependencies(
         &self,
         root_path: &PathBuf,
         _files: &[FileContext],
     ) -> Result<Vec<Dependency>, ContextError> {
-        let mut dependencies = Vec::new();
-
-        // Look for common dependency files
-        if let Ok(cargo_toml) = fs::read_to_string(root_path.join("Cargo.toml")) {
-            dependencies.extend(self.parse_cargo_dependencies(&cargo_toml)?);
-        }
-
-        if let Ok(package_json) = fs::read_to_string(root_path.join("package.json")) {
-            dependencies.extend(self.parse_npm_dependencies(&package_json)?);
-        }
-
-        if let Ok(requirements_txt) = fs::read_to_string(root_path.join("requirements.txt")) {
-            dependencies.extend(self.parse_python_dependencies(&requirements_txt)?);
-        }
-
-        if let Ok(go_mod) = fs::read_to_string(root_path.join("go.mod")) {
-            dependencies.extend(self.parse_go_dependencies(&go_mod)?);
-        }
-
-        Ok(dependencies)
+        manifests::discover_dependencies(root_path)
     }
 
     /// Analyze a single file and extract context information
-    async fn analyze_single_file(
+    ///
+    /// Returns `None` for binary files.
+    fn analyze_single_file(
         &self,
         file_path: &Path,
         root_path: &PathBuf,
-    ) -> Result<FileContext, ContextError> {
+    ) -> Result<Option<FileContext>, ContextError> {
         let metadata = fs::metadata(file_path).map_err(|e| {
             ContextError::AnalysisFailed(format!(
                 "Failed to read metadata for {:?}: {}",
@@ -135,20 +121,18 @@ impl CodebaseAnalyzer {
             ))
         })?;
 
-        // Try to read as bytes first to check if it's valid UTF-8
         let bytes = fs::read(file_path).map_err(|e| {
             ContextError::AnalysisFailed(format!("Failed to read file {:?}: {}", file_path, e))
         })?;
 
-        // Check if the file contains valid UTF-8
+        if is_binary(&bytes) {
+            return Ok(None);
+        }
+
+        // Text in other encodings is still worth analyzing
         let content = match String::from_utf8(bytes) {
             Ok(content) => content,
-            Err(_) => {
-                // Skip binary files silently instead of generating warnings
-                return Err(ContextError::AnalysisFailed(
-                    "File contains non-UTF-8 content".to_string(),
-                ));
-            }
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
         };
 
         let language = self.detect_language(file_path, &content);
@@ -164,12 +148,15 @@ impl CodebaseAnalyzer {
         let (imports, exports) = self.extract_imports_exports(&content, &language);
 
         // Extract symbols (basic implementation)
-        let symbols = self.extract_symbols(&content, &language);
+        let mut symbols = self.extract_symbols(&content, &language);
+        for symbol in &mut symbols {
+            symbol.file_path = file_path.to_path_buf();
+        }
 
         // Determine relationships based on imports/exports and file analysis
         let relationships = self.detect_relationships(&content, &language, file_path, root_path, &imports);
 
-        Ok(FileContext {
+        Ok(Some(FileContext {
             path: file_path.to_path_buf(),
             relative_path,
             language,
@@ -183,145 +170,8 @@ impl CodebaseAnalyzer {
             imports,
             exports,
             relationships,
-        })
-    }
-
-    /// Check if a file should be included based on patterns
-    fn should_include_file(&self, file_path: &Path, config: &AnalysisConfig) -> bool {
-        let path_str = file_path.to_string_lossy();
-
-        // Check exclude patterns first
-        for pattern in &config.exclude_patterns {
-            if self.matches_glob_pattern(&path_str, pattern) {
-                return false;
-            }
-        }
-
-        // Additional hardcoded exclusions for performance
-        if self.is_build_or_binary_file(file_path) {
-            return false;
-        }
-
-        // Check include patterns - if any match, include the file
-        if config.include_patterns.is_empty() {
-            // If no include patterns specified, include by default (after exclude checks)
-            return true;
-        }
-
-        for pattern in &config.include_patterns {
-            if self.matches_glob_pattern(&path_str, pattern) {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    /// Check if a file is a build artifact or binary file that should be excluded
-    fn is_build_or_binary_file(&self, file_path: &Path) -> bool {
-        let path_str = file_path.to_string_lossy();
-        let path_lower = path_str.to_lowercase();
-
-        // Exclude common build directories
-        if path_str.contains("/target/")
-            || path_str.contains("\\target\\")
-            || path_str.contains("/build/")
-            || path_str.contains("\\build\\")
-            || path_str.contains("/dist/")
-            || path_str.contains("\\dist\\")
-            || path_str.contains("/out/")
-            || path_str.contains("\\out\\")
-            || path_str.contains("/bin/")
-            || path_str.contains("\\bin\\")
-            || path_str.contains("/.git/")
-            || path_str.contains("\\.git\\")
-            || path_str.contains("/node_modules/")
-            || path_str.contains("\\node_modules\\")
-            || path_str.contains("/__pycache__/")
-            || path_str.contains("\\__pycache__\\")
-        {
-            return true;
-        }
-
-        // Exclude binary file extensions and other non-text files
-        if let Some(extension) = file_path.extension() {
-            let ext = extension.to_string_lossy().to_lowercase();
-            match ext.as_str() {
-                // Binary executables
-                "exe" | "dll" | "so" | "dylib" | "a" | "lib" | "o" | "obj" |
-                // Archives
-                "zip" | "tar" | "gz" | "bz2" | "xz" | "7z" | "rar" |
-                // Images
-                "png" | "jpg" | "jpeg" | "gif" | "bmp" | "ico" | "svg" |
-                // Videos
-                "mp4" | "avi" | "mov" | "wmv" | "flv" | "webm" |
-                // Audio
-                "mp3" | "wav" | "flac" | "aac" | "ogg" |
-                // Documents
-                "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" |
-                // Rust-specific build artifacts
-                "rlib" | "rmeta" | "d" |
-                // Lock files that are typically large/binary
-                "lock" if path_lower.contains("cargo.lock") => false, // Allow Cargo.lock
-                "lock" => true,
-                _ => false
-            }
-        } else {
-            // Files without extensions - check if they're common binary files
-            if let Some(filename) = file_path.file_name() {
-                let name = filename.to_string_lossy().to_lowercase();
-                // Check for known binary files without extensions
-                if name.starts_with("lib") && !name.contains(".") {
-                    true // Unix libraries
-                } else if name.len() > 50 {
-                    true // Very long names are often build artifacts
-                } else {
-                    // Allow common text files without extensions
-                    !matches!(
-                        name.as_str(),
-                        "makefile"
-                            | "dockerfile"
-                            | "vagrantfile"
-                            | "readme"
-                            | "license"
-                            | "changelog"
-                    )
-                }
-            } else {
-                false
-            }
-        }
-    }
-
-    /// Simple glob pattern matching
-    fn matches_glob_pattern(&self, path: &str, pattern: &str) -> bool {
-        // Simple implementation - in production, use a proper glob library
-        if pattern == "**/*" {
-            return true;
-        }
-
-        if pattern.starts_with("**/") {
-            let suffix = pattern.strip_prefix("**/").unwrap_or(pattern);
-            // For patterns like **/*.rs, check if path ends with .rs
-            if suffix.starts_with("*") {
-                let extension = suffix.strip_prefix("*").unwrap_or(suffix);
-                return path.ends_with(extension);
-            }
-            return path.contains(suffix);
-        }
-
-        if pattern.ends_with("/**") {
-            let prefix = pattern.strip_suffix("/**").unwrap_or(pattern);
-            return path.starts_with(prefix);
-        }
-
-        // Handle simple extension patterns like *.rs
-        if pattern.starts_with("*") && !pattern.contains("/") {
-            let extension = pattern.strip_prefix("*").unwrap_or(pattern);
-            return path.ends_with(extension);
-        }
-
-        path.contains(pattern)
+            history: None,
+        }))
     }
 
     /// Detect programming language from file extension and content
@@ -1081,77 +931,4 @@ impl CodebaseAnalyzer {
             false
         }
     }
-
-    /// Parse Cargo.toml dependencies
-    fn parse_cargo_dependencies(&self, content: &str) -> Result<Vec<Dependency>, ContextError> {
-        // Simple parsing - in production, use proper TOML parser
-        let mut dependencies = Vec::new();
-
-        let lines: Vec<&str> = content.lines().collect();
-        let mut in_dependencies = false;
-
-        for line in lines {
-            let trimmed = line.trim();
-
-            if trimmed == "[dependencies]" {
-                in_dependencies = true;
-                continue;
-            }
-
-            if trimmed.starts_with('[') && trimmed != "[dependencies]" {
-                in_dependencies = false;
-                continue;
-            }
-
-            if in_dependencies && trimmed.contains('=') {
-                if let Some((name, _version)) = trimmed.split_once('=') {
-                    dependencies.push(Dependency {
-                        name: name.trim().to_string(),
-                        version: None, // TODO: Parse version
-                        dependency_type: DependencyType::Runtime,
-                        source: DependencySource::PackageManager("cargo".to_string()),
-                    });
-                }
-            }
-        }
-
-        Ok(dependencies)
-    }
-
-    /// Parse package.json dependencies
-    fn parse_npm_dependencies(&self, _content: &str) -> Result<Vec<Dependency>, ContextError> {
-        // TODO: Implement JSON parsing for package.json
-        Ok(Vec::new())
-    }
-
-    /// Parse requirements.txt dependencies
-    fn parse_python_dependencies(&self, content: &str) -> Result<Vec<Dependency>, ContextError> {
-        let mut dependencies = Vec::new();
-
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if !trimmed.is_empty() && !trimmed.starts_with('#') {
-                let name = if let Some((name, _version)) = trimmed.split_once("==") {
-                    name.trim().to_string()
-                } else {
-                    trimmed.to_string()
-                };
-
-                dependencies.push(Dependency {
-                    name,
-                    version: None, // TODO: Parse version
-                    dependency_type: DependencyType::Runtime,
-                    source: DependencySource::PackageManager("pip".to_string()),
-                });
-            }
-        }
-
-        Ok(dependencies)
-    }
-
-    /// Parse go.mod dependencies
-    fn parse_go_dependencies(&self, _content: &str) -> Result<Vec<Dependency>, ContextError> {
-        // TODO: Implement go.mod parsing
-        Ok(Vec::new())
-    }
 }
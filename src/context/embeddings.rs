@@ -6,6 +6,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::syntax_chunker::SyntaxChunker;
+use super::vector_index::{self, VectorSearchBackend};
 
 /// Configuration for embedding generation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,7 +27,7 @@ impl Default for EmbeddingConfig {
     fn default() -> Self {
         Self {
             model_name: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
-            max_chunk_size: 512,
+            max_chunk_size: 1500,
             chunk_overlap: 50,
             similarity_threshold: 0.7,
             max_results: 20,
@@ -42,6 +47,9 @@ pub struct CodeChunk {
     pub language: String,
     pub chunk_type: ChunkType,
     pub symbols: Vec<String>, // Function/class names etc. in this chunk
+    /// Enclosing scope, e.g. `crate::ai::routing::ModelRouter::select_model`
+    #[serde(default)]
+    pub scope_path: Option<String>,
 }
 
 /// Type of code chunk for better embedding context
@@ -92,6 +100,9 @@ pub struct VectorStore {
     config: EmbeddingConfig,
     #[serde(skip)]
     embedding_cache: HashMap<String, Vec<f32>>,
+    /// Search index built from the current embeddings; exact search is used when absent
+    #[serde(skip)]
+    search_backend: Option<Arc<dyn VectorSearchBackend>>,
 }
 
 impl VectorStore {
@@ -101,6 +112,7 @@ impl VectorStore {
             chunks: HashMap::new(),
             config,
             embedding_cache: HashMap::new(),
+            search_backend: None,
         }
     }
 
@@ -108,6 +120,29 @@ impl VectorStore {
     pub fn add_embedding(&mut self, chunk: CodeChunk, embedding: CodeEmbedding) {
         self.chunks.insert(chunk.id.clone(), chunk);
         self.embeddings.insert(embedding.chunk_id.clone(), embedding);
+        self.search_backend = None;
+    }
+
+    /// Build a search index over the current embeddings.
+    ///
+    /// Uses the FAISS backend when the `faiss-vector-db` feature is enabled and
+    /// exact search otherwise. Any later modification drops the index again.
+    pub fn build_search_backend(&mut self) {
+        let dimension = self
+            .embeddings
+            .values()
+            .next()
+            .map(|e| e.vector.len())
+            .unwrap_or(0);
+        let entries = self
+            .embeddings
+            .iter()
+            .map(|(id, embedding)| (id.clone(), embedding.vector.clone()))
+            .collect();
+
+        let backend = vector_index::build_backend(dimension, entries);
+        tracing::debug!("Built {} vector search backend with {} vectors", backend.name(), backend.len());
+        self.search_backend = Some(backend);
     }
 
     /// Remove embeddings for a specific file
@@ -122,11 +157,30 @@ impl VectorStore {
             self.chunks.remove(&chunk_id);
             self.embeddings.remove(&chunk_id);
         }
+        self.search_backend = None;
     }
 
     /// Find similar chunks using cosine similarity
     pub fn find_similar(&self, query_embedding: &[f32], max_results: Option<usize>) -> Vec<SimilarityResult> {
         let max_results = max_results.unwrap_or(self.config.max_results);
+
+        if let Some(backend) = &self.search_backend {
+            return backend
+                .search(query_embedding, max_results)
+                .into_iter()
+                .filter(|(_, similarity)| *similarity >= self.config.similarity_threshold)
+                .filter_map(|(chunk_id, similarity)| {
+                    let chunk = self.chunks.get(&chunk_id)?;
+                    let embedding = self.embeddings.get(&chunk_id)?;
+                    Some(SimilarityResult {
+                        chunk: chunk.clone(),
+                        embedding: embedding.clone(),
+                        similarity_score: similarity,
+                    })
+                })
+                .collect();
+        }
+
         let mut results = Vec::new();
 
         for (chunk_id, embedding) in &self.embeddings {
@@ -179,6 +233,11 @@ pub trait EmbeddingProvider: Send + Sync {
 
     /// Get the dimension of embeddings produced by this provider
     fn embedding_dimension(&self) -> usize;
+
+    /// Identifier of the model producing the vectors, used to invalidate persisted indexes
+    fn model_name(&self) -> String {
+        String::new()
+    }
 }
 
 /// Local embedding provider using Candle
@@ -244,6 +303,282 @@ impl EmbeddingProvider for LocalEmbeddingProvider {
     fn embedding_dimension(&self) -> usize {
         self.dimension
     }
+
+    fn model_name(&self) -> String {
+        format!("local:{}", self.model_name)
+    }
+}
+
+/// Ollama embedding provider using the local `/api/embed` endpoint
+///
+/// Requests are batched, results are cached by content hash and the vector
+/// dimension is detected from the first response. Nothing leaves the machine
+/// running Ollama.
+pub struct OllamaEmbeddingProvider {
+    client: reqwest::Client,
+    endpoint: String,
+    model: String,
+    batch_size: usize,
+    max_retries: usize,
+    dimension: AtomicUsize,
+    cache: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+impl OllamaEmbeddingProvider {
+    pub fn new(
+        endpoint: String,
+        model: String,
+        timeout: std::time::Duration,
+        max_retries: usize,
+        batch_size: usize,
+    ) -> Result<Self, EmbeddingError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| EmbeddingError::ConfigError(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            model,
+            batch_size: batch_size.max(1),
+            max_retries,
+            dimension: AtomicUsize::new(0),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Create a provider reusing the Ollama connection settings
+    pub fn from_config(
+        ollama: &crate::config::OllamaConfig,
+        embeddings: &crate::config::EmbeddingProviderConfig,
+    ) -> Result<Self, EmbeddingError> {
+        let provider = Self::new(
+            ollama.endpoint.clone(),
+            embeddings
+                .model
+                .clone()
+                .unwrap_or_else(|| "nomic-embed-text".to_string()),
+            std::time::Duration::from_secs(ollama.timeout_seconds),
+            ollama.max_retries,
+            embeddings.batch_size,
+        )?;
+
+        if let Some(dimension) = embeddings.dimension {
+            provider.dimension.store(dimension, Ordering::Relaxed);
+        }
+
+        Ok(provider)
+    }
+
+    /// Embed a probe text to learn the model's vector dimension
+    pub async fn detect_dimension(&self) -> Result<usize, EmbeddingError> {
+        let probe = vec!["dimension probe".to_string()];
+        let vectors = self.request_embeddings(&probe).await?;
+        Ok(vectors.first().map(|v| v.len()).unwrap_or(0))
+    }
+
+    /// Number of cached embeddings
+    pub fn cache_size(&self) -> usize {
+        self.cache.lock().map(|cache| cache.len()).unwrap_or(0)
+    }
+
+    fn content_hash(text: &str) -> String {
+        format!("{:x}", md5::compute(text.as_bytes()))
+    }
+
+    async fn request_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let url = format!("{}/api/embed", self.endpoint);
+        let request = OllamaEmbedRequest {
+            model: &self.model,
+            input: texts,
+        };
+
+        let mut attempt = 0;
+        let response = loop {
+            let result = self.client.post(&url).json(&request).send().await;
+            match result {
+                Ok(response) if response.status().is_success() => break response,
+                Ok(response) if response.status().is_client_error() => {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(EmbeddingError::ApiError(format!(
+                        "Ollama returned {} for model '{}': {}",
+                        status, self.model, body
+                    )));
+                }
+                Ok(response) if attempt >= self.max_retries => {
+                    return Err(EmbeddingError::ApiError(format!(
+                        "Ollama returned {} after {} attempts",
+                        response.status(),
+                        attempt + 1
+                    )));
+                }
+                Err(e) if attempt >= self.max_retries => {
+                    return Err(EmbeddingError::ApiError(format!(
+                        "Failed to reach Ollama at {}: {}",
+                        self.endpoint, e
+                    )));
+                }
+                _ => {
+                    attempt += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(250 * (1 << attempt.min(4)))).await;
+                }
+            }
+        };
+
+        let parsed: OllamaEmbedResponse = response
+            .json()
+            .await
+            .map_err(|e| EmbeddingError::EmbeddingGenerationError(format!("Invalid embed response: {}", e)))?;
+
+        if parsed.embeddings.len() != texts.len() {
+            return Err(EmbeddingError::EmbeddingGenerationError(format!(
+                "Expected {} embeddings, received {}",
+                texts.len(),
+                parsed.embeddings.len()
+            )));
+        }
+
+        if let Some(first) = parsed.embeddings.first() {
+            let detected = first.len();
+            let known = self.dimension.load(Ordering::Relaxed);
+            if known == 0 {
+                self.dimension.store(detected, Ordering::Relaxed);
+            } else if known != detected {
+                return Err(EmbeddingError::EmbeddingGenerationError(format!(
+                    "Model '{}' returned {}-dimensional vectors, expected {}",
+                    self.model, detected, known
+                )));
+            }
+        }
+
+        Ok(parsed.embeddings)
+    }
+}
+
+#[async_trait::async_trait]
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    async fn embed_text(&self, text: &str) -> Result<Vec<f32>, EmbeddingError> {
+        self.embed_batch(&[text.to_string()]).await?
+            .pop()
+            .ok_or_else(|| EmbeddingError::EmbeddingGenerationError("Empty embedding response".to_string()))
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let hashes: Vec<String> = texts.iter().map(|t| Self::content_hash(t)).collect();
+        let mut results: Vec<Option<Vec<f32>>> = {
+            let cache = self.cache.lock().map_err(|_| {
+                EmbeddingError::EmbeddingGenerationError("Embedding cache poisoned".to_string())
+            })?;
+            hashes.iter().map(|hash| cache.get(hash).cloned()).collect()
+        };
+
+        // Only send texts that are not cached yet, deduplicated by hash
+        let mut pending: Vec<usize> = Vec::new();
+        for (i, result) in results.iter().enumerate() {
+            if result.is_none() && !pending.iter().any(|&j| hashes[j] == hashes[i]) {
+                pending.push(i);
+            }
+        }
+
+        for batch in pending.chunks(self.batch_size) {
+            let inputs: Vec<String> = batch.iter().map(|&i| texts[i].clone()).collect();
+            let vectors = self.request_embeddings(&inputs).await?;
+
+            let mut cache = self.cache.lock().map_err(|_| {
+                EmbeddingError::EmbeddingGenerationError("Embedding cache poisoned".to_string())
+            })?;
+            for (&i, vector) in batch.iter().zip(vectors) {
+                cache.insert(hashes[i].clone(), vector);
+            }
+        }
+
+        if !pending.is_empty() {
+            let cache = self.cache.lock().map_err(|_| {
+                EmbeddingError::EmbeddingGenerationError("Embedding cache poisoned".to_string())
+            })?;
+            for (i, result) in results.iter_mut().enumerate() {
+                if result.is_none() {
+                    *result = cache.get(&hashes[i]).cloned();
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|r| r.ok_or_else(|| EmbeddingError::EmbeddingGenerationError("Missing embedding".to_string())))
+            .collect()
+    }
+
+    fn embedding_dimension(&self) -> usize {
+        match self.dimension.load(Ordering::Relaxed) {
+            0 => match self.model.split(':').next().unwrap_or_default() {
+                "nomic-embed-text" => 768,
+                "mxbai-embed-large" => 1024,
+                "all-minilm" => 384,
+                _ => 0, // Unknown until the first response
+            },
+            dimension => dimension,
+        }
+    }
+
+    fn model_name(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+}
+
+/// Create the embedding provider selected in configuration
+///
+/// Returns `None` when embeddings are disabled.
+pub fn create_embedding_provider(
+    config: &crate::config::AIModelConfig,
+) -> Result<Option<Box<dyn EmbeddingProvider>>, EmbeddingError> {
+    let settings = &config.embeddings;
+    match settings.provider.as_str() {
+        "none" => Ok(None),
+        #[cfg(feature = "local-embeddings")]
+        "local" => Ok(Some(Box::new(LocalEmbeddingProvider::new(
+            settings
+                .model
+                .clone()
+                .unwrap_or_else(|| EmbeddingConfig::default().model_name),
+        )?))),
+        "ollama" => Ok(Some(Box::new(OllamaEmbeddingProvider::from_config(
+            &config.ollama,
+            settings,
+        )?))),
+        #[cfg(feature = "openai-embeddings")]
+        "openai" => {
+            let api_key = config
+                .openai
+                .as_ref()
+                .and_then(|openai| openai.api_key.clone())
+                .ok_or_else(|| EmbeddingError::ConfigError("OpenAI API key not configured".to_string()))?;
+            Ok(Some(Box::new(OpenAIEmbeddingProvider::new(
+                api_key,
+                settings
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| "text-embedding-3-small".to_string()),
+            )?)))
+        }
+        other => Err(EmbeddingError::ConfigError(format!(
+            "Embedding provider '{}' is not available in this build",
+            other
+        ))),
+    }
 }
 
 /// OpenAI embedding provider
@@ -312,6 +647,10 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
     fn embedding_dimension(&self) -> usize {
         self.dimension
     }
+
+    fn model_name(&self) -> String {
+        format!("openai:{}", self.model)
+    }
 }
 
 /// Error types for embedding operations
@@ -344,8 +683,24 @@ impl CodeChunker {
         Self { config }
     }
 
-    /// Split file content into chunks suitable for embedding
+    /// Split file content into chunks suitable for embedding.
+    ///
+    /// Languages with a tree-sitter grammar are chunked along syntactic items;
+    /// everything else, and files that fail to parse, are chunked by lines.
     pub fn chunk_file(&self, file_path: &PathBuf, content: &str, language: &str) -> Vec<CodeChunk> {
+        if SyntaxChunker::supports(language) {
+            if let Some(chunks) = SyntaxChunker::new(self.config.max_chunk_size).chunk(file_path, content, language) {
+                if !chunks.is_empty() {
+                    return chunks;
+                }
+            }
+        }
+
+        self.chunk_by_lines(file_path, content, language)
+    }
+
+    /// Split file content into fixed-size line windows with overlap
+    fn chunk_by_lines(&self, file_path: &PathBuf, content: &str, language: &str) -> Vec<CodeChunk> {
         let mut chunks = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
         
@@ -367,6 +722,7 @@ impl CodeChunker {
                     language: language.to_string(),
                     chunk_type: self.detect_chunk_type(&current_chunk),
                     symbols: self.extract_symbols(&current_chunk, language),
+                    scope_path: None,
                 });
 
                 // Start new chunk with overlap
@@ -394,6 +750,7 @@ impl CodeChunker {
                 language: language.to_string(),
                 chunk_type: self.detect_chunk_type(&current_chunk),
                 symbols: self.extract_symbols(&current_chunk, language),
+                scope_path: None,
             });
         }
 
@@ -499,6 +856,109 @@ mod tests {
         assert!(chunks[0].symbols.contains(&"main".to_string()) || chunks[0].symbols.contains(&"other_function".to_string()));
     }
 
+    #[test]
+    fn test_create_embedding_provider_from_config() {
+        let mut config = crate::config::AIModelConfig::default();
+        assert!(create_embedding_provider(&config).unwrap().is_none());
+
+        config.embeddings.provider = "ollama".to_string();
+        config.embeddings.model = Some("nomic-embed-text:latest".to_string());
+        let provider = create_embedding_provider(&config).unwrap().unwrap();
+        assert_eq!(provider.embedding_dimension(), 768);
+
+        config.embeddings.provider = "hosted".to_string();
+        assert!(create_embedding_provider(&config).is_err());
+    }
+
+    #[test]
+    fn test_embedding_config_field_defaults() {
+        let settings: crate::config::EmbeddingProviderConfig =
+            toml::from_str("provider = \"ollama\"").unwrap();
+        assert_eq!(settings.provider, "ollama");
+        assert_eq!(settings.batch_size, 32);
+        assert!(settings.model.is_none());
+    }
+
+    /// Stub of Ollama's `/api/embed` answering with `dimension`-wide vectors
+    /// and recording the inputs of every request
+    async fn stub_ollama(dimension: usize) -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let app = axum::Router::new().route(
+            "/api/embed",
+            axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+                let recorded = recorded.clone();
+                async move {
+                    let inputs: Vec<String> = serde_json::from_value(body["input"].clone()).unwrap();
+                    let embeddings: Vec<Vec<f32>> = inputs
+                        .iter()
+                        .map(|input| {
+                            let mut vector = vec![0.0; dimension];
+                            vector[0] = input.len() as f32;
+                            vector
+                        })
+                        .collect();
+                    recorded.lock().unwrap().push(inputs);
+                    axum::Json(serde_json::json!({ "embeddings": embeddings }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (endpoint, requests)
+    }
+
+    fn ollama(endpoint: &str, batch_size: usize) -> OllamaEmbeddingProvider {
+        let timeout = std::time::Duration::from_secs(5);
+        OllamaEmbeddingProvider::new(endpoint.to_string(), "custom-embed".to_string(), timeout, 0, batch_size).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ollama_batches_and_caches_by_content() {
+        let (endpoint, requests) = stub_ollama(3).await;
+        let provider = ollama(&endpoint, 2);
+
+        let texts: Vec<String> = ["a", "bb", "ccc", "a", "dddd"].iter().map(|t| t.to_string()).collect();
+        let vectors = provider.embed_batch(&texts).await.unwrap();
+
+        // Uncached texts are sent once each, `batch_size` at a time
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![vec!["a".to_string(), "bb".to_string()], vec!["ccc".to_string(), "dddd".to_string()]]
+        );
+        assert_eq!(vectors.len(), 5);
+        assert_eq!(vectors[1], vec![2.0, 0.0, 0.0]);
+        assert_eq!(vectors[3], vectors[0]);
+        assert_eq!(provider.cache_size(), 4);
+
+        // Cached content is answered without another request
+        assert_eq!(provider.embed_text("ccc").await.unwrap(), vec![3.0, 0.0, 0.0]);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        provider.embed_text("eeeee").await.unwrap();
+        assert_eq!(requests.lock().unwrap().last().unwrap(), &vec!["eeeee".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_ollama_detects_dimension_from_first_response() {
+        let (endpoint, _requests) = stub_ollama(5).await;
+
+        let provider = ollama(&endpoint, 8);
+        assert_eq!(provider.embedding_dimension(), 0);
+        provider.embed_text("fn main() {}").await.unwrap();
+        assert_eq!(provider.embedding_dimension(), 5);
+        assert_eq!(provider.detect_dimension().await.unwrap(), 5);
+
+        // A configured dimension that the model contradicts is an error
+        let configured = ollama(&endpoint, 8);
+        configured.dimension.store(768, Ordering::Relaxed);
+        assert!(matches!(
+            configured.embed_text("fn main() {}").await,
+            Err(EmbeddingError::EmbeddingGenerationError(message)) if message.contains("expected 768")
+        ));
+    }
+
     #[tokio::test]
     async fn test_vector_store() {
         let config = EmbeddingConfig::default();
@@ -513,6 +973,7 @@ mod tests {
             language: "rust".to_string(),
             chunk_type: ChunkType::Function,
             symbols: vec!["main".to_string()],
+            scope_path: None,
         };
         
         let embedding = CodeEmbedding {
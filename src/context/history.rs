@@ -0,0 +1,556 @@
+//! Change history signals from git
+//!
+//! `HistoryAnalyzer` walks the recent commits of the repository containing a
+//! codebase and derives per-file signals that a snapshot of the tree cannot
+//! provide: churn, recent authors, the age of the last change, a hotspot score
+//! and co-change coupling (files that usually change in the same commit).
+
+use git2::{Delta, DiffOptions, Patch, Repository, Sort, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{ContextError, FileContext};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Limits and thresholds for history analysis
+#[derive(Debug, Clone)]
+pub struct HistoryConfig {
+    /// Number of most recent commits walked
+    pub max_commits: usize,
+    /// Commits touching more files than this are ignored for coupling,
+    /// since mass renames and reformatting couple everything
+    pub max_files_per_commit: usize,
+    /// Authors of commits newer than this count as recent
+    pub recent_window_days: u64,
+    /// A commit this many days old adds half the churn weight of one made today
+    pub churn_half_life_days: f64,
+    /// Minimum number of shared commits for a co-change
+    pub min_shared_commits: usize,
+    /// Minimum share of a file's commits that also touched the other file
+    pub min_coupling: f32,
+    /// Co-changed files kept per file
+    pub max_co_changes: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_commits: 1000,
+            max_files_per_commit: 30,
+            recent_window_days: 90,
+            churn_half_life_days: 90.0,
+            min_shared_commits: 2,
+            min_coupling: 0.3,
+            max_co_changes: 5,
+        }
+    }
+}
+
+/// History signals for a single file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileHistory {
+    /// Number of commits that touched the file
+    pub commits: usize,
+    pub lines_added: usize,
+    pub lines_deleted: usize,
+    /// Authors within the recent window, most active first
+    pub recent_authors: Vec<String>,
+    /// Unix timestamp of the last commit that touched the file
+    pub last_commit_time: i64,
+    /// Days since the last commit that touched the file
+    pub age_days: u64,
+    /// Recency-weighted churn relative to the busiest file, from 0.0 to 1.0
+    pub hotspot_score: f32,
+    /// Files that usually change together with this one, strongest first
+    pub co_changes: Vec<CoChange>,
+}
+
+/// A file that tends to change in the same commits as another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoChange {
+    /// Path relative to the analyzed root
+    pub path: PathBuf,
+    pub shared_commits: usize,
+    /// Share of the file's commits that also touched `path`
+    pub confidence: f32,
+}
+
+/// A frequently and recently changed file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hotspot {
+    pub path: PathBuf,
+    pub score: f32,
+    pub commits: usize,
+    pub recent_authors: Vec<String>,
+    pub age_days: u64,
+}
+
+/// A file that probably needs to change along with a modified one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoChangeSuggestion {
+    pub changed: PathBuf,
+    pub suggested: PathBuf,
+    pub shared_commits: usize,
+    pub confidence: f32,
+}
+
+/// History signals for the files below an analyzed root
+#[derive(Debug, Clone, Default)]
+pub struct HistoryIndex {
+    pub commits_analyzed: usize,
+    /// Keyed by path relative to the analyzed root
+    pub files: HashMap<PathBuf, FileHistory>,
+}
+
+impl HistoryIndex {
+    pub fn get(&self, relative_path: &Path) -> Option<&FileHistory> {
+        self.files.get(relative_path)
+    }
+
+    /// Attach history to analyzed files by their relative path
+    pub fn attach(&self, files: &mut [FileContext]) {
+        for file in files {
+            file.history = self.files.get(&file.relative_path).cloned();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &FileHistory)> {
+        self.files
+            .iter()
+            .map(|(path, history)| (path.as_path(), history))
+    }
+}
+
+/// Walks commit history to compute `FileHistory` signals
+#[derive(Debug, Clone, Default)]
+pub struct HistoryAnalyzer {
+    config: HistoryConfig,
+}
+
+/// Per-file accumulator while walking commits
+#[derive(Default)]
+struct FileStats {
+    commits: usize,
+    lines_added: usize,
+    lines_deleted: usize,
+    weighted_churn: f64,
+    last_commit_time: i64,
+    recent_authors: HashMap<String, usize>,
+}
+
+impl HistoryAnalyzer {
+    pub fn new(config: HistoryConfig) -> Self {
+        Self { config }
+    }
+
+    /// Analyze the history of the repository containing `root`; paths in the
+    /// result are relative to `root`
+    pub fn analyze(&self, root: &Path) -> Result<HistoryIndex, ContextError> {
+        let repo = Repository::discover(root).map_err(history_error)?;
+        let prefix = repository_prefix(&repo, root)?;
+        let now = now_seconds();
+
+        let mut revwalk = repo.revwalk().map_err(history_error)?;
+        revwalk.set_sorting(Sort::TIME).map_err(history_error)?;
+        if revwalk.push_head().is_err() {
+            // No commits yet
+            return Ok(HistoryIndex::default());
+        }
+
+        let mut stats: HashMap<PathBuf, FileStats> = HashMap::new();
+        let mut pair_counts: HashMap<(PathBuf, PathBuf), usize> = HashMap::new();
+        let mut commits_analyzed = 0;
+
+        for oid in revwalk.take(self.config.max_commits) {
+            let commit = repo
+                .find_commit(oid.map_err(history_error)?)
+                .map_err(history_error)?;
+            // Merges repeat changes already counted on their branches
+            if commit.parent_count() > 1 {
+                continue;
+            }
+
+            let tree = commit.tree().map_err(history_error)?;
+            let parent_tree = match commit.parent_count() {
+                0 => None,
+                _ => Some(
+                    commit
+                        .parent(0)
+                        .and_then(|parent| parent.tree())
+                        .map_err(history_error)?,
+                ),
+            };
+
+            let mut options = DiffOptions::new();
+            options.context_lines(0);
+            if !prefix.as_os_str().is_empty() {
+                options.pathspec(prefix.to_string_lossy().replace('\\', "/"));
+            }
+            let diff = repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))
+                .map_err(history_error)?;
+
+            let time = commit.time().seconds();
+            let age_days = (now - time).max(0) as f64 / SECONDS_PER_DAY as f64;
+            let weight = 0.5_f64.powf(age_days / self.config.churn_half_life_days);
+            let author = commit
+                .author()
+                .name()
+                .map(str::to_string)
+                .unwrap_or_else(|| "unknown".to_string());
+            let is_recent = age_days <= self.config.recent_window_days as f64;
+
+            let mut touched = Vec::new();
+            for (idx, delta) in diff.deltas().enumerate() {
+                if delta.status() == Delta::Deleted {
+                    continue;
+                }
+                let Some(path) = delta.new_file().path() else {
+                    continue;
+                };
+                let Ok(relative) = path.strip_prefix(&prefix) else {
+                    continue;
+                };
+                let (added, deleted) = match Patch::from_diff(&diff, idx) {
+                    Ok(Some(patch)) => patch
+                        .line_stats()
+                        .map(|(_, added, deleted)| (added, deleted))
+                        .unwrap_or((0, 0)),
+                    _ => (0, 0),
+                };
+
+                let entry = stats.entry(relative.to_path_buf()).or_default();
+                entry.commits += 1;
+                entry.lines_added += added;
+                entry.lines_deleted += deleted;
+                entry.weighted_churn += weight;
+                entry.last_commit_time = entry.last_commit_time.max(time);
+                if is_recent {
+                    *entry.recent_authors.entry(author.clone()).or_default() += 1;
+                }
+                touched.push(relative.to_path_buf());
+            }
+
+            if touched.len() <= self.config.max_files_per_commit {
+                touched.sort();
+                for (i, first) in touched.iter().enumerate() {
+                    for second in &touched[i + 1..] {
+                        *pair_counts
+                            .entry((first.clone(), second.clone()))
+                            .or_default() += 1;
+                    }
+                }
+            }
+            commits_analyzed += 1;
+        }
+
+        Ok(self.build_index(stats, pair_counts, commits_analyzed, now))
+    }
+
+    fn build_index(
+        &self,
+        stats: HashMap<PathBuf, FileStats>,
+        pair_counts: HashMap<(PathBuf, PathBuf), usize>,
+        commits_analyzed: usize,
+        now: i64,
+    ) -> HistoryIndex {
+        let mut co_changes: HashMap<PathBuf, Vec<CoChange>> = HashMap::new();
+        for ((first, second), shared) in pair_counts {
+            if shared < self.config.min_shared_commits {
+                continue;
+            }
+            for (from, to) in [(&first, &second), (&second, &first)] {
+                let commits = stats.get(from).map(|s| s.commits).unwrap_or(0);
+                if commits == 0 {
+                    continue;
+                }
+                let confidence = shared as f32 / commits as f32;
+                if confidence >= self.config.min_coupling {
+                    co_changes.entry(from.clone()).or_default().push(CoChange {
+                        path: to.clone(),
+                        shared_commits: shared,
+                        confidence,
+                    });
+                }
+            }
+        }
+
+        let max_churn = stats
+            .values()
+            .map(|s| s.weighted_churn)
+            .fold(0.0_f64, f64::max);
+
+        let files = stats
+            .into_iter()
+            .map(|(path, stats)| {
+                let mut recent_authors: Vec<(String, usize)> =
+                    stats.recent_authors.into_iter().collect();
+                recent_authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+                let mut file_co_changes = co_changes.remove(&path).unwrap_or_default();
+                file_co_changes.sort_by(|a, b| {
+                    b.confidence
+                        .partial_cmp(&a.confidence)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| b.shared_commits.cmp(&a.shared_commits))
+                        .then_with(|| a.path.cmp(&b.path))
+                });
+                file_co_changes.truncate(self.config.max_co_changes);
+
+                let history = FileHistory {
+                    commits: stats.commits,
+                    lines_added: stats.lines_added,
+                    lines_deleted: stats.lines_deleted,
+                    recent_authors: recent_authors.into_iter().map(|(name, _)| name).collect(),
+                    last_commit_time: stats.last_commit_time,
+                    age_days: ((now - stats.last_commit_time).max(0) / SECONDS_PER_DAY) as u64,
+                    hotspot_score: if max_churn > 0.0 {
+                        (stats.weighted_churn / max_churn) as f32
+                    } else {
+                        0.0
+                    },
+                    co_changes: file_co_changes,
+                };
+                (path, history)
+            })
+            .collect();
+
+        HistoryIndex {
+            commits_analyzed,
+            files,
+        }
+    }
+}
+
+/// Working directory of the repository containing `path`
+pub fn repository_root(path: &Path) -> Option<PathBuf> {
+    let repo = Repository::discover(path).ok()?;
+    repo.workdir().map(Path::to_path_buf)
+}
+
+/// Modified, staged and untracked files below `root`, relative to `root`
+pub fn uncommitted_changes(root: &Path) -> Result<Vec<PathBuf>, ContextError> {
+    let repo = Repository::discover(root).map_err(history_error)?;
+    let prefix = repository_prefix(&repo, root)?;
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options)).map_err(history_error)?;
+
+    let mut changed: Vec<PathBuf> = statuses
+        .iter()
+        .filter(|entry| !entry.status().is_wt_deleted() && !entry.status().is_index_deleted())
+        .filter_map(|entry| {
+            let path = PathBuf::from(entry.path()?);
+            path.strip_prefix(&prefix).ok().map(Path::to_path_buf)
+        })
+        .collect();
+    changed.sort();
+    Ok(changed)
+}
+
+/// The most significant hotspots, highest score first
+pub fn hotspots<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a FileHistory)>,
+    limit: usize,
+) -> Vec<Hotspot> {
+    let mut hotspots: Vec<Hotspot> = files
+        .into_iter()
+        .filter(|(_, history)| history.hotspot_score > 0.0)
+        .map(|(path, history)| Hotspot {
+            path: path.to_path_buf(),
+            score: history.hotspot_score,
+            commits: history.commits,
+            recent_authors: history.recent_authors.clone(),
+            age_days: history.age_days,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    hotspots.truncate(limit);
+    hotspots
+}
+
+/// Files that usually change with the `changed` ones but are not among them
+pub fn suggest_co_changes<'a>(
+    files: impl IntoIterator<Item = (&'a Path, &'a FileHistory)>,
+    changed: &[PathBuf],
+) -> Vec<CoChangeSuggestion> {
+    let mut suggestions: HashMap<PathBuf, CoChangeSuggestion> = HashMap::new();
+    for (path, history) in files {
+        if !changed.iter().any(|c| c == path) {
+            continue;
+        }
+        for co_change in &history.co_changes {
+            if changed.contains(&co_change.path) {
+                continue;
+            }
+            let is_stronger = suggestions
+                .get(&co_change.path)
+                .map_or(true, |existing| co_change.confidence > existing.confidence);
+            if is_stronger {
+                suggestions.insert(
+                    co_change.path.clone(),
+                    CoChangeSuggestion {
+                        changed: path.to_path_buf(),
+                        suggested: co_change.path.clone(),
+                        shared_commits: co_change.shared_commits,
+                        confidence: co_change.confidence,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut suggestions: Vec<CoChangeSuggestion> = suggestions.into_values().collect();
+    suggestions.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.suggested.cmp(&b.suggested))
+    });
+    suggestions
+}
+
+/// Path of `root` inside the repository working directory
+fn repository_prefix(repo: &Repository, root: &Path) -> Result<PathBuf, ContextError> {
+    let workdir = repo.workdir().ok_or_else(|| {
+        ContextError::RepositoryAnalysisFailed("Bare repositories have no working tree".to_string())
+    })?;
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    Ok(root
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .unwrap_or_default())
+}
+
+fn history_error(error: git2::Error) -> ContextError {
+    ContextError::RepositoryAnalysisFailed(error.message().to_string())
+}
+
+fn now_seconds() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{Commit, Signature, Time};
+    use std::fs;
+
+    fn commit(repo: &Repository, files: &[(&str, &str)], author: &str, days_ago: i64) {
+        let root = repo.workdir().unwrap().to_path_buf();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full_path = root.join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(full_path, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+        let time = Time::new(now_seconds() - days_ago * SECONDS_PER_DAY, 0);
+        let signature = Signature::new(author, &format!("{}@example.com", author), &time).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "change",
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    fn sample_repo() -> (tempfile::TempDir, Repository) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        commit(
+            &repo,
+            &[("src/a.rs", "a1\n"), ("src/b.rs", "b1\n")],
+            "alice",
+            40,
+        );
+        commit(
+            &repo,
+            &[("src/a.rs", "a2\n"), ("src/b.rs", "b2\n")],
+            "bob",
+            30,
+        );
+        commit(&repo, &[("src/a.rs", "a3\nmore\n")], "alice", 20);
+        commit(&repo, &[("docs/c.md", "c\n")], "carol", 400);
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_churn_authors_and_coupling() {
+        let (dir, _repo) = sample_repo();
+        let index = HistoryAnalyzer::default().analyze(dir.path()).unwrap();
+        assert_eq!(index.commits_analyzed, 4);
+
+        let a = index.get(Path::new("src/a.rs")).unwrap();
+        assert_eq!(a.commits, 3);
+        assert_eq!(a.lines_added, 4);
+        assert_eq!(a.recent_authors, vec!["alice", "bob"]);
+        assert_eq!(a.age_days, 20);
+        assert_eq!(a.hotspot_score, 1.0);
+        assert_eq!(a.co_changes[0].path, PathBuf::from("src/b.rs"));
+        assert!((a.co_changes[0].confidence - 2.0 / 3.0).abs() < 1e-6);
+
+        let b = index.get(Path::new("src/b.rs")).unwrap();
+        assert_eq!(b.co_changes[0].shared_commits, 2);
+        assert_eq!(b.co_changes[0].confidence, 1.0);
+
+        let c = index.get(Path::new("docs/c.md")).unwrap();
+        assert!(c.recent_authors.is_empty());
+        assert!(c.co_changes.is_empty());
+        assert!(c.hotspot_score < 0.1);
+
+        let top = hotspots(index.iter(), 2);
+        assert_eq!(top[0].path, PathBuf::from("src/a.rs"));
+        assert_eq!(top[1].path, PathBuf::from("src/b.rs"));
+
+        // Paths are relative to the analyzed subdirectory
+        let nested = HistoryAnalyzer::default()
+            .analyze(&dir.path().join("src"))
+            .unwrap();
+        assert!(nested.get(Path::new("a.rs")).is_some());
+        assert!(nested.get(Path::new("docs/c.md")).is_none());
+    }
+
+    #[test]
+    fn test_co_change_suggestions_for_uncommitted_changes() {
+        let (dir, _repo) = sample_repo();
+        let index = HistoryAnalyzer::default().analyze(dir.path()).unwrap();
+
+        fs::write(dir.path().join("src/b.rs"), "b3\n").unwrap();
+        let changed = uncommitted_changes(dir.path()).unwrap();
+        assert_eq!(changed, vec![PathBuf::from("src/b.rs")]);
+
+        let suggestions = suggest_co_changes(index.iter(), &changed);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].changed, PathBuf::from("src/b.rs"));
+        assert_eq!(suggestions[0].suggested, PathBuf::from("src/a.rs"));
+
+        let both = vec![PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")];
+        assert!(suggest_co_changes(index.iter(), &both).is_empty());
+    }
+}
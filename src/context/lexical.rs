@@ -0,0 +1,392 @@
+//! Lexical (BM25) retrieval over code chunks
+//!
+//! This module provides an inverted index that complements the embedding based
+//! `VectorStore`. It works offline without any model, and its results can be
+//! fused with vector results using reciprocal rank fusion.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use super::embeddings::CodeChunk;
+
+/// Default `k` constant for reciprocal rank fusion
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Configuration for BM25 scoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bm25Config {
+    /// Term frequency saturation
+    pub k1: f32,
+    /// Document length normalization
+    pub b: f32,
+    /// Extra weight given to the symbol names declared in a chunk
+    pub symbol_boost: u32,
+    pub max_results: usize,
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            symbol_boost: 2,
+            max_results: 20,
+        }
+    }
+}
+
+/// Code-aware tokenizer that splits identifiers on camelCase and snake_case boundaries
+#[derive(Debug, Clone, Default)]
+pub struct CodeTokenizer;
+
+impl CodeTokenizer {
+    /// Tokenize text into lowercase terms.
+    ///
+    /// Each identifier produces its full form plus its sub-words, so
+    /// `select_model` yields `select_model`, `select` and `model`, and
+    /// `HTTPServer` yields `httpserver`, `http` and `server`.
+    pub fn tokenize(text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+
+        for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+            let word = word.trim_matches('_');
+            if word.is_empty() {
+                continue;
+            }
+
+            let parts = Self::split_identifier(word);
+            let full = word.to_lowercase();
+            if parts.len() > 1 && Self::is_indexable(&full) {
+                tokens.push(full);
+            }
+
+            for part in parts {
+                if Self::is_indexable(&part) {
+                    tokens.push(part);
+                }
+            }
+        }
+
+        tokens
+    }
+
+    /// Split a single identifier into lowercase sub-words
+    pub fn split_identifier(identifier: &str) -> Vec<String> {
+        let mut parts = Vec::new();
+
+        for segment in identifier.split('_').filter(|s| !s.is_empty()) {
+            let chars: Vec<char> = segment.chars().collect();
+            let mut current = String::new();
+
+            for (i, &c) in chars.iter().enumerate() {
+                let prev = if i > 0 { Some(chars[i - 1]) } else { None };
+                let next = chars.get(i + 1).copied();
+
+                let boundary = match prev {
+                    Some(p) => {
+                        // fooBar, v2Header
+                        (c.is_uppercase() && (p.is_lowercase() || p.is_ascii_digit()))
+                            // HTTPServer -> HTTP | Server
+                            || (c.is_uppercase()
+                                && p.is_uppercase()
+                                && next.is_some_and(|n| n.is_lowercase()))
+                    }
+                    None => false,
+                };
+
+                if boundary && !current.is_empty() {
+                    parts.push(current.to_lowercase());
+                    current.clear();
+                }
+                current.push(c);
+            }
+
+            if !current.is_empty() {
+                parts.push(current.to_lowercase());
+            }
+        }
+
+        parts
+    }
+
+    fn is_indexable(token: &str) -> bool {
+        token.chars().count() > 1 && !token.chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+/// Result from a lexical search
+#[derive(Debug, Clone)]
+pub struct LexicalResult {
+    pub chunk: CodeChunk,
+    pub score: f32,
+    pub matched_terms: Vec<String>,
+}
+
+/// Inverted BM25 index over code chunks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LexicalIndex {
+    config: Bm25Config,
+    chunks: HashMap<String, CodeChunk>,
+    /// term -> (chunk id -> term frequency)
+    postings: HashMap<String, HashMap<String, u32>>,
+    /// chunk id -> its distinct terms, so removal only touches its postings
+    #[serde(default)]
+    chunk_terms: HashMap<String, Vec<String>>,
+    doc_lengths: HashMap<String, usize>,
+    total_length: usize,
+}
+
+impl LexicalIndex {
+    pub fn new(config: Bm25Config) -> Self {
+        Self {
+            config,
+            chunks: HashMap::new(),
+            postings: HashMap::new(),
+            chunk_terms: HashMap::new(),
+            doc_lengths: HashMap::new(),
+            total_length: 0,
+        }
+    }
+
+    /// Add a chunk to the index, replacing any chunk with the same id
+    pub fn add_chunk(&mut self, chunk: CodeChunk) {
+        if self.chunks.contains_key(&chunk.id) {
+            self.remove_chunk(&chunk.id.clone());
+        }
+
+        let mut terms = CodeTokenizer::tokenize(&chunk.content);
+        if let Some(scope_path) = &chunk.scope_path {
+            terms.extend(CodeTokenizer::tokenize(scope_path));
+        }
+        for symbol in &chunk.symbols {
+            let symbol_terms = CodeTokenizer::tokenize(symbol);
+            for _ in 0..self.config.symbol_boost {
+                terms.extend(symbol_terms.iter().cloned());
+            }
+        }
+
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for term in &terms {
+            *frequencies.entry(term.clone()).or_insert(0) += 1;
+        }
+
+        let mut chunk_terms = Vec::with_capacity(frequencies.len());
+        for (term, tf) in frequencies {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(chunk.id.clone(), tf);
+            chunk_terms.push(term);
+        }
+        self.chunk_terms.insert(chunk.id.clone(), chunk_terms);
+
+        self.total_length += terms.len();
+        self.doc_lengths.insert(chunk.id.clone(), terms.len());
+        self.chunks.insert(chunk.id.clone(), chunk);
+    }
+
+    /// Remove a single chunk from the index
+    pub fn remove_chunk(&mut self, chunk_id: &str) {
+        if self.chunks.remove(chunk_id).is_none() {
+            return;
+        }
+
+        if let Some(length) = self.doc_lengths.remove(chunk_id) {
+            self.total_length = self.total_length.saturating_sub(length);
+        }
+
+        for term in self.chunk_terms.remove(chunk_id).unwrap_or_default() {
+            if let Some(docs) = self.postings.get_mut(&term) {
+                docs.remove(chunk_id);
+                if docs.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Remove all chunks belonging to a specific file
+    pub fn remove_file(&mut self, file_path: &PathBuf) {
+        let chunk_ids: Vec<String> = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| &chunk.file_path == file_path)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for chunk_id in chunk_ids {
+            self.remove_chunk(&chunk_id);
+        }
+    }
+
+    /// Search the index with BM25 scoring
+    pub fn search(&self, query: &str, max_results: Option<usize>) -> Vec<LexicalResult> {
+        let max_results = max_results.unwrap_or(self.config.max_results);
+        if self.chunks.is_empty() {
+            return Vec::new();
+        }
+
+        let query_terms: HashSet<String> = CodeTokenizer::tokenize(query).into_iter().collect();
+        let doc_count = self.chunks.len() as f32;
+        let avg_length = self.total_length as f32 / doc_count;
+
+        let mut scores: HashMap<&str, (f32, Vec<String>)> = HashMap::new();
+
+        for term in &query_terms {
+            let Some(docs) = self.postings.get(term) else {
+                continue;
+            };
+
+            let df = docs.len() as f32;
+            let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+
+            for (chunk_id, &tf) in docs {
+                let tf = tf as f32;
+                let length = *self.doc_lengths.get(chunk_id).unwrap_or(&0) as f32;
+                let norm = 1.0 - self.config.b + self.config.b * length / avg_length.max(1.0);
+                let term_score = idf * (tf * (self.config.k1 + 1.0)) / (tf + self.config.k1 * norm);
+
+                let entry = scores.entry(chunk_id.as_str()).or_insert((0.0, Vec::new()));
+                entry.0 += term_score;
+                entry.1.push(term.clone());
+            }
+        }
+
+        let mut results: Vec<LexicalResult> = scores
+            .into_iter()
+            .filter_map(|(chunk_id, (score, mut matched_terms))| {
+                matched_terms.sort();
+                self.chunks.get(chunk_id).map(|chunk| LexicalResult {
+                    chunk: chunk.clone(),
+                    score,
+                    matched_terms,
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.chunk.id.cmp(&b.chunk.id))
+        });
+        results.truncate(max_results);
+        results
+    }
+
+    /// Get a chunk by id
+    pub fn get_chunk(&self, chunk_id: &str) -> Option<&CodeChunk> {
+        self.chunks.get(chunk_id)
+    }
+
+    /// Get total number of indexed chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Check if index is empty
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Number of distinct terms in the index
+    pub fn vocabulary_size(&self) -> usize {
+        self.postings.len()
+    }
+}
+
+impl Default for LexicalIndex {
+    fn default() -> Self {
+        Self::new(Bm25Config::default())
+    }
+}
+
+/// Fuse several ranked lists of ids with reciprocal rank fusion.
+///
+/// Each list must be ordered best first. The fused score of an id is the sum
+/// of `1 / (k + rank)` over every list it appears in, with ranks starting at 1.
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>], k: f32) -> Vec<(String, f32)> {
+    let mut scores: HashMap<String, f32> = HashMap::new();
+
+    for ranking in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            *scores.entry(id.clone()).or_insert(0.0) += 1.0 / (k + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(String, f32)> = scores.into_iter().collect();
+    fused.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::embeddings::ChunkType;
+
+    fn chunk(id: &str, content: &str, symbols: &[&str]) -> CodeChunk {
+        CodeChunk {
+            id: id.to_string(),
+            file_path: PathBuf::from(format!("{}.rs", id)),
+            start_line: 1,
+            end_line: content.lines().count(),
+            content: content.to_string(),
+            language: "rust".to_string(),
+            chunk_type: ChunkType::Function,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            scope_path: None,
+        }
+    }
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(CodeTokenizer::split_identifier("select_model"), vec!["select", "model"]);
+        assert_eq!(CodeTokenizer::split_identifier("selectModel"), vec!["select", "model"]);
+        assert_eq!(CodeTokenizer::split_identifier("HTTPServer"), vec!["http", "server"]);
+        assert_eq!(CodeTokenizer::split_identifier("parseV2Header"), vec!["parse", "v2", "header"]);
+    }
+
+    #[test]
+    fn test_tokenize_keeps_full_identifier() {
+        let tokens = CodeTokenizer::tokenize("fn select_model(&self) -> ModelRouter");
+        assert!(tokens.contains(&"select_model".to_string()));
+        assert!(tokens.contains(&"select".to_string()));
+        assert!(tokens.contains(&"modelrouter".to_string()));
+        assert!(tokens.contains(&"router".to_string()));
+    }
+
+    #[test]
+    fn test_bm25_ranking() {
+        let mut index = LexicalIndex::default();
+        index.add_chunk(chunk("routing", "fn select_model(task: &Task) -> Model { route(task) }", &["select_model"]));
+        index.add_chunk(chunk("parser", "fn parse_header(input: &str) -> Header { todo!() }", &["parse_header"]));
+        index.add_chunk(chunk("other", "struct Config { model_name: String }", &["Config"]));
+
+        let results = index.search("selectModel", None);
+        assert!(!results.is_empty());
+        assert_eq!(results[0].chunk.id, "routing");
+
+        index.remove_file(&PathBuf::from("routing.rs"));
+        assert_eq!(index.len(), 2);
+        assert!(index.search("select", None).is_empty());
+        // Terms only the removed chunk used are gone, shared ones stay
+        assert!(!index.postings.contains_key("route"));
+        assert_eq!(index.postings["model"].len(), 1);
+        assert!(!index.chunk_terms.contains_key("routing"));
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let lexical = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let vector = vec!["b".to_string(), "d".to_string()];
+
+        let fused = reciprocal_rank_fusion(&[lexical, vector], DEFAULT_RRF_K);
+        assert_eq!(fused[0].0, "b");
+        assert_eq!(fused.len(), 4);
+    }
+}
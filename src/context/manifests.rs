@@ -0,0 +1,1233 @@
+//! Package manifest and lockfile parsing
+//!
+//! Dependencies are read from the manifests of each supported ecosystem and
+//! matched against the ecosystem's lockfile to report resolved versions:
+//!
+//! - Cargo: `Cargo.toml`, including workspace members, and `Cargo.lock`
+//! - npm: `package.json`, including workspaces, with `package-lock.json`,
+//!   `yarn.lock` or `pnpm-lock.yaml`
+//! - Go: `go.mod` and `go.sum`
+//! - Python: `pyproject.toml` (PEP 621 and Poetry) and `requirements*.txt`,
+//!   with `poetry.lock` or `uv.lock`
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+use super::{ContextError, Dependency, DependencySource, DependencyType};
+
+/// Dependency tables of a Cargo manifest
+const CARGO_TABLES: &[(&str, DependencyType)] = &[
+    ("dependencies", DependencyType::Runtime),
+    ("dev-dependencies", DependencyType::Development),
+    ("dev_dependencies", DependencyType::Development),
+    ("build-dependencies", DependencyType::Build),
+    ("build_dependencies", DependencyType::Build),
+];
+
+/// Dependency sections of a `package.json`
+const NPM_SECTIONS: &[(&str, DependencyType)] = &[
+    ("dependencies", DependencyType::Runtime),
+    ("devDependencies", DependencyType::Development),
+    ("optionalDependencies", DependencyType::Optional),
+    ("peerDependencies", DependencyType::Runtime),
+];
+
+/// Python extras and groups that hold development tooling
+const PYTHON_DEV_GROUPS: &[&str] = &["dev", "develop", "development", "test", "tests", "testing", "lint", "docs"];
+
+/// Collect the dependencies declared by every manifest under `root`
+pub fn discover_dependencies(root: &Path) -> Result<Vec<Dependency>, ContextError> {
+    let mut ecosystems = Vec::new();
+
+    if root.join("Cargo.toml").is_file() {
+        ecosystems.push(("cargo", cargo_dependencies(root)?));
+    }
+    if root.join("package.json").is_file() {
+        ecosystems.push(("npm", npm_dependencies(root)?));
+    }
+    if root.join("go.mod").is_file() {
+        ecosystems.push(("go", go_dependencies(root)?));
+    }
+    ecosystems.push(("python", python_dependencies(root)?));
+
+    // Workspace members commonly share dependencies, but a package of the
+    // same name in another ecosystem is a different dependency
+    let mut seen = HashSet::new();
+    let mut dependencies = Vec::new();
+    for (ecosystem, found) in ecosystems {
+        dependencies.extend(
+            found
+                .into_iter()
+                .filter(|dep| seen.insert((ecosystem, dep.name.clone(), dep.dependency_type))),
+        );
+    }
+    Ok(dependencies)
+}
+
+/// Versions pinned by a lockfile
+#[derive(Debug, Default)]
+struct LockedVersions {
+    versions: HashMap<String, Vec<String>>,
+    /// Exact `(name, requirement)` resolutions, as recorded by yarn and pnpm
+    ranges: HashMap<(String, String), String>,
+}
+
+impl LockedVersions {
+    fn insert(&mut self, name: &str, version: &str) {
+        let versions = self.versions.entry(name.to_string()).or_default();
+        if !versions.iter().any(|v| v == version) {
+            versions.push(version.to_string());
+        }
+    }
+
+    fn insert_range(&mut self, name: &str, range: &str, version: &str) {
+        self.ranges
+            .insert((name.to_string(), range.to_string()), version.to_string());
+        self.insert(name, version);
+    }
+
+    /// The locked version of `name` that satisfies `requirement`
+    ///
+    /// With several locked versions and a requirement that is not valid
+    /// semver, the version is ambiguous and `None` is returned.
+    fn resolve(&self, name: &str, requirement: Option<&str>) -> Option<String> {
+        if let Some(requirement) = requirement {
+            if let Some(version) = self.ranges.get(&(name.to_string(), requirement.to_string())) {
+                return Some(version.clone());
+            }
+        }
+
+        let candidates = self.versions.get(name)?;
+        if let [version] = candidates.as_slice() {
+            return Some(version.clone());
+        }
+
+        let requirement = semver::VersionReq::parse(requirement?.trim()).ok()?;
+        candidates
+            .iter()
+            .filter_map(|v| semver::Version::parse(v).ok().map(|parsed| (parsed, v)))
+            .filter(|(parsed, _)| requirement.matches(parsed))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, v)| v.clone())
+    }
+}
+
+fn read(path: &Path) -> Result<String, ContextError> {
+    fs::read_to_string(path)
+        .map_err(|e| ContextError::AnalysisFailed(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+fn read_toml(path: &Path) -> Result<TomlValue, ContextError> {
+    toml::from_str(&read(path)?)
+        .map_err(|e| ContextError::AnalysisFailed(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+fn read_json(path: &Path) -> Result<JsonValue, ContextError> {
+    serde_json::from_str(&read(path)?)
+        .map_err(|e| ContextError::AnalysisFailed(format!("Failed to parse {}: {}", path.display(), e)))
+}
+
+/// Lockfiles are advisory, so unreadable ones are skipped with a warning
+fn read_lockfile<T>(path: &Path, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    let parsed = parse(&content);
+    if parsed.is_none() {
+        tracing::warn!("Ignoring unparseable lockfile {}", path.display());
+    }
+    parsed
+}
+
+fn string_list(value: Option<&TomlValue>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|item| item.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Cargo
+// ---------------------------------------------------------------------------
+
+fn cargo_dependencies(root: &Path) -> Result<Vec<Dependency>, ContextError> {
+    let manifest = read_toml(&root.join("Cargo.toml"))?;
+    let locked = read_lockfile(&root.join("Cargo.lock"), |content| parse_toml_lock(content, str::to_string))
+        .unwrap_or_default();
+
+    let workspace = manifest.get("workspace");
+    let workspace_deps = workspace
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut manifests = vec![(root.to_path_buf(), manifest.clone())];
+    let members = string_list(workspace.and_then(|w| w.get("members")));
+    let excluded = expand_patterns(root, &string_list(workspace.and_then(|w| w.get("exclude"))));
+    for dir in expand_patterns(root, &members) {
+        let path = dir.join("Cargo.toml");
+        if dir == root || excluded.contains(&dir) || !path.is_file() {
+            continue;
+        }
+        match read_toml(&path) {
+            Ok(member) => manifests.push((dir, member)),
+            Err(e) => tracing::warn!("Skipping workspace member: {}", e),
+        }
+    }
+
+    let mut dependencies = Vec::new();
+    for (dir, manifest) in &manifests {
+        let mut tables: Vec<(&toml::Table, DependencyType)> = Vec::new();
+        for (key, dependency_type) in CARGO_TABLES {
+            if let Some(table) = manifest.get(*key).and_then(|t| t.as_table()) {
+                tables.push((table, *dependency_type));
+            }
+        }
+        // [target.'cfg(...)'.dependencies] and friends
+        if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+            for target in targets.values() {
+                for (key, dependency_type) in CARGO_TABLES {
+                    if let Some(table) = target.get(*key).and_then(|t| t.as_table()) {
+                        tables.push((table, *dependency_type));
+                    }
+                }
+            }
+        }
+
+        for (table, dependency_type) in tables {
+            for (key, spec) in table {
+                dependencies.push(cargo_dependency(
+                    key,
+                    spec,
+                    dependency_type,
+                    (dir, root),
+                    &workspace_deps,
+                    &locked,
+                ));
+            }
+        }
+    }
+
+    Ok(dependencies)
+}
+
+fn cargo_dependency(
+    key: &str,
+    spec: &TomlValue,
+    dependency_type: DependencyType,
+    (dir, root): (&Path, &Path),
+    workspace_deps: &toml::Table,
+    locked: &LockedVersions,
+) -> Dependency {
+    let optional = spec.get("optional").and_then(|o| o.as_bool()).unwrap_or(false);
+
+    // `serde = { workspace = true }` inherits from [workspace.dependencies],
+    // whose paths are relative to the workspace root
+    let (spec, base) = match workspace_deps.get(key) {
+        Some(inherited) if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) => (inherited, root),
+        _ => (spec, dir),
+    };
+
+    let version = match spec {
+        TomlValue::String(version) => Some(version.clone()),
+        _ => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+    };
+    let name = spec.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+
+    let source = if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+        DependencySource::Path(base.join(path))
+    } else if let Some(git) = spec.get("git").and_then(|g| g.as_str()) {
+        DependencySource::Git(git.to_string())
+    } else {
+        let registry = spec.get("registry").and_then(|r| r.as_str()).unwrap_or("cargo");
+        DependencySource::PackageManager(registry.to_string())
+    };
+
+    let dependency_type = if optional && dependency_type == DependencyType::Runtime {
+        DependencyType::Optional
+    } else {
+        dependency_type
+    };
+
+    Dependency {
+        name: name.to_string(),
+        resolved_version: locked.resolve(name, version.as_deref()),
+        version,
+        dependency_type,
+        source,
+    }
+}
+
+/// Parse the `[[package]]` entries shared by Cargo.lock, poetry.lock and uv.lock
+fn parse_toml_lock(content: &str, normalize: fn(&str) -> String) -> Option<LockedVersions> {
+    let lock: TomlValue = toml::from_str(content).ok()?;
+    let mut locked = LockedVersions::default();
+
+    for package in lock.get("package").and_then(|p| p.as_array()).into_iter().flatten() {
+        if let (Some(name), Some(version)) = (
+            package.get("name").and_then(|n| n.as_str()),
+            package.get("version").and_then(|v| v.as_str()),
+        ) {
+            locked.insert(&normalize(name), version);
+        }
+    }
+
+    Some(locked)
+}
+
+// ---------------------------------------------------------------------------
+// npm, yarn and pnpm
+// ---------------------------------------------------------------------------
+
+fn npm_dependencies(root: &Path) -> Result<Vec<Dependency>, ContextError> {
+    let manifest = read_json(&root.join("package.json"))?;
+    let (manager, locked) = npm_lock(root);
+
+    let mut patterns: Vec<String> = match manifest.get("workspaces") {
+        Some(JsonValue::Array(items)) => items.iter().filter_map(|i| i.as_str().map(String::from)).collect(),
+        Some(workspaces) => workspaces
+            .get("packages")
+            .and_then(|p| p.as_array())
+            .map(|items| items.iter().filter_map(|i| i.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+    if let Ok(content) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        if let Ok(workspace) = serde_yaml::from_str::<serde_yaml::Value>(&content) {
+            patterns.extend(
+                workspace
+                    .get("packages")
+                    .and_then(|p| p.as_sequence())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|p| p.as_str().map(String::from)),
+            );
+        }
+    }
+
+    let (excluded, included): (Vec<String>, Vec<String>) = patterns.into_iter().partition(|p| p.starts_with('!'));
+    let excluded: Vec<String> = excluded.iter().map(|p| p[1..].to_string()).collect();
+    let excluded = expand_patterns(root, &excluded);
+
+    let mut manifests = vec![(root.to_path_buf(), manifest)];
+    for dir in expand_patterns(root, &included) {
+        let path = dir.join("package.json");
+        if dir != root && !excluded.contains(&dir) && path.is_file() {
+            match read_json(&path) {
+                Ok(member) => manifests.push((dir, member)),
+                Err(e) => tracing::warn!("Skipping workspace member: {}", e),
+            }
+        }
+    }
+
+    let members: HashMap<String, PathBuf> = manifests
+        .iter()
+        .filter_map(|(dir, manifest)| Some((manifest.get("name")?.as_str()?.to_string(), dir.clone())))
+        .collect();
+
+    let mut dependencies = Vec::new();
+    for (dir, manifest) in &manifests {
+        for (section, dependency_type) in NPM_SECTIONS {
+            let Some(entries) = manifest.get(*section).and_then(|s| s.as_object()) else {
+                continue;
+            };
+
+            for (name, spec) in entries {
+                let spec = spec.as_str().unwrap_or("*");
+                let optional_peer = *section == "peerDependencies"
+                    && manifest
+                        .pointer(&format!("/peerDependenciesMeta/{}/optional", name.replace('~', "~0").replace('/', "~1")))
+                        .and_then(|o| o.as_bool())
+                        .unwrap_or(false);
+
+                let source = npm_source(name, spec, dir, &members, manager);
+                let resolved_version = match source {
+                    DependencySource::PackageManager(_) => locked.resolve(name, Some(spec)),
+                    _ => None,
+                };
+
+                dependencies.push(Dependency {
+                    name: name.clone(),
+                    version: Some(spec.to_string()),
+                    resolved_version,
+                    dependency_type: if optional_peer {
+                        DependencyType::Optional
+                    } else {
+                        *dependency_type
+                    },
+                    source,
+                });
+            }
+        }
+    }
+
+    Ok(dependencies)
+}
+
+fn npm_source(
+    name: &str,
+    spec: &str,
+    dir: &Path,
+    members: &HashMap<String, PathBuf>,
+    manager: &str,
+) -> DependencySource {
+    if let Some(path) = spec.strip_prefix("file:").or_else(|| spec.strip_prefix("link:")) {
+        return DependencySource::Path(dir.join(path));
+    }
+    if spec.starts_with("workspace:") {
+        if let Some(member) = members.get(name) {
+            return DependencySource::Path(member.clone());
+        }
+    }
+    if ["git:", "git+", "github:", "gitlab:", "bitbucket:"]
+        .iter()
+        .any(|prefix| spec.starts_with(prefix))
+    {
+        return DependencySource::Git(spec.to_string());
+    }
+    DependencySource::PackageManager(manager.to_string())
+}
+
+/// Find the lockfile in use and the package manager that wrote it
+fn npm_lock(root: &Path) -> (&'static str, LockedVersions) {
+    for file in ["package-lock.json", "npm-shrinkwrap.json"] {
+        if let Some(locked) = read_lockfile(&root.join(file), parse_npm_lock) {
+            return ("npm", locked);
+        }
+    }
+    if let Some(locked) = read_lockfile(&root.join("yarn.lock"), |content| Some(parse_yarn_lock(content))) {
+        return ("yarn", locked);
+    }
+    if let Some(locked) = read_lockfile(&root.join("pnpm-lock.yaml"), parse_pnpm_lock) {
+        return ("pnpm", locked);
+    }
+    ("npm", LockedVersions::default())
+}
+
+fn parse_npm_lock(content: &str) -> Option<LockedVersions> {
+    let lock: JsonValue = serde_json::from_str(content).ok()?;
+    let mut locked = LockedVersions::default();
+
+    // lockfileVersion 2 and 3 key packages by install path
+    if let Some(packages) = lock.get("packages").and_then(|p| p.as_object()) {
+        for (path, entry) in packages {
+            let Some((_, installed)) = path.rsplit_once("node_modules/") else {
+                continue;
+            };
+            let name = entry.get("name").and_then(|n| n.as_str()).unwrap_or(installed);
+            if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                locked.insert(name, version);
+            }
+        }
+    } else if let Some(dependencies) = lock.get("dependencies").and_then(|d| d.as_object()) {
+        for (name, entry) in dependencies {
+            if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                locked.insert(name, version);
+            }
+        }
+    }
+
+    Some(locked)
+}
+
+/// Parse both the classic and the Berry `yarn.lock` formats
+fn parse_yarn_lock(content: &str) -> LockedVersions {
+    let mut locked = LockedVersions::default();
+    let mut specs: Vec<(String, String)> = Vec::new();
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            // "lodash@^4.17.0", lodash@^4.17.21:
+            specs = line
+                .trim_end_matches(':')
+                .split(',')
+                .filter_map(|spec| split_yarn_spec(spec.trim().trim_matches('"')))
+                .collect();
+        } else if let Some(version) = line.trim().strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for (name, range) in specs.drain(..) {
+                locked.insert_range(&name, &range, version);
+            }
+        }
+    }
+
+    locked
+}
+
+fn split_yarn_spec(spec: &str) -> Option<(String, String)> {
+    // Scoped packages start with '@', so the separator is the next one
+    let at = spec.get(1..)?.find('@')? + 1;
+    let range = &spec[at + 1..];
+    let range = range.strip_prefix("npm:").unwrap_or(range);
+    Some((spec[..at].to_string(), range.to_string()))
+}
+
+fn parse_pnpm_lock(content: &str) -> Option<LockedVersions> {
+    let lock: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    let mut locked = LockedVersions::default();
+
+    // Lockfile v6+ nests sections under importers; v5 keeps them at the top level
+    let mut sections = vec![&lock];
+    if let Some(importers) = lock.get("importers").and_then(|i| i.as_mapping()) {
+        sections.extend(importers.values());
+    }
+
+    for section in sections {
+        for key in ["dependencies", "devDependencies", "optionalDependencies"] {
+            let Some(entries) = section.get(key).and_then(|e| e.as_mapping()) else {
+                continue;
+            };
+
+            for (name, entry) in entries {
+                let Some(name) = name.as_str() else {
+                    continue;
+                };
+                let (specifier, version) = match entry.as_str() {
+                    Some(version) => (
+                        section.get("specifiers").and_then(|s| s.get(name)).and_then(|s| s.as_str()),
+                        Some(version),
+                    ),
+                    None => (
+                        entry.get("specifier").and_then(|s| s.as_str()),
+                        entry.get("version").and_then(|v| v.as_str()),
+                    ),
+                };
+
+                let Some(version) = version.filter(|v| !v.starts_with("link:")) else {
+                    continue;
+                };
+                // Strip peer suffixes: 1.2.3(react@18.2.0) or 1.2.3_react@18.2.0
+                let version = version.split(['(', '_']).next().unwrap_or(version);
+
+                match specifier {
+                    Some(specifier) => locked.insert_range(name, specifier, version),
+                    None => locked.insert(name, version),
+                }
+            }
+        }
+    }
+
+    Some(locked)
+}
+
+// ---------------------------------------------------------------------------
+// Go
+// ---------------------------------------------------------------------------
+
+fn go_dependencies(root: &Path) -> Result<Vec<Dependency>, ContextError> {
+    let content = read(&root.join("go.mod"))?;
+    let sums = read_lockfile(&root.join("go.sum"), |content| Some(parse_go_sum(content)));
+
+    let mut requires: Vec<(String, String)> = Vec::new();
+    let mut replaces: HashMap<String, (String, Option<String>)> = HashMap::new();
+    let mut block: Option<String> = None;
+
+    for line in content.lines() {
+        let code = line.split("//").next().unwrap_or_default().trim();
+        if code.is_empty() {
+            continue;
+        }
+
+        let (directive, rest) = match &block {
+            Some(_) if code == ")" => {
+                block = None;
+                continue;
+            }
+            Some(directive) => (directive.clone(), code),
+            None => {
+                let (directive, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+                if rest.trim() == "(" {
+                    block = Some(directive.to_string());
+                    continue;
+                }
+                (directive.to_string(), rest.trim())
+            }
+        };
+
+        match directive.as_str() {
+            "require" => {
+                let mut parts = rest.split_whitespace();
+                if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+                    requires.push((module.to_string(), version.to_string()));
+                }
+            }
+            "replace" => {
+                if let Some((from, to)) = rest.split_once("=>") {
+                    let module = from.split_whitespace().next().unwrap_or_default();
+                    let mut target = to.split_whitespace();
+                    if let Some(target_path) = target.next() {
+                        replaces.insert(
+                            module.to_string(),
+                            (target_path.to_string(), target.next().map(String::from)),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let dependencies = requires
+        .into_iter()
+        .map(|(module, version)| {
+            let (source, resolved_version) = match replaces.get(&module) {
+                Some((target, None)) if target.starts_with('.') || target.starts_with('/') => {
+                    (DependencySource::Path(root.join(target)), None)
+                }
+                Some((_, Some(replacement))) => {
+                    (DependencySource::PackageManager("go".to_string()), Some(replacement.clone()))
+                }
+                _ => {
+                    // go.mod pins exact versions; go.sum confirms they were fetched
+                    let verified = sums
+                        .as_ref()
+                        .and_then(|sums| sums.versions.get(&module))
+                        .is_some_and(|versions| versions.contains(&version));
+                    (
+                        DependencySource::PackageManager("go".to_string()),
+                        verified.then(|| version.clone()),
+                    )
+                }
+            };
+
+            Dependency {
+                name: module,
+                version: Some(version),
+                resolved_version,
+                dependency_type: DependencyType::Runtime,
+                source,
+            }
+        })
+        .collect();
+
+    Ok(dependencies)
+}
+
+fn parse_go_sum(content: &str) -> LockedVersions {
+    let mut locked = LockedVersions::default();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+            locked.insert(module, version.trim_end_matches("/go.mod"));
+        }
+    }
+    locked
+}
+
+// ---------------------------------------------------------------------------
+// Python
+// ---------------------------------------------------------------------------
+
+/// PEP 503 name normalization, used to match lockfile entries
+fn normalize_python_name(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+fn python_dependencies(root: &Path) -> Result<Vec<Dependency>, ContextError> {
+    let locked = ["poetry.lock", "uv.lock", "pdm.lock"]
+        .iter()
+        .find_map(|file| read_lockfile(&root.join(file), |content| parse_toml_lock(content, normalize_python_name)))
+        .unwrap_or_default();
+
+    let mut dependencies = Vec::new();
+
+    let pyproject = root.join("pyproject.toml");
+    if pyproject.is_file() {
+        dependencies.extend(pyproject_dependencies(&read_toml(&pyproject)?, root, &locked));
+    }
+
+    let mut requirement_files: Vec<PathBuf> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("requirements") && n.ends_with(".txt"))
+        })
+        .collect();
+    requirement_files.sort();
+
+    let mut visited = HashSet::new();
+    for path in requirement_files {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_lowercase();
+        let dependency_type = if PYTHON_DEV_GROUPS.iter().any(|group| stem.contains(group)) {
+            DependencyType::Development
+        } else {
+            DependencyType::Runtime
+        };
+        requirements_dependencies(&path, dependency_type, &locked, &mut visited, &mut dependencies)?;
+    }
+
+    Ok(dependencies)
+}
+
+fn pyproject_dependencies(pyproject: &TomlValue, root: &Path, locked: &LockedVersions) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    let mut add_requirements = |requirements: Vec<String>, dependency_type: DependencyType| {
+        dependencies.extend(
+            requirements
+                .iter()
+                .filter_map(|r| python_requirement(r, root, dependency_type, locked)),
+        );
+    };
+
+    // PEP 621
+    let project = pyproject.get("project");
+    add_requirements(string_list(project.and_then(|p| p.get("dependencies"))), DependencyType::Runtime);
+    for (group, requirements) in project
+        .and_then(|p| p.get("optional-dependencies"))
+        .and_then(|o| o.as_table())
+        .into_iter()
+        .flatten()
+    {
+        add_requirements(string_list(Some(requirements)), python_group_type(group));
+    }
+
+    // PEP 735 dependency groups
+    for requirements in pyproject
+        .get("dependency-groups")
+        .and_then(|g| g.as_table())
+        .into_iter()
+        .flat_map(|groups| groups.values())
+    {
+        add_requirements(string_list(Some(requirements)), DependencyType::Development);
+    }
+
+    add_requirements(
+        string_list(pyproject.get("build-system").and_then(|b| b.get("requires"))),
+        DependencyType::Build,
+    );
+
+    // Poetry
+    if let Some(poetry) = pyproject.get("tool").and_then(|t| t.get("poetry")) {
+        let mut tables = vec![
+            (poetry.get("dependencies"), DependencyType::Runtime),
+            (poetry.get("dev-dependencies"), DependencyType::Development),
+        ];
+        for (group, table) in poetry.get("group").and_then(|g| g.as_table()).into_iter().flatten() {
+            let dependency_type = if group == "main" {
+                DependencyType::Runtime
+            } else {
+                DependencyType::Development
+            };
+            tables.push((table.get("dependencies"), dependency_type));
+        }
+
+        for (table, dependency_type) in tables {
+            for (name, spec) in table.and_then(|t| t.as_table()).into_iter().flatten() {
+                if name == "python" {
+                    continue;
+                }
+                dependencies.push(poetry_dependency(name, spec, root, dependency_type, locked));
+            }
+        }
+    }
+
+    dependencies
+}
+
+fn python_group_type(group: &str) -> DependencyType {
+    if PYTHON_DEV_GROUPS.contains(&group.to_lowercase().as_str()) {
+        DependencyType::Development
+    } else {
+        DependencyType::Optional
+    }
+}
+
+fn poetry_dependency(
+    name: &str,
+    spec: &TomlValue,
+    root: &Path,
+    dependency_type: DependencyType,
+    locked: &LockedVersions,
+) -> Dependency {
+    let version = match spec {
+        TomlValue::String(version) => Some(version.clone()),
+        _ => spec.get("version").and_then(|v| v.as_str()).map(String::from),
+    };
+
+    let source = if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+        DependencySource::Path(root.join(path))
+    } else if let Some(git) = spec.get("git").and_then(|g| g.as_str()) {
+        DependencySource::Git(git.to_string())
+    } else {
+        DependencySource::PackageManager("poetry".to_string())
+    };
+
+    let optional = spec.get("optional").and_then(|o| o.as_bool()).unwrap_or(false);
+
+    Dependency {
+        name: name.to_string(),
+        resolved_version: locked.resolve(&normalize_python_name(name), None),
+        version,
+        dependency_type: if optional && dependency_type == DependencyType::Runtime {
+            DependencyType::Optional
+        } else {
+            dependency_type
+        },
+        source,
+    }
+}
+
+fn requirements_dependencies(
+    path: &Path,
+    dependency_type: DependencyType,
+    locked: &LockedVersions,
+    visited: &mut HashSet<PathBuf>,
+    dependencies: &mut Vec<Dependency>,
+) -> Result<(), ContextError> {
+    if !visited.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let content = read(path)?.replace("\\\n", " ");
+
+    for line in content.lines() {
+        let line = match line.find(" #") {
+            Some(index) => &line[..index],
+            None => line,
+        }
+        .trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(included) = ["-r ", "--requirement "]
+            .iter()
+            .find_map(|flag| line.strip_prefix(flag))
+        {
+            let included = dir.join(included.trim());
+            if included.is_file() {
+                requirements_dependencies(&included, dependency_type, locked, visited, dependencies)?;
+            }
+        } else if let Some(target) = ["-e ", "--editable "].iter().find_map(|flag| line.strip_prefix(flag)) {
+            dependencies.extend(editable_requirement(target.trim(), dir, dependency_type));
+        } else if !line.starts_with('-') {
+            dependencies.extend(python_requirement(line, dir, dependency_type, locked));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a PEP 508 requirement such as `requests[socks]>=2.28; python_version > "3.8"`
+fn python_requirement(
+    requirement: &str,
+    dir: &Path,
+    dependency_type: DependencyType,
+    locked: &LockedVersions,
+) -> Option<Dependency> {
+    let requirement = requirement.split(';').next().unwrap_or_default().trim();
+    let name_end = requirement
+        .find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut rest = requirement[name_end..].trim();
+    if rest.starts_with('[') {
+        rest = rest.split_once(']').map(|(_, after)| after.trim()).unwrap_or_default();
+    }
+
+    let (version, source) = if let Some(url) = rest.strip_prefix('@') {
+        let url = url.trim();
+        let source = if url.starts_with("git+") {
+            DependencySource::Git(url.to_string())
+        } else if let Some(path) = url.strip_prefix("file://") {
+            DependencySource::Path(dir.join(path))
+        } else {
+            DependencySource::PackageManager("pip".to_string())
+        };
+        (None, source)
+    } else {
+        let spec = rest.trim_start_matches('(').trim_end_matches(')').trim();
+        (
+            (!spec.is_empty()).then(|| spec.to_string()),
+            DependencySource::PackageManager("pip".to_string()),
+        )
+    };
+
+    let pinned = version
+        .as_deref()
+        .and_then(|v| v.strip_prefix("=="))
+        .filter(|v| !v.contains([',', '*']))
+        .map(|v| v.trim().to_string());
+
+    Some(Dependency {
+        name: name.to_string(),
+        resolved_version: pinned.or_else(|| locked.resolve(&normalize_python_name(name), None)),
+        version,
+        dependency_type,
+        source,
+    })
+}
+
+/// `-e ./libs/core` or `-e git+https://host/repo.git#egg=core`
+fn editable_requirement(target: &str, dir: &Path, dependency_type: DependencyType) -> Option<Dependency> {
+    let egg = target
+        .split_once("#egg=")
+        .map(|(_, egg)| egg.split('&').next().unwrap_or(egg).to_string());
+
+    let (name, source) = if target.starts_with("git+") {
+        let repo = target.split('#').next().unwrap_or(target);
+        let name = egg.or_else(|| {
+            repo.rsplit('/')
+                .next()
+                .map(|n| n.split('@').next().unwrap_or(n).trim_end_matches(".git").to_string())
+        })?;
+        (name, DependencySource::Git(repo.to_string()))
+    } else {
+        let path = dir.join(target.split('#').next().unwrap_or(target));
+        let name = egg.or_else(|| path.file_name().and_then(|n| n.to_str()).map(String::from))?;
+        (name, DependencySource::Path(path))
+    };
+
+    Some(Dependency {
+        name,
+        version: None,
+        resolved_version: None,
+        dependency_type,
+        source,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Workspace member patterns
+// ---------------------------------------------------------------------------
+
+/// Expand workspace member patterns such as `crates/*` relative to `root`
+fn expand_patterns(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    for pattern in patterns {
+        let mut current = vec![root.to_path_buf()];
+        for component in pattern.split('/').filter(|c| !c.is_empty() && *c != ".") {
+            current = if component.contains(['*', '?']) {
+                current
+                    .iter()
+                    .flat_map(|dir| fs::read_dir(dir).into_iter().flatten().flatten())
+                    .filter(|entry| {
+                        entry.path().is_dir()
+                            && entry
+                                .file_name()
+                                .to_str()
+                                .is_some_and(|name| wildcard_match(component, name))
+                    })
+                    .map(|entry| entry.path())
+                    .collect()
+            } else {
+                current.iter().map(|dir| dir.join(component)).collect()
+            };
+        }
+        dirs.extend(current.into_iter().filter(|dir| dir.is_dir()));
+    }
+
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.chars().next() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len())
+            .filter(|&i| name.is_char_boundary(i))
+            .any(|i| wildcard_match(&pattern[1..], &name[i..])),
+        Some('?') => {
+            let mut chars = name.chars();
+            chars.next().is_some() && wildcard_match(&pattern[1..], chars.as_str())
+        }
+        Some(c) => name.starts_with(c) && wildcard_match(&pattern[c.len_utf8()..], &name[c.len_utf8()..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn find<'a>(dependencies: &'a [Dependency], name: &str) -> &'a Dependency {
+        dependencies
+            .iter()
+            .find(|dep| dep.name == name)
+            .unwrap_or_else(|| panic!("missing dependency {}", name))
+    }
+
+    #[test]
+    fn test_cargo_workspace_with_lockfile() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            r#"
+[workspace]
+members = ["crates/*"]
+exclude = ["crates/legacy"]
+
+[workspace.dependencies]
+serde = { version = "1.0", features = ["derive"] }
+"#,
+        );
+        write(
+            root,
+            "crates/core/Cargo.toml",
+            r#"
+[package]
+name = "core"
+
+[dependencies]
+serde = { workspace = true }
+rand = { version = "0.8", optional = true }
+util = { path = "../util" }
+
+[dev-dependencies]
+tempfile = "3"
+
+[build-dependencies]
+cc = "1.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#,
+        );
+        write(root, "crates/legacy/Cargo.toml", "[dependencies]\nold = \"1\"\n");
+        write(root, "crates/broken/Cargo.toml", "[dependencies\nbroken = \"1\"\n");
+        write(
+            root,
+            "Cargo.lock",
+            r#"
+[[package]]
+name = "serde"
+version = "1.0.197"
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+
+[[package]]
+name = "libc"
+version = "0.2.153"
+"#,
+        );
+
+        let dependencies = discover_dependencies(root).unwrap();
+        assert!(dependencies.iter().all(|dep| dep.name != "old"));
+
+        let serde = find(&dependencies, "serde");
+        assert_eq!(serde.version.as_deref(), Some("1.0"));
+        assert_eq!(serde.resolved_version.as_deref(), Some("1.0.197"));
+
+        let rand = find(&dependencies, "rand");
+        assert_eq!(rand.dependency_type, DependencyType::Optional);
+        assert_eq!(rand.resolved_version.as_deref(), Some("0.8.5"));
+
+        assert_eq!(find(&dependencies, "tempfile").dependency_type, DependencyType::Development);
+        assert_eq!(find(&dependencies, "cc").dependency_type, DependencyType::Build);
+        assert_eq!(find(&dependencies, "libc").resolved_version.as_deref(), Some("0.2.153"));
+        assert!(matches!(find(&dependencies, "util").source, DependencySource::Path(_)));
+    }
+
+    #[test]
+    fn test_npm_workspace_with_yarn_lock() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{
+  "name": "app",
+  "workspaces": ["packages/*"],
+  "dependencies": { "lodash": "^4.17.0", "@scope/ui": "workspace:*" },
+  "devDependencies": { "jest": "^29.0.0" },
+  "peerDependencies": { "react": "^18.0.0" },
+  "peerDependenciesMeta": { "react": { "optional": true } }
+}"#,
+        );
+        write(root, "packages/ui/package.json", r#"{ "name": "@scope/ui", "dependencies": { "@babel/core": "^7.0.0" } }"#);
+        write(
+            root,
+            "yarn.lock",
+            r#"# yarn lockfile v1
+
+"@babel/core@^7.0.0":
+  version "7.24.0"
+
+lodash@^4.17.0, lodash@^4.17.21:
+  version "4.17.21"
+
+jest@^29.0.0:
+  version "29.7.0"
+"#,
+        );
+
+        let dependencies = discover_dependencies(root).unwrap();
+
+        let lodash = find(&dependencies, "lodash");
+        assert_eq!(lodash.resolved_version.as_deref(), Some("4.17.21"));
+        assert!(matches!(&lodash.source, DependencySource::PackageManager(pm) if pm == "yarn"));
+
+        assert_eq!(find(&dependencies, "jest").dependency_type, DependencyType::Development);
+        assert_eq!(find(&dependencies, "react").dependency_type, DependencyType::Optional);
+        assert!(matches!(find(&dependencies, "@scope/ui").source, DependencySource::Path(_)));
+        assert_eq!(find(&dependencies, "@babel/core").resolved_version.as_deref(), Some("7.24.0"));
+    }
+
+    #[test]
+    fn test_npm_and_pnpm_lockfiles() {
+        let npm = parse_npm_lock(
+            r#"{ "lockfileVersion": 3, "packages": {
+                "": { "name": "app" },
+                "node_modules/lodash": { "version": "4.17.21" },
+                "node_modules/a/node_modules/lodash": { "version": "3.10.1" }
+            } }"#,
+        )
+        .unwrap();
+        assert_eq!(npm.resolve("lodash", Some("^4.0.0")).as_deref(), Some("4.17.21"));
+        assert_eq!(npm.resolve("lodash", Some("^3.0.0")).as_deref(), Some("3.10.1"));
+
+        let pnpm = parse_pnpm_lock(
+            r#"
+lockfileVersion: '9.0'
+importers:
+  .:
+    dependencies:
+      react-dom:
+        specifier: ^18.2.0
+        version: 18.2.0(react@18.2.0)
+"#,
+        )
+        .unwrap();
+        assert_eq!(pnpm.resolve("react-dom", Some("^18.2.0")).as_deref(), Some("18.2.0"));
+    }
+
+    #[test]
+    fn test_go_mod_with_sum_and_replace() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "go.mod",
+            r#"module example.com/app
+
+go 1.21
+
+require github.com/spf13/cobra v1.8.0
+
+require (
+	golang.org/x/sys v0.15.0 // indirect
+	example.com/lib v1.0.0
+	example.com/fork v1.0.0
+)
+
+replace example.com/lib => ../lib
+replace example.com/fork v1.0.0 => example.com/fork-ng v1.2.0
+"#,
+        );
+        write(
+            root,
+            "go.sum",
+            "github.com/spf13/cobra v1.8.0 h1:abc=\ngithub.com/spf13/cobra v1.8.0/go.mod h1:def=\n",
+        );
+
+        let dependencies = discover_dependencies(root).unwrap();
+        assert_eq!(dependencies.len(), 4);
+        assert_eq!(find(&dependencies, "github.com/spf13/cobra").resolved_version.as_deref(), Some("v1.8.0"));
+        assert_eq!(find(&dependencies, "golang.org/x/sys").resolved_version, None);
+        assert!(matches!(find(&dependencies, "example.com/lib").source, DependencySource::Path(_)));
+        assert_eq!(find(&dependencies, "example.com/fork").resolved_version.as_deref(), Some("v1.2.0"));
+    }
+
+    #[test]
+    fn test_same_name_in_two_ecosystems() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"app\"\n\n[dependencies]\nsemver = \"1\"\n");
+        write(root, "package.json", r#"{"dependencies": {"semver": "^7.5.0"}}"#);
+
+        let dependencies = discover_dependencies(root).unwrap();
+        let mut versions: Vec<&str> = dependencies
+            .iter()
+            .filter(|dep| dep.name == "semver")
+            .filter_map(|dep| dep.version.as_deref())
+            .collect();
+        versions.sort();
+        assert_eq!(versions, vec!["1", "^7.5.0"]);
+    }
+
+    #[test]
+    fn test_python_pyproject_and_requirements() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "pyproject.toml",
+            r#"
+[build-system]
+requires = ["setuptools>=61"]
+
+[project]
+dependencies = ["requests[socks]>=2.28; python_version > '3.8'"]
+
+[project.optional-dependencies]
+test = ["pytest>=7"]
+yaml = ["PyYAML"]
+
+[tool.poetry.dependencies]
+python = "^3.10"
+Django = { version = "^4.2", optional = true }
+
+[tool.poetry.group.lint.dependencies]
+ruff = "^0.3"
+"#,
+        );
+        write(root, "requirements.txt", "flask==3.0.0  # web\n-r requirements-base.txt\n-e ./libs/core\n");
+        write(root, "requirements-base.txt", "click\n");
+        write(root, "requirements-dev.txt", "black\n");
+        write(root, "poetry.lock", "[[package]]\nname = \"pyyaml\"\nversion = \"6.0.1\"\n");
+
+        let dependencies = discover_dependencies(root).unwrap();
+
+        let requests = find(&dependencies, "requests");
+        assert_eq!(requests.version.as_deref(), Some(">=2.28"));
+        assert_eq!(requests.dependency_type, DependencyType::Runtime);
+
+        assert_eq!(find(&dependencies, "pytest").dependency_type, DependencyType::Development);
+        let yaml = find(&dependencies, "PyYAML");
+        assert_eq!(yaml.dependency_type, DependencyType::Optional);
+        assert_eq!(yaml.resolved_version.as_deref(), Some("6.0.1"));
+        assert_eq!(find(&dependencies, "setuptools").dependency_type, DependencyType::Build);
+        assert_eq!(find(&dependencies, "Django").dependency_type, DependencyType::Optional);
+        assert_eq!(find(&dependencies, "ruff").dependency_type, DependencyType::Development);
+        assert!(dependencies.iter().all(|dep| dep.name != "python"));
+
+        assert_eq!(find(&dependencies, "flask").resolved_version.as_deref(), Some("3.0.0"));
+        assert_eq!(find(&dependencies, "click").dependency_type, DependencyType::Runtime);
+        assert!(matches!(find(&dependencies, "core").source, DependencySource::Path(_)));
+        assert_eq!(find(&dependencies, "black").dependency_type, DependencyType::Development);
+    }
+}
@@ -5,17 +5,31 @@
 
 pub mod analyzer;
 pub mod embeddings;
+pub mod history;
 pub mod indexer;
+pub mod lexical;
+pub mod manifests;
+pub mod packer;
+pub mod persistent_index;
+pub mod references;
 pub mod repository;
 pub mod semantic;
 pub mod symbols;
+pub mod syntax_chunker;
+pub mod vector_index;
+pub mod walker;
+pub mod watcher;
 
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use semantic::{SemanticAnalysis, SemanticAnalyzer};
-use embeddings::{VectorStore, EmbeddingConfig, EmbeddingProvider, CodeChunker};
+use embeddings::{VectorStore, EmbeddingConfig, EmbeddingProvider, CodeChunk, CodeChunker};
+use lexical::{LexicalIndex, Bm25Config};
+use persistent_index::{IndexRecord, PersistentIndex};
+use references::ReferenceIndex;
+use packer::{ContextPacker, PackRequest, PackedContext};
 
 // LocalEmbeddingProvider import removed - not currently used
 
@@ -29,6 +43,10 @@ pub struct CodebaseContext {
     pub repository_info: Option<repository::RepositoryInfo>,
     pub semantic_analysis: Option<SemanticAnalysis>,
     pub embeddings: Option<VectorStore>,
+    #[serde(default)]
+    pub lexical_index: Option<LexicalIndex>,
+    #[serde(default)]
+    pub references: Option<ReferenceIndex>,
     pub metadata: ContextMetadata,
 }
 
@@ -46,6 +64,9 @@ pub struct FileContext {
     pub imports: Vec<String>,
     pub exports: Vec<String>,
     pub relationships: Vec<FileRelationship>,
+    /// Change history from git, when the codebase is in a repository
+    #[serde(default)]
+    pub history: Option<history::FileHistory>,
 }
 
 /// Relationship between files in the codebase
@@ -71,13 +92,17 @@ pub enum RelationshipType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dependency {
     pub name: String,
+    /// Version requirement as declared in the manifest
     pub version: Option<String>,
+    /// Exact version pinned by the lockfile, if one was found
+    #[serde(default)]
+    pub resolved_version: Option<String>,
     pub dependency_type: DependencyType,
     pub source: DependencySource,
 }
 
 /// Types of dependencies
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DependencyType {
     Runtime,
     Development,
@@ -86,9 +111,11 @@ pub enum DependencyType {
 }
 
 /// Source of dependency information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DependencySource {
     PackageManager(String), // npm, cargo, pip, etc.
+    Path(PathBuf),
+    Git(String),
     System,
     Manual,
 }
@@ -135,6 +162,8 @@ impl Default for CodebaseContext {
             repository_info: None,
             semantic_analysis: None,
             embeddings: None,
+            lexical_index: None,
+            references: None,
             metadata: ContextMetadata::default(),
         }
     }
@@ -147,9 +176,12 @@ pub struct ContextManager {
     repository: repository::RepositoryAnalyzer,
     semantic_analyzer: SemanticAnalyzer,
     embedding_provider: Option<Box<dyn EmbeddingProvider>>,
+    /// Number of chunks sent to the embedding provider per request
+    embedding_batch_size: usize,
     code_chunker: CodeChunker,
     cache: HashMap<PathBuf, CodebaseContext>,
     semantic_cache: HashMap<PathBuf, SemanticAnalysis>,
+    language_servers: Option<std::sync::Arc<crate::lsp::LanguageServerPool>>,
 }
 
 /// Configuration for context analysis
@@ -159,12 +191,71 @@ pub struct AnalysisConfig {
     pub exclude_patterns: Vec<String>,
     pub max_file_size_mb: usize,
     pub follow_symlinks: bool,
+    /// Skip files excluded by `.gitignore`, `.git/info/exclude` and global excludes
+    pub respect_gitignore: bool,
+    /// Maximum number of files walked and analyzed concurrently
+    pub max_concurrency: usize,
     pub analyze_dependencies: bool,
     pub deep_analysis: bool,
     pub generate_embeddings: bool,
+    pub build_lexical_index: bool,
+    pub resolve_references: bool,
+    /// Attach churn, authorship and co-change signals from git history
+    pub analyze_history: bool,
+    pub persist_index: bool,
     pub cache_results: bool,
 }
 
+/// Result from hybrid code search
+#[derive(Debug, Clone)]
+pub struct HybridSearchResult {
+    pub chunk: CodeChunk,
+    /// Reciprocal rank fusion score
+    pub score: f32,
+    pub lexical_score: Option<f32>,
+    pub vector_score: Option<f32>,
+}
+
+impl HybridSearchResult {
+    fn new(chunk: CodeChunk) -> Self {
+        Self {
+            chunk,
+            score: 0.0,
+            lexical_score: None,
+            vector_score: None,
+        }
+    }
+}
+
+/// Files affected by an incremental context update
+#[derive(Debug, Clone, Default)]
+pub struct ContextUpdateSummary {
+    pub added: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl ContextUpdateSummary {
+    /// Whether the update changed anything
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+
+    /// Number of affected files
+    pub fn len(&self) -> usize {
+        self.added.len() + self.updated.len() + self.removed.len()
+    }
+
+    /// Each affected file with its change type: `added`, `modified` or `removed`
+    pub fn changes(&self) -> impl Iterator<Item = (&PathBuf, &'static str)> {
+        self.added
+            .iter()
+            .map(|path| (path, "added"))
+            .chain(self.updated.iter().map(|path| (path, "modified")))
+            .chain(self.removed.iter().map(|path| (path, "removed")))
+    }
+}
+
 /// Errors that can occur during context analysis
 #[derive(Debug, thiserror::Error)]
 pub enum ContextError {
@@ -196,9 +287,11 @@ impl ContextManager {
             repository: repository::RepositoryAnalyzer::new()?,
             semantic_analyzer: SemanticAnalyzer::new(),
             embedding_provider: None,
+            embedding_batch_size: crate::config::EmbeddingProviderConfig::default().batch_size,
             code_chunker: CodeChunker::new(EmbeddingConfig::default()),
             cache: HashMap::new(),
             semantic_cache: HashMap::new(),
+            language_servers: None,
         })
     }
 
@@ -225,7 +318,7 @@ impl ContextManager {
 
         // Analyze the file structure and content
         println!("Analyzing file structure and content for: {}", path_str);
-        let files = match self.analyzer.analyze_files(&path, &config).await {
+        let mut files = match self.analyzer.analyze_files(&path, &config).await {
             Ok(files) => {
                 println!("Successfully analyzed {} files", files.len());
                 files
@@ -236,10 +329,31 @@ impl ContextManager {
             }
         };
 
+        // Attach churn, authorship and co-change signals from git
+        if config.analyze_history {
+            let root = path.clone();
+            let history = tokio::task::spawn_blocking(move || {
+                history::HistoryAnalyzer::default().analyze(&root)
+            })
+            .await;
+            match history {
+                Ok(Ok(index)) => {
+                    println!(
+                        "Analyzed {} commits of history for {} files",
+                        index.commits_analyzed,
+                        index.files.len()
+                    );
+                    index.attach(&mut files);
+                }
+                Ok(Err(e)) => tracing::debug!("No history for {}: {}", path_str, e),
+                Err(e) => tracing::warn!("History analysis failed for {}: {}", path_str, e),
+            }
+        }
+
         // Build symbol index
         println!("Building symbol index for {} files", files.len());
 
-        let symbols = match self.indexer.index_symbols(&files).await {
+        let mut symbols = match self.indexer.index_symbols(&files).await {
             Ok(symbols) => {
                 println!("Successfully indexed {} symbols", symbols.total_symbols());
                 symbols
@@ -250,6 +364,20 @@ impl ContextManager {
             }
         };
 
+        // Link call sites, imports and impls to their definitions
+        let references = if config.resolve_references {
+            let index = self.build_reference_index(&files).await;
+            index.apply_to_symbols(&mut symbols);
+            println!(
+                "Resolved {} references to {} definitions",
+                index.reference_count(),
+                index.definition_count()
+            );
+            Some(index)
+        } else {
+            None
+        };
+
         // Analyze dependencies
         let dependencies = if config.analyze_dependencies {
             println!("Analyzing dependencies for: {}", path_str);
@@ -283,6 +411,8 @@ impl ContextManager {
                 repository_info: repository_info.clone(),
                 semantic_analysis: None,
                 embeddings: None, // Will be filled later
+                lexical_index: None,
+                references: None,
                 metadata: ContextMetadata::default(), // Temporary metadata
             };
 
@@ -325,33 +455,27 @@ impl ContextManager {
             None
         };
 
-        // Generate embeddings if enabled
-        let embeddings = if config.generate_embeddings && self.embedding_provider.is_some() {
-            println!("Generating code embeddings for: {}", path_str);
-            let embeddings_start = std::time::Instant::now();
-            
-            match self.generate_embeddings_for_files(&files).await {
-                Ok(vector_store) => {
-                    let embeddings_duration = embeddings_start.elapsed();
-                    println!(
-                        "Embeddings generated in {}ms with {} vectors",
-                        embeddings_duration.as_millis(),
-                        vector_store.len()
-                    );
-                    Some(vector_store)
-                }
+        let wants_embeddings = config.generate_embeddings && self.embedding_provider.is_some();
+        let (lexical_index, embeddings) = if config.persist_index
+            && (wants_embeddings || config.build_lexical_index)
+        {
+            // Reuse the on-disk index and only re-process changed files
+            match self.sync_persistent_index(&path, &files, wants_embeddings).await {
+                Ok(index) => (
+                    config
+                        .build_lexical_index
+                        .then(|| index.to_lexical_index(Bm25Config::default())),
+                    wants_embeddings.then(|| index.to_vector_store(EmbeddingConfig::default())),
+                ),
                 Err(e) => {
-                    println!(
-                        "Embeddings generation failed for {}: {} (took {}ms)",
-                        path_str,
-                        e,
-                        embeddings_start.elapsed().as_millis()
-                    );
-                    None
+                    println!("Persistent index unavailable for {}: {}", path_str, e);
+                    self.build_retrieval_indexes(&files, config.build_lexical_index, wants_embeddings)
+                        .await
                 }
             }
         } else {
-            None
+            self.build_retrieval_indexes(&files, config.build_lexical_index, wants_embeddings)
+                .await
         };
 
         let analysis_duration = start_time.elapsed();
@@ -390,6 +514,8 @@ impl ContextManager {
             repository_info,
             semantic_analysis,
             embeddings,
+            lexical_index,
+            references,
             metadata,
         };
 
@@ -451,6 +577,13 @@ impl ContextManager {
                     related_files.insert(relationship.target_file.clone());
                 }
             }
+
+            // Follow symbol-level references in both directions
+            if relationship_types.contains(&RelationshipType::References) {
+                if let Some(references) = &context.references {
+                    related_files.extend(references.related_files(&file_context.path));
+                }
+            }
         }
 
         related_files.into_iter().collect()
@@ -466,81 +599,182 @@ impl ContextManager {
         context.symbols.search(query, symbol_types)
     }
 
-    /// Update context for changed files
+    /// Update context for changed files.
+    ///
+    /// Paths that no longer exist are removed from the context. Existing files
+    /// are re-analyzed, and files whose content hash is unchanged are skipped.
+    /// The symbol index, file relationships, lexical index and embeddings are
+    /// refreshed for everything that changed.
     pub async fn update_context(
         &mut self,
         changed_files: &[PathBuf],
         context: &mut CodebaseContext,
         config: &AnalysisConfig,
-    ) -> Result<(), ContextError> {
+    ) -> Result<ContextUpdateSummary, ContextError> {
         let update_start = std::time::Instant::now();
-        let changed_files_count = changed_files.len();
+        let mut summary = ContextUpdateSummary::default();
 
-        println!(
+        tracing::info!(
             "Updating context for {} changed files in {}",
-            changed_files_count,
+            changed_files.len(),
             context.root_path.to_string_lossy()
         );
 
-        // Re-analyze changed files
-        let updated_files = match self
+        // A removed path may be a file or a whole directory
+        let (existing, missing): (Vec<PathBuf>, Vec<PathBuf>) =
+            changed_files.iter().cloned().partition(|path| path.exists());
+        summary.removed = context
+            .files
+            .iter()
+            .filter(|file| missing.iter().any(|path| file.path.starts_with(path)))
+            .map(|file| file.path.clone())
+            .collect();
+
+        // Re-analyze changed files, skipping those whose content is unchanged
+        let updated_files: Vec<FileContext> = match self
             .analyzer
-            .analyze_specific_files(changed_files, config)
+            .analyze_specific_files(&context.root_path, &existing, config)
             .await
         {
-            Ok(files) => {
-                println!("Successfully re-analyzed {} changed files", files.len());
-                files
-            }
+            Ok(files) => files
+                .into_iter()
+                .filter(|file| {
+                    !context
+                        .files
+                        .iter()
+                        .any(|f| f.path == file.path && f.content_hash == file.content_hash)
+                })
+                .collect(),
             Err(e) => {
-                println!("Failed to re-analyze changed files: {}", e);
+                tracing::warn!("Failed to re-analyze changed files: {}", e);
                 return Err(e);
             }
         };
 
-        let mut updated_count = 0;
-        let mut added_count = 0;
+        if updated_files.is_empty() && summary.removed.is_empty() {
+            return Ok(summary);
+        }
+
+        // Drop removed files and every relationship that points at them
+        let removed: HashSet<&PathBuf> = summary.removed.iter().collect();
+        context.files.retain(|file| !removed.contains(&file.path));
+        for file in &mut context.files {
+            file.relationships
+                .retain(|relationship| !removed.contains(&relationship.target_file));
+        }
+        for path in &summary.removed {
+            context.symbols.remove_file_symbols(path);
+            if let Some(index) = context.lexical_index.as_mut() {
+                index.remove_file(path);
+            }
+            if let Some(store) = context.embeddings.as_mut() {
+                store.remove_file_embeddings(path);
+            }
+        }
+
+        // Refresh retrieval indexes for the changed files
+        let needs_chunks = context.lexical_index.is_some()
+            || (context.embeddings.is_some() && self.embedding_provider.is_some());
+        let chunks = if needs_chunks {
+            self.chunk_files(&updated_files).await
+        } else {
+            Vec::new()
+        };
+
+        if let Some(index) = context.lexical_index.as_mut() {
+            for updated_file in &updated_files {
+                index.remove_file(&updated_file.path);
+            }
+            for chunk in &chunks {
+                index.add_chunk(chunk.clone());
+            }
+        }
+
+        if self.embedding_provider.is_some() {
+            if let Some(store) = context.embeddings.as_mut() {
+                for updated_file in &updated_files {
+                    store.remove_file_embeddings(&updated_file.path);
+                }
+                let vectors = self.embed_chunks(&chunks).await;
+                for (chunk, vector) in chunks.into_iter().zip(vectors) {
+                    if let Some(vector) = vector {
+                        let embedding = persistent_index::record_embedding(&chunk, vector);
+                        store.add_embedding(chunk, embedding);
+                    }
+                }
+                store.build_search_backend();
+            }
+        } else if let Some(store) = context.embeddings.as_mut() {
+            // Without a provider the old vectors would describe stale content
+            for updated_file in &updated_files {
+                store.remove_file_embeddings(&updated_file.path);
+            }
+        }
+
+        // Re-index symbols for updated files
+        if let Err(e) = self
+            .indexer
+            .update_symbols(&updated_files, &mut context.symbols)
+            .await
+        {
+            tracing::warn!("Failed to update symbol index: {}", e);
+            return Err(e);
+        }
+
+        if let Some(references) = context.references.as_mut() {
+            for path in &summary.removed {
+                references.remove_file(path);
+            }
+            for updated_file in &updated_files {
+                match tokio::fs::read_to_string(&updated_file.path).await {
+                    Ok(content) => references.update_file(&updated_file.path, &updated_file.language, &content),
+                    Err(_) => references.remove_file(&updated_file.path),
+                }
+            }
+            references.resolve();
+            references.apply_to_symbols(&mut context.symbols);
+        }
 
         // Update the context
-        for updated_file in updated_files {
+        for mut updated_file in updated_files {
             if let Some(existing_file) = context
                 .files
                 .iter_mut()
                 .find(|f| f.path == updated_file.path)
             {
+                summary.updated.push(updated_file.path.clone());
+                // History only changes with commits, so keep what was computed
+                updated_file.history = existing_file.history.take();
                 *existing_file = updated_file;
-                updated_count += 1;
             } else {
+                summary.added.push(updated_file.path.clone());
                 context.files.push(updated_file);
-                added_count += 1;
             }
         }
 
-        // Re-index symbols for updated files
-        match self
-            .indexer
-            .update_symbols(&context.files, &mut context.symbols)
-            .await
-        {
-            Ok(()) => {
-                println!("Symbol index updated successfully");
-            }
-            Err(e) => {
-                println!("Failed to update symbol index: {}", e);
-                return Err(e);
-            }
+        let languages = Self::count_languages(&context.files);
+        context.metadata.total_files = context.files.len();
+        context.metadata.total_lines = context.files.iter().map(|f| f.line_count).sum();
+        context.metadata.total_size_bytes = context.files.iter().map(|f| f.size_bytes).sum();
+        context.metadata.indexed_symbols = context.symbols.total_symbols();
+        context.metadata.languages = languages.clone();
+        context.metadata.language_breakdown = languages;
+
+        // Cached analyses of this codebase are now stale
+        if self.cache.contains_key(&context.root_path) {
+            self.cache.insert(context.root_path.clone(), context.clone());
         }
-
-        let update_duration = update_start.elapsed();
-
-        println!(
-            "Context update completed in {}ms: {} files updated, {} files added",
-            update_duration.as_millis(),
-            updated_count,
-            added_count
+        self.semantic_cache.remove(&context.root_path);
+
+        tracing::info!(
+            "Context update completed in {}ms: {} files updated, {} added, {} removed",
+            update_start.elapsed().as_millis(),
+            summary.updated.len(),
+            summary.added.len(),
+            summary.removed.len()
         );
 
-        Ok(())
+        Ok(summary)
     }
 
     /// Helper function to count languages in files
@@ -609,63 +843,256 @@ impl ContextManager {
         self.embedding_provider = Some(provider);
     }
 
-    /// Generate embeddings for a set of files
-    async fn generate_embeddings_for_files(
+    /// Set how many chunks are embedded per provider request
+    pub fn set_embedding_batch_size(&mut self, batch_size: usize) {
+        self.embedding_batch_size = batch_size.max(1);
+    }
+
+    /// Use language servers for diagnostics and signatures when packing context
+    pub fn set_language_servers(
+        &mut self,
+        language_servers: std::sync::Arc<crate::lsp::LanguageServerPool>,
+    ) {
+        self.language_servers = Some(language_servers);
+    }
+
+    pub fn language_servers(&self) -> Option<std::sync::Arc<crate::lsp::LanguageServerPool>> {
+        self.language_servers.clone()
+    }
+
+    /// Build lexical and vector indexes in memory from scratch
+    async fn build_retrieval_indexes(
         &self,
         files: &[FileContext],
-    ) -> Result<VectorStore, ContextError> {
-        let provider = self.embedding_provider.as_ref()
-            .ok_or_else(|| ContextError::AnalysisFailed("No embedding provider configured".to_string()))?;
-            
-        let mut vector_store = VectorStore::new(EmbeddingConfig::default());
-        
+        build_lexical: bool,
+        wants_embeddings: bool,
+    ) -> (Option<LexicalIndex>, Option<VectorStore>) {
+        // Chunk file contents once for both lexical and vector retrieval
+        let chunks = if wants_embeddings || build_lexical {
+            self.chunk_files(files).await
+        } else {
+            Vec::new()
+        };
+
+        let lexical_index = if build_lexical {
+            let mut index = LexicalIndex::new(Bm25Config::default());
+            for chunk in &chunks {
+                index.add_chunk(chunk.clone());
+            }
+            println!(
+                "Lexical index built with {} chunks and {} terms",
+                index.len(),
+                index.vocabulary_size()
+            );
+            Some(index)
+        } else {
+            None
+        };
+
+        // Generate embeddings if enabled
+        let embeddings = if wants_embeddings {
+            let embeddings_start = std::time::Instant::now();
+
+            match self.generate_embeddings_for_chunks(chunks).await {
+                Ok(vector_store) => {
+                    println!(
+                        "Embeddings generated in {}ms with {} vectors",
+                        embeddings_start.elapsed().as_millis(),
+                        vector_store.len()
+                    );
+                    Some(vector_store)
+                }
+                Err(e) => {
+                    println!(
+                        "Embeddings generation failed: {} (took {}ms)",
+                        e,
+                        embeddings_start.elapsed().as_millis()
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        (lexical_index, embeddings)
+    }
+
+    /// Extract and resolve cross-references for the source files of a codebase
+    async fn build_reference_index(&self, files: &[FileContext]) -> ReferenceIndex {
+        let mut index = ReferenceIndex::new();
+
+        for file in files {
+            if !syntax_chunker::SyntaxChunker::supports(&file.language) {
+                continue;
+            }
+            match tokio::fs::read_to_string(&file.path).await {
+                Ok(content) => index.update_file(&file.path, &file.language, &content),
+                Err(e) => tracing::debug!("Skipping references in {}: {}", file.path.display(), e),
+            }
+        }
+
+        index.resolve();
+        index
+    }
+
+    /// Bring the on-disk index under `.devkit/index/` in line with the current files.
+    ///
+    /// Files whose content hash is unchanged are served from the index; only new
+    /// or modified files are chunked and embedded again.
+    async fn sync_persistent_index(
+        &self,
+        root: &PathBuf,
+        files: &[FileContext],
+        wants_embeddings: bool,
+    ) -> Result<PersistentIndex, ContextError> {
+        let index_start = std::time::Instant::now();
+        let model = self
+            .embedding_provider
+            .as_ref()
+            .filter(|_| wants_embeddings)
+            .map(|provider| provider.model_name());
+
+        let (mut index, report) =
+            PersistentIndex::open(&PersistentIndex::default_dir(root), model.as_deref())
+                .map_err(|e| ContextError::CacheError(format!("Failed to open index: {}", e)))?;
+
+        if let Some(reason) = &report.reset_reason {
+            println!("Rebuilding code index: {}", reason);
+        }
+        if !report.corrupted_segments.is_empty() {
+            println!(
+                "Discarded {} corrupted index segments; {} files will be re-indexed",
+                report.corrupted_segments.len(),
+                report.invalidated_files.len()
+            );
+        }
+
+        let indexable: Vec<&FileContext> = files.iter().filter(|f| Self::is_text_file(&f.path)).collect();
+        let present: HashSet<&PathBuf> = indexable.iter().map(|f| &f.relative_path).collect();
+        index.retain_files(|path| present.contains(&path.to_path_buf()));
+
+        let mut reindexed = 0;
+        for file in &indexable {
+            if !index.needs_update(&file.relative_path, &file.content_hash, wants_embeddings) {
+                continue;
+            }
+
+            let chunks = self.chunk_files(std::slice::from_ref(*file)).await;
+            let vectors = if wants_embeddings {
+                self.embed_chunks(&chunks).await
+            } else {
+                vec![None; chunks.len()]
+            };
+
+            let records = chunks
+                .into_iter()
+                .zip(vectors)
+                .map(|(chunk, vector)| IndexRecord { chunk, vector })
+                .collect();
+
+            index
+                .update_file(&file.relative_path, &file.content_hash, records)
+                .map_err(|e| ContextError::CacheError(format!("Failed to update index: {}", e)))?;
+            reindexed += 1;
+        }
+
+        index
+            .save()
+            .map_err(|e| ContextError::CacheError(format!("Failed to save index: {}", e)))?;
+
+        let stats = index.stats();
+        println!(
+            "Code index synced in {}ms: {} files re-indexed, {} reused, {} chunks in {} segments",
+            index_start.elapsed().as_millis(),
+            reindexed,
+            stats.files.saturating_sub(reindexed),
+            stats.chunks,
+            stats.segments
+        );
+
+        Ok(index)
+    }
+
+    /// Read and chunk the text files of a codebase for retrieval
+    async fn chunk_files(&self, files: &[FileContext]) -> Vec<CodeChunk> {
+        let mut chunks = Vec::new();
+
         for file in files {
             // Only process text files that can be chunked
-            if Self::is_text_file(&file.path) {
-                // Read file content - in production, you'd want to read from the filesystem
-                // For now, we'll create a mock content based on the file context
-                let content = format!(
-                    "// File: {}\n// Language: {}\n// Symbols: {:?}\n// Imports: {:?}\n",
-                    file.path.display(),
-                    file.language,
-                    file.symbols,
-                    file.imports
-                );
-                
-                // Chunk the file
-                let chunks = self.code_chunker.chunk_file(&file.path, &content, &file.language);
-                
-                // Generate embeddings for each chunk
-                for chunk in chunks {
-                    match provider.embed_text(&chunk.content).await {
-                        Ok(vector) => {
-                            let embedding = embeddings::CodeEmbedding {
-                                chunk_id: chunk.id.clone(),
-                                vector,
-                                metadata: embeddings::EmbeddingMetadata {
-                                    file_path: chunk.file_path.clone(),
-                                    language: chunk.language.clone(),
-                                    chunk_type: chunk.chunk_type.clone(),
-                                    symbols: chunk.symbols.clone(),
-                                    content_hash: md5::compute(chunk.content.as_bytes())
-                                        .iter()
-                                        .map(|b| format!("{:02x}", b))
-                                        .collect::<Vec<_>>()
-                                        .join(""),
-                                },
-                                created_at: chrono::Utc::now(),
-                            };
-                            
-                            vector_store.add_embedding(chunk, embedding);
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to generate embedding for chunk {}: {}", chunk.id, e);
-                        }
-                    }
+            if !Self::is_text_file(&file.path) {
+                continue;
+            }
+
+            let content = match tokio::fs::read_to_string(&file.path).await {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::debug!("Falling back to file summary for {}: {}", file.path.display(), e);
+                    format!(
+                        "// File: {}\n// Language: {}\n// Symbols: {:?}\n// Imports: {:?}\n",
+                        file.path.display(),
+                        file.language,
+                        file.symbols.iter().map(|s| &s.name).collect::<Vec<_>>(),
+                        file.imports
+                    )
+                }
+            };
+
+            chunks.extend(self.code_chunker.chunk_file(&file.path, &content, &file.language));
+        }
+
+        chunks
+    }
+
+    /// Embed chunks in batches, yielding `None` for chunks that failed
+    async fn embed_chunks(&self, chunks: &[CodeChunk]) -> Vec<Option<Vec<f32>>> {
+        let Some(provider) = self.embedding_provider.as_ref() else {
+            return vec![None; chunks.len()];
+        };
+
+        let mut vectors = Vec::with_capacity(chunks.len());
+
+        // Generate embeddings in batches so providers can amortize requests
+        for batch in chunks.chunks(self.embedding_batch_size) {
+            let texts: Vec<String> = batch.iter().map(|chunk| chunk.content.clone()).collect();
+            match provider.embed_batch(&texts).await {
+                Ok(batch_vectors) if batch_vectors.len() == batch.len() => {
+                    vectors.extend(batch_vectors.into_iter().map(Some));
                 }
+                Ok(_) => {
+                    tracing::warn!("Embedding provider returned a partial batch of {} chunks", batch.len());
+                    vectors.extend(std::iter::repeat(None).take(batch.len()));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to generate embeddings for {} chunks: {}", batch.len(), e);
+                    vectors.extend(std::iter::repeat(None).take(batch.len()));
+                }
+            }
+        }
+
+        vectors
+    }
+
+    /// Generate embeddings for a set of chunks
+    async fn generate_embeddings_for_chunks(
+        &self,
+        chunks: Vec<CodeChunk>,
+    ) -> Result<VectorStore, ContextError> {
+        if self.embedding_provider.is_none() {
+            return Err(ContextError::AnalysisFailed("No embedding provider configured".to_string()));
+        }
+            
+        let mut vector_store = VectorStore::new(EmbeddingConfig::default());
+        let vectors = self.embed_chunks(&chunks).await;
+
+        for (chunk, vector) in chunks.into_iter().zip(vectors) {
+            if let Some(vector) = vector {
+                let embedding = persistent_index::record_embedding(&chunk, vector);
+                vector_store.add_embedding(chunk, embedding);
             }
         }
-        
+
+        vector_store.build_search_backend();
         Ok(vector_store)
     }
     
@@ -683,25 +1110,106 @@ impl ContextManager {
         }
     }
     
-    /// Search for similar code using embeddings
+    /// Search for similar code with hybrid lexical and vector retrieval.
+    ///
+    /// BM25 results from the lexical index and cosine results from the vector
+    /// store are fused with reciprocal rank fusion. Either source may be
+    /// missing; at least one must be available.
     pub async fn search_similar_code(
         &self,
         query: &str,
         context: &CodebaseContext,
         max_results: Option<usize>,
-    ) -> Result<Vec<embeddings::SimilarityResult>, ContextError> {
-        let provider = self.embedding_provider.as_ref()
-            .ok_or_else(|| ContextError::AnalysisFailed("No embedding provider configured".to_string()))?;
-            
-        let embeddings = context.embeddings.as_ref()
-            .ok_or_else(|| ContextError::AnalysisFailed("No embeddings available in context".to_string()))?;
-            
-        // Generate embedding for the query
-        let query_embedding = provider.embed_text(query).await
-            .map_err(|e| ContextError::AnalysisFailed(format!("Query embedding failed: {}", e)))?;
-            
-        // Search for similar chunks
-        Ok(embeddings.find_similar(&query_embedding, max_results))
+    ) -> Result<Vec<HybridSearchResult>, ContextError> {
+        if context.lexical_index.is_none() && context.embeddings.is_none() {
+            return Err(ContextError::AnalysisFailed(
+                "No lexical index or embeddings available in context".to_string(),
+            ));
+        }
+
+        let max_results = max_results.unwrap_or(EmbeddingConfig::default().max_results);
+        // Retrieve deeper than requested so fusion has candidates to reorder
+        let candidate_count = max_results * 3;
+
+        let lexical_results = context
+            .lexical_index
+            .as_ref()
+            .map(|index| index.search(query, Some(candidate_count)))
+            .unwrap_or_default();
+
+        let vector_results = match (self.embedding_provider.as_ref(), context.embeddings.as_ref()) {
+            (Some(provider), Some(store)) => {
+                // Generate embedding for the query
+                let query_embedding = provider.embed_text(query).await
+                    .map_err(|e| ContextError::AnalysisFailed(format!("Query embedding failed: {}", e)))?;
+                store.find_similar(&query_embedding, Some(candidate_count))
+            }
+            _ => Vec::new(),
+        };
+
+        let mut candidates: HashMap<String, HybridSearchResult> = HashMap::new();
+        for result in &lexical_results {
+            candidates
+                .entry(result.chunk.id.clone())
+                .or_insert_with(|| HybridSearchResult::new(result.chunk.clone()))
+                .lexical_score = Some(result.score);
+        }
+        for result in &vector_results {
+            candidates
+                .entry(result.chunk.id.clone())
+                .or_insert_with(|| HybridSearchResult::new(result.chunk.clone()))
+                .vector_score = Some(result.similarity_score);
+        }
+
+        let rankings = vec![
+            lexical_results.iter().map(|r| r.chunk.id.clone()).collect::<Vec<_>>(),
+            vector_results.iter().map(|r| r.chunk.id.clone()).collect::<Vec<_>>(),
+        ];
+
+        Ok(lexical::reciprocal_rank_fusion(&rankings, lexical::DEFAULT_RRF_K)
+            .into_iter()
+            .take(max_results)
+            .filter_map(|(chunk_id, score)| {
+                candidates.remove(&chunk_id).map(|mut result| {
+                    result.score = score;
+                    result
+                })
+            })
+            .collect())
+    }
+
+    /// Assemble prompt context for a task within a token budget.
+    ///
+    /// Ranks the applicable rules, target files, definitions the targets
+    /// reference, related tests and retrieval hits for the task, and packs
+    /// them greedily. Language servers, when set, add compiler diagnostics
+    /// for the targets. The returned manifest explains every decision.
+    pub async fn pack_context(
+        &self,
+        request: &PackRequest,
+        context: &CodebaseContext,
+        rules: Option<&crate::config::rules::RulesManager>,
+    ) -> Result<PackedContext, ContextError> {
+        let retrieval_hits = if request.max_retrieval_hits > 0 && !request.task.trim().is_empty() {
+            match self
+                .search_similar_code(&request.task, context, Some(request.max_retrieval_hits))
+                .await
+            {
+                Ok(hits) => hits,
+                Err(e) => {
+                    tracing::debug!("Skipping retrieval for context packing: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut packer = ContextPacker::new(request, context);
+        if let Some(language_servers) = &self.language_servers {
+            packer = packer.with_language_servers(language_servers);
+        }
+        Ok(packer.pack(rules, &retrieval_hits).await)
     }
 
     /// Analyze directory with optional breakdown for profiling
@@ -738,6 +1246,7 @@ impl Default for AnalysisConfig {
                 "**/.svn/**".to_string(),
                 "**/.hg/**".to_string(),
                 "**/.cache/**".to_string(),
+                "**/.devkit/**".to_string(),
                 "**/.tmp/**".to_string(),
                 "**/tmp/**".to_string(),
                 // IDE and editor files
@@ -756,9 +1265,17 @@ impl Default for AnalysisConfig {
             ],
             max_file_size_mb: 5, // Reduced from 10MB to 5MB for better performance
             follow_symlinks: false,
+            respect_gitignore: true,
+            max_concurrency: std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4),
             analyze_dependencies: true,
             deep_analysis: false, // Changed to false for better performance
             generate_embeddings: false, // Disabled by default for performance
+            build_lexical_index: true,
+            resolve_references: true,
+            analyze_history: true,
+            persist_index: false,
             cache_results: true,
         }
     }
@@ -0,0 +1,803 @@
+//! Token-budgeted context packing for prompts
+//!
+//! `ContextPacker` assembles the context for a task from ranked candidates:
+//! applicable project rules, the target files, definitions the targets
+//! reference, related tests, files that usually change together with the
+//! targets and retrieval hits. Snippets from hotspot files rank slightly
+//! higher. With language servers attached, compiler diagnostics for the
+//! targets are packed as well and referenced definitions are outlined with
+//! the server's resolved signature. Candidates are added greedily
+//! in score order and a file that does not fit is replaced by its symbol
+//! outline. Every candidate ends up in a `ContextManifest` that records why it
+//! was considered and how much of it made it into the prompt.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::references::Definition;
+use super::{CodebaseContext, FileContext, HybridSearchResult, RelationshipType};
+use crate::config::rules::{RuleContext, RulesManager};
+use crate::lsp::client::hover_signature;
+use crate::lsp::convert::{format_diagnostic, name_range};
+use crate::lsp::LanguageServerPool;
+
+/// Default token budget for packed prompt context
+pub const DEFAULT_TOKEN_BUDGET: usize = 8000;
+
+/// Score added to snippets from the busiest file in the history
+const HOTSPOT_WEIGHT: f32 = 5.0;
+
+/// Rough token estimate used for budgeting (about four bytes per token)
+pub fn estimate_tokens(text: &str) -> usize {
+    (text.len() + 3) / 4
+}
+
+/// What to pack context for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackRequest {
+    /// Task description, also used as the retrieval query
+    pub task: String,
+    /// Files the task is about, absolute or relative to the codebase root
+    pub target_files: Vec<PathBuf>,
+    pub token_budget: usize,
+    /// Maximum number of retrieval hits to consider
+    pub max_retrieval_hits: usize,
+    /// Referenced definitions considered per target file
+    pub max_referenced_symbols: usize,
+    pub agent_type: Option<String>,
+    pub task_type: Option<String>,
+}
+
+impl PackRequest {
+    pub fn new(task: impl Into<String>, token_budget: usize) -> Self {
+        Self {
+            task: task.into(),
+            target_files: Vec::new(),
+            token_budget,
+            max_retrieval_hits: 8,
+            max_referenced_symbols: 12,
+            agent_type: None,
+            task_type: None,
+        }
+    }
+
+    pub fn with_target_files(mut self, target_files: Vec<PathBuf>) -> Self {
+        self.target_files = target_files;
+        self
+    }
+
+    pub fn with_agent_type(mut self, agent_type: impl Into<String>) -> Self {
+        self.agent_type = Some(agent_type.into());
+        self
+    }
+
+    pub fn with_task_type(mut self, task_type: impl Into<String>) -> Self {
+        self.task_type = Some(task_type.into());
+        self
+    }
+}
+
+/// Where a piece of packed context came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContextSource {
+    Rule,
+    /// Compiler diagnostics for a target from its language server
+    Diagnostics,
+    TargetFile,
+    ReferencedSymbol,
+    RelatedTest,
+    /// A file that usually changes in the same commits as a target
+    CoChangedFile,
+    RetrievalHit,
+}
+
+/// How much of a candidate made it into the prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Inclusion {
+    Full,
+    /// Symbol outline in place of the full file
+    Outline,
+    Omitted,
+}
+
+/// One candidate considered for the prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub source: ContextSource,
+    pub label: String,
+    pub file_path: Option<PathBuf>,
+    pub score: f32,
+    /// Why the candidate was considered
+    pub reason: String,
+    pub inclusion: Inclusion,
+    /// Tokens spent on the candidate, zero when omitted
+    pub tokens: usize,
+    /// Why the candidate was shortened or left out
+    pub note: Option<String>,
+}
+
+/// Explains what was packed into a prompt and why
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextManifest {
+    pub token_budget: usize,
+    pub tokens_used: usize,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl ContextManifest {
+    /// Entries that made it into the prompt
+    pub fn included(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries.iter().filter(|e| e.inclusion != Inclusion::Omitted)
+    }
+
+    /// Human-readable summary, one line per candidate
+    pub fn explain(&self) -> String {
+        let mut out = format!(
+            "Context: {} of {} tokens used\n",
+            self.tokens_used, self.token_budget
+        );
+        for entry in &self.entries {
+            let status = match entry.inclusion {
+                Inclusion::Full => "included",
+                Inclusion::Outline => "outline",
+                Inclusion::Omitted => "omitted",
+            };
+            out.push_str(&format!(
+                "  [{}] {} ({} tokens): {}",
+                status, entry.label, entry.tokens, entry.reason
+            ));
+            if let Some(note) = &entry.note {
+                out.push_str(&format!("; {}", note));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// A rendered piece of prompt context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackedSection {
+    pub source: ContextSource,
+    pub label: String,
+    pub inclusion: Inclusion,
+    pub text: String,
+}
+
+/// Context packed within a token budget
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackedContext {
+    pub sections: Vec<PackedSection>,
+    pub manifest: ContextManifest,
+}
+
+impl PackedContext {
+    /// Prompt text of all included sections, in ranking order
+    pub fn render(&self) -> String {
+        self.sections
+            .iter()
+            .map(|section| section.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+}
+
+/// A candidate before budgeting
+#[derive(Debug)]
+struct Candidate {
+    source: ContextSource,
+    label: String,
+    file_path: Option<PathBuf>,
+    /// Line span within `file_path`; `None` covers the whole file
+    span: Option<(usize, usize)>,
+    score: f32,
+    reason: String,
+    text: String,
+    outline: Option<String>,
+}
+
+/// Collects and ranks candidates, then packs them into the budget
+pub struct ContextPacker<'a> {
+    request: &'a PackRequest,
+    context: &'a CodebaseContext,
+    candidates: Vec<Candidate>,
+    file_contents: HashMap<PathBuf, Option<String>>,
+    language_servers: Option<&'a LanguageServerPool>,
+}
+
+impl<'a> ContextPacker<'a> {
+    pub fn new(request: &'a PackRequest, context: &'a CodebaseContext) -> Self {
+        Self {
+            request,
+            context,
+            candidates: Vec::new(),
+            file_contents: HashMap::new(),
+            language_servers: None,
+        }
+    }
+
+    /// Query language servers for target diagnostics and definition signatures
+    pub fn with_language_servers(mut self, language_servers: &'a LanguageServerPool) -> Self {
+        self.language_servers = Some(language_servers);
+        self
+    }
+
+    /// Gather every candidate and pack them
+    pub async fn pack(
+        mut self,
+        rules: Option<&RulesManager>,
+        retrieval_hits: &[HybridSearchResult],
+    ) -> PackedContext {
+        let targets: Vec<&FileContext> = self
+            .request
+            .target_files
+            .iter()
+            .filter_map(|path| self.find_file(path))
+            .collect();
+
+        if let Some(rules) = rules {
+            self.add_rules(rules, &targets);
+        }
+        for (position, target) in targets.iter().enumerate() {
+            self.add_target(target, position).await;
+            self.add_diagnostics(target).await;
+        }
+        for target in &targets {
+            self.add_referenced_symbols(target).await;
+            self.add_related_tests(target).await;
+            self.add_co_changed_files(target).await;
+        }
+        self.add_retrieval_hits(retrieval_hits);
+
+        self.fill_budget()
+    }
+
+    fn find_file(&self, path: &Path) -> Option<&'a FileContext> {
+        let absolute = self.context.root_path.join(path);
+        self.context
+            .files
+            .iter()
+            .find(|f| f.path == path || f.relative_path == path || f.path == absolute)
+    }
+
+    /// Hotspot score of a file from its change history
+    fn hotspot_score(&self, path: &Path) -> f32 {
+        self.find_file(path)
+            .and_then(|file| file.history.as_ref())
+            .map(|history| history.hotspot_score)
+            .unwrap_or(0.0)
+    }
+
+    fn relative<'p>(&self, path: &'p Path) -> &'p Path {
+        path.strip_prefix(&self.context.root_path).unwrap_or(path)
+    }
+
+    async fn read(&mut self, path: &Path) -> Option<String> {
+        if let Some(content) = self.file_contents.get(path) {
+            return content.clone();
+        }
+        let content = tokio::fs::read_to_string(path).await.ok();
+        self.file_contents.insert(path.to_path_buf(), content.clone());
+        content
+    }
+
+    fn add_rules(&mut self, rules: &RulesManager, targets: &[&FileContext]) {
+        let environment_vars: HashMap<String, String> = std::env::vars().collect();
+        let contexts: Vec<RuleContext> = if targets.is_empty() {
+            vec![(None, None)]
+        } else {
+            targets
+                .iter()
+                .map(|t| (Some(t.path.clone()), Some(t.language.clone())))
+                .collect()
+        }
+        .into_iter()
+        .map(|(file_path, language)| RuleContext {
+            current_directory: self.context.root_path.clone(),
+            file_path,
+            language,
+            agent_type: self.request.agent_type.clone(),
+            task_type: self.request.task_type.clone(),
+            environment_vars: environment_vars.clone(),
+        })
+        .collect();
+
+        let mut seen = HashSet::new();
+        for rule_context in &contexts {
+            for rule in rules.get_effective_rules(rule_context) {
+                if !seen.insert(rule.id.clone()) {
+                    continue;
+                }
+                self.candidates.push(Candidate {
+                    source: ContextSource::Rule,
+                    label: format!("Rule: {}", rule.name),
+                    file_path: None,
+                    span: None,
+                    // Rules are small and always apply, so they rank first
+                    score: 100.0 + rule.priority as i32 as f32 / 1000.0,
+                    reason: format!(
+                        "{:?} rule from {}",
+                        rule.priority,
+                        rule.metadata.source_file.display()
+                    ),
+                    text: format!("## Rule: {}\n{}\n", rule.name, rule.content),
+                    outline: None,
+                });
+            }
+        }
+    }
+
+    async fn add_target(&mut self, target: &FileContext, position: usize) {
+        let Some(content) = self.read(&target.path).await else {
+            return;
+        };
+        self.candidates.push(Candidate {
+            source: ContextSource::TargetFile,
+            label: self.relative(&target.path).display().to_string(),
+            file_path: Some(target.path.clone()),
+            span: None,
+            score: 90.0 - position as f32 * 0.1,
+            reason: "target file".to_string(),
+            text: file_section(self.relative(&target.path), &target.language, &content),
+            outline: outline(self.relative(&target.path), target),
+        });
+    }
+
+    async fn add_diagnostics(&mut self, target: &FileContext) {
+        let Some(language_servers) = self.language_servers else {
+            return;
+        };
+        let Some(content) = self.read(&target.path).await else {
+            return;
+        };
+        let Some(diagnostics) = language_servers.diagnostics(&target.path, &content).await else {
+            return;
+        };
+        if diagnostics.is_empty() {
+            return;
+        }
+
+        let path = self.relative(&target.path);
+        let lines: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| format!("- {}", format_diagnostic(path, diagnostic)))
+            .collect();
+        self.candidates.push(Candidate {
+            source: ContextSource::Diagnostics,
+            label: format!("Diagnostics: {}", path.display()),
+            file_path: None,
+            span: None,
+            // Small and about the code being changed, so ahead of the targets themselves
+            score: 95.0,
+            reason: format!(
+                "{} diagnostic{} reported by the language server",
+                diagnostics.len(),
+                if diagnostics.len() == 1 { "" } else { "s" }
+            ),
+            text: format!("## Diagnostics: {}\n{}\n", path.display(), lines.join("\n")),
+            outline: None,
+        });
+    }
+
+    /// Signature of a definition as resolved by its language server
+    async fn resolved_signature(&self, definition: &Definition, content: &str) -> Option<String> {
+        let language_servers = self.language_servers?;
+        let range = name_range(content, definition.line, &definition.name);
+        let hover = language_servers
+            .hover(&definition.file_path, content, range.start)
+            .await?;
+        hover_signature(&hover)
+    }
+
+    async fn add_referenced_symbols(&mut self, target: &FileContext) {
+        let Some(references) = self.context.references.as_ref() else {
+            return;
+        };
+
+        let referenced = references.referenced_definitions(&target.path);
+        for (definition, count) in referenced.into_iter().take(self.request.max_referenced_symbols) {
+            let Some(content) = self.read(&definition.file_path).await else {
+                continue;
+            };
+            let snippet: Vec<&str> = content
+                .lines()
+                .skip(definition.line.saturating_sub(1))
+                .take(definition.end_line + 1 - definition.line.max(1))
+                .collect();
+            let path = self.relative(&definition.file_path);
+            let hotspot = self.hotspot_score(&definition.file_path);
+            let signature = match self.resolved_signature(definition, &content).await {
+                Some(signature) => Some(signature),
+                None => snippet.first().map(|line| line.trim_end().to_string()),
+            };
+
+            self.candidates.push(Candidate {
+                source: ContextSource::ReferencedSymbol,
+                label: format!("{} ({}:{})", definition.qualified_name, path.display(), definition.line),
+                file_path: Some(definition.file_path.clone()),
+                span: Some((definition.line, definition.end_line)),
+                score: 50.0 + count.min(10) as f32 * 2.0 + HOTSPOT_WEIGHT * hotspot,
+                reason: hotspot_reason(
+                    format!(
+                        "referenced {} time{} from {}",
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        self.relative(&target.path).display()
+                    ),
+                    hotspot,
+                ),
+                text: snippet_section(path, definition.line, &definition.language, &snippet.join("\n")),
+                outline: signature.map(|signature| {
+                    snippet_section(path, definition.line, &definition.language, &signature)
+                }),
+            });
+        }
+    }
+
+    async fn add_related_tests(&mut self, target: &FileContext) {
+        // Test files point at the file they test; targets may also point at their tests
+        let mut tests: Vec<&FileContext> = self
+            .context
+            .files
+            .iter()
+            .filter(|file| {
+                file.relationships.iter().any(|r| {
+                    r.relationship_type == RelationshipType::Tests && r.target_file == target.path
+                })
+            })
+            .collect();
+        for relationship in &target.relationships {
+            if relationship.relationship_type == RelationshipType::Tests {
+                if let Some(file) = self.find_file(&relationship.target_file) {
+                    tests.push(file);
+                }
+            }
+        }
+
+        for test in tests {
+            if test.path == target.path {
+                continue;
+            }
+            let Some(content) = self.read(&test.path).await else {
+                continue;
+            };
+            let path = self.relative(&test.path);
+            self.candidates.push(Candidate {
+                source: ContextSource::RelatedTest,
+                label: path.display().to_string(),
+                file_path: Some(test.path.clone()),
+                span: None,
+                score: 45.0,
+                reason: format!("tests {}", self.relative(&target.path).display()),
+                text: file_section(path, &test.language, &content),
+                outline: outline(path, test),
+            });
+        }
+    }
+
+    async fn add_co_changed_files(&mut self, target: &FileContext) {
+        let Some(history) = target.history.as_ref() else {
+            return;
+        };
+
+        for co_change in &history.co_changes {
+            let Some(file) = self.find_file(&co_change.path) else {
+                continue;
+            };
+            let Some(content) = self.read(&file.path).await else {
+                continue;
+            };
+            let path = self.relative(&file.path);
+            self.candidates.push(Candidate {
+                source: ContextSource::CoChangedFile,
+                label: path.display().to_string(),
+                file_path: Some(file.path.clone()),
+                span: None,
+                score: 30.0 + 15.0 * co_change.confidence,
+                reason: format!(
+                    "changed together with {} in {} commits ({:.0}% of its changes)",
+                    self.relative(&target.path).display(),
+                    co_change.shared_commits,
+                    co_change.confidence * 100.0
+                ),
+                text: file_section(path, &file.language, &content),
+                outline: outline(path, file),
+            });
+        }
+    }
+
+    fn add_retrieval_hits(&mut self, hits: &[HybridSearchResult]) {
+        let best = hits.iter().map(|hit| hit.score).fold(0.0_f32, f32::max);
+
+        for hit in hits.iter().take(self.request.max_retrieval_hits) {
+            let chunk = &hit.chunk;
+            let path = self.relative(&chunk.file_path);
+            let relevance = if best > 0.0 { hit.score / best } else { 0.0 };
+            let hotspot = self.hotspot_score(&chunk.file_path);
+            let label = match &chunk.scope_path {
+                Some(scope) => format!("{} ({}:{})", scope, path.display(), chunk.start_line),
+                None => format!("{}:{}-{}", path.display(), chunk.start_line, chunk.end_line),
+            };
+
+            self.candidates.push(Candidate {
+                source: ContextSource::RetrievalHit,
+                label,
+                file_path: Some(chunk.file_path.clone()),
+                span: Some((chunk.start_line, chunk.end_line)),
+                score: 10.0 + 20.0 * relevance + HOTSPOT_WEIGHT * hotspot,
+                reason: hotspot_reason(
+                    format!("matches the task (fusion score {:.3})", hit.score),
+                    hotspot,
+                ),
+                text: snippet_section(path, chunk.start_line, &chunk.language, &chunk.content),
+                outline: None,
+            });
+        }
+    }
+
+    /// Greedily add candidates in score order until the budget is spent
+    fn fill_budget(mut self) -> PackedContext {
+        self.candidates
+            .sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+
+        let budget = self.request.token_budget;
+        let mut packed = PackedContext {
+            sections: Vec::new(),
+            manifest: ContextManifest {
+                token_budget: budget,
+                ..ContextManifest::default()
+            },
+        };
+        let mut whole_files: HashSet<PathBuf> = HashSet::new();
+        let mut spans: Vec<(PathBuf, usize, usize)> = Vec::new();
+
+        for candidate in self.candidates {
+            let remaining = budget - packed.manifest.tokens_used;
+            let covered = candidate.file_path.as_ref().and_then(|path| {
+                if whole_files.contains(path) {
+                    return Some("already included with its file");
+                }
+                let (start, end) = candidate.span?;
+                spans
+                    .iter()
+                    .any(|(p, s, e)| p == path && start <= *e && *s <= end)
+                    .then_some("overlaps an included snippet")
+            });
+
+            let full_tokens = estimate_tokens(&candidate.text);
+            let (inclusion, text, note) = if let Some(reason) = covered {
+                (Inclusion::Omitted, None, Some(reason.to_string()))
+            } else if full_tokens <= remaining {
+                (Inclusion::Full, Some(candidate.text), None)
+            } else if let Some(outline) = candidate
+                .outline
+                .filter(|outline| estimate_tokens(outline) <= remaining)
+            {
+                (
+                    Inclusion::Outline,
+                    Some(outline),
+                    Some(format!("full text needs {} tokens", full_tokens)),
+                )
+            } else {
+                (
+                    Inclusion::Omitted,
+                    None,
+                    Some(format!(
+                        "needs {} tokens, {} remaining",
+                        full_tokens, remaining
+                    )),
+                )
+            };
+
+            let tokens = text.as_deref().map(estimate_tokens).unwrap_or(0);
+            if inclusion == Inclusion::Full {
+                if let Some(path) = &candidate.file_path {
+                    match candidate.span {
+                        Some((start, end)) => spans.push((path.clone(), start, end)),
+                        None => {
+                            whole_files.insert(path.clone());
+                        }
+                    }
+                }
+            }
+
+            if let Some(text) = text {
+                packed.sections.push(PackedSection {
+                    source: candidate.source,
+                    label: candidate.label.clone(),
+                    inclusion,
+                    text,
+                });
+            }
+            packed.manifest.tokens_used += tokens;
+            packed.manifest.entries.push(ManifestEntry {
+                source: candidate.source,
+                label: candidate.label,
+                file_path: candidate.file_path,
+                score: candidate.score,
+                reason: candidate.reason,
+                inclusion,
+                tokens,
+                note,
+            });
+        }
+
+        packed
+    }
+}
+
+/// Mention notable churn in a candidate's reason
+fn hotspot_reason(reason: String, hotspot: f32) -> String {
+    if hotspot >= 0.5 {
+        format!("{}; hotspot ({:.2})", reason, hotspot)
+    } else {
+        reason
+    }
+}
+
+fn file_section(path: &Path, language: &str, content: &str) -> String {
+    format!("## {}\n```{}\n{}\n```\n", path.display(), language, content.trim_end())
+}
+
+fn snippet_section(path: &Path, line: usize, language: &str, content: &str) -> String {
+    format!(
+        "## {}:{}\n```{}\n{}\n```\n",
+        path.display(),
+        line,
+        language,
+        content.trim_end()
+    )
+}
+
+/// Symbol outline of a file: one line per symbol with its signature
+fn outline(path: &Path, file: &FileContext) -> Option<String> {
+    if file.symbols.is_empty() {
+        return None;
+    }
+
+    let mut symbols: Vec<_> = file.symbols.iter().collect();
+    symbols.sort_by_key(|symbol| symbol.line);
+
+    let lines: Vec<String> = symbols
+        .iter()
+        .map(|symbol| {
+            let signature = symbol
+                .signature
+                .clone()
+                .unwrap_or_else(|| format!("{} {}", symbol.symbol_type, symbol.name));
+            format!("{:>5}: {}", symbol.line, signature.trim())
+        })
+        .collect();
+
+    Some(format!(
+        "## {} (outline, {} lines)\n{}\n",
+        path.display(),
+        file.line_count,
+        lines.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::history::{CoChange, FileHistory};
+    use crate::context::{AnalysisConfig, ContextManager};
+
+    async fn analyze(root: &Path) -> (ContextManager, CodebaseContext) {
+        let config = AnalysisConfig {
+            persist_index: false,
+            ..AnalysisConfig::default()
+        };
+        let mut manager = ContextManager::new().unwrap();
+        let context = manager
+            .analyze_codebase(root.to_path_buf(), config)
+            .await
+            .unwrap();
+        (manager, context)
+    }
+
+    #[tokio::test]
+    async fn test_pack_includes_target_and_referenced_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(
+            root.join("util.rs"),
+            "pub fn helper(x: u32) -> u32 {\n    x * 2\n}\n\npub fn unused() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("main.rs"),
+            "fn main() {\n    let value = helper(21);\n    println!(\"{}\", value);\n}\n",
+        )
+        .unwrap();
+
+        let (manager, context) = analyze(root).await;
+        let request = PackRequest::new("double a value", 2000)
+            .with_target_files(vec![PathBuf::from("main.rs")]);
+        let packed = manager.pack_context(&request, &context, None).await.unwrap();
+
+        let target = &packed.manifest.entries[0];
+        assert_eq!(target.source, ContextSource::TargetFile);
+        assert_eq!(target.inclusion, Inclusion::Full);
+
+        let helper = packed
+            .manifest
+            .entries
+            .iter()
+            .find(|e| e.source == ContextSource::ReferencedSymbol)
+            .expect("referenced symbol candidate");
+        assert!(helper.label.contains("helper"));
+        assert_eq!(helper.inclusion, Inclusion::Full);
+        assert!(packed.render().contains("x * 2"));
+        assert!(!packed.render().contains("unused"));
+        assert!(packed.manifest.tokens_used <= 2000);
+    }
+
+    #[tokio::test]
+    async fn test_pack_falls_back_to_outline() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut source = String::new();
+        for i in 0..40 {
+            source.push_str(&format!(
+                "pub fn function_{i}(input: &str) -> String {{\n    let trimmed = input.trim();\n    let repeated = trimmed.repeat({i});\n    let upper = repeated.to_uppercase();\n    format!(\"{{}}-{{}}\", upper, trimmed)\n}}\n\n"
+            ));
+        }
+        std::fs::write(root.join("big.rs"), &source).unwrap();
+
+        let (manager, context) = analyze(root).await;
+        let request = PackRequest::new("", 800).with_target_files(vec![PathBuf::from("big.rs")]);
+        let packed = manager.pack_context(&request, &context, None).await.unwrap();
+
+        let entry = &packed.manifest.entries[0];
+        assert_eq!(entry.inclusion, Inclusion::Outline);
+        assert!(entry.note.is_some());
+        assert!(packed.render().contains("function_39"));
+        assert!(packed.manifest.tokens_used <= 800);
+        assert!(packed.manifest.explain().contains("[outline] big.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_pack_includes_co_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("schema.rs"), "pub struct User {\n    pub id: u64,\n}\n").unwrap();
+        std::fs::write(root.join("migration.sql"), "ALTER TABLE users ADD COLUMN id;\n").unwrap();
+
+        let (manager, mut context) = analyze(root).await;
+        let schema = context
+            .files
+            .iter_mut()
+            .find(|f| f.relative_path == Path::new("schema.rs"))
+            .unwrap();
+        schema.history = Some(FileHistory {
+            commits: 4,
+            co_changes: vec![CoChange {
+                path: PathBuf::from("migration.sql"),
+                shared_commits: 3,
+                confidence: 0.75,
+            }],
+            ..FileHistory::default()
+        });
+
+        let request = PackRequest::new("add an email field", 2000)
+            .with_target_files(vec![PathBuf::from("schema.rs")]);
+        let packed = manager.pack_context(&request, &context, None).await.unwrap();
+
+        let co_changed = packed
+            .manifest
+            .entries
+            .iter()
+            .find(|e| e.source == ContextSource::CoChangedFile)
+            .expect("co-changed file candidate");
+        assert_eq!(co_changed.label, "migration.sql");
+        assert_eq!(co_changed.inclusion, Inclusion::Full);
+        assert!(co_changed.reason.contains("3 commits"));
+        assert!(packed.render().contains("ALTER TABLE"));
+    }
+}
@@ -0,0 +1,623 @@
+//! Persistent, incremental on-disk index of code chunks and embeddings
+//!
+//! The index lives under `.devkit/index/` in the analyzed project:
+//!
+//! - `manifest.json` records every indexed file with its content hash, the
+//!   segment holding its chunks and the checksum of every segment.
+//! - `segments/*.jsonl` are append-only files with one `IndexRecord` per line.
+//!
+//! Each save writes the chunks of changed files into a new segment, so only
+//! changed files are re-chunked and re-embedded. Records superseded by newer
+//! segments are dropped by compaction, and segment checksums are verified on
+//! open so corrupted data is re-indexed instead of trusted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::embeddings::{CodeChunk, CodeEmbedding, EmbeddingConfig, EmbeddingMetadata, VectorStore};
+use super::lexical::{Bm25Config, LexicalIndex};
+use crate::codegen::journal::write_atomic;
+
+/// On-disk format version; bump when the layout changes
+pub const INDEX_FORMAT_VERSION: u32 = 2;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const SEGMENTS_DIR: &str = "segments";
+
+/// Compact when fewer than this fraction of stored records are live
+const COMPACTION_LIVE_RATIO: f32 = 0.5;
+/// Compact when the index has more segments than this
+const COMPACTION_MAX_SEGMENTS: usize = 16;
+
+/// A chunk with its optional embedding vector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexRecord {
+    pub chunk: CodeChunk,
+    pub vector: Option<Vec<f32>>,
+}
+
+/// Index entry for a single source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub content_hash: String,
+    pub segment: String,
+    pub chunk_ids: Vec<String>,
+    pub embedded: bool,
+    pub indexed_at: DateTime<Utc>,
+}
+
+/// Metadata about a segment file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub name: String,
+    pub checksum: String,
+    pub records: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexManifest {
+    format_version: u32,
+    embedding_model: Option<String>,
+    dimension: Option<usize>,
+    updated_at: DateTime<Utc>,
+    files: HashMap<PathBuf, IndexedFile>,
+    segments: Vec<SegmentInfo>,
+}
+
+impl IndexManifest {
+    fn new(embedding_model: Option<String>) -> Self {
+        Self {
+            format_version: INDEX_FORMAT_VERSION,
+            embedding_model,
+            dimension: None,
+            updated_at: Utc::now(),
+            files: HashMap::new(),
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// Problems found while opening or verifying an index
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    /// The whole index was discarded (format or embedding model changed)
+    pub reset_reason: Option<String>,
+    /// Segments that were missing, unreadable or failed their checksum
+    pub corrupted_segments: Vec<String>,
+    /// Files dropped from the index and due for re-indexing
+    pub invalidated_files: Vec<PathBuf>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.reset_reason.is_none() && self.corrupted_segments.is_empty() && self.invalidated_files.is_empty()
+    }
+}
+
+/// Summary statistics of an index
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStats {
+    pub files: usize,
+    pub chunks: usize,
+    pub embedded_chunks: usize,
+    pub segments: usize,
+    pub stored_records: usize,
+    pub embedding_model: Option<String>,
+    pub dimension: Option<usize>,
+}
+
+/// Errors from the persistent index
+#[derive(Debug, thiserror::Error)]
+pub enum IndexError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Dimension mismatch: index has {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+}
+
+/// Persistent chunk and embedding index for one project
+#[derive(Debug)]
+pub struct PersistentIndex {
+    dir: PathBuf,
+    manifest: IndexManifest,
+    records: HashMap<String, IndexRecord>,
+    /// Files updated since the last save
+    dirty_files: HashSet<PathBuf>,
+    removed_any: bool,
+}
+
+impl PersistentIndex {
+    /// Default index location for a project root
+    pub fn default_dir(project_root: &Path) -> PathBuf {
+        project_root.join(".devkit").join("index")
+    }
+
+    /// Open the index in `dir`, creating it if needed.
+    ///
+    /// An index written by a different format version or embedding model is
+    /// discarded. Segments that fail verification are dropped together with
+    /// the files they hold, so those files are re-indexed on the next update.
+    pub fn open(dir: &Path, embedding_model: Option<&str>) -> Result<(Self, IntegrityReport), IndexError> {
+        std::fs::create_dir_all(dir.join(SEGMENTS_DIR))?;
+
+        let mut report = IntegrityReport::default();
+        let embedding_model = embedding_model.filter(|m| !m.is_empty()).map(str::to_string);
+
+        let manifest = match std::fs::read(dir.join(MANIFEST_FILE)) {
+            Ok(bytes) => match serde_json::from_slice::<IndexManifest>(&bytes) {
+                Ok(manifest) if manifest.format_version != INDEX_FORMAT_VERSION => {
+                    report.reset_reason = Some(format!(
+                        "index format {} is not supported (expected {})",
+                        manifest.format_version, INDEX_FORMAT_VERSION
+                    ));
+                    IndexManifest::new(embedding_model.clone())
+                }
+                Ok(manifest) if embedding_model.is_some() && manifest.embedding_model.is_some() && manifest.embedding_model != embedding_model => {
+                    report.reset_reason = Some(format!(
+                        "embedding model changed from {} to {}",
+                        manifest.embedding_model.unwrap_or_default(),
+                        embedding_model.clone().unwrap_or_default()
+                    ));
+                    IndexManifest::new(embedding_model.clone())
+                }
+                Ok(mut manifest) => {
+                    if manifest.embedding_model.is_none() {
+                        manifest.embedding_model = embedding_model.clone();
+                    }
+                    manifest
+                }
+                Err(e) => {
+                    report.reset_reason = Some(format!("manifest is unreadable: {}", e));
+                    IndexManifest::new(embedding_model.clone())
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => IndexManifest::new(embedding_model.clone()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut index = Self {
+            dir: dir.to_path_buf(),
+            manifest,
+            records: HashMap::new(),
+            dirty_files: HashSet::new(),
+            removed_any: report.reset_reason.is_some(),
+        };
+
+        index.load_segments(&mut report);
+        Ok((index, report))
+    }
+
+    /// Load live records from every segment, dropping anything that fails verification
+    fn load_segments(&mut self, report: &mut IntegrityReport) {
+        let mut valid_segments = Vec::new();
+
+        // chunk id -> segment holding its current version
+        let live_chunks: HashMap<String, String> = self
+            .manifest
+            .files
+            .values()
+            .flat_map(|file| file.chunk_ids.iter().map(move |id| (id.clone(), file.segment.clone())))
+            .collect();
+
+        for segment in std::mem::take(&mut self.manifest.segments) {
+            match self.read_segment(&segment) {
+                Ok(records) => {
+                    for record in records {
+                        if live_chunks.get(&record.chunk.id) == Some(&segment.name) {
+                            self.records.insert(record.chunk.id.clone(), record);
+                        }
+                    }
+                    valid_segments.push(segment);
+                }
+                Err(reason) => {
+                    tracing::warn!("Dropping index segment {}: {}", segment.name, reason);
+                    report.corrupted_segments.push(segment.name.clone());
+                    self.removed_any = true;
+                }
+            }
+        }
+
+        self.manifest.segments = valid_segments;
+
+        // Any file whose chunks are not all present is re-indexed
+        let incomplete: Vec<PathBuf> = self
+            .manifest
+            .files
+            .iter()
+            .filter(|(_, file)| file.chunk_ids.iter().any(|id| !self.records.contains_key(id)))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in incomplete {
+            self.remove_file(&path);
+            report.invalidated_files.push(path);
+        }
+    }
+
+    fn read_segment(&self, segment: &SegmentInfo) -> Result<Vec<IndexRecord>, String> {
+        let bytes = std::fs::read(self.segment_path(&segment.name)).map_err(|e| e.to_string())?;
+
+        let checksum = format!("{:x}", md5::compute(&bytes));
+        if checksum != segment.checksum {
+            return Err(format!("checksum mismatch (expected {}, found {})", segment.checksum, checksum));
+        }
+
+        let text = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn segment_path(&self, name: &str) -> PathBuf {
+        self.dir.join(SEGMENTS_DIR).join(name)
+    }
+
+    /// Check whether a file must be (re-)indexed
+    pub fn needs_update(&self, path: &Path, content_hash: &str, require_vectors: bool) -> bool {
+        match self.manifest.files.get(path) {
+            Some(file) => file.content_hash != content_hash || (require_vectors && !file.embedded),
+            None => true,
+        }
+    }
+
+    /// Replace the indexed chunks of a file
+    pub fn update_file(
+        &mut self,
+        path: &Path,
+        content_hash: &str,
+        records: Vec<IndexRecord>,
+    ) -> Result<(), IndexError> {
+        for record in &records {
+            if let Some(vector) = &record.vector {
+                match self.manifest.dimension {
+                    Some(expected) if expected != vector.len() => {
+                        return Err(IndexError::DimensionMismatch {
+                            expected,
+                            actual: vector.len(),
+                        });
+                    }
+                    None => self.manifest.dimension = Some(vector.len()),
+                    _ => {}
+                }
+            }
+        }
+
+        self.remove_file(path);
+
+        let entry = IndexedFile {
+            content_hash: content_hash.to_string(),
+            segment: String::new(), // Assigned on save
+            chunk_ids: records.iter().map(|r| r.chunk.id.clone()).collect(),
+            embedded: !records.is_empty() && records.iter().all(|r| r.vector.is_some()),
+            indexed_at: Utc::now(),
+        };
+
+        for record in records {
+            self.records.insert(record.chunk.id.clone(), record);
+        }
+        self.manifest.files.insert(path.to_path_buf(), entry);
+        self.dirty_files.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Remove a file from the index
+    pub fn remove_file(&mut self, path: &Path) {
+        if let Some(file) = self.manifest.files.remove(path) {
+            for chunk_id in &file.chunk_ids {
+                self.records.remove(chunk_id);
+            }
+            self.dirty_files.remove(path);
+            self.removed_any = true;
+        }
+    }
+
+    /// Drop every indexed file that does not satisfy the predicate
+    pub fn retain_files<F: Fn(&Path) -> bool>(&mut self, keep: F) {
+        let removed: Vec<PathBuf> = self
+            .manifest
+            .files
+            .keys()
+            .filter(|path| !keep(path))
+            .cloned()
+            .collect();
+
+        for path in removed {
+            self.remove_file(&path);
+        }
+    }
+
+    /// Records of an indexed file in chunk order
+    pub fn file_records(&self, path: &Path) -> Vec<&IndexRecord> {
+        self.manifest
+            .files
+            .get(path)
+            .map(|file| file.chunk_ids.iter().filter_map(|id| self.records.get(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// All live records
+    pub fn records(&self) -> impl Iterator<Item = &IndexRecord> {
+        self.records.values()
+    }
+
+    /// Write pending changes to disk, compacting when worthwhile
+    pub fn save(&mut self) -> Result<(), IndexError> {
+        if !self.dirty_files.is_empty() {
+            let name = format!("seg-{}-{}.jsonl", Utc::now().format("%Y%m%d%H%M%S"), &uuid::Uuid::new_v4().simple().to_string()[..8]);
+            let mut dirty: Vec<PathBuf> = self.dirty_files.drain().collect();
+            dirty.sort();
+
+            let mut records = Vec::new();
+            for path in &dirty {
+                if let Some(file) = self.manifest.files.get_mut(path) {
+                    file.segment = name.clone();
+                    records.extend(file.chunk_ids.iter().filter_map(|id| self.records.get(id)));
+                }
+            }
+
+            let info = self.write_segment(&name, &records)?;
+            self.manifest.segments.push(info);
+        } else if !self.removed_any {
+            return Ok(());
+        }
+
+        if self.should_compact() {
+            self.compact()?;
+        } else {
+            self.write_manifest()?;
+            self.remove_orphan_segments()?;
+        }
+
+        self.removed_any = false;
+        Ok(())
+    }
+
+    fn should_compact(&self) -> bool {
+        let stored: usize = self.manifest.segments.iter().map(|s| s.records).sum();
+        if stored == 0 {
+            return false;
+        }
+
+        let live_ratio = self.records.len() as f32 / stored as f32;
+        live_ratio < COMPACTION_LIVE_RATIO || self.manifest.segments.len() > COMPACTION_MAX_SEGMENTS
+    }
+
+    /// Rewrite all live records into a single segment and delete the rest
+    pub fn compact(&mut self) -> Result<(), IndexError> {
+        let name = format!("seg-{}-compact.jsonl", Utc::now().format("%Y%m%d%H%M%S"));
+
+        let mut paths: Vec<&PathBuf> = self.manifest.files.keys().collect();
+        paths.sort();
+        let records: Vec<&IndexRecord> = paths
+            .iter()
+            .flat_map(|path| self.manifest.files[*path].chunk_ids.iter())
+            .filter_map(|id| self.records.get(id))
+            .collect();
+
+        let info = self.write_segment(&name, &records)?;
+        for file in self.manifest.files.values_mut() {
+            file.segment = name.clone();
+        }
+        self.manifest.segments = vec![info];
+
+        self.write_manifest()?;
+        self.remove_orphan_segments()
+    }
+
+    /// Re-read every segment and check it against the manifest
+    pub fn verify(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+
+        for segment in &self.manifest.segments {
+            if self.read_segment(segment).is_err() {
+                report.corrupted_segments.push(segment.name.clone());
+                report.invalidated_files.extend(
+                    self.manifest
+                        .files
+                        .iter()
+                        .filter(|(_, file)| file.segment == segment.name)
+                        .map(|(path, _)| path.clone()),
+                );
+            }
+        }
+
+        report
+    }
+
+    fn write_segment(&self, name: &str, records: &[&IndexRecord]) -> Result<SegmentInfo, IndexError> {
+        let mut bytes = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut bytes, record)?;
+            bytes.push(b'\n');
+        }
+
+        write_atomic(&self.segment_path(name), &bytes)?;
+
+        Ok(SegmentInfo {
+            name: name.to_string(),
+            checksum: format!("{:x}", md5::compute(&bytes)),
+            records: records.len(),
+        })
+    }
+
+    fn write_manifest(&mut self) -> Result<(), IndexError> {
+        self.manifest.updated_at = Utc::now();
+        let bytes = serde_json::to_vec_pretty(&self.manifest)?;
+        write_atomic(&self.dir.join(MANIFEST_FILE), &bytes)?;
+        Ok(())
+    }
+
+    /// Delete segment files no longer referenced by the manifest
+    fn remove_orphan_segments(&mut self) -> Result<(), IndexError> {
+        let referenced: HashSet<&str> = self.manifest.files.values().map(|f| f.segment.as_str()).collect();
+
+        // Segments without live files are dropped from the manifest too
+        self.manifest.segments.retain(|s| referenced.contains(s.name.as_str()));
+        let known: HashSet<String> = self.manifest.segments.iter().map(|s| s.name.clone()).collect();
+
+        for entry in std::fs::read_dir(self.dir.join(SEGMENTS_DIR))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !known.contains(&name) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+
+        self.write_manifest()
+    }
+
+    /// Build a vector store from the embedded records
+    pub fn to_vector_store(&self, config: EmbeddingConfig) -> VectorStore {
+        let mut store = VectorStore::new(config);
+        for record in self.records.values() {
+            if let Some(vector) = &record.vector {
+                store.add_embedding(record.chunk.clone(), record_embedding(&record.chunk, vector.clone()));
+            }
+        }
+        store.build_search_backend();
+        store
+    }
+
+    /// Build a lexical index from all records
+    pub fn to_lexical_index(&self, config: Bm25Config) -> LexicalIndex {
+        let mut index = LexicalIndex::new(config);
+        for record in self.records.values() {
+            index.add_chunk(record.chunk.clone());
+        }
+        index
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        IndexStats {
+            files: self.manifest.files.len(),
+            chunks: self.records.len(),
+            embedded_chunks: self.records.values().filter(|r| r.vector.is_some()).count(),
+            segments: self.manifest.segments.len(),
+            stored_records: self.manifest.segments.iter().map(|s| s.records).sum(),
+            embedding_model: self.manifest.embedding_model.clone(),
+            dimension: self.manifest.dimension,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// Build the embedding entry stored alongside a chunk
+pub fn record_embedding(chunk: &CodeChunk, vector: Vec<f32>) -> CodeEmbedding {
+    CodeEmbedding {
+        chunk_id: chunk.id.clone(),
+        vector,
+        metadata: EmbeddingMetadata {
+            file_path: chunk.file_path.clone(),
+            language: chunk.language.clone(),
+            chunk_type: chunk.chunk_type.clone(),
+            symbols: chunk.symbols.clone(),
+            content_hash: format!("{:x}", md5::compute(chunk.content.as_bytes())),
+        },
+        created_at: Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::embeddings::ChunkType;
+    use tempfile::tempdir;
+
+    fn record(path: &str, id: &str, content: &str) -> IndexRecord {
+        IndexRecord {
+            chunk: CodeChunk {
+                id: id.to_string(),
+                file_path: PathBuf::from(path),
+                start_line: 1,
+                end_line: 1,
+                content: content.to_string(),
+                language: "rust".to_string(),
+                chunk_type: ChunkType::Function,
+                symbols: Vec::new(),
+                scope_path: None,
+            },
+            vector: Some(vec![1.0, 0.0, 0.0]),
+        }
+    }
+
+    #[test]
+    fn test_incremental_save_and_reopen() {
+        let dir = tempdir().unwrap();
+        let (mut index, report) = PersistentIndex::open(dir.path(), Some("test-model")).unwrap();
+        assert!(report.is_clean());
+
+        index.update_file(Path::new("a.rs"), "h1", vec![record("a.rs", "a:1", "fn a() {}")]).unwrap();
+        index.update_file(Path::new("b.rs"), "h2", vec![record("b.rs", "b:1", "fn b() {}")]).unwrap();
+        index.save().unwrap();
+
+        let (mut index, report) = PersistentIndex::open(dir.path(), Some("test-model")).unwrap();
+        assert!(report.is_clean());
+        assert_eq!(index.stats().chunks, 2);
+        assert!(!index.needs_update(Path::new("a.rs"), "h1", true));
+        assert!(index.needs_update(Path::new("a.rs"), "changed", true));
+
+        index.update_file(Path::new("a.rs"), "h3", vec![record("a.rs", "a:1", "fn a2() {}")]).unwrap();
+        index.save().unwrap();
+
+        let (index, _) = PersistentIndex::open(dir.path(), Some("test-model")).unwrap();
+        assert_eq!(index.file_records(Path::new("a.rs"))[0].chunk.content, "fn a2() {}");
+        assert_eq!(index.file_records(Path::new("b.rs")).len(), 1);
+        assert_eq!(index.to_vector_store(EmbeddingConfig::default()).len(), 2);
+    }
+
+    #[test]
+    fn test_corrupted_segment_is_invalidated() {
+        let dir = tempdir().unwrap();
+        let (mut index, _) = PersistentIndex::open(dir.path(), None).unwrap();
+        index.update_file(Path::new("a.rs"), "h1", vec![record("a.rs", "a:1", "fn a() {}")]).unwrap();
+        index.save().unwrap();
+
+        let segment = std::fs::read_dir(dir.path().join(SEGMENTS_DIR)).unwrap().next().unwrap().unwrap();
+        std::fs::write(segment.path(), b"garbage").unwrap();
+
+        let (index, report) = PersistentIndex::open(dir.path(), None).unwrap();
+        assert_eq!(report.corrupted_segments.len(), 1);
+        assert_eq!(report.invalidated_files, vec![PathBuf::from("a.rs")]);
+        assert!(index.needs_update(Path::new("a.rs"), "h1", false));
+    }
+
+    #[test]
+    fn test_model_change_resets_index() {
+        let dir = tempdir().unwrap();
+        let (mut index, _) = PersistentIndex::open(dir.path(), Some("model-a")).unwrap();
+        index.update_file(Path::new("a.rs"), "h1", vec![record("a.rs", "a:1", "fn a() {}")]).unwrap();
+        index.save().unwrap();
+
+        let (index, report) = PersistentIndex::open(dir.path(), Some("model-b")).unwrap();
+        assert!(report.reset_reason.is_some());
+        assert_eq!(index.stats().files, 0);
+    }
+
+    #[test]
+    fn test_compaction_keeps_only_live_records() {
+        let dir = tempdir().unwrap();
+        let (mut index, _) = PersistentIndex::open(dir.path(), None).unwrap();
+        for round in 0..3 {
+            index
+                .update_file(Path::new("a.rs"), &format!("h{}", round), vec![record("a.rs", "a:1", "fn a() {}")])
+                .unwrap();
+            index.save().unwrap();
+        }
+
+        index.compact().unwrap();
+        let stats = index.stats();
+        assert_eq!(stats.segments, 1);
+        assert_eq!(stats.stored_records, 1);
+        assert!(index.verify().is_clean());
+    }
+}
@@ -0,0 +1,1068 @@
+//! Cross-reference index linking symbol uses to their definitions
+//!
+//! Definitions and references (call sites, imports, trait implementations,
+//! inheritance and type usages) are extracted with tree-sitter. References are
+//! resolved by name, narrowed by their qualifier (`Type::new`, `self.method`)
+//! and by file locality. A qualifier that matches no candidate, such as a
+//! receiver of unknown type, leaves the reference unresolved. A reference that
+//! stays ambiguous links to every candidate, so callers and blast-radius
+//! queries over-approximate rather than miss a dependent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+use super::symbols::{ReferenceType, SymbolIndex, SymbolReference, SymbolType};
+use super::syntax_chunker::{grammar, module_path};
+
+/// A definition site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Definition {
+    pub id: String,
+    pub name: String,
+    /// Fully qualified name, e.g. `crate::ai::routing::ModelRouter::select_model`
+    pub qualified_name: String,
+    /// Enclosing type, trait, class or module name
+    pub parent: Option<String>,
+    pub symbol_type: SymbolType,
+    pub file_path: PathBuf,
+    pub line: usize,
+    pub end_line: usize,
+    pub language: String,
+}
+
+/// A reference as it appears in the source, before resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RawReference {
+    name: String,
+    /// Path or receiver the name was accessed through
+    qualifier: Option<String>,
+    reference_type: ReferenceType,
+    line: usize,
+    /// Byte column where the name starts
+    column: usize,
+    /// Enclosing definition the reference occurs in
+    caller: Option<String>,
+}
+
+/// A reference linked to the definitions it may refer to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedReference {
+    pub name: String,
+    pub reference: SymbolReference,
+    pub caller: Option<String>,
+    pub targets: Vec<String>,
+}
+
+/// Node of an exported call graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallGraphNode {
+    pub id: String,
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: PathBuf,
+    pub line: usize,
+}
+
+/// Edge of an exported call graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallGraphEdge {
+    pub from: String,
+    pub to: String,
+    /// Number of call sites
+    pub calls: usize,
+}
+
+/// Call graph over resolved definitions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallGraph {
+    pub nodes: Vec<CallGraphNode>,
+    pub edges: Vec<CallGraphEdge>,
+}
+
+impl CallGraph {
+    /// Render the graph in Graphviz DOT format
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n    rankdir=LR;\n    node [shape=box, fontname=\"monospace\"];\n");
+
+        for node in &self.nodes {
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\\n{}:{}\"];\n",
+                escape_dot(&node.id),
+                escape_dot(&node.qualified_name),
+                escape_dot(&node.file_path.to_string_lossy()),
+                node.line
+            ));
+        }
+
+        for edge in &self.edges {
+            if edge.calls > 1 {
+                dot.push_str(&format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    escape_dot(&edge.from),
+                    escape_dot(&edge.to),
+                    edge.calls
+                ));
+            } else {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", escape_dot(&edge.from), escape_dot(&edge.to)));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Cross-reference index over a codebase
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenceIndex {
+    definitions: HashMap<String, Definition>,
+    by_name: HashMap<String, Vec<String>>,
+    file_definitions: HashMap<PathBuf, Vec<String>>,
+    file_references: HashMap<PathBuf, Vec<RawReference>>,
+    resolved: Vec<ResolvedReference>,
+    /// References to a defined name that matched none of its definitions
+    #[serde(default)]
+    unresolved: Vec<ResolvedReference>,
+    /// definition id -> indexes into `resolved` that target it
+    incoming: HashMap<String, Vec<usize>>,
+    /// definition id -> indexes into `resolved` made from inside it
+    outgoing: HashMap<String, Vec<usize>>,
+}
+
+impl ReferenceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-extract definitions and references of one file.
+    ///
+    /// Call [`ReferenceIndex::resolve`] after a batch of updates.
+    pub fn update_file(&mut self, file_path: &Path, language: &str, content: &str) {
+        self.remove_file(file_path);
+
+        let Some(tree_language) = grammar(language) else {
+            return;
+        };
+        let mut parser = Parser::new();
+        if parser.set_language(tree_language).is_err() {
+            return;
+        }
+        let Some(tree) = parser.parse(content, None) else {
+            return;
+        };
+
+        let mut extractor = Extractor {
+            language,
+            source: content.as_bytes(),
+            file_path,
+            scope: module_path(file_path, language),
+            parents: Vec::new(),
+            callers: Vec::new(),
+            definitions: Vec::new(),
+            references: Vec::new(),
+        };
+        extractor.visit(tree.root_node());
+
+        let Extractor {
+            definitions,
+            references,
+            ..
+        } = extractor;
+
+        let ids: Vec<String> = definitions.iter().map(|d| d.id.clone()).collect();
+        for definition in definitions {
+            self.by_name
+                .entry(definition.name.clone())
+                .or_default()
+                .push(definition.id.clone());
+            self.definitions.insert(definition.id.clone(), definition);
+        }
+        self.file_definitions.insert(file_path.to_path_buf(), ids);
+        self.file_references.insert(file_path.to_path_buf(), references);
+    }
+
+    /// Remove everything extracted from a file
+    pub fn remove_file(&mut self, file_path: &Path) {
+        if let Some(ids) = self.file_definitions.remove(file_path) {
+            for id in ids {
+                if let Some(definition) = self.definitions.remove(&id) {
+                    if let Some(named) = self.by_name.get_mut(&definition.name) {
+                        named.retain(|other| other != &id);
+                        if named.is_empty() {
+                            self.by_name.remove(&definition.name);
+                        }
+                    }
+                }
+            }
+        }
+        self.file_references.remove(file_path);
+    }
+
+    /// Link every extracted reference to its candidate definitions
+    pub fn resolve(&mut self) {
+        let mut resolved = Vec::new();
+        let mut unresolved = Vec::new();
+
+        let mut files: Vec<&PathBuf> = self.file_references.keys().collect();
+        files.sort();
+        for file_path in files {
+            for raw in &self.file_references[file_path] {
+                let targets = self.resolve_reference(file_path, raw);
+                let reference = ResolvedReference {
+                    name: raw.name.clone(),
+                    reference: SymbolReference::new(
+                        file_path.clone(),
+                        raw.line,
+                        raw.column,
+                        raw.reference_type.clone(),
+                    ),
+                    caller: raw.caller.clone(),
+                    targets,
+                };
+                if !reference.targets.is_empty() {
+                    resolved.push(reference);
+                } else if self.by_name.contains_key(&raw.name) {
+                    unresolved.push(reference);
+                }
+            }
+        }
+
+        self.incoming.clear();
+        self.outgoing.clear();
+        for (position, reference) in resolved.iter().enumerate() {
+            for target in &reference.targets {
+                self.incoming.entry(target.clone()).or_default().push(position);
+            }
+            if let Some(caller) = &reference.caller {
+                self.outgoing.entry(caller.clone()).or_default().push(position);
+            }
+        }
+        self.resolved = resolved;
+        self.unresolved = unresolved;
+    }
+
+    fn resolve_reference(&self, file_path: &Path, raw: &RawReference) -> Vec<String> {
+        let Some(ids) = self.by_name.get(&raw.name) else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<&Definition> = ids.iter().filter_map(|id| self.definitions.get(id)).collect();
+
+        if let Some(qualifier) = &raw.qualifier {
+            // `self.run()` and `Self::new()` refer to the caller's own type
+            let qualifier = if matches!(qualifier.as_str(), "self" | "Self" | "this" | "cls") {
+                raw.caller
+                    .as_ref()
+                    .and_then(|caller| self.definitions.get(caller))
+                    .and_then(|caller| caller.parent.clone())
+            } else {
+                qualifier.rsplit([':', '.']).next().map(str::to_string)
+            };
+
+            // A qualifier that names no candidate's type or module is a
+            // receiver of unknown type, so guessing would link unrelated code
+            let Some(qualifier) = qualifier else {
+                return Vec::new();
+            };
+            candidates.retain(|d| qualifies(d, &qualifier));
+            if candidates.is_empty() {
+                return Vec::new();
+            }
+        }
+
+        let local: Vec<&Definition> = candidates.iter().copied().filter(|d| d.file_path == file_path).collect();
+        if !local.is_empty() {
+            candidates = local;
+        }
+
+        let mut targets: Vec<String> = candidates.into_iter().map(|d| d.id.clone()).collect();
+        targets.sort();
+        targets
+    }
+
+    /// Find definitions by name or by a qualified suffix such as `ModelRouter::select_model`
+    pub fn find_definitions(&self, query: &str) -> Vec<&Definition> {
+        let normalized = query.replace('.', "::");
+        let name = normalized.rsplit("::").next().unwrap_or(query);
+
+        let mut definitions: Vec<&Definition> = self
+            .by_name
+            .get(name)
+            .map(|ids| ids.iter().filter_map(|id| self.definitions.get(id)).collect())
+            .unwrap_or_default();
+
+        if normalized.contains("::") {
+            definitions.retain(|d| {
+                let qualified = d.qualified_name.replace('.', "::");
+                qualified == normalized || qualified.ends_with(&format!("::{}", normalized))
+            });
+        }
+
+        definitions.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+        definitions
+    }
+
+    /// Get a definition by id
+    pub fn get_definition(&self, id: &str) -> Option<&Definition> {
+        self.definitions.get(id)
+    }
+
+    /// All resolved references that point at a definition
+    pub fn references_to(&self, definition_id: &str) -> Vec<&ResolvedReference> {
+        self.incoming
+            .get(definition_id)
+            .map(|positions| positions.iter().map(|&p| &self.resolved[p]).collect())
+            .unwrap_or_default()
+    }
+
+    /// References to a defined name that could not be tied to any of its
+    /// definitions, such as method calls on a receiver of unknown type
+    pub fn unresolved_references(&self, name: &str) -> Vec<&ResolvedReference> {
+        self.unresolved.iter().filter(|r| r.name == name).collect()
+    }
+
+    /// Resolved references made on a line of a file
+    pub fn references_on_line(&self, file_path: &Path, line: usize) -> Vec<&ResolvedReference> {
+        self.resolved
+            .iter()
+            .filter(|r| r.reference.line_number == line && r.reference.file_path == file_path)
+            .collect()
+    }
+
+    /// Definitions declared in a file, in source order
+    pub fn file_definitions(&self, file_path: &Path) -> Vec<&Definition> {
+        let mut definitions: Vec<&Definition> = self
+            .file_definitions
+            .get(file_path)
+            .map(|ids| ids.iter().filter_map(|id| self.definitions.get(id)).collect())
+            .unwrap_or_default();
+        definitions.sort_by_key(|d| (d.line, d.end_line));
+        definitions
+    }
+
+    /// Definitions that call the given definition
+    pub fn callers(&self, definition_id: &str) -> Vec<&Definition> {
+        let mut seen = HashSet::new();
+        self.references_to(definition_id)
+            .into_iter()
+            .filter(|r| r.reference.reference_type == ReferenceType::Call)
+            .filter_map(|r| r.caller.as_ref())
+            .filter(|caller| seen.insert(caller.as_str()))
+            .filter_map(|caller| self.definitions.get(caller))
+            .collect()
+    }
+
+    /// Definitions called from inside the given definition
+    pub fn callees(&self, definition_id: &str) -> Vec<&Definition> {
+        let mut seen = HashSet::new();
+        self.outgoing
+            .get(definition_id)
+            .map(|positions| {
+                positions
+                    .iter()
+                    .map(|&p| &self.resolved[p])
+                    .filter(|r| r.reference.reference_type == ReferenceType::Call)
+                    .flat_map(|r| r.targets.iter())
+                    .filter(|target| seen.insert(target.as_str()))
+                    .filter_map(|target| self.definitions.get(target))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Transitive callers up to `depth` levels, with the level each was reached at.
+    ///
+    /// This is the blast radius of changing the definition: everything that
+    /// may behave differently if its signature or behavior changes.
+    pub fn blast_radius(&self, definition_id: &str, depth: usize) -> Vec<(&Definition, usize)> {
+        self.walk(definition_id, depth, |id| self.callers(id))
+    }
+
+    /// Transitive callees up to `depth` levels
+    pub fn transitive_callees(&self, definition_id: &str, depth: usize) -> Vec<(&Definition, usize)> {
+        self.walk(definition_id, depth, |id| self.callees(id))
+    }
+
+    fn walk<'a>(
+        &'a self,
+        start: &str,
+        depth: usize,
+        next: impl Fn(&str) -> Vec<&'a Definition>,
+    ) -> Vec<(&'a Definition, usize)> {
+        let mut visited: HashSet<&str> = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start.to_string(), 0)]);
+        let mut reached = Vec::new();
+
+        while let Some((id, level)) = queue.pop_front() {
+            if level >= depth {
+                continue;
+            }
+            for definition in next(&id) {
+                if visited.insert(definition.id.as_str()) {
+                    reached.push((definition, level + 1));
+                    queue.push_back((definition.id.clone(), level + 1));
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// Build a call graph, optionally limited to `depth` levels around a root definition
+    pub fn call_graph(&self, root: Option<&str>, depth: usize) -> CallGraph {
+        let included: Option<HashSet<String>> = root.map(|root| {
+            let mut ids: HashSet<String> = HashSet::from([root.to_string()]);
+            ids.extend(self.blast_radius(root, depth).into_iter().map(|(d, _)| d.id.clone()));
+            ids.extend(self.transitive_callees(root, depth).into_iter().map(|(d, _)| d.id.clone()));
+            ids
+        });
+        let keep = |id: &str| included.as_ref().map_or(true, |ids| ids.contains(id));
+
+        let mut counts: HashMap<(String, String), usize> = HashMap::new();
+        for reference in &self.resolved {
+            if reference.reference.reference_type != ReferenceType::Call {
+                continue;
+            }
+            let Some(caller) = &reference.caller else {
+                continue;
+            };
+            for target in &reference.targets {
+                if keep(caller) && keep(target) {
+                    *counts.entry((caller.clone(), target.clone())).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut node_ids: Vec<&String> = counts.keys().flat_map(|(from, to)| [from, to]).collect();
+        node_ids.sort();
+        node_ids.dedup();
+
+        let nodes = node_ids
+            .into_iter()
+            .filter_map(|id| self.definitions.get(id))
+            .map(|d| CallGraphNode {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                qualified_name: d.qualified_name.clone(),
+                file_path: d.file_path.clone(),
+                line: d.line,
+            })
+            .collect();
+
+        let mut edges: Vec<CallGraphEdge> = counts
+            .into_iter()
+            .map(|((from, to), calls)| CallGraphEdge { from, to, calls })
+            .collect();
+        edges.sort_by(|a, b| a.from.cmp(&b.from).then_with(|| a.to.cmp(&b.to)));
+
+        CallGraph { nodes, edges }
+    }
+
+    /// Files containing references to definitions in `file_path`, and files
+    /// defining what `file_path` references
+    pub fn related_files(&self, file_path: &Path) -> HashSet<PathBuf> {
+        let mut related = HashSet::new();
+
+        for id in self.file_definitions.get(file_path).into_iter().flatten() {
+            for reference in self.references_to(id) {
+                related.insert(reference.reference.file_path.clone());
+            }
+        }
+        for reference in &self.resolved {
+            if reference.reference.file_path == file_path {
+                for target in &reference.targets {
+                    if let Some(definition) = self.definitions.get(target) {
+                        related.insert(definition.file_path.clone());
+                    }
+                }
+            }
+        }
+
+        related.remove(file_path);
+        related
+    }
+
+    /// Definitions in other files that `file_path` references, with the
+    /// number of references, most referenced first
+    pub fn referenced_definitions(&self, file_path: &Path) -> Vec<(&Definition, usize)> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for reference in self.resolved.iter().filter(|r| r.reference.file_path == file_path) {
+            for target in &reference.targets {
+                *counts.entry(target.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut definitions: Vec<(&Definition, usize)> = counts
+            .into_iter()
+            .filter_map(|(id, count)| self.definitions.get(id).map(|definition| (definition, count)))
+            .filter(|(definition, _)| definition.file_path != file_path)
+            .collect();
+        definitions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.qualified_name.cmp(&b.0.qualified_name)));
+        definitions
+    }
+
+    /// Record resolved references on the matching symbols of a `SymbolIndex`
+    pub fn apply_to_symbols(&self, symbols: &mut SymbolIndex) {
+        symbols.clear_references();
+        for reference in &self.resolved {
+            for target in &reference.targets {
+                if let Some(definition) = self.definitions.get(target) {
+                    symbols.add_reference(&definition.name, &definition.file_path, reference.reference.clone());
+                }
+            }
+        }
+    }
+
+    /// Number of definitions
+    pub fn definition_count(&self) -> usize {
+        self.definitions.len()
+    }
+
+    /// Number of resolved references
+    pub fn reference_count(&self) -> usize {
+        self.resolved.len()
+    }
+}
+
+/// Walks one syntax tree collecting definitions and references
+struct Extractor<'a> {
+    language: &'a str,
+    source: &'a [u8],
+    file_path: &'a Path,
+    /// Module path plus enclosing type and function names
+    scope: Vec<String>,
+    /// Enclosing type, trait, class or module names, flagged when they are types
+    parents: Vec<(String, bool)>,
+    /// Enclosing definition ids
+    callers: Vec<String>,
+    definitions: Vec<Definition>,
+    references: Vec<RawReference>,
+}
+
+impl<'a> Extractor<'a> {
+    fn visit(&mut self, node: Node) {
+        let mut pushed_scope = false;
+        let mut pushed_parent = false;
+        let mut pushed_caller = false;
+
+        if let Some((symbol_type, name)) = self.definition(node) {
+            let separator = if self.language == "rust" { "::" } else { "." };
+            let mut qualified = self.scope.clone();
+            qualified.push(name.clone());
+            let id = format!("{}:{}:{}", self.file_path.to_string_lossy(), node.start_position().row + 1, name);
+            let is_container = matches!(
+                symbol_type,
+                SymbolType::Class | SymbolType::Trait | SymbolType::Module | SymbolType::Interface
+            );
+            let parent = self
+                .receiver_type(node)
+                .or_else(|| self.parents.last().map(|(parent, _)| parent.clone()));
+
+            self.definitions.push(Definition {
+                id: id.clone(),
+                name: name.clone(),
+                qualified_name: qualified.join(separator),
+                parent,
+                symbol_type: symbol_type.clone(),
+                file_path: self.file_path.to_path_buf(),
+                line: node.start_position().row + 1,
+                end_line: node.end_position().row + 1,
+                language: self.language.to_string(),
+            });
+
+            self.scope.push(name.clone());
+            pushed_scope = true;
+            if is_container {
+                self.parents.push((name, symbol_type != SymbolType::Module));
+                pushed_parent = true;
+            } else {
+                self.callers.push(id);
+                pushed_caller = true;
+            }
+        } else if let Some(type_name) = self.impl_target(node) {
+            self.scope.push(type_name.clone());
+            self.parents.push((type_name, true));
+            pushed_scope = true;
+            pushed_parent = true;
+        }
+
+        self.collect_references(node);
+
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        for child in children {
+            self.visit(child);
+        }
+
+        if pushed_scope {
+            self.scope.pop();
+        }
+        if pushed_parent {
+            self.parents.pop();
+        }
+        if pushed_caller {
+            self.callers.pop();
+        }
+    }
+
+    fn text(&self, node: Node) -> String {
+        node.utf8_text(self.source).unwrap_or_default().to_string()
+    }
+
+    fn field_text(&self, node: Node, field: &str) -> Option<String> {
+        node.child_by_field_name(field).map(|n| self.text(n))
+    }
+
+    fn definition(&self, node: Node) -> Option<(SymbolType, String)> {
+        let symbol_type = match (self.language, node.kind()) {
+            ("rust", "function_item") | ("rust", "function_signature_item") => self.function_type(),
+            ("rust", "struct_item") | ("rust", "union_item") => SymbolType::Struct,
+            ("rust", "enum_item") => SymbolType::Enum,
+            ("rust", "trait_item") => SymbolType::Trait,
+            ("rust", "type_item") => SymbolType::Type,
+            ("rust", "const_item") | ("rust", "static_item") => SymbolType::Constant,
+            ("rust", "mod_item") => SymbolType::Module,
+            ("rust", "macro_definition") => SymbolType::Function,
+            ("python", "function_definition") => self.function_type(),
+            ("python", "class_definition") => SymbolType::Class,
+            ("javascript" | "typescript", "function_declaration")
+            | ("javascript" | "typescript", "generator_function_declaration") => SymbolType::Function,
+            ("javascript" | "typescript", "method_definition") => SymbolType::Method,
+            ("javascript" | "typescript", "class_declaration")
+            | ("typescript", "abstract_class_declaration") => SymbolType::Class,
+            ("typescript", "interface_declaration") => SymbolType::Interface,
+            ("typescript", "type_alias_declaration") => SymbolType::Type,
+            ("typescript", "enum_declaration") => SymbolType::Enum,
+            ("javascript" | "typescript", "variable_declarator") => {
+                let value = node.child_by_field_name("value")?;
+                if !matches!(value.kind(), "arrow_function" | "function" | "function_expression") {
+                    return None;
+                }
+                SymbolType::Function
+            }
+            ("go", "function_declaration") => SymbolType::Function,
+            ("go", "method_declaration") => SymbolType::Method,
+            ("go", "type_spec") => match node.child_by_field_name("type").map(|t| t.kind()) {
+                Some("interface_type") => SymbolType::Interface,
+                _ => SymbolType::Struct,
+            },
+            _ => return None,
+        };
+
+        let name = self.field_text(node, "name")?;
+        Some((symbol_type, name))
+    }
+
+    /// Functions directly inside a type are methods
+    fn function_type(&self) -> SymbolType {
+        match self.parents.last() {
+            Some((_, true)) => SymbolType::Method,
+            _ => SymbolType::Function,
+        }
+    }
+
+    /// Receiver type of a Go method
+    fn receiver_type(&self, node: Node) -> Option<String> {
+        if node.kind() != "method_declaration" {
+            return None;
+        }
+        let receiver = node.child_by_field_name("receiver")?;
+        let mut cursor = receiver.walk();
+        let parameter = receiver.named_children(&mut cursor).find_map(|p| p.child_by_field_name("type"))?;
+        Some(base_name(&self.text(parameter)))
+    }
+
+    /// Type whose members are defined by an `impl` block
+    fn impl_target(&self, node: Node) -> Option<String> {
+        let type_node = match (self.language, node.kind()) {
+            ("rust", "impl_item") => node.child_by_field_name("type")?,
+            _ => return None,
+        };
+        Some(base_name(&self.text(type_node)))
+    }
+
+    fn push_reference(&mut self, node: Node, name: String, qualifier: Option<String>, reference_type: ReferenceType) {
+        if name.is_empty() {
+            return;
+        }
+        self.references.push(RawReference {
+            name,
+            qualifier,
+            reference_type,
+            line: node.start_position().row + 1,
+            column: node.start_position().column,
+            caller: self.callers.last().cloned(),
+        });
+    }
+
+    fn collect_references(&mut self, node: Node) {
+        match (self.language, node.kind()) {
+            (_, "call_expression") | ("python", "call") => {
+                if let Some(function) = node.child_by_field_name("function") {
+                    self.call_target(function);
+                }
+            }
+            ("javascript" | "typescript", "new_expression") => {
+                if let Some(constructor) = node.child_by_field_name("constructor") {
+                    self.call_target(constructor);
+                }
+            }
+            ("rust", "macro_invocation") => {
+                if let Some(name) = node.child_by_field_name("macro") {
+                    let name = self.text(name);
+                    let name = name.rsplit("::").next().unwrap_or_default().to_string();
+                    self.push_reference(node, name, None, ReferenceType::Call);
+                }
+            }
+            ("rust", "use_declaration") => {
+                if let Some(argument) = node.child_by_field_name("argument") {
+                    self.use_tree(argument, None);
+                }
+            }
+            ("rust", "impl_item") => {
+                if let Some(trait_node) = node.child_by_field_name("trait") {
+                    let (qualifier, name) = split_path(&self.text(trait_node));
+                    self.push_reference(trait_node, base_name(&name), qualifier, ReferenceType::Implementation);
+                }
+            }
+            ("rust", "type_identifier") if !self.is_definition_name(node) && !self.is_impl_header(node) => {
+                self.push_reference(node, self.text(node), None, ReferenceType::Usage);
+            }
+            ("python", "import_from_statement") | ("python", "import_statement") => {
+                let module = self.field_text(node, "module_name");
+                let mut cursor = node.walk();
+                let names: Vec<Node> = node.children_by_field_name("name", &mut cursor).collect();
+                for name_node in names {
+                    let target = match name_node.kind() {
+                        "aliased_import" => name_node.child_by_field_name("name").unwrap_or(name_node),
+                        _ => name_node,
+                    };
+                    let (qualifier, name) = split_path(&self.text(target));
+                    self.push_reference(name_node, name, module.clone().or(qualifier), ReferenceType::Import);
+                }
+            }
+            ("python", "class_definition") => {
+                if let Some(superclasses) = node.child_by_field_name("superclasses") {
+                    let mut cursor = superclasses.walk();
+                    let bases: Vec<Node> = superclasses.named_children(&mut cursor).collect();
+                    for base in bases {
+                        if matches!(base.kind(), "identifier" | "attribute") {
+                            let (qualifier, name) = split_path(&self.text(base));
+                            self.push_reference(base, name, qualifier, ReferenceType::Inheritance);
+                        }
+                    }
+                }
+            }
+            ("javascript" | "typescript", "import_specifier") => {
+                if let Some(name) = self.field_text(node, "name") {
+                    self.push_reference(node, name, None, ReferenceType::Import);
+                }
+            }
+            ("javascript" | "typescript", "import_clause") => {
+                let mut cursor = node.walk();
+                let default_import = node.named_children(&mut cursor).find(|child| child.kind() == "identifier");
+                if let Some(default_import) = default_import {
+                    self.push_reference(default_import, self.text(default_import), None, ReferenceType::Import);
+                }
+            }
+            ("javascript" | "typescript", "class_heritage") => self.heritage(node, ReferenceType::Inheritance),
+            ("go", "import_spec") => {
+                if let Some(path) = self.field_text(node, "path") {
+                    let path = path.trim_matches('"');
+                    let name = path.rsplit('/').next().unwrap_or(path).to_string();
+                    self.push_reference(node, name, Some(path.to_string()), ReferenceType::Import);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Record the function an expression calls
+    fn call_target(&mut self, function: Node) {
+        match function.kind() {
+            "identifier" => self.push_reference(function, self.text(function), None, ReferenceType::Call),
+            // Rust `Type::new`, `module::func`
+            "scoped_identifier" => {
+                let name = self.field_text(function, "name").unwrap_or_default();
+                let qualifier = self.field_text(function, "path");
+                let name_node = function.child_by_field_name("name").unwrap_or(function);
+                self.push_reference(name_node, name, qualifier, ReferenceType::Call);
+            }
+            // Rust `value.method`, JS `obj.method`, Python `obj.method`, Go `pkg.Func`
+            "field_expression" | "member_expression" | "attribute" | "selector_expression" => {
+                let (name_field, object_field) = match function.kind() {
+                    "field_expression" => ("field", "value"),
+                    "member_expression" => ("property", "object"),
+                    "attribute" => ("attribute", "object"),
+                    _ => ("field", "operand"),
+                };
+                let name = self.field_text(function, name_field).unwrap_or_default();
+                let qualifier = self.field_text(function, object_field);
+                let name_node = function.child_by_field_name(name_field).unwrap_or(function);
+                self.push_reference(name_node, name, qualifier, ReferenceType::Call);
+            }
+            // Rust `func::<T>()`
+            "generic_function" => {
+                if let Some(inner) = function.child_by_field_name("function") {
+                    self.call_target(inner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Record the leaf names imported by a Rust `use` tree
+    fn use_tree(&mut self, node: Node, prefix: Option<String>) {
+        match node.kind() {
+            "identifier" | "type_identifier" => {
+                self.push_reference(node, self.text(node), prefix, ReferenceType::Import);
+            }
+            "scoped_identifier" => {
+                let name = self.field_text(node, "name").unwrap_or_default();
+                let path = self.field_text(node, "path");
+                let name_node = node.child_by_field_name("name").unwrap_or(node);
+                self.push_reference(name_node, name, join_path(prefix, path), ReferenceType::Import);
+            }
+            "use_as_clause" => {
+                if let Some(path) = node.child_by_field_name("path") {
+                    self.use_tree(path, prefix);
+                }
+            }
+            "scoped_use_list" => {
+                let path = join_path(prefix, self.field_text(node, "path"));
+                if let Some(list) = node.child_by_field_name("list") {
+                    self.use_tree(list, path);
+                }
+            }
+            "use_list" => {
+                let mut cursor = node.walk();
+                let items: Vec<Node> = node.named_children(&mut cursor).collect();
+                for item in items {
+                    self.use_tree(item, prefix.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// JS/TS `extends Base` and `implements Contract`
+    fn heritage(&mut self, node: Node, reference_type: ReferenceType) {
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.named_children(&mut cursor).collect();
+        for child in children {
+            match child.kind() {
+                "identifier" | "type_identifier" => {
+                    self.push_reference(child, self.text(child), None, reference_type.clone())
+                }
+                "member_expression" => {
+                    let name = self.field_text(child, "property").unwrap_or_default();
+                    self.push_reference(child, name, self.field_text(child, "object"), reference_type.clone());
+                }
+                "implements_clause" => self.heritage(child, ReferenceType::Implementation),
+                "extends_clause" => self.heritage(child, ReferenceType::Inheritance),
+                "generic_type" => {
+                    if let Some(name) = self.field_text(child, "name") {
+                        self.push_reference(child, name, None, reference_type.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_definition_name(&self, node: Node) -> bool {
+        node.parent()
+            .and_then(|parent| parent.child_by_field_name("name"))
+            .is_some_and(|name| name == node)
+    }
+
+    /// The `Type` and `Trait` in `impl Trait for Type` are handled separately
+    fn is_impl_header(&self, node: Node) -> bool {
+        node.parent().is_some_and(|parent| {
+            parent.kind() == "impl_item"
+                && (parent.child_by_field_name("type") == Some(node) || parent.child_by_field_name("trait") == Some(node))
+        })
+    }
+}
+
+/// Split `a::b::c` or `a.b.c` into qualifier and final name
+fn split_path(path: &str) -> (Option<String>, String) {
+    let normalized = path.replace("::", ".");
+    match normalized.rsplit_once('.') {
+        Some((qualifier, name)) => (Some(qualifier.to_string()), name.to_string()),
+        None => (None, normalized),
+    }
+}
+
+fn join_path(prefix: Option<String>, path: Option<String>) -> Option<String> {
+    match (prefix, path) {
+        (Some(prefix), Some(path)) => Some(format!("{}::{}", prefix, path)),
+        (prefix, path) => prefix.or(path),
+    }
+}
+
+/// Whether `qualifier` names the definition's enclosing type or one of its modules
+fn qualifies(definition: &Definition, qualifier: &str) -> bool {
+    if definition.parent.as_deref() == Some(qualifier) {
+        return true;
+    }
+    let qualified = definition.qualified_name.replace('.', "::");
+    let mut segments: Vec<&str> = qualified.split("::").collect();
+    segments.pop();
+    segments.contains(&qualifier)
+}
+
+/// Strip generics and references from a type name
+fn base_name(text: &str) -> String {
+    let text = text.trim_start_matches(['&', '*']).trim_start_matches("mut ");
+    let text = text.split('<').next().unwrap_or(text);
+    text.rsplit("::").next().unwrap_or(text).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTING: &str = r#"
+use crate::ai::Model;
+
+pub trait Router {
+    fn route(&self) -> Model;
+}
+
+pub struct ModelRouter;
+
+impl ModelRouter {
+    pub fn new() -> Self {
+        ModelRouter
+    }
+
+    pub fn select_model(&self) -> Model {
+        self.score();
+        default_model()
+    }
+
+    fn score(&self) {}
+}
+
+impl Router for ModelRouter {
+    fn route(&self) -> Model {
+        self.select_model()
+    }
+}
+
+fn default_model() -> Model {
+    Model::default()
+}
+"#;
+
+    const MAIN: &str = r#"
+use crate::ai::routing::ModelRouter;
+
+fn main() {
+    let router = ModelRouter::new();
+    ModelRouter::select_model(&router);
+    router.select_model();
+}
+"#;
+
+    fn index() -> ReferenceIndex {
+        let mut index = ReferenceIndex::new();
+        index.update_file(Path::new("/repo/src/ai/routing.rs"), "rust", ROUTING);
+        index.update_file(Path::new("/repo/src/main.rs"), "rust", MAIN);
+        index.resolve();
+        index
+    }
+
+    #[test]
+    fn test_definitions_are_qualified() {
+        let index = index();
+        let select = index.find_definitions("ModelRouter::select_model");
+        assert_eq!(select.len(), 1);
+        assert_eq!(select[0].qualified_name, "crate::ai::routing::ModelRouter::select_model");
+        assert_eq!(select[0].symbol_type, SymbolType::Method);
+    }
+
+    #[test]
+    fn test_callers_and_callees() {
+        let index = index();
+        let select = &index.find_definitions("select_model")[0].id.clone();
+
+        let mut callers: Vec<String> = index.callers(select).iter().map(|d| d.name.clone()).collect();
+        callers.sort();
+        assert_eq!(callers, vec!["main", "route"]);
+
+        let mut callees: Vec<String> = index.callees(select).iter().map(|d| d.name.clone()).collect();
+        callees.sort();
+        assert_eq!(callees, vec!["default_model", "score"]);
+
+        // default_model <- select_model <- {route, main}
+        let default_model = &index.find_definitions("default_model")[0].id.clone();
+        let radius = index.blast_radius(default_model, 2);
+        assert_eq!(radius.len(), 3);
+        assert!(radius.iter().any(|(d, level)| d.name == "main" && *level == 2));
+    }
+
+    #[test]
+    fn test_unknown_receiver_is_unresolved() {
+        let index = index();
+        let select = &index.find_definitions("select_model")[0].id.clone();
+        let main_calls: Vec<usize> = index
+            .references_to(select)
+            .iter()
+            .filter(|r| r.reference.file_path == Path::new("/repo/src/main.rs"))
+            .map(|r| r.reference.line_number)
+            .collect();
+
+        // `ModelRouter::select_model(&router)` resolves, `router.select_model()` does not
+        assert_eq!(main_calls, vec![6]);
+    }
+
+    #[test]
+    fn test_imports_and_implementations() {
+        let index = index();
+        let router = &index.find_definitions("ModelRouter")[0].id.clone();
+        let references = index.references_to(router);
+        assert!(references
+            .iter()
+            .any(|r| r.reference.reference_type == ReferenceType::Import
+                && r.reference.file_path == Path::new("/repo/src/main.rs")));
+
+        let trait_id = &index.find_definitions("Router")[0].id.clone();
+        assert!(index
+            .references_to(trait_id)
+            .iter()
+            .any(|r| r.reference.reference_type == ReferenceType::Implementation));
+
+        let related = index.related_files(Path::new("/repo/src/ai/routing.rs"));
+        assert!(related.contains(Path::new("/repo/src/main.rs")));
+    }
+
+    #[test]
+    fn test_call_graph_dot() {
+        let index = index();
+        let graph = index.call_graph(None, 0);
+        assert!(graph.edges.iter().any(|e| e.from.ends_with(":main") && e.to.ends_with(":new")));
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph calls {"));
+        assert!(dot.contains("crate::ai::routing::ModelRouter::select_model"));
+    }
+
+    #[test]
+    fn test_python_references() {
+        let source = "from models import Base\n\nclass Router(Base):\n    def pick(self):\n        return self.score()\n\n    def score(self):\n        return 1\n";
+        let mut index = ReferenceIndex::new();
+        index.update_file(Path::new("router.py"), "python", source);
+        index.resolve();
+
+        let score = &index.find_definitions("Router.score")[0].id.clone();
+        let callers: Vec<String> = index.callers(score).iter().map(|d| d.name.clone()).collect();
+        assert_eq!(callers, vec!["pick"]);
+    }
+}
@@ -223,6 +223,24 @@ impl SymbolIndex {
         }
     }
 
+    /// Attach a reference to the symbols with this name defined in `file_path`
+    pub fn add_reference(&mut self, name: &str, file_path: &PathBuf, reference: SymbolReference) {
+        if let Some(symbols) = self.symbols.get_mut(name) {
+            for symbol in symbols.iter_mut().filter(|symbol| symbol.file_path == *file_path) {
+                symbol.add_reference(reference.clone());
+            }
+        }
+    }
+
+    /// Drop all recorded references, e.g. before re-resolving them
+    pub fn clear_references(&mut self) {
+        for symbols in self.symbols.values_mut() {
+            for symbol in symbols {
+                symbol.references.clear();
+            }
+        }
+    }
+
     /// Update symbols for a file
     pub fn update_file_symbols(&mut self, file_path: &PathBuf, new_symbols: Vec<Symbol>) {
         // Remove old symbols
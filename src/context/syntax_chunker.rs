@@ -0,0 +1,729 @@
+//! Syntax-aware code chunking using tree-sitter
+//!
+//! Chunks are aligned to syntactic items (functions, impl blocks, classes,
+//! modules) instead of character counts. Items larger than the chunk budget
+//! are split at statement boundaries, and every chunk records the scope path
+//! of its enclosing item, e.g. `crate::ai::routing::ModelRouter::select_model`.
+
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Node, Parser};
+
+use super::embeddings::{ChunkType, CodeChunk};
+
+/// How a syntax node takes part in chunking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    /// Function or method with a statement body
+    Function,
+    /// Item whose body holds further items (impl, trait, mod, class)
+    Container,
+    /// Type definition (struct, enum, interface, type alias)
+    Type,
+    Import,
+    Variable,
+    /// Wrapper whose inner declaration is the real item (decorators, exports)
+    Wrapper,
+    Other,
+    /// Comments and attributes, attached to the following item instead
+    Skip,
+}
+
+/// A contiguous piece of source produced for one item
+struct Piece {
+    start_byte: usize,
+    end_byte: usize,
+    chunk_type: ChunkType,
+    scope: Vec<String>,
+    symbol: Option<String>,
+}
+
+/// Chunker that aligns chunks to syntax tree items
+#[derive(Debug, Clone)]
+pub struct SyntaxChunker {
+    max_chunk_size: usize,
+}
+
+impl SyntaxChunker {
+    pub fn new(max_chunk_size: usize) -> Self {
+        Self {
+            max_chunk_size: max_chunk_size.max(64),
+        }
+    }
+
+    /// Whether a grammar is available for the language
+    pub fn supports(language: &str) -> bool {
+        grammar(language).is_some()
+    }
+
+    /// Chunk a file along its syntax tree.
+    ///
+    /// Returns `None` when the language is unsupported or the file cannot be
+    /// parsed, so callers can fall back to line-based chunking.
+    pub fn chunk(&self, file_path: &Path, content: &str, language: &str) -> Option<Vec<CodeChunk>> {
+        let tree_language = grammar(language)?;
+        let mut parser = Parser::new();
+        parser.set_language(tree_language).ok()?;
+        let tree = parser.parse(content, None)?;
+        let root = tree.root_node();
+
+        // Heavily broken files are better served by the line chunker
+        if root.has_error() && Self::error_ratio(root) > 0.3 {
+            return None;
+        }
+
+        let source = content.as_bytes();
+        let module = module_path(file_path, language);
+        let mut pieces = Vec::new();
+        self.collect_items(root, source, language, &module, &mut pieces);
+
+        let pieces = self.merge_small_pieces(pieces, source);
+        let separator = scope_separator(language);
+
+        Some(
+            pieces
+                .into_iter()
+                .filter_map(|piece| {
+                    let raw = content.get(piece.start_byte..piece.end_byte)?;
+                    let text = raw.trim();
+                    if text.is_empty() {
+                        return None;
+                    }
+                    let text_start = piece.start_byte + (raw.len() - raw.trim_start().len());
+                    let start_line = line_of_byte(source, text_start);
+                    let end_line = line_of_byte(source, text_start + text.len() - 1);
+
+                    Some(CodeChunk {
+                        id: format!("{}:{}:{}", file_path.to_string_lossy(), start_line, end_line),
+                        file_path: file_path.to_path_buf(),
+                        start_line,
+                        end_line,
+                        content: text.to_string(),
+                        language: language.to_string(),
+                        chunk_type: piece.chunk_type,
+                        symbols: piece.symbol.into_iter().collect(),
+                        scope_path: (!piece.scope.is_empty()).then(|| piece.scope.join(separator)),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Walk the items of a container node and produce pieces for each
+    fn collect_items(&self, parent: Node, source: &[u8], language: &str, scope: &[String], pieces: &mut Vec<Piece>) {
+        let mut cursor = parent.walk();
+        let children: Vec<Node> = parent.named_children(&mut cursor).collect();
+
+        for node in children {
+            self.collect_item(node, source, language, scope, pieces, false);
+        }
+    }
+
+    fn collect_item(
+        &self,
+        node: Node,
+        source: &[u8],
+        language: &str,
+        scope: &[String],
+        pieces: &mut Vec<Piece>,
+        in_container: bool,
+    ) {
+        let kind = classify(language, node);
+        let start = leading_trivia_start(node);
+
+        match kind {
+            ItemKind::Skip => {}
+            ItemKind::Wrapper => {
+                match inner_declaration(node) {
+                    Some(inner) => {
+                        let before = pieces.len();
+                        self.collect_item(inner, source, language, scope, pieces, in_container);
+                        // Keep decorators and `export` keywords with the item they wrap
+                        if let Some(first) = pieces.get_mut(before) {
+                            first.start_byte = first.start_byte.min(start.start_byte());
+                        }
+                    }
+                    None => pieces.push(self.piece(node, start, ChunkType::Generic, scope.to_vec(), None)),
+                }
+            }
+            ItemKind::Container => {
+                let name = item_name(language, node, source);
+                let mut inner_scope = scope.to_vec();
+                if let Some(name) = &name {
+                    inner_scope.push(name.clone());
+                }
+
+                if node.end_byte() - start.start_byte() <= self.max_chunk_size {
+                    pieces.push(self.piece(node, start, ChunkType::Class, inner_scope, name));
+                    return;
+                }
+
+                let Some(body) = container_body(node) else {
+                    self.split_by_lines(node, start, source, ChunkType::Class, inner_scope, name, pieces);
+                    return;
+                };
+
+                // Header: everything up to the first nested function, e.g. the
+                // class docstring and attributes or the impl signature
+                let mut cursor = body.walk();
+                let members: Vec<Node> = body.named_children(&mut cursor).collect();
+                let header_end = members
+                    .iter()
+                    .find(|member| {
+                        matches!(
+                            classify(language, **member),
+                            ItemKind::Function | ItemKind::Container | ItemKind::Wrapper
+                        )
+                    })
+                    .map(|member| leading_trivia_start(*member).start_byte())
+                    .unwrap_or(body.end_byte());
+
+                if header_end > start.start_byte() {
+                    let header_text = String::from_utf8_lossy(&source[start.start_byte()..header_end]);
+                    if header_text.trim().len() > 1 {
+                        pieces.push(Piece {
+                            start_byte: start.start_byte(),
+                            end_byte: header_end,
+                            chunk_type: ChunkType::Class,
+                            scope: inner_scope.clone(),
+                            symbol: name.clone(),
+                        });
+                    }
+                }
+
+                for member in members {
+                    if leading_trivia_start(member).start_byte() >= header_end {
+                        self.collect_item(member, source, language, &inner_scope, pieces, true);
+                    }
+                }
+            }
+            ItemKind::Function => {
+                let name = item_name(language, node, source);
+                let mut item_scope = scope.to_vec();
+                if let Some(name) = &name {
+                    item_scope.push(name.clone());
+                }
+                let chunk_type = if in_container || node.kind() == "method_declaration" {
+                    ChunkType::Method
+                } else {
+                    ChunkType::Function
+                };
+
+                if node.end_byte() - start.start_byte() <= self.max_chunk_size {
+                    pieces.push(self.piece(node, start, chunk_type, item_scope, name));
+                } else {
+                    self.split_at_statements(node, start, source, chunk_type, item_scope, name, pieces);
+                }
+            }
+            ItemKind::Type | ItemKind::Import | ItemKind::Variable | ItemKind::Other => {
+                let name = item_name(language, node, source);
+                let mut item_scope = scope.to_vec();
+                if kind != ItemKind::Import {
+                    if let Some(name) = &name {
+                        item_scope.push(name.clone());
+                    }
+                }
+                let chunk_type = match kind {
+                    ItemKind::Type => ChunkType::Class,
+                    ItemKind::Import => ChunkType::Import,
+                    ItemKind::Variable => ChunkType::Variable,
+                    _ => ChunkType::Generic,
+                };
+
+                if node.end_byte() - start.start_byte() <= self.max_chunk_size {
+                    pieces.push(self.piece(node, start, chunk_type, item_scope, name));
+                } else {
+                    self.split_by_lines(node, start, source, chunk_type, item_scope, name, pieces);
+                }
+            }
+        }
+    }
+
+    fn piece(&self, node: Node, start: Node, chunk_type: ChunkType, scope: Vec<String>, symbol: Option<String>) -> Piece {
+        Piece {
+            start_byte: start.start_byte(),
+            end_byte: node.end_byte(),
+            chunk_type,
+            scope,
+            symbol,
+        }
+    }
+
+    /// Split an oversized function between top-level statements of its body
+    #[allow(clippy::too_many_arguments)]
+    fn split_at_statements(
+        &self,
+        node: Node,
+        start: Node,
+        source: &[u8],
+        chunk_type: ChunkType,
+        scope: Vec<String>,
+        symbol: Option<String>,
+        pieces: &mut Vec<Piece>,
+    ) {
+        let statements: Vec<Node> = match node.child_by_field_name("body") {
+            Some(body) => {
+                let mut cursor = body.walk();
+                body.named_children(&mut cursor).collect()
+            }
+            None => Vec::new(),
+        };
+
+        if statements.len() < 2 {
+            self.split_by_lines(node, start, source, chunk_type, scope, symbol, pieces);
+            return;
+        }
+
+        // Boundaries between statements; the signature stays with the first group
+        let mut boundaries = vec![start.start_byte()];
+        let mut group_start = start.start_byte();
+        for statement in statements.iter().skip(1) {
+            let boundary = leading_trivia_start(*statement).start_byte();
+            if boundary - group_start > self.max_chunk_size && boundary > group_start {
+                boundaries.push(boundary);
+                group_start = boundary;
+            }
+        }
+        boundaries.push(node.end_byte());
+
+        for window in boundaries.windows(2) {
+            let (from, to) = (window[0], window[1]);
+            if to <= from {
+                continue;
+            }
+            pieces.push(Piece {
+                start_byte: from,
+                end_byte: to,
+                chunk_type: chunk_type.clone(),
+                scope: scope.clone(),
+                symbol: symbol.clone(),
+            });
+        }
+    }
+
+    /// Split an oversized item at line boundaries
+    #[allow(clippy::too_many_arguments)]
+    fn split_by_lines(
+        &self,
+        node: Node,
+        start: Node,
+        source: &[u8],
+        chunk_type: ChunkType,
+        scope: Vec<String>,
+        symbol: Option<String>,
+        pieces: &mut Vec<Piece>,
+    ) {
+        let end = node.end_byte();
+        let mut from = start.start_byte();
+
+        while from < end {
+            let mut to = (from + self.max_chunk_size).min(end);
+            if to < end {
+                // Back off to the last newline so lines stay whole
+                match source[from..to].iter().rposition(|&b| b == b'\n') {
+                    Some(newline) if newline > 0 => to = from + newline + 1,
+                    _ => to = char_boundary(source, from, to),
+                }
+            }
+
+            pieces.push(Piece {
+                start_byte: from,
+                end_byte: to,
+                chunk_type: chunk_type.clone(),
+                scope: scope.clone(),
+                symbol: symbol.clone(),
+            });
+            from = to;
+        }
+    }
+
+    /// Merge runs of adjacent small imports and variables into a single chunk
+    fn merge_small_pieces(&self, pieces: Vec<Piece>, source: &[u8]) -> Vec<Piece> {
+        let mut merged: Vec<Piece> = Vec::new();
+
+        for piece in pieces {
+            if let Some(last) = merged.last_mut() {
+                let mergeable = matches!(
+                    (&last.chunk_type, &piece.chunk_type),
+                    (ChunkType::Import, ChunkType::Import) | (ChunkType::Variable, ChunkType::Variable)
+                ) && last.scope.len() == piece.scope.len()
+                    && piece.end_byte - last.start_byte <= self.max_chunk_size
+                    && !source[last.end_byte..piece.start_byte.max(last.end_byte)].contains(&b'{');
+
+                if mergeable {
+                    last.end_byte = piece.end_byte;
+                    if matches!(last.chunk_type, ChunkType::Variable) {
+                        // The scope of a merged run is its parent, not the first item
+                        last.scope.truncate(last.scope.len().saturating_sub(1));
+                        last.symbol = None;
+                    }
+                    continue;
+                }
+            }
+            merged.push(piece);
+        }
+
+        merged
+    }
+
+    fn error_ratio(root: Node) -> f32 {
+        let mut cursor = root.walk();
+        let children: Vec<Node> = root.children(&mut cursor).collect();
+        if children.is_empty() {
+            return 0.0;
+        }
+        let errors = children.iter().filter(|c| c.is_error() || c.is_missing()).count();
+        errors as f32 / children.len() as f32
+    }
+}
+
+/// Tree-sitter grammar for an analyzer language name
+pub(crate) fn grammar(language: &str) -> Option<Language> {
+    match language {
+        "rust" => Some(tree_sitter_rust::language()),
+        "python" => Some(tree_sitter_python::language()),
+        "javascript" => Some(tree_sitter_javascript::language()),
+        "typescript" => Some(tree_sitter_typescript::language_typescript()),
+        "tsx" => Some(tree_sitter_typescript::language_tsx()),
+        "go" => Some(tree_sitter_go::language()),
+        _ => None,
+    }
+}
+
+fn classify(language: &str, node: Node) -> ItemKind {
+    let kind = node.kind();
+    if kind.contains("comment") || kind == "attribute_item" || kind == "inner_attribute_item" || kind == "decorator" {
+        return ItemKind::Skip;
+    }
+
+    match language {
+        "rust" => match kind {
+            "function_item" | "function_signature_item" | "macro_definition" => ItemKind::Function,
+            "impl_item" | "trait_item" => ItemKind::Container,
+            "mod_item" if node.child_by_field_name("body").is_some() => ItemKind::Container,
+            "struct_item" | "enum_item" | "union_item" | "type_item" | "associated_type" => ItemKind::Type,
+            "use_declaration" | "extern_crate_declaration" | "mod_item" => ItemKind::Import,
+            "const_item" | "static_item" | "let_declaration" => ItemKind::Variable,
+            _ => ItemKind::Other,
+        },
+        "python" => match kind {
+            "function_definition" => ItemKind::Function,
+            "class_definition" => ItemKind::Container,
+            "decorated_definition" => ItemKind::Wrapper,
+            "import_statement" | "import_from_statement" | "future_import_statement" => ItemKind::Import,
+            "expression_statement" if is_assignment(node) => ItemKind::Variable,
+            _ => ItemKind::Other,
+        },
+        "javascript" | "typescript" => match kind {
+            "function_declaration" | "generator_function_declaration" | "method_definition"
+            | "function_signature" | "method_signature" => ItemKind::Function,
+            "class_declaration" | "abstract_class_declaration" | "class" => ItemKind::Container,
+            "interface_declaration" | "type_alias_declaration" | "enum_declaration" => ItemKind::Type,
+            "import_statement" => ItemKind::Import,
+            "export_statement" => ItemKind::Wrapper,
+            "lexical_declaration" | "variable_declaration" if declares_function(node) => ItemKind::Function,
+            "lexical_declaration" | "variable_declaration" | "public_field_definition" | "field_definition" => {
+                ItemKind::Variable
+            }
+            _ => ItemKind::Other,
+        },
+        "go" => match kind {
+            "function_declaration" | "method_declaration" => ItemKind::Function,
+            "type_declaration" => ItemKind::Type,
+            "import_declaration" => ItemKind::Import,
+            "const_declaration" | "var_declaration" => ItemKind::Variable,
+            "package_clause" => ItemKind::Skip,
+            _ => ItemKind::Other,
+        },
+        _ => ItemKind::Other,
+    }
+}
+
+fn is_assignment(node: Node) -> bool {
+    node.named_child(0)
+        .is_some_and(|child| matches!(child.kind(), "assignment" | "augmented_assignment"))
+}
+
+/// `const handler = () => {}` and `let f = function () {}`
+fn declares_function(node: Node) -> bool {
+    let mut cursor = node.walk();
+    let declares = node.named_children(&mut cursor).any(|declarator| {
+        declarator
+            .child_by_field_name("value")
+            .is_some_and(|value| matches!(value.kind(), "arrow_function" | "function" | "function_expression"))
+    });
+    declares
+}
+
+/// The item wrapped by a decorator or export statement
+fn inner_declaration(node: Node) -> Option<Node> {
+    node.child_by_field_name("definition")
+        .or_else(|| node.child_by_field_name("declaration"))
+}
+
+fn container_body(node: Node) -> Option<Node> {
+    node.child_by_field_name("body")
+}
+
+/// Extend an item backwards over directly preceding doc comments and attributes
+fn leading_trivia_start(node: Node) -> Node {
+    let mut start = node;
+    while let Some(prev) = start.prev_named_sibling() {
+        let is_trivia = prev.kind().contains("comment") || prev.kind() == "attribute_item" || prev.kind() == "decorator";
+        let adjacent = start.start_position().row.saturating_sub(prev.end_position().row) <= 1;
+        if is_trivia && adjacent {
+            start = prev;
+        } else {
+            break;
+        }
+    }
+    start
+}
+
+fn line_of_byte(source: &[u8], byte: usize) -> usize {
+    source[..byte.min(source.len())].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+fn node_text<'a>(node: Node, source: &'a [u8]) -> Option<&'a str> {
+    node.utf8_text(source).ok()
+}
+
+/// Strip generics, references and pointers from a type name
+fn base_type_name(text: &str) -> String {
+    text.trim_start_matches(['&', '*'])
+        .trim_start_matches("mut ")
+        .split(['<', '['])
+        .next()
+        .unwrap_or(text)
+        .trim()
+        .to_string()
+}
+
+fn item_name(language: &str, node: Node, source: &[u8]) -> Option<String> {
+    match (language, node.kind()) {
+        ("rust", "impl_item") => node
+            .child_by_field_name("type")
+            .and_then(|t| node_text(t, source))
+            .map(base_type_name),
+        ("go", "method_declaration") => {
+            let receiver = node.child_by_field_name("receiver")?;
+            let mut cursor = receiver.walk();
+            let receiver_type = receiver
+                .named_children(&mut cursor)
+                .find_map(|param| param.child_by_field_name("type"))
+                .and_then(|t| node_text(t, source))
+                .map(base_type_name);
+            let method = node.child_by_field_name("name").and_then(|n| node_text(n, source))?;
+            Some(match receiver_type {
+                Some(receiver_type) => format!("{}.{}", receiver_type, method),
+                None => method.to_string(),
+            })
+        }
+        ("go", "type_declaration") | ("go", "const_declaration") | ("go", "var_declaration") => {
+            let mut cursor = node.walk();
+            let first_spec = node.named_children(&mut cursor).next()?;
+            first_spec
+                .child_by_field_name("name")
+                .and_then(|n| node_text(n, source))
+                .map(str::to_string)
+        }
+        (_, "lexical_declaration") | (_, "variable_declaration") => {
+            let mut cursor = node.walk();
+            let declarator = node.named_children(&mut cursor).next()?;
+            declarator
+                .child_by_field_name("name")
+                .and_then(|n| node_text(n, source))
+                .map(str::to_string)
+        }
+        ("python", "expression_statement") => node
+            .named_child(0)
+            .and_then(|assignment| assignment.child_by_field_name("left"))
+            .and_then(|n| node_text(n, source))
+            .map(str::to_string),
+        _ => node
+            .child_by_field_name("name")
+            .and_then(|n| node_text(n, source))
+            .map(str::to_string),
+    }
+}
+
+fn scope_separator(language: &str) -> &'static str {
+    match language {
+        "rust" => "::",
+        _ => ".",
+    }
+}
+
+/// Module path of a file, e.g. `crate::ai::routing` for `src/ai/routing.rs`
+pub fn module_path(file_path: &Path, language: &str) -> Vec<String> {
+    let stem = file_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string();
+
+    match language {
+        "rust" => {
+            let components: Vec<String> = file_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+
+            let Some(src_index) = components.iter().rposition(|c| c == "src") else {
+                return vec!["crate".to_string(), stem];
+            };
+
+            let mut path = vec!["crate".to_string()];
+            let inner = &components[src_index + 1..];
+            for (i, component) in inner.iter().enumerate() {
+                if i + 1 == inner.len() {
+                    if !matches!(stem.as_str(), "lib" | "main" | "mod") {
+                        path.push(stem.clone());
+                    }
+                } else {
+                    path.push(component.clone());
+                }
+            }
+            path
+        }
+        "python" => {
+            // Walk up through packages marked by __init__.py
+            let mut path = Vec::new();
+            if stem != "__init__" {
+                path.push(stem);
+            }
+            let mut dir: Option<PathBuf> = file_path.parent().map(Path::to_path_buf);
+            while let Some(current) = dir {
+                if !current.join("__init__.py").exists() {
+                    break;
+                }
+                if let Some(name) = current.file_name().and_then(|n| n.to_str()) {
+                    path.insert(0, name.to_string());
+                }
+                dir = current.parent().map(Path::to_path_buf);
+            }
+            path
+        }
+        "go" => file_path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .map(|package| vec![package.to_string()])
+            .unwrap_or_default(),
+        _ => vec![stem],
+    }
+}
+
+
+/// Largest UTF-8 boundary in `from..=to`, or the next one after `to` when a
+/// single character is wider than the whole range
+fn char_boundary(source: &[u8], from: usize, to: usize) -> usize {
+    let is_continuation = |i: usize| source.get(i).is_some_and(|&b| b & 0xC0 == 0x80);
+
+    let mut boundary = to;
+    while boundary > from && is_continuation(boundary) {
+        boundary -= 1;
+    }
+    if boundary > from {
+        return boundary;
+    }
+
+    boundary = to;
+    while is_continuation(boundary) {
+        boundary += 1;
+    }
+    boundary
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST_SOURCE: &str = r#"use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Routes tasks to models
+pub struct ModelRouter {
+    models: HashMap<String, Arc<String>>,
+}
+
+impl ModelRouter {
+    /// Pick a model for the task
+    pub fn select_model(&self, task: &str) -> Option<&Arc<String>> {
+        self.models.get(task)
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+}
+"#;
+
+    #[test]
+    fn test_rust_chunks_align_to_items() {
+        let chunker = SyntaxChunker::new(160);
+        let path = PathBuf::from("/repo/src/ai/routing.rs");
+        let chunks = chunker.chunk(&path, RUST_SOURCE, "rust").unwrap();
+
+        let select = chunks
+            .iter()
+            .find(|c| c.symbols.contains(&"select_model".to_string()))
+            .expect("select_model chunk");
+        assert!(select.content.starts_with("/// Pick a model"));
+        assert!(select.content.trim_end().ends_with('}'));
+        assert!(matches!(select.chunk_type, ChunkType::Method));
+        assert_eq!(
+            select.scope_path.as_deref(),
+            Some("crate::ai::routing::ModelRouter::select_model")
+        );
+
+        let imports = chunks.iter().filter(|c| matches!(c.chunk_type, ChunkType::Import)).count();
+        assert_eq!(imports, 1, "adjacent use declarations are merged");
+
+        let header = chunks.iter().find(|c| c.content == "impl ModelRouter {").unwrap();
+        assert_eq!((header.start_line, header.end_line), (9, 9));
+    }
+
+    #[test]
+    fn test_oversized_function_splits_at_statements() {
+        let body: String = (0..40).map(|i| format!("    let value_{} = compute({});\n", i, i)).collect();
+        let source = format!("fn large() {{\n{}}}\n", body);
+
+        let chunker = SyntaxChunker::new(200);
+        let chunks = chunker.chunk(Path::new("src/lib.rs"), &source, "rust").unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks[0].content.starts_with("fn large()"));
+        for chunk in &chunks {
+            assert_eq!(chunk.scope_path.as_deref(), Some("crate::large"));
+            assert!(chunk.content.trim_start().starts_with("let") || chunk.content.starts_with("fn"));
+        }
+    }
+
+    #[test]
+    fn test_python_class_scope() {
+        let source = "class Router:\n    \"\"\"Routes tasks\"\"\"\n\n    def select(self, task):\n        if task.kind == \"code\":\n            return self.code_model\n        return self.default_model\n";
+        let chunker = SyntaxChunker::new(80);
+        let chunks = chunker.chunk(Path::new("routing.py"), source, "python").unwrap();
+
+        let method = chunks.iter().find(|c| c.symbols.contains(&"select".to_string())).unwrap();
+        assert_eq!(method.scope_path.as_deref(), Some("routing.Router.select"));
+        assert!(matches!(method.chunk_type, ChunkType::Method));
+        assert_eq!(method.start_line, 4);
+    }
+
+    #[test]
+    fn test_line_split_keeps_multibyte_characters() {
+        let source = format!("const BANNER: &str = \"{}\";\n", "é".repeat(120));
+        let chunker = SyntaxChunker::new(61);
+        let chunks = chunker.chunk(Path::new("src/lib.rs"), &source, "rust").unwrap();
+
+        assert!(chunks.len() > 1);
+        let joined: String = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(joined.matches('é').count(), 120);
+    }
+
+    #[test]
+    fn test_module_path() {
+        assert_eq!(module_path(Path::new("/x/src/ai/mod.rs"), "rust"), vec!["crate", "ai"]);
+        assert_eq!(module_path(Path::new("/x/src/lib.rs"), "rust"), vec!["crate"]);
+        assert_eq!(module_path(Path::new("/x/pkg/server/handler.go"), "go"), vec!["server"]);
+    }
+}
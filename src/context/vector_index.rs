@@ -0,0 +1,197 @@
+//! Vector search backends for the embedding store
+//!
+//! `VectorStore` answers similarity queries through a `VectorSearchBackend`.
+//! Exact cosine search is always available; with the `faiss-vector-db`
+//! feature an approximate HNSW index is used instead, falling back to exact
+//! search if the index cannot be built.
+
+use std::sync::Arc;
+
+use super::embeddings::cosine_similarity;
+#[cfg(feature = "faiss-vector-db")]
+use super::embeddings::EmbeddingError;
+
+/// Nearest-neighbour search over a fixed set of vectors
+pub trait VectorSearchBackend: Send + Sync + std::fmt::Debug {
+    /// Return up to `k` `(id, cosine similarity)` pairs, best first
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)>;
+
+    /// Number of indexed vectors
+    fn len(&self) -> usize;
+
+    /// Backend name for diagnostics
+    fn name(&self) -> &'static str;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Brute-force cosine search
+#[derive(Debug, Default)]
+pub struct ExactBackend {
+    ids: Vec<String>,
+    vectors: Vec<Vec<f32>>,
+}
+
+impl ExactBackend {
+    pub fn new(entries: Vec<(String, Vec<f32>)>) -> Self {
+        let (ids, vectors) = entries.into_iter().unzip();
+        Self { ids, vectors }
+    }
+}
+
+impl VectorSearchBackend for ExactBackend {
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        let mut scored: Vec<(String, f32)> = self
+            .ids
+            .iter()
+            .zip(&self.vectors)
+            .map(|(id, vector)| (id.clone(), cosine_similarity(query, vector)))
+            .collect();
+
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+        scored
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+}
+
+/// Approximate search with a FAISS HNSW index over normalized vectors
+#[cfg(feature = "faiss-vector-db")]
+pub struct FaissBackend {
+    index: std::sync::Mutex<faiss::index::IndexImpl>,
+    ids: Vec<String>,
+    dimension: usize,
+}
+
+#[cfg(feature = "faiss-vector-db")]
+impl FaissBackend {
+    pub fn build(dimension: usize, entries: Vec<(String, Vec<f32>)>) -> Result<Self, EmbeddingError> {
+        use faiss::Index;
+
+        let mut index = faiss::index_factory(dimension as u32, "HNSW32", faiss::MetricType::InnerProduct)
+            .map_err(|e| EmbeddingError::ConfigError(format!("Failed to create FAISS index: {}", e)))?;
+
+        let mut ids = Vec::with_capacity(entries.len());
+        let mut data = Vec::with_capacity(entries.len() * dimension);
+        for (id, vector) in entries {
+            if vector.len() != dimension {
+                continue;
+            }
+            ids.push(id);
+            data.extend(normalized(&vector));
+        }
+
+        index
+            .add(&data)
+            .map_err(|e| EmbeddingError::EmbeddingGenerationError(format!("Failed to populate FAISS index: {}", e)))?;
+
+        Ok(Self {
+            index: std::sync::Mutex::new(index),
+            ids,
+            dimension,
+        })
+    }
+}
+
+#[cfg(feature = "faiss-vector-db")]
+impl std::fmt::Debug for FaissBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaissBackend")
+            .field("vectors", &self.ids.len())
+            .field("dimension", &self.dimension)
+            .finish()
+    }
+}
+
+#[cfg(feature = "faiss-vector-db")]
+impl VectorSearchBackend for FaissBackend {
+    fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        use faiss::Index;
+
+        if query.len() != self.dimension || self.ids.is_empty() {
+            return Vec::new();
+        }
+
+        let Ok(mut index) = self.index.lock() else {
+            return Vec::new();
+        };
+
+        match index.search(&normalized(query), k.min(self.ids.len())) {
+            Ok(result) => result
+                .labels
+                .iter()
+                .zip(result.distances.iter())
+                .filter_map(|(label, &score)| {
+                    label
+                        .get()
+                        .and_then(|position| self.ids.get(position as usize))
+                        .map(|id| (id.clone(), score))
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("FAISS search failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn name(&self) -> &'static str {
+        "faiss-hnsw"
+    }
+}
+
+#[cfg(feature = "faiss-vector-db")]
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
+/// Build the best available backend for the given vectors
+pub fn build_backend(dimension: usize, entries: Vec<(String, Vec<f32>)>) -> Arc<dyn VectorSearchBackend> {
+    #[cfg(feature = "faiss-vector-db")]
+    {
+        match FaissBackend::build(dimension, entries.clone()) {
+            Ok(backend) => return Arc::new(backend),
+            Err(e) => tracing::warn!("Falling back to exact vector search: {}", e),
+        }
+    }
+
+    let _ = dimension;
+    Arc::new(ExactBackend::new(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_backend_orders_by_similarity() {
+        let backend = ExactBackend::new(vec![
+            ("a".to_string(), vec![1.0, 0.0]),
+            ("b".to_string(), vec![0.0, 1.0]),
+            ("c".to_string(), vec![0.7, 0.7]),
+        ]);
+
+        let results = backend.search(&[1.0, 0.1], 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "a");
+        assert_eq!(results[1].0, "c");
+    }
+}
@@ -0,0 +1,330 @@
+//! Gitignore-aware, parallel file discovery
+//!
+//! `FileWalker` lists the files of a codebase the way git sees them:
+//! `.gitignore` files at every level, `.git/info/exclude` and the global
+//! excludes file are honoured, as is a `.devkitignore` for files that git
+//! tracks but analysis should skip. Directories are traversed on a pool of
+//! threads, and the include/exclude globs of the `AnalysisConfig` are applied
+//! on top of the ignore rules.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder, WalkState};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use super::{AnalysisConfig, ContextError};
+
+/// Ignore file for paths that should not be analyzed, in gitignore syntax
+pub const DEVKIT_IGNORE_FILE: &str = ".devkitignore";
+
+/// Number of leading bytes inspected when sniffing for binary content
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Whether content looks binary, using git's heuristic of a NUL byte near
+/// the start of the file
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// Lists the files of a codebase that should be analyzed
+#[derive(Debug, Clone)]
+pub struct FileWalker {
+    root: PathBuf,
+    /// `None` includes every file
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    max_file_size: u64,
+    follow_symlinks: bool,
+    respect_gitignore: bool,
+    /// The user's global excludes file, rooted at `root`
+    global_ignore: Gitignore,
+    threads: usize,
+}
+
+impl FileWalker {
+    pub fn new(root: &Path, config: &AnalysisConfig) -> Result<Self, ContextError> {
+        let include = if config.include_patterns.is_empty()
+            || config.include_patterns.iter().any(|p| p == "**/*" || p == "*")
+        {
+            None
+        } else {
+            Some(build_globset(&config.include_patterns)?)
+        };
+
+        let global_ignore = if config.respect_gitignore {
+            // Gitignore::global() roots the matcher at the process cwd, which
+            // can't match paths under a root outside it
+            let (global, error) = GitignoreBuilder::new(root).build_global();
+            if let Some(e) = error {
+                tracing::debug!("Ignoring unreadable global excludes file: {}", e);
+            }
+            global
+        } else {
+            Gitignore::empty()
+        };
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            include,
+            exclude: build_globset(&config.exclude_patterns)?,
+            max_file_size: (config.max_file_size_mb * 1024 * 1024) as u64,
+            follow_symlinks: config.follow_symlinks,
+            respect_gitignore: config.respect_gitignore,
+            global_ignore,
+            threads: config.max_concurrency.max(1),
+        })
+    }
+
+    /// Walk the codebase in parallel and return the accepted files, sorted
+    pub fn walk(&self) -> Vec<PathBuf> {
+        let mut builder = WalkBuilder::new(&self.root);
+        builder
+            .hidden(false)
+            .follow_links(self.follow_symlinks)
+            .git_ignore(self.respect_gitignore)
+            .git_exclude(self.respect_gitignore)
+            .git_global(self.respect_gitignore)
+            .ignore(self.respect_gitignore)
+            .parents(self.respect_gitignore)
+            // Honour .gitignore files even outside a git checkout
+            .require_git(false)
+            .add_custom_ignore_filename(DEVKIT_IGNORE_FILE)
+            .threads(self.threads);
+
+        // Prune excluded directories instead of filtering every file in them
+        let root = self.root.clone();
+        let exclude = self.exclude.clone();
+        builder.filter_entry(move |entry| {
+            if entry.depth() == 0 || !entry.file_type().is_some_and(|t| t.is_dir()) {
+                return true;
+            }
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            !excludes_dir(&exclude, relative)
+        });
+
+        let (tx, rx) = mpsc::channel();
+        builder.build_parallel().run(|| {
+            let tx = tx.clone();
+            Box::new(move |result| {
+                match result {
+                    Ok(entry) => {
+                        let is_file = entry.file_type().is_some_and(|t| t.is_file());
+                        if is_file && self.accepts(entry.path(), entry.metadata().ok().map(|m| m.len())) {
+                            let _ = tx.send(entry.into_path());
+                        }
+                    }
+                    Err(e) => tracing::debug!("Skipping unreadable path: {}", e),
+                }
+                WalkState::Continue
+            })
+        });
+        drop(tx);
+
+        let mut files: Vec<PathBuf> = rx.into_iter().collect();
+        files.sort();
+        files
+    }
+
+    /// Whether a single path belongs to the analyzed files, for incremental
+    /// updates that don't walk the tree
+    pub fn is_included(&self, path: &Path) -> bool {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        metadata.is_file() && self.accepts(path, Some(metadata.len())) && !self.is_ignored(path)
+    }
+
+    /// Apply include/exclude globs and the size limit
+    fn accepts(&self, path: &Path, size: Option<u64>) -> bool {
+        if size.is_some_and(|size| size > self.max_file_size) {
+            return false;
+        }
+
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if self.exclude.is_match(relative) {
+            return false;
+        }
+        match &self.include {
+            Some(include) => include.is_match(relative),
+            None => true,
+        }
+    }
+
+    /// Check ignore files from the file's directory up to the root; the
+    /// deepest file with a matching rule decides, as in git
+    fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return true;
+        };
+
+        let mut ignore_files = Vec::new();
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            ignore_files.push(current.join(DEVKIT_IGNORE_FILE));
+            if self.respect_gitignore {
+                ignore_files.push(current.join(".gitignore"));
+            }
+            if current == self.root {
+                break;
+            }
+            dir = current.parent();
+        }
+        if self.respect_gitignore {
+            ignore_files.push(self.root.join(".git").join("info").join("exclude"));
+        }
+
+        if relative
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.ancestors())
+            .any(|dir| !dir.as_os_str().is_empty() && excludes_dir(&self.exclude, dir))
+        {
+            return true;
+        }
+
+        for ignore_file in ignore_files.iter().filter(|f| f.is_file()) {
+            // .git/info/exclude patterns are relative to the repository root
+            let base = if ignore_file.ends_with(".git/info/exclude") {
+                self.root.as_path()
+            } else {
+                ignore_file.parent().unwrap_or(&self.root)
+            };
+            let mut builder = GitignoreBuilder::new(base);
+            builder.add(ignore_file);
+            let Ok(matcher) = builder.build() else {
+                continue;
+            };
+            match matcher.matched_path_or_any_parents(path, false) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        self.global_ignore
+            .matched_path_or_any_parents(relative, false)
+            .is_ignore()
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, ContextError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| ContextError::AnalysisFailed(format!("Invalid glob pattern {}: {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| ContextError::AnalysisFailed(format!("Failed to build glob set: {}", e)))
+}
+
+/// Whether exclude patterns such as `**/target/**` cover everything below `dir`
+fn excludes_dir(exclude: &GlobSet, dir: &Path) -> bool {
+    // Probe with a child path so `dir/**` style patterns match the directory
+    exclude.is_match(dir) || exclude.is_match(dir.join("_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write(root: &Path, path: &str, content: &[u8]) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn relative(root: &Path, files: Vec<PathBuf>) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_honours_ignore_files_and_globs() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", b"target/\n*.generated.rs\n!keep.generated.rs\n");
+        write(root, ".devkitignore", b"fixtures/\n");
+        write(root, "src/main.rs", b"fn main() {}");
+        write(root, "src/lib.generated.rs", b"// generated");
+        write(root, "src/keep.generated.rs", b"// generated");
+        write(root, "src/nested/.gitignore", b"scratch.rs\n");
+        write(root, "src/nested/scratch.rs", b"");
+        write(root, "src/nested/mod.rs", b"");
+        write(root, "target/debug/build.rs", b"");
+        write(root, "fixtures/sample.rs", b"");
+        write(root, "vendor/dep.rs", b"");
+        write(root, "notes.md", b"# notes");
+
+        let config = AnalysisConfig {
+            include_patterns: vec!["**/*.rs".to_string()],
+            exclude_patterns: vec!["**/vendor/**".to_string()],
+            ..AnalysisConfig::default()
+        };
+        let walker = FileWalker::new(root, &config).unwrap();
+
+        assert_eq!(
+            relative(root, walker.walk()),
+            vec!["src/keep.generated.rs", "src/main.rs", "src/nested/mod.rs"]
+        );
+
+        assert!(walker.is_included(&root.join("src/main.rs")));
+        assert!(!walker.is_included(&root.join("src/nested/scratch.rs")));
+        assert!(!walker.is_included(&root.join("target/debug/build.rs")));
+        assert!(!walker.is_included(&root.join("fixtures/sample.rs")));
+        assert!(!walker.is_included(&root.join("vendor/dep.rs")));
+        assert!(walker.is_included(&root.join("src/keep.generated.rs")));
+    }
+
+    #[test]
+    fn test_gitignore_can_be_disabled() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", b"generated/\n");
+        write(root, "generated/out.rs", b"");
+
+        let config = AnalysisConfig {
+            respect_gitignore: false,
+            exclude_patterns: Vec::new(),
+            ..AnalysisConfig::default()
+        };
+        let files = FileWalker::new(root, &config).unwrap().walk();
+        assert!(files.contains(&root.join("generated/out.rs")));
+    }
+
+    #[test]
+    fn test_global_excludes_apply_to_roots_outside_the_cwd() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert!(!root.starts_with(std::env::current_dir().unwrap()));
+        write(root, "src/main.rs", b"fn main() {}");
+        write(root, "src/main.rs.orig", b"");
+        write(root, "excludes", b"*.orig\n");
+
+        let mut walker = FileWalker::new(root, &AnalysisConfig::default()).unwrap();
+        assert!(walker.global_ignore.is_empty() || walker.global_ignore.path() == root);
+
+        // Stand in for the user's global excludes file
+        let mut builder = GitignoreBuilder::new(root);
+        builder.add(root.join("excludes"));
+        walker.global_ignore = builder.build().unwrap();
+
+        assert!(walker.is_included(&root.join("src/main.rs")));
+        assert!(!walker.is_included(&root.join("src/main.rs.orig")));
+    }
+
+    #[test]
+    fn test_binary_sniffing() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00\x00"));
+        assert!(!is_binary("fn main() { println!(\"héllo\"); }".as_bytes()));
+        assert!(!is_binary(b""));
+    }
+}
@@ -0,0 +1,323 @@
+//! File watcher that keeps a `CodebaseContext` current
+//!
+//! `ContextWatcher` listens for file system events under the codebase root,
+//! debounces bursts of events (editors often write a file several times per
+//! save) and feeds the affected paths to `ContextManager::update_context`.
+//! Update summaries are broadcast to local subscribers, which is how the
+//! interactive UI and web dashboard stay current. Given the application's
+//! shared bus with `with_bus`, every applied change is also published there
+//! as `SystemEvent::ContextUpdated`.
+
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use super::{AnalysisConfig, CodebaseContext, ContextError, ContextManager, ContextUpdateSummary};
+use crate::system_bus::{SystemBusHandle, SystemEvent};
+
+/// Directories whose contents never belong to the analyzed context
+const IGNORED_DIRS: &[&str] = &[".git", ".devkit", "target", "node_modules", "__pycache__", ".idea", ".vscode"];
+
+/// Watcher configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherConfig {
+    /// Quiet period after the last event before an update is applied
+    pub debounce_ms: u64,
+    /// Maximum number of paths applied in one update
+    pub max_batch_size: usize,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 300,
+            max_batch_size: 256,
+        }
+    }
+}
+
+/// Watches a codebase and applies incremental context updates
+pub struct ContextWatcher {
+    config: WatcherConfig,
+    analysis_config: AnalysisConfig,
+    context_manager: Arc<RwLock<ContextManager>>,
+    context: Arc<RwLock<CodebaseContext>>,
+    bus: Option<SystemBusHandle>,
+    updates: broadcast::Sender<ContextUpdateSummary>,
+    watcher: Option<RecommendedWatcher>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ContextWatcher {
+    /// Create a watcher for the context's root path
+    pub fn new(
+        context_manager: Arc<RwLock<ContextManager>>,
+        context: Arc<RwLock<CodebaseContext>>,
+        analysis_config: AnalysisConfig,
+        config: WatcherConfig,
+    ) -> Self {
+        let (updates, _) = broadcast::channel(64);
+
+        Self {
+            config,
+            analysis_config,
+            context_manager,
+            context,
+            bus: None,
+            updates,
+            watcher: None,
+            task: None,
+        }
+    }
+
+    /// Publish `SystemEvent::ContextUpdated` on a bus the UI, agents and code
+    /// generator are registered with; a bus without them drops every event
+    pub fn with_bus(mut self, bus: SystemBusHandle) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Subscribe to update summaries
+    pub fn subscribe(&self) -> broadcast::Receiver<ContextUpdateSummary> {
+        self.updates.subscribe()
+    }
+
+    /// Whether the watcher is running
+    pub fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    /// Start watching the codebase root
+    pub async fn start(&mut self) -> Result<(), ContextError> {
+        if self.is_running() {
+            return Ok(());
+        }
+
+        let root = self.context.read().await.root_path.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut watcher = RecommendedWatcher::new(
+            move |res| {
+                if tx.send(res).is_err() {
+                    debug!("Context watcher channel closed");
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| ContextError::AnalysisFailed(format!("Failed to create file watcher: {}", e)))?;
+
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| ContextError::AnalysisFailed(format!("Failed to watch {}: {}", root.display(), e)))?;
+
+        let task = tokio::spawn(Self::watch_loop(
+            rx,
+            self.config.clone(),
+            self.analysis_config.clone(),
+            Arc::clone(&self.context_manager),
+            Arc::clone(&self.context),
+            self.bus.clone(),
+            self.updates.clone(),
+        ));
+
+        self.watcher = Some(watcher);
+        self.task = Some(task);
+        Ok(())
+    }
+
+    /// Stop watching
+    pub fn stop(&mut self) {
+        self.watcher = None;
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Collect events until the debounce window passes without new ones
+    async fn watch_loop(
+        mut events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+        config: WatcherConfig,
+        analysis_config: AnalysisConfig,
+        context_manager: Arc<RwLock<ContextManager>>,
+        context: Arc<RwLock<CodebaseContext>>,
+        bus: Option<SystemBusHandle>,
+        updates: broadcast::Sender<ContextUpdateSummary>,
+    ) {
+        let debounce = Duration::from_millis(config.debounce_ms);
+
+        while let Some(first) = events.recv().await {
+            let mut pending = HashSet::new();
+            Self::collect_paths(first, &mut pending);
+
+            let mut closed = false;
+            loop {
+                match tokio::time::timeout(debounce, events.recv()).await {
+                    Ok(Some(event)) => Self::collect_paths(event, &mut pending),
+                    Ok(None) => {
+                        closed = true;
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            let mut paths: Vec<PathBuf> = pending.into_iter().collect();
+            paths.sort();
+            for batch in paths.chunks(config.max_batch_size.max(1)) {
+                Self::apply_update(batch, &analysis_config, &context_manager, &context, bus.as_ref(), &updates)
+                    .await;
+            }
+
+            if closed {
+                break;
+            }
+        }
+
+        debug!("Context watcher stopped");
+    }
+
+    fn collect_paths(event: notify::Result<Event>, pending: &mut HashSet<PathBuf>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("File watch error: {}", e);
+                return;
+            }
+        };
+
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any
+        ) {
+            return;
+        }
+
+        pending.extend(event.paths.into_iter().filter(|path| !Self::is_ignored(path)));
+    }
+
+    /// Skip version control, build output and editor scratch files
+    fn is_ignored(path: &Path) -> bool {
+        if path.components().any(|component| {
+            IGNORED_DIRS
+                .iter()
+                .any(|dir| component.as_os_str() == std::ffi::OsStr::new(dir))
+        }) {
+            return true;
+        }
+
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        name.ends_with('~')
+            || name.ends_with(".swp")
+            || name.ends_with(".swx")
+            || name.ends_with(".tmp")
+            || name.starts_with(".#")
+            || name == "4913" // Vim probes directory writability with this file
+    }
+
+    async fn apply_update(
+        paths: &[PathBuf],
+        analysis_config: &AnalysisConfig,
+        context_manager: &Arc<RwLock<ContextManager>>,
+        context: &Arc<RwLock<CodebaseContext>>,
+        bus: Option<&SystemBusHandle>,
+        updates: &broadcast::Sender<ContextUpdateSummary>,
+    ) {
+        let summary = {
+            let mut context = context.write().await;
+            let mut manager = context_manager.write().await;
+            match manager.update_context(paths, &mut context, analysis_config).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    error!("Failed to apply context update for {} paths: {}", paths.len(), e);
+                    return;
+                }
+            }
+        };
+
+        if summary.is_empty() {
+            return;
+        }
+
+        if let Some(bus) = bus {
+            for (path, change_type) in summary.changes() {
+                let event = SystemEvent::ContextUpdated {
+                    file_path: path.to_string_lossy().to_string(),
+                    change_type: change_type.to_string(),
+                };
+                if let Err(e) = bus.publish(event).await {
+                    warn!("Failed to publish context update: {}", e);
+                }
+            }
+        }
+
+        // No subscribers is not an error
+        let _ = updates.send(summary);
+    }
+}
+
+impl Drop for ContextWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignored_paths() {
+        assert!(ContextWatcher::is_ignored(Path::new("/repo/target/debug/build.rs")));
+        assert!(ContextWatcher::is_ignored(Path::new("/repo/.devkit/index/manifest.json")));
+        assert!(ContextWatcher::is_ignored(Path::new("/repo/src/.main.rs.swp")));
+        assert!(ContextWatcher::is_ignored(Path::new("/repo/src/main.rs~")));
+        assert!(!ContextWatcher::is_ignored(Path::new("/repo/src/main.rs")));
+    }
+
+    #[tokio::test]
+    async fn test_update_context_tracks_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::fs::write(root.join("lib.rs"), "pub fn alpha() {}\n").unwrap();
+
+        let config = AnalysisConfig {
+            persist_index: false,
+            ..AnalysisConfig::default()
+        };
+        let mut manager = ContextManager::new().unwrap();
+        let mut context = manager.analyze_codebase(root.clone(), config.clone()).await.unwrap();
+        assert_eq!(context.files.len(), 1);
+
+        // Unchanged content is a no-op
+        let summary = manager
+            .update_context(&[root.join("lib.rs")], &mut context, &config)
+            .await
+            .unwrap();
+        assert!(summary.is_empty());
+
+        std::fs::write(root.join("lib.rs"), "pub fn beta() {}\n").unwrap();
+        std::fs::write(root.join("extra.rs"), "pub fn gamma() {}\n").unwrap();
+        let summary = manager
+            .update_context(&[root.join("lib.rs"), root.join("extra.rs")], &mut context, &config)
+            .await
+            .unwrap();
+        assert_eq!(summary.updated, vec![root.join("lib.rs")]);
+        assert_eq!(summary.added, vec![root.join("extra.rs")]);
+        assert_eq!(context.metadata.total_files, 2);
+
+        std::fs::remove_file(root.join("extra.rs")).unwrap();
+        let summary = manager
+            .update_context(&[root.join("extra.rs")], &mut context, &config)
+            .await
+            .unwrap();
+        assert_eq!(summary.removed, vec![root.join("extra.rs")]);
+        assert!(context.symbols.find_symbols("gamma").is_empty());
+        assert_eq!(context.files.len(), 1);
+    }
+}
@@ -27,6 +27,7 @@ pub mod integration;
 pub mod integrations;
 pub mod interactive;
 pub mod logging;
+pub mod lsp;
 pub mod monitoring;
 pub mod plugins;
 pub mod sandbox;
@@ -0,0 +1,853 @@
+//! Client side of the Language Server Protocol
+//!
+//! `LanguageServerClient` drives an external language server such as
+//! rust-analyzer, pyright or gopls over a JSON-RPC stream: it performs the
+//! initialize handshake, keeps documents in sync with full-text updates,
+//! records published diagnostics and answers the few requests servers send
+//! back to the client.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, Notify};
+use tokio::task::JoinHandle;
+use tower_lsp::lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Initialized,
+    Notification, PublishDiagnostics,
+};
+use tower_lsp::lsp_types::request::{GotoDefinition, HoverRequest, Initialize, Request, Shutdown};
+use tower_lsp::lsp_types::{
+    ClientCapabilities, ClientInfo, Diagnostic, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, GotoCapability, GotoDefinitionParams,
+    GotoDefinitionResponse, HoverClientCapabilities, HoverContents, HoverParams, InitializeParams,
+    InitializedParams, Location, MarkedString, MarkupKind, Position,
+    PublishDiagnosticsClientCapabilities, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentClientCapabilities, TextDocumentContentChangeEvent, TextDocumentIdentifier,
+    TextDocumentItem, TextDocumentPositionParams, TextDocumentSyncClientCapabilities, Url,
+    VersionedTextDocumentIdentifier, WorkspaceFolder,
+};
+
+use crate::config::LanguageServerConfig;
+
+/// How long a server gets to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Errors talking to a language server
+#[derive(Debug, Error)]
+pub enum LspClientError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to start language server '{0}': {1}")]
+    SpawnFailed(String, String),
+
+    #[error("Protocol error: {0}")]
+    Protocol(String),
+
+    #[error("Language server error {code}: {message}")]
+    Server { code: i64, message: String },
+
+    #[error("Language server timed out on {0}")]
+    Timeout(String),
+
+    #[error("Language server connection closed")]
+    Closed,
+
+    #[error("Invalid document path: {0}")]
+    InvalidPath(String),
+}
+
+type PendingResponse = oneshot::Sender<Result<Value, LspClientError>>;
+type SharedWriter = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+/// Diagnostics last published for a document
+#[derive(Debug, Clone, Default)]
+struct Published {
+    /// Increases with every publish, so waiters can tell a fresh report from a stale one
+    sequence: u64,
+    version: Option<i32>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// An open document as the server sees it
+#[derive(Debug)]
+struct OpenDocument {
+    version: i32,
+    text: String,
+}
+
+/// State shared between the client and its reader task
+#[derive(Default)]
+struct Shared {
+    pending: Mutex<HashMap<i64, PendingResponse>>,
+    diagnostics: Mutex<HashMap<Url, Published>>,
+    diagnostics_published: Notify,
+}
+
+/// A connection to one running language server
+pub struct LanguageServerClient {
+    language: String,
+    writer: SharedWriter,
+    shared: Arc<Shared>,
+    next_id: AtomicI64,
+    documents: tokio::sync::Mutex<HashMap<Url, OpenDocument>>,
+    capabilities: ServerCapabilities,
+    server_name: Option<String>,
+    reader: JoinHandle<()>,
+    child: Option<tokio::sync::Mutex<Child>>,
+}
+
+impl LanguageServerClient {
+    /// Launch the configured server in `root` and initialize it
+    pub async fn spawn(config: &LanguageServerConfig, root: &Path) -> Result<Self, LspClientError> {
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| LspClientError::SpawnFailed(config.command.clone(), e.to_string()))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(LspClientError::SpawnFailed(
+                config.command.clone(),
+                "standard streams unavailable".to_string(),
+            ));
+        };
+
+        let mut client = Self::connect(
+            &config.language,
+            stdout,
+            stdin,
+            root,
+            config.initialization_options.clone(),
+        )
+        .await?;
+        client.child = Some(tokio::sync::Mutex::new(child));
+        Ok(client)
+    }
+
+    /// Initialize a server reachable over an existing stream pair
+    pub async fn connect<R, W>(
+        language: &str,
+        reader: R,
+        writer: W,
+        root: &Path,
+        initialization_options: Option<Value>,
+    ) -> Result<Self, LspClientError>
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: SharedWriter = Arc::new(tokio::sync::Mutex::new(Box::new(writer)));
+        let shared = Arc::new(Shared::default());
+        let reader = tokio::spawn(read_messages(
+            BufReader::new(reader),
+            shared.clone(),
+            writer.clone(),
+        ));
+
+        let mut client = Self {
+            language: language.to_string(),
+            writer,
+            shared,
+            next_id: AtomicI64::new(1),
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+            capabilities: ServerCapabilities::default(),
+            server_name: None,
+            reader,
+            child: None,
+        };
+
+        let root_uri = Url::from_directory_path(root)
+            .map_err(|_| LspClientError::InvalidPath(root.display().to_string()))?;
+        #[allow(deprecated)]
+        let params = InitializeParams {
+            process_id: Some(std::process::id()),
+            root_uri: Some(root_uri.clone()),
+            workspace_folders: Some(vec![WorkspaceFolder {
+                name: root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                uri: root_uri,
+            }]),
+            initialization_options,
+            capabilities: client_capabilities(),
+            client_info: Some(ClientInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            ..InitializeParams::default()
+        };
+
+        let result = client.request::<Initialize>(params).await?;
+        client.capabilities = result.capabilities;
+        client.server_name = result.server_info.map(|info| info.name);
+        client.notify::<Initialized>(InitializedParams {}).await?;
+        Ok(client)
+    }
+
+    /// Language the server was started for
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Name the server reported during initialization
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+
+    pub fn capabilities(&self) -> &ServerCapabilities {
+        &self.capabilities
+    }
+
+    /// Send a request and wait for its result
+    pub async fn request<R: Request>(
+        &self,
+        params: R::Params,
+    ) -> Result<R::Result, LspClientError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, sender);
+
+        let message = rpc_message(Some(id), R::METHOD, params)?;
+        if let Err(e) = write_message(&self.writer, &message).await {
+            self.forget(id);
+            return Err(e);
+        }
+
+        let value = match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(result)) => result?,
+            Ok(Err(_)) => return Err(LspClientError::Closed),
+            Err(_) => {
+                self.forget(id);
+                return Err(LspClientError::Timeout(R::METHOD.to_string()));
+            }
+        };
+        serde_json::from_value(value)
+            .map_err(|e| LspClientError::Protocol(format!("Invalid {} result: {}", R::METHOD, e)))
+    }
+
+    /// Send a notification
+    pub async fn notify<N: Notification>(&self, params: N::Params) -> Result<(), LspClientError> {
+        let message = rpc_message(None, N::METHOD, params)?;
+        write_message(&self.writer, &message).await
+    }
+
+    fn forget(&self, id: i64) {
+        self.shared
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id);
+    }
+
+    /// Make the server see `text` as the content of `path`.
+    ///
+    /// Opens the document on first use and sends the full text on later
+    /// changes. Returns the document version and whether anything was sent.
+    pub async fn sync_document(
+        &self,
+        path: &Path,
+        text: &str,
+    ) -> Result<(i32, bool), LspClientError> {
+        let uri = document_uri(path)?;
+        let mut documents = self.documents.lock().await;
+
+        match documents.get_mut(&uri) {
+            Some(document) if document.text == text => Ok((document.version, false)),
+            Some(document) => {
+                document.version += 1;
+                document.text = text.to_string();
+                let version = document.version;
+                self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+                    text_document: VersionedTextDocumentIdentifier::new(uri, version),
+                    content_changes: vec![TextDocumentContentChangeEvent {
+                        range: None,
+                        range_length: None,
+                        text: text.to_string(),
+                    }],
+                })
+                .await?;
+                Ok((version, true))
+            }
+            None => {
+                self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                    text_document: TextDocumentItem::new(
+                        uri.clone(),
+                        language_id(path, &self.language),
+                        1,
+                        text.to_string(),
+                    ),
+                })
+                .await?;
+                documents.insert(
+                    uri,
+                    OpenDocument {
+                        version: 1,
+                        text: text.to_string(),
+                    },
+                );
+                Ok((1, true))
+            }
+        }
+    }
+
+    /// Close a document so the server reads it from disk again
+    pub async fn close_document(&self, path: &Path) -> Result<(), LspClientError> {
+        let uri = document_uri(path)?;
+        if self.documents.lock().await.remove(&uri).is_none() {
+            return Ok(());
+        }
+        self.shared
+            .diagnostics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&uri);
+        self.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri),
+        })
+        .await
+    }
+
+    /// Diagnostics for `path` with `text` as its content.
+    ///
+    /// Waits up to `timeout` for the server to publish diagnostics for the
+    /// synced version. Servers that are still loading the workspace may not
+    /// answer in time, in which case the last published diagnostics are
+    /// returned.
+    pub async fn diagnostics(
+        &self,
+        path: &Path,
+        text: &str,
+        timeout: Duration,
+    ) -> Result<Vec<Diagnostic>, LspClientError> {
+        let uri = document_uri(path)?;
+        let before = self.published(&uri).map(|published| published.sequence);
+        let (version, changed) = self.sync_document(path, text).await?;
+        if !changed && before.is_some() {
+            return Ok(self
+                .published(&uri)
+                .map(|p| p.diagnostics)
+                .unwrap_or_default());
+        }
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let published = self.shared.diagnostics_published.notified();
+            if let Some(current) = self.published(&uri) {
+                let fresh = Some(current.sequence) != before;
+                if fresh && current.version.map_or(true, |v| v >= version) {
+                    return Ok(current.diagnostics);
+                }
+            }
+            if self.reader.is_finished() {
+                return Err(LspClientError::Closed);
+            }
+            if tokio::time::timeout_at(deadline, published).await.is_err() {
+                return Ok(self
+                    .published(&uri)
+                    .map(|p| p.diagnostics)
+                    .unwrap_or_default());
+            }
+        }
+    }
+
+    fn published(&self, uri: &Url) -> Option<Published> {
+        self.shared
+            .diagnostics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(uri)
+            .cloned()
+    }
+
+    /// Hover text at a position, usually the type or signature under the cursor
+    pub async fn hover(
+        &self,
+        path: &Path,
+        text: &str,
+        position: Position,
+    ) -> Result<Option<String>, LspClientError> {
+        if self.capabilities.hover_provider.is_none() {
+            return Ok(None);
+        }
+        self.sync_document(path, text).await?;
+        let hover = self
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: position_params(path, position)?,
+                work_done_progress_params: Default::default(),
+            })
+            .await?;
+        Ok(hover
+            .map(|hover| hover_text(&hover.contents))
+            .filter(|text| !text.trim().is_empty()))
+    }
+
+    /// Definitions of the symbol at a position
+    pub async fn definition(
+        &self,
+        path: &Path,
+        text: &str,
+        position: Position,
+    ) -> Result<Vec<Location>, LspClientError> {
+        if self.capabilities.definition_provider.is_none() {
+            return Ok(Vec::new());
+        }
+        self.sync_document(path, text).await?;
+        let response = self
+            .request::<GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: position_params(path, position)?,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            })
+            .await?;
+        Ok(match response {
+            None => Vec::new(),
+            Some(GotoDefinitionResponse::Scalar(location)) => vec![location],
+            Some(GotoDefinitionResponse::Array(locations)) => locations,
+            Some(GotoDefinitionResponse::Link(links)) => links
+                .into_iter()
+                .map(|link| Location::new(link.target_uri, link.target_selection_range))
+                .collect(),
+        })
+    }
+
+    /// Ask the server to shut down and wait briefly for it to exit
+    pub async fn shutdown(&self) -> Result<(), LspClientError> {
+        self.request::<Shutdown>(()).await?;
+        self.notify::<Exit>(()).await?;
+        if let Some(child) = &self.child {
+            let mut child = child.lock().await;
+            if tokio::time::timeout(Duration::from_secs(2), child.wait())
+                .await
+                .is_err()
+            {
+                let _ = child.kill().await;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LanguageServerClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl std::fmt::Debug for LanguageServerClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanguageServerClient")
+            .field("language", &self.language)
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+fn client_capabilities() -> ClientCapabilities {
+    ClientCapabilities {
+        text_document: Some(TextDocumentClientCapabilities {
+            synchronization: Some(TextDocumentSyncClientCapabilities::default()),
+            hover: Some(HoverClientCapabilities {
+                content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                ..HoverClientCapabilities::default()
+            }),
+            definition: Some(GotoCapability {
+                link_support: Some(true),
+                ..GotoCapability::default()
+            }),
+            publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
+                version_support: Some(true),
+                ..PublishDiagnosticsClientCapabilities::default()
+            }),
+            ..TextDocumentClientCapabilities::default()
+        }),
+        ..ClientCapabilities::default()
+    }
+}
+
+fn document_uri(path: &Path) -> Result<Url, LspClientError> {
+    Url::from_file_path(path).map_err(|_| LspClientError::InvalidPath(path.display().to_string()))
+}
+
+fn position_params(
+    path: &Path,
+    position: Position,
+) -> Result<TextDocumentPositionParams, LspClientError> {
+    Ok(TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(document_uri(path)?),
+        position,
+    ))
+}
+
+/// LSP language identifier of a file, refining the server's language by extension
+fn language_id(path: &Path, language: &str) -> String {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match extension {
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "tsx" => "typescriptreact",
+        _ => language,
+    }
+    .to_string()
+}
+
+/// Plain text of hover contents; code blocks keep their fences
+pub fn hover_text(contents: &HoverContents) -> String {
+    let marked = |marked: &MarkedString| match marked {
+        MarkedString::String(text) => text.clone(),
+        MarkedString::LanguageString(code) => {
+            format!("```{}\n{}\n```", code.language, code.value)
+        }
+    };
+    match contents {
+        HoverContents::Scalar(value) => marked(value),
+        HoverContents::Array(values) => values.iter().map(marked).collect::<Vec<_>>().join("\n\n"),
+        HoverContents::Markup(markup) => markup.value.clone(),
+    }
+}
+
+/// First code block of hover text, which servers use for the type or signature
+pub fn hover_signature(hover: &str) -> Option<String> {
+    let mut lines = hover
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("```"));
+    lines.next()?;
+    let signature: Vec<&str> = lines
+        .take_while(|line| !line.trim_start().starts_with("```"))
+        .collect();
+    let signature = signature.join("\n");
+    (!signature.trim().is_empty()).then(|| signature.trim().to_string())
+}
+
+/// Request or notification message; `()` parameters are left out as the protocol requires
+fn rpc_message(
+    id: Option<i64>,
+    method: &str,
+    params: impl serde::Serialize,
+) -> Result<Value, LspClientError> {
+    let params = serde_json::to_value(params)
+        .map_err(|e| LspClientError::Protocol(format!("Failed to encode {}: {}", method, e)))?;
+    let mut message = json!({ "jsonrpc": "2.0", "method": method });
+    if let Some(id) = id {
+        message["id"] = json!(id);
+    }
+    if !params.is_null() {
+        message["params"] = params;
+    }
+    Ok(message)
+}
+
+async fn write_message(writer: &SharedWriter, message: &Value) -> Result<(), LspClientError> {
+    let body = serde_json::to_string(message)
+        .map_err(|e| LspClientError::Protocol(format!("Failed to encode message: {}", e)))?;
+    let mut writer = writer.lock().await;
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes())
+        .await?;
+    writer.write_all(body.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read one framed message; `None` at end of stream
+async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+) -> Result<Option<Value>, LspClientError> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length
+        .ok_or_else(|| LspClientError::Protocol("Missing Content-Length header".to_string()))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| LspClientError::Protocol(format!("Invalid message: {}", e)))
+}
+
+/// Dispatch incoming messages until the stream closes
+async fn read_messages<R: AsyncRead + Unpin>(
+    mut reader: BufReader<R>,
+    shared: Arc<Shared>,
+    writer: SharedWriter,
+) {
+    loop {
+        let message = match read_message(&mut reader).await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::debug!("Language server stream failed: {}", e);
+                break;
+            }
+        };
+
+        let method = message.get("method").and_then(Value::as_str);
+        match (method, message.get("id")) {
+            // Response to one of our requests
+            (None, Some(id)) => {
+                let Some(id) = id.as_i64() else { continue };
+                let sender = shared
+                    .pending
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(&id);
+                if let Some(sender) = sender {
+                    let _ = sender.send(response_result(&message));
+                }
+            }
+            // Request from the server
+            (Some(method), Some(id)) => {
+                let reply = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": server_request_result(method, message.get("params")),
+                });
+                if let Err(e) = write_message(&writer, &reply).await {
+                    tracing::debug!("Failed to answer {}: {}", method, e);
+                }
+            }
+            (Some(PublishDiagnostics::METHOD), None) => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let Ok(params) = serde_json::from_value::<PublishDiagnosticsParams>(params) else {
+                    continue;
+                };
+                {
+                    let mut diagnostics =
+                        shared.diagnostics.lock().unwrap_or_else(|e| e.into_inner());
+                    let entry = diagnostics.entry(params.uri).or_default();
+                    entry.sequence += 1;
+                    entry.version = params.version;
+                    entry.diagnostics = params.diagnostics;
+                }
+                shared.diagnostics_published.notify_waiters();
+            }
+            // Progress, logs and other notifications are not needed
+            _ => {}
+        }
+    }
+
+    // Fail outstanding requests instead of letting them wait for the timeout
+    for (_, sender) in shared
+        .pending
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain()
+    {
+        let _ = sender.send(Err(LspClientError::Closed));
+    }
+    shared.diagnostics_published.notify_waiters();
+}
+
+fn response_result(message: &Value) -> Result<Value, LspClientError> {
+    match message.get("error") {
+        Some(error) => Err(LspClientError::Server {
+            code: error
+                .get("code")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            message: error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        }),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    }
+}
+
+/// Answer for a request the server sends to the client
+fn server_request_result(method: &str, params: Option<&Value>) -> Value {
+    match method {
+        // One (empty) settings value per requested section
+        "workspace/configuration" => {
+            let items = params
+                .and_then(|params| params.get("items"))
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            Value::Array(vec![Value::Null; items])
+        }
+        "workspace/applyEdit" => json!({ "applied": false }),
+        // Progress tokens, capability registration and the like need no answer
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tower_lsp::jsonrpc::Result as RpcResult;
+    use tower_lsp::lsp_types::{
+        DiagnosticSeverity, Hover, HoverProviderCapability, InitializeResult, MarkupContent, OneOf,
+        Range, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind,
+    };
+    use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+    /// Reports an error on every line containing `bad`
+    struct FakeServer {
+        client: Client,
+    }
+
+    impl FakeServer {
+        async fn check(&self, uri: Url, text: &str, version: i32) {
+            let diagnostics = text
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.contains("bad"))
+                .map(|(line, _)| Diagnostic {
+                    range: Range::new(Position::new(line as u32, 0), Position::new(line as u32, 3)),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: "mismatched types".to_string(),
+                    ..Diagnostic::default()
+                })
+                .collect();
+            self.client
+                .publish_diagnostics(uri, diagnostics, Some(version))
+                .await;
+        }
+    }
+
+    #[tower_lsp::async_trait]
+    impl LanguageServer for FakeServer {
+        async fn initialize(&self, _: InitializeParams) -> RpcResult<InitializeResult> {
+            Ok(InitializeResult {
+                capabilities: ServerCapabilities {
+                    text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                        TextDocumentSyncKind::FULL,
+                    )),
+                    hover_provider: Some(HoverProviderCapability::Simple(true)),
+                    definition_provider: Some(OneOf::Left(true)),
+                    ..ServerCapabilities::default()
+                },
+                server_info: Some(ServerInfo {
+                    name: "fake".to_string(),
+                    version: None,
+                }),
+            })
+        }
+
+        async fn shutdown(&self) -> RpcResult<()> {
+            Ok(())
+        }
+
+        async fn did_open(&self, params: DidOpenTextDocumentParams) {
+            let document = params.text_document;
+            self.check(document.uri, &document.text, document.version)
+                .await;
+        }
+
+        async fn did_change(&self, params: DidChangeTextDocumentParams) {
+            let text = &params.content_changes[0].text;
+            self.check(params.text_document.uri, text, params.text_document.version)
+                .await;
+        }
+
+        async fn hover(&self, _: HoverParams) -> RpcResult<Option<Hover>> {
+            Ok(Some(Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: "```rust\nfn double(x: u32) -> u32\n```\n---\nDoubles a value"
+                        .to_string(),
+                }),
+                range: None,
+            }))
+        }
+
+        async fn goto_definition(
+            &self,
+            params: GotoDefinitionParams,
+        ) -> RpcResult<Option<GotoDefinitionResponse>> {
+            let uri = params.text_document_position_params.text_document.uri;
+            Ok(Some(GotoDefinitionResponse::Scalar(Location::new(
+                uri,
+                Range::new(Position::new(0, 3), Position::new(0, 9)),
+            ))))
+        }
+    }
+
+    /// Client connected in memory to a server that flags lines containing `bad`
+    pub(crate) async fn fake_server_client(root: &Path) -> LanguageServerClient {
+        let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        let (service, socket) = LspService::new(|client| FakeServer { client });
+        tokio::spawn(Server::new(server_read, server_write, socket).serve(service));
+
+        let (client_read, client_write) = tokio::io::split(client_io);
+        LanguageServerClient::connect("rust", client_read, client_write, root, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_syncs_documents_and_queries_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = fake_server_client(dir.path()).await;
+        assert_eq!(client.server_name(), Some("fake"));
+
+        let path = dir.path().join("lib.rs");
+        let timeout = Duration::from_secs(5);
+        let diagnostics = client
+            .diagnostics(&path, "fn double() {}\nlet bad = 1;\n", timeout)
+            .await
+            .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+
+        // A changed document is re-checked under a new version
+        let diagnostics = client
+            .diagnostics(&path, "fn double() {}\n", timeout)
+            .await
+            .unwrap();
+        assert!(diagnostics.is_empty());
+
+        let hover = client
+            .hover(&path, "fn double() {}\n", Position::new(0, 4))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            hover_signature(&hover).as_deref(),
+            Some("fn double(x: u32) -> u32")
+        );
+
+        let definitions = client
+            .definition(&path, "fn double() {}\n", Position::new(0, 4))
+            .await
+            .unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].range.start, Position::new(0, 3));
+
+        client.shutdown().await.unwrap();
+    }
+}
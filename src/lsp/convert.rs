@@ -0,0 +1,284 @@
+//! Conversions between devkit types and LSP types
+//!
+//! LSP positions count UTF-16 code units while the context subsystem records
+//! 1-based lines and byte columns, so every position crossing the protocol
+//! boundary goes through these helpers.
+
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticSeverity, Location, NumberOrString, Position, Range, SymbolKind, Url,
+};
+
+use crate::agents::review::{ReviewCategory, ReviewIssue, ReviewSeverity};
+use crate::context::symbols::SymbolType;
+
+/// Source name attached to published diagnostics
+pub const DIAGNOSTIC_SOURCE: &str = "devkit";
+
+/// Text of a 0-based line, without its line ending
+pub fn line_text(text: &str, line: u32) -> Option<&str> {
+    text.lines().nth(line as usize)
+}
+
+/// Byte offset within a line of a UTF-16 character offset
+pub fn byte_column(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (offset, ch) in line.char_indices() {
+        if units >= character as usize {
+            return offset;
+        }
+        units += ch.len_utf16();
+    }
+    line.len()
+}
+
+/// UTF-16 character offset of a byte offset within a line
+pub fn utf16_column(line: &str, byte_column: usize) -> u32 {
+    let end = byte_column.min(line.len());
+    let end = (0..=end)
+        .rev()
+        .find(|&i| line.is_char_boundary(i))
+        .unwrap_or(0);
+    line[..end].chars().map(char::len_utf16).sum::<usize>() as u32
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+/// Identifier under the cursor and its range
+pub fn word_at(text: &str, position: Position) -> Option<(String, Range)> {
+    let line = line_text(text, position.line)?;
+    let cursor = byte_column(line, position.character);
+
+    let start = line[..cursor]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_identifier_char(*ch))
+        .last()
+        .map(|(offset, _)| offset)
+        .unwrap_or(cursor);
+    let end = line[cursor..]
+        .char_indices()
+        .find(|(_, ch)| !is_identifier_char(*ch))
+        .map(|(offset, _)| cursor + offset)
+        .unwrap_or(line.len());
+    if start == end {
+        return None;
+    }
+
+    let range = Range::new(
+        Position::new(position.line, utf16_column(line, start)),
+        Position::new(position.line, utf16_column(line, end)),
+    );
+    Some((line[start..end].to_string(), range))
+}
+
+/// Range of `name` on a 1-based line, falling back to the start of the line
+pub fn name_range(text: &str, line: usize, name: &str) -> Range {
+    let line = line.saturating_sub(1) as u32;
+    let Some(line_text) = line_text(text, line) else {
+        return Range::new(Position::new(line, 0), Position::new(line, 0));
+    };
+
+    let found = line_text.match_indices(name).find(|(offset, _)| {
+        let before = line_text[..*offset].chars().next_back();
+        let after = line_text[offset + name.len()..].chars().next();
+        !before.is_some_and(is_identifier_char) && !after.is_some_and(is_identifier_char)
+    });
+    match found {
+        Some((offset, _)) => Range::new(
+            Position::new(line, utf16_column(line_text, offset)),
+            Position::new(line, utf16_column(line_text, offset + name.len())),
+        ),
+        None => Range::new(Position::new(line, 0), Position::new(line, 0)),
+    }
+}
+
+/// Text covered by a range
+pub fn range_text(text: &str, range: Range) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut selected = Vec::new();
+    for line in range.start.line..=range.end.line {
+        let Some(content) = lines.get(line as usize) else {
+            break;
+        };
+        let start = if line == range.start.line {
+            byte_column(content, range.start.character)
+        } else {
+            0
+        };
+        let end = if line == range.end.line {
+            byte_column(content, range.end.character)
+        } else {
+            content.len()
+        };
+        selected.push(&content[start..end.max(start)]);
+    }
+    selected.join("\n")
+}
+
+/// Location of a 1-based line span in a file
+pub fn location(path: &Path, range: Range) -> Option<Location> {
+    Some(Location::new(Url::from_file_path(path).ok()?, range))
+}
+
+pub fn uri_to_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path().ok()
+}
+
+pub fn symbol_kind(symbol_type: &SymbolType) -> SymbolKind {
+    match symbol_type {
+        SymbolType::Function => SymbolKind::FUNCTION,
+        SymbolType::Method => SymbolKind::METHOD,
+        SymbolType::Struct => SymbolKind::STRUCT,
+        SymbolType::Class => SymbolKind::CLASS,
+        SymbolType::Interface | SymbolType::Trait => SymbolKind::INTERFACE,
+        SymbolType::Enum => SymbolKind::ENUM,
+        SymbolType::Variable => SymbolKind::VARIABLE,
+        SymbolType::Constant => SymbolKind::CONSTANT,
+        SymbolType::Module => SymbolKind::MODULE,
+        SymbolType::Namespace => SymbolKind::NAMESPACE,
+        SymbolType::Type => SymbolKind::TYPE_PARAMETER,
+        SymbolType::Unknown => SymbolKind::VARIABLE,
+    }
+}
+
+pub fn diagnostic_severity(severity: &ReviewSeverity) -> DiagnosticSeverity {
+    match severity {
+        ReviewSeverity::Critical | ReviewSeverity::High => DiagnosticSeverity::ERROR,
+        ReviewSeverity::Medium => DiagnosticSeverity::WARNING,
+        ReviewSeverity::Low => DiagnosticSeverity::INFORMATION,
+        ReviewSeverity::Info => DiagnosticSeverity::HINT,
+    }
+}
+
+/// Diagnostic for a review finding; issues without a line point at the top of the file
+pub fn review_diagnostic(issue: &ReviewIssue) -> Diagnostic {
+    let start = issue.line_start.unwrap_or(1).saturating_sub(1) as u32;
+    let end = issue
+        .line_end
+        .unwrap_or(issue.line_start.unwrap_or(1))
+        .max(start as usize + 1) as u32;
+
+    let mut message = issue.title.clone();
+    if !issue.description.is_empty() && issue.description != issue.title {
+        message.push_str(": ");
+        message.push_str(&issue.description);
+    }
+    if let Some(suggestion) = &issue.suggestion {
+        message.push_str(&format!("\nSuggestion: {}", suggestion));
+    }
+
+    Diagnostic {
+        range: Range::new(
+            Position::new(start, 0),
+            Position::new(end.saturating_sub(1), u32::MAX),
+        ),
+        severity: Some(diagnostic_severity(&issue.severity)),
+        code: Some(NumberOrString::String(format!("{:?}", issue.category))),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+/// Review finding for a diagnostic published by a language server
+pub fn review_issue(path: &Path, diagnostic: &Diagnostic) -> ReviewIssue {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => ReviewSeverity::High,
+        Some(DiagnosticSeverity::WARNING) => ReviewSeverity::Medium,
+        Some(DiagnosticSeverity::INFORMATION) => ReviewSeverity::Low,
+        _ => ReviewSeverity::Info,
+    };
+    let category = if severity == ReviewSeverity::High {
+        ReviewCategory::Bug
+    } else {
+        ReviewCategory::CodeSmell
+    };
+    let title = diagnostic
+        .message
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let source = diagnostic.source.as_deref().unwrap_or("language server");
+    let description = match &diagnostic.code {
+        Some(NumberOrString::String(code)) => {
+            format!("{} [{}]: {}", source, code, diagnostic.message)
+        }
+        Some(NumberOrString::Number(code)) => {
+            format!("{} [{}]: {}", source, code, diagnostic.message)
+        }
+        None => format!("{}: {}", source, diagnostic.message),
+    };
+
+    ReviewIssue {
+        category,
+        severity,
+        title,
+        description,
+        file_path: path.to_path_buf(),
+        line_start: Some(diagnostic.range.start.line as usize + 1),
+        line_end: Some(diagnostic.range.end.line as usize + 1),
+        suggestion: None,
+        auto_fixable: false,
+        code_snippet: None,
+    }
+}
+
+/// One line per diagnostic: `path:line:column: severity: message`
+pub fn format_diagnostic(path: &Path, diagnostic: &Diagnostic) -> String {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        _ => "hint",
+    };
+    format!(
+        "{}:{}:{}: {}: {}",
+        path.display(),
+        diagnostic.range.start.line + 1,
+        diagnostic.range.start.character + 1,
+        severity,
+        diagnostic.message.lines().next().unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_columns_round_trip() {
+        let line = "let café = \"😀\"; value";
+        let value = line.find("value").unwrap();
+        let character = utf16_column(line, value);
+        // é is one UTF-16 unit but two bytes, the emoji two units and four bytes
+        assert_eq!(character, value as u32 - 1 - 2);
+        assert_eq!(byte_column(line, character), value);
+    }
+
+    #[test]
+    fn test_word_and_name_ranges() {
+        let text = "fn main() {\n    let total = compute_total(items);\n}\n";
+        let (word, range) = word_at(text, Position::new(1, 20)).unwrap();
+        assert_eq!(word, "compute_total");
+        assert_eq!(
+            range,
+            Range::new(Position::new(1, 16), Position::new(1, 29))
+        );
+        assert!(word_at(text, Position::new(1, 2)).is_none());
+
+        // Whole-word match on a 1-based line
+        let text = "let totals = total + 1;\n";
+        assert_eq!(
+            name_range(text, 1, "total"),
+            Range::new(Position::new(0, 13), Position::new(0, 18))
+        );
+
+        let text = "one\ntwo three\nfour\n";
+        let range = Range::new(Position::new(1, 4), Position::new(2, 2));
+        assert_eq!(range_text(text, range), "three\nfo");
+    }
+}
@@ -0,0 +1,21 @@
+//! Language Server Protocol integration
+//!
+//! `devkit lsp` serves the codebase context to any LSP-capable editor:
+//! workspace symbols and navigation come from the `SymbolIndex` and the
+//! cross-reference index, hovers carry semantic and history insights,
+//! diagnostics come from the `CodeReviewAgent` and code actions run AI
+//! refactors that are returned as workspace edits.
+//!
+//! In the other direction, `LanguageServerPool` runs real language servers
+//! such as rust-analyzer, pyright and gopls so that reviews, packed context
+//! and the compile gate get compiler-grade diagnostics and types without a
+//! full build.
+
+pub mod client;
+pub mod convert;
+pub mod pool;
+pub mod server;
+
+pub use client::{LanguageServerClient, LspClientError};
+pub use pool::LanguageServerPool;
+pub use server::{serve_stdio, DevkitLanguageServer};
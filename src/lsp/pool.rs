@@ -0,0 +1,164 @@
+//! Language servers shared by the agents, the context packer and the quality gates
+//!
+//! `LanguageServerPool` starts the configured server for a language the first
+//! time a file of that language is queried and keeps it running. A server that
+//! fails to start is remembered so it is not retried on every query.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tower_lsp::lsp_types::{Diagnostic, Location, Position};
+
+use super::client::LanguageServerClient;
+use crate::config::{LanguageServerConfig, LanguageServersConfig};
+
+/// Lazily started language servers for one workspace root
+#[derive(Debug)]
+pub struct LanguageServerPool {
+    root: PathBuf,
+    config: LanguageServersConfig,
+    /// Running servers by language; `None` marks a server that failed to start
+    clients: Mutex<HashMap<String, Option<Arc<LanguageServerClient>>>>,
+}
+
+impl LanguageServerPool {
+    pub fn new(root: PathBuf, config: LanguageServersConfig) -> Self {
+        Self {
+            root,
+            config,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn diagnostics_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.diagnostics_timeout_ms)
+    }
+
+    /// Configured server for a file, by extension
+    pub fn server_config(&self, path: &Path) -> Option<&LanguageServerConfig> {
+        if !self.config.enabled {
+            return None;
+        }
+        let extension = path.extension()?.to_str()?;
+        self.config
+            .servers
+            .iter()
+            .find(|server| server.extensions.iter().any(|ext| ext == extension))
+    }
+
+    /// Whether a server is configured for the file's language
+    pub fn covers(&self, path: &Path) -> bool {
+        self.server_config(path).is_some()
+    }
+
+    /// Use an already connected client for a language
+    pub async fn insert_client(&self, client: LanguageServerClient) {
+        self.clients
+            .lock()
+            .await
+            .insert(client.language().to_string(), Some(Arc::new(client)));
+    }
+
+    /// Running server for a file, starting it on first use
+    pub async fn client_for(&self, path: &Path) -> Option<Arc<LanguageServerClient>> {
+        let config = self.server_config(path)?;
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&config.language) {
+            return client.clone();
+        }
+
+        let client = match LanguageServerClient::spawn(config, &self.root).await {
+            Ok(client) => {
+                tracing::info!(
+                    "Started {} language server for {}",
+                    config.command,
+                    self.root.display()
+                );
+                Some(Arc::new(client))
+            }
+            Err(e) => {
+                tracing::warn!("{} diagnostics unavailable: {}", config.language, e);
+                None
+            }
+        };
+        clients.insert(config.language.clone(), client.clone());
+        client
+    }
+
+    /// Diagnostics for `path` with `text` as its content; `None` without a server
+    pub async fn diagnostics(&self, path: &Path, text: &str) -> Option<Vec<Diagnostic>> {
+        let client = self.client_for(path).await?;
+        match client
+            .diagnostics(path, text, self.diagnostics_timeout())
+            .await
+        {
+            Ok(diagnostics) => Some(diagnostics),
+            Err(e) => {
+                tracing::debug!("Diagnostics failed for {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Hover text at a position, typically the resolved type or signature
+    pub async fn hover(&self, path: &Path, text: &str, position: Position) -> Option<String> {
+        let client = self.client_for(path).await?;
+        match client.hover(path, text, position).await {
+            Ok(hover) => hover,
+            Err(e) => {
+                tracing::debug!("Hover failed for {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Definitions of the symbol at a position
+    pub async fn definition(&self, path: &Path, text: &str, position: Position) -> Vec<Location> {
+        let Some(client) = self.client_for(path).await else {
+            return Vec::new();
+        };
+        client
+            .definition(path, text, position)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::debug!("Definition lookup failed for {}: {}", path.display(), e);
+                Vec::new()
+            })
+    }
+
+    /// Drop in-memory content for a file so servers read it from disk again
+    pub async fn close_document(&self, path: &Path) {
+        let Some(config) = self.server_config(path) else {
+            return;
+        };
+        let client = self
+            .clients
+            .lock()
+            .await
+            .get(&config.language)
+            .cloned()
+            .flatten();
+        if let Some(client) = client {
+            let _ = client.close_document(path).await;
+        }
+    }
+
+    /// Shut down every running server
+    pub async fn shutdown(&self) {
+        let clients: Vec<_> = self.clients.lock().await.drain().collect();
+        for (language, client) in clients {
+            if let Some(client) = client {
+                if let Err(e) = client.shutdown().await {
+                    tracing::debug!("Failed to shut down {} language server: {}", language, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,878 @@
+//! Stdio language server backed by the `ContextManager`
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::Mutex;
+use tower_lsp::jsonrpc::{Error as RpcError, Result as RpcResult};
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+use super::convert;
+use crate::agents::agent_types::RefactoringAgent;
+use crate::agents::review::{CodeReviewAgent, ReviewConfig};
+use crate::agents::task::AgentTask;
+use crate::agents::Agent;
+use crate::ai::AIManager;
+use crate::context::references::Definition;
+use crate::context::{AnalysisConfig, CodebaseContext, ContextError, ContextManager};
+
+/// Maximum number of results for a workspace symbol query
+const MAX_WORKSPACE_SYMBOLS: usize = 200;
+
+/// AI refactors offered as code actions: (title, instruction)
+const REFACTOR_ACTIONS: &[(&str, &str)] = &[
+    (
+        "Refactor with AI: improve readability",
+        "improve readability and structure",
+    ),
+    (
+        "Refactor with AI: improve error handling",
+        "improve error handling",
+    ),
+    (
+        "Refactor with AI: add documentation",
+        "add documentation comments",
+    ),
+];
+
+/// Analyzed state of the workspace
+struct Workspace {
+    root: PathBuf,
+    manager: ContextManager,
+    context: Option<CodebaseContext>,
+    config: AnalysisConfig,
+}
+
+/// Payload carried by an unresolved AI refactor code action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RefactorData {
+    uri: Url,
+    range: Range,
+    instruction: String,
+}
+
+/// Language server answering from the devkit codebase context
+pub struct DevkitLanguageServer {
+    client: Client,
+    workspace: Arc<Mutex<Workspace>>,
+    /// Text of open documents, which may differ from disk
+    documents: Arc<Mutex<HashMap<Url, String>>>,
+    reviewer: Arc<Mutex<CodeReviewAgent>>,
+    ai_manager: Option<Arc<AIManager>>,
+}
+
+impl DevkitLanguageServer {
+    pub fn new(
+        client: Client,
+        manager: ContextManager,
+        ai_manager: Option<Arc<AIManager>>,
+    ) -> Self {
+        let reviewer = match &ai_manager {
+            Some(ai_manager) => CodeReviewAgent::with_ai_manager(ai_manager.clone()),
+            None => CodeReviewAgent::new(),
+        };
+        Self {
+            client,
+            workspace: Arc::new(Mutex::new(Workspace {
+                root: std::env::current_dir().unwrap_or_default(),
+                manager,
+                context: None,
+                config: AnalysisConfig {
+                    deep_analysis: true,
+                    ..AnalysisConfig::default()
+                },
+            })),
+            documents: Arc::new(Mutex::new(HashMap::new())),
+            reviewer: Arc::new(Mutex::new(reviewer)),
+            ai_manager,
+        }
+    }
+
+    /// Analyze the workspace root and keep the resulting context
+    pub async fn index_workspace(&self) -> Result<usize, ContextError> {
+        let mut workspace = self.workspace.lock().await;
+        let root = workspace.root.clone();
+        let config = workspace.config.clone();
+        let context = workspace.manager.analyze_codebase(root, config).await?;
+        let files = context.files.len();
+        workspace.context = Some(context);
+        Ok(files)
+    }
+
+    /// Current text of a document, from the editor when open, otherwise from disk
+    async fn document_text(&self, uri: &Url) -> Option<String> {
+        if let Some(text) = self.documents.lock().await.get(uri) {
+            return Some(text.clone());
+        }
+        tokio::fs::read_to_string(convert::uri_to_path(uri)?)
+            .await
+            .ok()
+    }
+
+    /// Definitions the identifier at a position refers to, or declares
+    async fn definitions_at(&self, uri: &Url, position: Position) -> Vec<Definition> {
+        let (Some(path), Some(text)) = (convert::uri_to_path(uri), self.document_text(uri).await)
+        else {
+            return Vec::new();
+        };
+        let Some((word, _)) = convert::word_at(&text, position) else {
+            return Vec::new();
+        };
+
+        let workspace = self.workspace.lock().await;
+        let Some(references) = workspace
+            .context
+            .as_ref()
+            .and_then(|c| c.references.as_ref())
+        else {
+            return Vec::new();
+        };
+        let line = position.line as usize + 1;
+
+        // A resolved reference on this line names the definition precisely
+        let targets: Vec<Definition> = references
+            .references_on_line(&path, line)
+            .into_iter()
+            .filter(|reference| reference.name == word)
+            .flat_map(|reference| reference.targets.iter())
+            .filter_map(|id| references.get_definition(id).cloned())
+            .collect();
+        if !targets.is_empty() {
+            return targets;
+        }
+
+        // The cursor may be on the name of a definition itself
+        let declared: Vec<Definition> = references
+            .file_definitions(&path)
+            .into_iter()
+            .filter(|definition| definition.name == word && definition.line == line)
+            .cloned()
+            .collect();
+        if !declared.is_empty() {
+            return declared;
+        }
+
+        references
+            .find_definitions(&word)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    async fn definition_location(&self, definition: &Definition) -> Option<Location> {
+        let uri = Url::from_file_path(&definition.file_path).ok()?;
+        let text = self.document_text(&uri).await.unwrap_or_default();
+        let range = convert::name_range(&text, definition.line, &definition.name);
+        Some(Location::new(uri, range))
+    }
+
+    /// Markdown hover for a definition with reference counts and insights
+    async fn hover_markdown(&self, definition: &Definition) -> String {
+        let workspace = self.workspace.lock().await;
+        let context = workspace.context.as_ref();
+        let mut sections = Vec::new();
+
+        let symbol = context.and_then(|context| {
+            context
+                .symbols
+                .find_symbols(&definition.name)
+                .into_iter()
+                .find(|s| s.file_path == definition.file_path && s.line == definition.line)
+                .cloned()
+        });
+        let signature = symbol
+            .as_ref()
+            .and_then(|s| s.signature.clone())
+            .unwrap_or_else(|| format!("{} {}", definition.symbol_type, definition.qualified_name));
+        sections.push(format!(
+            "```{}\n{}\n```",
+            definition.language,
+            signature.trim()
+        ));
+        if let Some(documentation) = symbol.as_ref().and_then(|s| s.documentation.as_ref()) {
+            sections.push(documentation.clone());
+        }
+
+        let relative = definition
+            .file_path
+            .strip_prefix(&workspace.root)
+            .unwrap_or(&definition.file_path)
+            .to_path_buf();
+        let mut facts = vec![format!("`{}`", definition.qualified_name)];
+        if let Some(references) = context.and_then(|c| c.references.as_ref()) {
+            let uses = references.references_to(&definition.id).len();
+            let callers = references.callers(&definition.id).len();
+            facts.push(format!(
+                "{} reference{}, {} caller{}",
+                uses,
+                if uses == 1 { "" } else { "s" },
+                callers,
+                if callers == 1 { "" } else { "s" }
+            ));
+        }
+        sections.push(facts.join(" · "));
+
+        let mut insights = Vec::new();
+        if let Some(semantic) = context.and_then(|c| c.semantic_analysis.as_ref()) {
+            for inconsistency in &semantic.naming_insights.inconsistencies {
+                if inconsistency.symbol_name == definition.name
+                    && (inconsistency.file_path == definition.file_path
+                        || inconsistency.file_path == relative)
+                {
+                    insights.push(format!(
+                        "Naming: expected {} ({})",
+                        inconsistency.expected_convention, inconsistency.suggestion
+                    ));
+                }
+            }
+            let module = relative.to_string_lossy();
+            if semantic
+                .architectural_insights
+                .coupling_analysis
+                .hotspots
+                .iter()
+                .any(|hotspot| module.contains(hotspot.as_str()))
+            {
+                insights.push("Coupling hotspot: many modules depend on this file".to_string());
+            }
+            for suggestion in &semantic.context_suggestions {
+                if suggestion
+                    .applicable_files
+                    .iter()
+                    .any(|file| file == &definition.file_path || file == &relative)
+                {
+                    insights.push(suggestion.description.clone());
+                }
+            }
+        }
+        let history = context
+            .and_then(|c| c.files.iter().find(|f| f.path == definition.file_path))
+            .and_then(|file| file.history.as_ref());
+        if let Some(history) = history {
+            insights.push(format!(
+                "Changed in {} commits, last {} days ago (hotspot score {:.2})",
+                history.commits, history.age_days, history.hotspot_score
+            ));
+            if let Some(co_change) = history.co_changes.first() {
+                insights.push(format!(
+                    "Usually changes with {} ({:.0}% of commits)",
+                    co_change.path.display(),
+                    co_change.confidence * 100.0
+                ));
+            }
+        }
+        if !insights.is_empty() {
+            sections.push(
+                insights
+                    .iter()
+                    .map(|insight| format!("- {}", insight))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+
+        sections.join("\n\n---\n\n")
+    }
+
+    /// Review a saved or opened file and publish its diagnostics
+    async fn publish_review(&self, uri: Url) {
+        let Some(path) = convert::uri_to_path(&uri) else {
+            return;
+        };
+        let result = self
+            .reviewer
+            .lock()
+            .await
+            .review_code(std::slice::from_ref(&path), ReviewConfig::default())
+            .await;
+
+        match result {
+            Ok(result) => {
+                let diagnostics = result
+                    .issues
+                    .iter()
+                    .filter(|issue| issue.file_path == path)
+                    .map(convert::review_diagnostic)
+                    .collect();
+                self.client
+                    .publish_diagnostics(uri, diagnostics, None)
+                    .await;
+            }
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::WARNING, format!("Review failed: {}", e))
+                    .await;
+            }
+        }
+    }
+
+    /// Run the refactoring agent on a range and turn its output into an edit
+    async fn refactor_edit(&self, data: &RefactorData) -> Result<WorkspaceEdit, String> {
+        let ai_manager = self
+            .ai_manager
+            .clone()
+            .ok_or_else(|| "No AI provider is configured".to_string())?;
+        let text = self
+            .document_text(&data.uri)
+            .await
+            .ok_or_else(|| format!("Cannot read {}", data.uri))?;
+        let code = convert::range_text(&text, data.range);
+        if code.trim().is_empty() {
+            return Err("Nothing selected to refactor".to_string());
+        }
+
+        let task = AgentTask::new(
+            "refactor_code".to_string(),
+            data.instruction.clone(),
+            json!({ "existing_code": code }),
+        );
+        let mut agent = RefactoringAgent::with_ai_manager(ai_manager);
+        let result = agent.process_task(task).await.map_err(|e| e.to_string())?;
+        let refactored = result
+            .artifacts
+            .first()
+            .map(|artifact| artifact.content.trim().to_string())
+            .ok_or_else(|| "The refactoring agent returned no code".to_string())?;
+
+        // Remove markdown code blocks if present
+        let refactored = if refactored.starts_with("```") {
+            let lines: Vec<&str> = refactored.lines().collect();
+            if lines.len() > 2 && lines.last().unwrap().trim() == "```" {
+                lines[1..lines.len() - 1].join("\n")
+            } else {
+                refactored
+            }
+        } else {
+            refactored
+        };
+
+        let mut changes = HashMap::new();
+        changes.insert(
+            data.uri.clone(),
+            vec![TextEdit::new(data.range, refactored)],
+        );
+        Ok(WorkspaceEdit::new(changes))
+    }
+
+    /// Range of the innermost definition enclosing a position
+    async fn enclosing_definition_range(&self, uri: &Url, position: Position) -> Option<Range> {
+        let path = convert::uri_to_path(uri)?;
+        let text = self.document_text(uri).await?;
+        let workspace = self.workspace.lock().await;
+        let references = workspace.context.as_ref()?.references.as_ref()?;
+        let line = position.line as usize + 1;
+
+        let definition = references
+            .file_definitions(&path)
+            .into_iter()
+            .filter(|d| d.line <= line && line <= d.end_line)
+            .min_by_key(|d| d.end_line - d.line)?;
+        let end_line = definition.end_line.saturating_sub(1) as u32;
+        let end_character = convert::line_text(&text, end_line)
+            .map(|line| convert::utf16_column(line, line.len()))
+            .unwrap_or(0);
+        Some(Range::new(
+            Position::new(definition.line.saturating_sub(1) as u32, 0),
+            Position::new(end_line, end_character),
+        ))
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for DevkitLanguageServer {
+    async fn initialize(&self, params: InitializeParams) -> RpcResult<InitializeResult> {
+        #[allow(deprecated)]
+        let root = params
+            .workspace_folders
+            .as_ref()
+            .and_then(|folders| folders.first())
+            .map(|folder| folder.uri.clone())
+            .or(params.root_uri)
+            .and_then(|uri| convert::uri_to_path(&uri));
+        if let Some(root) = root {
+            self.workspace.lock().await.root = root;
+        }
+
+        let code_action_provider = self.ai_manager.is_some().then(|| {
+            CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![CodeActionKind::REFACTOR_REWRITE]),
+                resolve_provider: Some(true),
+                ..CodeActionOptions::default()
+            })
+        });
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..TextDocumentSyncOptions::default()
+                    },
+                )),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                code_action_provider,
+                ..ServerCapabilities::default()
+            },
+            server_info: Some(ServerInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        let client = self.client.clone();
+        let server = self.clone_handles();
+        tokio::spawn(async move {
+            match server.index_workspace().await {
+                Ok(files) => {
+                    client
+                        .log_message(MessageType::INFO, format!("devkit indexed {} files", files))
+                        .await
+                }
+                Err(e) => {
+                    client
+                        .show_message(MessageType::ERROR, format!("devkit indexing failed: {}", e))
+                        .await
+                }
+            }
+        });
+    }
+
+    async fn shutdown(&self) -> RpcResult<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents
+            .lock()
+            .await
+            .insert(uri.clone(), params.text_document.text);
+        self.publish_review(uri).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // Full sync: the last change carries the whole document
+        let Some(change) = params.content_changes.into_iter().last() else {
+            return;
+        };
+        let uri = params.text_document.uri;
+        self.documents
+            .lock()
+            .await
+            .insert(uri.clone(), change.text.clone());
+
+        // Keep navigation in step with unsaved edits
+        let Some(path) = convert::uri_to_path(&uri) else {
+            return;
+        };
+        let mut workspace = self.workspace.lock().await;
+        let Some(context) = workspace.context.as_mut() else {
+            return;
+        };
+        let language = context
+            .files
+            .iter()
+            .find(|f| f.path == path)
+            .map(|f| f.language.clone());
+        if let (Some(language), Some(references)) = (language, context.references.as_mut()) {
+            references.update_file(&path, &language, &change.text);
+            references.resolve();
+        }
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let uri = params.text_document.uri;
+        if let Some(path) = convert::uri_to_path(&uri) {
+            let mut workspace = self.workspace.lock().await;
+            let Workspace {
+                manager,
+                context,
+                config,
+                ..
+            } = &mut *workspace;
+            if let Some(context) = context.as_mut() {
+                if let Err(e) = manager.update_context(&[path], context, config).await {
+                    tracing::warn!("Failed to update context after save: {}", e);
+                }
+            }
+        }
+        self.publish_review(uri).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.lock().await.remove(&uri);
+        self.client.publish_diagnostics(uri, Vec::new(), None).await;
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> RpcResult<Option<Vec<SymbolInformation>>> {
+        let workspace = self.workspace.lock().await;
+        let Some(context) = workspace.context.as_ref() else {
+            return Ok(None);
+        };
+
+        let symbols = context
+            .symbols
+            .search(&params.query, None)
+            .into_iter()
+            .take(MAX_WORKSPACE_SYMBOLS)
+            .filter_map(|symbol| {
+                let range = Range::new(
+                    Position::new(symbol.line.saturating_sub(1) as u32, 0),
+                    Position::new(symbol.line.saturating_sub(1) as u32, 0),
+                );
+                #[allow(deprecated)]
+                Some(SymbolInformation {
+                    name: symbol.name.clone(),
+                    kind: convert::symbol_kind(&symbol.symbol_type),
+                    tags: None,
+                    deprecated: None,
+                    location: convert::location(&symbol.file_path, range)?,
+                    container_name: symbol.qualified_name.as_ref().and_then(|q| {
+                        q.rsplit_once("::")
+                            .map(|(container, _)| container.to_string())
+                    }),
+                })
+            })
+            .collect();
+        Ok(Some(symbols))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> RpcResult<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        let definitions = self
+            .definitions_at(&position.text_document.uri, position.position)
+            .await;
+
+        let mut locations = Vec::new();
+        for definition in &definitions {
+            if let Some(location) = self.definition_location(definition).await {
+                locations.push(location);
+            }
+        }
+        Ok((!locations.is_empty()).then_some(GotoDefinitionResponse::Array(locations)))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> RpcResult<Option<Vec<Location>>> {
+        let position = params.text_document_position;
+        let definitions = self
+            .definitions_at(&position.text_document.uri, position.position)
+            .await;
+
+        let mut locations = Vec::new();
+        if params.context.include_declaration {
+            for definition in &definitions {
+                if let Some(location) = self.definition_location(definition).await {
+                    locations.push(location);
+                }
+            }
+        }
+
+        let sites: Vec<(PathBuf, usize, String)> = {
+            let workspace = self.workspace.lock().await;
+            let Some(references) = workspace
+                .context
+                .as_ref()
+                .and_then(|c| c.references.as_ref())
+            else {
+                return Ok(None);
+            };
+            definitions
+                .iter()
+                .flat_map(|definition| references.references_to(&definition.id))
+                .map(|r| {
+                    (
+                        r.reference.file_path.clone(),
+                        r.reference.line_number,
+                        r.name.clone(),
+                    )
+                })
+                .collect()
+        };
+        for (path, line, name) in sites {
+            let Ok(uri) = Url::from_file_path(&path) else {
+                continue;
+            };
+            let text = self.document_text(&uri).await.unwrap_or_default();
+            let location = Location::new(uri, convert::name_range(&text, line, &name));
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+        Ok(Some(locations))
+    }
+
+    async fn hover(&self, params: HoverParams) -> RpcResult<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let definitions = self
+            .definitions_at(&position.text_document.uri, position.position)
+            .await;
+        let Some(definition) = definitions.first() else {
+            return Ok(None);
+        };
+
+        let mut markdown = self.hover_markdown(definition).await;
+        if definitions.len() > 1 {
+            markdown.push_str(&format!(
+                "\n\n_{} candidate definitions_",
+                definitions.len()
+            ));
+        }
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: markdown,
+            }),
+            range: None,
+        }))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> RpcResult<Option<CodeActionResponse>> {
+        if self.ai_manager.is_none() {
+            return Ok(None);
+        }
+
+        // Without a selection, refactor the definition around the cursor
+        let range = if params.range.start == params.range.end {
+            match self
+                .enclosing_definition_range(&params.text_document.uri, params.range.start)
+                .await
+            {
+                Some(range) => range,
+                None => return Ok(None),
+            }
+        } else {
+            params.range
+        };
+
+        let actions = REFACTOR_ACTIONS
+            .iter()
+            .map(|(title, instruction)| {
+                let data = RefactorData {
+                    uri: params.text_document.uri.clone(),
+                    range,
+                    instruction: instruction.to_string(),
+                };
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: title.to_string(),
+                    kind: Some(CodeActionKind::REFACTOR_REWRITE),
+                    data: serde_json::to_value(data).ok(),
+                    ..CodeAction::default()
+                })
+            })
+            .collect();
+        Ok(Some(actions))
+    }
+
+    async fn code_action_resolve(&self, mut action: CodeAction) -> RpcResult<CodeAction> {
+        let data: RefactorData = action
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value(data).ok())
+            .ok_or_else(|| RpcError::invalid_params("Code action is missing its refactor data"))?;
+
+        match self.refactor_edit(&data).await {
+            Ok(edit) => action.edit = Some(edit),
+            Err(message) => {
+                self.client
+                    .show_message(
+                        MessageType::ERROR,
+                        format!("AI refactor failed: {}", message),
+                    )
+                    .await;
+            }
+        }
+        Ok(action)
+    }
+}
+
+impl DevkitLanguageServer {
+    /// A second handle sharing this server's state, for background tasks
+    fn clone_handles(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            workspace: self.workspace.clone(),
+            documents: self.documents.clone(),
+            reviewer: self.reviewer.clone(),
+            ai_manager: self.ai_manager.clone(),
+        }
+    }
+}
+
+/// Serve the language server over the given streams until the client exits
+pub async fn serve<I, O>(
+    input: I,
+    output: O,
+    manager: ContextManager,
+    ai_manager: Option<Arc<AIManager>>,
+) where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let (service, socket) =
+        LspService::new(move |client| DevkitLanguageServer::new(client, manager, ai_manager));
+    Server::new(input, output, socket).serve(service).await;
+}
+
+/// Serve over stdin and stdout.
+///
+/// The protocol owns stdout, so on Unix the process's stdout is redirected to
+/// stderr first; progress output from the analysis then lands in the editor's
+/// log instead of corrupting the message stream.
+pub async fn serve_stdio(manager: ContextManager, ai_manager: Option<Arc<AIManager>>) {
+    let stdin = tokio::io::stdin();
+
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        let _ = std::io::stdout().flush();
+        // SAFETY: duplicating and replacing the process's own standard descriptors
+        let protocol_fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if protocol_fd >= 0 && unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } >= 0
+        {
+            // SAFETY: `protocol_fd` is a fresh descriptor owned by nothing else
+            let protocol = unsafe { std::fs::File::from_raw_fd(protocol_fd) };
+            serve(
+                stdin,
+                tokio::fs::File::from_std(protocol),
+                manager,
+                ai_manager,
+            )
+            .await;
+            return;
+        }
+    }
+
+    serve(stdin, tokio::io::stdout(), manager, ai_manager).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_navigation_from_reference_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::write(
+            root.join("util.rs"),
+            "/// Doubles a value\npub fn double(x: u32) -> u32 {\n    x * 2\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("main.rs"),
+            "fn main() {\n    let value = double(21);\n    let doubled = double(value);\n}\n",
+        )
+        .unwrap();
+
+        let (service, _socket) = LspService::new(|client| {
+            DevkitLanguageServer::new(client, ContextManager::new().unwrap(), None)
+        });
+        let server = service.inner();
+        let root_uri = Url::from_file_path(&root).unwrap();
+        #[allow(deprecated)]
+        let initialized = server
+            .initialize(InitializeParams {
+                root_uri: Some(root_uri),
+                ..InitializeParams::default()
+            })
+            .await
+            .unwrap();
+        assert!(initialized.capabilities.code_action_provider.is_none());
+        {
+            let mut workspace = server.workspace.lock().await;
+            workspace.config.persist_index = false;
+            workspace.config.deep_analysis = false;
+        }
+        server.index_workspace().await.unwrap();
+
+        let main_uri = Url::from_file_path(root.join("main.rs")).unwrap();
+        let util_uri = Url::from_file_path(root.join("util.rs")).unwrap();
+        let at_call = TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(main_uri.clone()),
+            Position::new(1, 18),
+        );
+
+        let definition = server
+            .goto_definition(GotoDefinitionParams {
+                text_document_position_params: at_call.clone(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+            .await
+            .unwrap();
+        let Some(GotoDefinitionResponse::Array(locations)) = definition else {
+            panic!("expected definition locations");
+        };
+        assert_eq!(
+            locations,
+            vec![Location::new(
+                util_uri.clone(),
+                Range::new(Position::new(1, 7), Position::new(1, 13))
+            )]
+        );
+
+        // References from the definition's own name
+        let references = server
+            .references(ReferenceParams {
+                text_document_position: TextDocumentPositionParams::new(
+                    TextDocumentIdentifier::new(util_uri),
+                    Position::new(1, 9),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+                context: ReferenceContext {
+                    include_declaration: false,
+                },
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(references.len(), 2);
+        assert!(references.iter().all(|location| location.uri == main_uri));
+
+        let hover = server
+            .hover(HoverParams {
+                text_document_position_params: at_call,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let HoverContents::Markup(markup) = hover.contents else {
+            panic!("expected markdown hover");
+        };
+        assert!(markup.value.contains("2 references"));
+
+        let symbols = server
+            .symbol(WorkspaceSymbolParams {
+                query: "double".to_string(),
+                ..WorkspaceSymbolParams::default()
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(symbols
+            .iter()
+            .any(|symbol| symbol.name == "double" && symbol.kind == SymbolKind::FUNCTION));
+    }
+}
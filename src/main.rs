@@ -13,8 +13,11 @@ mod config;
 mod context;
 mod interactive;
 mod logging;
+mod lsp;
 mod plugins;
+mod sandbox;
 mod shell;
+mod system_bus;
 mod ui;
 mod web;
 
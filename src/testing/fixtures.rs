@@ -141,6 +141,7 @@ impl ContextFixtures {
             imports,
             exports,
             relationships: Vec::new(),
+            history: None,
         }
     }
 
@@ -328,6 +328,7 @@ impl MockCodebaseAnalyzer {
             imports: Vec::new(),
             exports: Vec::new(),
             relationships: Vec::new(),
+            history: None,
         })
     }
 
@@ -349,6 +350,8 @@ impl MockCodebaseAnalyzer {
             repository_info: None,
             semantic_analysis: None,
             embeddings: None,
+            lexical_index: None,
+            references: None,
             metadata: crate::context::ContextMetadata {
                 analysis_timestamp: std::time::SystemTime::now(),
                 total_files: 0,
@@ -578,6 +581,8 @@ mod tests {
             repository_info: None,
             semantic_analysis: None,
             embeddings: None,
+            lexical_index: None,
+            references: None,
             metadata: crate::context::ContextMetadata {
                 analysis_timestamp: std::time::SystemTime::now(),
                 total_files: 0,
@@ -321,12 +321,15 @@ impl MockDataFactory {
                     },
                     openai: None,
                     anthropic: None,
+                    embeddings: crate::config::EmbeddingProviderConfig::default(),
                     context_window_size: 8192,
                     temperature: 0.7,
                     max_tokens: 1000,
                 },
             },
             chat: crate::config::ChatConfig::default(),
+            language_servers: crate::config::LanguageServersConfig::default(),
+            quality_gates: crate::codegen::diff_apply::QualityGateConfig::default(),
             shell: crate::config::ShellConfig {
                 preferred_shell: Some("bash".to_string()),
                 environment_variables: HashMap::new(),
@@ -426,6 +429,7 @@ impl MockDataFactory {
                 ],
                 exports: vec![format!("mock_function_{}", i)],
                 relationships: Vec::new(),
+                history: None,
             })
             .collect()
     }
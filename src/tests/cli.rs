@@ -17,6 +17,7 @@ async fn test_analyze_command_basic() {
         export: None,
         analysis_types: vec!["symbols".to_string(), "dependencies".to_string()],
         progress: false,
+        no_index: false,
     };
     
     // Test basic analysis functionality
@@ -23,6 +23,7 @@ pub mod keybindings;
 pub mod notifications;
 pub mod panels;
 pub mod progress;
+pub mod review;
 pub mod syntax;
 pub mod themes;
 
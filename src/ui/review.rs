@@ -11,13 +11,10 @@ use crossterm::{
 };
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span, Text},
-    widgets::{
-        block::Title, Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
-        ScrollbarOrientation, ScrollbarState, Wrap,
-    },
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
 use serde::{Deserialize, Serialize};
@@ -25,7 +22,8 @@ use std::collections::HashMap;
 use std::io;
 use tokio::sync::RwLock;
 
-use crate::codegen::diff_apply::{ChangeSet, FileDiff, ChangeType};
+use crate::codegen::diff_apply::{ChangeSet, ChangeType, DiffApplyError, FileDiff, HunkState};
+use crate::codegen::edit_formats::HunkLine;
 
 /// Multi-file review system
 #[derive(Debug)]
@@ -48,11 +46,12 @@ pub struct ReviewConfig {
     /// Whether to word wrap long lines
     pub word_wrap: bool,
     /// Color scheme for diffs
+    #[serde(skip)]
     pub color_scheme: ColorScheme,
 }
 
 /// Color scheme for diff display
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct ColorScheme {
     pub added_line: Color,
     pub removed_line: Color,
@@ -72,7 +71,6 @@ struct ReviewState {
     scroll_offset: usize,
     view_mode: ViewMode,
     decisions: HashMap<String, FileDecision>,
-    hunk_decisions: HashMap<(String, usize), HunkDecision>,
     show_help: bool,
     filter: ReviewFilter,
 }
@@ -88,6 +86,37 @@ struct ReviewFile {
     pub decision: FileDecision,
 }
 
+impl ReviewFile {
+    /// Set the file decision and every hunk along with it
+    fn set_decision(&mut self, decision: FileDecision) {
+        let hunk_state = match decision {
+            FileDecision::Accept => HunkState::Accepted,
+            FileDecision::Reject => HunkState::Rejected,
+            FileDecision::Pending => HunkState::Pending,
+            FileDecision::Partial => return,
+        };
+        for hunk in &mut self.hunks {
+            hunk.decision = hunk_state.clone();
+        }
+        self.decision = decision;
+    }
+    
+    /// Derive the file decision from its hunks
+    fn update_decision(&mut self) {
+        let applied = self.hunks.iter().filter(|hunk| hunk.decision.is_applied()).count();
+        let rejected = self.hunks.iter().filter(|hunk| hunk.decision == HunkState::Rejected).count();
+        self.decision = if applied == self.hunks.len() {
+            FileDecision::Accept
+        } else if rejected == self.hunks.len() {
+            FileDecision::Reject
+        } else if applied == 0 && rejected == 0 {
+            FileDecision::Pending
+        } else {
+            FileDecision::Partial
+        };
+    }
+}
+
 /// A hunk of changes within a file
 #[derive(Debug, Clone)]
 struct DiffHunk {
@@ -96,7 +125,27 @@ struct DiffHunk {
     pub start_line: usize,
     pub added_lines: usize,
     pub removed_lines: usize,
-    pub decision: HunkDecision,
+    pub decision: HunkState,
+}
+
+impl DiffHunk {
+    /// New side of the hunk, context included, as offered for editing
+    fn new_text(&self) -> String {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line.line_type, LineType::Context | LineType::Added))
+            .map(|line| format!("{}\n", line.content))
+            .collect()
+    }
+    
+    /// Accept the hunk with its new side replaced by `text`
+    fn set_edit(&mut self, text: String) {
+        self.decision = if text == self.new_text() {
+            HunkState::Accepted
+        } else {
+            HunkState::Edited(text)
+        };
+    }
 }
 
 /// A line within a diff hunk
@@ -139,14 +188,6 @@ pub enum FileDecision {
     Partial, // Some hunks accepted, some rejected
 }
 
-/// Decision for a hunk
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum HunkDecision {
-    Pending,
-    Accept,
-    Reject,
-}
-
 /// Filter for review display
 #[derive(Debug, Clone)]
 struct ReviewFilter {
@@ -160,12 +201,26 @@ struct ReviewFilter {
 pub struct ReviewResult {
     pub overall_decision: OverallDecision,
     pub file_decisions: HashMap<String, FileDecision>,
-    pub hunk_decisions: HashMap<String, Vec<HunkDecision>>,
+    pub hunk_decisions: HashMap<String, Vec<HunkState>>,
     pub applied_files: Vec<String>,
     pub rejected_files: Vec<String>,
     pub partial_files: Vec<String>,
 }
 
+impl ReviewResult {
+    /// Record the hunk decisions on the reviewed changeset, ready for
+    /// `DiffApplySystem::apply_partial`
+    pub fn apply_to(&self, changeset: &mut ChangeSet) -> Result<(), DiffApplyError> {
+        changeset.split_hunks();
+        for (path, states) in &self.hunk_decisions {
+            for (index, state) in states.iter().enumerate() {
+                changeset.set_hunk_state(std::path::Path::new(path), index, state.clone())?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Overall review decision
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OverallDecision {
@@ -203,7 +258,6 @@ impl ReviewSystem {
                 scroll_offset: 0,
                 view_mode: ViewMode::FileList,
                 decisions: HashMap::new(),
-                hunk_decisions: HashMap::new(),
                 show_help: false,
                 filter: ReviewFilter {
                     show_only_modified: false,
@@ -216,7 +270,9 @@ impl ReviewSystem {
     }
     
     /// Start interactive review of a changeset
-    pub async fn review_changeset(&self, changeset: ChangeSet) -> Result<ReviewResult, ReviewError> {
+    pub async fn review_changeset(&self, mut changeset: ChangeSet) -> Result<ReviewResult, ReviewError> {
+        changeset.split_hunks();
+        
         // Parse changeset into review format
         let review_files = self.parse_changeset(&changeset)?;
         
@@ -228,7 +284,6 @@ impl ReviewSystem {
             state.current_file_index = 0;
             state.current_hunk_index = 0;
             state.decisions.clear();
-            state.hunk_decisions.clear();
         }
         
         // Start interactive TUI
@@ -257,122 +312,65 @@ impl ReviewSystem {
         Ok(review_files)
     }
     
-    /// Parse file diff into hunks
+    /// Convert the backend hunks of a file diff for display
     fn parse_file_diff(&self, file_diff: &FileDiff) -> Result<Vec<DiffHunk>, ReviewError> {
-        // Simple diff parsing - in production this would use a proper diff library
-        let hunks = if let Some(ref original) = file_diff.original_content {
-            self.generate_unified_diff(original, &file_diff.new_content)?
+        let hunks = if file_diff.hunks.is_empty() {
+            file_diff.compute_hunks()
         } else {
-            // New file
-            vec![self.create_new_file_hunk(&file_diff.new_content)]
+            file_diff.hunks.clone()
         };
         
-        Ok(hunks)
-    }
-    
-    /// Generate unified diff hunks
-    fn generate_unified_diff(&self, original: &str, new: &str) -> Result<Vec<DiffHunk>, ReviewError> {
-        // Simplified diff generation - would use proper diff algorithm in production
-        let orig_lines: Vec<&str> = original.lines().collect();
-        let new_lines: Vec<&str> = new.lines().collect();
-        
-        // For now, create a single hunk with all changes
-        let mut diff_lines = Vec::new();
-        let mut line_num_old = 1;
-        let mut line_num_new = 1;
-        
-        // Simple line-by-line comparison
-        let max_lines = orig_lines.len().max(new_lines.len());
-        
-        for i in 0..max_lines {
-            match (orig_lines.get(i), new_lines.get(i)) {
-                (Some(old_line), Some(new_line)) => {
-                    if old_line == new_line {
-                        // Context line
-                        diff_lines.push(DiffLine {
+        let review_hunks = hunks
+            .into_iter()
+            .map(|hunk| {
+                let mut line_num_old = hunk.old_start;
+                let mut line_num_new = hunk.new_start;
+                let lines = hunk.lines.iter().map(|line| match line {
+                    HunkLine::Context(content) => {
+                        let line = DiffLine {
                             line_type: LineType::Context,
-                            content: old_line.to_string(),
+                            content: content.clone(),
                             line_number_old: Some(line_num_old),
                             line_number_new: Some(line_num_new),
-                        });
+                        };
                         line_num_old += 1;
                         line_num_new += 1;
-                    } else {
-                        // Changed line
-                        diff_lines.push(DiffLine {
+                        line
+                    }
+                    HunkLine::Remove(content) => {
+                        let line = DiffLine {
                             line_type: LineType::Removed,
-                            content: old_line.to_string(),
+                            content: content.clone(),
                             line_number_old: Some(line_num_old),
                             line_number_new: None,
-                        });
-                        diff_lines.push(DiffLine {
+                        };
+                        line_num_old += 1;
+                        line
+                    }
+                    HunkLine::Add(content) => {
+                        let line = DiffLine {
                             line_type: LineType::Added,
-                            content: new_line.to_string(),
+                            content: content.clone(),
                             line_number_old: None,
                             line_number_new: Some(line_num_new),
-                        });
-                        line_num_old += 1;
+                        };
                         line_num_new += 1;
+                        line
                     }
+                }).collect();
+                
+                DiffHunk {
+                    header: hunk.header.clone(),
+                    lines,
+                    start_line: hunk.new_start,
+                    added_lines: hunk.added_lines(),
+                    removed_lines: hunk.removed_lines(),
+                    decision: hunk.state,
                 }
-                (Some(old_line), None) => {
-                    // Removed line
-                    diff_lines.push(DiffLine {
-                        line_type: LineType::Removed,
-                        content: old_line.to_string(),
-                        line_number_old: Some(line_num_old),
-                        line_number_new: None,
-                    });
-                    line_num_old += 1;
-                }
-                (None, Some(new_line)) => {
-                    // Added line
-                    diff_lines.push(DiffLine {
-                        line_type: LineType::Added,
-                        content: new_line.to_string(),
-                        line_number_old: None,
-                        line_number_new: Some(line_num_new),
-                    });
-                    line_num_new += 1;
-                }
-                (None, None) => break,
-            }
-        }
-        
-        let added_lines = diff_lines.iter().filter(|l| l.line_type == LineType::Added).count();
-        let removed_lines = diff_lines.iter().filter(|l| l.line_type == LineType::Removed).count();
-        
-        Ok(vec![DiffHunk {
-            header: format!("@@ -1,{} +1,{} @@", orig_lines.len(), new_lines.len()),
-            lines: diff_lines,
-            start_line: 1,
-            added_lines,
-            removed_lines,
-            decision: HunkDecision::Pending,
-        }])
-    }
-    
-    /// Create hunk for new file
-    fn create_new_file_hunk(&self, content: &str) -> DiffHunk {
-        let lines: Vec<DiffLine> = content
-            .lines()
-            .enumerate()
-            .map(|(i, line)| DiffLine {
-                line_type: LineType::Added,
-                content: line.to_string(),
-                line_number_old: None,
-                line_number_new: Some(i + 1),
             })
             .collect();
         
-        DiffHunk {
-            header: format!("@@ -0,0 +1,{} @@", lines.len()),
-            lines,
-            start_line: 1,
-            added_lines: lines.len(),
-            removed_lines: 0,
-            decision: HunkDecision::Pending,
-        }
+        Ok(review_hunks)
     }
     
     /// Run the interactive review interface
@@ -402,20 +400,21 @@ impl ReviewSystem {
     async fn run_review_loop<B: Backend>(&self, terminal: &mut Terminal<B>) -> Result<ReviewResult, ReviewError> {
         loop {
             // Draw the interface
-            terminal.draw(|f| {
-                let rt = tokio::runtime::Handle::current();
-                rt.block_on(async {
-                    if let Err(e) = self.draw_frame(f).await {
+            {
+                let state = self.state.read().await;
+                terminal.draw(|f| {
+                    if let Err(e) = self.draw_frame(f, &state) {
                         eprintln!("Error drawing frame: {}", e);
                     }
-                });
-            })?;
+                })?;
+            }
             
             // Handle input
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
                     match self.handle_key_event(key.code).await {
                         KeyResult::Continue => continue,
+                        KeyResult::EditHunk => self.edit_current_hunk(terminal).await?,
                         KeyResult::Exit(result) => return Ok(result),
                         KeyResult::Error(e) => return Err(e),
                     }
@@ -424,26 +423,57 @@ impl ReviewSystem {
         }
     }
     
-    /// Draw the main frame
-    async fn draw_frame<B: Backend>(&self, f: &mut Frame<B>) -> Result<(), ReviewError> {
-        let state = self.state.read().await;
+    /// Edit the current hunk in the user's editor, suspending the TUI meanwhile
+    async fn edit_current_hunk<B: Backend>(&self, terminal: &mut Terminal<B>) -> Result<(), ReviewError> {
+        let text = {
+            let state = self.state.read().await;
+            let hunk = state
+                .files
+                .get(state.current_file_index)
+                .and_then(|file| file.hunks.get(state.current_hunk_index));
+            match hunk {
+                Some(hunk) => hunk.new_text(),
+                None => return Ok(()),
+            }
+        };
         
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+        let edited = edit_in_editor(&text);
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        terminal.clear()?;
+        
+        let edited = edited?;
+        let mut guard = self.state.write().await;
+        let state = &mut *guard;
+        if let Some(file) = state.files.get_mut(state.current_file_index) {
+            if let Some(hunk) = file.hunks.get_mut(state.current_hunk_index) {
+                hunk.set_edit(edited);
+            }
+            file.update_decision();
+        }
+        Ok(())
+    }
+    
+    /// Draw the main frame
+    fn draw_frame(&self, f: &mut Frame<'_>, state: &ReviewState) -> Result<(), ReviewError> {
         if state.show_help {
-            self.draw_help_popup(f).await;
+            self.draw_help_popup(f);
             return Ok(());
         }
         
         match state.view_mode {
-            ViewMode::FileList => self.draw_file_list(f, &state).await,
-            ViewMode::UnifiedDiff => self.draw_unified_diff(f, &state).await,
-            ViewMode::SideBySide => self.draw_side_by_side(f, &state).await,
-            ViewMode::HunkSelector => self.draw_hunk_selector(f, &state).await,
+            ViewMode::FileList => self.draw_file_list(f, state),
+            ViewMode::UnifiedDiff => self.draw_unified_diff(f, state),
+            ViewMode::SideBySide => self.draw_side_by_side(f, state),
+            ViewMode::HunkSelector => self.draw_hunk_selector(f, state),
         }
     }
     
     /// Draw file list view
-    async fn draw_file_list<B: Backend>(&self, f: &mut Frame<B>, state: &ReviewState) -> Result<(), ReviewError> {
-        let area = f.size();
+    fn draw_file_list(&self, f: &mut Frame<'_>, state: &ReviewState) -> Result<(), ReviewError> {
+        let area = f.area();
         
         // Create layout
         let chunks = Layout::default()
@@ -511,8 +541,8 @@ impl ReviewSystem {
     }
     
     /// Draw unified diff view (stub)
-    async fn draw_unified_diff<B: Backend>(&self, f: &mut Frame<B>, state: &ReviewState) -> Result<(), ReviewError> {
-        let area = f.size();
+    fn draw_unified_diff(&self, f: &mut Frame<'_>, _state: &ReviewState) -> Result<(), ReviewError> {
+        let area = f.area();
         let placeholder = Paragraph::new("Unified diff view - Implementation in progress")
             .block(Block::default().borders(Borders::ALL).title("Unified Diff"));
         f.render_widget(placeholder, area);
@@ -520,26 +550,114 @@ impl ReviewSystem {
     }
     
     /// Draw side-by-side diff view (stub)
-    async fn draw_side_by_side<B: Backend>(&self, f: &mut Frame<B>, state: &ReviewState) -> Result<(), ReviewError> {
-        let area = f.size();
+    fn draw_side_by_side(&self, f: &mut Frame<'_>, _state: &ReviewState) -> Result<(), ReviewError> {
+        let area = f.area();
         let placeholder = Paragraph::new("Side-by-side diff view - Implementation in progress")
             .block(Block::default().borders(Borders::ALL).title("Side-by-Side Diff"));
         f.render_widget(placeholder, area);
         Ok(())
     }
     
-    /// Draw hunk selector view (stub)
-    async fn draw_hunk_selector<B: Backend>(&self, f: &mut Frame<B>, state: &ReviewState) -> Result<(), ReviewError> {
-        let area = f.size();
-        let placeholder = Paragraph::new("Hunk selector view - Implementation in progress")
-            .block(Block::default().borders(Borders::ALL).title("Hunk Selector"));
-        f.render_widget(placeholder, area);
+    /// Draw hunk selector view
+    fn draw_hunk_selector(&self, f: &mut Frame<'_>, state: &ReviewState) -> Result<(), ReviewError> {
+        let area = f.area();
+        let Some(file) = state.files.get(state.current_file_index) else {
+            let empty = Paragraph::new("No files to review")
+                .block(Block::default().borders(Borders::ALL).title("Hunk Selector"));
+            f.render_widget(empty, area);
+            return Ok(());
+        };
+        
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),      // Header
+                Constraint::Percentage(30), // Hunk list
+                Constraint::Min(0),         // Current hunk
+                Constraint::Length(3),      // Footer
+            ])
+            .split(area);
+        
+        let header = Paragraph::new(format!("Hunks in {}", file.path))
+            .block(Block::default().borders(Borders::ALL).title("DevKit Review"))
+            .style(Style::default().fg(self.config.color_scheme.header));
+        f.render_widget(header, chunks[0]);
+        
+        let hunks: Vec<ListItem> = file.hunks
+            .iter()
+            .enumerate()
+            .map(|(i, hunk)| {
+                let decision_indicator = match hunk.decision {
+                    HunkState::Accepted => "✓",
+                    HunkState::Edited(_) => "✎",
+                    HunkState::Rejected => "✗",
+                    HunkState::Pending => "○",
+                };
+                
+                let style = if i == state.current_hunk_index {
+                    Style::default().fg(self.config.color_scheme.selected).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                
+                ListItem::new(format!(
+                    "{} {} (+{} -{})",
+                    decision_indicator,
+                    hunk.header,
+                    hunk.added_lines,
+                    hunk.removed_lines
+                )).style(style)
+            })
+            .collect();
+        
+        let hunk_list = List::new(hunks)
+            .block(Block::default().borders(Borders::ALL).title("Hunks"));
+        f.render_widget(hunk_list, chunks[1]);
+        
+        let lines: Vec<Line> = file.hunks
+            .get(state.current_hunk_index)
+            .map(|hunk| {
+                hunk.lines
+                    .iter()
+                    .map(|line| {
+                        let (prefix, color) = match line.line_type {
+                            LineType::Added => ("+", self.config.color_scheme.added_line),
+                            LineType::Removed => ("-", self.config.color_scheme.removed_line),
+                            LineType::Context => (" ", self.config.color_scheme.context_line),
+                            LineType::Header => ("", self.config.color_scheme.header),
+                        };
+                        let number = line.line_number_new.or(line.line_number_old);
+                        let mut spans = Vec::new();
+                        if self.config.show_line_numbers {
+                            spans.push(Span::styled(
+                                format!("{:>5} ", number.map(|n| n.to_string()).unwrap_or_default()),
+                                Style::default().fg(self.config.color_scheme.line_number),
+                            ));
+                        }
+                        spans.push(Span::styled(format!("{}{}", prefix, line.content), Style::default().fg(color)));
+                        Line::from(spans)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let mut hunk_view = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Current Hunk"));
+        if self.config.word_wrap {
+            hunk_view = hunk_view.wrap(Wrap { trim: false });
+        }
+        f.render_widget(hunk_view, chunks[2]);
+        
+        let footer = Paragraph::new("↑/↓: Navigate hunks | Space: Toggle hunk | a: Accept hunk | r: Reject hunk | e: Edit hunk | Tab: File list | q: Finish review")
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(footer, chunks[3]);
+        
         Ok(())
     }
     
     /// Draw help popup
-    async fn draw_help_popup<B: Backend>(&self, f: &mut Frame<B>) {
-        let area = f.size();
+    fn draw_help_popup(&self, f: &mut Frame<'_>) {
+        let area = f.area();
         let popup_area = self.centered_rect(80, 60, area);
         
         f.render_widget(Clear, popup_area);
@@ -558,6 +676,8 @@ impl ReviewSystem {
             Line::from("  r           - Reject current file"),
             Line::from("  A           - Accept all files"),
             Line::from("  R           - Reject all files"),
+            Line::from("  (in the hunk selector, Space/a/r act on the current hunk)"),
+            Line::from("  e           - Edit the current hunk in $EDITOR"),
             Line::from(""),
             Line::from("Other:"),
             Line::from("  h           - Toggle this help"),
@@ -576,7 +696,8 @@ impl ReviewSystem {
     
     /// Handle key events
     async fn handle_key_event(&self, key: KeyCode) -> KeyResult {
-        let mut state = self.state.write().await;
+        let mut guard = self.state.write().await;
+        let state = &mut *guard;
         
         if state.show_help {
             state.show_help = false;
@@ -585,7 +706,7 @@ impl ReviewSystem {
         
         match key {
             KeyCode::Char('q') => {
-                return KeyResult::Exit(self.generate_review_result(&state));
+                return KeyResult::Exit(self.generate_review_result(state));
             }
             KeyCode::Esc => {
                 return KeyResult::Exit(ReviewResult {
@@ -600,49 +721,83 @@ impl ReviewSystem {
             KeyCode::Char('h') => {
                 state.show_help = true;
             }
+            KeyCode::Char('e') if state.view_mode == ViewMode::HunkSelector => {
+                return KeyResult::EditHunk;
+            }
+            KeyCode::Up | KeyCode::Char('k') if state.view_mode == ViewMode::HunkSelector => {
+                if state.current_hunk_index > 0 {
+                    state.current_hunk_index -= 1;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') if state.view_mode == ViewMode::HunkSelector => {
+                let hunk_count = state.files.get(state.current_file_index).map_or(0, |file| file.hunks.len());
+                if state.current_hunk_index + 1 < hunk_count {
+                    state.current_hunk_index += 1;
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Char('a') | KeyCode::Char('r') if state.view_mode == ViewMode::HunkSelector => {
+                let hunk_index = state.current_hunk_index;
+                if let Some(file) = state.files.get_mut(state.current_file_index) {
+                    if let Some(hunk) = file.hunks.get_mut(hunk_index) {
+                        hunk.decision = match key {
+                            KeyCode::Char('a') => HunkState::Accepted,
+                            KeyCode::Char('r') => HunkState::Rejected,
+                            _ => match hunk.decision {
+                                HunkState::Pending => HunkState::Accepted,
+                                HunkState::Accepted | HunkState::Edited(_) => HunkState::Rejected,
+                                HunkState::Rejected => HunkState::Pending,
+                            },
+                        };
+                    }
+                    file.update_decision();
+                }
+            }
             KeyCode::Up | KeyCode::Char('k') => {
                 if state.current_file_index > 0 {
                     state.current_file_index -= 1;
+                    state.current_hunk_index = 0;
                 }
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 if state.current_file_index + 1 < state.files.len() {
                     state.current_file_index += 1;
+                    state.current_hunk_index = 0;
                 }
             }
             KeyCode::Char(' ') => {
                 // Toggle file decision
                 if let Some(file) = state.files.get_mut(state.current_file_index) {
-                    file.decision = match file.decision {
+                    let decision = match file.decision {
                         FileDecision::Pending => FileDecision::Accept,
                         FileDecision::Accept => FileDecision::Reject,
                         FileDecision::Reject => FileDecision::Pending,
                         FileDecision::Partial => FileDecision::Accept,
                     };
+                    file.set_decision(decision);
                 }
             }
             KeyCode::Char('a') => {
                 // Accept current file
                 if let Some(file) = state.files.get_mut(state.current_file_index) {
-                    file.decision = FileDecision::Accept;
+                    file.set_decision(FileDecision::Accept);
                 }
             }
             KeyCode::Char('r') => {
                 // Reject current file
                 if let Some(file) = state.files.get_mut(state.current_file_index) {
-                    file.decision = FileDecision::Reject;
+                    file.set_decision(FileDecision::Reject);
                 }
             }
             KeyCode::Char('A') => {
                 // Accept all files
                 for file in &mut state.files {
-                    file.decision = FileDecision::Accept;
+                    file.set_decision(FileDecision::Accept);
                 }
             }
             KeyCode::Char('R') => {
                 // Reject all files
                 for file in &mut state.files {
-                    file.decision = FileDecision::Reject;
+                    file.set_decision(FileDecision::Reject);
                 }
             }
             KeyCode::Enter => {
@@ -670,9 +825,14 @@ impl ReviewSystem {
         let mut applied_files = Vec::new();
         let mut rejected_files = Vec::new();
         let mut partial_files = Vec::new();
+        let mut hunk_decisions = HashMap::new();
         
         for file in &state.files {
             file_decisions.insert(file.path.clone(), file.decision.clone());
+            hunk_decisions.insert(
+                file.path.clone(),
+                file.hunks.iter().map(|hunk| hunk.decision.clone()).collect(),
+            );
             
             match file.decision {
                 FileDecision::Accept => applied_files.push(file.path.clone()),
@@ -693,7 +853,7 @@ impl ReviewSystem {
         ReviewResult {
             overall_decision,
             file_decisions,
-            hunk_decisions: HashMap::new(), // Would be populated with actual hunk decisions
+            hunk_decisions,
             applied_files,
             rejected_files,
             partial_files,
@@ -725,10 +885,33 @@ impl ReviewSystem {
 /// Result of key event handling
 enum KeyResult {
     Continue,
+    EditHunk,
     Exit(ReviewResult),
     Error(ReviewError),
 }
 
+/// Open `text` in `$VISUAL` or `$EDITOR` (falling back to `vi`) and return what was saved
+fn edit_in_editor(text: &str) -> Result<String, ReviewError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| ReviewError::InvalidState("No editor configured".to_string()))?;
+    
+    let file = tempfile::NamedTempFile::new()?;
+    std::fs::write(file.path(), text)?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(file.path())
+        .status()?;
+    if !status.success() {
+        return Err(ReviewError::InvalidState(format!("{} exited with {}", editor, status)));
+    }
+    Ok(std::fs::read_to_string(file.path())?)
+}
+
 impl Default for ReviewConfig {
     fn default() -> Self {
         Self {
@@ -753,4 +936,67 @@ impl Default for ColorScheme {
             header: Color::Blue,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::diff_apply::{DiffApplySystem, DiffMetadata};
+    use std::path::PathBuf;
+
+    fn changeset() -> ChangeSet {
+        let original: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let new_content = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+        let path = PathBuf::from("src/lib.rs");
+        let file = FileDiff {
+            diff_text: DiffApplySystem::generate_diff(Some(&original), &new_content, &path),
+            file_path: path,
+            original_content: Some(original),
+            new_content,
+            change_type: ChangeType::Modify,
+            metadata: DiffMetadata {
+                created_at: chrono::Utc::now(),
+                agent_id: "test".to_string(),
+                task_id: "task".to_string(),
+                confidence_score: 1.0,
+                estimated_lines_changed: 2,
+                language: Some("rust".to_string()),
+                description: String::new(),
+            },
+            hunks: Vec::new(),
+        };
+        ChangeSet::new("t", "d", "agent", "task", vec![file])
+    }
+
+    #[tokio::test]
+    async fn test_edited_hunk_reaches_the_changeset() {
+        let system = ReviewSystem::new(ReviewConfig::default());
+        let mut changeset = changeset();
+        changeset.split_hunks();
+        {
+            let mut state = system.state.write().await;
+            state.files = system.parse_changeset(&changeset).unwrap();
+            state.view_mode = ViewMode::HunkSelector;
+        }
+
+        assert!(matches!(system.handle_key_event(KeyCode::Char('e')).await, KeyResult::EditHunk));
+        let result = {
+            let mut state = system.state.write().await;
+            let hunk = &mut state.files[0].hunks[0];
+            let edited = hunk.new_text().replace("line two", "line 2 edited");
+            hunk.set_edit(edited);
+            state.files[0].hunks[1].decision = HunkState::Rejected;
+            state.files[0].update_decision();
+            system.generate_review_result(&state)
+        };
+        assert_eq!(result.partial_files, vec!["src/lib.rs".to_string()]);
+
+        result.apply_to(&mut changeset).unwrap();
+        let subset = changeset.accepted_subset();
+        let content = &subset.files[0].new_content;
+        assert!(content.contains("line 1\nline 2 edited\nline 3\n"));
+        assert!(content.contains("line 18\n"));
+    }
+}
@@ -9,6 +9,9 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::codegen::diff_apply::{ChangeSet, DiffApplyError, DiffApplySystem, HunkState};
 
 /// System status response
 #[derive(Debug, Serialize)]
@@ -65,6 +68,36 @@ pub struct OutputQuery {
     pub filter: Option<String>,
 }
 
+/// Changeset awaiting review
+#[derive(Debug, Serialize)]
+pub struct ChangeSetSummary {
+    pub id: String,
+    pub title: String,
+    pub total_files: usize,
+    pub total_lines_added: usize,
+    pub total_lines_removed: usize,
+}
+
+impl From<&ChangeSet> for ChangeSetSummary {
+    fn from(changeset: &ChangeSet) -> Self {
+        Self {
+            id: changeset.id.clone(),
+            title: changeset.title.clone(),
+            total_files: changeset.metadata.total_files,
+            total_lines_added: changeset.metadata.total_lines_added,
+            total_lines_removed: changeset.metadata.total_lines_removed,
+        }
+    }
+}
+
+/// Review decision for one hunk of a changeset
+#[derive(Debug, Deserialize)]
+pub struct HunkStateRequest {
+    pub file_path: PathBuf,
+    pub index: usize,
+    pub state: HunkState,
+}
+
 /// Get system status
 pub async fn get_system_status(
     State(state): State<AppState>,
@@ -229,4 +262,123 @@ pub async fn execute_command(
         "status": "executed",
         "command": request.command
     })))
-}
\ No newline at end of file
+}
+
+/// List changesets awaiting review
+pub async fn list_changesets(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ChangeSetSummary>>, StatusCode> {
+    let changesets = state.changesets.read().await;
+    
+    let summaries = changesets.values().map(ChangeSetSummary::from).collect();
+
+    Ok(Json(summaries))
+}
+
+/// Offer a changeset, such as an exported codemod, for review
+///
+/// Only the paths, change types and contents are taken from the request; the
+/// id, diffs and hunks are generated here, so a client can't choose where the
+/// backup and journal entry go or what an accepted hunk splices in.
+pub async fn create_changeset(
+    State(state): State<AppState>,
+    JsonRequest(offered): JsonRequest<ChangeSet>,
+) -> Result<Json<ChangeSetSummary>, StatusCode> {
+    let files = offered
+        .files
+        .into_iter()
+        .map(|mut file| {
+            file.diff_text = DiffApplySystem::generate_diff(
+                file.original_content.as_deref(),
+                &file.new_content,
+                &file.file_path,
+            );
+            file.hunks.clear();
+            file
+        })
+        .collect();
+    let changeset = ChangeSet::new(
+        &offered.title,
+        &offered.description,
+        &offered.metadata.agent_id,
+        &offered.metadata.task_id,
+        files,
+    );
+    changeset.check_paths().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let summary = ChangeSetSummary::from(&changeset);
+    state.register_changeset(changeset).await;
+
+    Ok(Json(summary))
+}
+
+/// Get a changeset split into hunks with their review state
+pub async fn get_changeset(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ChangeSet>, StatusCode> {
+    let mut changesets = state.changesets.write().await;
+    let changeset = changesets.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
+    changeset.split_hunks();
+
+    Ok(Json(changeset.clone()))
+}
+
+/// Accept, reject or edit one hunk of a changeset
+pub async fn set_hunk_state(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    JsonRequest(request): JsonRequest<HunkStateRequest>,
+) -> Result<Json<ChangeSet>, StatusCode> {
+    let mut changesets = state.changesets.write().await;
+    let changeset = changesets.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
+    changeset.split_hunks();
+    changeset
+        .set_hunk_state(&request.file_path, request.index, request.state)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(changeset.clone()))
+}
+
+/// Preview what applying the accepted hunks of a changeset would change
+pub async fn get_accepted_changes(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ChangeSet>, StatusCode> {
+    let changesets = state.changesets.read().await;
+    let changeset = changesets.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(changeset.accepted_subset()))
+}
+
+/// Apply the accepted hunks of a reviewed changeset and take it out of review
+///
+/// The quality gates always run; a failing changeset can only be forced
+/// through from the command line.
+pub async fn submit_changeset(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ChangeSet>, StatusCode> {
+    let changeset = state
+        .changesets
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut system = DiffApplySystem::start(state.quality_gates.clone(), &state.project_root)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let applied = system
+        .apply_partial(&changeset, &state.project_root, false)
+        .await
+        .map_err(|e| match e {
+            DiffApplyError::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            DiffApplyError::FileConflict(_) => StatusCode::CONFLICT,
+            DiffApplyError::InvalidPath(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+    state.changesets.write().await.remove(&id);
+
+    Ok(Json(applied))
+}
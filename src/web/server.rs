@@ -9,12 +9,13 @@ use super::{handlers, WebError};
 // use crate::analytics::{AnalyticsEngine, MetricsSummary};
 // use crate::session::{Session, SessionManager, SessionFilters};
 use crate::ui::coordination_viz::{CoordinationVisualizer, SystemSnapshot};
+use crate::codegen::diff_apply::{ChangeSet, QualityGateConfig};
 use crate::ui::UIEvent;
 use axum::{
     extract::{Path, Query, State, WebSocketUpgrade, ws::{WebSocket, Message}},
-    http::{StatusCode, Uri},
+    http::{header, HeaderValue, Method, StatusCode, Uri},
     response::{Html, IntoResponse, Json},
-    routing::{get, post},
+    routing::{get, post, put},
     Router,
 };
 
@@ -23,11 +24,12 @@ use futures_util::{stream::StreamExt, SinkExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::sync::{broadcast, mpsc, RwLock as TokioRwLock};
 use tower::{ServiceBuilder};
-use tower_http::{cors::CorsLayer, services::ServeDir};
+use tower_http::{cors::{AllowOrigin, CorsLayer}, services::ServeDir};
 use tracing::{debug, info, warn};
 
 /// Stub types for compilation - will be replaced with actual types when modules are complete
@@ -95,8 +97,17 @@ pub struct AppState {
     pub agent_status: Arc<TokioRwLock<HashMap<String, crate::agents::AgentStatus>>>,
     pub output_blocks: Arc<TokioRwLock<Vec<crate::ui::blocks::OutputBlock>>>,
     pub notifications: Arc<TokioRwLock<Vec<crate::ui::notifications::Notification>>>,
+    /// Changesets awaiting hunk-level review, by id
+    pub changesets: ChangeSetRegistry,
+    /// Project that submitted changesets are applied to
+    pub project_root: PathBuf,
+    /// Quality gates run on submitted changesets
+    pub quality_gates: QualityGateConfig,
 }
 
+/// Changesets awaiting review, shared between the server and their producers
+pub type ChangeSetRegistry = Arc<TokioRwLock<HashMap<String, ChangeSet>>>;
+
 /// WebSocket connection tracking
 #[derive(Debug, Clone)]
 pub struct WebSocketConnection {
@@ -165,9 +176,18 @@ impl AppState {
             agent_status: Arc::new(TokioRwLock::new(HashMap::new())),
             output_blocks: Arc::new(TokioRwLock::new(Vec::new())),
             notifications: Arc::new(TokioRwLock::new(Vec::new())),
+            changesets: Arc::new(TokioRwLock::new(HashMap::new())),
+            project_root: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            quality_gates: QualityGateConfig::default(),
         }
     }
     
+    /// Offer a changeset for review in the dashboard
+    pub async fn register_changeset(&self, mut changeset: ChangeSet) {
+        changeset.split_hunks();
+        self.changesets.write().await.insert(changeset.id.clone(), changeset);
+    }
+    
     /// Broadcast update to all WebSocket connections
     pub async fn broadcast_update(&self, update: DashboardUpdate) {
         if let Err(e) = self.data_broadcast.send(update) {
@@ -222,6 +242,23 @@ impl WebServer {
         Self { config, app_state }
     }
     
+    /// Apply submitted changesets to `project_root` instead of the working directory
+    pub fn with_project_root(mut self, project_root: PathBuf) -> Self {
+        self.app_state.project_root = project_root;
+        self
+    }
+    
+    /// Run `quality_gates` on submitted changesets, as configured for the project
+    pub fn with_quality_gates(mut self, quality_gates: QualityGateConfig) -> Self {
+        self.app_state.quality_gates = quality_gates;
+        self
+    }
+    
+    /// Registry that producers add changesets to for review in the dashboard
+    pub fn changesets(&self) -> ChangeSetRegistry {
+        self.app_state.changesets.clone()
+    }
+    
     /// Legacy constructor for backward compatibility
     pub fn new_basic(
         config: WebConfig,
@@ -305,6 +342,13 @@ impl WebServer {
             .route("/notifications", get(handlers::get_notifications))
             .route("/command", post(handlers::execute_command))
             
+            // Changeset review endpoints
+            .route("/changesets", get(handlers::list_changesets).post(handlers::create_changeset))
+            .route("/changesets/:id", get(handlers::get_changeset))
+            .route("/changesets/:id/hunks", put(handlers::set_hunk_state))
+            .route("/changesets/:id/accepted", get(handlers::get_accepted_changes))
+            .route("/changesets/:id/submit", post(handlers::submit_changeset))
+            
             // System API
             .route("/system/health", get(health_check))
             .route("/system/config", get(get_config).put(update_config))
@@ -330,11 +374,20 @@ impl WebServer {
             
             .with_state(self.app_state);
 
-        // Add CORS middleware if enabled
+        // Add CORS middleware if enabled. Only the dashboard's own origin may
+        // call the API: it can apply changesets to the project on disk, so any
+        // other page open in the browser must not be able to reach it.
         if self.config.cors_enabled {
+            let origins: Vec<HeaderValue> = [self.config.host.as_str(), "localhost", "127.0.0.1"]
+                .iter()
+                .filter_map(|host| format!("http://{}:{}", host, self.config.port).parse().ok())
+                .collect();
             app = app.layer(
                 ServiceBuilder::new().layer(
-                    CorsLayer::permissive()
+                    CorsLayer::new()
+                        .allow_origin(AllowOrigin::list(origins))
+                        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+                        .allow_headers([header::CONTENT_TYPE])
                 )
             );
         }
@@ -0,0 +1,5 @@
+import express from 'express';
+
+const app = express();
+app.get('/', (req, res) => res.send('ok'));
+app.listen({{port}}, () => console.log('http://localhost:{{port}}'));
@@ -0,0 +1,63 @@
+use axum::{extract::State, routing::get, Json, Router};
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::net::TcpListener;
+use tower::ServiceBuilder;
+use tower_http::trace::TraceLayer;
+use tracing::info;
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let database_url = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "{{database_url}}".into());
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let app = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .route("/health", get(health_check))
+        .with_state(pool)
+        .layer(ServiceBuilder::new().layer(TraceLayer::new_for_http()));
+
+    let listener = TcpListener::bind("127.0.0.1:{{port}}").await.unwrap();
+    info!("Server starting on http://127.0.0.1:{{port}}");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+}
+
+async fn health_check(State(pool): State<PgPool>) -> Json<serde_json::Value> {
+    match sqlx::query("SELECT 1").execute(&pool).await {
+        Ok(_) => Json(json!({"status": "ok", "database": "connected"})),
+        Err(_) => Json(json!({"status": "degraded", "database": "disconnected"})),
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Signal received, starting graceful shutdown");
+}
@@ -23,6 +23,7 @@ async fn test_devkit_self_replication() {
         target_path: target_path.clone(),
         preserve_git: false, // Don't copy git for test
         validate_generated: true,
+        check_fidelity: false,
         dry_run: true, // Safe test - won't actually create files
         include_tests: true,
         include_documentation: true,